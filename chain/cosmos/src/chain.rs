@@ -364,6 +364,7 @@ impl BlockStreamMapper<Chain> for FirehoseMapper {
         _logger: &Logger,
         _clock: Clock,
         _cursor: FirehoseCursor,
+        _type_url: &str,
         _block: Vec<u8>,
     ) -> Result<BlockStreamEvent<Chain>, BlockStreamError> {
         unimplemented!()
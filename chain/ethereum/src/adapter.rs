@@ -811,6 +811,7 @@ pub struct ProviderEthRpcMetrics {
     request_duration: Box<HistogramVec>,
     errors: Box<CounterVec>,
     status: Box<GaugeVec>,
+    get_logs_range: Box<GaugeVec>,
 }
 
 impl ProviderEthRpcMetrics {
@@ -838,10 +839,18 @@ impl ProviderEthRpcMetrics {
                 vec![String::from("provider")],
             )
             .unwrap();
+        let get_logs_range = registry
+            .new_gauge_vec(
+                "eth_rpc_get_logs_range",
+                "The largest number of blocks that eth_getLogs requests to the provider currently span",
+                vec![String::from("provider")],
+            )
+            .unwrap();
         Self {
             request_duration,
             errors,
             status,
+            get_logs_range,
         }
     }
 
@@ -860,12 +869,19 @@ impl ProviderEthRpcMetrics {
             .with_label_values(&[provider])
             .set(status.into());
     }
+
+    pub fn set_get_logs_range(&self, range: BlockNumber, provider: &str) {
+        self.get_logs_range
+            .with_label_values(&[provider])
+            .set(range as f64);
+    }
 }
 
 #[derive(Clone)]
 pub struct SubgraphEthRpcMetrics {
     request_duration: GaugeVec,
     errors: CounterVec,
+    get_logs_requests: GaugeVec,
     deployment: String,
 }
 
@@ -885,9 +901,17 @@ impl SubgraphEthRpcMetrics {
                 vec!["deployment", "method", "provider"].as_slice(),
             )
             .unwrap();
+        let get_logs_requests = registry
+            .global_gauge_vec(
+                "deployment_eth_get_logs_requests",
+                "Number of eth_getLogs requests made for the most recent block range of a subgraph deployment",
+                vec!["deployment"].as_slice(),
+            )
+            .unwrap();
         Self {
             request_duration,
             errors,
+            get_logs_requests,
             deployment: subgraph_hash.into(),
         }
     }
@@ -903,6 +927,12 @@ impl SubgraphEthRpcMetrics {
             .with_label_values(&[&self.deployment, method, provider])
             .inc();
     }
+
+    pub fn observe_get_logs_requests(&self, count: usize) {
+        self.get_logs_requests
+            .with_label_values(&[&self.deployment])
+            .set(count as f64);
+    }
 }
 
 /// Common trait for components that watch and manage access to Ethereum.
@@ -1027,7 +1057,7 @@ pub trait EthereumAdapter: Send + Sync + 'static {
 mod tests {
     use crate::adapter::{FunctionSelector, COMBINED_FILTER_TYPE_URL};
 
-    use super::{EthereumBlockFilter, LogFilterNode};
+    use super::{EthereumBlockFilter, LogFilterNode, SubgraphEthRpcMetrics};
     use super::{EthereumCallFilter, EthereumLogFilter, TriggerFilter};
 
     use graph::blockchain::TriggerFilter as _;
@@ -1036,7 +1066,7 @@ mod tests {
     use graph::prelude::ethabi::ethereum_types::H256;
     use graph::prelude::web3::types::Address;
     use graph::prelude::web3::types::Bytes;
-    use graph::prelude::EthereumCall;
+    use graph::prelude::{EthereumCall, MetricsRegistry};
     use hex::ToHex;
     use itertools::Itertools;
     use prost::Message;
@@ -1045,6 +1075,7 @@ mod tests {
     use std::collections::{HashMap, HashSet};
     use std::iter::FromIterator;
     use std::str::FromStr;
+    use std::sync::Arc;
 
     #[test]
     fn ethereum_log_filter_codec() {
@@ -1610,6 +1641,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn get_logs_requests_gauge() {
+        let registry = Arc::new(MetricsRegistry::mock());
+        let metrics1 = SubgraphEthRpcMetrics::new(registry.clone(), "QmDeployment1");
+        let metrics2 = SubgraphEthRpcMetrics::new(registry, "QmDeployment2");
+        let requests = |metrics: &SubgraphEthRpcMetrics| {
+            metrics
+                .get_logs_requests
+                .with_label_values(&[&metrics.deployment])
+                .get()
+        };
+
+        metrics1.observe_get_logs_requests(3);
+        metrics2.observe_get_logs_requests(2);
+        assert_eq!(3.0, requests(&metrics1));
+        assert_eq!(2.0, requests(&metrics2));
+
+        // The gauge reports the requests for the most recent block range,
+        // not the total across ranges
+        metrics1.observe_get_logs_requests(1);
+        assert_eq!(1.0, requests(&metrics1));
+        assert_eq!(2.0, requests(&metrics2));
+    }
+
     fn address(id: u64) -> Address {
         Address::from_low_u64_be(id)
     }
@@ -56,7 +56,7 @@ use crate::{
     },
     SubgraphEthRpcMetrics, TriggerFilter, ENV_VARS,
 };
-use crate::{BufferedCallCache, NodeCapabilities};
+use crate::{BufferedCallCache, LruCallCache, NodeCapabilities};
 use crate::{EthereumAdapter, RuntimeAdapter};
 use graph::blockchain::block_stream::{
     BlockStream, BlockStreamBuilder, BlockStreamError, BlockStreamMapper, FirehoseCursor,
@@ -264,6 +264,7 @@ pub trait RuntimeAdapterBuilder: Send + Sync + 'static {
         eth_adapters: Arc<EthereumNetworkAdapters>,
         call_cache: Arc<dyn EthereumCallCache>,
         chain_identifier: Arc<ChainIdentifier>,
+        chain_store: Arc<dyn ChainStore>,
     ) -> Arc<dyn RuntimeAdapterTrait<Chain>>;
 }
 
@@ -275,11 +276,13 @@ impl RuntimeAdapterBuilder for EthereumRuntimeAdapterBuilder {
         eth_adapters: Arc<EthereumNetworkAdapters>,
         call_cache: Arc<dyn EthereumCallCache>,
         chain_identifier: Arc<ChainIdentifier>,
+        chain_store: Arc<dyn ChainStore>,
     ) -> Arc<dyn RuntimeAdapterTrait<Chain>> {
         Arc::new(RuntimeAdapter {
             eth_adapters,
             call_cache,
             chain_identifier,
+            chain_store,
         })
     }
 }
@@ -331,6 +334,10 @@ impl Chain {
         is_ingestible: bool,
     ) -> Self {
         let chain_identifier = Arc::new(chain_store.chain_identifier().clone());
+        let call_cache: Arc<dyn EthereumCallCache> = match ENV_VARS.eth_call_cache_size {
+            0 => call_cache,
+            size => Arc::new(LruCallCache::new(call_cache, size, &name, &registry)),
+        };
         Chain {
             logger_factory,
             name,
@@ -491,6 +498,7 @@ impl Blockchain for Chain {
             self.eth_adapters.cheap_clone(),
             call_cache.cheap_clone(),
             self.chain_identifier.cheap_clone(),
+            self.chain_store.cheap_clone(),
         );
         let eth_call_gas = eth_call_gas(&self.chain_identifier);
 
@@ -550,9 +558,11 @@ impl Blockchain for Chain {
                 Box::new(PollingBlockIngestor::new(
                     logger,
                     graph::env::ENV_VARS.reorg_threshold,
-                    eth_adapter,
+                    eth_adapter.cheap_clone(),
                     self.chain_store().cheap_clone(),
-                    self.polling_ingestor_interval,
+                    eth_adapter
+                        .polling_interval()
+                        .unwrap_or(self.polling_ingestor_interval),
                     self.name.clone(),
                 )?)
             }
@@ -657,6 +667,27 @@ impl Block for BlockFinality {
 
 pub struct DummyDataSourceTemplate;
 
+/// Find the triggers matching `filter` in a block that already contains
+/// all the data needed for that, i.e., its receipts and, for call
+/// handlers, its calls. Unlike `TriggersAdapter::triggers_in_block`, this
+/// never needs to talk to the chain, which makes it possible to process
+/// blocks that were captured earlier
+pub fn triggers_in_full_block(
+    logger: &Logger,
+    block: EthereumBlockWithCalls,
+    filter: &TriggerFilter,
+) -> Result<BlockWithTriggers<Chain>, Error> {
+    let mut triggers = Vec::new();
+    triggers.append(&mut parse_log_triggers(&filter.log, &block.ethereum_block));
+    triggers.append(&mut parse_call_triggers(&filter.call, &block)?);
+    triggers.append(&mut parse_block_triggers(&filter.block, &block));
+    Ok(BlockWithTriggers::new(
+        BlockFinality::NonFinal(block),
+        triggers,
+        logger,
+    ))
+}
+
 pub struct TriggersAdapter {
     logger: Logger,
     ethrpc_metrics: Arc<SubgraphEthRpcMetrics>,
@@ -703,7 +734,7 @@ impl TriggersAdapterTrait<Chain> for TriggersAdapter {
         )
         .await?;
 
-        match &block {
+        match block {
             BlockFinality::Final(_) => {
                 let adapter = self.chain_client.rpc()?.cheapest_with(&self.capabilities)?;
                 let block_number = block.number() as BlockNumber;
@@ -722,14 +753,7 @@ impl TriggersAdapterTrait<Chain> for TriggersAdapter {
                 Ok(blocks.into_iter().next().unwrap())
             }
             BlockFinality::NonFinal(full_block) => {
-                let mut triggers = Vec::new();
-                triggers.append(&mut parse_log_triggers(
-                    &filter.log,
-                    &full_block.ethereum_block,
-                ));
-                triggers.append(&mut parse_call_triggers(&filter.call, full_block)?);
-                triggers.append(&mut parse_block_triggers(&filter.block, full_block));
-                Ok(BlockWithTriggers::new(block, triggers, logger))
+                triggers_in_full_block(logger, full_block, filter)
             }
         }
     }
@@ -835,6 +859,7 @@ impl BlockStreamMapper<Chain> for FirehoseMapper {
         _logger: &Logger,
         _clock: Clock,
         _cursor: FirehoseCursor,
+        _type_url: &str,
         _block: Vec<u8>,
     ) -> Result<BlockStreamEvent<Chain>, BlockStreamError> {
         unimplemented!()
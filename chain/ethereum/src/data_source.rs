@@ -1781,3 +1781,116 @@ fn test_call_expr() {
     assert_eq!(expr.func, "growth");
     assert_eq!(expr.args, vec![]);
 }
+
+#[test]
+fn test_trigger_timestamps() {
+    use graph::blockchain::BlockTime;
+    use graph::prelude::ethabi;
+    use graph::prelude::web3::types::{Bytes, U256, U64};
+
+    const ABI: &str = r#"[
+        {
+            "type": "event",
+            "name": "Ping",
+            "inputs": [{ "name": "value", "type": "uint256", "indexed": false }],
+            "anonymous": false
+        },
+        {
+            "type": "function",
+            "name": "ping",
+            "inputs": [{ "name": "value", "type": "uint256" }],
+            "outputs": [],
+            "stateMutability": "nonpayable"
+        }
+    ]"#;
+    const TIMESTAMP: i64 = 1_700_000_000;
+
+    let abi = Arc::new(MappingABI {
+        name: "Pinger".to_string(),
+        contract: Contract::load(ABI.as_bytes()).unwrap(),
+    });
+    let mapping = Mapping {
+        kind: "ethereum/events".to_string(),
+        api_version: semver::Version::new(0, 0, 10),
+        language: "wasm/assemblyscript".to_string(),
+        entities: vec![],
+        abis: vec![abi.cheap_clone()],
+        block_handlers: vec![MappingBlockHandler {
+            handler: "handleBlock".to_string(),
+            filter: None,
+        }],
+        call_handlers: vec![MappingCallHandler {
+            function: "ping(uint256)".to_string(),
+            handler: "handlePingCall".to_string(),
+        }],
+        event_handlers: vec![MappingEventHandler {
+            event: "Ping(uint256)".to_string(),
+            topic0: None,
+            handler: "handlePing".to_string(),
+            receipt: false,
+            calls: CallDecls::default(),
+        }],
+        runtime: Arc::new(vec![]),
+        link: Link::from("mapping"),
+    };
+    let ds = DataSource {
+        kind: "ethereum".to_string(),
+        network: Some("mainnet".to_string()),
+        name: "Pinger".to_string(),
+        manifest_idx: 0,
+        address: None,
+        start_block: 0,
+        end_block: None,
+        mapping,
+        context: Arc::new(None),
+        creation_block: None,
+        contract_abi: abi,
+    };
+
+    let tx_hash = H256::from_low_u64_be(2);
+    let mut block = LightEthereumBlock::default();
+    block.number = Some(U64::from(10));
+    block.hash = Some(H256::from_low_u64_be(1));
+    block.timestamp = U256::from(TIMESTAMP);
+    block.transactions = vec![Transaction {
+        hash: tx_hash,
+        ..Transaction::default()
+    }];
+    let block = Arc::new(block);
+
+    let value = ethabi::encode(&[Token::Uint(U256::from(7))]);
+    let log = Log {
+        topics: vec![H256::from(keccak256(b"Ping(uint256)"))],
+        data: Bytes(value.clone()),
+        block_hash: block.hash,
+        block_number: block.number,
+        transaction_hash: Some(tx_hash),
+        ..Log::default()
+    };
+    let mut input = keccak256(b"ping(uint256)")[..4].to_vec();
+    input.extend(value);
+    let call = EthereumCall {
+        input: Bytes(input),
+        transaction_hash: Some(tx_hash),
+        ..EthereumCall::default()
+    };
+
+    let logger = graph::log::logger(true);
+    let triggers = vec![
+        EthereumTrigger::Log(crate::trigger::LogRef::FullLog(Arc::new(log), None)),
+        EthereumTrigger::Call(Arc::new(call)),
+        EthereumTrigger::Block(block.block_ptr(), EthereumBlockTriggerType::End),
+    ];
+    for trigger in triggers {
+        let trigger = ds
+            .match_and_decode(&trigger, &block, &logger)
+            .unwrap()
+            .expect("the trigger matches the data source");
+        assert_eq!(
+            BlockTime::since_epoch(TIMESTAMP, 0),
+            trigger.timestamp(),
+            "timestamp for {}",
+            trigger.handler_name()
+        );
+    }
+}
@@ -30,6 +30,17 @@ pub struct EnvVars {
     /// default value is 500 blocks, which is reasonable according to Ethereum
     /// node operators.
     pub max_event_only_range: BlockNumber,
+    /// The smallest and largest number of blocks that a single
+    /// `eth_getLogs` request may span. Each provider starts out using the
+    /// largest range, halves it when it rejects a request as too heavy and
+    /// doubles it again after successful requests, always staying within
+    /// these bounds.
+    ///
+    /// Set by the environment variables `GRAPH_ETHEREUM_GET_LOGS_MIN_RANGE`
+    /// and `GRAPH_ETHEREUM_GET_LOGS_MAX_RANGE`. The default values are 1
+    /// and 2000 blocks.
+    pub get_logs_min_range: BlockNumber,
+    pub get_logs_max_range: BlockNumber,
     /// Set by the environment variable `ETHEREUM_BLOCK_BATCH_SIZE`. The
     /// default value is 10 blocks.
     pub block_batch_size: usize,
@@ -84,6 +95,11 @@ pub struct EnvVars {
     /// This is a comma separated list of chain ids for which the gas field will not be set
     /// when calling `eth_call`.
     pub eth_call_no_gas: Vec<String>,
+    /// Set by the env var `GRAPH_ETH_CALL_CACHE_SIZE`.
+    /// The number of `eth_call` results to keep in memory per chain, in
+    /// addition to the call cache in the database. The default is 0, which
+    /// disables the in-memory cache.
+    pub eth_call_cache_size: usize,
 }
 
 // This does not print any values avoid accidentally leaking any sensitive env vars
@@ -111,6 +127,8 @@ impl From<Inner> for EnvVars {
                 .collect(),
             trace_stream_step_size: x.trace_stream_step_size,
             max_event_only_range: x.max_event_only_range,
+            get_logs_min_range: x.get_logs_min_range,
+            get_logs_max_range: x.get_logs_max_range,
             block_batch_size: x.block_batch_size,
             max_block_range_size: x.max_block_range_size,
             json_rpc_timeout: Duration::from_secs(x.json_rpc_timeout_in_secs),
@@ -130,6 +148,7 @@ impl From<Inner> for EnvVars {
                 .filter(|s| !s.is_empty())
                 .map(str::to_string)
                 .collect(),
+            eth_call_cache_size: x.eth_call_cache_size,
         }
     }
 }
@@ -151,6 +170,10 @@ struct Inner {
     trace_stream_step_size: BlockNumber,
     #[envconfig(from = "GRAPH_ETHEREUM_MAX_EVENT_ONLY_RANGE", default = "500")]
     max_event_only_range: BlockNumber,
+    #[envconfig(from = "GRAPH_ETHEREUM_GET_LOGS_MIN_RANGE", default = "1")]
+    get_logs_min_range: BlockNumber,
+    #[envconfig(from = "GRAPH_ETHEREUM_GET_LOGS_MAX_RANGE", default = "2000")]
+    get_logs_max_range: BlockNumber,
     #[envconfig(from = "ETHEREUM_BLOCK_BATCH_SIZE", default = "10")]
     block_batch_size: usize,
     #[envconfig(from = "GRAPH_ETHEREUM_MAX_BLOCK_RANGE_SIZE", default = "2000")]
@@ -177,4 +200,6 @@ struct Inner {
     genesis_block_number: u64,
     #[envconfig(from = "GRAPH_ETH_CALL_NO_GAS", default = "421613")]
     eth_call_no_gas: String,
+    #[envconfig(from = "GRAPH_ETH_CALL_CACHE_SIZE", default = "0")]
+    eth_call_cache_size: usize,
 }
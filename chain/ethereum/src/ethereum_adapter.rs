@@ -47,8 +47,9 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::TryFrom;
 use std::iter::FromIterator;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::adapter::EthereumRpcError;
 use crate::adapter::ProviderStatus;
@@ -67,6 +68,59 @@ use crate::{
     TriggerFilter, ENV_VARS,
 };
 
+/// The number of blocks that a single `eth_getLogs` request to a provider
+/// may span. It is shared by all log streams for the provider, starts at
+/// `GRAPH_ETHEREUM_GET_LOGS_MAX_RANGE`, is halved whenever the provider
+/// rejects a request as too heavy and doubled again after each successful
+/// request, but always stays between the configured bounds
+#[derive(Debug)]
+struct LogRange {
+    size: AtomicI32,
+    min: BlockNumber,
+    max: BlockNumber,
+}
+
+impl LogRange {
+    fn new(min: BlockNumber, max: BlockNumber) -> Self {
+        let max = max.max(1);
+        let min = min.clamp(1, max);
+        LogRange {
+            size: AtomicI32::new(max),
+            min,
+            max,
+        }
+    }
+
+    fn size(&self) -> BlockNumber {
+        self.size.load(Ordering::SeqCst)
+    }
+
+    /// Shrink the range after a request spanning `used` blocks was
+    /// rejected. Since several requests can be in flight at once, the range
+    /// is never grown by this
+    fn shrink(&self, used: BlockNumber) -> BlockNumber {
+        let target = (used / 2).max(self.min);
+        let prev = self
+            .size
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |size| {
+                Some(size.min(target))
+            })
+            .unwrap();
+        prev.min(target)
+    }
+
+    fn grow(&self) -> BlockNumber {
+        let max = self.max;
+        let prev = self
+            .size
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |size| {
+                Some(size.saturating_mul(2).min(max))
+            })
+            .unwrap();
+        prev.saturating_mul(2).min(max)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EthereumAdapter {
     logger: Logger,
@@ -75,6 +129,10 @@ pub struct EthereumAdapter {
     metrics: Arc<ProviderEthRpcMetrics>,
     supports_eip_1898: bool,
     call_only: bool,
+    /// How often the block ingestor should poll this provider. If this is
+    /// `None`, the ingestor uses the interval configured for the chain
+    polling_interval: Option<Duration>,
+    log_range: Arc<LogRange>,
 }
 
 impl CheapClone for EthereumAdapter {
@@ -86,6 +144,8 @@ impl CheapClone for EthereumAdapter {
             metrics: self.metrics.cheap_clone(),
             supports_eip_1898: self.supports_eip_1898,
             call_only: self.call_only,
+            polling_interval: self.polling_interval,
+            log_range: self.log_range.cheap_clone(),
         }
     }
 }
@@ -95,6 +155,17 @@ impl EthereumAdapter {
         self.call_only
     }
 
+    pub fn polling_interval(&self) -> Option<Duration> {
+        self.polling_interval
+    }
+
+    /// Poll this provider every `polling_interval` for new blocks instead
+    /// of using the interval for the chain
+    pub fn with_polling_interval(mut self, polling_interval: Option<Duration>) -> Self {
+        self.polling_interval = polling_interval;
+        self
+    }
+
     pub async fn new(
         logger: Logger,
         provider: String,
@@ -114,6 +185,12 @@ impl EthereumAdapter {
             .map(|s| s.contains("TestRPC"))
             .unwrap_or(false);
 
+        let log_range = Arc::new(LogRange::new(
+            ENV_VARS.get_logs_min_range,
+            ENV_VARS.get_logs_max_range,
+        ));
+        provider_metrics.set_get_logs_range(log_range.size(), &provider);
+
         EthereumAdapter {
             logger,
             provider,
@@ -121,6 +198,8 @@ impl EthereumAdapter {
             metrics: provider_metrics,
             supports_eip_1898: supports_eip_1898 && !is_ganache,
             call_only,
+            polling_interval: None,
+            log_range,
         }
     }
 
@@ -332,6 +411,7 @@ impl EthereumAdapter {
         from: BlockNumber,
         to: BlockNumber,
         filter: EthGetLogsFilter,
+        requests: Arc<AtomicUsize>,
     ) -> DynTryFuture<'static, Vec<Log>, Error> {
         // Codes returned by Ethereum node providers if an eth_getLogs request is too heavy.
         const TOO_MANY_LOGS_FINGERPRINTS: &[&str] = &[
@@ -352,20 +432,23 @@ impl EthereumAdapter {
         let eth = self.cheap_clone();
         let filter = Arc::new(filter);
 
-        let step = match filter.contracts.is_empty() {
+        let max_step = match filter.contracts.is_empty() {
             // `to - from + 1`  blocks will be scanned.
             false => to - from,
             true => (to - from).min(ENV_VARS.max_event_only_range - 1),
         };
+        let step = max_step.min(eth.log_range.size() - 1);
 
         // Typically this will loop only once and fetch the entire range in one request. But if the
-        // node returns an error that signifies the request is to heavy to process, the range will
-        // be broken down to smaller steps.
+        // node returns an error that signifies the request is to heavy to process, the range for
+        // this provider will be broken down to smaller steps, and grown back once requests
+        // succeed again.
         futures03::stream::try_unfold((from, step), move |(start, step)| {
             let logger = logger.cheap_clone();
             let filter = filter.cheap_clone();
             let eth = eth.cheap_clone();
             let subgraph_metrics = subgraph_metrics.cheap_clone();
+            let requests = requests.cheap_clone();
 
             async move {
                 if start > to {
@@ -377,6 +460,7 @@ impl EthereumAdapter {
                     logger,
                     "Requesting logs for blocks [{}, {}], {}", start, end, filter
                 );
+                requests.fetch_add(1, Ordering::Relaxed);
                 let res = eth
                     .logs_with_sigs(
                         logger.cheap_clone(),
@@ -392,25 +476,31 @@ impl EthereumAdapter {
                     Err(e) => {
                         let string_err = e.to_string();
 
-                        // If the step is already 0, the request is too heavy even for a single
-                        // block. We hope this never happens, but if it does, make sure to error.
+                        // If the range is already at its minimum, the request is too heavy
+                        // even for the smallest range we are allowed to use. We hope this never
+                        // happens, but if it does, make sure to error.
                         if TOO_MANY_LOGS_FINGERPRINTS
                             .iter()
                             .any(|f| string_err.contains(f))
-                            && step > 0
+                            && step + 1 > eth.log_range.min
                         {
-                            // The range size for a request is `step + 1`. So it's ok if the step
-                            // goes down to 0, in that case we'll request one block at a time.
-                            let new_step = step / 10;
+                            // The range size for a request is `step + 1`
+                            let size = eth.log_range.shrink(step + 1);
+                            eth.metrics.set_get_logs_range(size, &eth.provider);
+                            let new_step = size - 1;
                             debug!(logger, "Reducing block range size to scan for events";
-                                               "new_size" => new_step + 1);
+                                               "new_size" => size);
                             Ok(Some((vec![], (start, new_step))))
                         } else {
                             warn!(logger, "Unexpected RPC error"; "error" => &string_err);
                             Err(anyhow!("{}", string_err))
                         }
                     }
-                    Ok(logs) => Ok(Some((logs, (end + 1, step)))),
+                    Ok(logs) => {
+                        let size = eth.log_range.grow();
+                        eth.metrics.set_get_logs_range(size, &eth.provider);
+                        Ok(Some((logs, (end + 1, max_step.min(size - 1)))))
+                    }
                 }
             }
         })
@@ -785,19 +875,33 @@ impl EthereumAdapter {
     ) -> DynTryFuture<'static, Vec<Log>, Error> {
         let eth: Self = self.cheap_clone();
         let logger = logger.clone();
+        let requests = Arc::new(AtomicUsize::new(0));
 
-        futures03::stream::iter(log_filter.eth_get_logs_filters().map(move |filter| {
-            eth.cheap_clone().log_stream(
-                logger.cheap_clone(),
-                subgraph_metrics.cheap_clone(),
-                from,
-                to,
-                filter,
-            )
+        // Filters with many contracts are split into several filters by
+        // `eth_get_logs_filters`, each of which needs its own request
+        let logs = futures03::stream::iter(log_filter.eth_get_logs_filters().map({
+            let subgraph_metrics = subgraph_metrics.cheap_clone();
+            let requests = requests.cheap_clone();
+            move |filter| {
+                eth.cheap_clone().log_stream(
+                    logger.cheap_clone(),
+                    subgraph_metrics.cheap_clone(),
+                    from,
+                    to,
+                    filter,
+                    requests.cheap_clone(),
+                )
+            }
         }))
         // Real limits on the number of parallel requests are imposed within the adapter.
         .buffered(ENV_VARS.block_ingestor_max_concurrent_json_rpc_calls)
-        .try_concat()
+        .try_concat();
+
+        async move {
+            let logs = logs.await?;
+            subgraph_metrics.observe_get_logs_requests(requests.load(Ordering::Relaxed));
+            Ok(logs)
+        }
         .boxed()
     }
 
@@ -1593,33 +1697,21 @@ impl EthereumAdapterTrait for EthereumAdapter {
     }
 }
 
-/// Returns blocks with triggers, corresponding to the specified range and filters.
-/// If a block contains no triggers, there may be no corresponding item in the stream.
-/// However the `to` block will always be present, even if triggers are empty.
-///
-/// Careful: don't use this function without considering race conditions.
-/// Chain reorgs could happen at any time, and could affect the answer received.
-/// Generally, it is only safe to use this function with blocks that have received enough
-/// confirmations to guarantee no further reorgs, **and** where the Ethereum node is aware of
-/// those confirmations.
-/// If the Ethereum node is far behind in processing blocks, even old blocks can be subject to
-/// reorgs.
-/// It is recommended that `to` be far behind the block number of latest block the Ethereum
-/// node is aware of.
-pub(crate) async fn blocks_with_triggers(
-    adapter: Arc<EthereumAdapter>,
-    logger: Logger,
-    chain_store: Arc<dyn ChainStore>,
+/// Find all triggers that `filter` matches in the blocks from `from` to
+/// `to` without loading the blocks. The triggers are not sorted, and the
+/// same caveats about reorgs as for `blocks_with_triggers` apply.
+pub async fn triggers_in_block_range(
+    adapter: &Arc<EthereumAdapter>,
+    logger: &Logger,
     subgraph_metrics: Arc<SubgraphEthRpcMetrics>,
     from: BlockNumber,
     to: BlockNumber,
     filter: &TriggerFilter,
-    unified_api_version: UnifiedMappingApiVersion,
-) -> Result<Vec<BlockWithTriggers<crate::Chain>>, Error> {
+    unified_api_version: &UnifiedMappingApiVersion,
+) -> Result<Vec<EthereumTrigger>, Error> {
     // Each trigger filter needs to be queried for the same block range
     // and the blocks yielded need to be deduped. If any error occurs
     // while searching for a trigger type, the entire operation fails.
-    let eth = adapter.clone();
     let call_filter = EthereumCallFilter::from(&filter.block);
 
     // Scan the block range to find relevant triggers
@@ -1629,7 +1721,7 @@ pub(crate) async fn blocks_with_triggers(
     // This is for `start` triggers which can be initialization handlers which needs to be run
     // before all other triggers
     if filter.block.trigger_every_block {
-        let block_future = eth
+        let block_future = adapter
             .block_range_to_ptrs(logger.clone(), from, to)
             .map(move |ptrs| {
                 ptrs.into_iter()
@@ -1646,28 +1738,28 @@ pub(crate) async fn blocks_with_triggers(
         trigger_futs.push(block_future)
     } else if !filter.block.polling_intervals.is_empty() {
         let block_futures_matching_once_filter =
-            eth.blocks_matching_polling_intervals(logger.clone(), from, to, &filter.block);
+            adapter.blocks_matching_polling_intervals(logger.clone(), from, to, &filter.block);
         trigger_futs.push(block_futures_matching_once_filter);
     }
 
     // Scan for Logs
     if !filter.log.is_empty() {
         let logs_future = get_logs_and_transactions(
-            &eth,
-            &logger,
+            adapter,
+            logger,
             subgraph_metrics.clone(),
             from,
             to,
             filter.log.clone(),
-            &unified_api_version,
+            unified_api_version,
         )
         .boxed();
         trigger_futs.push(logs_future)
     }
     // Scan for Calls
     if !filter.call.is_empty() {
-        let calls_future = eth
-            .calls_in_block_range(&logger, subgraph_metrics.clone(), from, to, &filter.call)
+        let calls_future = adapter
+            .calls_in_block_range(logger, subgraph_metrics.clone(), from, to, &filter.call)
             .map(Arc::new)
             .map(EthereumTrigger::Call)
             .collect()
@@ -1680,8 +1772,8 @@ pub(crate) async fn blocks_with_triggers(
         // To determine which blocks include a call to addresses
         // in the block filter, transform the `block_filter` into
         // a `call_filter` and run `blocks_with_calls`
-        let block_future = eth
-            .calls_in_block_range(&logger, subgraph_metrics.clone(), from, to, &call_filter)
+        let block_future = adapter
+            .calls_in_block_range(logger, subgraph_metrics.clone(), from, to, &call_filter)
             .map(|call| {
                 EthereumTrigger::Block(
                     BlockPtr::from(&call),
@@ -1694,6 +1786,43 @@ pub(crate) async fn blocks_with_triggers(
         trigger_futs.push(block_future)
     }
 
+    trigger_futs.try_concat().await
+}
+
+/// Returns blocks with triggers, corresponding to the specified range and filters.
+/// If a block contains no triggers, there may be no corresponding item in the stream.
+/// However the `to` block will always be present, even if triggers are empty.
+///
+/// Careful: don't use this function without considering race conditions.
+/// Chain reorgs could happen at any time, and could affect the answer received.
+/// Generally, it is only safe to use this function with blocks that have received enough
+/// confirmations to guarantee no further reorgs, **and** where the Ethereum node is aware of
+/// those confirmations.
+/// If the Ethereum node is far behind in processing blocks, even old blocks can be subject to
+/// reorgs.
+/// It is recommended that `to` be far behind the block number of latest block the Ethereum
+/// node is aware of.
+pub(crate) async fn blocks_with_triggers(
+    adapter: Arc<EthereumAdapter>,
+    logger: Logger,
+    chain_store: Arc<dyn ChainStore>,
+    subgraph_metrics: Arc<SubgraphEthRpcMetrics>,
+    from: BlockNumber,
+    to: BlockNumber,
+    filter: &TriggerFilter,
+    unified_api_version: UnifiedMappingApiVersion,
+) -> Result<Vec<BlockWithTriggers<crate::Chain>>, Error> {
+    let eth = adapter.clone();
+    let triggers_fut = triggers_in_block_range(
+        &eth,
+        &logger,
+        subgraph_metrics,
+        from,
+        to,
+        filter,
+        &unified_api_version,
+    );
+
     // Get hash for "to" block
     let to_hash_fut = eth
         .block_hash_by_block_number(&logger, to)
@@ -1710,7 +1839,7 @@ pub(crate) async fn blocks_with_triggers(
         .compat();
 
     // Join on triggers and block hash resolution
-    let (triggers, to_hash) = futures03::join!(trigger_futs.try_concat(), to_hash_fut);
+    let (triggers, to_hash) = futures03::join!(triggers_fut, to_hash_fut);
 
     // Unpack and handle possible errors in the previously joined futures
     let triggers =
@@ -2388,7 +2517,9 @@ mod tests {
 
     use crate::trigger::{EthereumBlockTriggerType, EthereumTrigger};
 
-    use super::{parse_block_triggers, EthereumBlock, EthereumBlockFilter, EthereumBlockWithCalls};
+    use super::{
+        parse_block_triggers, EthereumBlock, EthereumBlockFilter, EthereumBlockWithCalls, LogRange,
+    };
     use graph::blockchain::BlockPtr;
     use graph::prelude::ethabi::ethereum_types::U64;
     use graph::prelude::web3::types::{Address, Block, Bytes, H256};
@@ -2502,6 +2633,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn log_range_stays_within_bounds() {
+        let range = LogRange::new(10, 100);
+        assert_eq!(100, range.size());
+
+        assert_eq!(50, range.shrink(100));
+        assert_eq!(25, range.shrink(50));
+        assert_eq!(12, range.shrink(25));
+        assert_eq!(10, range.shrink(12));
+        assert_eq!(10, range.shrink(10));
+
+        // A rejected request from before the range shrank does not grow it
+        assert_eq!(10, range.shrink(100));
+
+        assert_eq!(20, range.grow());
+        assert_eq!(40, range.grow());
+        assert_eq!(80, range.grow());
+        assert_eq!(100, range.grow());
+        assert_eq!(100, range.grow());
+
+        // Misconfigured bounds still lead to a usable range
+        let range = LogRange::new(50, 0);
+        assert_eq!(1, range.size());
+        assert_eq!(1, range.shrink(1));
+        assert_eq!(1, range.grow());
+    }
+
     fn address(id: u64) -> Address {
         Address::from_low_u64_be(id)
     }
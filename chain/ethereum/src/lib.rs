@@ -6,16 +6,18 @@ mod data_source;
 mod env;
 mod ethereum_adapter;
 mod ingestor;
+mod lru_call_cache;
 pub mod runtime;
 mod transport;
 
 pub use self::capabilities::NodeCapabilities;
-pub use self::ethereum_adapter::EthereumAdapter;
+pub use self::ethereum_adapter::{triggers_in_block_range, EthereumAdapter};
 pub use self::runtime::RuntimeAdapter;
 pub use self::transport::Transport;
 pub use env::ENV_VARS;
 
 pub use buffered_call_cache::BufferedCallCache;
+pub use lru_call_cache::LruCallCache;
 
 // ETHDEP: These concrete types should probably not be exposed.
 pub use data_source::{
@@ -0,0 +1,285 @@
+use std::sync::{Arc, Mutex};
+
+use graph::{
+    cheap_clone::CheapClone,
+    components::store::EthereumCallCache,
+    data::store::{ethereum::call, scalar::Bytes},
+    prelude::{ethabi::Address, BlockPtr, CachedEthereumCall, CounterVec, MetricsRegistry},
+    slog::Logger,
+};
+use lru_time_cache::LruCache;
+
+/// The block hash, contract address and encoded call data of a call
+type Key = (Box<[u8]>, Address, Arc<Bytes>);
+
+fn key(call: &call::Request, block: &BlockPtr) -> Key {
+    (
+        block.hash.0.clone(),
+        call.address,
+        call.encoded_call.cheap_clone(),
+    )
+}
+
+/// A wrapper around an Ethereum call cache that keeps the most recently
+/// used call results in memory. Entries are keyed by the hash of the block
+/// and the call, i.e., the contract address and the encoded call data. Since
+/// the key includes the block hash, entries for blocks that were removed
+/// by a reorg are never returned and simply age out of the cache.
+///
+/// Unlike the `BufferedCallCache`, which only remembers calls for the
+/// block that is currently being processed by one subgraph, this cache is
+/// shared by all subgraphs on a chain. Only results that also go into the
+/// underlying cache are remembered, i.e., results of calls that succeeded
+pub struct LruCallCache {
+    call_cache: Arc<dyn EthereumCallCache>,
+    cache: Mutex<LruCache<Key, call::Retval>>,
+    network: String,
+    lookups: CounterVec,
+}
+
+impl LruCallCache {
+    pub fn new(
+        call_cache: Arc<dyn EthereumCallCache>,
+        capacity: usize,
+        network: &str,
+        registry: &MetricsRegistry,
+    ) -> Self {
+        let lookups = registry
+            .global_counter_vec(
+                "eth_call_memory_cache_lookups",
+                "Counts lookups in the in-memory cache of eth_call results by outcome",
+                vec!["network", "result"].as_slice(),
+            )
+            .unwrap();
+        Self {
+            call_cache,
+            cache: Mutex::new(LruCache::with_capacity(capacity)),
+            network: network.to_string(),
+            lookups,
+        }
+    }
+
+    fn get(&self, call: &call::Request, block: &BlockPtr) -> Option<call::Response> {
+        let retval = self.cache.lock().unwrap().get(&key(call, block)).cloned();
+        let result = if retval.is_some() { "hit" } else { "miss" };
+        self.lookups
+            .with_label_values(&[&self.network, result])
+            .inc();
+        // A hit takes the place of a lookup in the underlying store, and
+        // needs to be counted like one
+        retval.map(|retval| call.cheap_clone().response(retval, call::Source::Store))
+    }
+
+    fn insert(&self, call: &call::Request, block: &BlockPtr, retval: &call::Retval) {
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(key(call, block), retval.clone());
+    }
+}
+
+impl EthereumCallCache for LruCallCache {
+    fn get_call(
+        &self,
+        call: &call::Request,
+        block: BlockPtr,
+    ) -> Result<Option<call::Response>, graph::prelude::Error> {
+        if let Some(resp) = self.get(call, &block) {
+            return Ok(Some(resp));
+        }
+
+        let result = self.call_cache.get_call(call, block.cheap_clone())?;
+        if let Some(resp) = &result {
+            self.insert(call, &block, &resp.retval);
+        }
+        Ok(result)
+    }
+
+    fn get_calls(
+        &self,
+        reqs: &[call::Request],
+        block: BlockPtr,
+    ) -> Result<(Vec<call::Response>, Vec<call::Request>), graph::prelude::Error> {
+        let mut missing = Vec::new();
+        let mut resps = Vec::new();
+
+        for call in reqs {
+            match self.get(call, &block) {
+                Some(resp) => resps.push(resp),
+                None => missing.push(call.cheap_clone()),
+            }
+        }
+
+        if missing.is_empty() {
+            return Ok((resps, missing));
+        }
+
+        let (stored, calls) = self.call_cache.get_calls(&missing, block.cheap_clone())?;
+        for resp in &stored {
+            self.insert(&resp.req, &block, &resp.retval);
+        }
+
+        resps.extend(stored);
+        Ok((resps, calls))
+    }
+
+    fn get_calls_in_block(
+        &self,
+        block: BlockPtr,
+    ) -> Result<Vec<CachedEthereumCall>, graph::prelude::Error> {
+        self.call_cache.get_calls_in_block(block)
+    }
+
+    fn set_call(
+        &self,
+        logger: &Logger,
+        call: call::Request,
+        block: BlockPtr,
+        return_value: call::Retval,
+    ) -> Result<(), graph::prelude::Error> {
+        self.call_cache.set_call(
+            logger,
+            call.cheap_clone(),
+            block.cheap_clone(),
+            return_value.clone(),
+        )?;
+        self.insert(&call, &block, &return_value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use graph::components::store::EthereumCallCache;
+    use graph::data::store::ethereum::call;
+    use graph::prelude::{
+        ethabi::Address, web3::types::H256, BlockPtr, CachedEthereumCall, MetricsRegistry,
+    };
+    use graph::slog::{o, Discard, Logger};
+
+    use super::LruCallCache;
+
+    /// A call cache that remembers calls and counts how often it was asked
+    /// for one
+    #[derive(Default)]
+    struct CountingCache {
+        calls: Mutex<HashMap<(BlockPtr, call::Request), call::Retval>>,
+        lookups: AtomicUsize,
+    }
+
+    impl EthereumCallCache for CountingCache {
+        fn get_call(
+            &self,
+            call: &call::Request,
+            block: BlockPtr,
+        ) -> Result<Option<call::Response>, graph::prelude::Error> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            let calls = self.calls.lock().unwrap();
+            Ok(calls
+                .get(&(block, call.clone()))
+                .map(|retval| call.clone().response(retval.clone(), call::Source::Store)))
+        }
+
+        fn get_calls(
+            &self,
+            reqs: &[call::Request],
+            block: BlockPtr,
+        ) -> Result<(Vec<call::Response>, Vec<call::Request>), graph::prelude::Error> {
+            let mut found = Vec::new();
+            let mut missing = Vec::new();
+            for req in reqs {
+                match self.get_call(req, block.clone())? {
+                    Some(resp) => found.push(resp),
+                    None => missing.push(req.clone()),
+                }
+            }
+            Ok((found, missing))
+        }
+
+        fn get_calls_in_block(
+            &self,
+            _block: BlockPtr,
+        ) -> Result<Vec<CachedEthereumCall>, graph::prelude::Error> {
+            unimplemented!()
+        }
+
+        fn set_call(
+            &self,
+            _logger: &Logger,
+            call: call::Request,
+            block: BlockPtr,
+            return_value: call::Retval,
+        ) -> Result<(), graph::prelude::Error> {
+            self.calls
+                .lock()
+                .unwrap()
+                .insert((block, call), return_value);
+            Ok(())
+        }
+    }
+
+    fn block(number: i32, hash: u64) -> BlockPtr {
+        BlockPtr::from((H256::from_low_u64_be(hash), number))
+    }
+
+    fn request(data: u8) -> call::Request {
+        call::Request::new(Address::from_low_u64_be(1), vec![data], 0)
+    }
+
+    fn retval(data: u8) -> call::Retval {
+        call::Retval::Value(vec![data].into())
+    }
+
+    #[test]
+    fn lru_call_cache() {
+        let logger = Logger::root(Discard, o!());
+        let store = Arc::new(CountingCache::default());
+        let cache = LruCallCache::new(store.clone(), 2, "mainnet", &MetricsRegistry::mock());
+        let lookups = || store.lookups.load(Ordering::SeqCst);
+        let hits = || cache.lookups.with_label_values(&["mainnet", "hit"]).get();
+
+        let b1 = block(1, 1);
+        for data in [1, 2] {
+            cache
+                .set_call(&logger, request(data), b1.clone(), retval(data))
+                .unwrap();
+        }
+
+        // Calls that were set are served from memory
+        let resp = cache.get_call(&request(1), b1.clone()).unwrap().unwrap();
+        assert_eq!(retval(1), resp.retval);
+        assert_eq!(call::Source::Store, resp.source);
+        assert_eq!(0, lookups());
+        assert_eq!(1.0, hits());
+
+        // A block with the same number but a different hash, e.g., after a
+        // reorg, does not see the cached results
+        assert!(cache.get_call(&request(1), block(1, 2)).unwrap().is_none());
+        assert_eq!(1, lookups());
+        assert_eq!(1.0, hits());
+
+        // Adding a third call evicts the least recently used one, i.e.,
+        // the call with data 2; results that are read from the store are
+        // remembered, too
+        store
+            .set_call(&logger, request(3), b1.clone(), retval(3))
+            .unwrap();
+        let (resps, missing) = cache
+            .get_calls(&[request(1), request(3)], b1.clone())
+            .unwrap();
+        assert_eq!(2, resps.len());
+        assert!(missing.is_empty());
+        assert_eq!(2, lookups());
+        assert_eq!(2.0, hits());
+
+        cache.get_call(&request(2), b1.clone()).unwrap().unwrap();
+        assert_eq!(3, lookups());
+        cache.get_call(&request(3), b1.clone()).unwrap().unwrap();
+        assert_eq!(3, lookups());
+        assert_eq!(3.0, hits());
+    }
+}
@@ -14,7 +14,8 @@ use graph::data::store::ethereum::call;
 use graph::data::store::scalar::BigInt;
 use graph::data::subgraph::API_VERSION_0_0_9;
 use graph::futures03::compat::Future01CompatExt;
-use graph::prelude::web3::types::H160;
+use graph::prelude::web3::types::{H160, H256};
+use graph::prelude::{serde_json as json, BlockNumber, ChainStore, EthereumBlock};
 use graph::runtime::gas::Gas;
 use graph::runtime::{AscIndexId, IndexForAscTypeId};
 use graph::slog::debug;
@@ -29,7 +30,9 @@ use graph::{
     semver::Version,
     slog::Logger,
 };
-use graph_runtime_wasm::asc_abi::class::{AscBigInt, AscEnumArray, AscWrapped, EthereumValueKind};
+use graph_runtime_wasm::asc_abi::class::{
+    AscBigInt, AscEnumArray, AscWrapped, EthereumValueKind, Uint8Array,
+};
 use itertools::Itertools;
 
 use super::abi::{AscUnresolvedContractCall, AscUnresolvedContractCall_0_0_4};
@@ -60,10 +63,14 @@ pub const ETH_GET_BALANCE: Gas = Gas::new(5_000_000_000);
 // TODO: Determine the appropriate gas cost for `ETH_HAS_CODE`, initially aligned with `ETHEREUM_CALL`.
 pub const ETH_HAS_CODE: Gas = Gas::new(5_000_000_000);
 
+// TODO: Determine the appropriate gas cost for `ETH_BLOCK_HASH_FROM_NUMBER`, initially aligned with `ETHEREUM_CALL`.
+pub const ETH_BLOCK_HASH_FROM_NUMBER: Gas = Gas::new(5_000_000_000);
+
 pub struct RuntimeAdapter {
     pub eth_adapters: Arc<EthereumNetworkAdapters>,
     pub call_cache: Arc<dyn EthereumCallCache>,
     pub chain_identifier: Arc<ChainIdentifier>,
+    pub chain_store: Arc<dyn ChainStore>,
 }
 
 pub fn eth_call_gas(chain_identifier: &ChainIdentifier) -> Option<u32> {
@@ -131,7 +138,22 @@ impl blockchain::RuntimeAdapter<Chain> for RuntimeAdapter {
             }),
         };
 
-        Ok(vec![ethereum_call, ethereum_get_balance, ethereum_get_code])
+        let eth_adapters = self.eth_adapters.cheap_clone();
+        let chain_store = self.chain_store.cheap_clone();
+        let ethereum_block_hash_from_number = HostFn {
+            name: "ethereum.blockHashFromNumber",
+            func: Arc::new(move |ctx, wasm_ptr| {
+                eth_block_hash_from_number(&eth_adapters, chain_store.cheap_clone(), ctx, wasm_ptr)
+                    .map(|ptr| ptr.wasm_ptr())
+            }),
+        };
+
+        Ok(vec![
+            ethereum_call,
+            ethereum_get_balance,
+            ethereum_get_code,
+            ethereum_block_hash_from_number,
+        ])
     }
 }
 
@@ -246,6 +268,129 @@ fn eth_has_code(
     }
 }
 
+/// Where the hash of a block that a mapping asks for comes from
+#[derive(Debug, PartialEq)]
+enum BlockHashSource {
+    /// The block is the block that is being processed
+    Head,
+    /// The block is an ancestor of the block that is being processed
+    Ancestor(BlockNumber),
+    /// The block is after the block that is being processed or does not
+    /// exist
+    OutOfRange,
+}
+
+impl BlockHashSource {
+    fn new(head: &BlockPtr, number: &BigInt) -> Self {
+        if number < &BigInt::from(0) || number > &BigInt::from(head.number) {
+            Self::OutOfRange
+        } else if number == &BigInt::from(head.number) {
+            Self::Head
+        } else {
+            // `number` is between 0 and `head.number` and therefore fits
+            Self::Ancestor(u64::try_from(number).unwrap() as BlockNumber)
+        }
+    }
+}
+
+/// function ethereum.blockHashFromNumber(number: BigInt): Bytes | null
+///
+/// Returns `null` for blocks after the block that is being processed. For
+/// earlier blocks, the hash is taken from the chain store by following
+/// parent hashes from the block that is being processed, so that it comes
+/// from the chain that the subgraph is following. Only if the chain store
+/// does not have all the blocks in between is the Ethereum node asked, and
+/// then only if the node follows the same chain as the subgraph, which
+/// makes the result independent of reorgs that the node has seen but the
+/// subgraph hasn't processed yet
+fn eth_block_hash_from_number(
+    eth_adapters: &EthereumNetworkAdapters,
+    chain_store: Arc<dyn ChainStore>,
+    ctx: HostFnCtx<'_>,
+    wasm_ptr: u32,
+) -> Result<AscPtr<Uint8Array>, HostExportError> {
+    ctx.gas
+        .consume_host_fn_with_metrics(ETH_BLOCK_HASH_FROM_NUMBER, "eth_block_hash_from_number")?;
+
+    if ctx.heap.api_version() < API_VERSION_0_0_9 {
+        return Err(HostExportError::Deterministic(anyhow!(
+            "ethereum.blockHashFromNumber call is not supported before API version 0.0.9"
+        )));
+    }
+
+    let logger = &ctx.logger;
+    let block_ptr = &ctx.block_ptr;
+
+    let number: BigInt = asc_get(ctx.heap, wasm_ptr.into(), &ctx.gas, 0)?;
+
+    let hash = match BlockHashSource::new(block_ptr, &number) {
+        BlockHashSource::OutOfRange => return Ok(AscPtr::null()),
+        BlockHashSource::Head => block_ptr.hash_as_h256(),
+        BlockHashSource::Ancestor(number) => {
+            match ancestor_hash_from_store(chain_store, block_ptr, number)? {
+                Some(hash) => hash,
+                None => {
+                    let eth_adapter = eth_adapters.cheapest_with(&NodeCapabilities {
+                        archive: false,
+                        traces: false,
+                    })?;
+                    ancestor_hash_from_node(&eth_adapter, logger, block_ptr, number)?
+                }
+            }
+        }
+    };
+
+    Ok(asc_new(ctx.heap, &hash, &ctx.gas)?)
+}
+
+/// The hash of block `number` on the chain that ends in `block_ptr`, or
+/// `None` if the chain store does not have all the blocks between them
+fn ancestor_hash_from_store(
+    chain_store: Arc<dyn ChainStore>,
+    block_ptr: &BlockPtr,
+    number: BlockNumber,
+) -> Result<Option<H256>, HostExportError> {
+    let offset = block_ptr.number - number;
+    let block = graph::block_on(chain_store.ancestor_block(block_ptr.clone(), offset))?;
+    let Some(block) = block else {
+        return Ok(None);
+    };
+    let block: EthereumBlock = json::from_value(block).context("invalid block in chain store")?;
+    Ok(block.block.hash)
+}
+
+/// The hash of block `number` according to the Ethereum node. Fails as a
+/// possible reorg if the node does not have `block_ptr` on its main chain
+fn ancestor_hash_from_node(
+    eth_adapter: &EthereumAdapter,
+    logger: &Logger,
+    block_ptr: &BlockPtr,
+    number: BlockNumber,
+) -> Result<H256, HostExportError> {
+    let hash = graph::block_on(
+        eth_adapter
+            .block_hash_by_block_number(logger, number)
+            .compat(),
+    )
+    .map_err(HostExportError::PossibleReorg)?;
+
+    // The node's block at `number` is only an ancestor of the block we are
+    // processing if the node still considers that block part of the main
+    // chain
+    let on_main_chain = graph::block_on(eth_adapter.is_on_main_chain(logger, block_ptr.clone()))
+        .map_err(HostExportError::PossibleReorg)?;
+    if !on_main_chain {
+        return Err(HostExportError::PossibleReorg(anyhow!(
+            "block {} is not on the main chain of the Ethereum node",
+            block_ptr
+        )));
+    }
+
+    hash.ok_or_else(|| {
+        HostExportError::PossibleReorg(anyhow!("Ethereum node is missing block #{}", number))
+    })
+}
+
 /// Returns `Ok(None)` if the call was reverted.
 fn eth_call(
     eth_adapter: &EthereumAdapter,
@@ -374,3 +519,286 @@ pub struct UnresolvedContractCall {
 impl AscIndexId for AscUnresolvedContractCall {
     const INDEX_ASC_TYPE_ID: IndexForAscTypeId = IndexForAscTypeId::SmartContractCall;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::mem::MaybeUninit;
+    use std::sync::Arc;
+
+    use graph::anyhow::{anyhow, Error};
+    use graph::blockchain::{Block, BlockHash, BlockPtr, ChainIdentifier, HostFnCtx};
+    use graph::components::metrics::gas::GasMetrics;
+    use graph::components::metrics::stopwatch::StopwatchMetrics;
+    use graph::components::subgraph::HostMetrics;
+    use graph::components::transaction_receipt::LightTransactionReceipt;
+    use graph::data::store::scalar::BigInt;
+    use graph::data::subgraph::API_VERSION_0_0_9;
+    use graph::log::discard;
+    use graph::prelude::web3::types::{H256, U64};
+    use graph::prelude::{
+        async_trait, serde_json as json, BlockNumber, ChainStore, DeploymentHash, EthereumBlock,
+        LightEthereumBlock, MetricsRegistry, StoreError,
+    };
+    use graph::runtime::gas::GasCounter;
+    use graph::runtime::{
+        asc_get, asc_new, AscHeap, AscPtr, DeterministicHostError, HostExportError,
+        IndexForAscTypeId,
+    };
+    use graph::semver::Version;
+    use graph::util::mem::init_slice;
+    use graph_runtime_wasm::asc_abi::class::{AscBigInt, Uint8Array};
+
+    use crate::network::EthereumNetworkAdapters;
+
+    use super::{eth_block_hash_from_number, BlockHashSource};
+
+    /// A heap that lays out objects the same way as the memory of a
+    /// wasm instance
+    struct BytesHeap {
+        api_version: Version,
+        memory: Vec<u8>,
+    }
+
+    impl AscHeap for BytesHeap {
+        fn raw_new(&mut self, bytes: &[u8], _: &GasCounter) -> Result<u32, DeterministicHostError> {
+            self.memory.extend_from_slice(bytes);
+            Ok((self.memory.len() - bytes.len()) as u32)
+        }
+
+        fn read<'a>(
+            &self,
+            offset: u32,
+            buffer: &'a mut [MaybeUninit<u8>],
+            _: &GasCounter,
+        ) -> Result<&'a mut [u8], DeterministicHostError> {
+            let start = offset as usize;
+            let src = self
+                .memory
+                .get(start..start + buffer.len())
+                .ok_or_else(|| {
+                    DeterministicHostError::from(anyhow!("offset {} is out of bounds", offset))
+                })?;
+            Ok(init_slice(src, buffer))
+        }
+
+        fn read_u32(&self, offset: u32, gas: &GasCounter) -> Result<u32, DeterministicHostError> {
+            let mut data = [MaybeUninit::<u8>::uninit(); 4];
+            let init = self.read(offset, &mut data, gas)?;
+            Ok(u32::from_le_bytes(init.try_into().unwrap()))
+        }
+
+        fn api_version(&self) -> Version {
+            self.api_version.clone()
+        }
+
+        fn asc_type_id(
+            &mut self,
+            type_id_index: IndexForAscTypeId,
+        ) -> Result<u32, HostExportError> {
+            Ok(type_id_index as u32)
+        }
+    }
+
+    /// A chain store that only has the blocks from `first` to the end of
+    /// `hashes`, where `hashes[i]` is the hash of block `i`
+    struct BlocksStore {
+        hashes: Vec<H256>,
+        first: BlockNumber,
+    }
+
+    #[async_trait]
+    impl ChainStore for BlocksStore {
+        fn genesis_block_ptr(&self) -> Result<BlockPtr, Error> {
+            unimplemented!()
+        }
+
+        async fn upsert_block(&self, _: Arc<dyn Block>) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn upsert_light_blocks(&self, _: &[&dyn Block]) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        async fn attempt_chain_head_update(
+            self: Arc<Self>,
+            _: BlockNumber,
+        ) -> Result<Option<H256>, Error> {
+            unimplemented!()
+        }
+
+        async fn chain_head_ptr(self: Arc<Self>) -> Result<Option<BlockPtr>, Error> {
+            unimplemented!()
+        }
+
+        fn chain_head_cursor(&self) -> Result<Option<String>, Error> {
+            unimplemented!()
+        }
+
+        async fn set_chain_head(
+            self: Arc<Self>,
+            _: Arc<dyn Block>,
+            _: String,
+        ) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        async fn blocks(self: Arc<Self>, _: Vec<BlockHash>) -> Result<Vec<json::Value>, Error> {
+            unimplemented!()
+        }
+
+        async fn ancestor_block(
+            self: Arc<Self>,
+            block_ptr: BlockPtr,
+            offset: BlockNumber,
+        ) -> Result<Option<json::Value>, Error> {
+            let number = block_ptr.number - offset;
+            if number < self.first || block_ptr.number as usize >= self.hashes.len() {
+                return Ok(None);
+            }
+            let block = LightEthereumBlock {
+                hash: Some(self.hashes[number as usize]),
+                number: Some(U64::from(number as u64)),
+                ..Default::default()
+            };
+            let block = EthereumBlock {
+                block: Arc::new(block),
+                transaction_receipts: vec![],
+            };
+            Ok(Some(json::to_value(block)?))
+        }
+
+        fn cleanup_cached_blocks(
+            &self,
+            _: BlockNumber,
+        ) -> Result<Option<(BlockNumber, usize)>, Error> {
+            unimplemented!()
+        }
+
+        fn block_hashes_by_block_number(&self, _: BlockNumber) -> Result<Vec<BlockHash>, Error> {
+            unimplemented!()
+        }
+
+        fn confirm_block_hash(&self, _: BlockNumber, _: &BlockHash) -> Result<usize, Error> {
+            unimplemented!()
+        }
+
+        async fn block_number(
+            &self,
+            _: &BlockHash,
+        ) -> Result<Option<(String, BlockNumber, Option<u64>, Option<BlockHash>)>, StoreError>
+        {
+            unimplemented!()
+        }
+
+        async fn block_numbers(
+            &self,
+            _: Vec<BlockHash>,
+        ) -> Result<HashMap<BlockHash, BlockNumber>, StoreError> {
+            unimplemented!()
+        }
+
+        async fn transaction_receipts_in_block(
+            &self,
+            _: &H256,
+        ) -> Result<Vec<LightTransactionReceipt>, StoreError> {
+            unimplemented!()
+        }
+
+        async fn clear_call_cache(&self, _: BlockNumber, _: BlockNumber) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn chain_identifier(&self) -> &ChainIdentifier {
+            unimplemented!()
+        }
+    }
+
+    /// Call `ethereum.blockHashFromNumber(number)` while processing block
+    /// `head` with a chain store that has the blocks from `first` to `head`
+    fn block_hash_from_number(
+        head: BlockNumber,
+        first: BlockNumber,
+        number: i64,
+    ) -> Result<Option<H256>, HostExportError> {
+        let hashes: Vec<_> = (0..=head as u64).map(H256::from_low_u64_be).collect();
+        let chain_store = Arc::new(BlocksStore {
+            hashes: hashes.clone(),
+            first,
+        });
+        let eth_adapters = EthereumNetworkAdapters::new(None);
+
+        let registry = Arc::new(MetricsRegistry::mock());
+        let deployment = DeploymentHash::new("blockHashFromNumber").unwrap();
+        let host_metrics = Arc::new(HostMetrics::new(
+            registry.clone(),
+            deployment.as_str(),
+            StopwatchMetrics::new(
+                discard(),
+                deployment.clone(),
+                "test",
+                registry.clone(),
+                "test_shard".to_string(),
+            ),
+            GasMetrics::mock(),
+        ));
+        let gas = GasCounter::new(GasMetrics::mock());
+        let mut heap = BytesHeap {
+            api_version: API_VERSION_0_0_9.clone(),
+            memory: vec![],
+        };
+
+        let arg: AscPtr<AscBigInt> = asc_new(&mut heap, &BigInt::from(number), &gas)?;
+        let ctx = HostFnCtx {
+            logger: discard(),
+            block_ptr: BlockPtr::from((hashes[head as usize], head)),
+            heap: &mut heap,
+            gas: gas.clone(),
+            metrics: host_metrics,
+        };
+        let ptr: AscPtr<Uint8Array> =
+            eth_block_hash_from_number(&eth_adapters, chain_store, ctx, arg.wasm_ptr())?;
+        if ptr.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(asc_get(&heap, ptr, &gas, 0)?))
+    }
+
+    #[test]
+    fn block_hash_from_number_available() {
+        let hash = |number| Some(H256::from_low_u64_be(number));
+
+        assert_eq!(hash(100), block_hash_from_number(100, 0, 100).unwrap());
+        assert_eq!(hash(42), block_hash_from_number(100, 0, 42).unwrap());
+        assert_eq!(hash(0), block_hash_from_number(100, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn block_hash_from_number_out_of_range() {
+        assert_eq!(None, block_hash_from_number(100, 0, 101).unwrap());
+        assert_eq!(None, block_hash_from_number(100, 0, -1).unwrap());
+        assert_eq!(None, block_hash_from_number(100, 0, i64::MAX).unwrap());
+    }
+
+    #[test]
+    fn block_hash_from_number_not_in_chain_store() {
+        // Without the block in the chain store, the hash has to come from
+        // an Ethereum node, and there is none here; that must be an error,
+        // not `null`, since `null` would be recorded in the subgraph
+        assert!(block_hash_from_number(100, 50, 42).is_err());
+    }
+
+    #[test]
+    fn block_hash_source() {
+        let head = BlockPtr::from((H256::zero(), 100_i32));
+        let source = |number: i64| BlockHashSource::new(&head, &BigInt::from(number));
+
+        assert_eq!(BlockHashSource::Head, source(100));
+        assert_eq!(BlockHashSource::Ancestor(99), source(99));
+        assert_eq!(BlockHashSource::Ancestor(0), source(0));
+        assert_eq!(BlockHashSource::OutOfRange, source(101));
+        assert_eq!(BlockHashSource::OutOfRange, source(-1));
+        assert_eq!(BlockHashSource::OutOfRange, source(i64::MAX));
+    }
+}
@@ -1,4 +1,4 @@
-use graph::endpoint::{EndpointMetrics, Provider, RequestLabels};
+use graph::endpoint::{ConnectionType, EndpointMetrics, Provider, RateLimiter, RequestLabels};
 use jsonrpc_core::types::Call;
 use jsonrpc_core::Value;
 
@@ -16,6 +16,7 @@ pub enum Transport {
         client: http::Http,
         metrics: Arc<EndpointMetrics>,
         provider: Provider,
+        rate_limiter: Option<Arc<RateLimiter>>,
     },
     IPC(ipc::Ipc),
     WS(ws::WebSocket),
@@ -59,8 +60,19 @@ impl Transport {
             client: http::Http::with_client(client, rpc),
             metrics,
             provider: provider.as_ref().into(),
+            rate_limiter: None,
         }
     }
+
+    /// Make requests wait for `limiter` before they are sent. Only
+    /// JSON-RPC over HTTP can be rate limited; for other transports,
+    /// this does nothing
+    pub fn with_rate_limiter(mut self, limiter: Option<Arc<RateLimiter>>) -> Self {
+        if let Transport::RPC { rate_limiter, .. } = &mut self {
+            *rate_limiter = limiter;
+        }
+        self
+    }
 }
 
 impl web3::Transport for Transport {
@@ -68,11 +80,7 @@ impl web3::Transport for Transport {
 
     fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
         match self {
-            Transport::RPC {
-                client,
-                metrics: _,
-                provider: _,
-            } => client.prepare(method, params),
+            Transport::RPC { client, .. } => client.prepare(method, params),
             Transport::IPC(ipc) => ipc.prepare(method, params),
             Transport::WS(ws) => ws.prepare(method, params),
         }
@@ -84,9 +92,11 @@ impl web3::Transport for Transport {
                 client,
                 metrics,
                 provider,
+                rate_limiter,
             } => {
                 let metrics = metrics.cheap_clone();
                 let client = client.clone();
+                let rate_limiter = rate_limiter.clone();
                 let method = match request {
                     Call::MethodCall(ref m) => m.method.as_str(),
                     _ => "unknown",
@@ -95,9 +105,14 @@ impl web3::Transport for Transport {
                 let labels = RequestLabels {
                     provider: provider.clone(),
                     req_type: method.into(),
-                    conn_type: graph::endpoint::ConnectionType::Rpc,
+                    conn_type: ConnectionType::Rpc,
                 };
                 let out = async move {
+                    if let Some(limiter) = rate_limiter {
+                        let wait = limiter.acquire(1).await;
+                        metrics.rate_limited(&labels.provider, &labels.conn_type, wait);
+                    }
+
                     let out = client.send(id, request).await;
                     match out {
                         Ok(_) => metrics.success(&labels),
@@ -129,9 +144,28 @@ impl web3::BatchTransport for Transport {
         match self {
             Transport::RPC {
                 client,
-                metrics: _,
-                provider: _,
+                rate_limiter: None,
+                ..
             } => Box::new(client.send_batch(requests)),
+            Transport::RPC {
+                client,
+                metrics,
+                provider,
+                rate_limiter: Some(limiter),
+            } => {
+                let requests: Vec<_> = requests.into_iter().collect();
+                let client = client.clone();
+                let metrics = metrics.cheap_clone();
+                let provider = provider.clone();
+                let limiter = limiter.cheap_clone();
+
+                // Every request in the batch counts against the rate limit
+                Box::new(Box::pin(async move {
+                    let wait = limiter.acquire(requests.len()).await;
+                    metrics.rate_limited(&provider, &ConnectionType::Rpc, wait);
+                    client.send_batch(requests).await
+                }))
+            }
             Transport::IPC(ipc) => Box::new(ipc.send_batch(requests)),
             Transport::WS(ws) => Box::new(ws.send_batch(requests)),
         }
@@ -445,6 +445,7 @@ impl BlockStreamMapper<Chain> for FirehoseMapper {
         _logger: &Logger,
         _clock: Clock,
         cursor: FirehoseCursor,
+        _type_url: &str,
         message: Vec<u8>,
     ) -> Result<BlockStreamEvent<Chain>, BlockStreamError> {
         let BlockAndReceipts {
@@ -49,13 +49,13 @@ impl BlockStreamBuilderTrait<Chain> for BlockStreamBuilder {
             .new(o!("component" => "SubstreamsBlockStream"));
 
         let stream = match &filter.mapping_handler {
-            Some(handler) => SubstreamsBlockStream::new(
+            Some(handlers) => SubstreamsBlockStream::new(
                 deployment.hash,
                 chain.chain_client(),
                 subgraph_current_block,
                 block_cursor.clone(),
                 Arc::new(WasmBlockMapper {
-                    handler: handler.clone(),
+                    handlers: handlers.clone(),
                 }),
                 filter.modules.clone(),
                 filter.module_name.clone(),
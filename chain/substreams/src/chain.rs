@@ -1,10 +1,11 @@
 use crate::block_ingestor::SubstreamsBlockIngestor;
-use crate::{data_source::*, EntityChanges, TriggerData, TriggerFilter, TriggersAdapter};
+use crate::{
+    data_source::*, EntityChanges, RuntimeAdapter, TriggerData, TriggerFilter, TriggersAdapter,
+};
 use anyhow::Error;
 use graph::blockchain::client::ChainClient;
 use graph::blockchain::{
     BasicBlockchainBuilder, BlockIngestor, BlockTime, EmptyNodeCapabilities, NoopDecoderHook,
-    NoopRuntimeAdapter,
 };
 use graph::components::store::DeploymentCursorTracker;
 use graph::env::EnvVars;
@@ -182,7 +183,7 @@ impl Blockchain for Chain {
         })
     }
     fn runtime(&self) -> (Arc<dyn RuntimeAdapterTrait<Self>>, Self::DecoderHook) {
-        (Arc::new(NoopRuntimeAdapter::default()), NoopDecoderHook)
+        (Arc::new(RuntimeAdapter), NoopDecoderHook)
     }
 
     fn chain_client(&self) -> Arc<ChainClient<Self>> {
@@ -1,4 +1,7 @@
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
 
 use anyhow::{anyhow, Context, Error};
 use graph::{
@@ -156,10 +159,33 @@ pub struct Mapping {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MappingHandler {
-    pub handler: String,
+    pub handlers: MessageHandlers,
     pub runtime: Arc<Vec<u8>>,
 }
 
+/// The WASM handlers that the output of a substreams module is passed to.
+/// Handlers in `by_type` are keyed by the type URL of the protobuf message,
+/// or just the fully qualified message name, and take precedence over the
+/// `default` handler
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MessageHandlers {
+    pub default: Option<String>,
+    pub by_type: BTreeMap<String, String>,
+}
+
+impl MessageHandlers {
+    /// Return the handler for a message with the given type URL, such as
+    /// `type.googleapis.com/my.pkg.Transfer`
+    pub fn handler_for(&self, type_url: &str) -> Option<&str> {
+        let name = type_url.rsplit('/').next().unwrap_or(type_url);
+        self.by_type
+            .get(type_url)
+            .or_else(|| self.by_type.get(name))
+            .or(self.default.as_ref())
+            .map(|handler| handler.as_str())
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
 /// Raw representation of the data source for deserialization purposes.
 pub struct UnresolvedDataSource {
@@ -177,9 +203,19 @@ pub struct UnresolvedMapping {
     pub api_version: String,
     pub kind: String,
     pub handler: Option<String>,
+    #[serde(default)]
+    pub handlers: Vec<UnresolvedMessageHandler>,
     pub file: Option<Link>,
 }
 
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// A handler for module outputs of one protobuf message type
+pub struct UnresolvedMessageHandler {
+    pub handler: String,
+    pub message_type: String,
+}
+
 #[async_trait]
 impl blockchain::UnresolvedDataSource<Chain> for UnresolvedDataSource {
     async fn resolve(
@@ -228,15 +264,34 @@ impl blockchain::UnresolvedDataSource<Chain> for UnresolvedDataSource {
             .map_or(Ok(None), |x: u64| TryInto::<i32>::try_into(x).map(Some))
             .map_err(anyhow::Error::from)?;
 
-        let handler = match (self.mapping.handler, self.mapping.file) {
-            (Some(handler), Some(file)) => {
+        let mut by_type = BTreeMap::new();
+        for UnresolvedMessageHandler {
+            handler,
+            message_type,
+        } in self.mapping.handlers
+        {
+            if by_type.insert(message_type.clone(), handler).is_some() {
+                return Err(anyhow!(
+                    "message type {} has more than one handler",
+                    message_type
+                ));
+            }
+        }
+        let handlers = MessageHandlers {
+            default: self.mapping.handler,
+            by_type,
+        };
+
+        let has_handlers = handlers.default.is_some() || !handlers.by_type.is_empty();
+        let handler = match self.mapping.file {
+            Some(file) if has_handlers => {
                 let module_bytes = resolver
                     .cat(logger, &file)
                     .await
                     .with_context(|| format!("failed to resolve mapping {}", file.link))?;
 
                 Some(MappingHandler {
-                    handler,
+                    handlers,
                     runtime: Arc::new(module_bytes),
                 })
             }
@@ -321,7 +376,7 @@ impl blockchain::UnresolvedDataSourceTemplate<Chain> for NoopDataSourceTemplate
 
 #[cfg(test)]
 mod test {
-    use std::{str::FromStr, sync::Arc};
+    use std::{collections::BTreeMap, str::FromStr, sync::Arc};
 
     use anyhow::Error;
     use graph::{
@@ -340,7 +395,10 @@ mod test {
     };
     use prost::Message;
 
-    use crate::{DataSource, Mapping, UnresolvedDataSource, UnresolvedMapping, SUBSTREAMS_KIND};
+    use crate::{
+        DataSource, Mapping, MessageHandlers, UnresolvedDataSource, UnresolvedMapping,
+        UnresolvedMessageHandler, SUBSTREAMS_KIND,
+    };
 
     #[test]
     fn parse_data_source() {
@@ -363,6 +421,7 @@ mod test {
                 api_version: "0.0.7".into(),
                 kind: "substreams/graph-entities".into(),
                 handler: None,
+                handlers: vec![],
                 file: None,
             },
         };
@@ -391,6 +450,7 @@ mod test {
                 api_version: "0.0.7".into(),
                 kind: "substreams/graph-entities".into(),
                 handler: None,
+                handlers: vec![],
                 file: None,
             },
         };
@@ -508,6 +568,7 @@ mod test {
                 api_version: "0.0.7".into(),
                 kind: "substreams/graph-entities".into(),
                 handler: Some("bananas".to_string()),
+                handlers: vec![],
                 file: Some(Link {
                     link: "./src/mappings.ts".to_string(),
                 }),
@@ -516,6 +577,60 @@ mod test {
         assert_eq!(ds, expected);
     }
 
+    #[test]
+    fn parse_data_source_with_message_handlers() {
+        let ds: UnresolvedDataSource =
+            serde_yaml::from_str(TEMPLATE_DATA_SOURCE_WITH_MESSAGE_HANDLERS).unwrap();
+
+        assert_eq!(ds.mapping.handler, Some("handleOther".to_string()));
+        assert_eq!(
+            ds.mapping.handlers,
+            vec![
+                UnresolvedMessageHandler {
+                    handler: "handleTransfer".to_string(),
+                    message_type: "my.pkg.Transfer".to_string(),
+                },
+                UnresolvedMessageHandler {
+                    handler: "handleMint".to_string(),
+                    message_type: "type.googleapis.com/my.pkg.Mint".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn message_handler_dispatch() {
+        let mut handlers = MessageHandlers {
+            default: None,
+            by_type: BTreeMap::from_iter([
+                ("my.pkg.Transfer".to_string(), "handleTransfer".to_string()),
+                (
+                    "type.googleapis.com/my.pkg.Mint".to_string(),
+                    "handleMint".to_string(),
+                ),
+            ]),
+        };
+
+        assert_eq!(
+            handlers.handler_for("type.googleapis.com/my.pkg.Transfer"),
+            Some("handleTransfer")
+        );
+        assert_eq!(
+            handlers.handler_for("type.googleapis.com/my.pkg.Mint"),
+            Some("handleMint")
+        );
+        assert_eq!(
+            handlers.handler_for("type.googleapis.com/my.pkg.Burn"),
+            None
+        );
+
+        handlers.default = Some("handleOther".to_string());
+        assert_eq!(
+            handlers.handler_for("type.googleapis.com/my.pkg.Burn"),
+            Some("handleOther")
+        );
+    }
+
     fn gen_package() -> Package {
         Package {
             proto_files: vec![],
@@ -623,6 +738,28 @@ mod test {
           handler: bananas
     "#;
 
+    const TEMPLATE_DATA_SOURCE_WITH_MESSAGE_HANDLERS: &str = r#"
+        kind: substreams
+        name: Uniswap
+        network: mainnet
+        source:
+          package:
+            moduleName: output
+            file:
+              /: /ipfs/QmbHnhUFZa6qqqRyubUYhXntox1TCBxqryaBM1iNGqVJzT
+        mapping:
+          kind: substreams/graph-entities
+          apiVersion: 0.0.7
+          file:
+            /: ./src/mappings.ts
+          handler: handleOther
+          handlers:
+            - handler: handleTransfer
+              messageType: my.pkg.Transfer
+            - handler: handleMint
+              messageType: type.googleapis.com/my.pkg.Mint
+    "#;
+
     const TEMPLATE_DATA_SOURCE_WITH_PARAMS: &str = r#"
         kind: substreams
         name: Uniswap
@@ -0,0 +1,587 @@
+//! Decode protobuf messages into JSON values with the message descriptors
+//! from a substreams package, so that mappings do not need to carry their
+//! own protobuf decoder.
+//!
+//! The conversion follows the proto3 JSON mapping with a few exceptions
+//! that make the values easier to use from mappings:
+//!
+//! - `bytes` are hex strings with a `0x` prefix instead of base64
+//! - 64 bit integers are JSON numbers instead of strings; mappings can
+//!   turn them into a `BigInt` without losing precision
+//! - fields that are not set have their default value, except for
+//!   message fields, which are `null`, and members of a `oneof` and
+//!   `optional` fields, which are left out
+//! - unknown fields are ignored
+//!
+//! The result only depends on the bytes and the descriptors, which makes
+//! decoding deterministic.
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, bail, Error};
+use graph::prelude::serde_json::{self, Map, Value};
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+};
+
+/// Messages nested deeper than this are rejected. This is the same limit
+/// that `prost` uses
+const MAX_DEPTH: usize = 100;
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_FIXED64: u64 = 1;
+const WIRE_LEN: u64 = 2;
+const WIRE_START_GROUP: u64 = 3;
+const WIRE_END_GROUP: u64 = 4;
+const WIRE_FIXED32: u64 = 5;
+
+/// The message and enum types from a set of `.proto` files, keyed by their
+/// fully qualified name, e.g., `my.pkg.Transfer`
+#[derive(Debug, Default)]
+pub struct MessageDecoder {
+    messages: HashMap<String, DescriptorProto>,
+    enums: HashMap<String, EnumDescriptorProto>,
+}
+
+impl MessageDecoder {
+    pub fn new(files: &[FileDescriptorProto]) -> Self {
+        let mut decoder = MessageDecoder::default();
+        for file in files {
+            for message in &file.message_type {
+                decoder.add_message(file.package(), message);
+            }
+            for enum_type in &file.enum_type {
+                decoder.add_enum(file.package(), enum_type);
+            }
+        }
+        decoder
+    }
+
+    fn add_message(&mut self, scope: &str, message: &DescriptorProto) {
+        let name = qualify(scope, message.name());
+        for nested in &message.nested_type {
+            self.add_message(&name, nested);
+        }
+        for enum_type in &message.enum_type {
+            self.add_enum(&name, enum_type);
+        }
+        self.messages.insert(name, message.clone());
+    }
+
+    fn add_enum(&mut self, scope: &str, enum_type: &EnumDescriptorProto) {
+        self.enums
+            .insert(qualify(scope, enum_type.name()), enum_type.clone());
+    }
+
+    /// Decode `data` as a message of type `message_type`. The type can be
+    /// given as a fully qualified name or as a type URL like
+    /// `type.googleapis.com/my.pkg.Transfer`
+    pub fn decode(&self, message_type: &str, data: &[u8]) -> Result<Value, Error> {
+        let name = message_type.rsplit('/').next().unwrap_or(message_type);
+        let message = self.message(name)?;
+        self.decode_message(message, data, 0)
+    }
+
+    fn message(&self, name: &str) -> Result<&DescriptorProto, Error> {
+        let name = name.trim_start_matches('.');
+        self.messages
+            .get(name)
+            .ok_or_else(|| anyhow!("unknown protobuf message type `{}`", name))
+    }
+
+    fn decode_message(
+        &self,
+        message: &DescriptorProto,
+        data: &[u8],
+        depth: usize,
+    ) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            bail!("protobuf message `{}` is nested too deeply", message.name());
+        }
+
+        // The values we found for each field, keyed by its position in
+        // `message.field`. For message fields that are not repeated, we
+        // collect the raw bytes since several occurrences of such a field
+        // have to be merged, which is the same as decoding their
+        // concatenation
+        let mut values: BTreeMap<usize, Vec<Value>> = BTreeMap::new();
+        let mut embedded: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+
+        let mut reader = Reader { data, pos: 0 };
+        while !reader.is_empty() {
+            let key = reader.varint()?;
+            let (number, wire_type) = (key >> 3, key & 0x7);
+            let Some(pos) = message
+                .field
+                .iter()
+                .position(|field| field.number() as u64 == number)
+            else {
+                reader.skip(wire_type)?;
+                continue;
+            };
+            let field = &message.field[pos];
+            let repeated = field.label() == Label::Repeated;
+
+            match (field.r#type(), wire_type) {
+                (Type::Message, WIRE_LEN) if !repeated => {
+                    embedded
+                        .entry(pos)
+                        .or_default()
+                        .extend_from_slice(reader.bytes()?);
+                }
+                (Type::Message, WIRE_LEN) => {
+                    let nested = self.message(field.type_name())?;
+                    let value = self.decode_message(nested, reader.bytes()?, depth + 1)?;
+                    values.entry(pos).or_default().push(value);
+                }
+                (Type::String | Type::Bytes, WIRE_LEN) => {
+                    let value = self.scalar(field, WIRE_LEN, &mut reader)?;
+                    values.entry(pos).or_default().push(value);
+                }
+                (_, WIRE_LEN) if repeated => {
+                    // A packed list of scalars
+                    let mut packed = Reader {
+                        data: reader.bytes()?,
+                        pos: 0,
+                    };
+                    let wire_type = scalar_wire_type(field.r#type())?;
+                    let list = values.entry(pos).or_default();
+                    while !packed.is_empty() {
+                        list.push(self.scalar(field, wire_type, &mut packed)?);
+                    }
+                }
+                (_, wire_type) => {
+                    let value = self.scalar(field, wire_type, &mut reader)?;
+                    values.entry(pos).or_default().push(value);
+                }
+            }
+        }
+
+        let mut object = Map::new();
+        for (pos, field) in message.field.iter().enumerate() {
+            let name = field
+                .json_name
+                .clone()
+                .unwrap_or_else(|| field.name().to_string());
+            let mut found = values.remove(&pos);
+
+            let value = if field.label() == Label::Repeated {
+                let found = found.unwrap_or_default();
+                if self.is_map_entry(field) {
+                    Value::Object(map_from_entries(found))
+                } else {
+                    Value::Array(found)
+                }
+            } else if field.r#type() == Type::Message {
+                match embedded.remove(&pos) {
+                    Some(data) => {
+                        let nested = self.message(field.type_name())?;
+                        self.decode_message(nested, &data, depth + 1)?
+                    }
+                    None if field.oneof_index.is_some() => continue,
+                    None => Value::Null,
+                }
+            } else {
+                // The last occurrence of a scalar field wins
+                match found.as_mut().and_then(|found| found.pop()) {
+                    Some(value) => value,
+                    None if field.oneof_index.is_some() => continue,
+                    None => self.default_value(field)?,
+                }
+            };
+            object.insert(name, value);
+        }
+        Ok(Value::Object(object))
+    }
+
+    fn is_map_entry(&self, field: &FieldDescriptorProto) -> bool {
+        field.r#type() == Type::Message
+            && self
+                .message(field.type_name())
+                .ok()
+                .and_then(|message| message.options.as_ref())
+                .map(|options| options.map_entry())
+                .unwrap_or(false)
+    }
+
+    /// Read a scalar value for `field` that was encoded with `wire_type`
+    fn scalar(
+        &self,
+        field: &FieldDescriptorProto,
+        wire_type: u64,
+        reader: &mut Reader,
+    ) -> Result<Value, Error> {
+        let typ = field.r#type();
+        let expected = match typ {
+            Type::String | Type::Bytes => WIRE_LEN,
+            Type::Group | Type::Message => {
+                bail!("field `{}` has unsupported type {:?}", field.name(), typ)
+            }
+            _ => scalar_wire_type(typ)?,
+        };
+        if wire_type != expected {
+            bail!(
+                "field `{}` has wire type {} but should have wire type {}",
+                field.name(),
+                wire_type,
+                expected
+            );
+        }
+
+        let value = match typ {
+            Type::Int32 => Value::from(reader.varint()? as i64 as i32),
+            Type::Int64 => Value::from(reader.varint()? as i64),
+            Type::Uint32 => Value::from(reader.varint()? as u32),
+            Type::Uint64 => Value::from(reader.varint()?),
+            Type::Sint32 => Value::from(zigzag(reader.varint()?) as i32),
+            Type::Sint64 => Value::from(zigzag(reader.varint()?)),
+            Type::Bool => Value::from(reader.varint()? != 0),
+            Type::Enum => self.enum_value(field, reader.varint()? as i32),
+            Type::Fixed32 => Value::from(u32::from_le_bytes(reader.fixed()?)),
+            Type::Sfixed32 => Value::from(i32::from_le_bytes(reader.fixed()?)),
+            Type::Fixed64 => Value::from(u64::from_le_bytes(reader.fixed()?)),
+            Type::Sfixed64 => Value::from(i64::from_le_bytes(reader.fixed()?)),
+            // Go through the shortest decimal representation of the `f32`
+            // so that `0.1` does not turn into `0.10000000149011612`
+            Type::Float => float(
+                f32::from_le_bytes(reader.fixed()?)
+                    .to_string()
+                    .parse()
+                    .unwrap(),
+            ),
+            Type::Double => float(f64::from_le_bytes(reader.fixed()?)),
+            Type::String => {
+                let bytes = reader.bytes()?;
+                let s = std::str::from_utf8(bytes)
+                    .map_err(|_| anyhow!("field `{}` is not valid UTF-8", field.name()))?;
+                Value::from(s)
+            }
+            Type::Bytes => Value::from(format!("0x{}", hex::encode(reader.bytes()?))),
+            Type::Group | Type::Message => unreachable!("checked above"),
+        };
+        Ok(value)
+    }
+
+    /// The name of the enum value `number`, or the number itself if the
+    /// enum does not have a value with that number
+    fn enum_value(&self, field: &FieldDescriptorProto, number: i32) -> Value {
+        self.enums
+            .get(field.type_name().trim_start_matches('.'))
+            .and_then(|enum_type| {
+                enum_type
+                    .value
+                    .iter()
+                    .find(|value| value.number() == number)
+            })
+            .map(|value| Value::from(value.name()))
+            .unwrap_or_else(|| Value::from(number))
+    }
+
+    fn default_value(&self, field: &FieldDescriptorProto) -> Result<Value, Error> {
+        let value = match field.r#type() {
+            Type::Int32
+            | Type::Int64
+            | Type::Uint32
+            | Type::Uint64
+            | Type::Sint32
+            | Type::Sint64
+            | Type::Fixed32
+            | Type::Sfixed32
+            | Type::Fixed64
+            | Type::Sfixed64 => Value::from(0),
+            Type::Float | Type::Double => float(0.0),
+            Type::Bool => Value::from(false),
+            Type::String => Value::from(""),
+            Type::Bytes => Value::from("0x"),
+            Type::Enum => self.enum_value(field, 0),
+            typ @ (Type::Group | Type::Message) => {
+                bail!("field `{}` has unsupported type {:?}", field.name(), typ)
+            }
+        };
+        Ok(value)
+    }
+}
+
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+/// Turn the decoded entries of a map field into an object. The keys of
+/// protobuf maps are integers, booleans or strings; JSON object keys have
+/// to be strings
+fn map_from_entries(entries: Vec<Value>) -> Map<String, Value> {
+    let mut map = Map::new();
+    for mut entry in entries {
+        let key = match entry.get_mut("key").map(Value::take) {
+            Some(Value::String(key)) => key,
+            Some(key) => key.to_string(),
+            None => String::new(),
+        };
+        let value = entry.get_mut("value").map(Value::take);
+        map.insert(key, value.unwrap_or(Value::Null));
+    }
+    map
+}
+
+/// Convert a float to JSON. JSON has no representation for infinity and
+/// NaN, and the proto3 JSON mapping uses strings for them
+fn float(f: f64) -> Value {
+    if f.is_nan() {
+        Value::from("NaN")
+    } else if f.is_infinite() && f > 0.0 {
+        Value::from("Infinity")
+    } else if f.is_infinite() {
+        Value::from("-Infinity")
+    } else {
+        serde_json::Number::from_f64(f)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
+
+fn zigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+/// The wire type for a scalar of type `typ` that is not length-delimited
+fn scalar_wire_type(typ: Type) -> Result<u64, Error> {
+    match typ {
+        Type::Int32
+        | Type::Int64
+        | Type::Uint32
+        | Type::Uint64
+        | Type::Sint32
+        | Type::Sint64
+        | Type::Bool
+        | Type::Enum => Ok(WIRE_VARINT),
+        Type::Fixed64 | Type::Sfixed64 | Type::Double => Ok(WIRE_FIXED64),
+        Type::Fixed32 | Type::Sfixed32 | Type::Float => Ok(WIRE_FIXED32),
+        Type::String | Type::Bytes | Type::Group | Type::Message => {
+            bail!("values of type {:?} can not be packed", typ)
+        }
+    }
+}
+
+/// A cursor over protobuf wire data
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or_else(|| anyhow!("protobuf data ends in the middle of a varint"))?;
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("protobuf data contains a varint that is too long")
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| anyhow!("protobuf data ends in the middle of a field"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn fixed<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.varint()?;
+        let len = usize::try_from(len).map_err(|_| anyhow!("protobuf field is too long"))?;
+        self.take(len)
+    }
+
+    fn skip(&mut self, wire_type: u64) -> Result<(), Error> {
+        match wire_type {
+            WIRE_VARINT => self.varint().map(|_| ()),
+            WIRE_FIXED64 => self.take(8).map(|_| ()),
+            WIRE_LEN => self.bytes().map(|_| ()),
+            WIRE_FIXED32 => self.take(4).map(|_| ()),
+            WIRE_START_GROUP | WIRE_END_GROUP => bail!("protobuf groups are not supported"),
+            _ => bail!("protobuf data contains invalid wire type {}", wire_type),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use graph::prelude::serde_json::json;
+    use prost::encoding::{self, WireType};
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::{
+        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+        FileDescriptorProto, MessageOptions,
+    };
+
+    use super::MessageDecoder;
+
+    fn field(name: &str, number: i32, typ: Type, label: Label) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            r#type: Some(typ as i32),
+            label: Some(label as i32),
+            ..Default::default()
+        }
+    }
+
+    fn typed(mut field: FieldDescriptorProto, type_name: &str) -> FieldDescriptorProto {
+        field.type_name = Some(type_name.to_string());
+        field
+    }
+
+    fn descriptors() -> Vec<FileDescriptorProto> {
+        use Label::*;
+
+        let entry = DescriptorProto {
+            name: Some("BalancesEntry".to_string()),
+            field: vec![
+                field("key", 1, Type::String, Optional),
+                field("value", 2, Type::Uint64, Optional),
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let transfer = DescriptorProto {
+            name: Some("Transfer".to_string()),
+            field: vec![
+                field("from", 1, Type::Bytes, Optional),
+                field("amount", 2, Type::Uint64, Optional),
+                field("delta", 3, Type::Sint64, Optional),
+                typed(field("kind", 4, Type::Enum, Optional), ".test.Kind"),
+                field("ratio", 5, Type::Float, Optional),
+                field("ids", 6, Type::Int32, Repeated),
+                typed(
+                    field("parent", 7, Type::Message, Optional),
+                    ".test.Transfer",
+                ),
+                typed(
+                    field("balances", 8, Type::Message, Repeated),
+                    ".test.Transfer.BalancesEntry",
+                ),
+                field("memo", 9, Type::String, Optional),
+            ],
+            nested_type: vec![entry],
+            ..Default::default()
+        };
+        let kind = EnumDescriptorProto {
+            name: Some("Kind".to_string()),
+            value: vec![
+                EnumValueDescriptorProto {
+                    name: Some("UNKNOWN".to_string()),
+                    number: Some(0),
+                    ..Default::default()
+                },
+                EnumValueDescriptorProto {
+                    name: Some("MINT".to_string()),
+                    number: Some(1),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        vec![FileDescriptorProto {
+            package: Some("test".to_string()),
+            message_type: vec![transfer],
+            enum_type: vec![kind],
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn decodes_messages() {
+        let decoder = MessageDecoder::new(&descriptors());
+
+        let mut parent = Vec::new();
+        encoding::uint64::encode(2, &7, &mut parent);
+
+        let mut entry = Vec::new();
+        encoding::string::encode(1, &"alice".to_string(), &mut entry);
+        encoding::uint64::encode(2, &u64::MAX, &mut entry);
+
+        let mut data = Vec::new();
+        encoding::bytes::encode(1, &vec![0xab, 0xcd], &mut data);
+        encoding::uint64::encode(2, &42, &mut data);
+        encoding::sint64::encode(3, &-3, &mut data);
+        encoding::int32::encode(4, &1, &mut data);
+        encoding::float::encode(5, &0.1, &mut data);
+        encoding::int32::encode_packed(6, &[1, -1], &mut data);
+        encoding::bytes::encode(7, &parent, &mut data);
+        encoding::bytes::encode(8, &entry, &mut data);
+        // An unknown field that should be skipped
+        encoding::string::encode(99, &"ignored".to_string(), &mut data);
+
+        let value = decoder
+            .decode("type.googleapis.com/test.Transfer", &data)
+            .unwrap();
+        let expected = json!({
+            "from": "0xabcd",
+            "amount": 42,
+            "delta": -3,
+            "kind": "MINT",
+            "ratio": 0.1,
+            "ids": [1, -1],
+            "parent": {
+                "from": "0x",
+                "amount": 7,
+                "delta": 0,
+                "kind": "UNKNOWN",
+                "ratio": 0.0,
+                "ids": [],
+                "parent": null,
+                "balances": {},
+                "memo": "",
+            },
+            "balances": { "alice": u64::MAX },
+            "memo": "",
+        });
+        assert_eq!(expected, value);
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        let decoder = MessageDecoder::new(&descriptors());
+
+        assert!(decoder.decode("test.Unknown", &[]).is_err());
+
+        // Field 2 with a truncated varint
+        assert!(decoder.decode("test.Transfer", &[0x10, 0x80]).is_err());
+
+        // Field 2 is a varint, but the data is length-delimited
+        let mut data = Vec::new();
+        encoding::encode_key(2, WireType::LengthDelimited, &mut data);
+        encoding::encode_varint(0, &mut data);
+        assert!(decoder.decode("test.Transfer", &data).is_err());
+
+        // Invalid UTF-8 in a string
+        let mut data = Vec::new();
+        encoding::bytes::encode(9, &vec![0xff], &mut data);
+        assert!(decoder.decode("test.Transfer", &data).is_err());
+    }
+}
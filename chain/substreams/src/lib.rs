@@ -2,6 +2,8 @@ mod block_stream;
 mod chain;
 mod codec;
 mod data_source;
+mod decoder;
+mod runtime;
 mod trigger;
 
 pub mod block_ingestor;
@@ -12,6 +14,8 @@ pub use block_stream::BlockStreamBuilder;
 pub use chain::*;
 pub use codec::EntityChanges;
 pub use data_source::*;
+pub use decoder::MessageDecoder;
+pub use runtime::RuntimeAdapter;
 pub use trigger::*;
 
 pub use codec::Field;
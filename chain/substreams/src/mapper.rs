@@ -15,17 +15,19 @@ use graph::data_source::CausalityRegion;
 use graph::prelude::{async_trait, BigInt, BlockHash, BlockNumber, Logger, Value};
 use graph::prelude::{BigDecimal, BlockPtr};
 use graph::schema::InputSchema;
-use graph::slog::error;
+use graph::slog::{debug, error};
 use graph::substreams::Clock;
 use prost::Message;
 
-use crate::{Block, Chain, ParsedChanges, TriggerData};
+use crate::{Block, Chain, MessageHandlers, ParsedChanges, TriggerData};
 
 // WasmBlockMapper will not perform any transformation to the block and cannot make assumptions
 // about the block format. This mode just works a passthrough from the block stream to the subgraph
-// mapping which will do the decoding and store actions.
+// mapping which will do the decoding and store actions. The only thing it looks at is the type
+// of the module output to pick the handler that should receive it. Outputs for which there is no
+// handler are skipped, i.e., the block is processed without running any mapping.
 pub struct WasmBlockMapper {
-    pub handler: String,
+    pub handlers: MessageHandlers,
 }
 
 #[async_trait]
@@ -50,6 +52,7 @@ impl BlockStreamMapper<Chain> for WasmBlockMapper {
         logger: &Logger,
         clock: Clock,
         cursor: FirehoseCursor,
+        type_url: &str,
         block: Vec<u8>,
     ) -> Result<BlockStreamEvent<Chain>, BlockStreamError> {
         let Clock {
@@ -63,6 +66,27 @@ impl BlockStreamMapper<Chain> for WasmBlockMapper {
             number: BlockNumber::from(TryInto::<i32>::try_into(number).map_err(Error::from)?),
         };
 
+        let handler = match self.handlers.handler_for(type_url) {
+            Some(handler) => handler.to_string(),
+            None => {
+                debug!(logger,
+                    "Skipping substreams output without a handler";
+                    "type_url" => type_url,
+                    "number" => number,
+                );
+                let block = Block {
+                    hash: block_ptr.hash,
+                    number: block_ptr.number,
+                    changes: EntityChanges::default(),
+                    parsed_changes: vec![],
+                };
+                return Ok(BlockStreamEvent::ProcessBlock(
+                    BlockWithTriggers::new(block, vec![], logger),
+                    cursor,
+                ));
+            }
+        };
+
         let block_data = block.into_boxed_slice();
 
         // `timestamp` is an `Option`, but it should always be set
@@ -81,11 +105,7 @@ impl BlockStreamMapper<Chain> for WasmBlockMapper {
         };
 
         Ok(BlockStreamEvent::ProcessWasmBlock(
-            block_ptr,
-            timestamp,
-            block_data,
-            self.handler.clone(),
-            cursor,
+            block_ptr, timestamp, block_data, handler, cursor,
         ))
     }
 }
@@ -148,6 +168,7 @@ impl BlockStreamMapper<Chain> for Mapper {
         logger: &Logger,
         clock: Clock,
         cursor: FirehoseCursor,
+        _type_url: &str,
         block: Vec<u8>,
     ) -> Result<BlockStreamEvent<Chain>, BlockStreamError> {
         let block_number: BlockNumber = clock.number.try_into().map_err(Error::from)?;
@@ -273,16 +294,79 @@ fn decode_value(value: &crate::codec::value::Typed) -> anyhow::Result<Value> {
 
 #[cfg(test)]
 mod test {
-    use std::{ops::Add, str::FromStr};
+    use std::{collections::BTreeMap, ops::Add, str::FromStr};
 
-    use super::decode_value;
+    use super::{decode_value, WasmBlockMapper};
     use crate::codec::value::Typed;
     use crate::codec::{Array, Value};
+    use crate::MessageHandlers;
     use graph::{
+        blockchain::block_stream::{BlockStreamEvent, BlockStreamMapper, FirehoseCursor},
         data::store::scalar::Bytes,
         prelude::{BigDecimal, BigInt, Value as GraphValue},
+        slog::{o, Discard, Logger},
+        substreams::Clock,
     };
 
+    #[tokio::test]
+    async fn wasm_block_mapper_skips_outputs_without_handler() {
+        let logger = Logger::root(Discard, o!());
+        let mapper = WasmBlockMapper {
+            handlers: MessageHandlers {
+                default: None,
+                by_type: BTreeMap::from_iter([(
+                    "my.pkg.Transfer".to_string(),
+                    "handleTransfer".to_string(),
+                )]),
+            },
+        };
+        let clock = || Clock {
+            id: "0x01".to_string(),
+            number: 7,
+            timestamp: Some(prost_types::Timestamp {
+                seconds: 1,
+                nanos: 0,
+            }),
+        };
+
+        let event = mapper
+            .handle_substreams_block(
+                &logger,
+                clock(),
+                FirehoseCursor::None,
+                "type.googleapis.com/my.pkg.Transfer",
+                vec![1, 2, 3],
+            )
+            .await
+            .unwrap();
+        match event {
+            BlockStreamEvent::ProcessWasmBlock(ptr, _, data, handler, _) => {
+                assert_eq!(7, ptr.number);
+                assert_eq!(&[1, 2, 3], data.as_ref());
+                assert_eq!("handleTransfer", handler);
+            }
+            _ => panic!("expected a wasm block"),
+        }
+
+        let event = mapper
+            .handle_substreams_block(
+                &logger,
+                clock(),
+                FirehoseCursor::None,
+                "type.googleapis.com/my.pkg.Approval",
+                vec![1, 2, 3],
+            )
+            .await
+            .unwrap();
+        match event {
+            BlockStreamEvent::ProcessBlock(block, _) => {
+                assert_eq!(7, block.block.number);
+                assert!(block.trigger_data.is_empty());
+            }
+            _ => panic!("expected the output to be skipped"),
+        }
+    }
+
     #[test]
     fn validate_substreams_field_types() {
         struct Case {
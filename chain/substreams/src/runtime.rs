@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use anyhow::Error;
+use graph::blockchain::{self, HostFn, HostFnCtx};
+use graph::runtime::gas::{self, GasCounter};
+use graph::runtime::{
+    asc_get, asc_new, AscHeap, AscIndexId, AscPtr, AscType, DeterministicHostError, FromAscObj,
+    HostExportError, IndexForAscTypeId,
+};
+use graph_runtime_derive::AscType;
+use graph_runtime_wasm::asc_abi::class::{AscEnum, AscString, JsonValueKind, Uint8Array};
+
+use crate::decoder::MessageDecoder;
+use crate::{Chain, DataSource};
+
+/// Makes the message types from the substreams package of a data source
+/// available to its mappings through `protobuf.decode`
+pub struct RuntimeAdapter;
+
+impl blockchain::RuntimeAdapter<Chain> for RuntimeAdapter {
+    fn host_fns(&self, ds: &DataSource) -> Result<Vec<HostFn>, Error> {
+        let decoder = Arc::new(MessageDecoder::new(&ds.source.package.proto_files));
+
+        let protobuf_decode = HostFn {
+            name: "protobuf.decode",
+            func: Arc::new(move |ctx, wasm_ptr| {
+                protobuf_decode(&decoder, ctx, wasm_ptr).map(|ptr| ptr.wasm_ptr())
+            }),
+        };
+
+        Ok(vec![protobuf_decode])
+    }
+}
+
+/// The arguments of `protobuf.decode`: the fully qualified name of a
+/// message type from the package, and the serialized message
+pub struct ProtobufDecodeCall {
+    pub message_type: String,
+    pub data: Vec<u8>,
+}
+
+#[repr(C)]
+#[derive(AscType)]
+pub struct AscProtobufDecodeCall {
+    pub message_type: AscPtr<AscString>,
+    pub data: AscPtr<Uint8Array>,
+}
+
+impl AscIndexId for AscProtobufDecodeCall {
+    const INDEX_ASC_TYPE_ID: IndexForAscTypeId = IndexForAscTypeId::SubstreamsProtobufDecodeCall;
+}
+
+impl FromAscObj<AscProtobufDecodeCall> for ProtobufDecodeCall {
+    fn from_asc_obj<H: AscHeap + ?Sized>(
+        asc_call: AscProtobufDecodeCall,
+        heap: &H,
+        gas: &GasCounter,
+        depth: usize,
+    ) -> Result<Self, DeterministicHostError> {
+        Ok(ProtobufDecodeCall {
+            message_type: asc_get(heap, asc_call.message_type, gas, depth)?,
+            data: asc_get(heap, asc_call.data, gas, depth)?,
+        })
+    }
+}
+
+/// function protobuf.decode(call: ProtobufDecodeCall): JSONValue
+///
+/// Decoding only depends on the data and the descriptors in the package,
+/// and any failure is therefore deterministic
+fn protobuf_decode(
+    decoder: &MessageDecoder,
+    ctx: HostFnCtx,
+    wasm_ptr: u32,
+) -> Result<AscPtr<AscEnum<JsonValueKind>>, HostExportError> {
+    let call: ProtobufDecodeCall =
+        asc_get::<_, AscProtobufDecodeCall, _>(ctx.heap, wasm_ptr.into(), &ctx.gas, 0)?;
+
+    ctx.gas.consume_host_fn_with_metrics(
+        gas::PROTOBUF_DECODE.with_args(gas::complexity::Size, &call.data),
+        "protobuf_decode",
+    )?;
+
+    let value = decoder
+        .decode(&call.message_type, &call.data)
+        .map_err(|e| {
+            HostExportError::Deterministic(e.context(format!(
+                "failed to decode protobuf message of type `{}`",
+                call.message_type
+            )))
+        })?;
+    asc_new(ctx.heap, &value, &ctx.gas)
+}
@@ -19,7 +19,7 @@ use graph::{
 use graph_runtime_wasm::module::ToAscPtr;
 use lazy_static::__Deref;
 
-use crate::{Block, Chain, NoopDataSourceTemplate, ParsedChanges};
+use crate::{Block, Chain, MessageHandlers, NoopDataSourceTemplate, ParsedChanges};
 
 #[derive(Eq, PartialEq, PartialOrd, Ord, Debug)]
 pub struct TriggerData {}
@@ -58,9 +58,9 @@ pub struct TriggerFilter {
     pub(crate) module_name: String,
     pub(crate) start_block: Option<BlockNumber>,
     pub(crate) data_sources_len: u8,
-    // the handler to call for subgraph mappings, if this is set then the binary block content
+    // the handlers to call for subgraph mappings, if this is set then the binary block content
     // should be passed to the mappings.
-    pub(crate) mapping_handler: Option<String>,
+    pub(crate) mapping_handler: Option<MessageHandlers>,
 }
 
 #[cfg(debug_assertions)]
@@ -111,7 +111,7 @@ impl blockchain::TriggerFilter<Chain> for TriggerFilter {
             *modules = ds.source.package.modules.clone();
             *module_name = ds.source.module_name.clone();
             *start_block = ds.initial_block;
-            *mapping_handler = ds.mapping.handler.as_ref().map(|h| h.handler.clone());
+            *mapping_handler = ds.mapping.handler.as_ref().map(|h| h.handlers.clone());
         }
     }
 
@@ -213,7 +213,11 @@ where
                     // we are running an outdated version. In either case we should abort.
                     return Err(MappingError::Unknown(anyhow!("Detected UNSET entity operation, either a server error or there's a new type of operation and we're running an outdated protobuf")));
                 }
-                ParsedChanges::Upsert { key, entity } => {
+                ParsedChanges::Upsert { key, mut entity } => {
+                    entity
+                        .apply_scale(&key)
+                        .map_err(|e| MappingError::Unknown(key.unknown_attribute(e).into()))?;
+
                     write_poi_event(
                         proof_of_indexing,
                         &ProofOfIndexingEvent::SetEntity {
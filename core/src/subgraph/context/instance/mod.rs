@@ -114,8 +114,14 @@ where
             }
         };
 
+        // Data sources without a network of their own, like offchain data
+        // sources, belong to the main network
+        let network = data_source
+            .network()
+            .unwrap_or(self.network.as_str())
+            .to_string();
         let host = self.host_builder.build(
-            self.network.clone(),
+            network,
             self.subgraph_id.clone(),
             data_source,
             self.templates.cheap_clone(),
@@ -229,6 +235,22 @@ where
         }
     }
 
+    /// Like `hosts_for_trigger`, but only returns the hosts of data sources
+    /// on `network`. Used for subgraphs with data sources on several
+    /// networks, where a trigger of one network must not be handled by the
+    /// data sources of another network
+    pub fn hosts_for_network_trigger<'a>(
+        &'a self,
+        trigger: &TriggerData<C>,
+        network: &'a str,
+    ) -> Box<dyn Iterator<Item = &'a T::Host> + Send + 'a> {
+        let main = self.network.as_str();
+        Box::new(
+            self.hosts_for_trigger(trigger)
+                .filter(move |host| host.data_source().network().unwrap_or(main) == network),
+        )
+    }
+
     pub(super) fn causality_region_next_value(&mut self) -> CausalityRegion {
         self.causality_region_seq.next_val()
     }
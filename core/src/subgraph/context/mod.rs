@@ -76,6 +76,9 @@ where
     pub filter: Option<C::TriggerFilter>,
     pub(crate) trigger_processor: Box<dyn TriggerProcessor<C, T>>,
     pub(crate) decoder: Box<Decoder<C, T>>,
+    /// The decoders for the secondary networks of a subgraph with data
+    /// sources on several networks
+    pub(crate) network_decoders: HashMap<String, Box<Decoder<C, T>>>,
 }
 
 impl<C: Blockchain, T: RuntimeHostBuilder<C>> IndexingContext<C, T> {
@@ -88,6 +91,7 @@ impl<C: Blockchain, T: RuntimeHostBuilder<C>> IndexingContext<C, T> {
         offchain_monitor: OffchainMonitor,
         trigger_processor: Box<dyn TriggerProcessor<C, T>>,
         decoder: Box<Decoder<C, T>>,
+        network_decoders: HashMap<String, Box<Decoder<C, T>>>,
     ) -> Self {
         let instance = SubgraphInstance::new(
             manifest,
@@ -103,6 +107,7 @@ impl<C: Blockchain, T: RuntimeHostBuilder<C>> IndexingContext<C, T> {
             filter: None,
             trigger_processor,
             decoder,
+            network_decoders,
         }
     }
 
@@ -2,8 +2,9 @@ use crate::polling_monitor::{ArweaveService, IpfsService};
 use crate::subgraph::context::{IndexingContext, SubgraphKeepAlive};
 use crate::subgraph::inputs::IndexingInputs;
 use crate::subgraph::loader::load_dynamic_data_sources;
+use crate::subgraph::networks::SecondaryNetwork;
 use crate::subgraph::Decoder;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 
 use crate::subgraph::runner::SubgraphRunner;
 use graph::blockchain::block_stream::BlockStreamMetrics;
@@ -315,19 +316,24 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
             .with_context(|| format!("no chain configured for network {}", network))?
             .clone();
 
+        // Block numbers of data sources on other networks than the main
+        // network do not apply to the main network's block stream
+        let on_main_network =
+            |d: &&C::DataSource| d.network().map_or(true, |n| n == network.as_str());
+
         let start_blocks: Vec<BlockNumber> = data_sources
             .iter()
-            .filter_map(|d| d.as_onchain().map(|d: &C::DataSource| d.start_block()))
+            .filter_map(|d| d.as_onchain())
+            .filter(on_main_network)
+            .map(|d: &C::DataSource| d.start_block())
             .collect();
 
         let end_blocks: BTreeSet<BlockNumber> = manifest
             .data_sources
             .iter()
-            .filter_map(|d| {
-                d.as_onchain()
-                    .map(|d: &C::DataSource| d.end_block())
-                    .flatten()
-            })
+            .filter_map(|d| d.as_onchain())
+            .filter(on_main_network)
+            .filter_map(|d: &C::DataSource| d.end_block())
             .collect();
 
         let templates = Arc::new(manifest.templates.clone());
@@ -391,12 +397,69 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
         block_stream_metrics.deployment_head.set(deployment_head);
 
         let (runtime_adapter, decoder_hook) = chain.runtime();
-        let host_builder = graph_runtime_wasm::RuntimeHostBuilder::new(
+        let mut host_builder = graph_runtime_wasm::RuntimeHostBuilder::new(
             runtime_adapter,
             self.link_resolver.cheap_clone(),
             subgraph_store.ens_lookup(),
         );
 
+        // Set up the chains for data sources on networks other than the
+        // main network. Their blocks are read with the triggers adapter
+        // and merged into the blocks of the main network, which only works
+        // for chains that are polled over RPC
+        let mut secondary_networks = Vec::new();
+        let mut network_decoders = HashMap::new();
+        for name in manifest.networks().into_iter().filter(|n| n != &network) {
+            let network_chain = self
+                .chains
+                .get::<C>(name.clone())
+                .with_context(|| format!("no chain configured for network {}", name))?
+                .clone();
+            if chain.chain_client().is_firehose() || network_chain.chain_client().is_firehose() {
+                return Err(anyhow!(
+                    "subgraphs with data sources on several networks can not be indexed with \
+                     Firehose, but network {} or {} uses Firehose",
+                    network,
+                    name
+                ));
+            }
+
+            let network_data_sources = onchain_data_sources
+                .iter()
+                .filter(|d| d.network() == Some(name.as_str()))
+                .cloned()
+                .collect::<Vec<_>>();
+            let capabilities = C::NodeCapabilities::from_data_sources(&network_data_sources);
+            let triggers_adapter = network_chain
+                .triggers_adapter(
+                    &deployment,
+                    &capabilities,
+                    manifest.unified_mapping_api_version()?,
+                )
+                .with_context(|| {
+                    format!(
+                        "expected triggers adapter for network {} with required capabilities: {}",
+                        name, capabilities
+                    )
+                })?;
+
+            let (runtime_adapter, decoder_hook) = network_chain.runtime();
+            host_builder = host_builder.with_network(name.clone(), runtime_adapter);
+            network_decoders.insert(name.clone(), Box::new(Decoder::new(decoder_hook)));
+
+            let start_block = network_data_sources
+                .iter()
+                .map(|d| d.start_block())
+                .min()
+                .unwrap_or(0);
+            secondary_networks.push(SecondaryNetwork {
+                name,
+                chain: network_chain,
+                triggers_adapter,
+                start_block,
+            });
+        }
+
         let features = manifest.features.clone();
         let unified_api_version = manifest.unified_mapping_api_version()?;
         let poi_version = if manifest.spec_version.ge(&SPEC_VERSION_0_0_6) {
@@ -443,6 +506,7 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
                 offchain_monitor,
                 tp,
                 decoder,
+                network_decoders,
             );
             for data_source in data_sources {
                 ctx.add_dynamic_data_source(&logger, data_source)?;
@@ -462,6 +526,7 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
             logger.cheap_clone(),
             metrics,
             env_vars,
+            secondary_networks,
         ))
     }
 
@@ -0,0 +1,490 @@
+//! Indexing of subgraphs whose data sources are on more than one network.
+//!
+//! The network of the first data source in the manifest is the main
+//! network. Its block stream drives indexing, and all entity changes are
+//! written at main network block numbers. Blocks from the other, secondary,
+//! networks are merged into the main network by timestamp: a secondary
+//! block is handled together with the first main network block whose
+//! timestamp is not earlier than its own. Within a main network block, the
+//! triggers of that block are handled first, followed by the merged blocks
+//! ordered by network, in the order in which the networks first appear in
+//! the manifest, and by block number.
+//!
+//! The assignment of secondary blocks only depends on the timestamps of the
+//! blocks of the networks involved, and is therefore the same every time a
+//! subgraph is indexed. Secondary blocks are only handled once they are
+//! final, so that a reorg on a secondary network can not change what has
+//! already been written. After a restart or a revert, the position on each
+//! secondary network is derived from the timestamp of the subgraph's block
+//! pointer, which means that no additional state needs to be stored.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+
+use graph::blockchain::block_stream::BlockWithTriggers;
+use graph::blockchain::{Block, BlockTime, Blockchain, TriggersAdapter};
+use graph::prelude::{anyhow, BlockNumber, Error};
+
+/// The number of blocks of a secondary network that are scanned for
+/// triggers at once
+const SCAN_RANGE: BlockNumber = 500;
+
+pub struct SecondaryNetwork<C: Blockchain> {
+    pub name: String,
+    pub chain: Arc<C>,
+    pub triggers_adapter: Arc<dyn TriggersAdapter<C>>,
+    /// The earliest start block of the data sources on this network
+    pub start_block: BlockNumber,
+}
+
+/// A block from a secondary network that is handled together with a main
+/// network block
+pub(crate) struct MergedBlock<C: Blockchain> {
+    pub network: String,
+    pub block: BlockWithTriggers<C>,
+}
+
+/// The secondary blocks that have been assigned to the main network blocks
+/// up to and including a block from the block stream
+pub(crate) struct Assignment<C: Blockchain> {
+    /// Main network blocks before the block from the block stream, which
+    /// the block stream skipped since they have no triggers, together with
+    /// the secondary blocks assigned to them, in block order
+    pub skipped: Vec<(BlockWithTriggers<C>, Vec<MergedBlock<C>>)>,
+    /// The secondary blocks assigned to the block from the block stream
+    pub merged: Vec<MergedBlock<C>>,
+}
+
+struct Cursor<C: Blockchain> {
+    filter: C::TriggerFilter,
+    /// The first block that has not been scanned yet
+    next: BlockNumber,
+    /// The timestamp of the block before `next`, or `None` if nothing has
+    /// been scanned since the last reset
+    scanned_time: Option<BlockTime>,
+    /// Scanned blocks with triggers that have not been handled yet
+    pending: VecDeque<BlockWithTriggers<C>>,
+}
+
+pub(crate) struct NetworkMerge<C: Blockchain> {
+    networks: Vec<SecondaryNetwork<C>>,
+    cursors: Vec<Cursor<C>>,
+    needs_reset: bool,
+}
+
+impl<C: Blockchain> NetworkMerge<C> {
+    pub fn new(networks: Vec<SecondaryNetwork<C>>) -> Self {
+        let cursors = networks
+            .iter()
+            .map(|network| Cursor {
+                filter: C::TriggerFilter::default(),
+                next: network.start_block,
+                scanned_time: None,
+                pending: VecDeque::new(),
+            })
+            .collect();
+        NetworkMerge {
+            networks,
+            cursors,
+            needs_reset: true,
+        }
+    }
+
+    pub fn networks(&self) -> &[SecondaryNetwork<C>] {
+        &self.networks
+    }
+
+    /// Set the trigger filter for each secondary network. This also
+    /// invalidates the position on each network since scanned blocks only
+    /// have the triggers that matched the previous filter
+    pub fn set_filters(&mut self, filter: impl Fn(&str) -> C::TriggerFilter) {
+        for (network, cursor) in self.networks.iter().zip(self.cursors.iter_mut()) {
+            cursor.filter = filter(&network.name);
+        }
+        self.invalidate();
+    }
+
+    /// Forget the position on each secondary network. It will be derived
+    /// from the subgraph's block pointer the next time blocks are merged.
+    /// This must be called whenever the subgraph's block pointer moves
+    /// other than by processing blocks
+    pub fn invalidate(&mut self) {
+        self.needs_reset = true;
+    }
+
+    /// Position each secondary network on the first block that is later than
+    /// the main network block `head`, the subgraph's block pointer, since
+    /// all earlier blocks have been handled already
+    async fn reset(
+        &mut self,
+        main: &dyn TriggersAdapter<C>,
+        head: Option<BlockNumber>,
+        finals: &[BlockNumber],
+    ) -> Result<(), Error> {
+        let head_time = match head {
+            Some(head) => Some(
+                block_at(main, &C::TriggerFilter::default(), head)
+                    .await?
+                    .block
+                    .timestamp(),
+            ),
+            None => None,
+        };
+        for ((network, cursor), final_block) in self
+            .networks
+            .iter()
+            .zip(self.cursors.iter_mut())
+            .zip(finals)
+        {
+            cursor.next = network.start_block;
+            cursor.scanned_time = None;
+            cursor.pending.clear();
+
+            let Some(head_time) = head_time else {
+                continue;
+            };
+            let (mut lo, mut hi) = (network.start_block, *final_block + 1);
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                let block =
+                    block_at(network.triggers_adapter.as_ref(), &cursor.filter, mid).await?;
+                if block.block.timestamp() > head_time {
+                    hi = mid;
+                } else {
+                    lo = mid + 1;
+                }
+            }
+            cursor.next = lo;
+        }
+        self.needs_reset = false;
+        Ok(())
+    }
+
+    /// Return the secondary blocks with triggers whose timestamp is at most
+    /// `time` and that have not been returned before. `head` is the
+    /// subgraph's block pointer on the main network `main`, and `finals`
+    /// has the number of the latest final block for each secondary network.
+    ///
+    /// Return `None` if some network does not have a final block with a
+    /// timestamp of at least `time` yet; calling this again once more blocks
+    /// are final will pick up where this call left off
+    pub async fn blocks_until(
+        &mut self,
+        time: BlockTime,
+        main: &dyn TriggersAdapter<C>,
+        head: Option<BlockNumber>,
+        finals: &[BlockNumber],
+    ) -> Result<Option<Vec<MergedBlock<C>>>, Error> {
+        if self.needs_reset {
+            self.reset(main, head, finals).await?;
+        }
+
+        // Scan all networks before handing out any blocks so that the
+        // blocks of one network are never handed out without those of the
+        // other networks
+        for ((network, cursor), final_block) in self
+            .networks
+            .iter()
+            .zip(self.cursors.iter_mut())
+            .zip(finals)
+        {
+            while cursor.scanned_time.map_or(true, |scanned| scanned < time) {
+                if cursor.next > *final_block {
+                    return Ok(None);
+                }
+                let to = (cursor.next + SCAN_RANGE - 1).min(*final_block);
+                let blocks = network
+                    .triggers_adapter
+                    .scan_triggers(cursor.next, to, &cursor.filter)
+                    .await?;
+                let last = blocks
+                    .last()
+                    .filter(|block| block.block.number() == to)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "scanning blocks {}..={} on network {} did not return block {}",
+                            cursor.next,
+                            to,
+                            network.name,
+                            to
+                        )
+                    })?;
+                cursor.scanned_time = Some(last.block.timestamp());
+                cursor.next = to + 1;
+                cursor
+                    .pending
+                    .extend(blocks.into_iter().filter(|block| block.trigger_count() > 0));
+            }
+        }
+
+        let mut merged = Vec::new();
+        for (network, cursor) in self.networks.iter().zip(self.cursors.iter_mut()) {
+            while cursor
+                .pending
+                .front()
+                .map_or(false, |block| block.block.timestamp() <= time)
+            {
+                merged.push(MergedBlock {
+                    network: network.name.clone(),
+                    block: cursor.pending.pop_front().unwrap(),
+                });
+            }
+        }
+        Ok(Some(merged))
+    }
+}
+
+/// Assign each of the `merged` blocks to the first main network block in
+/// `lower..=block` whose timestamp is not earlier than its own. All merged
+/// blocks must have a timestamp of at most that of `block`
+pub(crate) async fn assign<C: Blockchain>(
+    adapter: &dyn TriggersAdapter<C>,
+    filter: &C::TriggerFilter,
+    lower: BlockNumber,
+    block: &C::Block,
+    merged: Vec<MergedBlock<C>>,
+) -> Result<Assignment<C>, Error> {
+    let upper = block.number();
+    let mut fetched: BTreeMap<BlockNumber, BlockWithTriggers<C>> = BTreeMap::new();
+    let mut groups: BTreeMap<BlockNumber, Vec<MergedBlock<C>>> = BTreeMap::new();
+
+    for merged_block in merged {
+        let time = merged_block.block.block.timestamp();
+        let (mut lo, mut hi) = (lower.min(upper), upper);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if !fetched.contains_key(&mid) {
+                fetched.insert(mid, block_at(adapter, filter, mid).await?);
+            }
+            if fetched[&mid].block.timestamp() >= time {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        groups.entry(lo).or_default().push(merged_block);
+    }
+
+    let merged = groups.remove(&upper).unwrap_or_default();
+    let skipped = groups
+        .into_iter()
+        .map(|(number, group)| (fetched.remove(&number).unwrap(), group))
+        .collect();
+    Ok(Assignment { skipped, merged })
+}
+
+/// The block with the given number together with its triggers
+async fn block_at<C: Blockchain>(
+    adapter: &dyn TriggersAdapter<C>,
+    filter: &C::TriggerFilter,
+    number: BlockNumber,
+) -> Result<BlockWithTriggers<C>, Error> {
+    adapter
+        .scan_triggers(number, number, filter)
+        .await?
+        .into_iter()
+        .last()
+        .filter(|block| block.block.number() == number)
+        .ok_or_else(|| anyhow!("block {} is not available", number))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use graph::blockchain::mock::{MockBlock, MockBlockchain, MockTriggerData, MockTriggerFilter};
+    use graph::blockchain::BlockPtr;
+    use graph::prelude::{async_trait, tokio, Logger};
+
+    use super::*;
+
+    /// A chain with triggers in the blocks in `triggers`. Mock blocks are
+    /// one second apart, so that block `n` has timestamp `n`
+    struct TestChain {
+        triggers: BTreeSet<BlockNumber>,
+    }
+
+    #[async_trait]
+    impl TriggersAdapter<MockBlockchain> for TestChain {
+        async fn ancestor_block(
+            &self,
+            _ptr: BlockPtr,
+            _offset: BlockNumber,
+        ) -> Result<Option<MockBlock>, Error> {
+            unimplemented!()
+        }
+
+        async fn scan_triggers(
+            &self,
+            from: BlockNumber,
+            to: BlockNumber,
+            _filter: &MockTriggerFilter,
+        ) -> Result<Vec<BlockWithTriggers<MockBlockchain>>, Error> {
+            Ok((from..=to)
+                .filter(|number| *number == to || self.triggers.contains(number))
+                .map(|number| BlockWithTriggers {
+                    block: MockBlock {
+                        number: number as u64,
+                    },
+                    trigger_data: if self.triggers.contains(&number) {
+                        vec![MockTriggerData]
+                    } else {
+                        vec![]
+                    },
+                })
+                .collect())
+        }
+
+        async fn triggers_in_block(
+            &self,
+            _logger: &Logger,
+            _block: MockBlock,
+            _filter: &MockTriggerFilter,
+        ) -> Result<BlockWithTriggers<MockBlockchain>, Error> {
+            unimplemented!()
+        }
+
+        async fn is_on_main_chain(&self, _ptr: BlockPtr) -> Result<bool, Error> {
+            unimplemented!()
+        }
+
+        async fn parent_ptr(&self, _block: &BlockPtr) -> Result<Option<BlockPtr>, Error> {
+            unimplemented!()
+        }
+    }
+
+    fn chain(triggers: &[BlockNumber]) -> TestChain {
+        TestChain {
+            triggers: triggers.iter().copied().collect(),
+        }
+    }
+
+    fn merge(triggers: &[BlockNumber]) -> NetworkMerge<MockBlockchain> {
+        NetworkMerge::new(vec![SecondaryNetwork {
+            name: "secondary".to_string(),
+            chain: Arc::new(MockBlockchain),
+            triggers_adapter: Arc::new(chain(triggers)),
+            start_block: 0,
+        }])
+    }
+
+    fn time(number: i64) -> BlockTime {
+        BlockTime::since_epoch(number, 0)
+    }
+
+    fn numbers(blocks: &[MergedBlock<MockBlockchain>]) -> Vec<BlockNumber> {
+        blocks
+            .iter()
+            .map(|block| block.block.block.number())
+            .collect()
+    }
+
+    fn merged(numbers: &[BlockNumber]) -> Vec<MergedBlock<MockBlockchain>> {
+        numbers
+            .iter()
+            .map(|number| MergedBlock {
+                network: "secondary".to_string(),
+                block: BlockWithTriggers {
+                    block: MockBlock {
+                        number: *number as u64,
+                    },
+                    trigger_data: vec![MockTriggerData],
+                },
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn merges_blocks_after_head() {
+        let main = chain(&[]);
+        let mut merge = merge(&[3, 12, 15, 21]);
+
+        // Block 3 was handled before the subgraph got to block 10
+        let blocks = merge
+            .blocks_until(time(20), &main, Some(10), &[30])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![12, 15], numbers(&blocks));
+
+        let blocks = merge
+            .blocks_until(time(25), &main, Some(20), &[30])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![21], numbers(&blocks));
+
+        // After a revert, blocks are handed out again
+        merge.invalidate();
+        let blocks = merge
+            .blocks_until(time(20), &main, Some(12), &[30])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![15], numbers(&blocks));
+
+        // Without a block pointer, merging starts at the start block
+        merge.invalidate();
+        let blocks = merge
+            .blocks_until(time(20), &main, None, &[30])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![3, 12, 15], numbers(&blocks));
+    }
+
+    #[tokio::test]
+    async fn waits_for_final_blocks() {
+        let main = chain(&[]);
+        let mut merge = merge(&[3, 12, 15, 21]);
+
+        // Block 12 is final, but is held back until the network is final up
+        // to the requested time
+        let blocks = merge
+            .blocks_until(time(20), &main, Some(10), &[14])
+            .await
+            .unwrap();
+        assert!(blocks.is_none());
+
+        let blocks = merge
+            .blocks_until(time(20), &main, Some(10), &[20])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![12, 15], numbers(&blocks));
+    }
+
+    #[tokio::test]
+    async fn assigns_blocks_to_skipped_blocks() {
+        let main = chain(&[20]);
+        let block = MockBlock { number: 20 };
+
+        let assignment = assign(
+            &main,
+            &MockTriggerFilter,
+            11,
+            &block,
+            merged(&[11, 12, 15, 20]),
+        )
+        .await
+        .unwrap();
+
+        let skipped: Vec<_> = assignment
+            .skipped
+            .iter()
+            .map(|(block, merged)| (block.block.number(), numbers(merged)))
+            .collect();
+        assert_eq!(
+            vec![(11, vec![11]), (12, vec![12]), (15, vec![15])],
+            skipped
+        );
+        assert_eq!(vec![20], numbers(&assignment.merged));
+
+        // Blocks from before `lower` go to `lower`
+        let assignment = assign(&main, &MockTriggerFilter, 11, &block, merged(&[5]))
+            .await
+            .unwrap();
+        assert_eq!(11, assignment.skipped[0].0.block.number());
+        assert_eq!(vec![5], numbers(&assignment.skipped[0].1));
+    }
+}
@@ -650,6 +650,14 @@ async fn create_subgraph_version<C: Blockchain, S: SubgraphStore>(
         .map_err(SubgraphRegistrarError::NetworkNotSupported)?
         .cheap_clone();
 
+    // Data sources on other networks are indexed with the chains for
+    // those networks, which therefore also need to be available
+    for network in manifest.networks() {
+        chains
+            .get::<C>(network)
+            .map_err(SubgraphRegistrarError::NetworkNotSupported)?;
+    }
+
     let logger = logger.clone();
     let store = store.clone();
     let deployment_store = store.clone();
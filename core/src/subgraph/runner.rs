@@ -1,6 +1,7 @@
 use crate::subgraph::context::IndexingContext;
 use crate::subgraph::error::BlockProcessingError;
 use crate::subgraph::inputs::IndexingInputs;
+use crate::subgraph::networks::{self, Assignment, MergedBlock, NetworkMerge, SecondaryNetwork};
 use crate::subgraph::state::IndexingState;
 use crate::subgraph::stream::new_block_stream;
 use atomic_refcell::AtomicRefCell;
@@ -50,6 +51,9 @@ where
     inputs: Arc<IndexingInputs<C>>,
     logger: Logger,
     pub metrics: RunnerMetrics,
+    /// The secondary networks of a subgraph with data sources on several
+    /// networks, `None` if all data sources are on the same network
+    merge: Option<NetworkMerge<C>>,
 }
 
 impl<C, T> SubgraphRunner<C, T>
@@ -63,7 +67,13 @@ where
         logger: Logger,
         metrics: RunnerMetrics,
         env_vars: Arc<EnvVars>,
+        secondary_networks: Vec<SecondaryNetwork<C>>,
     ) -> Self {
+        let merge = if secondary_networks.is_empty() {
+            None
+        } else {
+            Some(NetworkMerge::new(secondary_networks))
+        };
         Self {
             inputs: Arc::new(inputs),
             ctx,
@@ -80,6 +90,7 @@ where
             },
             logger,
             metrics,
+            merge,
         }
     }
 
@@ -99,6 +110,11 @@ where
         // 2. Unmark any offchain data sources that were marked done on the blocks being removed.
         // When no offchain datasources are present, 2. should be a noop.
         self.ctx.revert_data_sources(block_number + 1)?;
+
+        // 3. Derive the position on secondary networks from the new block pointer.
+        if let Some(merge) = &mut self.merge {
+            merge.invalidate();
+        }
         Ok(())
     }
 
@@ -120,12 +136,19 @@ where
         let current_ptr = self.inputs.store.block_ptr();
         let static_filters = self.is_static_filters_enabled();
 
-        // Filter out data sources that have reached their end block
-        let end_block_filter = |ds: &&C::DataSource| match current_ptr.as_ref() {
-            // We filter out datasources for which the current block is at or past their end block.
-            Some(block) => ds.end_block().map_or(true, |end| block.number < end),
-            // If there is no current block, we keep all datasources.
-            None => true,
+        // Filter out data sources that have reached their end block, and
+        // data sources on other networks than the main network, whose
+        // triggers are scanned separately
+        let network = self.inputs.network.as_str();
+        let end_block_filter = |ds: &&C::DataSource| {
+            let on_main_network = ds.network().map_or(true, |n| n == network);
+            on_main_network
+                && match current_ptr.as_ref() {
+                    // We filter out datasources for which the current block is at or past their end block.
+                    Some(block) => ds.end_block().map_or(true, |end| block.number < end),
+                    // If there is no current block, we keep all datasources.
+                    None => true,
+                }
         };
 
         // if static_filters is not enabled we just stick to the filter based on all the data sources.
@@ -206,6 +229,15 @@ where
             let block_stream_cancel_handle = block_stream_canceler.handle();
             // TriggerFilter needs to be rebuilt eveytime the blockstream is restarted
             self.ctx.filter = Some(self.build_filter());
+            if let Some(merge) = &mut self.merge {
+                let ctx = &self.ctx;
+                merge.set_filters(|network| {
+                    C::TriggerFilter::from_data_sources(
+                        ctx.onchain_data_sources()
+                            .filter(|ds| ds.network() == Some(network)),
+                    )
+                });
+            }
 
             let mut block_stream = new_block_stream(
                 &self.inputs,
@@ -279,6 +311,7 @@ where
         block_stream_cancel_handle: &CancelHandle,
         block: BlockWithTriggers<C>,
         firehose_cursor: FirehoseCursor,
+        merged: Vec<MergedBlock<C>>,
     ) -> Result<Action, BlockProcessingError> {
         let triggers = block.trigger_data;
         let block = Arc::new(block.block);
@@ -315,8 +348,17 @@ where
             .stopwatch
             .start_section(PROCESS_TRIGGERS_SECTION_NAME);
 
-        // Match and decode all triggers in the block
-        let hosts_filter = |trigger: &TriggerData<C>| self.ctx.instance.hosts_for_trigger(trigger);
+        // Match and decode all triggers in the block. When data sources are
+        // on several networks, only those on the main network match
+        let network = self.inputs.network.as_str();
+        let is_merging = self.merge.is_some();
+        let hosts_filter = |trigger: &TriggerData<C>| match is_merging {
+            true => self
+                .ctx
+                .instance
+                .hosts_for_network_trigger(trigger, network),
+            false => self.ctx.instance.hosts_for_trigger(trigger),
+        };
         let match_res = self
             .ctx
             .decoder
@@ -536,6 +578,12 @@ where
             }
         }
 
+        // Handle the blocks from secondary networks that are merged into
+        // this block after all of the block's own triggers
+        block_state = self
+            .process_merged_blocks(&logger, merged, block_state, &proof_of_indexing)
+            .await?;
+
         let has_errors = block_state.has_errors();
         let is_non_fatal_errors_active = self
             .inputs
@@ -569,11 +617,14 @@ where
             modifications: mut mods,
             entity_lfu_cache: cache,
             evict_stats,
+            entity_count,
+            entity_weight,
         } = block_state
             .entity_cache
             .as_modifications(block.number())
             .map_err(|e| BlockProcessingError::Unknown(e.into()))?;
         section.end();
+        self.observe_block_entity_cache(&logger, entity_count, entity_weight);
 
         trace!(self.logger, "Entity cache statistics";
             "weight" => evict_stats.new_weight,
@@ -730,6 +781,181 @@ where
             .await
     }
 
+    /// Handle the triggers of blocks from secondary networks that are
+    /// merged into the main network block that is being processed. The
+    /// triggers are handled by the data sources on the block's network, in
+    /// the order of the blocks; see `networks.rs` for how blocks are merged
+    async fn process_merged_blocks(
+        &mut self,
+        logger: &Logger,
+        merged: Vec<MergedBlock<C>>,
+        mut block_state: BlockState,
+        proof_of_indexing: &SharedProofOfIndexing,
+    ) -> Result<BlockState, BlockProcessingError> {
+        for MergedBlock { network, block } in merged {
+            let triggers = block.trigger_data;
+            let block = Arc::new(block.block);
+            let number = block.number();
+            let logger = logger.new(o!(
+                "network" => network.clone(),
+                "merged_block_number" => number,
+            ));
+
+            debug!(logger, "Start processing merged block";
+                   "triggers" => triggers.len());
+
+            let causality_region = PoICausalityRegion::from_network(&network);
+            let decoder = self
+                .ctx
+                .network_decoders
+                .get(&network)
+                .ok_or_else(|| anyhow!("no decoder for network {}", network))?;
+
+            // Data sources on secondary networks stop at their end block
+            // without restarting the block stream
+            let hosts_filter = |trigger: &TriggerData<C>| {
+                let hosts = self
+                    .ctx
+                    .instance
+                    .hosts_for_network_trigger(trigger, &network)
+                    .filter(move |host| {
+                        host.data_source()
+                            .end_block()
+                            .map_or(true, |end| number <= end)
+                    });
+                Box::new(hosts) as Box<dyn Iterator<Item = &T::Host> + Send + '_>
+            };
+            let match_res = decoder
+                .match_and_decode_many(
+                    &logger,
+                    &block,
+                    triggers.into_iter().map(TriggerData::Onchain),
+                    hosts_filter,
+                    &self.metrics.subgraph,
+                )
+                .await;
+
+            let mut res = Ok(block_state);
+            match match_res {
+                Ok(runnables) => {
+                    for runnable in runnables {
+                        let process_res = self
+                            .ctx
+                            .trigger_processor
+                            .process_trigger(
+                                &logger,
+                                runnable.hosted_triggers,
+                                &block,
+                                res.unwrap(),
+                                proof_of_indexing,
+                                &causality_region,
+                                &self.inputs.debug_fork,
+                                &self.metrics.subgraph,
+                                self.inputs.instrument,
+                            )
+                            .await
+                            .map_err(|e| e.add_trigger_context(&runnable.trigger));
+                        match process_res {
+                            Ok(state) => res = Ok(state),
+                            Err(e) => {
+                                res = Err(e);
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    res = Err(e);
+                }
+            }
+
+            block_state = res.map_err(|e| {
+                // Merged blocks are final, so a `PossibleReorg` is not
+                // expected here and is treated as an ordinary error.
+                match e {
+                    MappingError::PossibleReorg(e) | MappingError::Unknown(e) => {
+                        BlockProcessingError::Unknown(e)
+                    }
+                }
+            })?;
+
+            if block_state.has_created_data_sources() {
+                return Err(BlockProcessingError::Unknown(anyhow!(
+                    "Attempted to create a data source in a handler for network {}. \
+                     Data sources can only be created by handlers on the main network {}.",
+                    network,
+                    self.inputs.network
+                )));
+            }
+        }
+        Ok(block_state)
+    }
+
+    /// For subgraphs with data sources on several networks, wait until all
+    /// secondary networks are final up to the timestamp of `block`, and
+    /// assign their blocks to the main network blocks up to `block`.
+    /// Return `None` if the block stream was canceled while waiting
+    async fn assign_merged_blocks(
+        &mut self,
+        block: &C::Block,
+        cancel_handle: &CancelHandle,
+    ) -> Result<Option<Assignment<C>>, Error> {
+        let Some(merge) = &mut self.merge else {
+            return Ok(Some(Assignment {
+                skipped: vec![],
+                merged: vec![],
+            }));
+        };
+
+        let head = self.inputs.store.block_ptr().map(|ptr| ptr.number);
+        let merged = loop {
+            let mut finals = Vec::new();
+            for network in merge.networks() {
+                let chain_head = network.chain.chain_store().chain_head_ptr().await?;
+                finals.push(chain_head.map_or(-1, |ptr| ptr.number - ENV_VARS.reorg_threshold));
+            }
+
+            let merged = merge
+                .blocks_until(
+                    block.timestamp(),
+                    self.inputs.triggers_adapter.as_ref(),
+                    head,
+                    &finals,
+                )
+                .await?;
+            if let Some(merged) = merged {
+                break merged;
+            }
+
+            debug!(self.logger, "Waiting for secondary networks to finalize blocks";
+                "block_number" => block.number());
+            tokio::time::sleep(ENV_VARS.ingestor_polling_interval).await;
+            if cancel_handle.is_canceled() {
+                return Ok(None);
+            }
+        };
+
+        // The first block that the subgraph has not processed yet
+        let lower = match head {
+            Some(head) => head + 1,
+            None => self.inputs.start_blocks.iter().min().copied().unwrap_or(0),
+        };
+        let filter = self
+            .ctx
+            .filter
+            .as_ref()
+            .expect("the filter is set when the block stream is started");
+        let assignment = networks::assign(
+            self.inputs.triggers_adapter.as_ref(),
+            filter,
+            lower,
+            block,
+            merged,
+        )
+        .await?;
+        Ok(Some(assignment))
+    }
+
     fn create_dynamic_data_sources(
         &mut self,
         created_data_sources: Vec<InstanceDSTemplateInfo>,
@@ -775,6 +1001,21 @@ where
                 }
             };
 
+            // Blocks of secondary networks are merged into the main
+            // network, which does not work for data sources that are
+            // created on the fly
+            if let Some(network) = data_source.network() {
+                if network != self.inputs.network {
+                    return Err(anyhow!(
+                        "data source {} is on network {}, but data sources can only be \
+                         created on the main network {}",
+                        data_source.name(),
+                        network,
+                        self.inputs.network
+                    ));
+                }
+            }
+
             // Try to create a runtime host for the data source
             let host = self
                 .ctx
@@ -953,6 +1194,30 @@ where
         }
     }
 
+    /// Report the number and size of the entity changes of a block. All
+    /// changes of a block are always written together so that the block
+    /// is applied atomically; blocks that exceed the soft limit are
+    /// therefore only warned about
+    fn observe_block_entity_cache(
+        &self,
+        logger: &Logger,
+        entity_count: usize,
+        entity_weight: usize,
+    ) {
+        self.metrics
+            .subgraph
+            .observe_block_entity_cache(entity_count, entity_weight);
+
+        if let Some(limit) = ENV_VARS.mappings.entity_cache_block_soft_limit {
+            if entity_weight > limit {
+                warn!(logger, "Entity changes of block exceed the soft entity cache limit";
+                    "entity_count" => entity_count,
+                    "size_kb" => entity_weight / 1000,
+                    "limit_kb" => limit / 1000);
+            }
+        }
+    }
+
     /// We consider a subgraph caught up when it's at most 10 blocks behind the chain head.
     async fn is_caught_up(&mut self, block_ptr: &BlockPtr) -> Result<bool, Error> {
         const CAUGHT_UP_DISTANCE: BlockNumber = 10;
@@ -1289,11 +1554,14 @@ where
             modifications: mut mods,
             entity_lfu_cache: cache,
             evict_stats,
+            entity_count,
+            entity_weight,
         } = block_state
             .entity_cache
             .as_modifications(block_ptr.number)
             .map_err(|e| BlockProcessingError::Unknown(e.into()))?;
         section.end();
+        self.observe_block_entity_cache(&logger, entity_count, entity_weight);
 
         trace!(self.logger, "Entity cache statistics";
             "weight" => evict_stats.new_weight,
@@ -1419,7 +1687,34 @@ where
                 .observe(block.trigger_count() as f64);
         }
 
+        // Secondary network blocks can be assigned to blocks that the block
+        // stream skipped since they have no triggers; those blocks are
+        // processed first
+        let Some(Assignment { skipped, merged }) = self
+            .assign_merged_blocks(&block.block, cancel_handle)
+            .await?
+        else {
+            return Ok(Action::Stop);
+        };
+        for (skipped_block, skipped_merged) in skipped {
+            let start = Instant::now();
+            let skipped_ptr = skipped_block.ptr();
+            let res = self
+                .process_block(
+                    cancel_handle,
+                    skipped_block,
+                    FirehoseCursor::None,
+                    skipped_merged,
+                )
+                .await;
+            match self.handle_action(start, skipped_ptr, res).await? {
+                Action::Continue => (),
+                action => return Ok(action),
+            }
+        }
+
         if block.trigger_count() == 0
+            && merged.is_empty()
             && self.state.skip_ptr_updates_timer.elapsed() <= SKIP_PTR_UPDATES_THRESHOLD
             && !self.inputs.store.is_deployment_synced()
             && !close_to_chain_head(
@@ -1437,7 +1732,9 @@ where
 
         let start = Instant::now();
 
-        let res = self.process_block(cancel_handle, block, cursor).await;
+        let res = self
+            .process_block(cancel_handle, block, cursor, merged)
+            .await;
 
         self.handle_action(start, block_ptr, res).await
     }
@@ -1459,6 +1756,16 @@ where
 
         info!(&self.logger, "Reverting block to get back to main chain"; "subgraph_ptr" => &subgraph_ptr, "revert_to_ptr" => &revert_to_ptr);
 
+        let depth = subgraph_ptr.number - revert_to_ptr.number;
+        if depth > ENV_VARS.deep_reorg_threshold {
+            warn!(&self.logger, "Deep reorg detected";
+                "depth" => depth,
+                "threshold" => ENV_VARS.deep_reorg_threshold,
+                "subgraph_ptr" => &subgraph_ptr,
+                "revert_to_ptr" => &revert_to_ptr);
+            self.metrics.stream.deep_reorgs.inc();
+        }
+
         if let Err(e) = self
             .inputs
             .store
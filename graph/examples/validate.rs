@@ -28,30 +28,40 @@
 ///
 /// ```
 use clap::Parser;
+use flate2::read::MultiGzDecoder;
 
-use graph::data::graphql::ext::DirectiveFinder;
+use graph::data::graphql::ext::{DirectiveFinder, TypeDefinitionExt, TypeExt};
 use graph::data::graphql::DirectiveExt;
 use graph::data::graphql::DocumentExt;
-use graph::data::subgraph::SPEC_VERSION_1_1_0;
+use graph::data::subgraph::{spec_versions, LATEST_VERSION, SPEC_VERSION_1_1_0};
 use graph::prelude::s;
 use graph::prelude::DeploymentHash;
-use graph::schema::InputSchema;
+use graph::schema::{
+    APISchemaError, IndexLint, InputSchema, ParseOptions, SchemaLimits, SchemaMemoryReport,
+    SchemaValidationError, SchemaValidationErrors, TypeKind,
+};
+use graph::semver::Version;
 use graphql_parser::parse_schema;
 use serde::Deserialize;
 use std::alloc::GlobalAlloc;
 use std::alloc::Layout;
 use std::alloc::System;
+use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::str::FromStr;
-use std::sync::atomic::AtomicBool;
 use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use std::sync::{mpsc, Mutex};
+use std::thread;
 use std::time::{Duration, Instant};
 
-use graph::anyhow::{anyhow, bail, Result};
+use graph::anyhow::{Context, Result};
 
 // Install an allocator that tracks allocation sizes
 
@@ -114,9 +124,12 @@ struct Entry {
     schema: String,
 }
 
+#[derive(Clone, Copy)]
 enum RunMode {
     Validate,
     Size,
+    Breakdown,
+    DumpApi,
 }
 
 impl FromStr for RunMode {
@@ -126,11 +139,31 @@ impl FromStr for RunMode {
         match s {
             "validate" => Ok(RunMode::Validate),
             "size" => Ok(RunMode::Size),
+            "breakdown" => Ok(RunMode::Breakdown),
+            "dump-api" => Ok(RunMode::DumpApi),
             _ => Err("Invalid mode".to_string()),
         }
     }
 }
 
+#[derive(Clone, Copy)]
+enum Format {
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err("Invalid format".to_string()),
+        }
+    }
+}
+
 #[derive(Parser)]
 #[clap(
     name = "validate",
@@ -145,51 +178,331 @@ struct Opts {
     batch: bool,
     #[clap(long)]
     api: bool,
-    #[clap(short, long, default_value = "validate", possible_values = &["validate", "size"])]
+    #[clap(short, long, default_value = "validate", possible_values = &["validate", "size", "breakdown", "dump-api"])]
     mode: RunMode,
-    /// Subgraph schemas to validate
-    #[clap(required = true)]
+    /// With `--mode dump-api`, write the API schema for each input schema
+    /// into a file in this directory instead of printing it to stdout
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
+    /// How to print results. With `json`, one JSON object is printed per
+    /// schema on its own line
+    #[clap(long, default_value = "text", possible_values = &["text", "json"])]
+    format: Format,
+    /// Do not stop at the first schema that fails; instead, print a
+    /// summary of all failures at the end
+    #[clap(short, long)]
+    keep_going: bool,
+    /// Buffer all results and print them sorted by the numeric `sgd` id of
+    /// the schema so that the output of different runs can be diffed.
+    /// Schemas that are not from batch input come last, sorted by name
+    #[clap(long)]
+    sorted: bool,
+    /// With `--mode size`, print the N schemas that took longest to parse
+    /// and the N schemas with the largest input schema to stderr after all
+    /// schemas have been processed
+    #[clap(long, value_name = "N")]
+    top: Option<usize>,
+    /// The number of threads to use for validating schemas. This is
+    /// ignored for `--mode size` since the sizes are measured with a
+    /// global allocation counter that can only track one schema at a time
+    #[clap(short, long, default_value = "1")]
+    jobs: usize,
+    /// Allow `@fulltext` directives in schemas
+    #[clap(long)]
+    allow_fulltext: bool,
+    /// For valid schemas, also list the immutable entity types, including
+    /// timeseries and aggregations. This only applies to `--mode validate`
+    /// with a single `--spec`
+    #[clap(long)]
+    list_immutable: bool,
+    /// For valid schemas, also warn about fields that queries are likely
+    /// to filter on but that will not have a useful index. The warnings
+    /// are advisory and do not make validation fail. This only applies to
+    /// `--mode validate` with a single `--spec`
+    #[clap(long, conflicts_with = "list-immutable")]
+    lint: bool,
+    /// Also reject schemas that exceed the limits that are checked when a
+    /// subgraph is deployed. The limits are taken from the
+    /// `GRAPH_SCHEMA_MAX_*` environment variables unless they are set with
+    /// the `--max-*` options. This only applies to `--mode validate`
+    #[clap(long)]
+    limits: bool,
+    /// With `--limits`, the maximum number of entity types and
+    /// aggregations
+    #[clap(long, value_name = "N", requires = "limits")]
+    max_entity_types: Option<usize>,
+    /// With `--limits`, the maximum number of fields of one type
+    #[clap(long, value_name = "N", requires = "limits")]
+    max_fields_per_type: Option<usize>,
+    /// With `--limits`, the maximum number of values of one enum
+    #[clap(long, value_name = "N", requires = "limits")]
+    max_enum_values: Option<usize>,
+    /// With `--limits`, the maximum number of `@fulltext` indexes
+    #[clap(long, value_name = "N", requires = "limits")]
+    max_fulltext_indexes: Option<usize>,
+    /// The spec versions to validate against, separated by commas. When
+    /// more than one version is given, each schema is validated against all
+    /// of them and the result for each version is printed
+    #[clap(long, default_value = "1.1.0", value_delimiter = ',')]
+    spec: Vec<Version>,
+    /// Read the schema from stdin instead of from files. With `--batch`,
+    /// stdin must be in the same JSONL format as batch input files
+    #[clap(long, conflicts_with = "schemas")]
+    stdin: bool,
+    /// Treat batch input as gzip-compressed. Batch files whose name ends
+    /// in `.gz` are always decompressed, even without this flag
+    #[clap(long, requires = "batch")]
+    gzip: bool,
+    /// Skip this many schemas at the start of each batch input file
+    #[clap(long, requires = "batch")]
+    skip: Option<usize>,
+    /// Stop after this many schemas from each batch input file, not
+    /// counting the ones skipped with `--skip`
+    #[clap(long, requires = "batch")]
+    limit: Option<usize>,
+    /// The extension of the files to use from directories given in
+    /// `schemas`. Defaults to `json` with `--batch` and `graphql` otherwise
+    #[clap(long)]
+    ext: Option<String>,
+    /// Subgraph schemas to validate. Directories are searched recursively
+    /// for files with the extension given by `--ext`
+    #[clap(required_unless_present = "stdin")]
     schemas: Vec<String>,
 }
 
-fn parse(raw: &str, name: &str, api: bool) -> Result<DeploymentHash> {
+fn parse(
+    raw: &str,
+    name: &str,
+    api: bool,
+    spec_version: &Version,
+    options: &ParseOptions,
+) -> Result<(DeploymentHash, InputSchema)> {
     let schema = parse_schema(raw)
         .map(|v| v.into_static())
-        .map_err(|e| anyhow!("Failed to parse schema sgd{name}: {e}"))?;
+        .with_context(|| format!("Failed to parse schema {name}"))?;
     let id = subgraph_id(&schema);
-    let input_schema = match InputSchema::parse(&SPEC_VERSION_1_1_0, raw, id.clone()) {
-        Ok(schema) => schema,
-        Err(e) => {
-            bail!("InputSchema: {}[{}]: {}", name, id, e);
-        }
-    };
+    let input_schema = InputSchema::parse_with_options(spec_version, raw, id.clone(), options)
+        .with_context(|| format!("InputSchema: {}[{}]", name, id))?;
     if api {
-        let _api_schema = match input_schema.api_schema() {
-            Ok(schema) => schema,
-            Err(e) => {
-                bail!("ApiSchema: {}[{}]: {}", name, id, e);
+        let _api_schema = input_schema
+            .api_schema()
+            .with_context(|| format!("ApiSchema: {}[{}]", name, id))?;
+    }
+    Ok((id, input_schema))
+}
+
+/// Like `parse`, but also run the checks that a deployment of the schema
+/// would, and check that the schema stays within `limits` if they are given
+fn parse_with_limits(
+    raw: &str,
+    name: &str,
+    api: bool,
+    spec_version: &Version,
+    options: &ParseOptions,
+    limits: Option<&SchemaLimits>,
+) -> Result<(DeploymentHash, InputSchema)> {
+    let (id, schema) = parse(raw, name, api, spec_version, options)?;
+    schema
+        .validate_for_deployment(spec_version)
+        .map_err(|errors| SchemaValidationErrors {
+            id: id.clone(),
+            errors,
+        })
+        .with_context(|| format!("Deployment: {}[{}]", name, id))?;
+    if let Some(limits) = limits {
+        schema
+            .check_limits(limits)
+            .map_err(|errors| SchemaValidationErrors {
+                id: id.clone(),
+                errors,
+            })
+            .with_context(|| format!("Limits: {}[{}]", name, id))?;
+    }
+    Ok((id, schema))
+}
+
+/// Return the first error from a schema validation error which can list
+/// many errors, one per line, after a header
+fn first_error(e: &graph::anyhow::Error) -> String {
+    match e.downcast_ref::<SchemaValidationErrors>() {
+        Some(errors) if !errors.errors.is_empty() => errors.errors[0].to_string(),
+        _ => format!("{:#}", e),
+    }
+}
+
+/// Return a stable code that classifies the error `e`. When validation
+/// found several errors, the code is the kind of the first one
+fn error_code(e: &graph::anyhow::Error) -> &'static str {
+    if let Some(errors) = e.downcast_ref::<SchemaValidationErrors>() {
+        errors
+            .errors
+            .first()
+            .map(SchemaValidationError::kind)
+            .unwrap_or("ValidationError")
+    } else if let Some(error) = e.downcast_ref::<SchemaValidationError>() {
+        error.kind()
+    } else if e
+        .downcast_ref::<graphql_parser::schema::ParseError>()
+        .is_some()
+    {
+        "SyntaxError"
+    } else if e.downcast_ref::<APISchemaError>().is_some() {
+        "ApiSchemaError"
+    } else {
+        "Other"
+    }
+}
+
+/// What a `Runner` produced for a schema that it could process
+enum Outcome {
+    Valid,
+    /// The schema is valid and has these immutable entity types
+    Immutable(Vec<String>),
+    /// The schema is valid; these are the warnings about fields that
+    /// lack an index
+    Lint(Vec<IndexLint>),
+    Sizes(Sizes),
+    /// The estimated memory used by each type in the input schema
+    Breakdown(SchemaMemoryReport),
+    /// The result of validating against each of several spec versions,
+    /// with the first error for the versions where validation failed
+    Specs(Vec<(Version, Option<String>)>),
+    /// The generated API schema, as GraphQL text
+    Api(String),
+}
+
+/// The result of running a `Runner` on one schema
+struct Report {
+    name: String,
+    id: Option<DeploymentHash>,
+    outcome: Result<Outcome, Failure>,
+}
+
+/// Why a `Runner` failed to process a schema
+struct Failure {
+    /// A stable code for the kind of failure; see `error_code`
+    code: &'static str,
+    message: String,
+}
+
+impl Report {
+    fn new(name: &str, id: Option<DeploymentHash>, outcome: Result<Outcome>) -> Self {
+        Report {
+            name: name.to_string(),
+            id,
+            outcome: outcome.map_err(|e| Failure {
+                code: error_code(&e),
+                message: format!("{:#}", e),
+            }),
+        }
+    }
+
+    fn is_ok(&self) -> bool {
+        self.failure_code().is_none()
+    }
+
+    /// The code under which the summary counts this report as failed, or
+    /// `None` if it succeeded. Comparing spec versions fails if the schema
+    /// is invalid for any of them
+    fn failure_code(&self) -> Option<&'static str> {
+        match &self.outcome {
+            Ok(Outcome::Specs(specs)) if specs.iter().any(|(_, err)| err.is_some()) => {
+                Some("SpecVersionError")
             }
-        };
+            Ok(_) => None,
+            Err(failure) => Some(failure.code),
+        }
+    }
+
+    /// The key by which `--sorted` orders reports: schemas from batch
+    /// input, named `sgdN`, are ordered by `N`
+    fn sort_key(&self) -> (Option<u64>, &str) {
+        let sgd = self
+            .name
+            .strip_prefix("sgd")
+            .and_then(|n| n.parse::<u64>().ok());
+        (sgd, &self.name)
+    }
+}
+
+/// Print the `reports` ordered by their `sort_key`
+fn print_sorted(printer: &Printer, reports: &mut Vec<Report>) {
+    reports.sort_by(|a, b| {
+        let (a_sgd, a_name) = a.sort_key();
+        let (b_sgd, b_name) = b.sort_key();
+        // Put reports without an sgd id last
+        (a_sgd.is_none(), a_sgd, a_name).cmp(&(b_sgd.is_none(), b_sgd, b_name))
+    });
+    for report in reports.drain(..) {
+        printer.print(&report);
     }
-    Ok(id)
 }
 
-trait Runner {
-    fn run(&self, raw: &str, name: &str, api: bool);
+trait Runner: Send + Sync {
+    fn run(&self, raw: &str, name: &str, api: bool) -> Report;
 }
 
-struct Validator;
+struct Validator {
+    spec_versions: Vec<Version>,
+    options: ParseOptions,
+    limits: Option<SchemaLimits>,
+    list_immutable: bool,
+    lint: bool,
+}
+
+impl Validator {
+    fn compare_specs(&self, raw: &str, name: &str, api: bool) -> Report {
+        let id = match parse_schema(raw).with_context(|| format!("Failed to parse schema {name}")) {
+            Ok(schema) => subgraph_id(&schema.into_static()),
+            Err(e) => return Report::new(name, None, Err(e)),
+        };
+        let specs = self
+            .spec_versions
+            .iter()
+            .map(|spec_version| {
+                let err = parse_with_limits(
+                    raw,
+                    name,
+                    api,
+                    spec_version,
+                    &self.options,
+                    self.limits.as_ref(),
+                )
+                .err()
+                .map(|e| first_error(&e));
+                (spec_version.clone(), err)
+            })
+            .collect();
+        Report::new(name, Some(id), Ok(Outcome::Specs(specs)))
+    }
+}
 
 impl Runner for Validator {
-    fn run(&self, raw: &str, name: &str, api: bool) {
-        match parse(raw, name, api) {
-            Ok(id) => {
-                println!("Schema {}[{}]: OK", name, id);
+    fn run(&self, raw: &str, name: &str, api: bool) -> Report {
+        if self.spec_versions.len() > 1 {
+            return self.compare_specs(raw, name, api);
+        }
+        match parse_with_limits(
+            raw,
+            name,
+            api,
+            &self.spec_versions[0],
+            &self.options,
+            self.limits.as_ref(),
+        ) {
+            Ok((id, schema)) if self.list_immutable => {
+                let immutable = schema
+                    .immutable_entity_types()
+                    .iter()
+                    .map(|entity_type| entity_type.as_str().to_string())
+                    .collect();
+                Report::new(name, Some(id), Ok(Outcome::Immutable(immutable)))
             }
-            Err(e) => {
-                println!("Error: {}", e);
-                exit(1);
+            Ok((id, schema)) if self.lint => {
+                Report::new(name, Some(id), Ok(Outcome::Lint(schema.index_lints())))
             }
+            Ok((id, _)) => Report::new(name, Some(id), Ok(Outcome::Valid)),
+            Err(e) => Report::new(name, None, Err(e)),
         }
     }
 }
@@ -207,10 +520,76 @@ struct Sizes {
     api_text: usize,
     /// Time to parse the schema as an input and an API schema
     time: Duration,
+    /// Size of the `*_filter` input types in the API schema
+    api_filter: usize,
+    /// Size of the `*_orderBy` enums in the API schema
+    api_order_by: usize,
+    /// Size of the types for `_meta`, block constraints and similar
+    /// plumbing in the API schema
+    api_meta: usize,
+    /// Size of the types that the API schema generates for aggregations,
+    /// together with the aggregation collection fields on `Query` and
+    /// `Subscription`; zero if the schema has no aggregations
+    api_aggregation: usize,
+}
+
+/// The groups into which we divide the definitions in the API schema when
+/// measuring its size; the groups are determined purely by the naming
+/// conventions that API schema generation uses
+#[derive(Clone, Copy, PartialEq)]
+enum ApiGroup {
+    Aggregation,
+    Filter,
+    OrderBy,
+    Meta,
+    Other,
+}
+
+impl ApiGroup {
+    /// Types that the API schema adds that do not follow the `_Name_`
+    /// convention for meta types
+    const META_TYPES: &'static [&'static str] = &[
+        "Block_height",
+        "BlockChangedFilter",
+        "OrderDirection",
+        "Aggregation_interval",
+    ];
+
+    fn of(defn: &s::Definition, schema: &InputSchema) -> ApiGroup {
+        let name = match defn {
+            s::Definition::TypeDefinition(typedef) => typedef.name(),
+            s::Definition::DirectiveDefinition(_) => return ApiGroup::Meta,
+            s::Definition::SchemaDefinition(_) | s::Definition::TypeExtension(_) => {
+                return ApiGroup::Other
+            }
+        };
+        if is_aggregation(schema, name) {
+            ApiGroup::Aggregation
+        } else if name.ends_with("_filter") {
+            ApiGroup::Filter
+        } else if name.ends_with("_orderBy") {
+            ApiGroup::OrderBy
+        } else if (name.starts_with('_') && name.ends_with('_')) || Self::META_TYPES.contains(&name)
+        {
+            ApiGroup::Meta
+        } else {
+            ApiGroup::Other
+        }
+    }
+}
+
+/// Return `true` if `name` is an aggregation type in `schema` or one of the
+/// `_filter` and `_orderBy` types that the API schema generates for it
+fn is_aggregation(schema: &InputSchema, name: &str) -> bool {
+    let name = name
+        .strip_suffix("_filter")
+        .or_else(|| name.strip_suffix("_orderBy"))
+        .unwrap_or(name);
+    schema.kind_of_declared_type(name) == Some(TypeKind::Aggregation)
 }
 
 struct Sizer {
-    first: AtomicBool,
+    options: ParseOptions,
 }
 
 impl Sizer {
@@ -222,10 +601,10 @@ impl Sizer {
         Ok((end, res))
     }
 
-    fn collect_sizes(&self, raw: &str, name: &str) -> Result<Sizes> {
+    fn collect_sizes(&self, raw: &str, name: &str) -> Result<(DeploymentHash, Sizes)> {
         // Prime possible lazy_statics etc.
         let start = Instant::now();
-        let id = parse(raw, name, true)?;
+        let (id, _) = parse(raw, name, true, &SPEC_VERSION_1_1_0, &self.options)?;
         let elapsed = start.elapsed();
         let txt_size = raw.len();
         let (gql_size, _) = self.size(|| {
@@ -233,78 +612,654 @@ impl Sizer {
                 .map(|v| v.into_static())
                 .map_err(Into::into)
         })?;
-        let (input_size, input_schema) =
-            self.size(|| InputSchema::parse_latest(raw, id.clone()).map_err(Into::into))?;
+        let (input_size, input_schema) = self.size(|| {
+            InputSchema::parse_with_options(LATEST_VERSION, raw, id.clone(), &self.options)
+        })?;
         let (api_size, api) = self.size(|| input_schema.api_schema().map_err(Into::into))?;
         let api_text = api.document().to_string().len();
-        Ok(Sizes {
+        let group_size = |group: ApiGroup| {
+            self.size(|| {
+                Ok(api
+                    .document()
+                    .definitions
+                    .iter()
+                    .filter(|defn| ApiGroup::of(defn, &input_schema) == group)
+                    .cloned()
+                    .collect::<Vec<_>>())
+            })
+            .map(|(size, _)| size)
+        };
+        let api_filter = group_size(ApiGroup::Filter)?;
+        let api_order_by = group_size(ApiGroup::OrderBy)?;
+        let api_meta = group_size(ApiGroup::Meta)?;
+        let (agg_fields_size, _) = self.size(|| {
+            Ok(api
+                .document()
+                .get_root_query_type()
+                .into_iter()
+                .chain(api.document().get_root_subscription_type())
+                .flat_map(|root| root.fields.iter())
+                .filter(|field| is_aggregation(&input_schema, field.field_type.get_base_type()))
+                .cloned()
+                .collect::<Vec<_>>())
+        })?;
+        let api_aggregation = group_size(ApiGroup::Aggregation)? + agg_fields_size;
+        let sizes = Sizes {
             gql: gql_size,
             text: txt_size,
             input: input_size,
             api: api_size,
             api_text,
             time: elapsed,
-        })
+            api_filter,
+            api_order_by,
+            api_meta,
+            api_aggregation,
+        };
+        Ok((id, sizes))
     }
 }
 
 impl Runner for Sizer {
-    fn run(&self, raw: &str, name: &str, _api: bool) {
-        if self.first.swap(false, SeqCst) {
-            println!("name,raw,gql,input,api,api_text,time_ns");
-        }
+    fn run(&self, raw: &str, name: &str, _api: bool) -> Report {
         match self.collect_sizes(raw, name) {
-            Ok(sizes) => {
+            Ok((id, sizes)) => Report::new(name, Some(id), Ok(Outcome::Sizes(sizes))),
+            Err(e) => Report::new(name, None, Err(e)),
+        }
+    }
+}
+
+/// Break the memory used by an `InputSchema` down by the types in it
+struct Breakdown {
+    options: ParseOptions,
+}
+
+impl Runner for Breakdown {
+    fn run(&self, raw: &str, name: &str, _api: bool) -> Report {
+        match parse(raw, name, false, &SPEC_VERSION_1_1_0, &self.options) {
+            Ok((id, schema)) => {
+                let report = schema.memory_report();
+                Report::new(name, Some(id), Ok(Outcome::Breakdown(report)))
+            }
+            Err(e) => Report::new(name, None, Err(e)),
+        }
+    }
+}
+
+/// Generate the API schema for an input schema so it can be dumped
+struct ApiDumper {
+    options: ParseOptions,
+}
+
+impl ApiDumper {
+    fn dump(&self, raw: &str, name: &str) -> Result<(DeploymentHash, String)> {
+        let (id, schema) = parse(raw, name, false, &SPEC_VERSION_1_1_0, &self.options)?;
+        let api = schema
+            .api_schema()
+            .with_context(|| format!("ApiSchema: {}[{}]", name, id))?;
+        let text = format!(
+            "# API schema for {} (deployment {})\n{}",
+            name,
+            id,
+            api.document()
+        );
+        Ok((id, text))
+    }
+}
+
+impl Runner for ApiDumper {
+    fn run(&self, raw: &str, name: &str, _api: bool) -> Report {
+        match self.dump(raw, name) {
+            Ok((id, text)) => Report::new(name, Some(id), Ok(Outcome::Api(text))),
+            Err(e) => Report::new(name, None, Err(e)),
+        }
+    }
+}
+
+fn type_kind(kind: TypeKind) -> &'static str {
+    match kind {
+        TypeKind::Object => "object",
+        TypeKind::Interface => "interface",
+        TypeKind::Aggregation => "aggregation",
+    }
+}
+
+/// Tally of the reports for all schemas we ran, used for the summary
+/// printed with `--keep-going`
+#[derive(Default)]
+struct Summary {
+    ok: usize,
+    failed: Vec<(String, &'static str)>,
+    /// The number of failures for each error code
+    codes: BTreeMap<&'static str, usize>,
+}
+
+impl Summary {
+    fn add(&mut self, report: &Report) {
+        match report.failure_code() {
+            None => self.ok += 1,
+            Some(code) => {
+                self.failed.push((report.name.clone(), code));
+                *self.codes.entry(code).or_default() += 1;
+            }
+        }
+    }
+
+    fn print(&self) {
+        eprintln!("Summary: {} ok, {} failed", self.ok, self.failed.len());
+        for (code, count) in &self.codes {
+            eprintln!("  {}: {}", code, count);
+        }
+        for (name, code) in &self.failed {
+            eprintln!("  failed: {} [{}]", name, code);
+        }
+    }
+
+    fn is_ok(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// The parse time and input size of all schemas measured with `--mode
+/// size`, used to print the most expensive ones with `--top`
+struct Top {
+    n: usize,
+    /// The name, parse time and input size for each schema
+    sizes: Vec<(String, Duration, usize)>,
+}
+
+impl Top {
+    fn new(n: usize) -> Self {
+        Top {
+            n,
+            sizes: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, report: &Report) {
+        if let Ok(Outcome::Sizes(sizes)) = &report.outcome {
+            self.sizes
+                .push((report.name.clone(), sizes.time, sizes.input));
+        }
+    }
+
+    fn print(&mut self) {
+        self.sizes
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        eprintln!("Slowest {} schemas to parse:", self.n);
+        for (name, time, _) in self.sizes.iter().take(self.n) {
+            eprintln!("  {:>12}ns {}", time.as_nanos(), name);
+        }
+        self.sizes
+            .sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        eprintln!("Largest {} input schemas:", self.n);
+        for (name, _, input) in self.sizes.iter().take(self.n) {
+            eprintln!("  {:>12}b  {}", input, name);
+        }
+    }
+}
+
+/// Print `Report`s in the format requested on the command line so that
+/// runners do not need to know anything about output formatting
+struct Printer {
+    mode: RunMode,
+    format: Format,
+    /// Whether we still need to print the CSV header for sizes
+    header: Cell<bool>,
+    /// Where to write dumped API schemas; stdout if this is not set
+    out_dir: Option<PathBuf>,
+}
+
+impl Printer {
+    fn new(mode: RunMode, format: Format, out_dir: Option<PathBuf>) -> Self {
+        Printer {
+            mode,
+            format,
+            header: Cell::new(true),
+            out_dir,
+        }
+    }
+
+    /// Write the API schema `text` for the schema `name` to a file in the
+    /// output directory, or to stdout if there is none. The file is named
+    /// after the schema, e.g., `sgd12.graphql` in batch mode
+    fn dump_api(&self, name: &str, text: &str) {
+        match &self.out_dir {
+            Some(dir) => {
+                let stem = Path::new(name)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_else(|| name.to_string());
+                let path = dir.join(format!("{stem}.graphql"));
+                ensure(
+                    std::fs::write(&path, text),
+                    &format!("Failed to write {}", path.display()),
+                );
+            }
+            None => println!("{}", text),
+        }
+    }
+
+    fn print(&self, report: &Report) {
+        match self.format {
+            Format::Text => self.print_text(report),
+            Format::Json => self.print_json(report),
+        }
+    }
+
+    fn print_text(&self, report: &Report) {
+        let name = &report.name;
+        let id = report
+            .id
+            .as_ref()
+            .map(|id| id.as_str())
+            .unwrap_or("unknown");
+        match (&report.outcome, self.mode) {
+            (Ok(Outcome::Valid), _) => {
+                println!("Schema {}[{}]: OK", name, id);
+            }
+            (Ok(Outcome::Immutable(immutable)), _) => {
+                println!("Schema {}[{}]: OK", name, id);
+                for entity_type in immutable {
+                    println!("  immutable: {}", entity_type);
+                }
+            }
+            (Ok(Outcome::Lint(lints)), _) => {
+                println!("Schema {}[{}]: OK", name, id);
+                for lint in lints {
+                    println!("  warning: {}", lint);
+                }
+            }
+            (Ok(Outcome::Specs(specs)), _) => {
+                println!("Schema {}[{}]:", name, id);
+                for (spec_version, err) in specs {
+                    match err {
+                        None => println!("  {:<8} OK", spec_version.to_string()),
+                        Some(e) => println!("  {:<8} Error: {}", spec_version.to_string(), e),
+                    }
+                }
+            }
+            (Ok(Outcome::Sizes(sizes)), _) => {
+                if self.header.replace(false) {
+                    println!(concat!(
+                        "name,raw,gql,input,api,api_text,time_ns,",
+                        "api_filter,api_order_by,api_meta,api_aggregation"
+                    ));
+                }
                 println!(
-                    "{name},{},{},{},{},{},{}",
+                    "{name},{},{},{},{},{},{},{},{},{},{}",
                     sizes.text,
                     sizes.gql,
                     sizes.input,
                     sizes.api,
                     sizes.api_text,
-                    sizes.time.as_nanos()
+                    sizes.time.as_nanos(),
+                    sizes.api_filter,
+                    sizes.api_order_by,
+                    sizes.api_meta,
+                    sizes.api_aggregation
                 );
             }
+            (Ok(Outcome::Breakdown(breakdown)), _) => {
+                if self.header.replace(false) {
+                    println!("name,type,kind,fields,bytes,interned");
+                }
+                for t in &breakdown.types {
+                    println!(
+                        "{name},{},{},{},{},{}",
+                        t.name,
+                        type_kind(t.kind),
+                        t.fields,
+                        t.bytes,
+                        t.interned
+                    );
+                }
+                println!("{name},,enums,,{},", breakdown.enums);
+            }
+            (Ok(Outcome::Api(text)), _) => self.dump_api(name, text),
+            (Err(e), RunMode::Validate) => println!("Error [{}]: {}", e.code, e.message),
+            (Err(e), RunMode::Size | RunMode::Breakdown | RunMode::DumpApi) => {
+                eprintln!("Error [{}]: {}", e.code, e.message)
+            }
+        }
+    }
+
+    fn print_json(&self, report: &Report) {
+        let mut obj = serde_json::Map::new();
+        obj.insert("name".to_string(), report.name.as_str().into());
+        obj.insert(
+            "id".to_string(),
+            report
+                .id
+                .as_ref()
+                .map(|id| serde_json::Value::from(id.as_str()))
+                .unwrap_or(serde_json::Value::Null),
+        );
+        obj.insert("ok".to_string(), report.is_ok().into());
+        match &report.outcome {
+            Ok(Outcome::Valid) => {
+                obj.insert("error".to_string(), serde_json::Value::Null);
+            }
+            Ok(Outcome::Immutable(immutable)) => {
+                obj.insert("error".to_string(), serde_json::Value::Null);
+                obj.insert("immutable".to_string(), immutable.clone().into());
+            }
+            Ok(Outcome::Lint(lints)) => {
+                obj.insert("error".to_string(), serde_json::Value::Null);
+                let lints = lints
+                    .iter()
+                    .map(|lint| {
+                        serde_json::json!({
+                            "type": lint.entity_type,
+                            "field": lint.field,
+                            "kind": lint.kind.as_str(),
+                            "suggestion": lint.suggestion,
+                        })
+                    })
+                    .collect();
+                obj.insert("lints".to_string(), serde_json::Value::Array(lints));
+            }
+            Ok(Outcome::Breakdown(breakdown)) => {
+                obj.insert("error".to_string(), serde_json::Value::Null);
+                let types = breakdown
+                    .types
+                    .iter()
+                    .map(|t| {
+                        serde_json::json!({
+                            "type": t.name,
+                            "kind": type_kind(t.kind),
+                            "fields": t.fields,
+                            "bytes": t.bytes,
+                            "interned": t.interned,
+                        })
+                    })
+                    .collect();
+                obj.insert("types".to_string(), serde_json::Value::Array(types));
+                obj.insert("enums".to_string(), breakdown.enums.into());
+            }
+            Ok(Outcome::Specs(specs)) => {
+                obj.insert("error".to_string(), serde_json::Value::Null);
+                let specs = specs
+                    .iter()
+                    .map(|(spec_version, err)| {
+                        serde_json::json!({
+                            "spec": spec_version.to_string(),
+                            "ok": err.is_none(),
+                            "error": err,
+                        })
+                    })
+                    .collect();
+                obj.insert("specs".to_string(), serde_json::Value::Array(specs));
+            }
+            Ok(Outcome::Api(text)) => {
+                obj.insert("error".to_string(), serde_json::Value::Null);
+                if self.out_dir.is_some() {
+                    self.dump_api(&report.name, text);
+                } else {
+                    obj.insert("api".to_string(), text.as_str().into());
+                }
+            }
+            Ok(Outcome::Sizes(sizes)) => {
+                obj.insert("error".to_string(), serde_json::Value::Null);
+                obj.insert("raw".to_string(), sizes.text.into());
+                obj.insert("gql".to_string(), sizes.gql.into());
+                obj.insert("input".to_string(), sizes.input.into());
+                obj.insert("api".to_string(), sizes.api.into());
+                obj.insert("api_text".to_string(), sizes.api_text.into());
+                obj.insert("time_ns".to_string(), (sizes.time.as_nanos() as u64).into());
+                obj.insert("api_filter".to_string(), sizes.api_filter.into());
+                obj.insert("api_order_by".to_string(), sizes.api_order_by.into());
+                obj.insert("api_meta".to_string(), sizes.api_meta.into());
+                obj.insert("api_aggregation".to_string(), sizes.api_aggregation.into());
+            }
             Err(e) => {
-                eprintln!("Error: {}", e);
-                exit(1);
+                obj.insert("error".to_string(), e.message.as_str().into());
+                obj.insert("code".to_string(), e.code.into());
             }
         }
+        println!("{}", serde_json::Value::Object(obj));
     }
 }
 
-pub fn main() {
-    // Allow fulltext search in schemas
-    std::env::set_var("GRAPH_ALLOW_NON_DETERMINISTIC_FULLTEXT_SEARCH", "true");
+/// Return the raw schema and the name for each entry in the batch input
+/// `rdr`, leaving out the first `skip` entries and stopping after `limit`
+/// entries. Skipped lines are not parsed
+fn entries<R: BufRead>(
+    rdr: R,
+    skip: Option<usize>,
+    limit: Option<usize>,
+) -> impl Iterator<Item = (String, String)> {
+    rdr.lines()
+        .skip(skip.unwrap_or(0))
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|line| {
+            let line = line.expect("invalid line").replace("\\\\", "\\");
+            let entry = serde_json::from_str::<Entry>(&line).expect("line is valid json");
+            (entry.schema, format!("sgd{}", entry.id))
+        })
+}
+
+/// Wrap `rdr` in a gzip decoder if `gzip` is set. Concatenated gzip
+/// members are decoded as one stream
+fn batch_reader<R: Read + Send + 'static>(rdr: R, gzip: bool) -> BufReader<Box<dyn Read + Send>> {
+    let rdr: Box<dyn Read + Send> = if gzip {
+        Box::new(MultiGzDecoder::new(rdr))
+    } else {
+        Box::new(rdr)
+    };
+    BufReader::new(rdr)
+}
+
+/// Add all files with extension `ext` in `dir` and its subdirectories to
+/// `files`, in a deterministic order
+fn find_files(dir: &Path, ext: &str, files: &mut Vec<String>) {
+    let entries = ensure(
+        std::fs::read_dir(dir),
+        &format!("Failed to read directory {}", dir.display()),
+    );
+    let mut paths: Vec<_> = entries
+        .map(|entry| ensure(entry, "Failed to read directory entry").path())
+        .collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            find_files(&path, ext, files);
+        } else if path.extension() == Some(ext.as_ref()) {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+}
+
+/// Replace each directory in `paths` with the files with extension `ext`
+/// in it
+fn expand_paths(paths: &[String], ext: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    for path in paths {
+        if Path::new(path).is_dir() {
+            find_files(Path::new(path), ext, &mut files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+/// Return the raw schema and the name for each schema we need to process
+fn schemas(opt: &Opts) -> Box<dyn Iterator<Item = (String, String)> + Send + '_> {
+    let default_ext = if opt.batch { "json" } else { "graphql" };
+    let ext = opt
+        .ext
+        .as_deref()
+        .unwrap_or(default_ext)
+        .trim_start_matches('.');
+    let files = expand_paths(&opt.schemas, ext);
+    match (opt.stdin, opt.batch) {
+        (true, true) => {
+            eprintln!("Validating schemas from stdin");
+            Box::new(entries(
+                batch_reader(std::io::stdin(), opt.gzip),
+                opt.skip,
+                opt.limit,
+            ))
+        }
+        (true, false) => {
+            eprintln!("Validating schema from stdin");
+            let mut raw = String::new();
+            std::io::stdin()
+                .read_to_string(&mut raw)
+                .expect("stdin is readable");
+            Box::new(std::iter::once((raw, "stdin".to_string())))
+        }
+        (false, true) => Box::new(files.into_iter().flat_map(|schema| {
+            eprintln!("Validating schemas from {schema}");
+            let file = File::open(&schema).expect("file exists");
+            entries(
+                batch_reader(file, opt.gzip || schema.ends_with(".gz")),
+                opt.skip,
+                opt.limit,
+            )
+        })),
+        (false, false) => Box::new(files.into_iter().map(|schema| {
+            eprintln!("Validating schema from {schema}");
+            let raw = std::fs::read_to_string(&schema).expect("file exists");
+            (raw, schema)
+        })),
+    }
+}
+
+/// Run `runner` on all `schemas` using `jobs` worker threads. The schemas
+/// are read on a separate thread, and `handle` is called on the current
+/// thread for each report in the order in which the workers finish
+fn run_parallel(
+    runner: &dyn Runner,
+    schemas: Box<dyn Iterator<Item = (String, String)> + Send + '_>,
+    jobs: usize,
+    api: bool,
+    mut handle: impl FnMut(Report),
+) {
+    // Bound the queue so that we do not read the entire input into memory
+    // when the workers can't keep up
+    let (job_tx, job_rx) = mpsc::sync_channel::<(String, String)>(jobs * 4);
+    let job_rx = Mutex::new(job_rx);
+    let (report_tx, report_rx) = mpsc::channel::<Report>();
 
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            for job in schemas {
+                if job_tx.send(job).is_err() {
+                    break;
+                }
+            }
+        });
+        for _ in 0..jobs {
+            let job_rx = &job_rx;
+            let report_tx = report_tx.clone();
+            scope.spawn(move || loop {
+                let job = job_rx.lock().unwrap().recv();
+                match job {
+                    Ok((raw, name)) => {
+                        if report_tx.send(runner.run(&raw, &name, api)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            });
+        }
+        drop(report_tx);
+
+        for report in report_rx {
+            handle(report);
+        }
+    });
+}
+
+pub fn main() {
     let opt = Opts::parse();
 
+    for spec_version in &opt.spec {
+        if !spec_versions().any(|known| known == spec_version) {
+            usage(&format!("Unknown spec version {spec_version}"));
+        }
+    }
+    if opt.top.is_some() && !matches!(opt.mode, RunMode::Size) {
+        usage("--top can only be used with --mode size");
+    }
+
+    let options = ParseOptions::default().allow_fulltext(opt.allow_fulltext);
+    let limits = opt.limits.then(|| {
+        let defaults = SchemaLimits::default();
+        SchemaLimits {
+            max_entity_types: opt.max_entity_types.unwrap_or(defaults.max_entity_types),
+            max_fields_per_type: opt
+                .max_fields_per_type
+                .unwrap_or(defaults.max_fields_per_type),
+            max_enum_values: opt.max_enum_values.unwrap_or(defaults.max_enum_values),
+            max_fulltext_indexes: opt
+                .max_fulltext_indexes
+                .unwrap_or(defaults.max_fulltext_indexes),
+        }
+    });
     let runner: Box<dyn Runner> = match opt.mode {
-        RunMode::Validate => Box::new(Validator),
-        RunMode::Size => Box::new(Sizer {
-            first: AtomicBool::new(true),
+        RunMode::Validate => Box::new(Validator {
+            spec_versions: opt.spec.clone(),
+            options,
+            limits,
+            list_immutable: opt.list_immutable,
+            lint: opt.lint,
         }),
+        RunMode::Size => Box::new(Sizer { options }),
+        RunMode::Breakdown => Box::new(Breakdown { options }),
+        RunMode::DumpApi => Box::new(ApiDumper { options }),
+    };
+    if let Some(dir) = &opt.out_dir {
+        ensure(
+            std::fs::create_dir_all(dir),
+            &format!("Failed to create {}", dir.display()),
+        );
+    }
+    let printer = Printer::new(opt.mode, opt.format, opt.out_dir.clone());
+    let mut summary = Summary::default();
+    let mut top = opt.top.map(Top::new);
+    let mut buffered = Vec::new();
+    let mut handle = |report: Report| {
+        summary.add(&report);
+        if let Some(top) = top.as_mut() {
+            top.add(&report);
+        }
+        let failed = !report.is_ok();
+        if opt.sorted {
+            buffered.push(report);
+        } else {
+            printer.print(&report);
+        }
+        if failed && !opt.keep_going {
+            print_sorted(&printer, &mut buffered);
+            exit(1);
+        }
     };
 
-    if opt.batch {
-        for schema in &opt.schemas {
-            eprintln!("Validating schemas from {schema}");
-            let file = File::open(schema).expect("file exists");
-            let rdr = BufReader::new(file);
-            for line in rdr.lines() {
-                let line = line.expect("invalid line").replace("\\\\", "\\");
-                let entry = serde_json::from_str::<Entry>(&line).expect("line is valid json");
-
-                let raw = &entry.schema;
-                let name = format!("sgd{}", entry.id);
-                runner.run(raw, &name, opt.api);
+    match opt.mode {
+        RunMode::Validate | RunMode::Breakdown | RunMode::DumpApi if opt.jobs > 1 => {
+            run_parallel(runner.as_ref(), schemas(&opt), opt.jobs, opt.api, handle);
+        }
+        RunMode::Validate | RunMode::Size | RunMode::Breakdown | RunMode::DumpApi => {
+            for (raw, name) in schemas(&opt) {
+                handle(runner.run(&raw, &name, opt.api));
             }
         }
-    } else {
-        for schema in &opt.schemas {
-            eprintln!("Validating schema from {schema}");
-            let raw = std::fs::read_to_string(schema).expect("file exists");
-            runner.run(&raw, schema, opt.api);
+    }
+
+    print_sorted(&printer, &mut buffered);
+
+    if let Some(top) = top.as_mut() {
+        top.print();
+    }
+
+    if opt.keep_going {
+        summary.print();
+        if !summary.is_ok() {
+            exit(1);
         }
     }
 }
@@ -355,6 +355,7 @@ pub trait BlockStreamMapper<C: Blockchain>: Send + Sync {
         logger: &Logger,
         clock: Clock,
         cursor: FirehoseCursor,
+        type_url: &str,
         block: Vec<u8>,
     ) -> Result<BlockStreamEvent<C>, BlockStreamError>;
 
@@ -410,8 +411,8 @@ pub trait BlockStreamMapper<C: Blockchain>: Send + Sync {
                     None => return Err(BlockStreamError::from(SubstreamsError::MissingClockError)),
                 };
 
-                let value = match module_output.map_output {
-                    Some(Any { type_url: _, value }) => value,
+                let (type_url, value) = match module_output.map_output {
+                    Some(Any { type_url, value }) => (type_url, value),
                     None => return Ok(None),
                 };
 
@@ -419,7 +420,7 @@ pub trait BlockStreamMapper<C: Blockchain>: Send + Sync {
                 let cursor = FirehoseCursor::from(cursor);
 
                 let event = self
-                    .handle_substreams_block(&logger, clock, cursor, value)
+                    .handle_substreams_block(&logger, clock, cursor, &type_url, value)
                     .await?;
 
                 Ok(Some(event))
@@ -564,6 +565,8 @@ pub struct BlockStreamMetrics {
     pub deployment_head: Box<Gauge>,
     pub deployment_failed: Box<Gauge>,
     pub reverted_blocks: Gauge,
+    /// Counts reverts that are deeper than `GRAPH_DEEP_REORG_THRESHOLD`
+    pub deep_reorgs: Box<Counter>,
     pub stopwatch: StopwatchMetrics,
 }
 
@@ -582,6 +585,16 @@ impl BlockStreamMetrics {
                 deployment_id.as_str(),
             )
             .expect("Failed to create `deployment_reverted_blocks` gauge");
+        let deep_reorgs = registry
+            .new_counter_with_labels(
+                "deployment_deep_reorgs",
+                "Counts reverts that are deeper than the deep reorg threshold",
+                labels! {
+                    String::from("deployment") => deployment_id.to_string(),
+                    String::from("network") => network.clone()
+                },
+            )
+            .expect("failed to create `deployment_deep_reorgs` counter");
         let labels = labels! {
             String::from("deployment") => deployment_id.to_string(),
             String::from("network") => network,
@@ -605,6 +618,7 @@ impl BlockStreamMetrics {
             deployment_head,
             deployment_failed,
             reverted_blocks,
+            deep_reorgs,
             stopwatch,
         }
     }
@@ -21,6 +21,8 @@ struct FirehoseBlockStreamMetrics {
     connect_duration: GaugeVec,
     time_between_responses: HistogramVec,
     responses: CounterVec,
+    retries: GaugeVec,
+    backoff: GaugeVec,
 }
 
 impl FirehoseBlockStreamMetrics {
@@ -59,9 +61,34 @@ impl FirehoseBlockStreamMetrics {
                     vec!["deployment", "provider", "kind"].as_slice(),
                 )
                 .unwrap(),
+
+            retries: registry
+                .global_gauge_vec(
+                    "deployment_firehose_blockstream_retries",
+                    "The number of consecutive failed attempts to stream from a Firehose endpoint",
+                    vec!["deployment", "provider"].as_slice(),
+                )
+                .unwrap(),
+
+            backoff: registry
+                .global_gauge_vec(
+                    "deployment_firehose_blockstream_backoff",
+                    "The time in seconds to wait before reconnecting a Firehose block stream",
+                    vec!["deployment", "provider"].as_slice(),
+                )
+                .unwrap(),
         }
     }
 
+    fn observe_backoff(&self, provider: &str, retries: u64, delay: Duration) {
+        self.retries
+            .with_label_values(&[&self.deployment, &provider])
+            .set(retries as f64);
+        self.backoff
+            .with_label_values(&[&self.deployment, &provider])
+            .set(delay.as_secs_f64());
+    }
+
     fn observe_successful_connection(&self, time: &mut Instant, provider: &str) {
         self.restarts
             .with_label_values(&[&self.deployment, &provider, "true"])
@@ -205,8 +232,13 @@ fn stream_blocks<C: Blockchain, F: FirehoseMapper<C>>(
 
     let headers = firehose::ConnectionHeaders::new().with_deployment(deployment.clone());
 
-    // Back off exponentially whenever we encounter a connection error or a stream with bad data
-    let mut backoff = ExponentialBackoff::new(Duration::from_millis(500), Duration::from_secs(45));
+    // Back off exponentially whenever we encounter a connection error or a stream with bad data,
+    // and add jitter so that many deployments do not reconnect to the same provider in lockstep
+    let mut backoff = ExponentialBackoff::with_jitter(
+        Duration::from_millis(500),
+        ENV_VARS.firehose_backoff_ceil,
+        ENV_VARS.firehose_backoff_jitter,
+    );
 
     // This attribute is needed because `try_stream!` seems to break detection of `skip_backoff` assignments
     #[allow(unused_assignments)]
@@ -253,6 +285,7 @@ fn stream_blocks<C: Blockchain, F: FirehoseMapper<C>>(
                     metrics.observe_successful_connection(&mut connect_start, &endpoint.provider);
 
                     let mut last_response_time = Instant::now();
+                    let connected_at = Instant::now();
                     let mut expected_stream_end = false;
 
                     for await response in stream {
@@ -266,8 +299,13 @@ fn stream_blocks<C: Blockchain, F: FirehoseMapper<C>>(
                             &logger,
                         ).await {
                             Ok(BlockResponse::Proceed(event, cursor)) => {
-                                // Reset backoff because we got a good value from the stream
-                                backoff.reset();
+                                // Reset backoff once the stream has been delivering good values
+                                // for a while; resetting on the first good value would let a
+                                // flapping endpoint make us reconnect aggressively
+                                if backoff.attempt > 0 && connected_at.elapsed() >= ENV_VARS.firehose_backoff_reset {
+                                    backoff.reset();
+                                    metrics.observe_backoff(&endpoint.provider, 0, Duration::ZERO);
+                                }
 
                                 metrics.observe_response("proceed", &mut last_response_time, &endpoint.provider);
 
@@ -276,8 +314,10 @@ fn stream_blocks<C: Blockchain, F: FirehoseMapper<C>>(
                                 latest_cursor = FirehoseCursor::from(cursor);
                             },
                             Ok(BlockResponse::Rewind(revert_to)) => {
-                                // Reset backoff because we got a good value from the stream
-                                backoff.reset();
+                                if backoff.attempt > 0 && connected_at.elapsed() >= ENV_VARS.firehose_backoff_reset {
+                                    backoff.reset();
+                                    metrics.observe_backoff(&endpoint.provider, 0, Duration::ZERO);
+                                }
 
                                 metrics.observe_response("rewind", &mut last_response_time, &endpoint.provider);
 
@@ -332,7 +372,9 @@ fn stream_blocks<C: Blockchain, F: FirehoseMapper<C>>(
 
             // If we reach this point, we must wait a bit before retrying, unless `skip_backoff` is true
             if !skip_backoff {
-                backoff.sleep_async().await;
+                let delay = backoff.next_attempt();
+                metrics.observe_backoff(&endpoint.provider, backoff.attempt, delay);
+                tokio::time::sleep(delay).await;
             }
         }
     }
@@ -20,8 +20,8 @@ use super::{
 };
 
 use super::{
-    block_stream::BlockWithTriggers, Block, BlockPtr, Blockchain, BlockchainKind, DataSource,
-    DataSourceTemplate, RuntimeAdapter, TriggerData, TriggerFilter, TriggersAdapter,
+    block_stream::BlockWithTriggers, Block, BlockHash, BlockPtr, Blockchain, BlockchainKind,
+    DataSource, DataSourceTemplate, RuntimeAdapter, TriggerData, TriggerFilter, TriggersAdapter,
     UnresolvedDataSource, UnresolvedDataSourceTemplate,
 };
 
@@ -33,17 +33,24 @@ pub struct MockBlock {
     pub number: u64,
 }
 
+fn mock_hash(number: u64) -> BlockHash {
+    BlockHash::from(number.to_be_bytes().to_vec())
+}
+
+/// Mock blocks are one second apart, starting at the epoch
 impl Block for MockBlock {
     fn ptr(&self) -> BlockPtr {
-        todo!()
+        BlockPtr::new(mock_hash(self.number), self.number as BlockNumber)
     }
 
     fn parent_ptr(&self) -> Option<BlockPtr> {
-        todo!()
+        self.number
+            .checked_sub(1)
+            .map(|number| BlockPtr::new(mock_hash(number), number as BlockNumber))
     }
 
     fn timestamp(&self) -> BlockTime {
-        todo!()
+        BlockTime::since_epoch(self.number as i64, 0)
     }
 }
 
@@ -0,0 +1,255 @@
+//! A typed client for the status API of the index node server, for use by
+//! tooling that needs to check on deployments. The types mirror the parts
+//! of the `SubgraphIndexingStatus` type from the index node's GraphQL
+//! schema that are most commonly needed.
+//!
+//! This module is only available with the `client` feature.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use serde_json::json;
+use url::Url;
+
+use crate::data::store::scalar::Bytes;
+use crate::data::subgraph::schema::SubgraphHealth;
+use crate::prelude::BlockNumber;
+
+/// The fields that are queried for every `SubgraphIndexingStatus`
+const STATUS_FIELDS: &str = "
+  subgraph
+  synced
+  health
+  entityCount
+  node
+  paused
+  fatalError { message block { hash number } handler deterministic }
+  nonFatalErrors { message block { hash number } handler deterministic }
+  chains {
+    network
+    chainHeadBlock { hash number }
+    earliestBlock { number }
+    latestBlock { hash number }
+    lastHealthyBlock { hash number }
+  }
+";
+
+#[derive(Debug, thiserror::Error)]
+pub enum StatusClientError {
+    #[error("Request error: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("The status API returned errors: {}", .0.join(", "))]
+    GraphQL(Vec<String>),
+    #[error("The status API returned no data")]
+    NoData,
+}
+
+/// Deserialize a value that the status API sends as a string, like a
+/// `BigInt`
+fn from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(serde::de::Error::custom)
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Block {
+    pub hash: Bytes,
+    #[serde(deserialize_with = "from_str")]
+    pub number: BlockNumber,
+}
+
+/// The earliest block for which a deployment has data. The status API
+/// always reports its hash as `0x0`, so only the number is available
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct EarliestBlock {
+    #[serde(deserialize_with = "from_str")]
+    pub number: BlockNumber,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct SubgraphError {
+    pub message: String,
+    pub block: Option<Block>,
+    pub handler: Option<String>,
+    pub deterministic: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainIndexingStatus {
+    pub network: String,
+    pub chain_head_block: Option<Block>,
+    pub earliest_block: Option<EarliestBlock>,
+    pub latest_block: Option<Block>,
+    pub last_healthy_block: Option<Block>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexingStatus {
+    /// The IPFS hash of the deployment
+    pub subgraph: String,
+    pub synced: bool,
+    pub health: SubgraphHealth,
+    pub fatal_error: Option<SubgraphError>,
+    pub non_fatal_errors: Vec<SubgraphError>,
+    pub chains: Vec<ChainIndexingStatus>,
+    #[serde(deserialize_with = "from_str")]
+    pub entity_count: u64,
+    pub node: Option<String>,
+    pub paused: bool,
+}
+
+#[derive(Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQLError>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexingStatuses {
+    indexing_statuses: Vec<IndexingStatus>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexingStatusForCurrentVersion {
+    indexing_status_for_current_version: Option<IndexingStatus>,
+}
+
+/// Client for the status API of an index node. Clones share the
+/// underlying connection pool.
+#[derive(Clone, Debug)]
+pub struct StatusClient {
+    url: Url,
+    // reqwest::Client doesn't need to be `Arc` because it has one internally
+    // already.
+    client: reqwest::Client,
+}
+
+impl StatusClient {
+    /// Create a client for the status API at `url`, usually something like
+    /// `http://localhost:8030/graphql`
+    pub fn new(url: Url) -> Self {
+        StatusClient {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// The status of the deployments with the given IPFS hashes, or of all
+    /// deployments if `deployments` is `None`
+    pub async fn indexing_statuses(
+        &self,
+        deployments: Option<&[String]>,
+    ) -> Result<Vec<IndexingStatus>, StatusClientError> {
+        let query = format!(
+            "query($subgraphs: [String!]) {{ indexingStatuses(subgraphs: $subgraphs) {{ {} }} }}",
+            STATUS_FIELDS
+        );
+        let data: IndexingStatuses = self
+            .query(&query, json!({ "subgraphs": deployments }))
+            .await?;
+        Ok(data.indexing_statuses)
+    }
+
+    /// The status of the current version of the subgraph `subgraph_name`,
+    /// or `None` if the subgraph has no current version
+    pub async fn indexing_status_for_current_version(
+        &self,
+        subgraph_name: &str,
+    ) -> Result<Option<IndexingStatus>, StatusClientError> {
+        let query = format!(
+            "query($name: String!) {{ indexingStatusForCurrentVersion(subgraphName: $name) {{ {} }} }}",
+            STATUS_FIELDS
+        );
+        let data: IndexingStatusForCurrentVersion =
+            self.query(&query, json!({ "name": subgraph_name })).await?;
+        Ok(data.indexing_status_for_current_version)
+    }
+
+    /// Run an arbitrary query against the status API and decode the
+    /// `data` of the response into `T`
+    pub async fn query<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<T, StatusClientError> {
+        let body = json!({ "query": query, "variables": variables });
+        let response: Response<T> = self
+            .client
+            .post(self.url.clone())
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if !response.errors.is_empty() {
+            let errors = response.errors.into_iter().map(|e| e.message).collect();
+            return Err(StatusClientError::GraphQL(errors));
+        }
+        response.data.ok_or(StatusClientError::NoData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_indexing_status() {
+        let raw = r#"{
+          "data": {
+            "indexingStatuses": [{
+              "subgraph": "QmXW3qvxV7zXnwRntpj7yoK8HZVtaraZ67uMqaLRvXdxha",
+              "synced": false,
+              "health": "failed",
+              "entityCount": "1234",
+              "node": "index_node_0",
+              "paused": false,
+              "fatalError": {
+                "message": "boom",
+                "block": { "hash": "0x0102", "number": "17" },
+                "handler": "handleTransfer",
+                "deterministic": true
+              },
+              "nonFatalErrors": [],
+              "chains": [{
+                "network": "mainnet",
+                "chainHeadBlock": { "hash": "0x03", "number": "20" },
+                "earliestBlock": { "number": "0" },
+                "latestBlock": { "hash": "0x0102", "number": "16" },
+                "lastHealthyBlock": null
+              }]
+            }]
+          }
+        }"#;
+
+        let response: Response<IndexingStatuses> = serde_json::from_str(raw).unwrap();
+        assert!(response.errors.is_empty());
+        let status = &response.data.unwrap().indexing_statuses[0];
+        assert_eq!(SubgraphHealth::Failed, status.health);
+        assert_eq!(1234, status.entity_count);
+        let error = status.fatal_error.as_ref().unwrap();
+        assert_eq!(Some("handleTransfer"), error.handler.as_deref());
+        assert_eq!(17, error.block.as_ref().unwrap().number);
+        let chain = &status.chains[0];
+        assert_eq!(20, chain.chain_head_block.as_ref().unwrap().number);
+        assert_eq!(None, chain.last_healthy_block);
+    }
+}
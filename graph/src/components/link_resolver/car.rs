@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use cid::Cid;
+use futures03::stream;
+use sha2::{Digest, Sha256};
+use slog::{trace, Logger};
+
+use crate::cheap_clone::CheapClone;
+use crate::data::subgraph::Link;
+use crate::derive::CheapClone;
+use crate::prelude::Error;
+
+use super::{JsonStreamValue, JsonValueStream, LinkResolver};
+
+/// Multicodec for raw binary blocks
+const RAW_CODEC: u64 = 0x55;
+/// Multicodec for blocks encoded with the `dag-pb` protobuf format
+const DAG_PB_CODEC: u64 = 0x70;
+/// Multihash codes for the hash functions we can verify
+const IDENTITY_HASH: u64 = 0x00;
+const SHA2_256_HASH: u64 = 0x12;
+
+/// The fixed bytes at the start of a CARv2 file
+const CAR_V2_PRAGMA: [u8; 11] = [
+    0x0a, 0xa1, 0x67, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x02,
+];
+
+/// The `dag-pb` and UnixFS messages that are needed to reassemble files
+/// from their blocks. See https://ipld.io/specs/codecs/dag-pb/spec/ and
+/// https://github.com/ipfs/specs/blob/main/UNIXFS.md
+mod pb {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Link {
+        #[prost(bytes = "vec", optional, tag = "1")]
+        pub hash: Option<Vec<u8>>,
+        #[prost(string, optional, tag = "2")]
+        pub name: Option<String>,
+        #[prost(uint64, optional, tag = "3")]
+        pub tsize: Option<u64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Node {
+        #[prost(message, repeated, tag = "2")]
+        pub links: Vec<Link>,
+        #[prost(bytes = "vec", optional, tag = "1")]
+        pub data: Option<Vec<u8>>,
+    }
+
+    pub const UNIXFS_RAW: i32 = 0;
+    pub const UNIXFS_DIRECTORY: i32 = 1;
+    pub const UNIXFS_FILE: i32 = 2;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct UnixFsData {
+        #[prost(int32, tag = "1")]
+        pub r#type: i32,
+        #[prost(bytes = "vec", optional, tag = "2")]
+        pub data: Option<Vec<u8>>,
+        #[prost(uint64, optional, tag = "3")]
+        pub filesize: Option<u64>,
+        #[prost(uint64, repeated, packed = "false", tag = "4")]
+        pub blocksizes: Vec<u64>,
+    }
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64, Error> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf
+            .get(*pos)
+            .ok_or_else(|| anyhow!("unexpected end of CAR file"))?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("invalid varint in CAR file"))
+}
+
+/// CIDv0 and CIDv1 for the same content only differ in their version, and
+/// we want to find a block no matter which version a link uses
+fn normalize(cid: &Cid) -> Cid {
+    Cid::new_v1(cid.codec(), *cid.hash())
+}
+
+/// Check that `data` hashes to the digest in `cid`
+pub(super) fn verify(cid: &Cid, data: &[u8]) -> Result<(), Error> {
+    let hash = cid.hash();
+    let matches = match hash.code() {
+        IDENTITY_HASH => hash.digest() == data,
+        SHA2_256_HASH => hash.digest() == Sha256::digest(data).as_slice(),
+        code => {
+            return Err(anyhow!(
+                "block {} uses unsupported hash function 0x{:x}",
+                cid,
+                code
+            ))
+        }
+    };
+    if !matches {
+        return Err(anyhow!(
+            "the contents of block {} do not match its CID",
+            cid
+        ));
+    }
+    Ok(())
+}
+
+/// Parse the blocks in the CARv1 data in `buf`, verifying each block
+/// against its CID
+fn parse_car_v1(buf: &[u8]) -> Result<HashMap<Cid, Vec<u8>>, Error> {
+    let mut pos = 0;
+    // The header is a DAG-CBOR map with the roots and the version; we
+    // resolve links by CID and don't need the roots
+    let header_len = read_varint(buf, &mut pos)? as usize;
+    pos += header_len;
+
+    let mut blocks = HashMap::new();
+    while pos < buf.len() {
+        let len = read_varint(buf, &mut pos)? as usize;
+        let section = buf
+            .get(pos..pos + len)
+            .ok_or_else(|| anyhow!("unexpected end of CAR file"))?;
+        pos += len;
+
+        let mut cursor = Cursor::new(section);
+        let cid = Cid::read_bytes(&mut cursor)?;
+        let data = &section[cursor.position() as usize..];
+        verify(&cid, data)?;
+        blocks.insert(normalize(&cid), data.to_vec());
+    }
+    Ok(blocks)
+}
+
+/// Parse a CAR file in either version 1 or version 2 of the format
+fn parse_car(buf: &[u8]) -> Result<HashMap<Cid, Vec<u8>>, Error> {
+    if !buf.starts_with(&CAR_V2_PRAGMA) {
+        return parse_car_v1(buf);
+    }
+
+    // The CARv2 header follows the pragma and consists of 16 bytes of
+    // characteristics followed by the offset and size of the CARv1 data
+    // and the offset of the index as little-endian u64
+    let header = buf
+        .get(CAR_V2_PRAGMA.len()..CAR_V2_PRAGMA.len() + 40)
+        .ok_or_else(|| anyhow!("unexpected end of CAR file"))?;
+    let data_offset = u64::from_le_bytes(header[16..24].try_into().unwrap()) as usize;
+    let data_size = u64::from_le_bytes(header[24..32].try_into().unwrap()) as usize;
+    let data = buf
+        .get(data_offset..data_offset + data_size)
+        .ok_or_else(|| anyhow!("unexpected end of CAR file"))?;
+    parse_car_v1(data)
+}
+
+/// A `LinkResolver` that reads subgraph files from the blocks of a CAR
+/// file instead of from IPFS nodes so that subgraphs can be deployed
+/// without network access. All blocks are read and verified against their
+/// CID when the file is opened.
+///
+/// Links take the same form as for `IpfsResolver`, i.e., `/ipfs/<cid>`
+/// or just `<cid>`, optionally followed by a path into a directory.
+#[derive(Clone, CheapClone)]
+pub struct CarResolver {
+    blocks: Arc<HashMap<Cid, Vec<u8>>>,
+}
+
+impl Debug for CarResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CarResolver")
+            .field("blocks", &self.blocks.len())
+            .finish()
+    }
+}
+
+impl CarResolver {
+    /// Read all blocks from the CAR file at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let buf = std::fs::read(path)
+            .map_err(|e| anyhow!("failed to read CAR file {}: {}", path.display(), e))?;
+        Self::from_bytes(&buf)
+    }
+
+    /// Read all blocks from the contents `buf` of a CAR file
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            blocks: Arc::new(parse_car(buf)?),
+        })
+    }
+
+    fn block(&self, cid: &Cid) -> Result<&[u8], Error> {
+        self.blocks
+            .get(&normalize(cid))
+            .map(Vec::as_slice)
+            .ok_or_else(|| anyhow!("block {} is not in the CAR file", cid))
+    }
+
+    /// Resolve a link like `/ipfs/<cid>/some/file` to the CID of the
+    /// block that `<cid>` followed by the path points to
+    fn resolve_path(&self, link: &str) -> Result<Cid, Error> {
+        let path = link.trim_start_matches("/ipfs/");
+        let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+        let root = segments
+            .next()
+            .ok_or_else(|| anyhow!("invalid IPFS link `{}`", link))?;
+        let mut cid = Cid::try_from(root)?;
+        for segment in segments {
+            if cid.codec() != DAG_PB_CODEC {
+                return Err(anyhow!("`{}` in `{}` is not a directory", segment, link));
+            }
+            let node: pb::Node = prost::Message::decode(self.block(&cid)?)?;
+            let child = node
+                .links
+                .iter()
+                .find(|l| l.name.as_deref() == Some(segment))
+                .and_then(|l| l.hash.as_ref())
+                .ok_or_else(|| anyhow!("`{}` not found in `{}`", segment, link))?;
+            cid = Cid::try_from(child.as_slice())?;
+        }
+        Ok(cid)
+    }
+
+    /// Append the contents of the UnixFS file rooted at `cid` to `out`
+    fn read_file(&self, cid: &Cid, out: &mut Vec<u8>) -> Result<(), Error> {
+        let block = self.block(cid)?;
+        match cid.codec() {
+            RAW_CODEC => out.extend_from_slice(block),
+            DAG_PB_CODEC => {
+                let node: pb::Node = prost::Message::decode(block)?;
+                let unixfs: pb::UnixFsData =
+                    prost::Message::decode(node.data.as_deref().unwrap_or_default())?;
+                match unixfs.r#type {
+                    pb::UNIXFS_RAW | pb::UNIXFS_FILE => {}
+                    pb::UNIXFS_DIRECTORY => {
+                        return Err(anyhow!("{} is a directory, not a file", cid))
+                    }
+                    typ => return Err(anyhow!("{} has unsupported UnixFS type {}", cid, typ)),
+                }
+                if let Some(data) = &unixfs.data {
+                    out.extend_from_slice(data);
+                }
+                for link in &node.links {
+                    let hash = link
+                        .hash
+                        .as_ref()
+                        .ok_or_else(|| anyhow!("link without hash in block {}", cid))?;
+                    self.read_file(&Cid::try_from(hash.as_slice())?, out)?;
+                }
+            }
+            codec => return Err(anyhow!("block {} has unsupported codec 0x{:x}", cid, codec)),
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LinkResolver for CarResolver {
+    fn with_timeout(&self, _timeout: Duration) -> Box<dyn LinkResolver> {
+        Box::new(self.cheap_clone())
+    }
+
+    fn with_retries(&self) -> Box<dyn LinkResolver> {
+        Box::new(self.cheap_clone())
+    }
+
+    async fn cat(&self, logger: &Logger, link: &Link) -> Result<Vec<u8>, Error> {
+        trace!(logger, "CAR cat"; "link" => &link.link);
+        let cid = self.resolve_path(&link.link)?;
+        let mut data = Vec::new();
+        self.read_file(&cid, &mut data)?;
+        Ok(data)
+    }
+
+    async fn get_block(&self, logger: &Logger, link: &Link) -> Result<Vec<u8>, Error> {
+        trace!(logger, "CAR block get"; "link" => &link.link);
+        let cid = self.resolve_path(&link.link)?;
+        Ok(self.block(&cid)?.to_vec())
+    }
+
+    async fn json_stream(&self, logger: &Logger, link: &Link) -> Result<JsonValueStream, Error> {
+        let data = self.cat(logger, link).await?;
+        let values: Vec<_> = data
+            .split(|b| *b == b'\n')
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| {
+                serde_json::from_slice(line)
+                    .map(|value| JsonStreamValue { value, line: i + 1 })
+                    .map_err(|e| anyhow!("{} in line {} of {}", e, i + 1, link.link))
+            })
+            .collect();
+        Ok(Box::pin(stream::iter(values)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::Multihash;
+    use futures03::TryStreamExt;
+    use prost::Message;
+    use slog::o;
+
+    use super::*;
+
+    fn cid_for(codec: u64, data: &[u8]) -> Cid {
+        let hash = Multihash::<64>::wrap(SHA2_256_HASH, Sha256::digest(data).as_slice()).unwrap();
+        Cid::new_v1(codec, hash)
+    }
+
+    fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn car(blocks: &[(Cid, Vec<u8>)]) -> Vec<u8> {
+        // DAG-CBOR for `{ "roots": [], "version": 1 }`
+        let header = b"\xa2\x65roots\x80\x67version\x01";
+        let mut out = Vec::new();
+        write_varint(header.len() as u64, &mut out);
+        out.extend_from_slice(header);
+        for (cid, data) in blocks {
+            let cid = cid.to_bytes();
+            write_varint((cid.len() + data.len()) as u64, &mut out);
+            out.extend_from_slice(&cid);
+            out.extend_from_slice(data);
+        }
+        out
+    }
+
+    fn dag_pb(typ: i32, links: Vec<(&str, Cid)>) -> (Cid, Vec<u8>) {
+        let data = pb::UnixFsData {
+            r#type: typ,
+            data: None,
+            filesize: None,
+            blocksizes: vec![],
+        };
+        let node = pb::Node {
+            links: links
+                .into_iter()
+                .map(|(name, cid)| pb::Link {
+                    hash: Some(cid.to_bytes()),
+                    name: Some(name.to_string()),
+                    tsize: None,
+                })
+                .collect(),
+            data: Some(data.encode_to_vec()),
+        };
+        let bytes = node.encode_to_vec();
+        (cid_for(DAG_PB_CODEC, &bytes), bytes)
+    }
+
+    #[tokio::test]
+    async fn read_files_from_car() {
+        let logger = Logger::root(slog::Discard, o!());
+
+        let first = b"{\"a\": 1}\n".to_vec();
+        let second = b"{\"b\": 2}".to_vec();
+        let first_cid = cid_for(RAW_CODEC, &first);
+        let second_cid = cid_for(RAW_CODEC, &second);
+        let (file_cid, file) = dag_pb(pb::UNIXFS_FILE, vec![("", first_cid), ("", second_cid)]);
+        let (dir_cid, dir) = dag_pb(pb::UNIXFS_DIRECTORY, vec![("data.json", file_cid)]);
+
+        let resolver = CarResolver::from_bytes(&car(&[
+            (first_cid, first.clone()),
+            (second_cid, second),
+            (file_cid, file),
+            (dir_cid, dir),
+        ]))
+        .unwrap();
+
+        let link = Link {
+            link: format!("/ipfs/{}", first_cid),
+        };
+        assert_eq!(first, resolver.cat(&logger, &link).await.unwrap());
+
+        let link = Link {
+            link: format!("/ipfs/{}/data.json", dir_cid),
+        };
+        let data = resolver.cat(&logger, &link).await.unwrap();
+        assert_eq!(b"{\"a\": 1}\n{\"b\": 2}".to_vec(), data);
+
+        let values: Vec<_> = resolver
+            .json_stream(&logger, &link)
+            .await
+            .unwrap()
+            .map_ok(|value| value.value)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![serde_json::json!({"a": 1}), serde_json::json!({"b": 2})],
+            values
+        );
+
+        let link = Link {
+            link: dir_cid.to_string(),
+        };
+        assert!(resolver.cat(&logger, &link).await.is_err());
+
+        let link = Link {
+            link: cid_for(RAW_CODEC, b"missing").to_string(),
+        };
+        assert!(resolver.cat(&logger, &link).await.is_err());
+    }
+
+    #[test]
+    fn reject_corrupt_blocks() {
+        let data = b"the real thing".to_vec();
+        let cid = cid_for(RAW_CODEC, &data);
+        let err = CarResolver::from_bytes(&car(&[(cid, b"an impostor".to_vec())])).unwrap_err();
+        assert!(err.to_string().contains("do not match its CID"));
+    }
+}
@@ -9,12 +9,15 @@ use crate::util::futures::RetryConfigNoTimeout;
 use anyhow::anyhow;
 use async_trait::async_trait;
 use bytes::BytesMut;
+use cid::Cid;
 use futures03::compat::Stream01CompatExt;
 use futures03::future::TryFutureExt;
 use futures03::stream::{FuturesUnordered, StreamExt, TryStreamExt};
 use lru_time_cache::LruCache;
 use serde_json::Value;
 
+use super::car::verify;
+
 use crate::{
     cheap_clone::CheapClone,
     derive::CheapClone,
@@ -25,25 +28,35 @@ use crate::{
 
 fn retry_policy<I: Send + Sync>(
     always_retry: bool,
+    limit: Option<usize>,
     op: &'static str,
     logger: &Logger,
 ) -> RetryConfigNoTimeout<I, IpfsError> {
+    let policy = match limit {
+        Some(limit) => retry(op, logger).limit(limit),
+        None => retry(op, logger).no_limit(),
+    };
+
     // Even if retries were not requested, networking errors are still retried until we either get
     // a valid HTTP response or a timeout.
     if always_retry {
-        retry(op, logger).no_limit()
+        policy
     } else {
-        retry(op, logger)
-            .no_limit()
-            .when(|res: &Result<_, IpfsError>| match res {
-                Ok(_) => false,
-                Err(IpfsError::FileTooLarge(..)) => false,
-                Err(e) => !(e.is_status() || e.is_timeout()),
-            })
+        policy.when(|res: &Result<_, IpfsError>| match res {
+            Ok(_) => false,
+            Err(IpfsError::FileTooLarge(..)) | Err(IpfsError::CidMismatch(..)) => false,
+            Err(e) => !(e.is_status() || e.is_timeout()),
+        })
     }
     .no_timeout() // The timeout should be set in the internal future.
 }
 
+/// The order in which clients are tried: the client at index `first`,
+/// followed by all others in the order in which they were configured
+fn fallback_order(len: usize, first: usize) -> impl Iterator<Item = usize> {
+    std::iter::once(first).chain((0..len).filter(move |i| *i != first))
+}
+
 /// The IPFS APIs don't have a quick "do you have the file" function. Instead, we
 /// just rely on whether an API times out. That makes sense for IPFS, but not for
 /// our application. We want to be able to quickly select from a potential list
@@ -61,9 +74,9 @@ async fn select_fastest_client(
     path: String,
     timeout: Duration,
     do_retry: bool,
-) -> Result<IpfsClient, Error> {
+) -> Result<usize, Error> {
     if clients.len() == 1 {
-        return Ok(clients[0].cheap_clone());
+        return Ok(0);
     }
 
     let mut err: Option<Error> = None;
@@ -74,7 +87,7 @@ async fn select_fastest_client(
         .map(|(i, c)| {
             let c = c.cheap_clone();
             let path = path.clone();
-            retry_policy(do_retry, "IPFS exists", &logger).run(move || {
+            retry_policy(do_retry, None, "IPFS exists", &logger).run(move || {
                 let path = path.clone();
                 let c = c.cheap_clone();
                 async move { c.exists(&path, Some(timeout)).map_ok(|()| i).await }
@@ -85,7 +98,7 @@ async fn select_fastest_client(
     while let Some(result) = exists.next().await {
         match result {
             Ok(index) => {
-                return Ok(clients[index].cheap_clone());
+                return Ok(index);
             }
             Err(e) => err = Some(e.into()),
         }
@@ -120,6 +133,77 @@ impl IpfsResolver {
             env_vars,
         }
     }
+
+    /// Run `op` against the client that responded fastest for `path`, and
+    /// fall back to the other clients in the order in which they were
+    /// configured if that fails. With retries enabled, keep cycling
+    /// through the clients until one of them succeeds
+    async fn with_fallback<T, F, R>(
+        &self,
+        logger: &Logger,
+        op_name: &'static str,
+        path: &str,
+        op: F,
+    ) -> Result<T, Error>
+    where
+        T: Debug + Send + Sync + 'static,
+        F: Fn(IpfsClient) -> R + Clone + Send + Sync + 'static,
+        R: std::future::Future<Output = Result<T, IpfsError>> + Send + 'static,
+    {
+        let first = select_fastest_client(
+            self.clients.cheap_clone(),
+            logger.cheap_clone(),
+            path.to_string(),
+            self.timeout,
+            self.retry,
+        )
+        .await?;
+
+        // With a single client, there is nothing to fall back to, and we
+        // retry as long as the retry policy allows
+        let limit = if self.clients.len() > 1 {
+            Some(self.env_vars.mappings.ipfs_attempts_per_client)
+        } else {
+            None
+        };
+
+        loop {
+            let mut last_err = None;
+            for index in fallback_order(self.clients.len(), first) {
+                let client = self.clients[index].cheap_clone();
+                let op = op.clone();
+                let res = retry_policy(self.retry, limit, op_name, logger)
+                    .run({
+                        let client = client.cheap_clone();
+                        move || op(client.cheap_clone())
+                    })
+                    .await;
+                match res {
+                    Ok(data) => {
+                        debug!(logger, "IPFS request served";
+                            "op" => op_name,
+                            "path" => path,
+                            "client" => client.host());
+                        return Ok(data);
+                    }
+                    Err(e @ IpfsError::FileTooLarge(..)) => return Err(e.into()),
+                    Err(e) => {
+                        if self.clients.len() > 1 {
+                            warn!(logger, "IPFS request failed, trying the next client";
+                                "op" => op_name,
+                                "path" => path,
+                                "client" => client.host(),
+                                "error" => e.to_string());
+                        }
+                        last_err = Some(e);
+                    }
+                }
+            }
+            if !self.retry {
+                return Err(last_err.expect("there is at least one client").into());
+            }
+        }
+    }
 }
 
 impl Debug for IpfsResolver {
@@ -157,24 +241,14 @@ impl LinkResolverTrait for IpfsResolver {
         }
         trace!(logger, "IPFS cache miss"; "hash" => &path);
 
-        let client = select_fastest_client(
-            self.clients.cheap_clone(),
-            logger.cheap_clone(),
-            path.clone(),
-            self.timeout,
-            self.retry,
-        )
-        .await?;
-
         let max_cache_file_size = self.env_vars.mappings.max_ipfs_cache_file_size;
         let max_file_size = self.env_vars.mappings.max_ipfs_file_bytes;
 
         let req_path = path.clone();
         let timeout = self.timeout;
-        let data = retry_policy(self.retry, "ipfs.cat", logger)
-            .run(move || {
+        let data = self
+            .with_fallback(logger, "ipfs.cat", &path, move |client| {
                 let path = req_path.clone();
-                let client = client.clone();
                 async move {
                     Ok(client
                         .cat_all(&path, Some(timeout), max_file_size)
@@ -202,24 +276,24 @@ impl LinkResolverTrait for IpfsResolver {
 
     async fn get_block(&self, logger: &Logger, link: &Link) -> Result<Vec<u8>, Error> {
         trace!(logger, "IPFS block get"; "hash" => &link.link);
-        let client = select_fastest_client(
-            self.clients.cheap_clone(),
-            logger.cheap_clone(),
-            link.link.clone(),
-            self.timeout,
-            self.retry,
-        )
-        .await?;
+
+        // Blocks are small enough that we can check that a client sent us
+        // the right data and otherwise try another one. Files can't be
+        // checked that way since we would have to chunk them exactly like
+        // they were chunked when they were added
+        let cid = Cid::try_from(link.link.as_str()).ok();
 
         // Note: The IPFS protocol limits the size of blocks to 1MB, so we don't need to enforce size
         // limits here.
-        let link = link.link.clone();
-        let data = retry_policy(self.retry, "ipfs.getBlock", logger)
-            .run(move || {
-                let link = link.clone();
-                let client = client.clone();
+        let req_link = link.link.clone();
+        let data = self
+            .with_fallback(logger, "ipfs.getBlock", &link.link, move |client| {
+                let link = req_link.clone();
                 async move {
                     let data = client.get_block(link.clone()).await?.to_vec();
+                    if let Some(cid) = cid {
+                        verify(&cid, &data).map_err(|_| IpfsError::CidMismatch(link))?;
+                    }
                     Result::<Vec<u8>, _>::Ok(data)
                 }
             })
@@ -232,7 +306,7 @@ impl LinkResolverTrait for IpfsResolver {
         // Discard the `/ipfs/` prefix (if present) to get the hash.
         let path = link.link.trim_start_matches("/ipfs/").to_string();
 
-        let client = select_fastest_client(
+        let index = select_fastest_client(
             self.clients.cheap_clone(),
             logger.cheap_clone(),
             path.to_string(),
@@ -240,6 +314,7 @@ impl LinkResolverTrait for IpfsResolver {
             self.retry,
         )
         .await?;
+        let client = self.clients[index].cheap_clone();
 
         let max_file_size = self.env_vars.mappings.max_ipfs_map_file_size;
         let mut cummulative_file_size = 0;
@@ -352,6 +427,31 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn skips_unavailable_clients() {
+        assert_eq!(vec![1, 0, 2], fallback_order(3, 1).collect::<Vec<_>>());
+
+        let client = IpfsClient::localhost();
+        let unavailable = IpfsClient::new("http://localhost:1").unwrap();
+        let resolver = super::IpfsResolver::new(
+            vec![unavailable, client.clone()],
+            Arc::new(EnvVars::default()),
+        );
+
+        let logger = Logger::root(slog::Discard, o!());
+
+        let text = "served by the second client";
+        let link = client.add(text.as_bytes().into()).await.unwrap().hash;
+        let link = Link { link };
+        let data = IpfsResolver::cat(&resolver, &logger, &link).await.unwrap();
+        assert_eq!(text.as_bytes(), data.as_slice());
+
+        let block = IpfsResolver::get_block(&resolver, &logger, &link)
+            .await
+            .unwrap();
+        assert!(!block.is_empty());
+    }
+
     async fn json_round_trip(text: &'static str, env_vars: EnvVars) -> Result<Vec<Value>, Error> {
         let client = IpfsClient::localhost();
         let resolver = super::IpfsResolver::new(vec![client.clone()], Arc::new(env_vars));
@@ -19,6 +19,9 @@ pub struct SubgraphInstanceMetrics {
     trigger_processing_duration: Box<Histogram>,
     blocks_processed_secs: Box<Counter>,
     blocks_processed_count: Box<Counter>,
+    block_entity_count: Box<Histogram>,
+    block_entity_cache_size: Box<Histogram>,
+    block_entity_cache_size_max: Gauge,
 }
 
 impl SubgraphInstanceMetrics {
@@ -60,6 +63,30 @@ impl SubgraphInstanceMetrics {
             )
             .expect("failed to create `deployment_transact_block_operations_duration_{}");
 
+        let block_entity_count = registry
+            .new_deployment_histogram(
+                "deployment_block_entity_count",
+                "Measures the number of entities changed in each block for a subgraph deployment",
+                subgraph_hash,
+                vec![1.0, 10.0, 100.0, 1_000.0, 10_000.0, 100_000.0],
+            )
+            .expect("failed to create `deployment_block_entity_count` histogram");
+        let block_entity_cache_size = registry
+            .new_deployment_histogram(
+                "deployment_block_entity_cache_size",
+                "Measures the size in bytes of the entity changes that each block accumulates in the entity cache",
+                subgraph_hash,
+                vec![1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9],
+            )
+            .expect("failed to create `deployment_block_entity_cache_size` histogram");
+        let block_entity_cache_size_max = registry
+            .new_deployment_gauge(
+                "deployment_block_entity_cache_size_max",
+                "The largest size in bytes of the entity changes of a block since the deployment was started",
+                subgraph_hash,
+            )
+            .expect("failed to create `deployment_block_entity_cache_size_max` gauge");
+
         let firehose_connection_errors = registry
             .new_deployment_counter(
                 "firehose_connection_errors",
@@ -95,6 +122,19 @@ impl SubgraphInstanceMetrics {
             stopwatch,
             blocks_processed_secs,
             blocks_processed_count,
+            block_entity_count,
+            block_entity_cache_size,
+            block_entity_cache_size_max,
+        }
+    }
+
+    /// Record how many entities a block changed and how much space those
+    /// changes took up in the entity cache
+    pub fn observe_block_entity_cache(&self, entity_count: usize, entity_weight: usize) {
+        self.block_entity_count.observe(entity_count as f64);
+        self.block_entity_cache_size.observe(entity_weight as f64);
+        if entity_weight as f64 > self.block_entity_cache_size_max.get() {
+            self.block_entity_cache_size_max.set(entity_weight as f64);
         }
     }
 
@@ -114,6 +154,9 @@ impl SubgraphInstanceMetrics {
         registry.unregister(self.block_trigger_count.clone());
         registry.unregister(self.trigger_processing_duration.clone());
         registry.unregister(self.block_ops_transaction_duration.clone());
+        registry.unregister(self.block_entity_count.clone());
+        registry.unregister(self.block_entity_cache_size.clone());
+        registry.unregister(Box::new(self.block_entity_cache_size_max.clone()));
     }
 }
 
@@ -1,8 +1,10 @@
+use http_body_util::combinators::UnsyncBoxBody;
 use http_body_util::Full;
 use hyper::body::Bytes;
 use hyper::Response;
 
 use crate::data::query::QueryError;
+use std::convert::Infallible;
 use std::error::Error;
 use std::fmt;
 
@@ -10,6 +12,9 @@ use crate::components::store::StoreError;
 
 pub type ServerResponse = Response<Full<Bytes>>;
 pub type ServerResult = Result<ServerResponse, ServerError>;
+/// A response whose body might be streamed to the client rather than sent
+/// in one piece
+pub type StreamingServerResponse = Response<UnsyncBoxBody<Bytes, Infallible>>;
 
 /// Errors that can occur while processing incoming requests.
 #[derive(Debug)]
@@ -1,10 +1,11 @@
 use std::future::Future;
 use std::net::SocketAddr;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use hyper::body::Incoming;
-use hyper::Request;
+use hyper::body::{Body, Incoming};
+use hyper::{Request, Response};
 
 use crate::cheap_clone::CheapClone;
 use crate::hyper::server::conn::http1;
@@ -12,12 +13,71 @@ use crate::hyper::service::service_fn;
 use crate::hyper_util::rt::TokioIo;
 use crate::slog::error;
 use crate::tokio::net::TcpListener;
+use crate::tokio::sync::watch;
 use crate::tokio::task::JoinHandle;
 use crate::{anyhow, tokio};
+use lazy_static::lazy_static;
 
 use crate::prelude::Logger;
 
-use super::query::ServerResult;
+use super::query::ServerError;
+
+lazy_static! {
+    /// Used to shut down all servers started with `start` gracefully
+    pub static ref DRAIN: Drain = Drain::new();
+}
+
+/// Keeps track of the connections that servers started with `start` have
+/// open so that we can stop accepting new connections and wait for the
+/// open ones to finish when the node shuts down
+pub struct Drain {
+    sender: watch::Sender<bool>,
+    connections: AtomicUsize,
+}
+
+impl Drain {
+    fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Drain {
+            sender,
+            connections: AtomicUsize::new(0),
+        }
+    }
+
+    fn receiver(&self) -> watch::Receiver<bool> {
+        self.sender.subscribe()
+    }
+
+    fn connection(&'static self) -> ConnectionGuard {
+        self.connections.fetch_add(1, Ordering::SeqCst);
+        ConnectionGuard(self)
+    }
+
+    /// Stop accepting new connections and ask open connections to close
+    /// once the request they are serving has been answered. Wait at most
+    /// `timeout` for all connections to close and return how many are
+    /// still open after that
+    pub async fn drain(&self, timeout: Duration) -> usize {
+        self.sender.send_replace(true);
+
+        let start = Instant::now();
+        loop {
+            let open = self.connections.load(Ordering::SeqCst);
+            if open == 0 || start.elapsed() >= timeout {
+                return open;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+struct ConnectionGuard(&'static Drain);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
 
 /// A handle to the server that can be used to shut it down. The `accepting`
 /// field is only used in tests to check if the server is running
@@ -26,23 +86,52 @@ pub struct ServerHandle {
     pub accepting: Arc<AtomicBool>,
 }
 
-pub async fn start<F, S>(
+/// Serve requests on `port` with `handler`. Most handlers respond with a
+/// `ServerResponse`, but handlers that need to stream their response can
+/// use any other body type
+pub async fn start<F, S, B>(
     logger: Logger,
     port: u16,
     handler: F,
 ) -> Result<ServerHandle, anyhow::Error>
 where
     F: Fn(Request<Incoming>) -> S + Send + Clone + 'static,
-    S: Future<Output = ServerResult> + Send + 'static,
+    S: Future<Output = Result<Response<B>, ServerError>> + Send + 'static,
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
 {
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     let listener = TcpListener::bind(addr).await?;
+    Ok(serve(logger, listener, handler, &DRAIN))
+}
+
+/// Serve the connections that `listener` accepts with `handler` until
+/// `drain` is drained
+fn serve<F, S, B>(
+    logger: Logger,
+    listener: TcpListener,
+    handler: F,
+    drain: &'static Drain,
+) -> ServerHandle
+where
+    F: Fn(Request<Incoming>) -> S + Send + Clone + 'static,
+    S: Future<Output = Result<Response<B>, ServerError>> + Send + 'static,
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
     let accepting = Arc::new(AtomicBool::new(false));
     let accepting2 = accepting.cheap_clone();
     let handle = crate::spawn(async move {
         accepting2.store(true, std::sync::atomic::Ordering::SeqCst);
+        let mut drained = drain.receiver();
         loop {
-            let (stream, _) = match listener.accept().await {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = drained.changed() => break,
+            };
+            let (stream, _) = match accepted {
                 Ok(res) => res,
                 Err(e) => {
                     error!(logger, "Error accepting connection"; "error" => e.to_string());
@@ -55,16 +144,97 @@ where
             let io = TokioIo::new(stream);
 
             let handler = handler.clone();
+            let mut closing = drain.receiver();
+            let guard = drain.connection();
             // Spawn a tokio task to serve multiple connections concurrently
             tokio::task::spawn(async move {
+                let _guard = guard;
                 let new_service = service_fn(handler);
                 // Finally, we bind the incoming connection to our `hello` service
-                http1::Builder::new()
+                let conn = http1::Builder::new()
                     // `service_fn` converts our function in a `Service`
-                    .serve_connection(io, new_service)
-                    .await
+                    .serve_connection(io, new_service);
+                tokio::pin!(conn);
+                tokio::select! {
+                    res = conn.as_mut() => res,
+                    _ = closing.changed() => {
+                        // Finish the request that is in flight, if any,
+                        // and then close the connection
+                        conn.as_mut().graceful_shutdown();
+                        conn.await
+                    }
+                }
             });
         }
     });
-    Ok(ServerHandle { handle, accepting })
+    ServerHandle { handle, accepting }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use http_body_util::Full;
+    use hyper::body::{Bytes, Incoming};
+    use hyper::{Request, Response};
+
+    use crate::log::discard;
+    use crate::tokio::net::TcpListener;
+    use crate::tokio::sync::{watch, Notify};
+
+    use super::{serve, Drain, ServerError};
+
+    /// Draining lets a request that is in flight finish, closes its
+    /// connection afterwards and refuses new connections
+    #[tokio::test]
+    async fn drain_finishes_requests_and_refuses_connections() {
+        let drain: &'static Drain = Box::leak(Box::new(Drain::new()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let arrived = Arc::new(Notify::new());
+        let (release, released) = watch::channel(false);
+        let arrived2 = arrived.clone();
+        let handler = move |_: Request<Incoming>| {
+            let arrived = arrived2.clone();
+            let mut released = released.clone();
+            async move {
+                arrived.notify_one();
+                released.wait_for(|released| *released).await.unwrap();
+                Ok::<_, ServerError>(Response::new(Full::new(Bytes::from("done"))))
+            }
+        };
+        let server = serve(discard(), listener, handler, drain);
+
+        // Reading to the end only finishes once the server closes the
+        // connection
+        let client = std::thread::spawn(move || {
+            let mut stream = std::net::TcpStream::connect(addr).unwrap();
+            stream
+                .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        arrived.notified().await;
+
+        let drained = tokio::spawn(async move { drain.drain(Duration::from_secs(10)).await });
+        server.handle.await.unwrap();
+        assert!(
+            std::net::TcpStream::connect(addr).is_err(),
+            "new connections are refused"
+        );
+        assert!(!drained.is_finished(), "drain waits for the open request");
+
+        release.send(true).unwrap();
+        let response = tokio::task::spawn_blocking(move || client.join().unwrap())
+            .await
+            .unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("done"), "{response}");
+        assert_eq!(0, drained.await.unwrap());
+    }
 }
@@ -1,6 +1,6 @@
 use anyhow::anyhow;
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self, Debug};
 use std::sync::Arc;
 
@@ -9,7 +9,8 @@ use crate::components::store::write::EntityModification;
 use crate::components::store::{self as s, Entity, EntityOperation};
 use crate::data::store::{EntityValidationError, Id, IdType, IntoEntityIterator};
 use crate::prelude::ENV_VARS;
-use crate::schema::{EntityKey, InputSchema};
+use crate::schema::{EntityKey, EntityType, InputSchema};
+use crate::util::cache_weight::CacheWeight;
 use crate::util::intern::Error as InternError;
 use crate::util::lfu_cache::{EvictStats, LfuCache};
 
@@ -21,7 +22,11 @@ pub type EntityLfuCache = LfuCache<EntityKey, Option<Arc<Entity>>>;
 pub enum GetScope {
     /// Get from all previously stored entities in the store
     Store,
-    /// Get from the entities that have been stored during this block
+    /// Get from the entities that have been stored during this block.
+    /// Entities that were not changed in this block are not visible, and
+    /// entities that were removed in this block are returned as `None`.
+    /// This scope has no influence on `load_related`, which always
+    /// considers both the store and the changes made in this block
     InBlock,
 }
 
@@ -34,6 +39,13 @@ enum EntityOp {
 }
 
 impl EntityOp {
+    fn weight(&self) -> usize {
+        match self {
+            EntityOp::Remove => 0,
+            EntityOp::Update(entity) | EntityOp::Overwrite(entity) => entity.weight(),
+        }
+    }
+
     fn apply_to<E: Borrow<Entity>>(
         self,
         entity: &Option<E>,
@@ -120,6 +132,11 @@ pub struct ModificationsAndCache {
     pub modifications: Vec<s::EntityModification>,
     pub entity_lfu_cache: EntityLfuCache,
     pub evict_stats: EvictStats,
+    /// The number of entities that were changed in the block
+    pub entity_count: usize,
+    /// The weight of the changes that accumulated in the cache during the
+    /// block, in bytes
+    pub entity_weight: usize,
 }
 
 impl EntityCache {
@@ -193,7 +210,20 @@ impl EntityCache {
                 // Unwrap: we just inserted the entity
                 self.current.get(key).unwrap().cheap_clone()
             }
-            GetScope::InBlock => None,
+            GetScope::InBlock => {
+                let first_op = self
+                    .updates
+                    .get(key)
+                    .or_else(|| self.handler_updates.get(key));
+                match first_op {
+                    None => return Ok(None),
+                    // An `Update` only contains the attributes that were
+                    // set in this block and needs to be applied to the
+                    // entity in the store to produce the whole entity
+                    Some(EntityOp::Update(_)) => return self.get(key, GetScope::Store),
+                    Some(EntityOp::Remove) | Some(EntityOp::Overwrite(_)) => None,
+                }
+            }
         };
 
         // Always test the cache consistency in debug mode. The test only
@@ -218,20 +248,58 @@ impl EntityCache {
         Ok(entity)
     }
 
+    /// Load the entities that the derived field `eref.entity_field`
+    /// refers to. If the field has an interface type, the entities of all
+    /// types implementing the interface are returned. Entities are ordered
+    /// by id, and entities of different types with the same id in the
+    /// order in which their types implement the interface
     pub fn load_related(
         &mut self,
         eref: &LoadRelatedRequest,
     ) -> Result<Vec<Entity>, anyhow::Error> {
-        let (entity_type, field) = self.schema.get_field_related(eref)?;
-
-        let query = DerivedEntityQuery {
-            entity_type,
-            entity_field: field.name.clone().into(),
-            value: eref.entity_id.clone(),
-            causality_region: eref.causality_region,
-        };
+        let queries: Vec<_> = self
+            .schema
+            .get_field_related(eref)?
+            .into_iter()
+            .map(|(entity_type, field)| DerivedEntityQuery {
+                entity_type,
+                entity_field: field.name.clone().into(),
+                value: eref.entity_id.clone(),
+                causality_region: eref.causality_region,
+            })
+            .collect();
+
+        // When the field is derived from several fields, there is one
+        // query for each of them, and an entity is only related if all the
+        // queries for its type find it
+        let mut queries_per_type: HashMap<&EntityType, usize> = HashMap::new();
+        for query in &queries {
+            *queries_per_type.entry(&query.entity_type).or_default() += 1;
+        }
+        let mut found: HashMap<EntityKey, usize> = HashMap::new();
+        let mut entities = Vec::new();
+        for query in &queries {
+            for (key, entity) in self.load_derived(query)? {
+                let count = found.entry(key).or_default();
+                *count += 1;
+                if *count == queries_per_type[&query.entity_type] {
+                    entities.push(entity);
+                }
+            }
+        }
+        // The sort is stable, so entities of different types with the
+        // same id stay in the order of their types
+        entities.sort_by_key(|entity| entity.id());
+        Ok(entities)
+    }
 
-        let mut entity_map = self.store.get_derived(&query)?;
+    /// Load the entities of type `query.entity_type` that `query` matches,
+    /// taking pending changes into account
+    fn load_derived(
+        &mut self,
+        query: &DerivedEntityQuery,
+    ) -> Result<BTreeMap<EntityKey, Entity>, anyhow::Error> {
+        let mut entity_map = self.store.get_derived(query)?;
 
         for (key, entity) in entity_map.iter() {
             // Only insert to the cache if it's not already there
@@ -295,7 +363,7 @@ impl EntityCache {
         // - Add the entity to entity_map.
         for (key, op) in self.updates.iter() {
             if !entity_map.contains_key(key) {
-                if let Some(entity) = matches_query(op, &query, key)? {
+                if let Some(entity) = matches_query(op, query, key)? {
                     if let Some(handler_op) = self.handler_updates.get(key).cloned() {
                         // If there's a corresponding update in handler_updates, apply it to the entity
                         // and insert the updated entity into entity_map
@@ -322,7 +390,7 @@ impl EntityCache {
         // If these conditions are met, add the entity to entity_map.
         for (key, handler_op) in self.handler_updates.iter() {
             if !entity_map.contains_key(key) && !self.updates.contains_key(key) {
-                if let Some(entity) = matches_query(handler_op, &query, key)? {
+                if let Some(entity) = matches_query(handler_op, query, key)? {
                     entity_map.insert(key.clone(), entity);
                 }
             }
@@ -337,7 +405,7 @@ impl EntityCache {
             entity_map.remove(&key);
         }
 
-        Ok(entity_map.into_values().collect())
+        Ok(entity_map)
     }
 
     pub fn remove(&mut self, key: EntityKey) {
@@ -429,6 +497,13 @@ impl EntityCache {
     ) -> Result<ModificationsAndCache, StoreError> {
         assert!(!self.in_handler);
 
+        let entity_count = self.updates.len();
+        let entity_weight = self
+            .updates
+            .iter()
+            .map(|(key, op)| key.weight() + op.weight())
+            .sum();
+
         // The first step is to make sure all entities being set are in `self.current`.
         // For each subgraph, we need a map of entity type to missing entity ids.
         let missing = self
@@ -521,6 +596,8 @@ impl EntityCache {
             modifications: mods,
             entity_lfu_cache: self.current,
             evict_stats,
+            entity_count,
+            entity_weight,
         })
     }
 }
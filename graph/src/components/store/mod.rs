@@ -30,7 +30,7 @@ use crate::cheap_clone::CheapClone;
 use crate::components::store::write::EntityModification;
 use crate::constraint_violation;
 use crate::data::store::scalar::Bytes;
-use crate::data::store::{Id, IdList, Value};
+use crate::data::store::{Id, IdList, QueryObject, Value, ID};
 use crate::data::value::Word;
 use crate::data_source::CausalityRegion;
 use crate::derive::CheapClone;
@@ -100,6 +100,9 @@ impl DerivedEntityQuery {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Child {
     pub attr: Attribute,
+    /// For fields that are derived from several fields, the other child
+    /// attributes that must reference the parent, too. Empty otherwise
+    pub more_attrs: Vec<Attribute>,
     pub entity_type: EntityType,
     pub filter: Box<EntityFilter>,
     pub derived: bool,
@@ -172,10 +175,14 @@ impl fmt::Display for EntityFilter {
             NotEndsWith(a, v) => write!(f, "{a} !~ *{v}$"),
             NotEndsWithNoCase(a, v) => write!(f, "{a} !~ *{v}$i"),
             ChangeBlockGte(b) => write!(f, "block >= {b}"),
-            Child(child /* a, et, cf, _ */) => write!(
+            Child(child /* a, ma, et, cf, _ */) => write!(
                 f,
                 "join on {} with {}({})",
-                child.attr, child.entity_type, child.filter
+                std::iter::once(&child.attr)
+                    .chain(child.more_attrs.iter())
+                    .join(" and "),
+                child.entity_type,
+                child.filter
             ),
         }
     }
@@ -259,6 +266,60 @@ pub enum EntityOrder {
     Unordered,
 }
 
+impl EntityOrder {
+    /// Return a filter that matches the entities that come after the
+    /// entity with id `id` and the value `value` for the attribute that
+    /// `self` orders by. This is what keyset pagination uses instead of
+    /// skipping rows. For `EntityOrder::Default` and for ordering by `id`,
+    /// `value` is ignored. Orders by a child entity and unordered queries
+    /// can not be expressed that way, and we return `None` for them
+    ///
+    /// Ties are broken by `id` in the same direction as the order, and
+    /// `null` values come last in ascending and first in descending order
+    pub fn keyset_filter(&self, value: Value, id: Value) -> Option<EntityFilter> {
+        use EntityFilter as f;
+
+        let (attr, ascending) = match self {
+            EntityOrder::Ascending(attr, _) => (attr, true),
+            EntityOrder::Descending(attr, _) => (attr, false),
+            EntityOrder::Default => return Some(f::GreaterThan(ID.to_string(), id)),
+            EntityOrder::ChildAscending(_)
+            | EntityOrder::ChildDescending(_)
+            | EntityOrder::Unordered => return None,
+        };
+        if attr == ID.as_str() {
+            return Some(if ascending {
+                f::GreaterThan(attr.clone(), id)
+            } else {
+                f::LessThan(attr.clone(), id)
+            });
+        }
+
+        let id_after = if ascending {
+            f::GreaterThan(ID.to_string(), id)
+        } else {
+            f::LessThan(ID.to_string(), id)
+        };
+        let filter = match (value, ascending) {
+            (Value::Null, true) => f::And(vec![f::Equal(attr.clone(), Value::Null), id_after]),
+            (Value::Null, false) => f::Or(vec![
+                f::And(vec![f::Equal(attr.clone(), Value::Null), id_after]),
+                f::Not(attr.clone(), Value::Null),
+            ]),
+            (value, true) => f::Or(vec![
+                f::GreaterThan(attr.clone(), value.clone()),
+                f::And(vec![f::Equal(attr.clone(), value), id_after]),
+                f::Equal(attr.clone(), Value::Null),
+            ]),
+            (value, false) => f::Or(vec![
+                f::LessThan(attr.clone(), value.clone()),
+                f::And(vec![f::Equal(attr.clone(), value), id_after]),
+            ]),
+        };
+        Some(filter)
+    }
+}
+
 /// How many entities to return, how many to skip etc.
 #[derive(Clone, Debug, PartialEq)]
 pub struct EntityRange {
@@ -349,6 +410,9 @@ impl ChildMultiplicity {
 pub enum EntityLink {
     /// The parent id is stored in this child attribute
     Direct(WindowAttribute, ChildMultiplicity),
+    /// The parent id is stored in all of these child attributes. This is
+    /// used for fields that are derived from several fields
+    Composite(Vec<WindowAttribute>, ChildMultiplicity),
     /// Join with the parents table to get at the parent id
     Parent(EntityType, ParentLink),
 }
@@ -423,6 +487,16 @@ pub type BlockNumber = i32;
 
 pub const BLOCK_NUMBER_MAX: BlockNumber = std::i32::MAX;
 
+/// One version of an entity, together with the range of blocks for which
+/// it was current. `block_end` is `None` if the version was still current
+/// at the block at which the history was queried
+#[derive(Clone, Debug)]
+pub struct EntityVersion {
+    pub block_start: BlockNumber,
+    pub block_end: Option<BlockNumber>,
+    pub object: QueryObject,
+}
+
 /// A query for entities in a store.
 ///
 /// Details of how query generation for `EntityQuery` works can be found
@@ -1102,6 +1176,10 @@ pub trait PruneReporter: Send + 'static {
     /// actually analyzed
     fn finish_analyze(&mut self, stats: &[VersionStats], analyzed: &[&str]) {}
 
+    /// Pruning will work on `tables`; `resumed` are the tables that an
+    /// earlier, interrupted run already pruned and that will be skipped
+    fn start_tables(&mut self, tables: &[&str], resumed: &[&str]) {}
+
     fn start_table(&mut self, table: &str) {}
     fn prune_batch(&mut self, table: &str, rows: usize, phase: PrunePhase, finished: bool) {}
     fn start_switch(&mut self) {}
@@ -1266,3 +1344,69 @@ pub struct CachedEthereumCall {
     /// The encoded return value of this call.
     pub return_value: Vec<u8>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{EntityFilter as f, EntityOrder, Value, ValueType};
+
+    #[test]
+    fn keyset_filter() {
+        let name = || "name".to_string();
+        let id = || Value::from("t2");
+
+        assert_eq!(
+            Some(f::GreaterThan("id".to_string(), id())),
+            EntityOrder::Default.keyset_filter(Value::Null, id())
+        );
+        assert_eq!(
+            Some(f::LessThan("id".to_string(), id())),
+            EntityOrder::Descending("id".to_string(), ValueType::String).keyset_filter(id(), id())
+        );
+        assert_eq!(
+            None,
+            EntityOrder::Unordered.keyset_filter(Value::Null, id())
+        );
+
+        let asc = EntityOrder::Ascending(name(), ValueType::String);
+        assert_eq!(
+            Some(f::Or(vec![
+                f::GreaterThan(name(), Value::from("b")),
+                f::And(vec![
+                    f::Equal(name(), Value::from("b")),
+                    f::GreaterThan("id".to_string(), id())
+                ]),
+                f::Equal(name(), Value::Null),
+            ])),
+            asc.keyset_filter(Value::from("b"), id())
+        );
+        assert_eq!(
+            Some(f::And(vec![
+                f::Equal(name(), Value::Null),
+                f::GreaterThan("id".to_string(), id())
+            ])),
+            asc.keyset_filter(Value::Null, id())
+        );
+
+        let desc = EntityOrder::Descending(name(), ValueType::String);
+        assert_eq!(
+            Some(f::Or(vec![
+                f::LessThan(name(), Value::from("b")),
+                f::And(vec![
+                    f::Equal(name(), Value::from("b")),
+                    f::LessThan("id".to_string(), id())
+                ]),
+            ])),
+            desc.keyset_filter(Value::from("b"), id())
+        );
+        assert_eq!(
+            Some(f::Or(vec![
+                f::And(vec![
+                    f::Equal(name(), Value::Null),
+                    f::LessThan("id".to_string(), id())
+                ]),
+                f::Not(name(), Value::Null),
+            ])),
+            desc.keyset_filter(Value::Null, id())
+        );
+    }
+}
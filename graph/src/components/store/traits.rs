@@ -575,6 +575,18 @@ pub trait QueryStore: Send + Sync {
         query: EntityQuery,
     ) -> Result<(Vec<QueryObject>, Trace), QueryExecutionError>;
 
+    /// Find the versions of the entity `entity_type[id]` that were created
+    /// at or before `block`, ordered by the block at which they were created.
+    /// Skips the first `skip` versions and returns at most `first`
+    fn find_history(
+        &self,
+        entity_type: &EntityType,
+        id: &Id,
+        block: BlockNumber,
+        first: u32,
+        skip: u32,
+    ) -> Result<Vec<EntityVersion>, QueryExecutionError>;
+
     async fn is_deployment_synced(&self) -> Result<bool, Error>;
 
     async fn block_ptr(&self) -> Result<Option<BlockPtr>, StoreError>;
@@ -587,6 +599,11 @@ pub trait QueryStore: Send + Sync {
         block_hashes: Vec<BlockHash>,
     ) -> Result<HashMap<BlockHash, BlockNumber>, StoreError>;
 
+    /// Check whether `block` is on the chain that ends in `head`, i.e.,
+    /// whether it is `head` or one of its ancestors. Fail if the store does
+    /// not have enough blocks to tell
+    async fn is_ancestor(&self, head: &BlockPtr, block: &BlockPtr) -> Result<bool, StoreError>;
+
     /// Returns the blocknumber, timestamp and the parentHash. Timestamp depends on the chain block type
     /// and can have multiple formats, it can also not be prevent. For now this is only available
     /// for EVM chains both firehose and rpc.
@@ -627,6 +644,9 @@ pub trait StatusStore: Send + Sync + 'static {
 
     fn status(&self, filter: status::Filter) -> Result<Vec<status::Info>, StoreError>;
 
+    /// The current state of the connection pools of all database shards
+    fn pool_status(&self) -> Vec<status::PoolStatus>;
+
     /// Support for the explorer-specific API
     fn version_info(&self, version_id: &str) -> Result<VersionInfo, StoreError>;
 
@@ -13,6 +13,7 @@ use crate::data_source::{
     DataSource, DataSourceTemplate, MappingTrigger, TriggerData, TriggerWithHandler,
 };
 use crate::prelude::*;
+use crate::runtime::gas::Gas;
 use crate::runtime::HostExportError;
 use crate::{blockchain::Blockchain, components::subgraph::SharedProofOfIndexing};
 
@@ -112,6 +113,8 @@ pub trait RuntimeHost<C: Blockchain>: Send + Sync + 'static {
 
 pub struct HostMetrics {
     handler_execution_time: Box<HistogramVec>,
+    handler_gas_used: Box<HistogramVec>,
+    handler_memory_peak: Box<HistogramVec>,
     host_fn_execution_time: Box<HistogramVec>,
     eth_call_execution_time: Box<HistogramVec>,
     pub gas_metrics: GasMetrics,
@@ -134,6 +137,36 @@ impl HostMetrics {
                 vec![0.1, 0.5, 1.0, 10.0, 100.0],
             )
             .expect("failed to create `deployment_handler_execution_time` histogram");
+        // Bucket boundaries are fractions of the gas limit so that the
+        // histogram shows how close handlers come to running out of gas
+        let max_gas = ENV_VARS.max_gas_per_handler as f64;
+        let handler_gas_used = registry
+            .new_deployment_histogram_vec(
+                "deployment_handler_gas_used",
+                "Measures the gas used by handlers",
+                subgraph,
+                vec![String::from("handler")],
+                [0.0001, 0.001, 0.01, 0.1, 0.5, 1.0]
+                    .iter()
+                    .map(|fraction| fraction * max_gas)
+                    .collect(),
+            )
+            .expect("failed to create `deployment_handler_gas_used` histogram");
+        // WASM memory only ever grows, and every handler runs in a fresh
+        // instance, so the size of the memory at the end of a handler is
+        // the most it used
+        let handler_memory_peak = registry
+            .new_deployment_histogram_vec(
+                "deployment_handler_memory_peak",
+                "Measures the peak WASM memory in bytes used by handlers",
+                subgraph,
+                vec![String::from("handler")],
+                [1, 16, 64, 256, 1024, 4096]
+                    .iter()
+                    .map(|mb| (mb << 20) as f64)
+                    .collect(),
+            )
+            .expect("failed to create `deployment_handler_memory_peak` histogram");
         let eth_call_execution_time = registry
             .new_deployment_histogram_vec(
                 "deployment_eth_call_execution_time",
@@ -155,6 +188,8 @@ impl HostMetrics {
             .expect("failed to create `deployment_host_fn_execution_time` histogram");
         Self {
             handler_execution_time,
+            handler_gas_used,
+            handler_memory_peak,
             host_fn_execution_time,
             stopwatch,
             gas_metrics,
@@ -168,6 +203,26 @@ impl HostMetrics {
             .observe(duration);
     }
 
+    pub fn observe_handler_gas_used(&self, gas: Gas, handler: &str) {
+        self.handler_gas_used
+            .with_label_values(&[handler][..])
+            .observe(gas.0 as f64);
+    }
+
+    /// The number of times that gas was recorded for `handler`, and the
+    /// sum of the gas
+    #[cfg(debug_assertions)]
+    pub fn handler_gas_used(&self, handler: &str) -> (u64, f64) {
+        let histogram = self.handler_gas_used.with_label_values(&[handler][..]);
+        (histogram.get_sample_count(), histogram.get_sample_sum())
+    }
+
+    pub fn observe_handler_memory_peak(&self, bytes: usize, handler: &str) {
+        self.handler_memory_peak
+            .with_label_values(&[handler][..])
+            .observe(bytes as f64);
+    }
+
     pub fn observe_host_fn_execution_time(&self, duration: f64, fn_name: &str) {
         self.host_fn_execution_time
             .with_label_values(&[fn_name][..])
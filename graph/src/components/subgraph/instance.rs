@@ -80,6 +80,9 @@ pub struct BlockState {
     // Marks whether a handler is currently executing.
     in_handler: bool,
 
+    // The number of values handed out by `crypto.random` in this block.
+    random_calls: u64,
+
     pub metrics: BlockStateMetrics,
 }
 
@@ -93,6 +96,7 @@ impl BlockState {
             handler_created_data_sources: Vec::new(),
             processed_data_sources: Vec::new(),
             in_handler: false,
+            random_calls: 0,
             metrics: BlockStateMetrics::new(),
         }
     }
@@ -110,6 +114,7 @@ impl BlockState {
             handler_created_data_sources,
             processed_data_sources,
             in_handler,
+            random_calls,
             metrics,
         } = self;
 
@@ -121,6 +126,9 @@ impl BlockState {
         entity_cache.extend(other.entity_cache);
         processed_data_sources.extend(other.processed_data_sources);
         persisted_data_sources.extend(other.persisted_data_sources);
+        // `other` was seeded from this state with `continue_random_calls`
+        // and can only have counted further
+        *random_calls = (*random_calls).max(other.random_calls);
         metrics.extend(other.metrics)
     }
 
@@ -178,4 +186,20 @@ impl BlockState {
     pub fn persist_data_source(&mut self, ds: StoredDynamicDataSource) {
         self.persisted_data_sources.push(ds)
     }
+
+    /// Return how many random values have been produced in this block so
+    /// far and count one more. Handlers run in the same order on every
+    /// node, which makes the counter deterministic
+    pub fn next_random_call(&mut self) -> u64 {
+        let n = self.random_calls;
+        self.random_calls += 1;
+        n
+    }
+
+    /// Continue counting `crypto.random` calls from where `other` is. Used
+    /// for states that will later be merged into `other` with `extend` so
+    /// that they do not hand out the values `other` already produced
+    pub fn continue_random_calls(&mut self, other: &BlockState) {
+        self.random_calls = other.random_calls;
+    }
 }
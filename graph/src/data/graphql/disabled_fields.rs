@@ -0,0 +1,309 @@
+//! Per-deployment configuration of API fields that should not be served
+//!
+//! The configuration is read from the TOML file given by
+//! `GRAPH_GRAPHQL_DISABLED_FIELDS_FILE`, which has one table per
+//! deployment:
+//!
+//! ```toml
+//! [QmXYZ]
+//! meta = false
+//! fulltext = false
+//! fields = [ "Token.holders", "Query.swaps" ]
+//! ```
+//!
+//! The file is checked for changes periodically and reloaded when it was
+//! modified so that changes take effect without a restart
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, read_to_string};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::Deserialize;
+
+use crate::anyhow;
+use crate::cheap_clone::CheapClone;
+use crate::data::graphql::ext::DirectiveFinder;
+use crate::prelude::{info, s, warn, Logger, ENV_VARS};
+use crate::schema::{ApiSchema, META_FIELD_NAME};
+
+/// How often we check whether the configuration file changed
+const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The API fields that are disabled for one deployment
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeploymentFields {
+    /// Whether to serve the `_meta` field
+    pub meta: bool,
+    /// Whether to serve fulltext query fields
+    pub fulltext: bool,
+    /// Additional fields that should not be served, as `<Type>.<field>`
+    pub fields: HashSet<String>,
+}
+
+impl Default for DeploymentFields {
+    fn default() -> Self {
+        Self {
+            meta: true,
+            fulltext: true,
+            fields: HashSet::new(),
+        }
+    }
+}
+
+impl DeploymentFields {
+    /// Return `true` if `field` on the type `type_name` should be removed
+    /// from the API schema
+    pub fn hides(&self, type_name: &str, field: &s::Field) -> bool {
+        if !self.meta && field.name == META_FIELD_NAME {
+            return true;
+        }
+        if !self.fulltext && field.find_directive("fulltext").is_some() {
+            return true;
+        }
+        self.fields
+            .contains(&format!("{}.{}", type_name, field.name))
+    }
+}
+
+/// The configuration for all deployments, keyed by deployment hash
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config(HashMap<String, DeploymentFields>);
+
+impl Config {
+    pub fn from_file(path: &str) -> Result<Self, anyhow::Error> {
+        Self::parse(&read_to_string(path)?)
+    }
+
+    pub fn parse(toml: &str) -> Result<Self, anyhow::Error> {
+        let config: HashMap<String, DeploymentFields> = toml::from_str(toml)?;
+        for (deployment, fields) in &config {
+            for field in &fields.fields {
+                match field.split_once('.') {
+                    Some((typ, name)) if !typ.is_empty() && !name.is_empty() => {}
+                    _ => anyhow::bail!(
+                        "invalid field `{}` for deployment {}: expected `<Type>.<field>`",
+                        field,
+                        deployment
+                    ),
+                }
+            }
+        }
+        Ok(Config(config))
+    }
+
+    pub fn for_deployment(&self, deployment: &str) -> Option<&DeploymentFields> {
+        self.0.get(deployment)
+    }
+}
+
+struct State {
+    config: Config,
+    /// The modification time of the file when we last read it
+    modified: Option<SystemTime>,
+    last_check: Instant,
+    /// The pruned API schemas, keyed by deployment hash, together with the
+    /// schema they were derived from
+    schemas: HashMap<String, (Arc<ApiSchema>, Arc<ApiSchema>)>,
+}
+
+/// Removes the disabled fields from the API schemas of deployments
+pub struct DisabledFields {
+    logger: Logger,
+    path: Option<String>,
+    state: Mutex<State>,
+}
+
+impl DisabledFields {
+    /// Read the configuration from the file at `path`. If `path` is
+    /// `None`, no fields are disabled
+    pub fn new(logger: &Logger, path: Option<String>) -> Result<Self, anyhow::Error> {
+        let (config, modified) = match &path {
+            Some(path) => {
+                let modified = fs::metadata(path)?.modified().ok();
+                (Config::from_file(path)?, modified)
+            }
+            None => (Config::default(), None),
+        };
+        Ok(Self {
+            logger: logger.cheap_clone(),
+            path,
+            state: Mutex::new(State {
+                config,
+                modified,
+                last_check: Instant::now(),
+                schemas: HashMap::new(),
+            }),
+        })
+    }
+
+    /// Use the file set in `GRAPH_GRAPHQL_DISABLED_FIELDS_FILE`
+    ///
+    /// # Panics
+    /// If the file can not be read or parsed
+    pub fn from_env(logger: &Logger) -> Self {
+        let path = ENV_VARS.graphql.disabled_fields_file.clone();
+        Self::new(logger, path.clone()).unwrap_or_else(|e| {
+            panic!(
+                "failed to read disabled fields from {}: {:#}",
+                path.unwrap_or_default(),
+                e
+            )
+        })
+    }
+
+    /// Return the API schema that should be served for the deployment of
+    /// `schema`, i.e., `schema` without any of the fields that are
+    /// disabled for that deployment
+    pub fn api_schema(&self, schema: Arc<ApiSchema>) -> Result<Arc<ApiSchema>, anyhow::Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(schema),
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if state.last_check.elapsed() >= RELOAD_INTERVAL {
+            self.reload(path, &mut state);
+        }
+
+        let State {
+            config, schemas, ..
+        } = &mut *state;
+        let id = schema.id().as_str();
+        let fields = match config.for_deployment(id) {
+            Some(fields) => fields,
+            None => return Ok(schema),
+        };
+        if let Some((orig, pruned)) = schemas.get(id) {
+            if Arc::ptr_eq(orig, &schema) {
+                return Ok(pruned.cheap_clone());
+            }
+        }
+        let pruned = Arc::new(schema.without_fields(|typ, field| fields.hides(typ, field))?);
+        schemas.insert(id.to_string(), (schema.cheap_clone(), pruned.cheap_clone()));
+        Ok(pruned)
+    }
+
+    /// Reload the configuration if the file changed since we last read it.
+    /// If the new file can not be read, keep the previous configuration
+    fn reload(&self, path: &str, state: &mut State) {
+        state.last_check = Instant::now();
+
+        let modified = match fs::metadata(path).and_then(|md| md.modified()) {
+            Ok(modified) => modified,
+            Err(e) => {
+                warn!(self.logger, "Can not check disabled fields file for changes";
+                    "path" => path, "error" => e.to_string());
+                return;
+            }
+        };
+        if state.modified == Some(modified) {
+            return;
+        }
+        // Remember the modification time even if the file is broken so we
+        // only complain once about each change
+        state.modified = Some(modified);
+
+        match Config::from_file(path) {
+            Ok(config) => {
+                info!(self.logger, "Reloaded disabled fields"; "path" => path);
+                state.config = config;
+                state.schemas.clear();
+            }
+            Err(e) => {
+                warn!(self.logger, "Failed to reload disabled fields, keeping the previous configuration";
+                    "path" => path, "error" => format!("{:#}", e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::data::subgraph::LATEST_VERSION;
+    use crate::prelude::DeploymentHash;
+    use crate::schema::{ast, InputSchema};
+
+    use super::{Config, DeploymentFields};
+
+    const SCHEMA: &str = r#"
+        type _Schema_ @fulltext(
+          name: "search"
+          language: en
+          algorithm: rank
+          include: [{ entity: "Token", fields: [{ name: "name" }] }]
+        )
+
+        type Token @entity {
+          id: ID!
+          name: String!
+          holders: [Account!]! @derivedFrom(field: "token")
+        }
+
+        type Account @entity {
+          id: ID!
+          token: Token!
+        }
+    "#;
+
+    #[test]
+    fn parses_correctly() {
+        let content = r#"
+        [QmDeployment]
+        meta = false
+        fields = [ "Token.holders" ]
+
+        [QmOther]
+        fulltext = false
+        "#;
+
+        let config = Config::parse(content).unwrap();
+        let fields = config.for_deployment("QmDeployment").unwrap();
+        assert!(!fields.meta);
+        assert!(fields.fulltext);
+        assert!(fields.fields.contains("Token.holders"));
+
+        let fields = config.for_deployment("QmOther").unwrap();
+        assert!(fields.meta);
+        assert!(!fields.fulltext);
+        assert!(fields.fields.is_empty());
+
+        assert_eq!(None, config.for_deployment("QmUnknown"));
+
+        assert!(Config::parse("[QmDeployment]\nfields = [\"holders\"]").is_err());
+        assert!(Config::parse("[QmDeployment]\nmeat = false").is_err());
+    }
+
+    #[test]
+    fn hides_fields() {
+        let id = DeploymentHash::new("disabledFields").unwrap();
+        let schema = InputSchema::parse(LATEST_VERSION, SCHEMA, id)
+            .unwrap()
+            .api_schema()
+            .unwrap();
+
+        let fields = DeploymentFields {
+            meta: false,
+            fulltext: false,
+            fields: ["Token.holders".to_string()].into_iter().collect(),
+        };
+        let pruned = schema
+            .without_fields(|typ, field| fields.hides(typ, field))
+            .unwrap();
+
+        let query_type = pruned.query_type.as_ref();
+        assert!(ast::get_field(query_type, "_meta").is_none());
+        assert!(ast::get_field(query_type, "search").is_none());
+        assert!(ast::get_field(query_type, "tokens").is_some());
+        let token = pruned.object_or_interface("Token").unwrap();
+        assert!(token.field("holders").is_none());
+        assert!(token.field("name").is_some());
+
+        let pruned = schema
+            .without_fields(|typ, field| DeploymentFields::default().hides(typ, field))
+            .unwrap();
+        let query_type = pruned.query_type.as_ref();
+        assert!(ast::get_field(query_type, "_meta").is_some());
+        assert!(ast::get_field(query_type, "search").is_some());
+    }
+}
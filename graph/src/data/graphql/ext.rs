@@ -48,6 +48,10 @@ pub trait DocumentExt {
 
     fn find_interface(&self, name: &str) -> Option<&InterfaceType>;
 
+    /// Return all `@fulltext` directives on the subgraph schema type,
+    /// regardless of whether fulltext search is allowed
+    fn fulltext_directives(&self) -> Vec<&Directive>;
+
     fn get_fulltext_directives(&self) -> Result<Vec<&Directive>, anyhow::Error>;
 
     fn get_root_query_type(&self) -> Option<&ObjectType>;
@@ -127,17 +131,19 @@ impl DocumentExt for Document {
         })
     }
 
-    fn get_fulltext_directives(&self) -> Result<Vec<&Directive>, anyhow::Error> {
-        let directives = self.get_object_type_definition(SCHEMA_TYPE_NAME).map_or(
-            vec![],
-            |subgraph_schema_type| {
+    fn fulltext_directives(&self) -> Vec<&Directive> {
+        self.get_object_type_definition(SCHEMA_TYPE_NAME)
+            .map_or(vec![], |subgraph_schema_type| {
                 subgraph_schema_type
                     .directives
                     .iter()
                     .filter(|directives| directives.name.eq("fulltext"))
                     .collect()
-            },
-        );
+            })
+    }
+
+    fn get_fulltext_directives(&self) -> Result<Vec<&Directive>, anyhow::Error> {
+        let directives = self.fulltext_directives();
         if !ENV_VARS.allow_non_deterministic_fulltext_search && !directives.is_empty() {
             Err(anyhow::anyhow!("Fulltext search is not yet deterministic"))
         } else {
@@ -323,6 +329,22 @@ pub trait DirectiveFinder {
             .and_then(|directive| directive.argument("field"))
             .and_then(|value| value.as_str())
     }
+
+    /// The names of the fields this field is derived from; that is either
+    /// the `field` or the list of `fields` of the `@derivedFrom` directive.
+    /// The list is empty if the field is not derived
+    fn derived_from_fields(&self) -> Vec<&str> {
+        let Some(directive) = self.find_directive("derivedFrom") else {
+            return vec![];
+        };
+        if let Some(field) = directive.argument("field").and_then(|value| value.as_str()) {
+            return vec![field];
+        }
+        match directive.argument("fields") {
+            Some(Value::List(fields)) => fields.iter().filter_map(|value| value.as_str()).collect(),
+            _ => vec![],
+        }
+    }
 }
 
 impl DirectiveFinder for ObjectType {
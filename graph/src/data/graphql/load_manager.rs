@@ -6,8 +6,10 @@ use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
-use crate::components::metrics::{Counter, GaugeVec, MetricsRegistry};
+use crate::cheap_clone::CheapClone;
+use crate::components::metrics::{Counter, CounterVec, GaugeVec, MetricsRegistry};
 use crate::components::store::{DeploymentId, PoolWaitStats};
 use crate::data::graphql::shape_hash::shape_hash;
 use crate::data::query::{CacheStatus, QueryExecutionError};
@@ -214,9 +216,15 @@ pub struct LoadManager {
     jailed_queries: RwLock<HashSet<QueryRef>>,
     /// Per shard state of whether we are killing queries or not
     kill_state: HashMap<String, RwLock<KillState>>,
+    /// Per shard limit on the number of queries that can run at the same
+    /// time. Empty if admission control is turned off
+    admission: HashMap<String, Arc<Semaphore>>,
+    /// How long a query waits for a slot in `admission`
+    admission_wait: Duration,
     effort_gauge: Box<GaugeVec>,
     query_counters: HashMap<CacheStatus, Counter>,
     kill_rate_gauge: Box<GaugeVec>,
+    admission_rejected: Box<CounterVec>,
 }
 
 impl LoadManager {
@@ -240,6 +248,11 @@ impl LoadManager {
             "enabled"
         };
         info!(logger, "Creating LoadManager in {} mode", mode,);
+        if ENV_VARS.load_max_in_flight > 0 {
+            info!(logger, "Limiting queries per shard";
+                  "max_in_flight" => ENV_VARS.load_max_in_flight,
+                  "admission_wait_ms" => ENV_VARS.load_admission_wait.as_millis());
+        }
 
         let shard_label: Vec<_> = SHARD_LABEL.into_iter().map(String::from).collect();
         let effort_gauge = registry
@@ -253,9 +266,16 @@ impl LoadManager {
             .new_gauge_vec(
                 "query_kill_rate",
                 "The rate at which the load manager kills queries",
-                shard_label,
+                shard_label.clone(),
             )
             .expect("failed to create `query_kill_rate` counter");
+        let admission_rejected = registry
+            .new_counter_vec(
+                "query_admission_rejected",
+                "The number of queries rejected because too many queries were running",
+                shard_label,
+            )
+            .expect("failed to create `query_admission_rejected` counter");
         let query_counters = CacheStatus::iter()
             .map(|s| {
                 let labels = HashMap::from_iter(vec![("cache_status".to_owned(), s.to_string())]);
@@ -276,6 +296,15 @@ impl LoadManager {
                 .map(|shard| (shard.clone(), ShardEffort::default())),
         );
 
+        let admission = if ENV_VARS.load_max_in_flight > 0 {
+            HashMap::from_iter(shards.iter().map(|shard| {
+                let sema = Arc::new(Semaphore::new(ENV_VARS.load_max_in_flight));
+                (shard.clone(), sema)
+            }))
+        } else {
+            HashMap::new()
+        };
+
         let kill_state = HashMap::from_iter(
             shards
                 .into_iter()
@@ -288,9 +317,49 @@ impl LoadManager {
             blocked_queries,
             jailed_queries: RwLock::new(HashSet::new()),
             kill_state,
+            admission,
+            admission_wait: ENV_VARS.load_admission_wait,
             effort_gauge,
             query_counters,
             kill_rate_gauge,
+            admission_rejected,
+        }
+    }
+
+    /// Wait for a slot to run a query against `shard`. The query must hold
+    /// on to the returned permit until it is done. If all slots are taken
+    /// and none frees up within `GRAPH_LOAD_ADMISSION_WAIT`, the query is
+    /// rejected with `QueryExecutionError::Saturated` so that clients get a
+    /// quick answer instead of piling up behind the connection pool.
+    ///
+    /// Returns `None` if admission control is turned off, or if the query
+    /// should proceed without a slot because we are only simulating
+    pub async fn admit(
+        &self,
+        shard: &str,
+    ) -> Result<Option<OwnedSemaphorePermit>, QueryExecutionError> {
+        let sema = match self.admission.get(shard) {
+            Some(sema) => sema.cheap_clone(),
+            None => return Ok(None),
+        };
+
+        // `timeout` polls the future once before looking at the deadline,
+        // so a free slot is always taken, even with a wait of zero
+        match tokio::time::timeout(self.admission_wait, sema.acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            // The semaphore is never closed, but if it were, there's no
+            // point in waiting for it
+            Ok(Err(_)) | Err(_) => {
+                self.admission_rejected.with_label_values(&[shard]).inc();
+                if ENV_VARS.load_simulate {
+                    info!(self.logger, "Rejecting query because shard is saturated";
+                          "shard" => shard,
+                          "simulate" => true);
+                    Ok(None)
+                } else {
+                    Err(QueryExecutionError::Saturated)
+                }
+            }
         }
     }
 
@@ -548,3 +617,80 @@ impl LoadManager {
         kill_rate
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::sync::Semaphore;
+
+    use crate::components::metrics::MetricsRegistry;
+    use crate::data::query::QueryExecutionError;
+    use crate::log::discard;
+
+    use super::LoadManager;
+
+    const SHARD: &str = "primary";
+
+    /// A load manager that lets `max_in_flight` queries run against
+    /// `SHARD` at the same time and makes other queries wait for `wait`
+    fn load_manager(max_in_flight: usize, wait: Duration) -> Arc<LoadManager> {
+        let mut manager = LoadManager::new(
+            &discard(),
+            vec![SHARD.to_string()],
+            vec![],
+            Arc::new(MetricsRegistry::mock()),
+        );
+        manager.admission =
+            HashMap::from([(SHARD.to_string(), Arc::new(Semaphore::new(max_in_flight)))]);
+        manager.admission_wait = wait;
+        Arc::new(manager)
+    }
+
+    fn rejected(manager: &LoadManager) -> f64 {
+        manager.admission_rejected.with_label_values(&[SHARD]).get()
+    }
+
+    #[tokio::test]
+    async fn admission_admits_and_rejects() {
+        let manager = load_manager(1, Duration::from_millis(10));
+
+        let permit = manager.admit(SHARD).await.unwrap();
+        assert!(permit.is_some());
+        assert_eq!(0.0, rejected(&manager));
+
+        // The only slot is taken and does not free up in time
+        let res = manager.admit(SHARD).await;
+        assert!(matches!(res, Err(QueryExecutionError::Saturated)));
+        assert_eq!(1.0, rejected(&manager));
+
+        // Once the slot is free, queries are admitted again
+        drop(permit);
+        assert!(manager.admit(SHARD).await.unwrap().is_some());
+        assert_eq!(1.0, rejected(&manager));
+
+        // Shards without a limit do not use admission control
+        assert!(manager.admit("other").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn admission_queues() {
+        let manager = load_manager(1, Duration::from_secs(30));
+
+        let permit = manager.admit(SHARD).await.unwrap();
+        let waiting = {
+            let manager = manager.clone();
+            tokio::spawn(async move { manager.admit(SHARD).await.map(|permit| permit.is_some()) })
+        };
+
+        // The second query waits for the slot instead of being rejected
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        drop(permit);
+        assert!(waiting.await.unwrap().unwrap());
+        assert_eq!(0.0, rejected(&manager));
+    }
+}
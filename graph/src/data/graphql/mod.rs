@@ -25,6 +25,8 @@ pub mod shape_hash;
 
 pub mod load_manager;
 
+pub mod disabled_fields;
+
 pub mod object_or_interface;
 pub use object_or_interface::ObjectOrInterface;
 
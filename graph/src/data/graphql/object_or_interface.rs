@@ -1,11 +1,11 @@
 use crate::prelude::s;
-use crate::schema::{EntityType, Schema};
+use crate::schema::{EntityType, Schema, ENTITY_CONNECTION_DIRECTIVE, ENTITY_VERSION_DIRECTIVE};
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
 use std::mem;
 
-use super::ObjectTypeExt;
+use super::{DirectiveExt, ObjectTypeExt, ValueExt};
 
 #[derive(Copy, Clone, Debug)]
 pub enum ObjectOrInterface<'a> {
@@ -134,4 +134,24 @@ impl<'a> ObjectOrInterface<'a> {
             ObjectOrInterface::Interface(i) => i.is_meta(),
         }
     }
+
+    /// If this is the `<Type>_version` type of a history query in an API
+    /// schema, return the name of the entity type whose versions it holds
+    pub fn version_of(self) -> Option<&'a str> {
+        self.directives()
+            .iter()
+            .find(|directive| directive.name == ENTITY_VERSION_DIRECTIVE)
+            .and_then(|directive| directive.argument("entity"))
+            .and_then(|entity| entity.as_str())
+    }
+
+    /// If this is the `<Type>Connection` type of a connection query in an
+    /// API schema, return the name of the entity type it pages through
+    pub fn connection_of(self) -> Option<&'a str> {
+        self.directives()
+            .iter()
+            .find(|directive| directive.name == ENTITY_CONNECTION_DIRECTIVE)
+            .and_then(|directive| directive.argument("entity"))
+            .and_then(|entity| entity.as_str())
+    }
 }
@@ -65,6 +65,7 @@ pub enum QueryExecutionError {
     CyclicalFragment(String),
     TooExpensive,
     Throttled,
+    Saturated,
     UndefinedFragment(String),
     Panic(String),
     EventStreamError,
@@ -78,6 +79,7 @@ pub enum QueryExecutionError {
     IdMissing,
     IdNotString,
     ConstraintViolation(String),
+    BlockNotOnChain(String, String), // (block, deployment)
 }
 
 impl QueryExecutionError {
@@ -130,6 +132,7 @@ impl QueryExecutionError {
             | EventStreamError
             | TooExpensive
             | Throttled
+            | Saturated
             | DeploymentReverted
             | SubgraphManifestResolveError(_)
             | InvalidSubgraphManifest
@@ -138,7 +141,8 @@ impl QueryExecutionError {
             | DeploymentNotFound(_)
             | IdMissing
             | IdNotString
-            | ConstraintViolation(_) => false,
+            | ConstraintViolation(_)
+            | BlockNotOnChain(_, _) => false,
         }
     }
 }
@@ -220,7 +224,9 @@ impl fmt::Display for QueryExecutionError {
                 write!(f, "Failed to get subgraph ID from type: `{}`", s)
             }
             RangeArgumentsError(arg, max, actual) => {
-                write!(f, "The `{}` argument must be between 0 and {}, but is {}", arg, max, actual)
+                // `first` must be positive, `skip` can be zero
+                let min = if *arg == "first" { 1 } else { 0 };
+                write!(f, "The `{}` argument must be between {} and {}, but is {}", arg, min, max, actual)
             }
             InvalidFilterError => write!(f, "Filter must by an object"),
             EntityFieldError(e, a) => {
@@ -281,6 +287,7 @@ impl fmt::Display for QueryExecutionError {
             FulltextQueryInvalidSyntax(msg) => write!(f, "Invalid fulltext search query syntax. Error: {}. Hint: Search terms with spaces need to be enclosed in single quotes", msg),
             TooExpensive => write!(f, "query is too expensive"),
             Throttled => write!(f, "service is overloaded and can not run the query right now. Please try again in a few minutes"),
+            Saturated => write!(f, "too many queries are running right now. Please try again shortly"),
             DeploymentReverted => write!(f, "the chain was reorganized while executing the query"),
             SubgraphManifestResolveError(e) => write!(f, "failed to resolve subgraph manifest: {}", e),
             InvalidSubgraphManifest => write!(f, "invalid subgraph manifest file"),
@@ -289,6 +296,7 @@ impl fmt::Display for QueryExecutionError {
             IdMissing => write!(f, "entity is missing an `id` attribute"),
             IdNotString => write!(f, "entity `id` attribute is not a string"),
             ConstraintViolation(msg) => write!(f, "internal constraint violated: {}", msg),
+            BlockNotOnChain(block, id) => write!(f, "block {} is not on the chain indexed by subgraph {}", block, id),
         }
     }
 }
@@ -142,6 +142,7 @@ impl Query {
 
         let (query_text, variables_text) = if trace
             || ENV_VARS.log_gql_timing()
+            || ENV_VARS.graphql.slow_query_threshold.is_some()
             || (ENV_VARS.graphql.enable_validations && ENV_VARS.graphql.silent_graphql_validations)
         {
             (
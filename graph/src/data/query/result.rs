@@ -1,11 +1,15 @@
 use super::error::{QueryError, QueryExecutionError};
 use super::trace::{HttpTrace, TRACE_NONE};
 use crate::cheap_clone::CheapClone;
-use crate::components::server::query::ServerResponse;
+use crate::components::server::query::{ServerResponse, StreamingServerResponse};
 use crate::data::value::Object;
 use crate::derive::CacheWeight;
+use crate::env::ENV_VARS;
 use crate::prelude::{r, CacheWeight, DeploymentHash};
-use http_body_util::Full;
+use crate::tokio::sync::mpsc;
+use futures03::stream;
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::{Bytes, Frame};
 use hyper::header::{
     ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
     CONTENT_TYPE,
@@ -13,9 +17,10 @@ use hyper::header::{
 use hyper::Response;
 use serde::ser::*;
 use serde::Serialize;
-use std::convert::TryFrom;
+use std::convert::{Infallible, TryFrom};
+use std::io;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use super::{CacheStatus, Trace};
 
@@ -77,6 +82,12 @@ impl QueryResults {
         self.results.iter().any(|result| result.not_found())
     }
 
+    /// Whether the query was turned away because too many queries were
+    /// already running against its shard
+    pub fn saturated(&self) -> bool {
+        self.results.iter().any(|result| result.saturated())
+    }
+
     pub fn deployment_hash(&self) -> Option<&DeploymentHash> {
         self.results
             .iter()
@@ -105,7 +116,10 @@ impl Serialize for QueryResults {
         if has_errors {
             len += 1;
         }
-        len += 1;
+        if !self.trace.is_none() {
+            // `trace`, `http` and `extensions`
+            len += 3;
+        }
         let mut state = serializer.serialize_struct("QueryResults", len)?;
 
         // Serialize data.
@@ -142,9 +156,28 @@ impl Serialize for QueryResults {
         }
 
         if !self.trace.is_none() {
-            let http = HttpTrace::new(start.elapsed(), self.results.weight());
+            let to_json = start.elapsed();
+            let http = HttpTrace::new(to_json, self.results.weight());
             state.serialize_field("trace", &self.trace)?;
             state.serialize_field("http", &http)?;
+
+            struct SerExtensions<'a>(&'a Trace, Duration);
+
+            impl Serialize for SerExtensions<'_> {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    let complexity = self.0.complexity();
+                    let len = 2 + complexity.is_some() as usize;
+                    let mut map = serializer.serialize_map(Some(len))?;
+                    if let Some(complexity) = complexity {
+                        map.serialize_entry("complexity", &complexity)?;
+                    }
+                    map.serialize_entry("timing", &self.0.timing())?;
+                    map.serialize_entry("to_json_ms", &self.1.as_millis())?;
+                    map.end()
+                }
+            }
+
+            state.serialize_field("extensions", &SerExtensions(&self.trace, to_json))?;
         }
         state.end()
     }
@@ -202,20 +235,110 @@ impl QueryResults {
         self.results.push(other);
     }
 
-    pub fn as_http_response(&self) -> ServerResponse {
-        let json = serde_json::to_string(&self).unwrap();
+    fn http_response<B>(&self, body: B) -> Response<B> {
         let attestable = self.results.iter().all(|r| r.is_attestable());
+        // Rejections from admission control are answered with a 503 so that
+        // clients and load balancers can retry the query elsewhere
+        let status = if self.saturated() { 503 } else { 200 };
         Response::builder()
-            .status(200)
+            .status(status)
             .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
             .header(CONTENT_TYPE, "application/json")
             .header(ACCESS_CONTROL_ALLOW_HEADERS, "Content-Type, User-Agent")
             .header(ACCESS_CONTROL_ALLOW_METHODS, "GET, OPTIONS, POST")
             .header(CONTENT_TYPE, "application/json")
             .header("Graph-Attestable", attestable.to_string())
-            .body(Full::from(json))
+            .body(body)
             .unwrap()
     }
+
+    pub fn as_http_response(&self) -> ServerResponse {
+        let json = serde_json::to_string(&self).unwrap();
+        self.http_response(Full::from(json))
+    }
+
+    /// Use `as_streaming_http_response` for results that are larger than
+    /// `GRAPH_GRAPHQL_STREAM_RESPONSE_THRESHOLD` and `as_http_response`
+    /// for all others
+    pub fn into_http_response(self) -> StreamingServerResponse {
+        let stream = ENV_VARS
+            .graphql
+            .stream_response_threshold
+            .map_or(false, |threshold| self.results.weight() > threshold);
+        if stream {
+            self.as_streaming_http_response()
+        } else {
+            self.as_http_response().map(|body| body.boxed_unsync())
+        }
+    }
+
+    /// Like `as_http_response`, but the results are serialized on a
+    /// blocking thread and sent to the client in chunks while they are
+    /// being serialized, so that the JSON for the entire response never
+    /// has to be held in memory
+    pub fn as_streaming_http_response(self) -> StreamingServerResponse {
+        let (sender, receiver) = mpsc::channel(ChunkWriter::QUEUE_LEN);
+        let response = self.http_response(());
+        crate::spawn_blocking_allow_panic(move || {
+            let mut writer = ChunkWriter::new(sender);
+            // Serialization only fails if the client went away, in which
+            // case there is nobody left to tell about it
+            serde_json::to_writer(&mut writer, &self)
+                .map_err(io::Error::from)
+                .and_then(|()| io::Write::flush(&mut writer))
+                .ok();
+        });
+        let body = stream::unfold(receiver, |mut receiver| async move {
+            let chunk = receiver.recv().await?;
+            Some((Ok::<_, Infallible>(Frame::data(chunk)), receiver))
+        });
+        response.map(|()| StreamBody::new(body).boxed_unsync())
+    }
+}
+
+/// Collects the JSON for a streamed response into chunks and hands them
+/// to the response body. Since there is only room for a few chunks in the
+/// queue, serialization can't get far ahead of what the client reads
+struct ChunkWriter {
+    buf: Vec<u8>,
+    sender: mpsc::Sender<Bytes>,
+}
+
+impl ChunkWriter {
+    const CHUNK_SIZE: usize = 64 * 1024;
+    const QUEUE_LEN: usize = 4;
+
+    fn new(sender: mpsc::Sender<Bytes>) -> Self {
+        ChunkWriter {
+            buf: Vec::with_capacity(Self::CHUNK_SIZE),
+            sender,
+        }
+    }
+
+    fn send(&mut self) -> io::Result<()> {
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(Self::CHUNK_SIZE));
+        self.sender
+            .blocking_send(Bytes::from(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "the response was dropped"))
+    }
+}
+
+impl io::Write for ChunkWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= Self::CHUNK_SIZE {
+            self.send()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            Ok(())
+        } else {
+            self.send()
+        }
+    }
 }
 
 /// The result of running a query, if successful.
@@ -269,6 +392,15 @@ impl QueryResult {
         })
     }
 
+    pub fn saturated(&self) -> bool {
+        self.errors.iter().any(|e| {
+            matches!(
+                e,
+                QueryError::ExecutionError(QueryExecutionError::Saturated)
+            )
+        })
+    }
+
     pub fn has_data(&self) -> bool {
         self.data.is_some()
     }
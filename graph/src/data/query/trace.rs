@@ -51,6 +51,8 @@ pub enum Trace {
         /// the time it takes to serialize the result
         elapsed: Duration,
         query_parsing: Duration,
+        /// The complexity of the query as estimated before executing it
+        complexity: u64,
         /// A list of `Trace::Block`, one for each block constraint in the query
         blocks: Vec<TraceWithCacheStatus>,
     },
@@ -88,6 +90,7 @@ impl Trace {
         query: &Arc<String>,
         variables: &Arc<String>,
         query_id: &str,
+        complexity: u64,
         do_trace: bool,
     ) -> Trace {
         if do_trace {
@@ -98,6 +101,7 @@ impl Trace {
                 elapsed: Duration::ZERO,
                 setup: Duration::ZERO,
                 query_parsing: Duration::ZERO,
+                complexity,
                 blocks: Vec::new(),
             }
         } else {
@@ -252,6 +256,91 @@ impl Trace {
     pub fn query_total(&self) -> QueryTotal {
         QueryTotal::calculate(self)
     }
+
+    /// Return the complexity of the query for a `Trace::Root`
+    pub fn complexity(&self) -> Option<u64> {
+        match self {
+            Trace::Root { complexity, .. } => Some(*complexity),
+            Trace::None | Trace::Block { .. } | Trace::Query { .. } => None,
+        }
+    }
+
+    /// Return a summary of where the time for this trace went. This is
+    /// only meaningful for a `Trace::Root`
+    pub fn timing(&self) -> Timing<'_> {
+        Timing::calculate(self)
+    }
+}
+
+/// A summary of a `Trace::Root` that breaks the time for a query down into
+/// planning, the individual SQL queries, and assembling the result from the
+/// SQL query results. It is computed entirely from the trace
+pub struct Timing<'a> {
+    /// Time for parsing the query and setting up for execution
+    pub planning: Duration,
+    /// The SQL queries in the order in which they were run, skipping
+    /// blocks whose results came from the cache
+    pub sql: Vec<&'a Trace>,
+    /// The total time for executing the SQL queries
+    pub sql_total: Duration,
+    /// The time for executing the query that was not spent in SQL
+    /// queries, i.e., the time for resolving and assembling results
+    pub assembly: Duration,
+}
+
+impl<'a> Timing<'a> {
+    fn add_queries(sql: &mut Vec<&'a Trace>, trace: &'a Trace) {
+        use Trace::*;
+        match trace {
+            None | Root { .. } => { /* nothing to do */ }
+            Block { children, .. } => {
+                children
+                    .iter()
+                    .for_each(|(_, trace)| Self::add_queries(sql, trace));
+            }
+            Query { children, .. } => {
+                sql.push(trace);
+                children
+                    .iter()
+                    .for_each(|(_, trace)| Self::add_queries(sql, trace));
+            }
+        }
+    }
+
+    fn calculate(trace: &'a Trace) -> Self {
+        let mut sql = Vec::new();
+        let (planning, execution) = match trace {
+            Trace::Root {
+                setup,
+                elapsed,
+                query_parsing,
+                blocks,
+                ..
+            } => {
+                blocks
+                    .iter()
+                    .filter(|twc| twc.cache_status.uses_database())
+                    .for_each(|twc| Self::add_queries(&mut sql, &twc.trace));
+                (*query_parsing + *setup, elapsed.saturating_sub(*setup))
+            }
+            Trace::None | Trace::Block { .. } | Trace::Query { .. } => {
+                (Duration::ZERO, Duration::ZERO)
+            }
+        };
+        let sql_total = sql
+            .iter()
+            .map(|trace| match trace {
+                Trace::Query { elapsed, .. } => *elapsed,
+                _ => Duration::ZERO,
+            })
+            .sum();
+        Timing {
+            planning,
+            sql,
+            sql_total,
+            assembly: execution.saturating_sub(sql_total),
+        }
+    }
 }
 
 #[derive(Default)]
@@ -336,10 +425,11 @@ impl Serialize for Trace {
                 elapsed,
                 setup,
                 query_parsing,
+                complexity,
                 blocks,
             } => {
                 let qt = self.query_total();
-                let mut map = ser.serialize_map(Some(8))?;
+                let mut map = ser.serialize_map(Some(9))?;
                 map.serialize_entry("query", query)?;
                 if !variables.is_empty() && variables.as_str() != "{}" {
                     map.serialize_entry("variables", variables)?;
@@ -348,6 +438,7 @@ impl Serialize for Trace {
                 map.serialize_entry("elapsed_ms", &elapsed.as_millis())?;
                 map.serialize_entry("setup_ms", &setup.as_millis())?;
                 map.serialize_entry("query_parsing_ms", &query_parsing.as_millis())?;
+                map.serialize_entry("complexity", complexity)?;
                 map.serialize_entry("db", &qt)?;
                 map.serialize_entry("blocks", blocks)?;
                 map.end()
@@ -390,6 +481,46 @@ impl Serialize for Trace {
     }
 }
 
+impl Serialize for Timing<'_> {
+    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        struct Sql<'a>(&'a Trace);
+
+        impl Serialize for Sql<'_> {
+            fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                let mut map = ser.serialize_map(Some(4))?;
+                if let Trace::Query {
+                    query,
+                    elapsed,
+                    conn_wait,
+                    entity_count,
+                    ..
+                } = self.0
+                {
+                    map.serialize_entry("query", query)?;
+                    map.serialize_entry("elapsed_ms", &elapsed.as_millis())?;
+                    map.serialize_entry("conn_wait_ms", &conn_wait.as_millis())?;
+                    map.serialize_entry("entity_count", entity_count)?;
+                }
+                map.end()
+            }
+        }
+
+        let sql: Vec<_> = self.sql.iter().map(|trace| Sql(trace)).collect();
+        let mut map = ser.serialize_map(Some(4))?;
+        map.serialize_entry("planning_ms", &self.planning.as_millis())?;
+        map.serialize_entry("sql_ms", &self.sql_total.as_millis())?;
+        map.serialize_entry("sql", &sql)?;
+        map.serialize_entry("assembly_ms", &self.assembly.as_millis())?;
+        map.end()
+    }
+}
+
 impl Serialize for TraceWithCacheStatus {
     fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
     where
@@ -413,3 +544,99 @@ impl Serialize for HttpTrace {
         map.end()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::{CacheStatus, Trace};
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn query(sql: &str, elapsed: u64) -> Trace {
+        Trace::query(sql, ms(elapsed), 1)
+    }
+
+    fn root(blocks: Vec<(Trace, CacheStatus)>) -> Trace {
+        let text = Arc::new("query { things { id } }".to_string());
+        let mut root = Trace::root(&text, &Arc::new(String::new()), "q1", 7, true);
+        root.query_parsing(ms(3));
+        for (block, cache_status) in blocks {
+            root.append(Arc::new(block), cache_status);
+        }
+        root.finish(ms(10), ms(100));
+        root
+    }
+
+    #[test]
+    fn timing_breaks_down_elapsed_time() {
+        let mut block = Trace::block(1, true);
+        let mut parent = query("select parents", 20);
+        parent.push("children", query("select children", 30));
+        block.push("parents", parent);
+        block.push("others", query("select others", 5));
+
+        let root = root(vec![(block, CacheStatus::Miss)]);
+        let timing = root.timing();
+
+        // Planning is query parsing plus setup
+        assert_eq!(ms(13), timing.planning);
+        let sql: Vec<_> = timing
+            .sql
+            .iter()
+            .map(|trace| match trace {
+                Trace::Query { query, .. } => query.as_str(),
+                _ => unreachable!("timing only collects queries"),
+            })
+            .collect();
+        assert_eq!(
+            vec!["select parents", "select children", "select others"],
+            sql
+        );
+        assert_eq!(ms(55), timing.sql_total);
+        // Execution takes 100ms - 10ms of setup; the rest of that was not
+        // spent in SQL queries
+        assert_eq!(ms(35), timing.assembly);
+    }
+
+    #[test]
+    fn timing_skips_cached_blocks() {
+        let mut cached = Trace::block(1, true);
+        cached.push("things", query("select cached", 40));
+        let mut fresh = Trace::block(2, true);
+        fresh.push("things", query("select fresh", 15));
+
+        let root = root(vec![
+            (cached, CacheStatus::Hit),
+            (fresh, CacheStatus::Insert),
+        ]);
+        let timing = root.timing();
+
+        assert_eq!(1, timing.sql.len());
+        assert_eq!(ms(15), timing.sql_total);
+        assert_eq!(ms(75), timing.assembly);
+    }
+
+    #[test]
+    fn timing_does_not_underflow() {
+        let mut block = Trace::block(1, true);
+        block.push("things", query("select slow", 500));
+
+        let root = root(vec![(block, CacheStatus::Miss)]);
+        let timing = root.timing();
+
+        assert_eq!(ms(500), timing.sql_total);
+        assert_eq!(Duration::ZERO, timing.assembly);
+    }
+
+    #[test]
+    fn timing_of_non_root_is_empty() {
+        let timing = Trace::None.timing();
+        assert_eq!(Duration::ZERO, timing.planning);
+        assert!(timing.sql.is_empty());
+        assert_eq!(Duration::ZERO, timing.sql_total);
+        assert_eq!(Duration::ZERO, timing.assembly);
+    }
+}
@@ -403,7 +403,7 @@ impl Value {
                 // Check if `ty` is a custom scalar type, otherwise assume it's
                 // just a string.
                 match n.as_str() {
-                    BYTES_SCALAR => Value::Bytes(scalar::Bytes::from_str(s)?),
+                    BYTES_SCALAR => Value::Bytes(scalar::Bytes::from_query_str(s)?),
                     BIG_INT_SCALAR => Value::BigInt(scalar::BigInt::from_str(s).map_err(|e| {
                         QueryExecutionError::ValueParseError("BigInt".to_string(), format!("{}", e))
                     })?),
@@ -953,6 +953,22 @@ impl Entity {
         Ok(())
     }
 
+    /// Apply the scale of fields that have a `@scale` directive to their
+    /// values, see `Field::scale_value`. Entity types without such fields
+    /// are not touched
+    pub fn apply_scale(&mut self, key: &EntityKey) -> Result<(), InternError> {
+        let Ok(object_type) = key.entity_type.object_type() else {
+            return Ok(());
+        };
+
+        for field in object_type.scaled_fields() {
+            if let Some(value) = self.remove(&field.name) {
+                self.insert(&field.name, field.scale_value(value))?;
+            }
+        }
+        Ok(())
+    }
+
     /// Validate that this entity matches the object type definition in the
     /// schema. An entity that passes these checks can be stored
     /// successfully in the subgraph's database schema
@@ -1227,6 +1243,69 @@ fn entity_validation() {
     );
 }
 
+#[test]
+fn entity_apply_scale() {
+    use crate::schema::InputSchema;
+    use scalar::{BigDecimal, BigInt};
+
+    const DOCUMENT: &str = "
+    type Token @entity {
+        id: ID!,
+        supply: BigDecimal! @scale(decimals: 18),
+        price: BigDecimal @scale(decimals: 2),
+        prices: [BigDecimal!] @scale(decimals: 2),
+        raw: BigDecimal
+    }";
+
+    let schema = InputSchema::parse_latest(DOCUMENT, DeploymentHash::new("scale").unwrap())
+        .expect("Failed to parse test schema");
+    let key = schema
+        .entity_type("Token")
+        .unwrap()
+        .key(Id::String("t1".into()));
+
+    let big_int = |s: &str| Value::BigInt(BigInt::from_str(s).unwrap());
+    let big_decimal = |s: &str| Value::BigDecimal(BigDecimal::from_str(s).unwrap());
+
+    let mut token = entity! { schema =>
+        id: "t1",
+        supply: big_int("1234500000000000000000"),
+        price: big_decimal("1.235"),
+        prices: Value::List(vec![big_int("12345"), big_decimal("0.001")]),
+        raw: big_int("17")
+    };
+    token.apply_scale(&key).unwrap();
+
+    assert_eq!(Some(&big_decimal("1234.5")), token.get("supply"));
+    assert_eq!(Some(&big_decimal("1.24")), token.get("price"));
+    assert_eq!(
+        Some(&Value::List(vec![big_decimal("123.45"), big_decimal("0")])),
+        token.get("prices")
+    );
+    // Fields without `@scale` are left alone
+    assert_eq!(Some(&big_int("17")), token.get("raw"));
+
+    // Before spec version 1.3.0, `@scale` has no effect
+    let schema = InputSchema::parse(
+        &crate::data::subgraph::SPEC_VERSION_1_2_0,
+        DOCUMENT,
+        DeploymentHash::new("scale").unwrap(),
+    )
+    .expect("Failed to parse test schema");
+    let key = schema
+        .entity_type("Token")
+        .unwrap()
+        .key(Id::String("t1".into()));
+    let mut token = entity! { schema =>
+        id: "t1",
+        supply: big_decimal("1234.56789"),
+        price: big_decimal("1.235")
+    };
+    token.apply_scale(&key).unwrap();
+    assert_eq!(Some(&big_decimal("1234.56789")), token.get("supply"));
+    assert_eq!(Some(&big_decimal("1.235")), token.get("price"));
+}
+
 #[test]
 fn fmt_debug() {
     assert_eq!("String(\"hello\")", format!("{:?}", Value::from("hello")));
@@ -18,6 +18,14 @@ pub use old_bigdecimal::ToPrimitive;
 use super::BigInt;
 
 /// All operations on `BigDecimal` return a normalized value.
+///
+/// Normalizing rounds values to `MAX_SIGNFICANT_DIGITS` significant
+/// digits, rounding halfway cases of positive values up, and truncating
+/// negative values towards zero. Division first computes the quotient to
+/// 100 significant digits, rounding the last digit half up, and then
+/// normalizes it. This is the behavior of the pinned version of the
+/// `bigdecimal` crate; changing it would change the results of mappings,
+/// and with that the PoI, of existing subgraphs.
 // Caveat: The exponent is currently an i64 and may overflow. See
 // https://github.com/akubera/bigdecimal-rs/issues/54.
 // Using `#[serde(from = "BigDecimal"]` makes sure deserialization calls `BigDecimal::new()`.
@@ -84,6 +92,27 @@ impl BigDecimal {
 
         BigDecimal(OldBigDecimal::new(int_val, scale))
     }
+
+    /// Round to at most `decimals` digits after the decimal point, rounding
+    /// halfway cases away from zero. Values that already have no more than
+    /// `decimals` digits after the decimal point are returned unchanged
+    #[must_use]
+    pub fn round_to_scale(&self, decimals: i64) -> BigDecimal {
+        use num_traits::Signed;
+
+        let (digits, scale) = self.0.as_bigint_and_exponent();
+        if scale <= decimals {
+            return self.clone();
+        }
+
+        let divisor = num_traits::pow(num_bigint::BigInt::from(10), (scale - decimals) as usize);
+        let mut rounded = &digits / &divisor;
+        let remainder = &digits % &divisor;
+        if remainder.abs() * num_bigint::BigInt::from(2) >= divisor {
+            rounded += digits.signum();
+        }
+        Self::from(OldBigDecimal::new(rounded, decimals))
+    }
 }
 
 impl Display for BigDecimal {
@@ -656,6 +685,69 @@ mod test {
         }
     }
 
+    #[test]
+    fn round_to_scale() {
+        let vals = vec![
+            ("1.23456", 2, "1.23"),
+            ("1.235", 2, "1.24"),
+            ("-1.235", 2, "-1.24"),
+            ("0.4", 0, "0"),
+            ("0.5", 0, "1"),
+            ("1.5", 3, "1.5"),
+            ("1234", 2, "1234"),
+            ("0.000000000000000000123", 18, "0"),
+        ];
+
+        for (value, decimals, expected) in vals {
+            let value = BigDecimal::from_str(value).unwrap();
+            assert_eq!(expected, value.round_to_scale(decimals).to_string());
+        }
+    }
+
+    #[test]
+    fn arithmetic_rounding() {
+        fn dec(s: &str) -> BigDecimal {
+            BigDecimal::from_str(s).unwrap()
+        }
+
+        let quotients = vec![
+            ("1", "3", "0.3333333333333333333333333333333333"),
+            ("2", "3", "0.6666666666666666666666666666666667"),
+            ("1", "7", "0.1428571428571428571428571428571429"),
+            ("1", "8", "0.125"),
+            ("10", "4", "2.5"),
+            ("-1", "8", "-0.125"),
+            ("1", "-8", "-0.125"),
+            ("1000000", "0.001", "1000000000"),
+        ];
+        for (x, y, expected) in quotients {
+            assert_eq!(expected, (dec(x) / dec(y)).to_string(), "{} / {}", x, y);
+        }
+
+        let parsed = vec![
+            (
+                "12345678901234567890123456789012345",
+                "12345678901234567890123456789012350",
+            ),
+            (
+                "1.0000000000000000000000000000000005",
+                "1.000000000000000000000000000000001",
+            ),
+            ("1.0000000000000000000000000000000004", "1"),
+        ];
+        for (value, expected) in parsed {
+            assert_eq!(expected, dec(value).to_string());
+        }
+
+        let tiny = dec("0.0000000000000000000000000000000000000001");
+        assert_eq!("1", (dec("1") + tiny.clone()).to_string());
+        assert_eq!("1", (dec("1") - tiny).to_string());
+        assert_eq!(
+            "0.1111111111111111111111111111111111",
+            (dec("0.3333333333333333333333333333333333") / dec("3")).to_string()
+        );
+    }
+
     #[test]
     fn fmt_debug() {
         let bi = BigInt::from(-17);
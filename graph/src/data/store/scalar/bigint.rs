@@ -50,6 +50,12 @@ mod big_int {
             Ok(Self(inner))
         }
 
+        /// Checks that the result of one of the operators, which do not
+        /// check the digit limit, is still within `MAX_BITS`
+        pub fn checked(self) -> Result<Self, anyhow::Error> {
+            Self::new(self.0)
+        }
+
         /// Creates a BigInt without checking the digit limit.
         pub(in super::super) fn unchecked_new(inner: num_bigint::BigInt) -> Self {
             Self(inner)
@@ -222,6 +228,24 @@ impl BigInt {
 
         BigInt::new(self.inner().pow(&exponent))
     }
+
+    /// Fail if raising `self` to the power of `exponent` is certain to
+    /// produce a value that is too big, without computing it. The absolute
+    /// value of `self` is at least `2^(bits - 1)`, and the result therefore
+    /// has at least `(bits - 1) * exponent + 1` bits
+    pub fn check_pow(&self, exponent: u8) -> Result<(), anyhow::Error> {
+        let min_bits = self.bits().saturating_sub(1) * exponent as usize + 1;
+        if min_bits + 1 > BigInt::MAX_BITS as usize {
+            anyhow::bail!(
+                "BigInt is too big, raising a BigInt with {} bits to the power of {} needs at least {} bits (max {})",
+                self.bits(),
+                exponent,
+                min_bits + 1,
+                BigInt::MAX_BITS
+            );
+        }
+        Ok(())
+    }
 }
 
 impl From<i32> for BigInt {
@@ -388,4 +412,36 @@ mod test {
             BigInt::from_signed_bytes_le(&(-1i32).to_le_bytes()).unwrap(),
         );
     }
+
+    #[test]
+    fn pow_checks_size() {
+        assert_eq!(BigInt::from(1024), BigInt::from(2).pow(10).unwrap());
+        assert_eq!(BigInt::from(1), BigInt::from(0).pow(0).unwrap());
+        assert_eq!(BigInt::from(0), BigInt::from(0).pow(255).unwrap());
+        assert_eq!(BigInt::from(-1), BigInt::from(-1).pow(255).unwrap());
+
+        // 2^65025 is well within the limit; (2^64 - 1)^65025 is rejected
+        // without computing it
+        let small = BigInt::from(2).pow(255).unwrap();
+        small.check_pow(255).unwrap();
+        assert_eq!(255 * 255 + 1, small.pow(255).unwrap().bits());
+        let big = BigInt::from(u64::MAX).pow(255).unwrap();
+        let err = big.check_pow(255).unwrap_err();
+        assert!(err.to_string().starts_with("BigInt is too big"), "{err}");
+        BigInt::from(0).check_pow(255).unwrap();
+        BigInt::from(-1).check_pow(255).unwrap();
+    }
+
+    #[test]
+    fn checked_rejects_results_that_are_too_big() {
+        let big = BigInt::from_unsigned_bytes_le(&[255; 54426]).unwrap();
+        assert_eq!(435408, big.bits());
+        assert!((big.clone() + big.clone()).checked().is_ok());
+
+        let err = (big.clone() * big).checked().unwrap_err();
+        assert_eq!(
+            "BigInt is too big, total bits 870817 (max 435412)",
+            err.to_string()
+        );
+    }
 }
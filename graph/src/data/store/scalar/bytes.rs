@@ -34,6 +34,22 @@ impl Bytes {
     pub fn as_slice(&self) -> &[u8] {
         &self.0
     }
+
+    /// Parse a hex string the way users write it in queries. Unlike
+    /// `from_str`, this also accepts a `0X` prefix and an odd number of
+    /// digits, which is treated as if the string had a leading zero, so
+    /// that `0xAbC`, `0x0abc` and `abc` all result in the same value
+    pub fn from_query_str(s: &str) -> Result<Bytes, hex::FromHexError> {
+        let digits = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s);
+        if digits.len() % 2 == 1 {
+            hex::decode(format!("0{digits}")).map(|x| Bytes(x.into()))
+        } else {
+            hex::decode(digits).map(|x| Bytes(x.into()))
+        }
+    }
 }
 
 impl Display for Bytes {
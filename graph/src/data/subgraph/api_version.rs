@@ -21,6 +21,17 @@ pub const API_VERSION_0_0_8: Version = Version::new(0, 0, 8);
 /// Enables new host function `eth_get_balance`
 pub const API_VERSION_0_0_9: Version = Version::new(0, 0, 9);
 
+/// Enables new host functions `crypto.random`, `store.setMany`, and
+/// `block.timestamp`
+pub const API_VERSION_0_0_10: Version = Version::new(0, 0, 10);
+
+/// Makes `json.try_fromBytes` abort the handler when it runs out of gas
+/// instead of returning an error to the mapping, makes the BigInt host
+/// functions fail when their result is bigger than `BigInt::MAX_BITS`, and
+/// makes `store.set` fail deterministically when the entity does not match
+/// the schema
+pub const API_VERSION_0_0_11: Version = Version::new(0, 0, 11);
+
 /// Before this check was introduced, there were already subgraphs in the wild with spec version
 /// 0.0.3, due to confusion with the api version. To avoid breaking those, we accept 0.0.3 though it
 /// doesn't exist.
@@ -54,11 +65,38 @@ pub const SPEC_VERSION_1_1_0: Version = Version::new(1, 1, 0);
 // Enables eth call declarations
 pub const SPEC_VERSION_1_2_0: Version = Version::new(1, 2, 0);
 
+// Enables `@abstract` types that entity types can extend
+// Enables the `@scale` directive on `BigDecimal` fields
+pub const SPEC_VERSION_1_3_0: Version = Version::new(1, 3, 0);
+
 // The latest spec version available
-pub const LATEST_VERSION: &Version = &SPEC_VERSION_1_2_0;
+pub const LATEST_VERSION: &Version = &SPEC_VERSION_1_3_0;
 
 pub const MIN_SPEC_VERSION: Version = Version::new(0, 0, 2);
 
+/// All spec versions we know about, in increasing order. This needs to be
+/// updated whenever a new spec version is added
+const SPEC_VERSIONS: &[Version] = &[
+    MIN_SPEC_VERSION,
+    SPEC_VERSION_0_0_3,
+    SPEC_VERSION_0_0_4,
+    SPEC_VERSION_0_0_5,
+    SPEC_VERSION_0_0_6,
+    SPEC_VERSION_0_0_7,
+    SPEC_VERSION_0_0_8,
+    SPEC_VERSION_0_0_9,
+    SPEC_VERSION_1_0_0,
+    SPEC_VERSION_1_1_0,
+    SPEC_VERSION_1_2_0,
+    SPEC_VERSION_1_3_0,
+];
+
+/// Return all spec versions we know about, from `MIN_SPEC_VERSION` up to
+/// and including `LATEST_VERSION`
+pub fn spec_versions() -> impl Iterator<Item = &'static Version> {
+    SPEC_VERSIONS.iter()
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct UnifiedMappingApiVersion(Option<Version>);
 
@@ -135,3 +173,11 @@ fn unified_mapping_api_version_from_iterator() {
         }
     }
 }
+
+#[test]
+fn spec_versions_are_ordered() {
+    let versions: Vec<_> = spec_versions().collect();
+    assert_eq!(Some(&&MIN_SPEC_VERSION), versions.first());
+    assert_eq!(Some(&LATEST_VERSION), versions.last());
+    assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+}
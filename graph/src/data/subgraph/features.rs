@@ -35,6 +35,7 @@ pub enum SubgraphFeature {
     FullTextSearch,
     #[serde(alias = "nonDeterministicIpfs")]
     IpfsOnEthereumContracts,
+    MultipleNetworks,
 }
 
 impl fmt::Display for SubgraphFeature {
@@ -90,6 +91,7 @@ pub fn detect_features<C: Blockchain>(
         detect_grafting(manifest),
         detect_full_text_search(&manifest.schema),
         detect_ipfs_on_ethereum_contracts(manifest)?,
+        detect_multiple_networks(manifest),
     ]
     .into_iter()
     .flatten()
@@ -123,6 +125,12 @@ fn detect_full_text_search(schema: &InputSchema) -> Option<SubgraphFeature> {
     }
 }
 
+fn detect_multiple_networks<C: Blockchain>(
+    manifest: &SubgraphManifest<C>,
+) -> Option<SubgraphFeature> {
+    (manifest.networks().len() > 1).then_some(SubgraphFeature::MultipleNetworks)
+}
+
 pub struct InvalidMapping;
 
 impl From<InvalidMapping> for SubgraphFeatureValidationError {
@@ -148,17 +156,19 @@ fn detect_ipfs_on_ethereum_contracts<C: Blockchain>(
 mod tests {
     use super::*;
     use SubgraphFeature::*;
-    const VARIANTS: [SubgraphFeature; 4] = [
+    const VARIANTS: [SubgraphFeature; 5] = [
         NonFatalErrors,
         Grafting,
         FullTextSearch,
         IpfsOnEthereumContracts,
+        MultipleNetworks,
     ];
-    const STRING: [&str; 4] = [
+    const STRING: [&str; 5] = [
         "nonFatalErrors",
         "grafting",
         "fullTextSearch",
         "ipfsOnEthereumContracts",
+        "multipleNetworks",
     ];
 
     #[test]
@@ -49,7 +49,7 @@ use crate::{
     derive::CacheWeight,
     ensure,
     prelude::{r, Value, ENV_VARS},
-    schema::{InputSchema, SchemaValidationError},
+    schema::{InputSchema, SchemaLimits, SchemaValidationError},
 };
 
 use crate::prelude::{impl_slog_value, BlockNumber, Deserialize, Serialize};
@@ -109,14 +109,21 @@ impl_slog_value!(DeploymentHash);
 
 impl DeploymentHash {
     /// Check that `s` is a valid `SubgraphDeploymentId` and create a new one.
-    /// If `s` is longer than 46 characters, or contains characters other than
-    /// alphanumeric characters or `_`, return s (as a `String`) as the error
+    /// CIDv1 hashes like `bafy..` are accepted in any multibase encoding
+    /// and converted to their canonical base32 form. Otherwise, if `s` is
+    /// longer than 46 characters, the length of a CIDv0 hash `Qm..`, or
+    /// contains characters other than alphanumeric characters or `_`,
+    /// return s (as a `String`) as the error
     pub fn new(s: impl Into<String>) -> Result<Self, String> {
         let s = s.into();
 
-        // Enforce length limit
+        // Enforce length limit; CIDv1 hashes are always longer than CIDv0
+        // hashes
         if s.len() > 46 {
-            return Err(s);
+            return match cid::Cid::try_from(s.as_str()) {
+                Ok(cid) if cid.version() == cid::Version::V1 => Ok(DeploymentHash(cid.to_string())),
+                _ => Err(s),
+            };
         }
 
         // Check that the ID contains only allowed characters.
@@ -338,8 +345,11 @@ pub enum SubgraphManifestValidationError {
     NoDataSources,
     #[error("subgraph source address is required")]
     SourceAddressRequired,
-    #[error("subgraph cannot index data from different Ethereum networks")]
-    MultipleEthereumNetworks,
+    #[error(
+        "subgraph data sources use different networks ({}), which requires spec version 0.0.4 or later and the `multipleNetworks` feature",
+        .0.join(", ")
+    )]
+    MultipleEthereumNetworks(Vec<String>),
     #[error("subgraph must have at least one Ethereum network data source")]
     EthereumNetworkRequired,
     #[error("the specified block must exist on the Ethereum network")]
@@ -705,18 +715,16 @@ impl<C: Blockchain> UnvalidatedSubgraphManifest<C> {
             errors.push(different_api_versions.into());
         };
 
-        let mut networks = self
-            .0
-            .data_sources
-            .iter()
-            .filter_map(|d| Some(d.as_onchain()?.network()?.to_string()))
-            .collect::<Vec<String>>();
-        networks.sort();
-        networks.dedup();
+        // Using several networks needs the `multipleNetworks` feature,
+        // which `validate_subgraph_features` checks below
+        let networks = self.0.networks();
         match networks.len() {
             0 => errors.push(SubgraphManifestValidationError::EthereumNetworkRequired),
             1 => (),
-            _ => errors.push(SubgraphManifestValidationError::MultipleEthereumNetworks),
+            _ if self.0.spec_version < SPEC_VERSION_0_0_4 => errors.push(
+                SubgraphManifestValidationError::MultipleEthereumNetworks(networks),
+            ),
+            _ => (),
         }
 
         if let Some(graft) = &self.0.graft {
@@ -727,6 +735,18 @@ impl<C: Blockchain> UnvalidatedSubgraphManifest<C> {
             }
         }
 
+        if let Err(schema_errors) = self.0.schema.check_limits(&SchemaLimits::default()) {
+            errors.push(SubgraphManifestValidationError::SchemaValidationError(
+                schema_errors,
+            ));
+        }
+
+        if let Err(schema_errors) = self.0.schema.validate_for_deployment(&self.0.spec_version) {
+            errors.push(SubgraphManifestValidationError::SchemaValidationError(
+                schema_errors,
+            ));
+        }
+
         // Validate subgraph feature usage and declaration.
         if self.0.spec_version >= SPEC_VERSION_0_0_4 {
             if let Err(feature_validation_error) = validate_subgraph_features(&self.0) {
@@ -763,18 +783,42 @@ impl<C: Blockchain> SubgraphManifest<C> {
         Ok(resolved)
     }
 
+    /// The main network of the subgraph, i.e., the network of its first
+    /// data source. The deployment belongs to that network, and its block
+    /// stream drives indexing; see `networks` for subgraphs with data
+    /// sources on several networks
     pub fn network_name(&self) -> String {
-        // Assume the manifest has been validated, ensuring network names are homogenous
+        // Assume the manifest has been validated, ensuring that there is a network
         self.data_sources
             .iter()
             .find_map(|d| Some(d.as_onchain()?.network()?.to_string()))
             .expect("Validated manifest does not have a network defined on any datasource")
     }
 
+    /// All networks that data sources use, in the order in which they
+    /// first appear in the manifest. The first one is the main network
+    pub fn networks(&self) -> Vec<String> {
+        let mut networks: Vec<String> = Vec::new();
+        for network in self
+            .data_sources
+            .iter()
+            .filter_map(|d| d.as_onchain()?.network())
+        {
+            if !networks.iter().any(|n| n == network) {
+                networks.push(network.to_string());
+            }
+        }
+        networks
+    }
+
+    /// The start blocks of the data sources on the main network
     pub fn start_blocks(&self) -> Vec<BlockNumber> {
+        let network = self.network_name();
         self.data_sources
             .iter()
-            .filter_map(|d| Some(d.as_onchain()?.start_block()))
+            .filter_map(|d| d.as_onchain())
+            .filter(|d| d.network().map_or(true, |n| n == network))
+            .map(|d| d.start_block())
             .collect()
     }
 
@@ -1075,6 +1119,38 @@ fn display_vector(input: &[impl std::fmt::Display]) -> impl std::fmt::Display {
     format!("[{}]", formatted_errors)
 }
 
+#[test]
+fn test_deployment_hash_validation() {
+    // CIDv0 and CIDv1 of the empty UnixFS directory
+    const CID_V0: &str = "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn";
+    const CID_V1: &str = "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354";
+
+    assert_eq!(CID_V0, DeploymentHash::new(CID_V0).unwrap().as_str());
+    assert_eq!(CID_V1, DeploymentHash::new(CID_V1).unwrap().as_str());
+    // CIDv1 in other encodings is converted to base32
+    assert_eq!(
+        CID_V1,
+        DeploymentHash::new("zdj7WbTaiJT1fgatdet9Ei9iDB5hdCxkbVyhyh8YTUnXMiwYi")
+            .unwrap()
+            .as_str()
+    );
+    assert_eq!(
+        CID_V1,
+        DeploymentHash::new(CID_V1.to_uppercase()).unwrap().as_str()
+    );
+    // A raw CIDv1 as used for files
+    assert!(
+        DeploymentHash::new("bafkreigkhuldxkyfkoaye4rgcqcwr45667vkygd45plwq6hawy7j4rbdky").is_ok()
+    );
+
+    assert!(DeploymentHash::new("testsubgraph").is_ok());
+    assert!(DeploymentHash::new("subgraphs").is_err());
+    assert!(DeploymentHash::new("not-a-hash").is_err());
+    // Long strings that are not CIDv1 and truncated CIDv1
+    assert!(DeploymentHash::new(format!("{}x", CID_V0)).is_err());
+    assert!(DeploymentHash::new(&CID_V1[..58]).is_err());
+}
+
 #[test]
 fn test_subgraph_name_validation() {
     assert!(SubgraphName::new("a").is_ok());
@@ -5,6 +5,8 @@ use crate::blockchain::BlockHash;
 use crate::components::store::{BlockNumber, DeploymentId};
 use crate::data::graphql::{object, IntoValue};
 use crate::prelude::{r, BlockPtr, Value};
+use serde::Serialize;
+use std::sync::Arc;
 
 pub enum Filter {
     /// Get all versions for the named subgraph
@@ -104,6 +106,8 @@ pub struct Info {
     pub fatal_error: Option<SubgraphError>,
     pub non_fatal_errors: Vec<SubgraphError>,
     pub paused: Option<bool>,
+    /// The reason that was given when the deployment was paused
+    pub paused_reason: Option<String>,
 
     /// Indexing status on different chains involved in the subgraph's data sources.
     pub chains: Vec<ChainInfo>,
@@ -114,10 +118,131 @@ pub struct Info {
     pub node: Option<String>,
 
     pub history_blocks: i32,
+
+    /// The most recent reorgs of the deployment, oldest first. These are
+    /// only tracked in memory while the deployment is running
+    pub recent_reorgs: Vec<Reorg>,
+
+    /// How many blocks per second the deployment has indexed recently.
+    /// This is only tracked in memory while the deployment is running
+    pub blocks_per_second: Option<f64>,
+
+    /// How much space the deployment takes up in the database
+    pub storage: Option<Arc<StorageStats>>,
+}
+
+impl Info {
+    /// Estimate how many seconds it will take the deployment to reach the
+    /// chain head if it keeps indexing at its recent speed. There is no
+    /// estimate if the deployment is paused or failed, or if it has not
+    /// made any progress recently
+    pub fn seconds_to_head(&self) -> Option<u64> {
+        if self.paused == Some(true) || self.fatal_error.is_some() {
+            return None;
+        }
+
+        let chain = self.chains.first()?;
+        let head = chain.chain_head_block.as_ref()?.number();
+        let latest = chain
+            .latest_block
+            .as_ref()
+            .map(|block| block.number())
+            .unwrap_or(chain.earliest_block_number);
+        if latest >= head {
+            return Some(0);
+        }
+
+        let blocks_per_second = self.blocks_per_second.filter(|bps| *bps > 0.0)?;
+        Some(((head - latest) as f64 / blocks_per_second).ceil() as u64)
+    }
+}
+
+/// A revert of a deployment to an earlier block because of a reorg
+#[derive(Clone, Debug)]
+pub struct Reorg {
+    /// The block the deployment was reverted to
+    pub block_number: BlockNumber,
+    /// How many blocks were reverted
+    pub depth: BlockNumber,
+    /// When the revert happened, in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl IntoValue for Reorg {
+    fn into_value(self) -> r::Value {
+        let Reorg {
+            block_number,
+            depth,
+            timestamp,
+        } = self;
+
+        object! {
+            __typename: "Reorg",
+            blockNumber: block_number,
+            depth: depth,
+            timestamp: format!("{}", timestamp),
+        }
+    }
+}
+
+/// How much space a deployment takes up in the database. The numbers are
+/// based on Postgres' table statistics and are therefore estimates
+#[derive(Clone, Debug)]
+pub struct StorageStats {
+    /// The size of all the tables of the deployment, including their
+    /// indexes
+    pub storage_bytes: u64,
+    pub entity_types: Vec<EntityTypeStorage>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EntityTypeStorage {
+    pub entity_type: String,
+    /// The number of entities, not counting older versions of them
+    pub entity_count: u64,
+    /// The size of the table for the entity type, including its indexes
+    pub storage_bytes: u64,
+}
+
+impl IntoValue for EntityTypeStorage {
+    fn into_value(self) -> r::Value {
+        let EntityTypeStorage {
+            entity_type,
+            entity_count,
+            storage_bytes,
+        } = self;
+
+        object! {
+            __typename: "EntityTypeStorage",
+            entityType: entity_type,
+            entityCount: format!("{}", entity_count),
+            storageBytes: format!("{}", storage_bytes),
+        }
+    }
+}
+
+/// A snapshot of how busy one of the connection pools for a database
+/// shard is
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStatus {
+    pub shard: String,
+    /// The name of the pool; `main` for the pool that is used for writes,
+    /// and the name of the replica for pools to read replicas
+    pub pool: String,
+    /// The configured maximum number of connections
+    pub max_connections: u32,
+    /// The number of connections that are currently open
+    pub connections: u32,
+    /// The number of open connections that are checked out
+    pub in_use: u32,
+    /// The number of callers waiting to check out a connection
+    pub waiting: u32,
 }
 
 impl IntoValue for Info {
     fn into_value(self) -> r::Value {
+        let seconds_to_head = self.seconds_to_head();
         let Info {
             id: _,
             subgraph,
@@ -126,10 +251,14 @@ impl IntoValue for Info {
             fatal_error,
             health,
             paused,
+            paused_reason,
             node,
             non_fatal_errors,
             synced,
             history_blocks,
+            recent_reorgs,
+            blocks_per_second: _,
+            storage,
         } = self;
 
         fn subgraph_error_to_value(subgraph_error: SubgraphError) -> r::Value {
@@ -167,12 +296,17 @@ impl IntoValue for Info {
             synced: synced,
             health: r::Value::from(health),
             paused: paused,
+            pausedReason: paused_reason,
             fatalError: fatal_error_val,
             nonFatalErrors: non_fatal_errors,
             chains: chains.into_iter().map(|chain| chain.into_value()).collect::<Vec<_>>(),
             entityCount: format!("{}", entity_count),
             node: node,
             historyBlocks: history_blocks,
+            recentReorgs: recent_reorgs.into_iter().map(|reorg| reorg.into_value()).collect::<Vec<_>>(),
+            estimatedSecondsToHead: seconds_to_head.map(|secs| format!("{}", secs)),
+            storageBytes: storage.as_ref().map(|storage| format!("{}", storage.storage_bytes)),
+            entityTypes: storage.map(|storage| storage.entity_types.clone()),
         }
     }
 }
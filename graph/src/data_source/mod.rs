@@ -142,6 +142,13 @@ impl<C: Blockchain> DataSource<C> {
         }
     }
 
+    pub fn network(&self) -> Option<&str> {
+        match self {
+            Self::Onchain(ds) => ds.network(),
+            Self::Offchain(_) => None,
+        }
+    }
+
     pub fn creation_block(&self) -> Option<BlockNumber> {
         match self {
             Self::Onchain(ds) => ds.creation_block(),
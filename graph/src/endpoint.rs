@@ -1,12 +1,14 @@
 use std::{
     collections::HashMap,
+    num::NonZeroU32,
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc,
+        Arc, Mutex,
     },
+    time::{Duration, Instant},
 };
 
-use prometheus::IntCounterVec;
+use prometheus::{CounterVec, IntCounterVec};
 use slog::{warn, Logger};
 
 use crate::{components::metrics::MetricsRegistry, data::value::Word};
@@ -67,6 +69,7 @@ pub struct EndpointMetrics {
     logger: Logger,
     providers: ProviderCount,
     counter: Box<IntCounterVec>,
+    rate_limit_wait: Box<CounterVec>,
 }
 
 impl std::fmt::Debug for EndpointMetrics {
@@ -95,10 +98,19 @@ impl EndpointMetrics {
             )
             .expect("unable to create endpoint_request counter_vec");
 
+        let rate_limit_wait = registry
+            .new_counter_vec(
+                "endpoint_rate_limit_wait_seconds",
+                "time requests spent waiting for the rate limit of their provider",
+                vec!["conn_type".to_string(), "provider".to_string()],
+            )
+            .expect("unable to create endpoint_rate_limit_wait_seconds counter_vec");
+
         Self {
             logger,
             providers,
             counter,
+            rate_limit_wait,
         }
     }
 
@@ -159,6 +171,18 @@ impl EndpointMetrics {
             .inc();
     }
 
+    /// Record that requests to `provider` were held back for `wait` by the
+    /// provider's rate limit
+    pub fn rate_limited(&self, provider: &Provider, conn_type: &ConnectionType, wait: Duration) {
+        if wait.is_zero() {
+            return;
+        }
+
+        self.rate_limit_wait
+            .with_label_values(&[conn_type.into(), provider.as_str()])
+            .inc_by(wait.as_secs_f64());
+    }
+
     /// Returns the current error count of a host or 0 if the host
     /// doesn't have a value on the map.
     pub fn get_count(&self, provider: &Provider) -> u64 {
@@ -169,15 +193,75 @@ impl EndpointMetrics {
     }
 }
 
+/// A token bucket that limits how many requests are sent to a provider.
+/// Up to `requests_per_second` requests can be sent in a burst; requests
+/// beyond that are not rejected but wait until it is their turn. All
+/// connections to the same provider should share one `RateLimiter`
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_second: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// The number of requests that can be sent right away. This becomes
+    /// negative when requests are waiting for their turn
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: NonZeroU32) -> Self {
+        let requests_per_second = requests_per_second.get() as f64;
+        Self {
+            requests_per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: requests_per_second,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until `requests` more requests may be sent and return how long
+    /// that took
+    pub async fn acquire(&self, requests: usize) -> Duration {
+        let wait = self.reserve(requests, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        wait
+    }
+
+    /// Take `requests` tokens from the bucket and return how long the
+    /// caller has to wait until the tokens have been refilled
+    fn reserve(&self, requests: usize, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.requests_per_second)
+            .min(self.requests_per_second);
+        bucket.last_refill = now;
+        bucket.tokens -= requests as f64;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.requests_per_second)
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use std::num::NonZeroU32;
     use std::sync::Arc;
+    use std::time::{Duration, Instant};
 
     use slog::{o, Discard, Logger};
 
     use crate::{
         components::metrics::MetricsRegistry,
-        endpoint::{EndpointMetrics, Provider},
+        endpoint::{EndpointMetrics, Provider, RateLimiter},
     };
 
     #[tokio::test]
@@ -198,4 +282,25 @@ mod test {
         assert_eq!(metrics.get_count(&b), 2);
         assert_eq!(metrics.get_count(&c), 0);
     }
+
+    #[test]
+    fn rate_limiter_delays_requests_over_the_limit() {
+        let limiter = RateLimiter::new(NonZeroU32::new(10).unwrap());
+        let start = Instant::now();
+
+        assert_eq!(Duration::ZERO, limiter.reserve(10, start));
+        assert_eq!(Duration::from_millis(100), limiter.reserve(1, start));
+        assert_eq!(Duration::from_millis(200), limiter.reserve(1, start));
+
+        // After a second, the two requests that had to wait have been paid
+        // for and the bucket has room for eight more
+        let later = start + Duration::from_secs(1);
+        assert_eq!(Duration::ZERO, limiter.reserve(8, later));
+        assert_eq!(Duration::from_millis(100), limiter.reserve(1, later));
+
+        // The bucket never holds more than a second's worth of requests
+        let much_later = later + Duration::from_secs(60);
+        assert_eq!(Duration::ZERO, limiter.reserve(10, much_later));
+        assert_eq!(Duration::from_millis(100), limiter.reserve(1, much_later));
+    }
 }
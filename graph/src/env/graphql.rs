@@ -1,4 +1,7 @@
-use std::fmt;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
 use super::*;
 
@@ -59,12 +62,21 @@ pub struct EnvVarsGraphQl {
     /// value of 0 means that there is no limit on the size of a cache
     /// entry.
     pub query_cache_max_entry_ratio: usize,
+    /// How long cached query results can be served before they need to be
+    /// recomputed. Set by `GRAPH_QUERY_CACHE_TTL` (expressed in seconds).
+    /// By default, cached results do not expire.
+    pub query_cache_ttl: Option<Duration>,
     /// Set by the environment variable `GRAPH_GRAPHQL_QUERY_TIMEOUT` (expressed in
     /// seconds). No default value is provided.
     pub query_timeout: Option<Duration>,
     /// Set by the environment variable `GRAPH_GRAPHQL_MAX_COMPLEXITY`. No
     /// default value is provided.
     pub max_complexity: Option<u64>,
+    /// Maximum complexities for individual deployments that take
+    /// precedence over `max_complexity`, keyed by deployment hash. Set by
+    /// `GRAPH_GRAPHQL_MAX_COMPLEXITY_OVERRIDES` as a comma-separated list
+    /// of `<deployment hash>=<max complexity>`. Empty by default
+    pub max_complexity_overrides: HashMap<String, u64>,
     /// Set by the environment variable `GRAPH_GRAPHQL_MAX_DEPTH`. The default
     /// value is 255.
     pub max_depth: u8,
@@ -103,6 +115,28 @@ pub struct EnvVarsGraphQl {
     /// Set by the env var `GRAPH_PARALLEL_BLOCK_CONSTRAINTS`
     /// Whether to run top-level queries with different block constraints in parallel
     pub parallel_block_constraints: bool,
+    /// The deployments whose API schema has `<type>History` queries that
+    /// return all versions of an entity. Set by
+    /// `GRAPH_GRAPHQL_HISTORY_QUERIES` as a comma-separated list of
+    /// deployment hashes. Empty by default
+    pub history_queries: HashSet<String>,
+    /// Set by `GRAPH_GRAPHQL_SLOW_QUERY_THRESHOLD` (expressed in
+    /// milliseconds). Queries that take at least this long are logged
+    /// together with a breakdown of their SQL timing. Off by default
+    pub slow_query_threshold: Option<Duration>,
+    /// Set by the flag `GRAPH_GRAPHQL_SLOW_QUERY_REDACT`. Whether to remove
+    /// string literals and variables from the query text in slow query
+    /// logs. Off by default
+    pub slow_query_redact: bool,
+    /// Set by `GRAPH_GRAPHQL_DISABLED_FIELDS_FILE`, the path to a TOML file
+    /// that lists the API fields that are disabled for some deployments.
+    /// The file is reloaded when it changes. Not set by default
+    pub disabled_fields_file: Option<String>,
+    /// Set by `GRAPH_GRAPHQL_STREAM_RESPONSE_THRESHOLD` (expressed in
+    /// bytes). Results whose estimated size in memory is above this are
+    /// serialized incrementally and streamed to the client instead of
+    /// being turned into one JSON string first. Off by default
+    pub stream_response_threshold: Option<usize>,
 }
 
 // This does not print any values avoid accidentally leaking any sensitive env vars
@@ -112,6 +146,23 @@ impl fmt::Debug for EnvVarsGraphQl {
     }
 }
 
+impl EnvVarsGraphQl {
+    /// The maximum complexity for queries against `deployment`; an
+    /// override for the deployment takes precedence over `max_complexity`,
+    /// which is usually the global limit `self.max_complexity`
+    pub fn max_complexity_for(&self, deployment: &str, max_complexity: Option<u64>) -> Option<u64> {
+        self.max_complexity_overrides
+            .get(deployment)
+            .copied()
+            .or(max_complexity)
+    }
+
+    /// Whether the API schema for `deployment` should have history queries
+    pub fn history_queries_for(&self, deployment: &str) -> bool {
+        self.history_queries.contains(deployment)
+    }
+}
+
 impl From<InnerGraphQl> for EnvVarsGraphQl {
     fn from(x: InnerGraphQl) -> Self {
         Self {
@@ -136,8 +187,10 @@ impl From<InnerGraphQl> for EnvVarsGraphQl {
             query_cache_max_mem: x.query_cache_max_mem_in_mb.0 * 1000 * 1000,
             query_cache_stale_period: x.query_cache_stale_period,
             query_cache_max_entry_ratio: x.query_cache_max_entry_ratio,
+            query_cache_ttl: x.query_cache_ttl_in_secs.map(Duration::from_secs),
             query_timeout: x.query_timeout_in_secs.map(Duration::from_secs),
             max_complexity: x.max_complexity.map(|x| x.0),
+            max_complexity_overrides: x.max_complexity_overrides.0,
             max_depth: x.max_depth.0,
             max_first: x.max_first,
             max_skip: x.max_skip.0,
@@ -149,6 +202,17 @@ impl From<InnerGraphQl> for EnvVarsGraphQl {
             disable_child_sorting: x.disable_child_sorting.0,
             query_trace_token: x.query_trace_token,
             parallel_block_constraints: x.parallel_block_constraints.0,
+            history_queries: x
+                .history_queries
+                .split(',')
+                .map(str::trim)
+                .filter(|deployment| !deployment.is_empty())
+                .map(str::to_string)
+                .collect(),
+            slow_query_threshold: x.slow_query_threshold_in_ms.map(Duration::from_millis),
+            slow_query_redact: x.slow_query_redact.0,
+            disabled_fields_file: x.disabled_fields_file,
+            stream_response_threshold: x.stream_response_threshold,
         }
     }
 }
@@ -176,10 +240,14 @@ pub struct InnerGraphQl {
     query_cache_stale_period: u64,
     #[envconfig(from = "GRAPH_QUERY_CACHE_MAX_ENTRY_RATIO", default = "3")]
     query_cache_max_entry_ratio: usize,
+    #[envconfig(from = "GRAPH_QUERY_CACHE_TTL")]
+    query_cache_ttl_in_secs: Option<u64>,
     #[envconfig(from = "GRAPH_GRAPHQL_QUERY_TIMEOUT")]
     query_timeout_in_secs: Option<u64>,
     #[envconfig(from = "GRAPH_GRAPHQL_MAX_COMPLEXITY")]
     max_complexity: Option<NoUnderscores<u64>>,
+    #[envconfig(from = "GRAPH_GRAPHQL_MAX_COMPLEXITY_OVERRIDES", default = "")]
+    max_complexity_overrides: ComplexityOverrides,
     #[envconfig(from = "GRAPH_GRAPHQL_MAX_DEPTH", default = "")]
     max_depth: WithDefaultUsize<u8, { u8::MAX as usize }>,
     #[envconfig(from = "GRAPH_GRAPHQL_MAX_FIRST", default = "1000")]
@@ -202,4 +270,96 @@ pub struct InnerGraphQl {
     query_trace_token: String,
     #[envconfig(from = "GRAPH_PARALLEL_BLOCK_CONSTRAINTS", default = "false")]
     pub parallel_block_constraints: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_GRAPHQL_HISTORY_QUERIES", default = "")]
+    history_queries: String,
+    #[envconfig(from = "GRAPH_GRAPHQL_SLOW_QUERY_THRESHOLD")]
+    slow_query_threshold_in_ms: Option<u64>,
+    #[envconfig(from = "GRAPH_GRAPHQL_SLOW_QUERY_REDACT", default = "false")]
+    slow_query_redact: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_GRAPHQL_DISABLED_FIELDS_FILE")]
+    disabled_fields_file: Option<String>,
+    #[envconfig(from = "GRAPH_GRAPHQL_STREAM_RESPONSE_THRESHOLD")]
+    stream_response_threshold: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct ComplexityOverrides(HashMap<String, u64>);
+
+impl FromStr for ComplexityOverrides {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (deployment, max) = entry.split_once('=').ok_or_else(|| {
+                    format!(
+                        "invalid entry `{entry}`: expected `<deployment hash>=<max complexity>`"
+                    )
+                })?;
+                let max = NoUnderscores::<u64>::from_str(max.trim())
+                    .map_err(|e| format!("invalid max complexity in `{entry}`: {e}"))?;
+                Ok((deployment.trim().to_string(), max.0))
+            })
+            .collect::<Result<_, _>>()
+            .map(ComplexityOverrides)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use envconfig::Envconfig;
+
+    use super::{ComplexityOverrides, EnvVarsGraphQl, InnerGraphQl};
+
+    fn env_vars(vars: &[(&str, &str)]) -> EnvVarsGraphQl {
+        let vars = HashMap::from_iter(
+            vars.iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        );
+        InnerGraphQl::init_from_hashmap(&vars).unwrap().into()
+    }
+
+    #[test]
+    fn parse_complexity_overrides() {
+        let overrides = ComplexityOverrides::from_str(" QmA=1_000 , QmB = 20,,").unwrap();
+        assert_eq!(
+            HashMap::from_iter([("QmA".to_string(), 1000), ("QmB".to_string(), 20)]),
+            overrides.0
+        );
+        assert!(ComplexityOverrides::from_str("").unwrap().0.is_empty());
+
+        for bad in ["QmA", "QmA=", "QmA=ten", "QmA=-1", "QmA=1,QmB"] {
+            let err = ComplexityOverrides::from_str(bad).unwrap_err();
+            assert!(err.contains("invalid"), "`{bad}` gave error `{err}`");
+        }
+    }
+
+    #[test]
+    fn max_complexity_for_deployment() {
+        let env = env_vars(&[
+            ("GRAPH_GRAPHQL_MAX_COMPLEXITY", "100"),
+            ("GRAPH_GRAPHQL_MAX_COMPLEXITY_OVERRIDES", "QmA=1000"),
+        ]);
+        assert_eq!(Some(100), env.max_complexity);
+        assert_eq!(
+            Some(1000),
+            env.max_complexity_for("QmA", env.max_complexity)
+        );
+        assert_eq!(Some(1000), env.max_complexity_for("QmA", None));
+        // Unknown deployments use the limit they are given
+        assert_eq!(Some(100), env.max_complexity_for("QmB", env.max_complexity));
+        assert_eq!(Some(7), env.max_complexity_for("QmB", Some(7)));
+        assert_eq!(None, env.max_complexity_for("QmB", None));
+
+        let vars = HashMap::from_iter([(
+            "GRAPH_GRAPHQL_MAX_COMPLEXITY_OVERRIDES".to_string(),
+            "QmA=lots".to_string(),
+        )]);
+        assert!(InnerGraphQl::init_from_hashmap(&vars).is_err());
+    }
 }
@@ -15,8 +15,16 @@ pub struct EnvVarsMapping {
     /// Set by the environment variable `GRAPH_ENTITY_CACHE_SIZE` (expressed in
     /// kilobytes). The default value is 10 megabytes.
     pub entity_cache_size: usize,
+    /// A soft limit on the size of the entity changes of a single block.
+    /// Blocks whose changes take up more space than this in the entity
+    /// cache are logged with a warning so that problem blocks can be
+    /// spotted.
+    ///
+    /// Set by the environment variable `GRAPH_ENTITY_CACHE_BLOCK_SOFT_LIMIT`
+    /// (expressed in kilobytes). Off by default.
+    pub entity_cache_block_soft_limit: Option<usize>,
     /// Set by the environment variable `GRAPH_MAX_API_VERSION`. The default
-    /// value is `0.0.8`.
+    /// value is `0.0.11`.
     pub max_api_version: Version,
     /// Set by the environment variable `GRAPH_MAPPING_HANDLER_TIMEOUT`
     /// (expressed in seconds). No default is provided.
@@ -38,6 +46,12 @@ pub struct EnvVarsMapping {
     /// Set by the environment variable `GRAPH_IPFS_TIMEOUT` (expressed in
     /// seconds). The default value is 60s.
     pub ipfs_timeout: Duration,
+    /// How often a request is attempted against one IPFS client before
+    /// falling back to the next one when several clients are configured.
+    ///
+    /// Set by the environment variable `GRAPH_IPFS_ATTEMPTS_PER_CLIENT`.
+    /// The default value is 3.
+    pub ipfs_attempts_per_client: usize,
     /// Sets the `ipfs.map` file size limit.
     ///
     /// Set by the environment variable `GRAPH_MAX_IPFS_MAP_FILE_SIZE_LIMIT`
@@ -76,6 +90,9 @@ impl From<InnerMappingHandlers> for EnvVarsMapping {
         Self {
             entity_cache_dead_weight: x.entity_cache_dead_weight.0,
             entity_cache_size: x.entity_cache_size_in_kb * 1000,
+            entity_cache_block_soft_limit: x
+                .entity_cache_block_soft_limit_in_kb
+                .map(|limit| limit * 1000),
 
             max_api_version: x.max_api_version,
             timeout: x.mapping_handler_timeout_in_secs.map(Duration::from_secs),
@@ -84,6 +101,7 @@ impl From<InnerMappingHandlers> for EnvVarsMapping {
             max_ipfs_cache_file_size: x.max_ipfs_cache_file_size.0,
             max_ipfs_cache_size: x.max_ipfs_cache_size,
             ipfs_timeout: Duration::from_secs(x.ipfs_timeout_in_secs),
+            ipfs_attempts_per_client: x.ipfs_attempts_per_client,
             max_ipfs_map_file_size: x.max_ipfs_map_file_size.0,
             max_ipfs_file_bytes: x.max_ipfs_file_bytes.0,
             ipfs_request_limit: x.ipfs_request_limit,
@@ -99,7 +117,9 @@ pub struct InnerMappingHandlers {
     entity_cache_dead_weight: EnvVarBoolean,
     #[envconfig(from = "GRAPH_ENTITY_CACHE_SIZE", default = "10000")]
     entity_cache_size_in_kb: usize,
-    #[envconfig(from = "GRAPH_MAX_API_VERSION", default = "0.0.9")]
+    #[envconfig(from = "GRAPH_ENTITY_CACHE_BLOCK_SOFT_LIMIT")]
+    entity_cache_block_soft_limit_in_kb: Option<usize>,
+    #[envconfig(from = "GRAPH_MAX_API_VERSION", default = "0.0.11")]
     max_api_version: Version,
     #[envconfig(from = "GRAPH_MAPPING_HANDLER_TIMEOUT")]
     mapping_handler_timeout_in_secs: Option<u64>,
@@ -113,6 +133,8 @@ pub struct InnerMappingHandlers {
     max_ipfs_cache_size: u64,
     #[envconfig(from = "GRAPH_IPFS_TIMEOUT", default = "60")]
     ipfs_timeout_in_secs: u64,
+    #[envconfig(from = "GRAPH_IPFS_ATTEMPTS_PER_CLIENT", default = "3")]
+    ipfs_attempts_per_client: usize,
     #[envconfig(from = "GRAPH_MAX_IPFS_MAP_FILE_SIZE", default = "")]
     max_ipfs_map_file_size: WithDefaultUsize<usize, { 256 * 1024 * 1024 }>,
     #[envconfig(from = "GRAPH_MAX_IPFS_FILE_BYTES", default = "")]
@@ -65,11 +65,38 @@ pub struct EnvVars {
     ///
     /// Set by the flag `GRAPH_LOAD_SIMULATE`.
     pub load_simulate: bool,
+    /// The maximum number of queries that may run against a shard at the
+    /// same time. Queries beyond that wait for up to
+    /// [`EnvVars::load_admission_wait`] and are rejected if no slot opens
+    /// up in that time.
+    ///
+    /// Set by the environment variable `GRAPH_LOAD_MAX_IN_FLIGHT`. The
+    /// default value is 0, which disables admission control.
+    pub load_max_in_flight: usize,
+    /// Set by the environment variable `GRAPH_LOAD_ADMISSION_WAIT`
+    /// (expressed in milliseconds). The default value is 100ms.
+    pub load_admission_wait: Duration,
     /// Set by the flag `GRAPH_ALLOW_NON_DETERMINISTIC_FULLTEXT_SEARCH`, but
     /// enabled anyway (overridden) if [debug
     /// assertions](https://doc.rust-lang.org/reference/conditional-compilation.html#debug_assertions)
     /// are enabled.
     pub allow_non_deterministic_fulltext_search: bool,
+    /// The maximum number of entity types and aggregations in the schema
+    /// of a subgraph that is being deployed. Set by the environment
+    /// variable `GRAPH_SCHEMA_MAX_ENTITY_TYPES`. The default value is 1000.
+    pub schema_max_entity_types: usize,
+    /// The maximum number of fields of a type in the schema of a subgraph
+    /// that is being deployed. Set by the environment variable
+    /// `GRAPH_SCHEMA_MAX_FIELDS_PER_TYPE`. The default value is 1000.
+    pub schema_max_fields_per_type: usize,
+    /// The maximum number of values of an enum in the schema of a subgraph
+    /// that is being deployed. Set by the environment variable
+    /// `GRAPH_SCHEMA_MAX_ENUM_VALUES`. The default value is 10000.
+    pub schema_max_enum_values: usize,
+    /// The maximum number of `@fulltext` indexes in the schema of a
+    /// subgraph that is being deployed. Set by the environment variable
+    /// `GRAPH_SCHEMA_MAX_FULLTEXT_INDEXES`. The default value is 100.
+    pub schema_max_fulltext_indexes: usize,
     /// Set by the environment variable `GRAPH_MAX_SPEC_VERSION`. The default
     /// value is `0.0.7`.
     pub max_spec_version: Version,
@@ -126,6 +153,11 @@ pub struct EnvVars {
     /// Set by the environment variable `GRAPH_KILL_IF_UNRESPONSIVE_TIMEOUT_SECS`
     /// (expressed in seconds). The default value is 10s.
     pub kill_if_unresponsive_timeout: Duration,
+    /// How long to wait for in-flight queries and pending writes when the
+    /// node receives `SIGTERM` before exiting anyway.
+    /// Set by the environment variable `GRAPH_SHUTDOWN_TIMEOUT_SECS`
+    /// (expressed in seconds). The default value is 20s.
+    pub shutdown_timeout: Duration,
     /// Guards public access to POIs in the `index-node`.
     ///
     /// Set by the environment variable `GRAPH_POI_ACCESS_TOKEN`. No default
@@ -179,6 +211,11 @@ pub struct EnvVars {
     /// Set by the environment variable `ETHEREUM_REORG_THRESHOLD`. The default
     /// value is 250 blocks.
     pub reorg_threshold: BlockNumber,
+    /// Reverts that go back more than this many blocks are logged as a
+    /// warning and counted in the `deployment_deep_reorgs` metric. Set by
+    /// the environment variable `GRAPH_DEEP_REORG_THRESHOLD`. The default
+    /// value is 10 blocks.
+    pub deep_reorg_threshold: BlockNumber,
     /// The time to wait between polls when using polling block ingestor.
     /// The value is set by `ETHERUM_POLLING_INTERVAL` in millis and the
     /// default is 1000.
@@ -188,6 +225,23 @@ pub struct EnvVars {
     pub subgraph_settings: Option<String>,
     /// Whether to prefer substreams blocks streams over firehose when available.
     pub prefer_substreams_block_streams: bool,
+    /// Ceiling for the backoff when reconnecting a Firehose block stream.
+    ///
+    /// Set by the environment variable `GRAPH_FIREHOSE_BACKOFF_CEIL_SECS`
+    /// (expressed in seconds). The default value is 45s.
+    pub firehose_backoff_ceil: Duration,
+    /// Jitter factor for the backoff when reconnecting a Firehose block
+    /// stream.
+    ///
+    /// Set by the environment variable `GRAPH_FIREHOSE_BACKOFF_JITTER`
+    /// (clamped between 0.0 and 1.0). The default value is 0.2.
+    pub firehose_backoff_jitter: f64,
+    /// How long a Firehose block stream has to deliver blocks before the
+    /// backoff for reconnecting it is reset.
+    ///
+    /// Set by the environment variable `GRAPH_FIREHOSE_BACKOFF_RESET_SECS`
+    /// (expressed in seconds). The default value is 30s.
+    pub firehose_backoff_reset: Duration,
     /// Set by the flag `GRAPH_ENABLE_DIPS_METRICS`. Whether to enable
     /// gas metrics. Off by default.
     pub enable_dips_metrics: bool,
@@ -220,10 +274,16 @@ impl EnvVars {
             load_threshold: Duration::from_millis(inner.load_threshold_in_ms),
             load_jail_threshold: inner.load_jail_threshold,
             load_simulate: inner.load_simulate.0,
+            load_max_in_flight: inner.load_max_in_flight,
+            load_admission_wait: Duration::from_millis(inner.load_admission_wait_in_ms),
             allow_non_deterministic_fulltext_search: inner
                 .allow_non_deterministic_fulltext_search
                 .0
                 || cfg!(debug_assertions),
+            schema_max_entity_types: inner.schema_max_entity_types,
+            schema_max_fields_per_type: inner.schema_max_fields_per_type,
+            schema_max_enum_values: inner.schema_max_enum_values,
+            schema_max_fulltext_indexes: inner.schema_max_fulltext_indexes,
             max_spec_version: inner.max_spec_version,
             load_window_size: Duration::from_secs(inner.load_window_size_in_secs),
             load_bin_size: Duration::from_secs(inner.load_bin_size_in_secs),
@@ -250,6 +310,7 @@ impl EnvVars {
             kill_if_unresponsive_timeout: Duration::from_secs(
                 inner.kill_if_unresponsive_timeout_secs,
             ),
+            shutdown_timeout: Duration::from_secs(inner.shutdown_timeout_secs),
             poi_access_token: inner.poi_access_token,
             subgraph_max_data_sources: inner.subgraph_max_data_sources.0,
             disable_fail_fast: inner.disable_fail_fast.0,
@@ -264,9 +325,13 @@ impl EnvVars {
             external_ws_base_url: inner.external_ws_base_url,
             static_filters_threshold: inner.static_filters_threshold,
             reorg_threshold: inner.reorg_threshold,
+            deep_reorg_threshold: inner.deep_reorg_threshold,
             ingestor_polling_interval: Duration::from_millis(inner.ingestor_polling_interval),
             subgraph_settings: inner.subgraph_settings,
             prefer_substreams_block_streams: inner.prefer_substreams_block_streams,
+            firehose_backoff_ceil: Duration::from_secs(inner.firehose_backoff_ceil_in_secs),
+            firehose_backoff_jitter: inner.firehose_backoff_jitter,
+            firehose_backoff_reset: Duration::from_secs(inner.firehose_backoff_reset_in_secs),
             enable_dips_metrics: inner.enable_dips_metrics.0,
             history_blocks_override: inner.history_blocks_override,
             min_history_blocks: inner
@@ -320,11 +385,23 @@ struct Inner {
     load_jail_threshold: Option<f64>,
     #[envconfig(from = "GRAPH_LOAD_SIMULATE", default = "false")]
     load_simulate: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_LOAD_MAX_IN_FLIGHT", default = "0")]
+    load_max_in_flight: usize,
+    #[envconfig(from = "GRAPH_LOAD_ADMISSION_WAIT", default = "100")]
+    load_admission_wait_in_ms: u64,
     #[envconfig(
         from = "GRAPH_ALLOW_NON_DETERMINISTIC_FULLTEXT_SEARCH",
         default = "false"
     )]
     allow_non_deterministic_fulltext_search: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_SCHEMA_MAX_ENTITY_TYPES", default = "1000")]
+    schema_max_entity_types: usize,
+    #[envconfig(from = "GRAPH_SCHEMA_MAX_FIELDS_PER_TYPE", default = "1000")]
+    schema_max_fields_per_type: usize,
+    #[envconfig(from = "GRAPH_SCHEMA_MAX_ENUM_VALUES", default = "10000")]
+    schema_max_enum_values: usize,
+    #[envconfig(from = "GRAPH_SCHEMA_MAX_FULLTEXT_INDEXES", default = "100")]
+    schema_max_fulltext_indexes: usize,
     #[envconfig(from = "GRAPH_MAX_SPEC_VERSION", default = "1.0.0")]
     max_spec_version: Version,
     #[envconfig(from = "GRAPH_LOAD_WINDOW_SIZE", default = "300")]
@@ -366,6 +443,8 @@ struct Inner {
     kill_if_unresponsive: EnvVarBoolean,
     #[envconfig(from = "GRAPH_KILL_IF_UNRESPONSIVE_TIMEOUT_SECS", default = "10")]
     kill_if_unresponsive_timeout_secs: u64,
+    #[envconfig(from = "GRAPH_SHUTDOWN_TIMEOUT_SECS", default = "20")]
+    shutdown_timeout_secs: u64,
     #[envconfig(from = "GRAPH_POI_ACCESS_TOKEN")]
     poi_access_token: Option<String>,
     #[envconfig(from = "GRAPH_SUBGRAPH_MAX_DATA_SOURCES", default = "1_000_000_000")]
@@ -392,6 +471,8 @@ struct Inner {
     external_ws_base_url: Option<String>,
     #[envconfig(from = "GRAPH_STATIC_FILTERS_THRESHOLD", default = "10000")]
     static_filters_threshold: usize,
+    #[envconfig(from = "GRAPH_DEEP_REORG_THRESHOLD", default = "10")]
+    deep_reorg_threshold: BlockNumber,
     // JSON-RPC specific.
     #[envconfig(from = "ETHEREUM_REORG_THRESHOLD", default = "250")]
     reorg_threshold: BlockNumber,
@@ -404,6 +485,12 @@ struct Inner {
         default = "false"
     )]
     prefer_substreams_block_streams: bool,
+    #[envconfig(from = "GRAPH_FIREHOSE_BACKOFF_CEIL_SECS", default = "45")]
+    firehose_backoff_ceil_in_secs: u64,
+    #[envconfig(from = "GRAPH_FIREHOSE_BACKOFF_JITTER", default = "0.2")]
+    firehose_backoff_jitter: f64,
+    #[envconfig(from = "GRAPH_FIREHOSE_BACKOFF_RESET_SECS", default = "30")]
+    firehose_backoff_reset_in_secs: u64,
     #[envconfig(from = "GRAPH_ENABLE_DIPS_METRICS", default = "false")]
     enable_dips_metrics: EnvVarBoolean,
     #[envconfig(from = "GRAPH_HISTORY_BLOCKS_OVERRIDE")]
@@ -113,6 +113,15 @@ pub struct EnvVarsStore {
     /// is 10_000 which corresponds to 10MB. Setting this to 0 disables
     /// write batching.
     pub write_batch_size: usize,
+    /// The maximum number of rows to write with one `INSERT` statement,
+    /// and the maximum number of entity versions to clamp with one
+    /// statement for updates and deletes. Writes for one entity type are
+    /// always split so that a statement does not exceed the maximum number
+    /// of bind variables Postgres allows; this setting lowers the number of
+    /// rows per statement further. All statements are still executed in one transaction. Set
+    /// by `GRAPH_STORE_MAX_ROWS_PER_INSERT`. The default is to only be
+    /// limited by the number of bind variables
+    pub max_rows_per_insert: Option<usize>,
     /// Whether to create GIN indexes for array attributes. Set by
     /// `GRAPH_STORE_CREATE_GIN_INDEXES`. The default is `false`
     pub create_gin_indexes: bool,
@@ -120,6 +129,30 @@ pub struct EnvVarsStore {
     pub use_brin_for_all_query_types: bool,
     /// Temporary env var to disable certain lookups in the chain store
     pub disable_block_cache_for_lookup: bool,
+    /// How many of the most recent reverts to remember for each deployment
+    /// so that they can be reported by the index node status API. Set by
+    /// `GRAPH_STORE_RECENT_REORGS`. The default is 10
+    pub recent_reorgs: usize,
+    /// Over how much time to average the indexing speed of a deployment
+    /// when estimating how long it will take to reach the chain head. Set
+    /// by `GRAPH_STORE_THROUGHPUT_WINDOW` (expressed in seconds). The
+    /// default is 300
+    pub throughput_window: Duration,
+    /// How long to cache the entity counts and storage sizes that the
+    /// index node status API reports for each deployment. Set by
+    /// `GRAPH_STORE_STORAGE_STATS_TTL` (expressed in seconds). The default
+    /// is 300
+    pub storage_stats_ttl: Duration,
+    /// The Postgres `statement_timeout` for writes during indexing, i.e.,
+    /// transacting and reverting blocks. Set by
+    /// `GRAPH_STORE_WRITE_STATEMENT_TIMEOUT` (expressed in seconds). The
+    /// default is no timeout
+    pub write_statement_timeout: Option<Duration>,
+    /// The Postgres `statement_timeout` for administrative operations like
+    /// pruning, analyzing tables and creating or dropping indexes. Set by
+    /// `GRAPH_STORE_ADMIN_STATEMENT_TIMEOUT` (expressed in seconds). The
+    /// default is no timeout
+    pub admin_statement_timeout: Option<Duration>,
 }
 
 // This does not print any values avoid accidentally leaking any sensitive env vars
@@ -165,9 +198,15 @@ impl From<InnerStore> for EnvVarsStore {
             history_slack_factor: x.history_slack_factor.0,
             write_batch_duration: Duration::from_secs(x.write_batch_duration_in_secs),
             write_batch_size: x.write_batch_size * 1_000,
+            max_rows_per_insert: x.max_rows_per_insert.filter(|max| *max > 0),
             create_gin_indexes: x.create_gin_indexes,
             use_brin_for_all_query_types: x.use_brin_for_all_query_types,
             disable_block_cache_for_lookup: x.disable_block_cache_for_lookup,
+            recent_reorgs: x.recent_reorgs,
+            throughput_window: Duration::from_secs(x.throughput_window_in_secs),
+            storage_stats_ttl: Duration::from_secs(x.storage_stats_ttl_in_secs),
+            write_statement_timeout: x.write_statement_timeout_in_secs.map(Duration::from_secs),
+            admin_statement_timeout: x.admin_statement_timeout_in_secs.map(Duration::from_secs),
         }
     }
 }
@@ -223,12 +262,24 @@ pub struct InnerStore {
     write_batch_duration_in_secs: u64,
     #[envconfig(from = "GRAPH_STORE_WRITE_BATCH_SIZE", default = "10000")]
     write_batch_size: usize,
+    #[envconfig(from = "GRAPH_STORE_MAX_ROWS_PER_INSERT")]
+    max_rows_per_insert: Option<usize>,
     #[envconfig(from = "GRAPH_STORE_CREATE_GIN_INDEXES", default = "false")]
     create_gin_indexes: bool,
     #[envconfig(from = "GRAPH_STORE_USE_BRIN_FOR_ALL_QUERY_TYPES", default = "false")]
     use_brin_for_all_query_types: bool,
     #[envconfig(from = "GRAPH_STORE_DISABLE_BLOCK_CACHE_FOR_LOOKUP", default = "false")]
     disable_block_cache_for_lookup: bool,
+    #[envconfig(from = "GRAPH_STORE_RECENT_REORGS", default = "10")]
+    recent_reorgs: usize,
+    #[envconfig(from = "GRAPH_STORE_THROUGHPUT_WINDOW", default = "300")]
+    throughput_window_in_secs: u64,
+    #[envconfig(from = "GRAPH_STORE_STORAGE_STATS_TTL", default = "300")]
+    storage_stats_ttl_in_secs: u64,
+    #[envconfig(from = "GRAPH_STORE_WRITE_STATEMENT_TIMEOUT")]
+    write_statement_timeout_in_secs: Option<u64>,
+    #[envconfig(from = "GRAPH_STORE_ADMIN_STATEMENT_TIMEOUT")]
+    admin_statement_timeout_in_secs: Option<u64>,
 }
 
 #[derive(Clone, Copy, Debug)]
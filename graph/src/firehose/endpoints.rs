@@ -5,7 +5,7 @@ use crate::{
     cheap_clone::CheapClone,
     components::store::BlockNumber,
     data::value::Word,
-    endpoint::{ConnectionType, EndpointMetrics, Provider, RequestLabels},
+    endpoint::{ConnectionType, EndpointMetrics, Provider, RateLimiter, RequestLabels},
     firehose::decode_firehose_block,
     prelude::{anyhow, debug, info, DeploymentHash},
     substreams_rpc,
@@ -51,6 +51,7 @@ pub struct FirehoseEndpoint {
     pub compression_enabled: bool,
     pub subgraph_limit: SubgraphLimit,
     endpoint_metrics: Arc<EndpointMetrics>,
+    rate_limiter: Option<Arc<RateLimiter>>,
     channel: Channel,
 }
 
@@ -210,6 +211,24 @@ impl FirehoseEndpoint {
             compression_enabled,
             subgraph_limit,
             endpoint_metrics,
+            rate_limiter: None,
+        }
+    }
+
+    /// Make requests wait for `limiter` before they are sent. All
+    /// connections to the same provider should share one limiter
+    pub fn with_rate_limiter(mut self, limiter: Option<Arc<RateLimiter>>) -> Self {
+        self.rate_limiter = limiter;
+        self
+    }
+
+    /// Wait until the rate limit of the provider, if there is one, allows
+    /// sending another request
+    async fn throttle(&self, conn_type: ConnectionType) {
+        if let Some(limiter) = &self.rate_limiter {
+            let wait = limiter.acquire(1).await;
+            self.endpoint_metrics
+                .rate_limited(&self.provider, &conn_type, wait);
         }
     }
 
@@ -326,6 +345,7 @@ impl FirehoseEndpoint {
             )),
         };
 
+        self.throttle(ConnectionType::Firehose).await;
         let mut client = self.new_client();
         match client.block(req).await {
             Ok(v) => Ok(M::decode(
@@ -375,6 +395,7 @@ impl FirehoseEndpoint {
         // That way, we either get the final block if the block is now in a final segment of the
         // chain (or probabilisticly if not finality concept exists for the chain). Or we get the
         // block that is in the longuest chain according to Firehose.
+        self.throttle(ConnectionType::Firehose).await;
         let response_stream = client
             .blocks(firehose::Request {
                 start_block_num: number as i64,
@@ -432,6 +453,7 @@ impl FirehoseEndpoint {
         request: firehose::Request,
         headers: &ConnectionHeaders,
     ) -> Result<tonic::Streaming<firehose::Response>, anyhow::Error> {
+        self.throttle(ConnectionType::Firehose).await;
         let mut client = self.new_stream_client();
         let request = headers.add_to_request(request);
         let response_stream = client.blocks(request).await?;
@@ -445,6 +467,7 @@ impl FirehoseEndpoint {
         request: substreams_rpc::Request,
         headers: &ConnectionHeaders,
     ) -> Result<tonic::Streaming<substreams_rpc::Response>, anyhow::Error> {
+        self.throttle(ConnectionType::Substreams).await;
         let mut client = self.new_substreams_client();
         let request = headers.add_to_request(request);
         let response_stream = client.blocks(request).await?;
@@ -21,6 +21,8 @@ pub enum IpfsError {
     Request(#[from] reqwest::Error),
     #[error("IPFS file {0} is too large. It can be at most {1} bytes")]
     FileTooLarge(String, usize),
+    #[error("the contents of IPFS block {0} do not match its CID")]
+    CidMismatch(String),
 }
 
 impl IpfsError {
@@ -138,6 +140,11 @@ impl IpfsClient {
         }
     }
 
+    /// The host of the IPFS node this client talks to, for use in logs
+    pub fn host(&self) -> &str {
+        self.base.host().unwrap_or_default()
+    }
+
     /// To check the existence of a cid, we do a cat of a single byte.
     pub async fn exists(&self, cid: &str, timeout: Option<Duration>) -> Result<(), IpfsError> {
         self.call(self.cat_url("cat", cid, Some(1)), None, timeout)
@@ -18,6 +18,10 @@ pub mod cheap_clone;
 
 pub mod ipfs_client;
 
+/// Typed client for the index node status API
+#[cfg(feature = "client")]
+pub mod client;
+
 pub mod data_source;
 
 pub mod blockchain;
@@ -83,3 +83,7 @@ pub const JSON_FROM_BYTES: GasOp = GasOp {
     base_cost: DEFAULT_BASE_COST,
     size_mult: DEFAULT_GAS_PER_BYTE * 100,
 };
+
+// Decoding a protobuf message produces the same kind of values as parsing JSON does, from an
+// input that is even more compact
+pub const PROTOBUF_DECODE: GasOp = JSON_FROM_BYTES;
@@ -368,7 +368,16 @@ pub enum IndexForAscTypeId {
     // ...
     // LastStarknetType = 4499,
 
-    // Reserved discriminant space for a future blockchain type IDs: [4,500, 5,499]
+    // Substreams types
+    SubstreamsProtobufDecodeCall = 4500,
+    // Continue to add more Substreams type IDs here.
+    // e.g.:
+    // NextSubstreamsType = 4501,
+    // AnotherSubstreamsType = 4502,
+    // ...
+    // LastSubstreamsType = 5499,
+
+    // Reserved discriminant space for a future blockchain type IDs: [5,500, 6,499]
     //
     // Generated with the following shell script:
     //
@@ -11,7 +11,10 @@ use crate::cheap_clone::CheapClone;
 use crate::data::graphql::{ObjectOrInterface, ObjectTypeExt, TypeExt};
 use crate::data::store::IdType;
 use crate::env::ENV_VARS;
-use crate::schema::{ast, META_FIELD_NAME, META_FIELD_TYPE, SCHEMA_TYPE_NAME};
+use crate::schema::{
+    ast, connection, BLOCK_END_FIELD_NAME, BLOCK_START_FIELD_NAME, ENTITY_CONNECTION_DIRECTIVE,
+    ENTITY_VERSION_DIRECTIVE, META_FIELD_NAME, META_FIELD_TYPE, SCHEMA_TYPE_NAME,
+};
 
 use crate::data::graphql::ext::{
     camel_cased_names, DefinitionExt, DirectiveExt, DocumentExt, ValueExt,
@@ -35,6 +38,17 @@ pub enum APISchemaError {
     SchemaCreationFailed(String),
 }
 
+/// The number of entities that a collection field returns when the query
+/// does not specify `first`, lowered to `GRAPH_GRAPHQL_MAX_FIRST` if that
+/// is smaller so that such queries do not exceed the limit
+fn default_first() -> s::Value {
+    s::Value::Int(default_first_within(ENV_VARS.graphql.max_first).into())
+}
+
+fn default_first_within(max_first: u32) -> i32 {
+    max_first.min(100) as i32
+}
+
 // The followoing types are defined in meta.graphql
 const BLOCK_HEIGHT: &str = "Block_height";
 const CHANGE_BLOCK_FILTER_NAME: &str = "BlockChangedFilter";
@@ -115,7 +129,10 @@ impl ApiSchema {
     /// `__schema` and `__type`
     pub(in crate::schema) fn from_api_schema(mut schema: Schema) -> Result<Self, anyhow::Error> {
         add_introspection_schema(&mut schema.document);
+        Self::from_schema(schema)
+    }
 
+    fn from_schema(schema: Schema) -> Result<Self, anyhow::Error> {
         let query_type = schema
             .document
             .get_root_query_type()
@@ -155,6 +172,41 @@ impl ApiSchema {
         &self.schema.document
     }
 
+    /// Return a copy of this schema from which all fields for which `hide`
+    /// returns `true` have been removed. `hide` is called with the name of
+    /// the object or interface type and the field. Queries against the
+    /// resulting schema that use any of the removed fields fail validation
+    pub fn without_fields<F>(&self, hide: F) -> Result<Self, anyhow::Error>
+    where
+        F: Fn(&str, &s::Field) -> bool,
+    {
+        let mut schema = self.schema.clone();
+
+        for defn in &mut schema.document.definitions {
+            match defn {
+                s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) => {
+                    let name = &obj_type.name;
+                    obj_type.fields.retain(|field| !hide(name, field));
+                }
+                s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => {
+                    let name = &intf_type.name;
+                    intf_type.fields.retain(|field| !hide(name, field));
+                }
+                _ => { /* nothing to hide */ }
+            }
+        }
+        for obj_type in schema.types_for_interface.values_mut().flatten() {
+            let name = &obj_type.name;
+            obj_type.fields.retain(|field| !hide(name, field));
+        }
+        for intf_type in schema.interfaces_for_type.values_mut().flatten() {
+            let name = &intf_type.name;
+            intf_type.fields.retain(|field| !hide(name, field));
+        }
+
+        Self::from_schema(schema)
+    }
+
     pub fn id(&self) -> &DeploymentHash {
         &self.schema.id
     }
@@ -352,6 +404,18 @@ fn add_introspection_schema(schema: &mut s::Document) {
 /// all its fields and their input arguments, based on the existing types.
 pub(in crate::schema) fn api_schema(
     input_schema: &InputSchema,
+) -> Result<s::Document, APISchemaError> {
+    let history = ENV_VARS
+        .graphql
+        .history_queries_for(input_schema.id().as_str());
+    api_schema_with_history(input_schema, history)
+}
+
+/// Derive the API schema; if `history` is `true`, add history queries for
+/// all mutable entity types
+fn api_schema_with_history(
+    input_schema: &InputSchema,
+    history: bool,
 ) -> Result<s::Document, APISchemaError> {
     // Refactor: Don't clone the schema.
     let mut api = init_api_schema(input_schema)?;
@@ -359,7 +423,11 @@ pub(in crate::schema) fn api_schema(
     add_types_for_object_types(&mut api, input_schema)?;
     add_types_for_interface_types(&mut api, input_schema)?;
     add_types_for_aggregation_types(&mut api, input_schema)?;
-    add_query_type(&mut api.document, input_schema)?;
+    add_connection_types(&mut api.document, input_schema)?;
+    if history {
+        add_version_types(&mut api.document, input_schema)?;
+    }
+    add_query_type(&mut api.document, input_schema, history)?;
     add_subscription_type(&mut api.document, input_schema)?;
     Ok(api.document)
 }
@@ -476,6 +544,195 @@ fn add_types_for_object_types(
     Ok(())
 }
 
+/// Adds a `<type_name>_version` type for each mutable object type. These
+/// are the types of the objects that history queries return: they have the
+/// stored fields of the object type, with references to other entities
+/// turned into the ids of those entities, and the range of blocks for which
+/// the version was current
+fn add_version_types(
+    api: &mut s::Document,
+    input_schema: &InputSchema,
+) -> Result<(), APISchemaError> {
+    /// Replace the name of the base type of `field_type` with `name`
+    fn with_base_type(field_type: &s::Type, name: &str) -> s::Type {
+        match field_type {
+            s::Type::NamedType(_) => s::Type::NamedType(name.to_string()),
+            s::Type::ListType(inner) => s::Type::ListType(Box::new(with_base_type(inner, name))),
+            s::Type::NonNullType(inner) => {
+                s::Type::NonNullType(Box::new(with_base_type(inner, name)))
+            }
+        }
+    }
+
+    for (name, object_type) in input_schema
+        .object_types()
+        .filter(|(_, object_type)| !object_type.immutable)
+    {
+        let version_type_name = format!("{}_version", name);
+        if api.get_named_type(&version_type_name).is_some() {
+            return Err(APISchemaError::TypeExists(version_type_name));
+        }
+
+        let mut fields: Vec<_> = object_type
+            .fields
+            .iter()
+            .filter(|field| !field.is_derived())
+            .map(|field| {
+                let field_type =
+                    match input_schema.kind_of_declared_type(field.field_type.get_base_type()) {
+                        Some(TypeKind::Object) | Some(TypeKind::Interface) => {
+                            with_base_type(&field.field_type, field.value_type.to_str())
+                        }
+                        _ => field.field_type.clone(),
+                    };
+                s::Field {
+                    position: Pos::default(),
+                    description: None,
+                    name: field.name.to_string(),
+                    arguments: vec![],
+                    field_type,
+                    directives: vec![],
+                }
+            })
+            .collect();
+        fields.push(s::Field {
+            position: Pos::default(),
+            description: Some("The block at which this version was created".to_string()),
+            name: BLOCK_START_FIELD_NAME.to_string(),
+            arguments: vec![],
+            field_type: s::Type::NonNullType(Box::new(s::Type::NamedType("Int".to_string()))),
+            directives: vec![],
+        });
+        fields.push(s::Field {
+            position: Pos::default(),
+            description: Some(
+                "The block at which this version was replaced or removed, or null if it is \
+                 still current"
+                    .to_string(),
+            ),
+            name: BLOCK_END_FIELD_NAME.to_string(),
+            arguments: vec![],
+            field_type: s::Type::NamedType("Int".to_string()),
+            directives: vec![],
+        });
+
+        let typedef = s::TypeDefinition::Object(s::ObjectType {
+            position: Pos::default(),
+            description: Some(format!("A version of a `{}` entity", name)),
+            name: version_type_name,
+            implements_interfaces: vec![],
+            directives: vec![s::Directive {
+                position: Pos::default(),
+                name: ENTITY_VERSION_DIRECTIVE.to_string(),
+                arguments: vec![("entity".to_string(), s::Value::String(name.to_string()))],
+            }],
+            fields,
+        });
+        api.definitions.push(s::Definition::TypeDefinition(typedef));
+    }
+    Ok(())
+}
+
+/// Adds the types that connection queries return for object types with
+/// `@entity(connection: true)`: a `<Type>Connection` with the `edges` of
+/// a page and its `pageInfo`, a `<Type>Edge` that holds one entity and
+/// its cursor, and the `PageInfo` type that all connections share
+fn add_connection_types(
+    api: &mut s::Document,
+    input_schema: &InputSchema,
+) -> Result<(), APISchemaError> {
+    fn field(name: &str, field_type: s::Type) -> s::Field {
+        s::Field {
+            position: Pos::default(),
+            description: None,
+            name: name.to_string(),
+            arguments: vec![],
+            field_type,
+            directives: vec![],
+        }
+    }
+
+    fn named(name: &str) -> s::Type {
+        s::Type::NamedType(name.to_string())
+    }
+
+    fn non_null(name: &str) -> s::Type {
+        s::Type::NonNullType(Box::new(named(name)))
+    }
+
+    fn object_type(
+        name: String,
+        fields: Vec<s::Field>,
+        directives: Vec<s::Directive>,
+    ) -> s::ObjectType {
+        s::ObjectType {
+            position: Pos::default(),
+            description: None,
+            name,
+            implements_interfaces: vec![],
+            directives,
+            fields,
+        }
+    }
+
+    let names: Vec<_> = input_schema
+        .object_types()
+        .filter(|(_, object_type)| object_type.connection)
+        .map(|(name, _)| name)
+        .collect();
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    let mut object_types = vec![object_type(
+        connection::PAGE_INFO_TYPE.to_string(),
+        vec![
+            field(connection::HAS_NEXT_PAGE, non_null("Boolean")),
+            field(connection::HAS_PREVIOUS_PAGE, non_null("Boolean")),
+            field(connection::START_CURSOR, named("String")),
+            field(connection::END_CURSOR, named("String")),
+        ],
+        vec![],
+    )];
+    for name in names {
+        let edge_type = format!("{}Edge", name);
+        object_types.push(object_type(
+            format!("{}Connection", name),
+            vec![
+                field(
+                    connection::EDGES,
+                    s::Type::NonNullType(Box::new(s::Type::ListType(Box::new(non_null(
+                        &edge_type,
+                    ))))),
+                ),
+                field(connection::PAGE_INFO, non_null(connection::PAGE_INFO_TYPE)),
+            ],
+            vec![s::Directive {
+                position: Pos::default(),
+                name: ENTITY_CONNECTION_DIRECTIVE.to_string(),
+                arguments: vec![("entity".to_string(), s::Value::String(name.to_string()))],
+            }],
+        ));
+        object_types.push(object_type(
+            edge_type,
+            vec![
+                field(connection::CURSOR, non_null("String")),
+                field(connection::NODE, non_null(name)),
+            ],
+            vec![],
+        ));
+    }
+
+    for object_type in object_types {
+        if api.get_named_type(&object_type.name).is_some() {
+            return Err(APISchemaError::TypeExists(object_type.name));
+        }
+        let typedef = s::TypeDefinition::Object(object_type);
+        api.definitions.push(s::Definition::TypeDefinition(typedef));
+    }
+    Ok(())
+}
+
 /// Adds `*_orderBy` and `*_filter` enum types for the given interfaces to the schema.
 fn add_types_for_interface_types(
     api: &mut Schema,
@@ -574,6 +831,9 @@ fn field_enum_values_from_child_entity(
 
     let type_name = match ENV_VARS.graphql.disable_child_sorting {
         true => None,
+        // Sorting joins with the child along a single field, but such a
+        // field needs all of the fields it is derived from to match
+        false if field.is_derived_from_several() => None,
         false => resolve_supported_type_name(&field.field_type),
     };
 
@@ -635,6 +895,29 @@ fn filter_type_defn(name: String, mut fields: Vec<s::InputValue>) -> s::Definiti
     s::Definition::TypeDefinition(typedef)
 }
 
+/// The names of the fields of the `<type_name>_filter` type that is
+/// generated for an object or interface type with the given fields. Each
+/// name comes with the name of the field it filters on, or `None` for the
+/// fields that every filter type has. Schema validation uses this to find
+/// collisions between generated filters
+pub(in crate::schema) fn filter_field_names(
+    schema: &Schema,
+    fields: &[Field],
+) -> Result<Vec<(String, Option<String>)>, APISchemaError> {
+    let mut names = vec![];
+    for field in fields {
+        for input_value in field_filter_input_values(schema, field, FilterOps::Object)? {
+            names.push((input_value.name, Some(field.name.to_string())));
+        }
+    }
+    if let s::Definition::TypeDefinition(s::TypeDefinition::InputObject(filter)) =
+        filter_type_defn(String::new(), vec![])
+    {
+        names.extend(filter.fields.into_iter().map(|field| (field.name, None)));
+    }
+    Ok(names)
+}
+
 /// Selector for the kind of field filters to generate
 #[derive(Copy, Clone)]
 enum FilterOps {
@@ -668,7 +951,7 @@ impl FilterOps {
         skip.default_value = Some(s::Value::Int(0.into()));
 
         let mut first = input_value("first", "", s::Type::NamedType("Int".to_string()));
-        first.default_value = Some(s::Value::Int(100.into()));
+        first.default_value = Some(default_first());
 
         let filter_type = s::Type::NamedType(format!("{}_filter", type_name));
         let filter = input_value("where", "", filter_type);
@@ -1034,7 +1317,11 @@ fn input_value(name: &str, suffix: &'static str, value_type: s::Type) -> s::Inpu
 }
 
 /// Adds a root `Query` object type to the schema.
-fn add_query_type(api: &mut s::Document, input_schema: &InputSchema) -> Result<(), APISchemaError> {
+fn add_query_type(
+    api: &mut s::Document,
+    input_schema: &InputSchema,
+    history: bool,
+) -> Result<(), APISchemaError> {
     let type_name = String::from("Query");
 
     if api.get_named_type(&type_name).is_some() {
@@ -1060,6 +1347,20 @@ fn add_query_type(api: &mut s::Document, input_schema: &InputSchema) -> Result<(
         .collect();
     fields.append(&mut agg_fields);
     fields.append(&mut fulltext_fields);
+    fields.extend(
+        input_schema
+            .object_types()
+            .filter(|(_, object_type)| object_type.connection)
+            .map(|(name, _)| query_field_for_connection(name)),
+    );
+    if history {
+        fields.extend(
+            input_schema
+                .object_types()
+                .filter(|(_, object_type)| !object_type.immutable)
+                .map(|(name, _)| query_field_for_history(name)),
+        );
+    }
     fields.push(meta_field());
 
     let typedef = s::TypeDefinition::Object(s::ObjectType {
@@ -1100,7 +1401,7 @@ fn query_field_for_fulltext(fulltext: &s::Directive) -> Option<s::Field> {
             description: None,
             name: String::from("first"),
             value_type: s::Type::NamedType(String::from("Int")),
-            default_value: Some(s::Value::Int(100.into())),
+            default_value: Some(default_first()),
             directives: vec![],
         },
         // skip: Int
@@ -1262,6 +1563,74 @@ fn query_fields_for_type(type_name: &str, ops: FilterOps) -> Vec<s::Field> {
     ]
 }
 
+/// The `<type>History` query that returns all versions of the entity of
+/// type `type_name` with a given id, ordered by the block at which they were
+/// created
+fn query_field_for_history(type_name: &str) -> s::Field {
+    let id = s::InputValue {
+        position: Pos::default(),
+        description: None,
+        name: "id".to_string(),
+        value_type: s::Type::NonNullType(Box::new(s::Type::NamedType("ID".to_string()))),
+        default_value: None,
+        directives: vec![],
+    };
+    let mut skip = input_value("skip", "", s::Type::NamedType("Int".to_string()));
+    skip.default_value = Some(s::Value::Int(0.into()));
+    let mut first = input_value("first", "", s::Type::NamedType("Int".to_string()));
+    first.default_value = Some(default_first());
+
+    let (singular, _) = camel_cased_names(type_name);
+    s::Field {
+        position: Pos::default(),
+        description: Some(format!("All versions of a `{}` entity", type_name)),
+        name: format!("{}History", singular),
+        arguments: vec![id, skip, first, block_argument(), subgraph_error_argument()],
+        field_type: s::Type::NonNullType(Box::new(s::Type::ListType(Box::new(
+            s::Type::NonNullType(Box::new(s::Type::NamedType(format!(
+                "{}_version",
+                type_name
+            )))),
+        )))),
+        directives: vec![],
+    }
+}
+
+/// The `<types>Connection` query that pages through the entities of type
+/// `type_name` with cursors instead of `skip`
+fn query_field_for_connection(type_name: &str) -> s::Field {
+    let after = input_value(
+        connection::AFTER,
+        "",
+        s::Type::NamedType("String".to_string()),
+    );
+    let mut arguments: Vec<_> = FilterOps::Object
+        .collection_arguments(type_name)
+        .into_iter()
+        .filter(|arg| arg.name != "skip")
+        .collect();
+    // Put `after` right after `first`
+    arguments.insert(1, after);
+    arguments.push(block_argument());
+    arguments.push(subgraph_error_argument());
+
+    let (_, plural) = camel_cased_names(type_name);
+    s::Field {
+        position: Pos::default(),
+        description: Some(format!(
+            "A page of `{}` entities and the cursors to fetch the next page",
+            type_name
+        )),
+        name: format!("{}Connection", plural),
+        arguments,
+        field_type: s::Type::NonNullType(Box::new(s::Type::NamedType(format!(
+            "{}Connection",
+            type_name
+        )))),
+        directives: vec![],
+    }
+}
+
 fn query_fields_for_agg_type(type_name: &str) -> Vec<s::Field> {
     let mut collection_arguments = FilterOps::Aggregation.collection_arguments(type_name);
     collection_arguments.push(block_argument());
@@ -1308,7 +1677,7 @@ fn meta_field() -> s::Field {
 mod tests {
     use crate::{
         data::{
-            graphql::{ext::FieldExt, ObjectTypeExt, TypeExt as _},
+            graphql::{ext::FieldExt, DocumentExt as _, ObjectTypeExt, TypeExt as _},
             subgraph::LATEST_VERSION,
         },
         prelude::{s, DeploymentHash},
@@ -1499,6 +1868,7 @@ mod tests {
                   favoritePet: Pet!
                   leastFavoritePet: Pet @derivedFrom(field: "mostHatedBy")
                   mostFavoritePets: [Pet!] @derivedFrom(field: "mostLovedBy")
+                  hatedAndLovedPet: Pet @derivedFrom(fields: ["mostHatedBy", "mostLovedBy"])
                   favoriteMeal: Meal!
                   leastFavoriteMeal: Meal @derivedFrom(field: "mostHatedBy")
                   mostFavoriteMeals: [Meal!] @derivedFrom(field: "mostLovedBy")
@@ -1538,6 +1908,7 @@ mod tests {
                 "leastFavoritePet__id",
                 "leastFavoritePet__name",
                 "mostFavoritePets",
+                "hatedAndLovedPet",
                 "favoriteMeal",
                 "favoriteMeal__id",
                 "favoriteMeal__name",
@@ -2149,6 +2520,75 @@ type Gravatar @entity {
         .expect("\"metadata\" field is missing on Query type");
     }
 
+    #[test]
+    fn api_schema_limits_default_first() {
+        let schema = parse("type User @entity { id: ID! }");
+
+        let first = query_field(&schema, "users")
+            .arguments
+            .iter()
+            .find(|arg| arg.name == "first")
+            .expect("`users` has a `first` argument");
+        assert_eq!(
+            1000,
+            crate::env::ENV_VARS.graphql.max_first,
+            "the test needs the default for GRAPH_GRAPHQL_MAX_FIRST"
+        );
+        assert_eq!(Some(s::Value::Int(100.into())), first.default_value);
+
+        assert_eq!(100, super::default_first_within(1000));
+        assert_eq!(100, super::default_first_within(100));
+        assert_eq!(50, super::default_first_within(50));
+    }
+
+    #[test]
+    fn without_fields_removes_fields() {
+        const SCHEMA: &str = r#"
+          interface Named {
+            id: ID!
+            name: String!
+          }
+
+          type User implements Named @entity {
+            id: ID!
+            name: String!
+            posts: [Post!]! @derivedFrom(field: "author")
+          }
+
+          type Post @entity {
+            id: ID!
+            author: User!
+          }
+        "#;
+        let schema = parse(SCHEMA);
+        let pruned = schema
+            .without_fields(|type_name, field| match type_name {
+                "Query" => field.name == "_meta" || field.name == "posts",
+                "User" | "Named" => field.name == "name",
+                _ => false,
+            })
+            .expect("pruning fields works");
+
+        let query_type = pruned.query_type.as_ref();
+        assert!(ast::get_field(query_type, "_meta").is_none());
+        assert!(ast::get_field(query_type, "posts").is_none());
+        assert!(ast::get_field(query_type, "post").is_some());
+        assert!(ast::get_field(query_type, "__schema").is_some());
+
+        let user = pruned.object_or_interface("User").unwrap();
+        assert!(user.field("name").is_none());
+        assert!(user.field("posts").is_some());
+        let named = pruned.object_or_interface("Named").unwrap();
+        assert!(named.field("name").is_none());
+        let impls = &pruned.types_for_interface()["Named"];
+        assert!(impls.iter().all(|t| ast::get_field(t, "name").is_none()));
+
+        // The original schema is not changed
+        assert!(ast::get_field(schema.query_type.as_ref(), "_meta").is_some());
+        let user = schema.object_or_interface("User").unwrap();
+        assert!(user.field("name").is_some());
+    }
+
     #[test]
     fn intf_implements_intf() {
         const SCHEMA: &str = r#"
@@ -2390,4 +2830,132 @@ type Gravatar @entity {
             );
         }
     }
+
+    #[test]
+    fn api_schema_contains_history_queries() {
+        const SCHEMA: &str = r#"
+type Token @entity { id: ID!, owner: Account!, amount: BigInt! }
+type Account @entity { id: Bytes!, tokens: [Token!]! @derivedFrom(field: "owner") }
+type Transfer @entity(immutable: true) { id: ID!, amount: BigInt! }"#;
+
+        let schema = parse(SCHEMA);
+        assert!(schema.get_named_type("Token_version").is_none());
+
+        let input_schema = InputSchema::parse(LATEST_VERSION, SCHEMA, ID.clone()).unwrap();
+        let document = super::api_schema_with_history(&input_schema, true).unwrap();
+        let object_type = |name: &str| match document.get_named_type(name) {
+            Some(TypeDefinition::Object(t)) => t.clone(),
+            _ => panic!("type {} is missing", name),
+        };
+
+        let query_type = object_type("Query");
+        let history = query_type
+            .field("tokenHistory")
+            .expect("tokenHistory is missing");
+        assert_eq!("Token_version", history.field_type.get_base_type());
+        assert!(query_type.field("accountHistory").is_some());
+        assert!(query_type.field("transferHistory").is_none());
+        assert!(document.get_named_type("Transfer_version").is_none());
+
+        let token_version = object_type("Token_version");
+        let fields: Vec<_> = token_version
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.field_type.to_string()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("id", "ID!".to_string()),
+                ("owner", "Bytes!".to_string()),
+                ("amount", "BigInt!".to_string()),
+                ("_block_start", "Int!".to_string()),
+                ("_block_end", "Int".to_string()),
+            ],
+            fields
+        );
+        let account_version = object_type("Account_version");
+        assert!(account_version.field("tokens").is_none());
+    }
+
+    #[test]
+    fn api_schema_contains_connections() {
+        const SCHEMA: &str = r#"
+type Token @entity(connection: true) { id: ID!, amount: BigInt! }
+type Account @entity { id: Bytes!, name: String! }"#;
+
+        let schema = parse(SCHEMA);
+        let object_type = |name: &str| match schema.get_named_type(name) {
+            Some(TypeDefinition::Object(t)) => t.clone(),
+            _ => panic!("type {} is missing", name),
+        };
+        let field_types = |t: &s::ObjectType| -> Vec<_> {
+            t.fields
+                .iter()
+                .map(|field| (field.name.clone(), field.field_type.to_string()))
+                .collect()
+        };
+
+        let connection = query_field(&schema, "tokensConnection");
+        assert_eq!("TokenConnection!", connection.field_type.to_string());
+        let args: Vec<_> = connection
+            .arguments
+            .iter()
+            .map(|arg| arg.name.as_str())
+            .collect();
+        assert_eq!(
+            vec![
+                "first",
+                "after",
+                "orderBy",
+                "orderDirection",
+                "where",
+                "block",
+                "subgraphError"
+            ],
+            args
+        );
+
+        let token_connection = object_type("TokenConnection");
+        assert_eq!(
+            vec![
+                ("edges".to_string(), "[TokenEdge!]!".to_string()),
+                ("pageInfo".to_string(), "PageInfo!".to_string()),
+            ],
+            field_types(&token_connection)
+        );
+        let directive = token_connection
+            .directives
+            .iter()
+            .find(|dir| dir.name == "entityConnection")
+            .expect("TokenConnection is marked as a connection");
+        assert_eq!(
+            vec![("entity".to_string(), Value::String("Token".to_string()))],
+            directive.arguments
+        );
+        assert_eq!(
+            vec![
+                ("cursor".to_string(), "String!".to_string()),
+                ("node".to_string(), "Token!".to_string()),
+            ],
+            field_types(&object_type("TokenEdge"))
+        );
+        assert_eq!(
+            vec![
+                ("hasNextPage".to_string(), "Boolean!".to_string()),
+                ("hasPreviousPage".to_string(), "Boolean!".to_string()),
+                ("startCursor".to_string(), "String".to_string()),
+                ("endCursor".to_string(), "String".to_string()),
+            ],
+            field_types(&object_type("PageInfo"))
+        );
+
+        // Connections are opt-in
+        assert!(schema.get_named_type("AccountConnection").is_none());
+        assert!(schema.get_named_type("AccountEdge").is_none());
+        let query_type = object_type("Query");
+        assert!(query_type.field("accountsConnection").is_none());
+
+        let schema = parse(r#"type Account @entity { id: Bytes!, name: String! }"#);
+        assert!(schema.get_named_type("PageInfo").is_none());
+    }
 }
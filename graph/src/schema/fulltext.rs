@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::convert::TryFrom;
 
 use crate::data::graphql::{DirectiveExt, ValueExt};
@@ -98,10 +98,66 @@ impl TryFrom<&str> for FulltextAlgorithm {
     }
 }
 
+/// The weight of an included field, which determines how much matches in
+/// that field count when ranking results. `A` is the highest weight; fields
+/// without a weight are treated like `D`
+#[derive(Clone, Debug, PartialEq)]
+pub enum FulltextWeight {
+    A,
+    B,
+    C,
+    D,
+}
+
+impl TryFrom<&str> for FulltextWeight {
+    type Error = String;
+    fn try_from(weight: &str) -> Result<Self, Self::Error> {
+        match weight {
+            "A" => Ok(FulltextWeight::A),
+            "B" => Ok(FulltextWeight::B),
+            "C" => Ok(FulltextWeight::C),
+            "D" => Ok(FulltextWeight::D),
+            invalid => Err(format!(
+                "The provided fulltext weight {} is invalid. It must be one of: A, B, C, D",
+                invalid
+            )),
+        }
+    }
+}
+
+impl FulltextWeight {
+    /// Return the weight as a valid SQL string for `setweight`. The string
+    /// is safe to directly use verbatim in a query
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::A => "'A'",
+            Self::B => "'B'",
+            Self::C => "'C'",
+            Self::D => "'D'",
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct FulltextConfig {
     pub language: FulltextLanguage,
     pub algorithm: FulltextAlgorithm,
+    /// Languages for included fields that override `language`
+    pub field_languages: BTreeMap<String, FulltextLanguage>,
+    /// Weights for included fields
+    pub field_weights: BTreeMap<String, FulltextWeight>,
+}
+
+impl FulltextConfig {
+    /// The language to use for the tsvector of the included field `field`
+    pub fn language_for(&self, field: &str) -> &FulltextLanguage {
+        self.field_languages.get(field).unwrap_or(&self.language)
+    }
+
+    /// The weight of the included field `field`, if it has one
+    pub fn weight_for(&self, field: &str) -> Option<&FulltextWeight> {
+        self.field_weights.get(field)
+    }
 }
 
 pub struct FulltextDefinition {
@@ -129,24 +185,29 @@ impl From<&s::Directive> for FulltextDefinition {
         // Currently fulltext query fields are limited to 1 entity, so we just take the first (and only) included Entity
         let included_entity = included_entity_list.first().unwrap().as_object().unwrap();
         let included_field_values = included_entity.get("fields").unwrap().as_list().unwrap();
-        let included_fields: HashSet<String> = included_field_values
-            .iter()
-            .map(|field| {
-                field
-                    .as_object()
-                    .unwrap()
-                    .get("name")
-                    .unwrap()
-                    .as_str()
-                    .unwrap()
-                    .into()
-            })
-            .collect();
+        let mut included_fields = HashSet::new();
+        let mut field_languages = BTreeMap::new();
+        let mut field_weights = BTreeMap::new();
+        for field in included_field_values {
+            let field = field.as_object().unwrap();
+            let name: String = field.get("name").unwrap().as_str().unwrap().into();
+            if let Some(language) = field.get("language") {
+                let language = FulltextLanguage::try_from(language.as_enum().unwrap()).unwrap();
+                field_languages.insert(name.clone(), language);
+            }
+            if let Some(weight) = field.get("weight") {
+                let weight = FulltextWeight::try_from(weight.as_enum().unwrap()).unwrap();
+                field_weights.insert(name.clone(), weight);
+            }
+            included_fields.insert(name);
+        }
 
         FulltextDefinition {
             config: FulltextConfig {
                 language,
                 algorithm,
+                field_languages,
+                field_weights,
             },
             included_fields,
             name: name.into(),
@@ -0,0 +1,297 @@
+//! Abstract base types whose fields are inlined into the entity types that
+//! extend them:
+//!
+//! ```graphql
+//! type Timestamped @abstract {
+//!   createdAt: Timestamp!
+//!   updatedAt: Timestamp!
+//! }
+//!
+//! type Token @entity(extends: ["Timestamped"]) {
+//!   id: Bytes!
+//!   symbol: String!
+//! }
+//! ```
+//!
+//! The fields of the base types are appended to the fields of the entity
+//! type in the order in which the base types are listed, and the abstract
+//! types are removed from the schema. Everything after parsing, including
+//! the relational layout and the API schema, therefore only ever sees
+//! entity types with all their fields spelled out.
+use std::collections::HashMap;
+
+use semver::Version;
+
+use crate::data::graphql::ext::DirectiveFinder;
+use crate::data::graphql::TypeExt;
+use crate::data::subgraph::SPEC_VERSION_1_3_0;
+use crate::prelude::s;
+use crate::schema::SchemaValidationError as Err;
+
+use super::kw;
+
+fn abstract_type(def: &s::Definition) -> Option<&s::ObjectType> {
+    match def {
+        s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type))
+            if obj_type.find_directive(kw::ABSTRACT).is_some() =>
+        {
+            Some(obj_type)
+        }
+        _ => None,
+    }
+}
+
+/// The names of the base types in the `extends` argument, which can be a
+/// single name or a list of names
+fn base_names(value: s::Value) -> Option<Vec<String>> {
+    match value {
+        s::Value::String(name) => Some(vec![name]),
+        s::Value::List(values) => values
+            .into_iter()
+            .map(|value| match value {
+                s::Value::String(name) => Some(name),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+fn check_field_types(
+    type_name: &str,
+    fields: &[s::Field],
+    abstract_types: &HashMap<String, s::ObjectType>,
+    errors: &mut Vec<Err>,
+) {
+    for field in fields {
+        let base = field.field_type.get_base_type();
+        if abstract_types.contains_key(base) {
+            errors.push(Err::AbstractTypeAsFieldType(
+                type_name.to_string(),
+                field.name.clone(),
+                base.to_string(),
+            ));
+        }
+    }
+}
+
+/// Remove the `@abstract` types from `document` and add their fields to
+/// the entity types that list them in the `extends` argument of their
+/// `@entity` directive
+pub(super) fn inline_abstract_types(
+    spec_version: &Version,
+    mut document: s::Document,
+) -> Result<s::Document, Vec<Err>> {
+    let abstract_types: HashMap<_, _> = document
+        .definitions
+        .iter()
+        .filter_map(abstract_type)
+        .map(|obj_type| (obj_type.name.clone(), obj_type.clone()))
+        .collect();
+
+    if !abstract_types.is_empty() && spec_version < &SPEC_VERSION_1_3_0 {
+        return Err(vec![Err::AbstractTypesNotSupported(spec_version.clone())]);
+    }
+
+    let mut errors = Vec::new();
+    let mut abstract_names: Vec<_> = abstract_types.keys().collect();
+    abstract_names.sort();
+    for name in abstract_names {
+        let obj_type = &abstract_types[name];
+        if obj_type.find_directive(kw::ENTITY).is_some() {
+            errors.push(Err::AbstractEntity(name.clone()));
+        }
+    }
+
+    document
+        .definitions
+        .retain(|def| abstract_type(def).is_none());
+
+    for def in document.definitions.iter_mut() {
+        let obj_type = match def {
+            s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) => obj_type,
+            s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => {
+                check_field_types(
+                    &intf_type.name,
+                    &intf_type.fields,
+                    &abstract_types,
+                    &mut errors,
+                );
+                continue;
+            }
+            _ => continue,
+        };
+        check_field_types(
+            &obj_type.name,
+            &obj_type.fields,
+            &abstract_types,
+            &mut errors,
+        );
+
+        let Some(dir) = obj_type
+            .directives
+            .iter_mut()
+            .find(|dir| dir.name == kw::ENTITY)
+        else {
+            continue;
+        };
+        let Some(pos) = dir
+            .arguments
+            .iter()
+            .position(|(name, _)| name == kw::EXTENDS)
+        else {
+            continue;
+        };
+        let (_, value) = dir.arguments.remove(pos);
+        let Some(bases) = base_names(value) else {
+            errors.push(Err::InvalidExtends(obj_type.name.clone()));
+            continue;
+        };
+
+        for base in bases {
+            let Some(base_type) = abstract_types.get(&base) else {
+                errors.push(Err::ExtendsUnknownType(obj_type.name.clone(), base));
+                continue;
+            };
+            for field in &base_type.fields {
+                if obj_type.fields.iter().any(|other| other.name == field.name) {
+                    errors.push(Err::InheritedFieldConflict(
+                        obj_type.name.clone(),
+                        field.name.clone(),
+                        base.clone(),
+                    ));
+                } else {
+                    obj_type.fields.push(field.clone());
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(document)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::subgraph::{LATEST_VERSION, SPEC_VERSION_1_2_0};
+    use crate::prelude::DeploymentHash;
+    use crate::schema::{InputSchema, SchemaValidationError as Err, SchemaValidationErrors};
+
+    const SCHEMA: &str = r#"
+        type Timestamped @abstract {
+          createdAt: BigInt!
+          updatedAt: BigInt!
+        }
+
+        type Owned @abstract {
+          owner: Bytes! @index(where: "balance > 0")
+          balance: BigInt!
+        }
+
+        type Token @entity(extends: ["Timestamped", "Owned"]) {
+          id: Bytes!
+          symbol: String!
+          transfers: [Transfer!]! @derivedFrom(field: "token")
+        }
+
+        type Transfer @entity(immutable: true, extends: "Timestamped") {
+          id: Bytes!
+          token: Token!
+        }
+    "#;
+
+    fn parse(schema: &str) -> Result<InputSchema, Vec<Err>> {
+        let id = DeploymentHash::new("inherit").unwrap();
+        InputSchema::parse(LATEST_VERSION, schema, id).map_err(|e| {
+            e.downcast::<SchemaValidationErrors>()
+                .map(|errors| errors.errors)
+                .unwrap_or_else(|e| panic!("unexpected error: {:#}", e))
+        })
+    }
+
+    #[test]
+    fn inlines_base_fields() {
+        let schema = parse(SCHEMA).unwrap();
+
+        let fields = |name: &str| -> Vec<String> {
+            let entity_type = schema.entity_type(name).unwrap();
+            entity_type
+                .object_type()
+                .unwrap()
+                .fields
+                .iter()
+                .map(|field| field.name.to_string())
+                .collect()
+        };
+        assert_eq!(
+            vec![
+                "id",
+                "symbol",
+                "transfers",
+                "createdAt",
+                "updatedAt",
+                "owner",
+                "balance"
+            ],
+            fields("Token")
+        );
+        assert_eq!(
+            vec!["id", "token", "createdAt", "updatedAt"],
+            fields("Transfer")
+        );
+        assert!(schema.entity_type("Timestamped").is_err());
+
+        let token = schema.entity_type("Token").unwrap();
+        assert_eq!(
+            Some("balance > 0"),
+            token
+                .object_type()
+                .unwrap()
+                .field("owner")
+                .unwrap()
+                .index_where()
+        );
+        assert!(schema.entity_type("Transfer").unwrap().is_immutable());
+
+        let api = schema.api_schema().unwrap();
+        let token = api.object_or_interface("Token").unwrap();
+        assert!(token.field("createdAt").is_some());
+        assert!(api.object_or_interface("Timestamped").is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_inheritance() {
+        let errors = parse(
+            r#"
+            type Base @abstract { name: String! }
+            type Thing @entity(extends: "Base") { id: ID!, name: String! }
+            type Other @entity(extends: ["Missing"]) { id: ID!, base: Base }
+            type Bad @entity(extends: 1) { id: ID! }
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            vec![
+                Err::InheritedFieldConflict("Thing".into(), "name".into(), "Base".into()),
+                Err::AbstractTypeAsFieldType("Other".into(), "base".into(), "Base".into()),
+                Err::ExtendsUnknownType("Other".into(), "Missing".into()),
+                Err::InvalidExtends("Bad".into()),
+            ],
+            errors
+        );
+
+        let errors = parse("type Base @abstract @entity { id: ID! }").unwrap_err();
+        assert_eq!(vec![Err::AbstractEntity("Base".into())], errors);
+
+        let id = DeploymentHash::new("inherit").unwrap();
+        let err = InputSchema::parse(&SPEC_VERSION_1_2_0, SCHEMA, id).unwrap_err();
+        let errors = err.downcast::<SchemaValidationErrors>().unwrap().errors;
+        assert_eq!(
+            vec![Err::AbstractTypesNotSupported(SPEC_VERSION_1_2_0)],
+            errors
+        );
+    }
+}
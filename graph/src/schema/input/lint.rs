@@ -0,0 +1,259 @@
+//! Advisory checks for fields that queries are likely to filter on but
+//! that the database will not have a useful index for. None of these
+//! checks make a schema invalid; they only point out fields where queries
+//! can get slow as the subgraph grows
+use std::fmt;
+
+use crate::data::graphql::TypeExt;
+use crate::data::store::ValueType;
+use crate::schema::input::{Field, InputSchema, POI_OBJECT};
+
+/// Field names that usually hold human readable text that users will want
+/// to search in with `_contains` or `_contains_nocase` filters
+const TEXT_FIELD_SUFFIXES: &[&str] = &[
+    "name",
+    "title",
+    "description",
+    "symbol",
+    "label",
+    "text",
+    "content",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexLintKind {
+    /// A list field that is stored as an array; array attributes are not
+    /// indexed unless `GRAPH_STORE_CREATE_GIN_INDEXES` is set
+    UnindexedList,
+    /// A `String` field that looks like free text; substring filters can't
+    /// use the index on it
+    TextSearch,
+    /// A `String` or `Bytes` field that looks like it holds the id of
+    /// another entity, but is not declared as a reference
+    PlainForeignKey,
+}
+
+impl IndexLintKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IndexLintKind::UnindexedList => "UnindexedList",
+            IndexLintKind::TextSearch => "TextSearch",
+            IndexLintKind::PlainForeignKey => "PlainForeignKey",
+        }
+    }
+}
+
+/// A field that queries might filter on without an index to support that
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexLint {
+    pub kind: IndexLintKind,
+    pub entity_type: String,
+    pub field: String,
+    /// What the schema author could do about it
+    pub suggestion: String,
+}
+
+impl fmt::Display for IndexLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{} [{}]: {}",
+            self.entity_type,
+            self.field,
+            self.kind.as_str(),
+            self.suggestion
+        )
+    }
+}
+
+impl InputSchema {
+    /// Check the fields of all entity types for filters that the database
+    /// can not support with an index and return a warning for each of
+    /// them. The checks are heuristics based on the type and the name of
+    /// fields; the warnings are advisory and the schema is valid regardless
+    pub fn index_lints(&self) -> Vec<IndexLint> {
+        let pool = &self.inner.pool;
+        let poi = pool.lookup(POI_OBJECT).unwrap();
+        let entity_names: Vec<_> = self
+            .inner
+            .type_infos
+            .iter()
+            .filter_map(|ti| ti.object_type())
+            .filter(|obj_type| obj_type.name != poi)
+            .map(|obj_type| pool.get(obj_type.name).unwrap())
+            .collect();
+
+        let mut lints = Vec::new();
+        for obj_type in self
+            .inner
+            .type_infos
+            .iter()
+            .filter_map(|ti| ti.object_type())
+        {
+            if obj_type.name == poi || obj_type.timeseries {
+                continue;
+            }
+            let fulltext: Vec<_> = self
+                .entity_fulltext_definitions(pool.get(obj_type.name).unwrap())
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|def| def.included_fields)
+                .collect();
+            for field in obj_type.fields.iter() {
+                if field.is_derived() || field.name.as_str() == "id" {
+                    continue;
+                }
+                let lint = self
+                    .list_lint(field)
+                    .or_else(|| text_lint(field, &fulltext))
+                    .or_else(|| foreign_key_lint(field, &entity_names));
+                if let Some((kind, suggestion)) = lint {
+                    lints.push(IndexLint {
+                        kind,
+                        entity_type: pool.get(obj_type.name).unwrap().to_string(),
+                        field: field.name.to_string(),
+                        suggestion,
+                    });
+                }
+            }
+        }
+        lints
+    }
+
+    /// Lists of numbers and enums are never indexed, and filtering on them
+    /// is rare; lists of strings, bytes and references are only indexed
+    /// when GIN indexes are turned on
+    fn list_lint(&self, field: &Field) -> Option<(IndexLintKind, String)> {
+        if !field.is_list() {
+            return None;
+        }
+        let base = field.field_type.get_base_type();
+        let suggestion = if self.is_reference(base) {
+            format!(
+                "`{}_contains` filters scan the whole table since lists are not indexed; \
+                 consider adding a field that references this type to `{}` and making this \
+                 field `@derivedFrom` it",
+                field.name, base
+            )
+        } else if !self.is_enum_type(base)
+            && matches!(field.value_type, ValueType::String | ValueType::Bytes)
+        {
+            format!(
+                "`{}_contains` filters scan the whole table since lists are not indexed; \
+                 consider storing the values in their own entity type",
+                field.name
+            )
+        } else {
+            return None;
+        };
+        Some((IndexLintKind::UnindexedList, suggestion))
+    }
+}
+
+fn text_lint(field: &Field, fulltext: &[String]) -> Option<(IndexLintKind, String)> {
+    if field.field_type.get_base_type() != "String" {
+        return None;
+    }
+    let name = field.name.to_lowercase();
+    if !TEXT_FIELD_SUFFIXES
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+        || fulltext
+            .iter()
+            .any(|included| included == field.name.as_str())
+    {
+        return None;
+    }
+    Some((
+        IndexLintKind::TextSearch,
+        format!(
+            "`{}_contains` and `{}_contains_nocase` filters can not use an index; \
+             consider including the field in a `@fulltext` search",
+            field.name, field.name
+        ),
+    ))
+}
+
+fn foreign_key_lint(field: &Field, entity_names: &[&str]) -> Option<(IndexLintKind, String)> {
+    let base = field.field_type.get_base_type();
+    if base != "String" && base != "Bytes" {
+        return None;
+    }
+    let name = field.name.as_str();
+    let stem = name
+        .strip_suffix("Id")
+        .or_else(|| name.strip_suffix("ID"))
+        .or_else(|| name.strip_suffix("_id"))
+        .unwrap_or(name);
+    let target = entity_names
+        .iter()
+        .find(|entity| entity.eq_ignore_ascii_case(stem))?;
+    Some((
+        IndexLintKind::PlainForeignKey,
+        format!(
+            "the field looks like it holds the id of a `{}` but is a plain `{}`; \
+             declare it as `{}` so that the index on it can be used for nested \
+             `{}_` filters and `@derivedFrom` fields",
+            target, base, target, field.name
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::data::subgraph::LATEST_VERSION;
+    use crate::prelude::DeploymentHash;
+    use crate::schema::InputSchema;
+
+    use super::IndexLintKind;
+
+    const SCHEMA: &str = r#"
+        type Token @entity {
+          id: Bytes!
+          name: String!
+          symbol: String!
+          decimals: Int!
+          tags: [String!]!
+          amounts: [BigInt!]!
+          holders: [Account!]!
+          transfers: [Transfer!]! @derivedFrom(field: "token")
+        }
+
+        type Account @entity {
+          id: Bytes!
+        }
+
+        type Transfer @entity(immutable: true) {
+          id: Bytes!
+          token: Token!
+          accountId: Bytes!
+          memo: String
+        }
+    "#;
+
+    #[test]
+    fn finds_unindexed_fields() {
+        let id = DeploymentHash::new("indexLints").unwrap();
+        let schema = InputSchema::parse(LATEST_VERSION, SCHEMA, id).unwrap();
+
+        let mut lints: Vec<_> = schema
+            .index_lints()
+            .into_iter()
+            .map(|lint| (lint.entity_type, lint.field, lint.kind))
+            .collect();
+        lints.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+
+        let expected = vec![
+            ("Token", "holders", IndexLintKind::UnindexedList),
+            ("Token", "name", IndexLintKind::TextSearch),
+            ("Token", "symbol", IndexLintKind::TextSearch),
+            ("Token", "tags", IndexLintKind::UnindexedList),
+            ("Transfer", "accountId", IndexLintKind::PlainForeignKey),
+        ];
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(typ, field, kind)| (typ.to_string(), field.to_string(), kind))
+            .collect();
+        assert_eq!(expected, lints);
+    }
+}
@@ -1,4 +1,5 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::mem;
 use std::ops::Range;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -17,18 +18,27 @@ use crate::data::graphql::{DirectiveExt, DocumentExt, ObjectTypeExt, TypeExt, Va
 use crate::data::store::{
     self, EntityValidationError, IdType, IntoEntityIterator, TryIntoEntityIterator, ValueType, ID,
 };
+use crate::data::subgraph::SPEC_VERSION_1_3_0;
 use crate::data::value::Word;
 use crate::derive::CheapClone;
+use crate::env::ENV_VARS;
 use crate::prelude::q::Value;
-use crate::prelude::{s, DeploymentHash};
+use crate::prelude::{s, BlockNumber, DeploymentHash};
 use crate::schema::api::api_schema;
+use crate::util::cache_weight::CacheWeight;
 use crate::util::intern::{Atom, AtomPool};
 
 use crate::schema::fulltext::FulltextDefinition;
-use crate::schema::{ApiSchema, AsEntityTypeName, EntityType, Schema};
+use crate::schema::{
+    ApiSchema, AsEntityTypeName, EntityType, Schema, SchemaValidationError, SchemaValidationErrors,
+};
 
+mod inherit;
+mod lint;
 pub mod sqlexpr;
 
+pub use lint::{IndexLint, IndexLintKind};
+
 /// The name of the PoI entity type
 pub(crate) const POI_OBJECT: &str = "Poi$";
 /// The name of the digest attribute of POI entities
@@ -40,6 +50,7 @@ pub mod kw {
     pub const ENTITY: &str = "entity";
     pub const IMMUTABLE: &str = "immutable";
     pub const TIMESERIES: &str = "timeseries";
+    pub const RETENTION: &str = "retention";
     pub const TIMESTAMP: &str = "timestamp";
     pub const AGGREGATE: &str = "aggregate";
     pub const AGGREGATION: &str = "aggregation";
@@ -49,6 +60,15 @@ pub mod kw {
     pub const INTERVALS: &str = "intervals";
     pub const INTERVAL: &str = "interval";
     pub const CUMULATIVE: &str = "cumulative";
+    pub const INDEX: &str = "index";
+    pub const WHERE: &str = "where";
+    pub const CONNECTION: &str = "connection";
+    pub const SCALE: &str = "scale";
+    pub const DECIMALS: &str = "decimals";
+    pub const DEPRECATED: &str = "deprecated";
+    pub const REASON: &str = "reason";
+    pub const ABSTRACT: &str = "abstract";
+    pub const EXTENDS: &str = "extends";
 }
 
 /// The internal representation of a subgraph schema, i.e., the
@@ -159,7 +179,12 @@ impl TypeInfo {
 }
 
 impl TypeInfo {
-    fn for_object(schema: &Schema, pool: &AtomPool, obj_type: &s::ObjectType) -> Self {
+    fn for_object(
+        spec_version: &Version,
+        schema: &Schema,
+        pool: &AtomPool,
+        obj_type: &s::ObjectType,
+    ) -> Self {
         let shared_interfaces: Vec<_> = match schema.interfaces_for_type(&obj_type.name) {
             Some(intfs) => {
                 let mut shared_interfaces: Vec<_> = intfs
@@ -174,8 +199,13 @@ impl TypeInfo {
             }
             None => Vec::new(),
         };
-        let object_type =
-            ObjectType::new(schema, pool, obj_type, shared_interfaces.into_boxed_slice());
+        let object_type = ObjectType::new(
+            spec_version,
+            schema,
+            pool,
+            obj_type,
+            shared_interfaces.into_boxed_slice(),
+        );
         TypeInfo::Object(object_type)
     }
 
@@ -218,22 +248,32 @@ pub struct Field {
     pub name: Word,
     pub field_type: s::Type,
     pub value_type: ValueType,
-    derived_from: Option<Word>,
+    /// The fields of the child type that this field is derived from. This
+    /// is empty for fields that are not derived, and has more than one
+    /// entry for fields that use `@derivedFrom(fields: [..])`
+    derived_from: Vec<Word>,
+    /// The predicate from an `@index(where: ..)` directive. When this is
+    /// set, the index for the field only covers the rows for which the
+    /// predicate is true
+    index_where: Option<Word>,
+    /// The number of decimals from a `@scale(decimals: ..)` directive on a
+    /// `BigDecimal` field. Mappings can set such a field to the raw integer
+    /// as a `BigInt`, which the store divides by `10^decimals`; `BigDecimal`
+    /// values are rounded to that many digits after the decimal point when
+    /// they are stored or used in filters
+    scale: Option<i64>,
 }
 
 impl Field {
-    pub fn new(
-        schema: &Schema,
-        name: &str,
-        field_type: &s::Type,
-        derived_from: Option<Word>,
-    ) -> Self {
+    pub fn new(schema: &Schema, name: &str, field_type: &s::Type, derived_from: Vec<Word>) -> Self {
         let value_type = Self::scalar_value_type(&schema, field_type);
         Self {
             name: Word::from(name),
             field_type: field_type.clone(),
             value_type,
             derived_from,
+            index_where: None,
+            scale: None,
         }
     }
 
@@ -284,17 +324,65 @@ impl Field {
         self.field_type.is_list()
     }
 
-    pub fn derived_from<'a>(&self, schema: &'a InputSchema) -> Option<&'a Field> {
-        let derived_from = self.derived_from.as_ref()?;
+    /// The fields of the child type that this field is derived from. A
+    /// child belongs to the parent if all of these fields reference the
+    /// parent. The result is empty if this field is not derived
+    pub fn derived_from<'a>(&self, schema: &'a InputSchema) -> Vec<&'a Field> {
         let name = schema
             .pool()
             .lookup(&self.field_type.get_base_type())
             .unwrap();
-        schema.field(name, derived_from)
+        self.derived_from
+            .iter()
+            .filter_map(|derived_from| schema.field(name, derived_from))
+            .collect()
     }
 
     pub fn is_derived(&self) -> bool {
-        self.derived_from.is_some()
+        !self.derived_from.is_empty()
+    }
+
+    /// Whether the field uses `@derivedFrom(fields: [..])` with more than
+    /// one field, so that a child only belongs to it if all of these
+    /// fields reference the parent
+    pub fn is_derived_from_several(&self) -> bool {
+        self.derived_from.len() > 1
+    }
+
+    pub fn index_where(&self) -> Option<&str> {
+        self.index_where.as_deref()
+    }
+
+    pub fn scale(&self) -> Option<i64> {
+        self.scale
+    }
+
+    /// Apply the scale of this field to `value` if the field has one. A
+    /// `BigInt` is taken to be the raw integer and turned into the
+    /// `BigDecimal` `value / 10^decimals`, and a `BigDecimal` is rounded to
+    /// `decimals` digits after the decimal point. Other values are returned
+    /// unchanged
+    pub fn scale_value(&self, value: store::Value) -> store::Value {
+        fn round(value: store::Value, decimals: i64) -> store::Value {
+            match value {
+                store::Value::BigInt(raw) => {
+                    store::Value::BigDecimal(store::scalar::BigDecimal::new(raw, -decimals))
+                }
+                store::Value::BigDecimal(d) => store::Value::BigDecimal(d.round_to_scale(decimals)),
+                store::Value::List(values) => store::Value::List(
+                    values
+                        .into_iter()
+                        .map(|value| round(value, decimals))
+                        .collect(),
+                ),
+                value => value,
+            }
+        }
+
+        match self.scale {
+            Some(decimals) => round(value, decimals),
+            None => value,
+        }
     }
 }
 
@@ -368,9 +456,16 @@ impl<'a> ObjectOrInterface<'a> {
         }
     }
 
-    pub fn derived_from(&self, field_name: &str) -> Option<&str> {
-        let field = self.field(field_name)?;
-        field.derived_from.as_ref().map(|name| name.as_str())
+    pub fn derived_from(&self, field_name: &str) -> Vec<&str> {
+        self.field(field_name)
+            .map(|field| {
+                field
+                    .derived_from
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
     pub fn entity_type(&self) -> EntityType {
@@ -413,12 +508,22 @@ pub struct ObjectType {
     /// is part of an aggregation
     aggregation: Option<Atom>,
     pub timeseries: bool,
+    /// Whether the API schema should have a Relay-style connection query
+    /// for this type, set with `@entity(connection: true)`
+    pub connection: bool,
+    /// How long raw data points are kept if this is a timeseries with a
+    /// `retention` argument
+    pub retention: Option<Retention>,
     interfaces: Box<[Word]>,
     shared_interfaces: Box<[Atom]>,
+    /// The indices into `fields` of the fields that have a `@scale`
+    /// directive
+    scaled_fields: Box<[usize]>,
 }
 
 impl ObjectType {
     fn new(
+        spec_version: &Version,
         schema: &Schema,
         pool: &AtomPool,
         object_type: &s::ObjectType,
@@ -429,9 +534,39 @@ impl ObjectType {
             .fields
             .iter()
             .map(|field| {
-                let derived_from = field.derived_from().map(|name| Word::from(name));
-                Field::new(schema, &field.name, &field.field_type, derived_from)
+                let derived_from = field
+                    .derived_from_fields()
+                    .into_iter()
+                    .map(Word::from)
+                    .collect();
+                let index_where = field
+                    .find_directive(kw::INDEX)
+                    .and_then(|dir| dir.argument(kw::WHERE))
+                    .and_then(|value| value.as_str())
+                    .map(Word::from);
+                // Older spec versions did not know `@scale` and ignored
+                // it, and subgraphs deployed with them must keep storing
+                // the values they always stored
+                let scale = field
+                    .find_directive(kw::SCALE)
+                    .filter(|_| spec_version >= &SPEC_VERSION_1_3_0)
+                    .and_then(|dir| dir.argument(kw::DECIMALS))
+                    .and_then(|value| match value {
+                        Value::Int(decimals) => decimals.as_i64(),
+                        _ => None,
+                    });
+                Field {
+                    index_where,
+                    scale,
+                    ..Field::new(schema, &field.name, &field.field_type, derived_from)
+                }
             })
+            .collect::<Box<[Field]>>();
+        let scaled_fields = fields
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| field.scale.is_some())
+            .map(|(idx, _)| idx)
             .collect();
         let interfaces = object_type
             .implements_interfaces
@@ -452,6 +587,14 @@ impl ObjectType {
             None => timeseries,
             _ => unreachable!("validations ensure we don't get here"),
         };
+        let connection = match dir.argument(kw::CONNECTION) {
+            Some(Value::Boolean(conn)) => *conn,
+            None => false,
+            _ => unreachable!("validations ensure we don't get here"),
+        };
+        let retention = dir
+            .argument(kw::RETENTION)
+            .map(|value| Retention::parse(value).expect("validations ensure we don't get here"));
         Self {
             name,
             fields,
@@ -459,8 +602,11 @@ impl ObjectType {
             immutable,
             aggregation: None,
             timeseries,
+            connection,
+            retention,
             interfaces,
             shared_interfaces,
+            scaled_fields,
         }
     }
 
@@ -470,13 +616,17 @@ impl ObjectType {
                 name: ID.clone(),
                 field_type: s::Type::NamedType("ID".to_string()),
                 value_type: ValueType::String,
-                derived_from: None,
+                derived_from: vec![],
+                index_where: None,
+                scale: None,
             },
             Field {
                 name: Word::from(POI_DIGEST),
                 field_type: s::Type::NamedType("String".to_string()),
                 value_type: ValueType::String,
-                derived_from: None,
+                derived_from: vec![],
+                index_where: None,
+                scale: None,
             },
         ]
         .into_boxed_slice();
@@ -490,8 +640,11 @@ impl ObjectType {
             immutable: false,
             aggregation: None,
             timeseries: false,
+            connection: false,
+            retention: None,
             fields,
             shared_interfaces: Box::new([]),
+            scaled_fields: Box::new([]),
         }
     }
 
@@ -499,6 +652,11 @@ impl ObjectType {
         self.fields.iter().find(|field| field.name == name)
     }
 
+    /// The fields that have a `@scale` directive
+    pub fn scaled_fields(&self) -> impl Iterator<Item = &Field> {
+        self.scaled_fields.iter().map(|idx| &self.fields[*idx])
+    }
+
     /// Return `true` if this object type is part of an aggregation
     pub fn is_aggregation(&self) -> bool {
         self.aggregation.is_some()
@@ -533,7 +691,11 @@ impl InterfaceType {
                 // since the API schema does not contain certain filters for
                 // derived fields on interfaces that it would for
                 // non-derived fields
-                let derived_from = field.derived_from().map(|name| Word::from(name));
+                let derived_from = field
+                    .derived_from_fields()
+                    .into_iter()
+                    .map(Word::from)
+                    .collect();
                 Field::new(schema, &field.name, &field.field_type, derived_from)
             })
             .collect();
@@ -610,6 +772,7 @@ pub enum AggregateFn {
     Count,
     First,
     Last,
+    Avg,
 }
 
 impl FromStr for AggregateFn {
@@ -623,6 +786,7 @@ impl FromStr for AggregateFn {
             "count" => Ok(AggregateFn::Count),
             "first" => Ok(AggregateFn::First),
             "last" => Ok(AggregateFn::Last),
+            "avg" => Ok(AggregateFn::Avg),
             _ => Err(anyhow!("invalid aggregate function `{}`", s)),
         }
     }
@@ -632,7 +796,7 @@ impl AggregateFn {
     pub fn has_arg(&self) -> bool {
         use AggregateFn::*;
         match self {
-            Sum | Max | Min | First | Last => true,
+            Sum | Max | Min | First | Last | Avg => true,
             Count => false,
         }
     }
@@ -646,6 +810,7 @@ impl AggregateFn {
             Count => "count",
             First => "first",
             Last => "last",
+            Avg => "avg",
         }
     }
 }
@@ -738,6 +903,50 @@ impl FromStr for AggregationInterval {
     }
 }
 
+/// How long the raw data points of a timeseries are kept, as declared with
+/// `@entity(timeseries: true, retention: ..)`. An integer is a number of
+/// blocks, and a string like `"30d"` is a duration with one of the units
+/// `s`, `m`, `h`, `d`, or `w`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Retention {
+    /// Keep data points that were added in this many most recent blocks
+    Blocks(BlockNumber),
+    /// Keep data points whose timestamp is less than this far behind the
+    /// timestamp of the latest block
+    Duration(Duration),
+}
+
+impl Retention {
+    fn parse(value: &Value) -> Option<Self> {
+        match value {
+            Value::Int(blocks) => blocks
+                .as_i64()
+                .and_then(|blocks| BlockNumber::try_from(blocks).ok())
+                .filter(|blocks| *blocks > 0)
+                .map(Retention::Blocks),
+            Value::String(duration) => {
+                const UNITS: [(&str, u64); 5] = [
+                    ("s", 1),
+                    ("m", 60),
+                    ("h", 3600),
+                    ("d", 24 * 3600),
+                    ("w", 7 * 24 * 3600),
+                ];
+                let (count, secs) = UNITS.iter().find_map(|(unit, secs)| {
+                    duration.strip_suffix(unit).map(|count| (count, *secs))
+                })?;
+                count
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|count| *count > 0)
+                    .and_then(|count| count.checked_mul(secs))
+                    .map(|secs| Retention::Duration(Duration::from_secs(secs)))
+            }
+            _ => None,
+        }
+    }
+}
+
 /// The connection between the object type that stores the data points for
 /// an aggregation and the type that stores the finalised aggregations.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -827,7 +1036,9 @@ impl Aggregate {
             name: self.name.clone(),
             field_type: self.field_type.clone(),
             value_type: self.value_type,
-            derived_from: None,
+            derived_from: vec![],
+            index_where: None,
+            scale: None,
         }
     }
 }
@@ -863,7 +1074,7 @@ impl Aggregation {
             .fields
             .iter()
             .filter(|field| field.find_directive(kw::AGGREGATE).is_none())
-            .map(|field| Field::new(schema, &field.name, &field.field_type, None))
+            .map(|field| Field::new(schema, &field.name, &field.field_type, vec![]))
             .collect();
         let aggregates: Box<[_]> = agg_type
             .fields
@@ -888,8 +1099,11 @@ impl Aggregation {
                     immutable: true,
                     aggregation: Some(name),
                     timeseries: false,
+                    connection: false,
+                    retention: None,
                     interfaces: Box::new([]),
                     shared_interfaces: Box::new([]),
+                    scaled_fields: Box::new([]),
                 }
             })
             .collect();
@@ -956,11 +1170,78 @@ pub struct Inner {
     agg_mappings: Box<[AggregationMapping]>,
 }
 
+/// Options that control how an `InputSchema` is parsed
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    /// Whether `@fulltext` directives are allowed in the schema. Since
+    /// fulltext search is not deterministic, schemas that use it are
+    /// rejected unless this is set
+    pub allow_fulltext: bool,
+}
+
+impl ParseOptions {
+    pub fn allow_fulltext(mut self, allow_fulltext: bool) -> Self {
+        self.allow_fulltext = allow_fulltext;
+        self
+    }
+}
+
+impl Default for ParseOptions {
+    /// The default options are taken from the environment; in particular,
+    /// `allow_fulltext` is set from
+    /// `GRAPH_ALLOW_NON_DETERMINISTIC_FULLTEXT_SEARCH`
+    fn default() -> Self {
+        Self {
+            allow_fulltext: ENV_VARS.allow_non_deterministic_fulltext_search,
+        }
+    }
+}
+
+/// Limits on the size of a schema. They are only checked when a subgraph
+/// is deployed, so that lowering them does not break subgraphs that are
+/// already deployed
+#[derive(Clone, Debug)]
+pub struct SchemaLimits {
+    /// The maximum number of entity types and aggregations
+    pub max_entity_types: usize,
+    /// The maximum number of fields of an entity type, interface or
+    /// aggregation
+    pub max_fields_per_type: usize,
+    /// The maximum number of values of an enum
+    pub max_enum_values: usize,
+    /// The maximum number of `@fulltext` indexes
+    pub max_fulltext_indexes: usize,
+}
+
+impl Default for SchemaLimits {
+    /// The default limits are taken from the `GRAPH_SCHEMA_MAX_*`
+    /// environment variables
+    fn default() -> Self {
+        Self {
+            max_entity_types: ENV_VARS.schema_max_entity_types,
+            max_fields_per_type: ENV_VARS.schema_max_fields_per_type,
+            max_enum_values: ENV_VARS.schema_max_enum_values,
+            max_fulltext_indexes: ENV_VARS.schema_max_fulltext_indexes,
+        }
+    }
+}
+
 impl InputSchema {
     /// A convenience function for creating an `InputSchema` from the string
     /// representation of the subgraph's GraphQL schema `raw` and its
     /// deployment hash `id`. The returned schema is fully validated.
     pub fn parse(spec_version: &Version, raw: &str, id: DeploymentHash) -> Result<Self, Error> {
+        Self::parse_with_options(spec_version, raw, id, &ParseOptions::default())
+    }
+
+    /// Like `parse`, but with explicit `options` instead of the ones
+    /// derived from the environment
+    pub fn parse_with_options(
+        spec_version: &Version,
+        raw: &str,
+        id: DeploymentHash,
+        options: &ParseOptions,
+    ) -> Result<Self, Error> {
         fn agg_mappings(ts_types: &[TypeInfo]) -> Box<[AggregationMapping]> {
             let mut mappings: Vec<_> = ts_types
                 .iter()
@@ -983,19 +1264,21 @@ impl InputSchema {
             mappings.into_boxed_slice()
         }
 
-        let schema = Schema::parse(raw, id.clone())?;
-        validations::validate(spec_version, &schema).map_err(|errors| {
-            anyhow!(
-                "Validation errors in subgraph `{}`:\n{}",
-                id,
-                errors
-                    .into_iter()
-                    .enumerate()
-                    .map(|(n, e)| format!("  ({}) - {}", n + 1, e))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            )
-        })?;
+        let document = graphql_parser::parse_schema(raw)?.into_static();
+        let document =
+            inherit::inline_abstract_types(spec_version, document).map_err(|errors| {
+                SchemaValidationErrors {
+                    id: id.clone(),
+                    errors,
+                }
+            })?;
+        let schema = Schema::new(id.clone(), document)?;
+        validations::validate(spec_version, &schema)
+            .map_err(|errors| SchemaValidationErrors { id, errors })?;
+
+        if !options.allow_fulltext && !schema.document.fulltext_directives().is_empty() {
+            return Err(SchemaValidationError::FulltextNotAllowed.into());
+        }
 
         let pool = Arc::new(atom_pool(&schema.document));
 
@@ -1010,7 +1293,7 @@ impl InputSchema {
             .get_object_type_definitions()
             .into_iter()
             .filter(|obj_type| obj_type.find_directive("entity").is_some())
-            .map(|obj_type| TypeInfo::for_object(&schema, &pool, obj_type));
+            .map(|obj_type| TypeInfo::for_object(spec_version, &schema, &pool, obj_type));
         let intf_types = schema
             .document
             .get_interface_type_definitions()
@@ -1045,6 +1328,81 @@ impl InputSchema {
         })
     }
 
+    /// Check that the schema does not exceed any of `limits`
+    pub fn check_limits(&self, limits: &SchemaLimits) -> Result<(), Vec<SchemaValidationError>> {
+        let document = &self.inner.schema.document;
+        let mut errors = Vec::new();
+
+        let obj_types: Vec<_> = document
+            .get_object_type_definitions()
+            .into_iter()
+            .filter(|obj_type| {
+                obj_type.find_directive(kw::ENTITY).is_some()
+                    || obj_type.find_directive(kw::AGGREGATION).is_some()
+            })
+            .collect();
+        if obj_types.len() > limits.max_entity_types {
+            errors.push(SchemaValidationError::TooManyEntityTypes(
+                obj_types.len(),
+                limits.max_entity_types,
+            ));
+        }
+
+        let fields = obj_types
+            .iter()
+            .map(|obj_type| (&obj_type.name, obj_type.fields.len()))
+            .chain(
+                document
+                    .get_interface_type_definitions()
+                    .into_iter()
+                    .map(|intf_type| (&intf_type.name, intf_type.fields.len())),
+            );
+        for (name, count) in fields {
+            if count > limits.max_fields_per_type {
+                errors.push(SchemaValidationError::TooManyFields(
+                    name.clone(),
+                    count,
+                    limits.max_fields_per_type,
+                ));
+            }
+        }
+
+        for enum_type in document.get_enum_definitions() {
+            if enum_type.values.len() > limits.max_enum_values {
+                errors.push(SchemaValidationError::TooManyEnumValues(
+                    enum_type.name.clone(),
+                    enum_type.values.len(),
+                    limits.max_enum_values,
+                ));
+            }
+        }
+
+        let fulltext_indexes = document.fulltext_directives().len();
+        if fulltext_indexes > limits.max_fulltext_indexes {
+            errors.push(SchemaValidationError::TooManyFulltextIndexes(
+                fulltext_indexes,
+                limits.max_fulltext_indexes,
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check the schema of a subgraph that is being deployed. These checks
+    /// are not part of `parse` since that is also used to load the schemas
+    /// of existing deployments, and adding a check must not make
+    /// deployments that were created before it unloadable
+    pub fn validate_for_deployment(
+        &self,
+        spec_version: &Version,
+    ) -> Result<(), Vec<SchemaValidationError>> {
+        validations::validate_for_deployment(spec_version, &self.inner.schema)
+    }
+
     /// Parse with the latest spec version
     pub fn parse_latest(raw: &str, id: DeploymentHash) -> Result<Self, Error> {
         use crate::data::subgraph::LATEST_VERSION;
@@ -1092,10 +1450,15 @@ impl InputSchema {
     ///
     /// When asked to load the related entities from "Account" in the field "wallets"
     /// This function will return the type "Wallet" with the field "account"
+    ///
+    /// If the derived field has an interface type, return the type and
+    /// field for each object type that implements the interface, in the
+    /// order in which the implementers are declared. If the field is
+    /// derived from several fields, return each of them for each type
     pub fn get_field_related(
         &self,
         key: &LoadRelatedRequest,
-    ) -> Result<(EntityType, &Field), Error> {
+    ) -> Result<Vec<(EntityType, &Field)>, Error> {
         fn field_err(key: &LoadRelatedRequest, err: &str) -> Error {
             anyhow!(
                 "Entity {}[{}]: {err} `{}`",
@@ -1117,16 +1480,24 @@ impl InputSchema {
             return Err(field_err(key, "field is not derived"));
         }
 
-        let derived_from = field.find_directive("derivedFrom").unwrap();
         let entity_type = self.entity_type(field.field_type.get_base_type())?;
-        let field_name = derived_from.argument("field").unwrap();
-
-        let field = self
-            .object_type(entity_type.atom)?
-            .field(field_name.as_str().unwrap())
-            .ok_or_else(|| field_err(key, "unknown field"))?;
+        let field_names = field.derived_from_fields();
 
-        Ok((entity_type, field))
+        let obj_types: Vec<_> = match self.type_info(entity_type.atom)? {
+            TypeInfo::Interface(intf_type) => self.implementers(intf_type).collect(),
+            _ => vec![self.object_type(entity_type.atom)?],
+        };
+        obj_types
+            .into_iter()
+            .flat_map(|obj_type| {
+                field_names.iter().map(move |field_name| {
+                    let field = obj_type
+                        .field(field_name)
+                        .ok_or_else(|| field_err(key, "unknown field"))?;
+                    Ok((EntityType::new(self.cheap_clone(), obj_type.name), field))
+                })
+            })
+            .collect()
     }
 
     /// Return the `TypeInfo` for the type with name `atom`. For object and
@@ -1347,6 +1718,18 @@ impl InputSchema {
             .collect()
     }
 
+    /// Return a list of the entity types that are immutable. Besides the
+    /// types declared with `@entity(immutable: true)`, this includes
+    /// timeseries and the entity types for aggregations, which are always
+    /// immutable. This does not include the type for the PoI
+    pub fn immutable_entity_types(&self) -> Vec<EntityType> {
+        self.entity_types()
+            .into_iter()
+            .chain(self.ts_entity_types())
+            .filter(|entity_type| entity_type.is_immutable())
+            .collect()
+    }
+
     /// Return a list of all the aggregation mappings for this schema. The
     /// `interval` of the aggregations are non-decreasing
     pub fn agg_mappings(&self) -> impl Iterator<Item = &AggregationMapping> {
@@ -1364,15 +1747,18 @@ impl InputSchema {
         &self,
         entity: &str,
     ) -> Result<Vec<FulltextDefinition>, anyhow::Error> {
-        Self::fulltext_definitions(&self.inner.schema.document, entity)
+        Ok(Self::fulltext_definitions(
+            &self.inner.schema.document,
+            entity,
+        ))
     }
 
-    fn fulltext_definitions(
-        document: &s::Document,
-        entity: &str,
-    ) -> Result<Vec<FulltextDefinition>, anyhow::Error> {
-        Ok(document
-            .get_fulltext_directives()?
+    /// Parsing the schema ensures that it only contains `@fulltext`
+    /// directives if they are allowed, and we therefore do not need to
+    /// check that here
+    fn fulltext_definitions(document: &s::Document, entity: &str) -> Vec<FulltextDefinition> {
+        document
+            .fulltext_directives()
             .into_iter()
             .filter(|directive| match directive.argument("include") {
                 Some(Value::List(includes)) if !includes.is_empty() => {
@@ -1389,7 +1775,7 @@ impl InputSchema {
                 _ => false,
             })
             .map(FulltextDefinition::from)
-            .collect())
+            .collect()
     }
 
     pub fn id(&self) -> &DeploymentHash {
@@ -1401,7 +1787,7 @@ impl InputSchema {
     }
 
     pub fn get_fulltext_directives(&self) -> Result<Vec<&s::Directive>, Error> {
-        self.inner.schema.document.get_fulltext_directives()
+        Ok(self.inner.schema.document.fulltext_directives())
     }
 
     pub fn make_entity<I: IntoEntityIterator>(
@@ -1565,6 +1951,152 @@ impl InputSchema {
         }?;
         Some(EntityType::new(self.cheap_clone(), obj_type.name))
     }
+
+    /// Estimate how much memory the types in this schema use. Strings that
+    /// are interned in the schema's pool are only counted once, for the
+    /// first type that uses them, so that field names shared between types
+    /// are not double-counted. The estimate does not include the parsed
+    /// GraphQL document that the schema also holds on to
+    pub fn memory_report(&self) -> SchemaMemoryReport {
+        let pool = &self.inner.pool;
+        let poi = pool.lookup(POI_OBJECT).unwrap();
+        let mut seen = HashSet::new();
+        let mut interned = |name: &str| -> usize {
+            match pool.lookup(name) {
+                Some(atom) if seen.insert(atom) => interned_weight(name),
+                _ => 0,
+            }
+        };
+
+        let types = self
+            .inner
+            .type_infos
+            .iter()
+            .filter(|ti| ti.name() != poi)
+            .map(|ti| {
+                let name = pool.get(ti.name()).unwrap();
+                let mut interned_bytes = interned(name);
+                for field in ti.fields() {
+                    interned_bytes += interned(field.name.as_str());
+                }
+                if let TypeInfo::Aggregation(agg_type) = ti {
+                    for obj_type in agg_type.obj_types.iter() {
+                        interned_bytes += interned(pool.get(obj_type.name).unwrap());
+                    }
+                }
+                TypeMemory {
+                    name: name.to_string(),
+                    kind: ti.kind(),
+                    fields: ti.fields().len(),
+                    bytes: mem::size_of::<TypeInfo>() + ti.indirect_weight(),
+                    interned: interned_bytes,
+                }
+            })
+            .collect();
+        let enums = self
+            .inner
+            .enum_map
+            .0
+            .iter()
+            .map(|(name, values)| {
+                name.weight() + values.iter().map(CacheWeight::weight).sum::<usize>()
+            })
+            .sum();
+        SchemaMemoryReport { types, enums }
+    }
+}
+
+/// An estimate of the memory used by one type in an `InputSchema`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeMemory {
+    pub name: String,
+    pub kind: TypeKind,
+    /// The number of fields of the type
+    pub fields: usize,
+    /// The bytes used by the data structures describing the type
+    pub bytes: usize,
+    /// The bytes used by interned strings that were first used by this type
+    pub interned: usize,
+}
+
+impl TypeMemory {
+    pub fn total(&self) -> usize {
+        self.bytes + self.interned
+    }
+}
+
+/// A breakdown of the memory used by an `InputSchema` by type, as produced
+/// by `InputSchema::memory_report`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaMemoryReport {
+    /// The memory used by each type, in the order in which they are stored
+    /// in the schema
+    pub types: Vec<TypeMemory>,
+    /// The bytes used for the values of all enums
+    pub enums: usize,
+}
+
+impl SchemaMemoryReport {
+    pub fn total(&self) -> usize {
+        self.types.iter().map(TypeMemory::total).sum::<usize>() + self.enums
+    }
+}
+
+/// The pool keeps each interned string twice, once in the list of atoms
+/// and once as the key in the map from strings to atoms
+fn interned_weight(name: &str) -> usize {
+    2 * (mem::size_of::<Box<str>>() + name.len()) + mem::size_of::<Atom>()
+}
+
+fn type_weight(field_type: &s::Type) -> usize {
+    match field_type {
+        s::Type::NamedType(name) => name.capacity(),
+        s::Type::ListType(inner) | s::Type::NonNullType(inner) => {
+            mem::size_of::<s::Type>() + type_weight(inner)
+        }
+    }
+}
+
+impl CacheWeight for Field {
+    fn indirect_weight(&self) -> usize {
+        self.name.indirect_weight()
+            + type_weight(&self.field_type)
+            + self.derived_from.indirect_weight()
+            + self.index_where.indirect_weight()
+    }
+}
+
+impl CacheWeight for ObjectType {
+    fn indirect_weight(&self) -> usize {
+        self.fields.indirect_weight()
+            + self.interfaces.indirect_weight()
+            + self.shared_interfaces.len() * mem::size_of::<Atom>()
+            + self.scaled_fields.len() * mem::size_of::<usize>()
+    }
+}
+
+impl CacheWeight for Aggregate {
+    fn indirect_weight(&self) -> usize {
+        self.name.indirect_weight() + self.arg.indirect_weight() + type_weight(&self.field_type)
+    }
+}
+
+impl CacheWeight for TypeInfo {
+    fn indirect_weight(&self) -> usize {
+        match self {
+            TypeInfo::Object(obj_type) => obj_type.indirect_weight(),
+            TypeInfo::Interface(intf_type) => {
+                intf_type.fields.indirect_weight()
+                    + intf_type.implementers.len() * mem::size_of::<Atom>()
+            }
+            TypeInfo::Aggregation(agg_type) => {
+                agg_type.intervals.len() * mem::size_of::<AggregationInterval>()
+                    + agg_type.fields.indirect_weight()
+                    + agg_type.aggregates.indirect_weight()
+                    + agg_type.obj_types.indirect_weight()
+            }
+        }
+    }
 }
 
 /// Create a new pool that contains the names of all the types defined
@@ -1635,7 +2167,7 @@ fn atom_pool(document: &s::Document) -> AtomPool {
     }
 
     for object_type in document.get_object_type_definitions() {
-        for defn in InputSchema::fulltext_definitions(&document, &object_type.name).unwrap() {
+        for defn in InputSchema::fulltext_definitions(&document, &object_type.name) {
             pool.intern(defn.name.as_str());
         }
     }
@@ -1645,7 +2177,10 @@ fn atom_pool(document: &s::Document) -> AtomPool {
 
 /// Validations for an `InputSchema`.
 mod validations {
-    use std::{collections::HashSet, str::FromStr};
+    use std::{
+        collections::{hash_map::Entry, HashMap, HashSet},
+        str::FromStr,
+    };
 
     use itertools::Itertools;
     use semver::Version;
@@ -1653,17 +2188,20 @@ mod validations {
     use crate::{
         data::{
             graphql::{
-                ext::{DirectiveFinder, FieldExt},
+                ext::{camel_cased_names, DirectiveFinder, FieldExt},
                 DirectiveExt, DocumentExt, ObjectTypeExt, TypeExt, ValueExt,
             },
             store::{IdType, ValueType, ID},
-            subgraph::SPEC_VERSION_1_1_0,
+            subgraph::{SPEC_VERSION_1_1_0, SPEC_VERSION_1_3_0},
+            value::Word,
         },
         prelude::s,
         schema::{
-            input::{kw, sqlexpr, AggregateFn, AggregationInterval},
-            FulltextAlgorithm, FulltextLanguage, Schema as BaseSchema, SchemaValidationError,
-            SchemaValidationError as Err, Strings, SCHEMA_TYPE_NAME,
+            api::filter_field_names,
+            input::{kw, sqlexpr, AggregateFn, AggregationInterval, Field, Retention},
+            FulltextAlgorithm, FulltextLanguage, FulltextWeight, Schema as BaseSchema,
+            SchemaValidationError, SchemaValidationError as Err, Strings, META_FIELD_NAME,
+            SCHEMA_TYPE_NAME,
         },
     };
 
@@ -1710,6 +2248,29 @@ mod validations {
         }
     }
 
+    /// The validations for `InputSchema::validate_for_deployment`. They
+    /// can assume that `schema` passed `validate`
+    pub(super) fn validate_for_deployment(
+        spec_version: &Version,
+        schema: &BaseSchema,
+    ) -> Result<(), Vec<SchemaValidationError>> {
+        let schema = Schema::new(spec_version, schema);
+
+        let mut errors: Vec<SchemaValidationError> = [schema.validate_derived_from_cycles()]
+            .into_iter()
+            .filter_map(Result::err)
+            .collect();
+        errors.append(&mut schema.validate_index_directives());
+        errors.append(&mut schema.validate_scale_directives());
+        errors.append(&mut schema.validate_deprecated_directives());
+        errors.append(&mut schema.validate_generated_names());
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     impl<'a> Schema<'a> {
         fn new(spec_version: &'a Version, schema: &'a BaseSchema) -> Self {
             let subgraph_schema_type = schema.subgraph_schema_object_type();
@@ -1892,14 +2453,45 @@ mod validations {
                         };
 
                         for field_value in fields {
-                            let field_name = match field_value {
+                            let (field_name, field_map) = match field_value {
                             s::Value::Object(field_map) => match field_map.get("name") {
-                                Some(s::Value::String(name)) => name,
+                                Some(s::Value::String(name)) => (name, field_map),
                                 _ => return vec![SchemaValidationError::FulltextIncludedFieldMissingRequiredProperty],
                             },
                             _ => return vec![SchemaValidationError::FulltextIncludeEntityMissingOrIncorrectAttributes],
                         };
 
+                            // A field can override the language of the directive
+                            match field_map.get("language") {
+                                None => {}
+                                Some(s::Value::Enum(language)) => {
+                                    if FulltextLanguage::try_from(language.as_str()).is_err() {
+                                        return vec![
+                                            SchemaValidationError::FulltextLanguageInvalid(
+                                                language.to_string(),
+                                            ),
+                                        ];
+                                    }
+                                }
+                                Some(language) => {
+                                    return vec![SchemaValidationError::FulltextLanguageInvalid(
+                                        language.to_string(),
+                                    )]
+                                }
+                            }
+
+                            // A field can have a weight for ranking
+                            match field_map.get("weight") {
+                                None => {}
+                                Some(s::Value::Enum(weight))
+                                    if FulltextWeight::try_from(weight.as_str()).is_ok() => {}
+                                Some(weight) => {
+                                    return vec![SchemaValidationError::FulltextWeightInvalid(
+                                        weight.to_string(),
+                                    )]
+                                }
+                            }
+
                             // Validate the included field is a String field on the local entity types specified
                             if !&entity_type
                             .fields
@@ -1954,45 +2546,267 @@ mod validations {
                 })
         }
 
-        /// The `@entity` directive accepts two flags `immutable` and
-        /// `timeseries`, and when `timeseries` is `true`, `immutable` can
-        /// not be `false`.
-        ///
-        /// For timeseries, also check that there is a `timestamp` field of
-        /// type `Int8` and that the `id` field has type `Int8`
-        fn validate_entity_directives(&self) -> Vec<SchemaValidationError> {
-            fn id_type_is_int8(object_type: &s::ObjectType) -> Option<SchemaValidationError> {
-                let field = match object_type.field(&*ID) {
-                    Some(field) => field,
-                    None => {
-                        return Some(Err::IdFieldMissing(object_type.name.to_owned()));
+        /// An `@index(where: ..)` directive on a field of an entity type
+        /// makes the index for that field a partial index. The predicate
+        /// is a SQL expression that can only refer to other fields of the
+        /// entity type that are not derived, and that can only use the
+        /// operations and functions that aggregation expressions can use
+        fn validate_index_directives(&self) -> Vec<SchemaValidationError> {
+            let mut errors = Vec::new();
+            for object_type in &self.entity_types {
+                for field in &object_type.fields {
+                    let Some(dir) = field.find_directive(kw::INDEX) else {
+                        continue;
+                    };
+                    if field.is_derived() {
+                        errors.push(Err::IndexOnDerivedField(
+                            object_type.name.to_owned(),
+                            field.name.to_owned(),
+                        ));
+                        continue;
+                    }
+                    let Some(pred) = dir.argument(kw::WHERE).and_then(|value| value.as_str())
+                    else {
+                        errors.push(Err::IndexInvalidWhere(
+                            object_type.name.to_owned(),
+                            field.name.to_owned(),
+                        ));
+                        continue;
+                    };
+                    let check_ident = |ident: &str| -> Result<(), SchemaValidationError> {
+                        match object_type.field(ident) {
+                            Some(other) if !other.is_derived() => Ok(()),
+                            Some(_) | None => Err(Err::IndexUnknownField(
+                                object_type.name.to_owned(),
+                                field.name.to_owned(),
+                                ident.to_owned(),
+                            )),
+                        }
+                    };
+                    if let Err(mut errs) = sqlexpr::parse(pred, check_ident) {
+                        errors.append(&mut errs);
+                    } else if let Err(e) = sqlexpr::check_complete(pred) {
+                        errors.push(e);
                     }
-                };
-
-                match field.field_type.value_type() {
-                    Ok(ValueType::Int8) => None,
-                    Ok(_) | Err(_) => Some(Err::IllegalIdType(format!(
-                        "Timeseries `{}` must have an `id` field of type `Int8`",
-                        object_type.name
-                    ))),
                 }
             }
+            errors
+        }
 
-            fn bool_arg(
-                dir: &s::Directive,
-                name: &str,
-            ) -> Result<Option<bool>, SchemaValidationError> {
-                let arg = dir.argument(name);
-                match arg {
-                    Some(s::Value::Boolean(b)) => Ok(Some(*b)),
-                    Some(_) => Err(SchemaValidationError::EntityDirectiveNonBooleanArgValue(
-                        name.to_owned(),
-                    )),
-                    None => Ok(None),
+        /// A `@scale(decimals: ..)` directive can only be used on fields of
+        /// type `BigDecimal` or lists of them, and `decimals` must be an
+        /// integer between 0 and the number of significant digits that a
+        /// `BigDecimal` can have. The directive needs spec version 1.3.0
+        fn validate_scale_directives(&self) -> Vec<SchemaValidationError> {
+            let mut errors = Vec::new();
+            for object_type in &self.entity_types {
+                for field in &object_type.fields {
+                    let Some(dir) = field.find_directive(kw::SCALE) else {
+                        continue;
+                    };
+                    if self.spec_version < &SPEC_VERSION_1_3_0 {
+                        return vec![Err::ScaleNotSupported(self.spec_version.clone())];
+                    }
+                    if !matches!(field.field_type.value_type(), Ok(ValueType::BigDecimal)) {
+                        errors.push(Err::ScaleOnNonBigDecimalField(
+                            object_type.name.to_owned(),
+                            field.name.to_owned(),
+                        ));
+                        continue;
+                    }
+                    let decimals = match dir.argument(kw::DECIMALS) {
+                        Some(Value::Int(decimals)) => decimals.as_i64(),
+                        _ => None,
+                    };
+                    let max = store::scalar::BigDecimal::MAX_SIGNFICANT_DIGITS as i64;
+                    if !matches!(decimals, Some(decimals) if (0..=max).contains(&decimals)) {
+                        errors.push(Err::ScaleInvalidDecimals(
+                            object_type.name.to_owned(),
+                            field.name.to_owned(),
+                        ));
+                    }
                 }
             }
+            errors
+        }
 
-            self.entity_types
+        /// A `@deprecated` directive on a field can only have a `reason`
+        /// argument, and that must be a string. Deprecated fields are
+        /// marked as such in the API schema but can still be queried
+        fn validate_deprecated_directives(&self) -> Vec<SchemaValidationError> {
+            let mut errors = Vec::new();
+            let types = self.schema.document.get_object_and_interface_type_fields();
+            for (type_name, fields) in types {
+                for field in fields {
+                    let Some(dir) = field.find_directive(kw::DEPRECATED) else {
+                        continue;
+                    };
+                    let valid = dir.arguments.iter().all(|(name, value)| {
+                        name == kw::REASON && matches!(value, Value::String(_))
+                    });
+                    if !valid {
+                        errors.push(Err::DeprecatedInvalidArgument(
+                            type_name.to_owned(),
+                            field.name.to_owned(),
+                        ));
+                    }
+                }
+            }
+            errors
+        }
+
+        /// Check that the names that the API schema generates from the
+        /// schema do not collide with each other: the filters in the
+        /// `<Type>_filter` types for entity types and interfaces, and the
+        /// fields of the `Query` type. A collision happens for example when
+        /// a type has fields `name` and `name_not`, or when a field is
+        /// called `and`. Since this builds `Field`s, it must only be called
+        /// on schemas that passed `validate`
+        fn validate_generated_names(&self) -> Vec<SchemaValidationError> {
+            /// Record that `source` generates `name` in the type `typ`,
+            /// reporting each pair of sources that collide in `typ` only
+            /// once
+            fn add(
+                errors: &mut Vec<SchemaValidationError>,
+                reported: &mut HashSet<(String, String, String)>,
+                seen: &mut HashMap<String, String>,
+                typ: &str,
+                name: String,
+                source: String,
+            ) {
+                match seen.entry(name) {
+                    Entry::Occupied(entry) => {
+                        let first = entry.get();
+                        let key = (typ.to_string(), first.clone(), source.clone());
+                        if first != &source && reported.insert(key) {
+                            errors.push(Err::GeneratedNameCollision(
+                                typ.to_string(),
+                                entry.key().clone(),
+                                first.clone(),
+                                source,
+                            ));
+                        }
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(source);
+                    }
+                }
+            }
+
+            let mut errors = Vec::new();
+            let mut reported = HashSet::new();
+
+            let interfaces = self.schema.document.get_interface_type_definitions();
+            let types = self
+                .entity_types
+                .iter()
+                .map(|obj_type| (&obj_type.name, &obj_type.fields))
+                .chain(
+                    interfaces
+                        .iter()
+                        .map(|intf_type| (&intf_type.name, &intf_type.fields)),
+                );
+            for (type_name, fields) in types.clone() {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|field| {
+                        let derived_from = field
+                            .derived_from_fields()
+                            .into_iter()
+                            .map(Word::from)
+                            .collect();
+                        Field::new(self.schema, &field.name, &field.field_type, derived_from)
+                    })
+                    .collect();
+                let Ok(names) = filter_field_names(self.schema, &fields) else {
+                    continue;
+                };
+                let filter_type = format!("{}_filter", type_name);
+                let mut seen = HashMap::new();
+                for (name, field) in names {
+                    let source = match field {
+                        Some(field) => format!("field `{}`", field),
+                        None => "every filter type".to_string(),
+                    };
+                    add(
+                        &mut errors,
+                        &mut reported,
+                        &mut seen,
+                        &filter_type,
+                        name,
+                        source,
+                    );
+                }
+            }
+
+            let mut seen = HashMap::new();
+            seen.insert(
+                META_FIELD_NAME.to_string(),
+                "the subgraph metadata".to_string(),
+            );
+            for (type_name, _) in types {
+                let (singular, plural) = camel_cased_names(type_name);
+                for name in [singular, plural] {
+                    let source = format!("type `{}`", type_name);
+                    add(&mut errors, &mut reported, &mut seen, "Query", name, source);
+                }
+            }
+            for agg_type in &self.aggregations {
+                let (_, plural) = camel_cased_names(&agg_type.name);
+                let source = format!("aggregation `{}`", agg_type.name);
+                add(
+                    &mut errors,
+                    &mut reported,
+                    &mut seen,
+                    "Query",
+                    plural,
+                    source,
+                );
+            }
+            errors
+        }
+
+        /// The `@entity` directive accepts three flags `immutable`,
+        /// `timeseries` and `connection`, and when `timeseries` is `true`,
+        /// `immutable` can not be `false`.
+        ///
+        /// For timeseries, also check that there is a `timestamp` field of
+        /// type `Int8` and that the `id` field has type `Int8`. Only
+        /// timeseries can have a `retention`, and it must be a positive
+        /// number of blocks or a duration
+        fn validate_entity_directives(&self) -> Vec<SchemaValidationError> {
+            fn id_type_is_int8(object_type: &s::ObjectType) -> Option<SchemaValidationError> {
+                let field = match object_type.field(&*ID) {
+                    Some(field) => field,
+                    None => {
+                        return Some(Err::IdFieldMissing(object_type.name.to_owned()));
+                    }
+                };
+
+                match field.field_type.value_type() {
+                    Ok(ValueType::Int8) => None,
+                    Ok(_) | Err(_) => Some(Err::IllegalIdType(format!(
+                        "Timeseries `{}` must have an `id` field of type `Int8`",
+                        object_type.name
+                    ))),
+                }
+            }
+
+            fn bool_arg(
+                dir: &s::Directive,
+                name: &str,
+            ) -> Result<Option<bool>, SchemaValidationError> {
+                let arg = dir.argument(name);
+                match arg {
+                    Some(s::Value::Boolean(b)) => Ok(Some(*b)),
+                    Some(_) => Err(SchemaValidationError::EntityDirectiveNonBooleanArgValue(
+                        name.to_owned(),
+                    )),
+                    None => Ok(None),
+                }
+            }
+
+            self.entity_types
                 .iter()
                 .filter_map(|object_type| {
                     let dir = object_type.find_directive(kw::ENTITY).unwrap();
@@ -2004,6 +2818,21 @@ mod validations {
                         Ok(b) => b.unwrap_or(timeseries),
                         Err(e) => return Some(e),
                     };
+                    if let Err(e) = bool_arg(dir, kw::CONNECTION) {
+                        return Some(e);
+                    }
+                    if let Some(retention) = dir.argument(kw::RETENTION) {
+                        if !timeseries {
+                            return Some(SchemaValidationError::RetentionOnNonTimeseries(
+                                object_type.name.clone(),
+                            ));
+                        }
+                        if Retention::parse(retention).is_none() {
+                            return Some(SchemaValidationError::InvalidRetention(
+                                object_type.name.clone(),
+                            ));
+                        }
+                    }
                     if timeseries {
                         if !immutable {
                             Some(SchemaValidationError::MutableTimeseries(
@@ -2087,9 +2916,20 @@ mod validations {
                 "String".into(),
                 "Bytes".into(),
                 "BigInt".into(),
+                "Float".into(),
+                "Int8".into(),
+                "Timestamp".into(),
                 // Reserved Query and Subscription types
                 "Query".into(),
                 "Subscription".into(),
+                // The types that every API schema gets from `meta.graphql`
+                "_Meta_".into(),
+                "_Block_".into(),
+                "_SubgraphErrorPolicy_".into(),
+                "Block_height".into(),
+                "BlockChangedFilter".into(),
+                "OrderDirection".into(),
+                "Aggregation_interval".into(),
             ];
 
             reserved_types.append(&mut filter_types);
@@ -2153,8 +2993,8 @@ mod validations {
 
             // Iterate over all derived fields in all entity types; include the
             // interface types that the entity with the `@derivedFrom` implements
-            // and the `field` argument of @derivedFrom directive
-            for (object_type, interface_types, field, target_field) in self
+            // and the @derivedFrom directive
+            for (object_type, interface_types, field, directive) in self
                 .entity_types
                 .iter()
                 .flat_map(|object_type| {
@@ -2186,29 +3026,71 @@ mod validations {
                                 })
                                 .collect::<Vec<_>>(),
                             field,
-                            directive.argument("field"),
+                            directive,
                         )
                     })
                 })
             {
-                // Turn `target_field` into the string name of the field
-                let target_field = target_field.ok_or_else(|| {
-                    invalid(
-                        object_type,
-                        &field.name,
-                        "the @derivedFrom directive must have a `field` argument",
-                    )
-                })?;
-                let target_field = match target_field {
-                    s::Value::String(s) => s,
-                    _ => {
+                // Turn the `field` or `fields` argument into the string
+                // names of the fields we derive from
+                let target_names = match (directive.argument("field"), directive.argument("fields"))
+                {
+                    (Some(_), Some(_)) => {
+                        return Err(invalid(
+                            object_type,
+                            &field.name,
+                            "the @derivedFrom directive can not have both a `field` and a `fields` argument",
+                        ))
+                    }
+                    (None, None) => {
+                        return Err(invalid(
+                            object_type,
+                            &field.name,
+                            "the @derivedFrom directive must have a `field` argument",
+                        ))
+                    }
+                    (Some(s::Value::String(name)), None) => vec![name],
+                    (Some(_), None) => {
                         return Err(invalid(
                             object_type,
                             &field.name,
                             "the @derivedFrom `field` argument must be a string",
                         ))
                     }
+                    (None, Some(s::Value::List(values))) => {
+                        let names = values
+                            .iter()
+                            .map(|value| match value {
+                                s::Value::String(name) => Some(name),
+                                _ => None,
+                            })
+                            .collect::<Option<Vec<_>>>()
+                            .filter(|names| !names.is_empty())
+                            .ok_or_else(|| {
+                                invalid(
+                                    object_type,
+                                    &field.name,
+                                    "the @derivedFrom `fields` argument must be a non-empty list of strings",
+                                )
+                            })?;
+                        if names.iter().unique().count() != names.len() {
+                            return Err(invalid(
+                                object_type,
+                                &field.name,
+                                "the @derivedFrom `fields` argument must not list a field more than once",
+                            ));
+                        }
+                        names
+                    }
+                    (None, Some(_)) => {
+                        return Err(invalid(
+                            object_type,
+                            &field.name,
+                            "the @derivedFrom `fields` argument must be a non-empty list of strings",
+                        ))
+                    }
                 };
+                let composite = directive.argument("fields").is_some();
 
                 // Check that the type we are deriving from exists
                 let target_type_name = field.field_type.get_base_type();
@@ -2222,54 +3104,112 @@ mod validations {
                         )
                     })?;
 
-                // Check that the type we are deriving from has a field with the
-                // right name and type
-                let target_field = target_fields
-                    .iter()
-                    .find(|field| field.name.eq(target_field))
-                    .ok_or_else(|| {
+                for target_name in target_names {
+                    // Check that the type we are deriving from has a field with the
+                    // right name and type
+                    let target_field = target_fields
+                        .iter()
+                        .find(|field| field.name.eq(target_name))
+                        .ok_or_else(|| {
+                            let msg = format!(
+                                "field `{}` does not exist on type `{}`",
+                                target_name, target_type_name
+                            );
+                            invalid(object_type, &field.name, &msg)
+                        })?;
+
+                    // Queries look for the parent id in each of the fields
+                    // listed in `fields`, and they therefore all have to be
+                    // stored
+                    if composite && target_field.is_derived() {
                         let msg = format!(
-                            "field `{}` does not exist on type `{}`",
-                            target_field, target_type_name
+                            "field `{}` on type `{}` is derived and can not be listed in `fields`",
+                            target_name, target_type_name
                         );
-                        invalid(object_type, &field.name, &msg)
-                    })?;
-
-                // The field we are deriving from has to point back to us; as an
-                // exception, we allow deriving from the `id` of another type.
-                // For that, we will wind up comparing the `id`s of the two types
-                // when we query, and just assume that that's ok.
-                let target_field_type = target_field.field_type.get_base_type();
-                if target_field_type != object_type.name
-                    && &target_field.name != ID.as_str()
-                    && !interface_types
-                        .iter()
-                        .any(|iface| target_field_type.eq(iface.as_str()))
-                {
-                    fn type_signatures(name: &str) -> Vec<String> {
-                        vec![
-                            format!("{}", name),
-                            format!("{}!", name),
-                            format!("[{}!]", name),
-                            format!("[{}!]!", name),
-                        ]
+                        return Err(invalid(object_type, &field.name, &msg));
                     }
 
-                    let mut valid_types = type_signatures(&object_type.name);
-                    valid_types.extend(
-                        interface_types
+                    // The field we are deriving from has to point back to us; as an
+                    // exception, we allow deriving from the `id` of another type.
+                    // For that, we will wind up comparing the `id`s of the two types
+                    // when we query, and just assume that that's ok.
+                    let target_field_type = target_field.field_type.get_base_type();
+                    if target_field_type != object_type.name
+                        && &target_field.name != ID.as_str()
+                        && !interface_types
                             .iter()
-                            .flat_map(|iface| type_signatures(iface)),
+                            .any(|iface| target_field_type.eq(iface.as_str()))
+                    {
+                        fn type_signatures(name: &str) -> Vec<String> {
+                            vec![
+                                format!("{}", name),
+                                format!("{}!", name),
+                                format!("[{}!]", name),
+                                format!("[{}!]!", name),
+                            ]
+                        }
+
+                        let mut valid_types = type_signatures(&object_type.name);
+                        valid_types.extend(
+                            interface_types
+                                .iter()
+                                .flat_map(|iface| type_signatures(iface)),
+                        );
+                        let valid_types = valid_types.join(", ");
+
+                        let msg = format!(
+                        "field `{tf}` on type `{tt}` must have one of the following types: {valid_types}",
+                        tf = target_field.name,
+                        tt = target_type_name,
+                        valid_types = valid_types,
                     );
-                    let valid_types = valid_types.join(", ");
+                        return Err(invalid(object_type, &field.name, &msg));
+                    }
+                }
+            }
+            Ok(())
+        }
 
-                    let msg = format!(
-                    "field `{tf}` on type `{tt}` must have one of the following types: {valid_types}",
-                    tf = target_field.name,
-                    tt = target_type_name,
-                    valid_types = valid_types,
-                );
-                    return Err(invalid(object_type, &field.name, &msg));
+        /// Check that following a derived field to the field it is derived
+        /// from, and so on, always ends at a field that is stored. Chains of
+        /// derived fields are fine as long as they do not form a cycle
+        fn validate_derived_from_cycles(&self) -> Result<(), SchemaValidationError> {
+            fn derived_from<'a>(
+                type_fields: &HashMap<&'a str, &'a Vec<s::Field>>,
+                type_name: &str,
+                field_name: &str,
+            ) -> Option<(&'a str, &'a str)> {
+                let field = type_fields
+                    .get(type_name)?
+                    .iter()
+                    .find(|field| field.name == field_name)?;
+                match field.find_directive("derivedFrom")?.argument("field")? {
+                    s::Value::String(target) => {
+                        Some((field.field_type.get_base_type(), target.as_str()))
+                    }
+                    _ => None,
+                }
+            }
+
+            let type_fields = self.schema.document.get_object_and_interface_type_fields();
+
+            for type_name in type_fields.keys().sorted() {
+                for field in type_fields[type_name].iter() {
+                    let start = (*type_name, field.name.as_str());
+                    let mut path = vec![start];
+                    let mut current = start;
+                    while let Some(next) = derived_from(&type_fields, current.0, current.1) {
+                        if let Some(pos) = path.iter().position(|step| *step == next) {
+                            let cycle = path[pos..]
+                                .iter()
+                                .chain(std::iter::once(&next))
+                                .map(|(type_name, field)| format!("`{type_name}.{field}`"))
+                                .join(" -> ");
+                            return Err(SchemaValidationError::DerivedFromCycle(cycle));
+                        }
+                        path.push(next);
+                        current = next;
+                    }
                 }
             }
             Ok(())
@@ -2470,6 +3410,16 @@ mod validations {
                                 }
                             };
                             match agg.argument(kw::CUMULATIVE) {
+                                Some(s::Value::Boolean(true)) if func == AggregateFn::Avg => {
+                                    // Averages of two buckets can not be
+                                    // combined without knowing how many
+                                    // values went into each of them
+                                    errors.push(Err::AggregationCumulativeAvg(
+                                        agg_type.name.to_owned(),
+                                        field.name.to_owned(),
+                                    ));
+                                    continue;
+                                }
                                 Some(s::Value::Boolean(_)) | None => { /* ok */ }
                                 Some(_) => {
                                     errors.push(Err::AggregationInvalidCumulative(
@@ -2489,6 +3439,16 @@ mod validations {
                                     continue;
                                 }
                             };
+                            // Averages are computed with `numeric` division
+                            // and would lose precision in an integer field
+                            if func == AggregateFn::Avg && field_type != ValueType::BigDecimal {
+                                errors.push(Err::AggregationNonDecimalAvg(
+                                    agg_type.name.to_owned(),
+                                    field.name.to_owned(),
+                                    field_type.to_str().to_owned(),
+                                ));
+                                continue;
+                            }
                             // It would be nicer to use a proper struct here
                             // and have that implement
                             // `sqlexpr::ExprVisitor` but we need access to
@@ -2738,6 +3698,8 @@ type E @entity { id: ID! }
 type F @entity { id: ID! }
 type G @entity { id: ID! a: BigInt }
 type H @entity { id: ID! a: A! }
+type K @entity { id: ID! a: A! b: [A!]! g: G }
+type L @entity { id: ID! a: A! m: A @derivedFrom(field: \"id\") }
 # This sets up a situation where we need to allow `Transaction.from` to
 # point to an interface because of `Account.txn`
 type Transaction @entity { from: Address! }
@@ -2797,6 +3759,77 @@ type Account implements Address @entity { id: ID!, txn: Transaction! @derivedFro
                 "type must be an existing entity or interface",
             );
             validate("j: B @derivedFrom(field: \"id\")", "ok");
+            validate("k: [K!]! @derivedFrom(fields: [\"a\", \"b\"])", "ok");
+            validate(
+                "k: [K!]! @derivedFrom(fields: [\"a\", \"g\"])",
+                "field `g` on type `K` must have one of the following types: A, A!, [A!], [A!]!",
+            );
+            validate(
+                "k: [K!]! @derivedFrom(fields: [\"a\", \"c\"])",
+                "field `c` does not exist on type `K`",
+            );
+            validate(
+                "k: [K!]! @derivedFrom(fields: [])",
+                "the @derivedFrom `fields` argument must be a non-empty list of strings",
+            );
+            validate(
+                "k: [K!]! @derivedFrom(fields: \"a\")",
+                "the @derivedFrom `fields` argument must be a non-empty list of strings",
+            );
+            validate(
+                "k: [K!]! @derivedFrom(fields: [\"a\", \"a\"])",
+                "the @derivedFrom `fields` argument must not list a field more than once",
+            );
+            validate(
+                "k: [K!]! @derivedFrom(field: \"a\", fields: [\"b\"])",
+                "the @derivedFrom directive can not have both a `field` and a `fields` argument",
+            );
+            validate(
+                "l: [L!]! @derivedFrom(fields: [\"a\", \"m\"])",
+                "field `m` on type `L` is derived and can not be listed in `fields`",
+            );
+        }
+
+        #[test]
+        fn test_derived_from_cycles() {
+            fn validate(raw: &str) -> Result<(), SchemaValidationError> {
+                let document = graphql_parser::parse_schema(raw)
+                    .expect("Failed to parse raw schema")
+                    .into_static();
+                let schema = BaseSchema::new(DeploymentHash::new("id").unwrap(), document).unwrap();
+                let schema = Schema::new(LATEST_VERSION, &schema);
+                schema.validate_derived_from_cycles()
+            }
+
+            const CYCLE: &str = "
+type A @entity { id: ID!, bs: [B!]! @derivedFrom(field: \"a\") }
+type B @entity { id: ID!, a: A! @derivedFrom(field: \"bs\") }";
+            assert_eq!(
+                validate(CYCLE).unwrap_err().to_string(),
+                "The @derivedFrom fields `A.bs` -> `B.a` -> `A.bs` form a cycle"
+            );
+
+            const SELF_CYCLE: &str = "
+type A @entity { id: ID!, a: A @derivedFrom(field: \"a\") }";
+            assert_eq!(
+                validate(SELF_CYCLE).unwrap_err().to_string(),
+                "The @derivedFrom fields `A.a` -> `A.a` form a cycle"
+            );
+
+            // A chain of derived fields that ends at a stored field is fine
+            const CHAIN: &str = "
+type A @entity { id: ID!, b: B! }
+type B @entity { id: ID!, as: [A!]! @derivedFrom(field: \"b\") }
+type C @entity { id: ID!, bs: [B!]! @derivedFrom(field: \"as\") }";
+            assert!(validate(CHAIN).is_ok());
+
+            // Cycles are only rejected when a subgraph is deployed
+            let hash = DeploymentHash::new("test").unwrap();
+            let schema = crate::schema::InputSchema::parse_latest(CYCLE, hash).unwrap();
+            assert_eq!(
+                schema.validate_for_deployment(LATEST_VERSION).unwrap_err(),
+                vec![validate(CYCLE).unwrap_err()]
+            );
         }
 
         #[test]
@@ -2961,6 +3994,374 @@ type Gravatar @entity {
             assert_eq!(schema.validate_fulltext_directives(), vec![]);
         }
 
+        #[test]
+        fn test_fulltext_directive_field_languages() {
+            fn validate(fields: &str) -> Vec<SchemaValidationError> {
+                let raw = format!(
+                    r#"
+type _Schema_ @fulltext(
+  name: "search"
+  language: en
+  algorithm: rank
+  include: [{{ entity: "Post", fields: [{fields}] }}]
+)
+type Post @entity {{
+  id: ID!
+  title: String!
+  body: String!
+}}"#
+                );
+                let document = graphql_parser::parse_schema(&raw).expect("Failed to parse schema");
+                let schema =
+                    BaseSchema::new(DeploymentHash::new("id1").unwrap(), document).unwrap();
+                let schema = Schema::new(LATEST_VERSION, &schema);
+                schema.validate_fulltext_directives()
+            }
+
+            assert_eq!(validate(r#"{ name: "title" }, { name: "body" }"#), vec![]);
+            assert_eq!(
+                validate(r#"{ name: "title", language: de }, { name: "body" }"#),
+                vec![]
+            );
+            assert_eq!(
+                validate(r#"{ name: "title", language: xx }, { name: "body" }"#),
+                vec![SchemaValidationError::FulltextLanguageInvalid(
+                    "xx".to_string()
+                )]
+            );
+            assert_eq!(
+                validate(r#"{ name: "title", language: "de" }"#),
+                vec![SchemaValidationError::FulltextLanguageInvalid(
+                    "\"de\"".to_string()
+                )]
+            );
+        }
+
+        #[test]
+        fn test_fulltext_directive_field_weights() {
+            fn validate(fields: &str) -> Vec<SchemaValidationError> {
+                let raw = format!(
+                    r#"
+type _Schema_ @fulltext(
+  name: "search"
+  language: en
+  algorithm: rank
+  include: [{{ entity: "Post", fields: [{fields}] }}]
+)
+type Post @entity {{
+  id: ID!
+  title: String!
+  body: String!
+}}"#
+                );
+                let document = graphql_parser::parse_schema(&raw).expect("Failed to parse schema");
+                let schema =
+                    BaseSchema::new(DeploymentHash::new("id1").unwrap(), document).unwrap();
+                let schema = Schema::new(LATEST_VERSION, &schema);
+                schema.validate_fulltext_directives()
+            }
+
+            assert_eq!(
+                validate(r#"{ name: "title", weight: A }, { name: "body", weight: C }"#),
+                vec![]
+            );
+            assert_eq!(
+                validate(r#"{ name: "title", weight: E }, { name: "body" }"#),
+                vec![SchemaValidationError::FulltextWeightInvalid(
+                    "E".to_string()
+                )]
+            );
+            assert_eq!(
+                validate(r#"{ name: "title", weight: "A" }"#),
+                vec![SchemaValidationError::FulltextWeightInvalid(
+                    "\"A\"".to_string()
+                )]
+            );
+        }
+
+        #[test]
+        fn test_index_directive_validation() {
+            fn check(field: &str) -> Vec<SchemaValidationError> {
+                let raw = format!(
+                    "type Token @entity {{ id: ID!, active: Boolean!, supply: BigInt!, {field} }}
+                     type Holder @entity {{ id: ID!, token: Token! }}"
+                );
+                let schema = parse(&raw);
+                let schema = Schema::new(LATEST_VERSION, &schema);
+                schema.validate_index_directives()
+            }
+
+            assert_eq!(
+                check(r#"owner: Bytes @index(where: "active and supply > 0")"#),
+                vec![]
+            );
+            assert_eq!(
+                check(r#"owner: Bytes @index(where: "owner is not null")"#),
+                vec![]
+            );
+            assert_eq!(
+                check(r#"owner: Bytes @index(where: "balance > 0")"#),
+                vec![Err::IndexUnknownField(
+                    "Token".to_string(),
+                    "owner".to_string(),
+                    "balance".to_string()
+                )]
+            );
+            assert_eq!(
+                check(r#"owner: Bytes @index(where: true)"#),
+                vec![Err::IndexInvalidWhere(
+                    "Token".to_string(),
+                    "owner".to_string()
+                )]
+            );
+            assert_eq!(
+                check(
+                    r#"holders: [Holder!]! @derivedFrom(field: "token") @index(where: "active")"#
+                ),
+                vec![Err::IndexOnDerivedField(
+                    "Token".to_string(),
+                    "holders".to_string()
+                )]
+            );
+            let errs = check(r#"owner: Bytes @index(where: "active; drop table token")"#);
+            assert!(matches!(errs.as_slice(), [Err::ExprParseError(_)]));
+            let errs = check(r#"owner: Bytes @index(where: "random() > 0.5")"#);
+            assert!(matches!(errs.as_slice(), [Err::ExprNotSupported(_)]));
+
+            // The directive is only checked when a subgraph is deployed
+            let raw = r#"type Token @entity { id: ID!, owner: Bytes @index(where: true) }"#;
+            let hash = DeploymentHash::new("test").unwrap();
+            let schema = crate::schema::InputSchema::parse_latest(raw, hash).unwrap();
+            assert_eq!(
+                schema.validate_for_deployment(LATEST_VERSION).unwrap_err(),
+                vec![Err::IndexInvalidWhere(
+                    "Token".to_string(),
+                    "owner".to_string()
+                )]
+            );
+        }
+
+        #[test]
+        fn test_scale_directive_validation() {
+            fn check(field: &str) -> Vec<SchemaValidationError> {
+                let raw = format!("type Token @entity {{ id: ID!, {field} }}");
+                let schema = parse(&raw);
+                let schema = Schema::new(LATEST_VERSION, &schema);
+                schema.validate_scale_directives()
+            }
+
+            assert_eq!(check("price: BigDecimal! @scale(decimals: 18)"), vec![]);
+            assert_eq!(check("prices: [BigDecimal!]! @scale(decimals: 0)"), vec![]);
+            assert_eq!(
+                check("supply: BigInt! @scale(decimals: 18)"),
+                vec![Err::ScaleOnNonBigDecimalField(
+                    "Token".to_string(),
+                    "supply".to_string()
+                )]
+            );
+            for decimals in ["-1", "35", r#""18""#] {
+                assert_eq!(
+                    check(&format!("price: BigDecimal! @scale(decimals: {decimals})")),
+                    vec![Err::ScaleInvalidDecimals(
+                        "Token".to_string(),
+                        "price".to_string()
+                    )]
+                );
+            }
+            assert_eq!(
+                check("price: BigDecimal! @scale"),
+                vec![Err::ScaleInvalidDecimals(
+                    "Token".to_string(),
+                    "price".to_string()
+                )]
+            );
+
+            // Older spec versions do not support the directive
+            let schema =
+                parse("type Token @entity { id: ID!, price: BigDecimal! @scale(decimals: 18) }");
+            let version = crate::data::subgraph::SPEC_VERSION_1_2_0;
+            assert_eq!(
+                Schema::new(&version, &schema).validate_scale_directives(),
+                vec![Err::ScaleNotSupported(version.clone())]
+            );
+
+            // The directive is only checked when a subgraph is deployed
+            let raw = "type Token @entity { id: ID!, supply: BigInt! @scale(decimals: 18) }";
+            let hash = DeploymentHash::new("test").unwrap();
+            let schema = crate::schema::InputSchema::parse_latest(raw, hash).unwrap();
+            assert_eq!(
+                schema.validate_for_deployment(LATEST_VERSION).unwrap_err(),
+                vec![Err::ScaleOnNonBigDecimalField(
+                    "Token".to_string(),
+                    "supply".to_string()
+                )]
+            );
+        }
+
+        #[test]
+        fn test_deprecated_directive_validation() {
+            fn check(field: &str) -> Vec<SchemaValidationError> {
+                let raw = format!("type Token @entity {{ id: ID!, {field} }}");
+                let schema = parse(&raw);
+                let schema = Schema::new(LATEST_VERSION, &schema);
+                schema.validate_deprecated_directives()
+            }
+
+            assert_eq!(check("name: String! @deprecated"), vec![]);
+            assert_eq!(
+                check(r#"name: String! @deprecated(reason: "use symbol")"#),
+                vec![]
+            );
+            for args in ["reason: 1", r#"why: "use symbol""#] {
+                assert_eq!(
+                    check(&format!("name: String! @deprecated({args})")),
+                    vec![Err::DeprecatedInvalidArgument(
+                        "Token".to_string(),
+                        "name".to_string()
+                    )]
+                );
+            }
+
+            // The directive is only checked when a subgraph is deployed
+            let raw = "type Token @entity { id: ID!, name: String! @deprecated(reason: 1) }";
+            let hash = DeploymentHash::new("test").unwrap();
+            let schema = crate::schema::InputSchema::parse_latest(raw, hash).unwrap();
+            assert_eq!(
+                schema.validate_for_deployment(LATEST_VERSION).unwrap_err(),
+                vec![Err::DeprecatedInvalidArgument(
+                    "Token".to_string(),
+                    "name".to_string()
+                )]
+            );
+        }
+
+        #[test]
+        fn test_generated_names_validation() {
+            fn check(raw: &str) -> Vec<SchemaValidationError> {
+                let schema = parse(raw);
+                validate(&schema)
+                    .and_then(|()| super::validate_for_deployment(LATEST_VERSION, &schema))
+                    .err()
+                    .unwrap_or_default()
+            }
+
+            fn collision(typ: &str, name: &str, first: &str, second: &str) -> Err {
+                Err::GeneratedNameCollision(
+                    typ.to_string(),
+                    name.to_string(),
+                    first.to_string(),
+                    second.to_string(),
+                )
+            }
+
+            assert_eq!(
+                check(
+                    "type Token @entity { id: ID!, name: String!, owner: Account! }
+                     type Account @entity { id: ID!, tokens: [Token!]! @derivedFrom(field: \"owner\") }"
+                ),
+                vec![]
+            );
+            assert_eq!(
+                check("type Token @entity { id: ID!, name: String!, name_not: String! }"),
+                vec![collision(
+                    "Token_filter",
+                    "name_not",
+                    "field `name`",
+                    "field `name_not`"
+                )]
+            );
+            assert_eq!(
+                check(
+                    "type Token @entity { id: ID!, owner: Account!, owner_: String! }
+                     type Account @entity { id: ID! }"
+                ),
+                vec![collision(
+                    "Token_filter",
+                    "owner_",
+                    "field `owner`",
+                    "field `owner_`"
+                )]
+            );
+            assert_eq!(
+                check("type Token @entity { id: ID!, or: Boolean! }"),
+                vec![collision(
+                    "Token_filter",
+                    "or",
+                    "field `or`",
+                    "every filter type"
+                )]
+            );
+            assert_eq!(
+                check(
+                    "type Token @entity { id: ID! }
+                     type Tokens @entity { id: ID! }"
+                ),
+                vec![collision(
+                    "Query",
+                    "tokens",
+                    "type `Token`",
+                    "type `Tokens`"
+                )]
+            );
+            assert_eq!(
+                check("enum OrderDirection { asc, desc }"),
+                vec![Err::UsageOfReservedTypes(Strings(vec![
+                    "OrderDirection".to_string()
+                ]))]
+            );
+
+            // Collisions are only checked when a subgraph is deployed
+            let raw = "type Token @entity { id: ID!, name: String!, name_not: String! }";
+            let hash = DeploymentHash::new("test").unwrap();
+            let schema = crate::schema::InputSchema::parse_latest(raw, hash).unwrap();
+            assert_eq!(
+                schema.validate_for_deployment(LATEST_VERSION).unwrap_err(),
+                vec![collision(
+                    "Token_filter",
+                    "name_not",
+                    "field `name`",
+                    "field `name_not`"
+                )]
+            );
+        }
+
+        #[test]
+        fn test_retention_validation() {
+            fn check(args: &str) -> Vec<SchemaValidationError> {
+                let raw = format!(
+                    "type Data @entity({args}) {{ id: Int8!, timestamp: Timestamp!, price: BigDecimal! }}"
+                );
+                let schema = parse(&raw);
+                let schema = Schema::new(LATEST_VERSION, &schema);
+                schema.validate_entity_directives()
+            }
+
+            for retention in ["1000", r#""90s""#, r#""30d""#, r#""2w""#] {
+                assert_eq!(
+                    check(&format!("timeseries: true, retention: {retention}")),
+                    vec![]
+                );
+            }
+            for retention in ["0", "-5", r#""30""#, r#""d""#, r#""0h""#, r#""1y""#, "true"] {
+                assert_eq!(
+                    check(&format!("timeseries: true, retention: {retention}")),
+                    vec![Err::InvalidRetention("Data".to_string())]
+                );
+            }
+            assert_eq!(
+                check("retention: 1000"),
+                vec![Err::RetentionOnNonTimeseries("Data".to_string())]
+            );
+
+            let value = s::Value::String("12h".to_string());
+            assert_eq!(
+                Retention::parse(&value),
+                Some(Retention::Duration(std::time::Duration::from_secs(
+                    12 * 3600
+                )))
+            );
+        }
+
         #[test]
         fn agg() {
             fn parse_annotation(file_name: &str, line: &str) -> (bool, Version, String) {
@@ -3223,4 +4624,137 @@ mod tests {
         assert!(hippo_stats_hour_ent.share_interfaces().unwrap().is_empty());
         assert!(hippo_stats_hour_ent.is_immutable());
     }
+
+    #[test]
+    fn immutable_entity_types() {
+        let schema = make_schema();
+
+        let immutable: Vec<_> = schema
+            .immutable_entity_types()
+            .iter()
+            .map(|entity_type| entity_type.as_str().to_string())
+            .collect();
+        assert!(immutable.contains(&"HippoData".to_string()));
+        assert!(immutable.contains(&"HippoStats_hour".to_string()));
+        assert!(!immutable.contains(&"Hippo".to_string()));
+        assert!(!immutable.contains(&"Animal".to_string()));
+        assert!(!immutable.contains(&POI_OBJECT.to_string()));
+    }
+
+    #[test]
+    fn memory_report() {
+        use super::{interned_weight, TypeKind};
+
+        let schema = make_schema();
+        let report = schema.memory_report();
+
+        assert!(report.types.iter().all(|t| t.name != POI_OBJECT));
+
+        // `Rhino` only has names that other types also use, so that only
+        // its own name gets attributed to it
+        let rhino = report.types.iter().find(|t| t.name == "Rhino").unwrap();
+        assert_eq!(TypeKind::Object, rhino.kind);
+        assert_eq!(2, rhino.fields);
+        assert_eq!(interned_weight("Rhino"), rhino.interned);
+
+        let stats = report
+            .types
+            .iter()
+            .find(|t| t.name == "HippoStats")
+            .unwrap();
+        assert_eq!(TypeKind::Aggregation, stats.kind);
+        assert!(stats.bytes > 0);
+        assert!(report.total() > 0);
+    }
+    #[test]
+    fn parse_options_fulltext() {
+        use crate::data::subgraph::LATEST_VERSION;
+
+        use super::ParseOptions;
+
+        const SCHEMA: &str = r#"
+        type _Schema_ @fulltext(
+          name: "search"
+          language: en
+          algorithm: rank
+          include: [{ entity: "Thing", fields: [{ name: "name" }] }]
+        )
+
+        type Thing @entity {
+          id: ID!
+          name: String!
+        }"#;
+
+        let id = DeploymentHash::new("test").unwrap();
+        let allow = ParseOptions::default().allow_fulltext(true);
+        let schema =
+            InputSchema::parse_with_options(LATEST_VERSION, SCHEMA, id.clone(), &allow).unwrap();
+        assert_eq!(1, schema.get_fulltext_directives().unwrap().len());
+        assert_eq!(
+            1,
+            schema.entity_fulltext_definitions("Thing").unwrap().len()
+        );
+
+        let deny = ParseOptions::default().allow_fulltext(false);
+        let err = InputSchema::parse_with_options(LATEST_VERSION, SCHEMA, id, &deny).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Fulltext search is not yet deterministic"));
+    }
+    #[test]
+    fn validation_error_kinds() {
+        use crate::schema::SchemaValidationErrors;
+
+        const SCHEMA: &str = r#"
+        type Thing @entity {
+          name: String!
+        }"#;
+
+        let id = DeploymentHash::new("test").unwrap();
+        let err = InputSchema::parse_latest(SCHEMA, id).unwrap_err();
+        let errors = err
+            .downcast_ref::<SchemaValidationErrors>()
+            .expect("validation failures are reported as SchemaValidationErrors");
+        assert_eq!("IdFieldMissing", errors.errors[0].kind());
+    }
+    #[test]
+    fn check_limits() {
+        use super::SchemaLimits;
+        use crate::schema::SchemaValidationError::{
+            TooManyEntityTypes, TooManyEnumValues, TooManyFields,
+        };
+
+        const SCHEMA: &str = r#"
+        enum Color { RED, GREEN, BLUE }
+
+        type Thing @entity {
+          id: ID!
+          name: String!
+          color: Color!
+        }
+
+        type Other @entity {
+          id: ID!
+        }"#;
+
+        let schema =
+            InputSchema::parse_latest(SCHEMA, DeploymentHash::new("test").unwrap()).unwrap();
+
+        assert_eq!(Ok(()), schema.check_limits(&SchemaLimits::default()));
+
+        let limits = SchemaLimits {
+            max_entity_types: 1,
+            max_fields_per_type: 2,
+            max_enum_values: 2,
+            max_fulltext_indexes: 0,
+        };
+        assert_eq!(
+            Err(vec![
+                TooManyEntityTypes(2, 1),
+                TooManyFields("Thing".to_string(), 3, 2),
+                TooManyEnumValues("Color".to_string(), 3, 2),
+            ]),
+            schema.check_limits(&limits)
+        );
+    }
 }
@@ -2,7 +2,7 @@
 use sqlparser::ast as p;
 use sqlparser::dialect::PostgreSqlDialect;
 use sqlparser::parser::{Parser as SqlParser, ParserError};
-use sqlparser::tokenizer::Tokenizer;
+use sqlparser::tokenizer::{Token, Tokenizer};
 
 use crate::schema::SchemaValidationError;
 
@@ -26,6 +26,21 @@ pub(crate) fn parse<F: CheckIdentFn>(
         .map_err(|()| validator.errors)
 }
 
+/// Check that all of `sql` is a single expression. `parse` stops at the
+/// end of the first expression and ignores anything after it. That is
+/// harmless since the SQL that is generated from the expression leaves it
+/// out, but it is most likely a mistake in the schema
+pub(crate) fn check_complete(sql: &str) -> Result<(), SchemaValidationError> {
+    let dialect = PostgreSqlDialect {};
+    SqlParser::new(&dialect)
+        .try_with_sql(sql)
+        .and_then(|mut parser| {
+            parser.parse_expr()?;
+            parser.expect_token(&Token::EOF)
+        })
+        .map_err(|e| SchemaValidationError::ExprParseError(e.to_string()))
+}
+
 /// A visitor for `VistExpr` that gets called for the constructs for which
 /// we need different behavior between validation and query generation in
 /// `store/postgres/src/relational/rollup.rs`. Note that the visitor can
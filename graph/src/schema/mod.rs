@@ -6,6 +6,7 @@ use anyhow::Error;
 use graphql_parser::{self, Pos};
 use semver::Version;
 use serde::{Deserialize, Serialize};
+use strum_macros::IntoStaticStr;
 use thiserror::Error;
 
 use std::collections::BTreeMap;
@@ -28,12 +29,15 @@ pub use api::{is_introspection_field, APISchemaError, INTROSPECTION_QUERY_TYPE};
 pub use api::{ApiSchema, ErrorPolicy};
 pub use entity_key::EntityKey;
 pub use entity_type::{AsEntityTypeName, EntityType};
-pub use fulltext::{FulltextAlgorithm, FulltextConfig, FulltextDefinition, FulltextLanguage};
+pub use fulltext::{
+    FulltextAlgorithm, FulltextConfig, FulltextDefinition, FulltextLanguage, FulltextWeight,
+};
 pub use input::sqlexpr::{ExprVisitor, VisitExpr};
 pub(crate) use input::POI_OBJECT;
 pub use input::{
     kw, Aggregate, AggregateFn, Aggregation, AggregationInterval, AggregationMapping, Field,
-    InputSchema, InterfaceType, ObjectOrInterface, ObjectType, TypeKind,
+    IndexLint, IndexLintKind, InputSchema, InterfaceType, ObjectOrInterface, ObjectType,
+    ParseOptions, Retention, SchemaLimits, SchemaMemoryReport, TypeKind, TypeMemory,
 };
 
 pub const SCHEMA_TYPE_NAME: &str = "_Schema_";
@@ -46,6 +50,34 @@ pub const INTROSPECTION_TYPE_FIELD_NAME: &str = "__type";
 
 pub const BLOCK_FIELD_TYPE: &str = "_Block_";
 
+/// The directive that marks the `<Type>_version` types that history
+/// queries return in the API schema. Its `entity` argument is the name of
+/// the entity type whose versions the type holds
+pub const ENTITY_VERSION_DIRECTIVE: &str = "entityVersion";
+/// The fields of `<Type>_version` types for the range of blocks in which
+/// a version was current
+pub const BLOCK_START_FIELD_NAME: &str = "_block_start";
+pub const BLOCK_END_FIELD_NAME: &str = "_block_end";
+
+/// The directive that marks the `<Type>Connection` types that connection
+/// queries return in the API schema. Its `entity` argument is the name of
+/// the entity type that the connection pages through
+pub const ENTITY_CONNECTION_DIRECTIVE: &str = "entityConnection";
+
+/// Names of the types, fields and arguments of Relay-style connections
+pub mod connection {
+    pub const PAGE_INFO_TYPE: &str = "PageInfo";
+    pub const EDGES: &str = "edges";
+    pub const NODE: &str = "node";
+    pub const CURSOR: &str = "cursor";
+    pub const PAGE_INFO: &str = "pageInfo";
+    pub const HAS_NEXT_PAGE: &str = "hasNextPage";
+    pub const HAS_PREVIOUS_PAGE: &str = "hasPreviousPage";
+    pub const START_CURSOR: &str = "startCursor";
+    pub const END_CURSOR: &str = "endCursor";
+    pub const AFTER: &str = "after";
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Strings(Vec<String>);
 
@@ -56,7 +88,7 @@ impl fmt::Display for Strings {
     }
 }
 
-#[derive(Debug, Error, PartialEq, Eq)]
+#[derive(Debug, Error, PartialEq, Eq, IntoStaticStr)]
 pub enum SchemaValidationError {
     #[error("Interface `{0}` not defined")]
     InterfaceUndefined(String),
@@ -75,6 +107,8 @@ pub enum SchemaValidationError {
     InterfaceImplementorsMixId(String, String),
     #[error("Field `{1}` in type `{0}` has invalid @derivedFrom: {2}")]
     InvalidDerivedFrom(String, String, String), // (type, field, reason)
+    #[error("The @derivedFrom fields {0} form a cycle")]
+    DerivedFromCycle(String),
     #[error("The following type names are reserved: `{0}`")]
     UsageOfReservedTypes(Strings),
     #[error("_Schema_ type is only for @fulltext and must not have any fields")]
@@ -99,6 +133,8 @@ pub enum SchemaValidationError {
     FulltextAlgorithmUndefined,
     #[error("Fulltext algorithm is invalid: {0}")]
     FulltextAlgorithmInvalid(String),
+    #[error("Fulltext weight is invalid: {0}. It must be one of A, B, C, D")]
+    FulltextWeightInvalid(String),
     #[error("Fulltext include is invalid")]
     FulltextIncludeInvalid,
     #[error("Fulltext directive requires an 'include' list")]
@@ -147,6 +183,10 @@ pub enum SchemaValidationError {
     MutableTimeseries(String),
     #[error("Timeseries {0} is missing a `timestamp` field")]
     TimeseriesMissingTimestamp(String),
+    #[error("Type {0} has a `retention` but only timeseries can have one")]
+    RetentionOnNonTimeseries(String),
+    #[error("Timeseries {0} has an invalid `retention`: it must be a positive number of blocks or a duration like \"30d\"")]
+    InvalidRetention(String),
     #[error("Type {0} has a `timestamp` field of type {1}, but it must be of type Timestamp")]
     InvalidTimestampType(String, String),
     #[error("Aggregaton {0} uses {1} as the source, but there is no timeseries of that name")]
@@ -177,6 +217,10 @@ pub enum SchemaValidationError {
     AggregationNonNumericArg(String, String, String, String),
     #[error("Field {1} in aggregation {0} has an invalid value for `cumulative`. It needs to be a boolean")]
     AggregationInvalidCumulative(String, String),
+    #[error("Field {1} in aggregation {0} uses `avg` and can therefore not be cumulative")]
+    AggregationCumulativeAvg(String, String),
+    #[error("Field {1} in aggregation {0} uses `avg` and must have type BigDecimal, not {2}")]
+    AggregationNonDecimalAvg(String, String, String),
     #[error("Aggregations are not supported with spec version {0}; please migrate the subgraph to the latest version")]
     AggregationsNotSupported(Version),
     #[error("Using Int8 as the type for the `id` field is not supported with spec version {0}; please migrate the subgraph to the latest version")]
@@ -187,6 +231,73 @@ pub enum SchemaValidationError {
     ExprIllegalFunction(String),
     #[error("Failed to parse expression: {0}")]
     ExprParseError(String),
+    #[error("Fulltext search is not yet deterministic")]
+    FulltextNotAllowed,
+    #[error("Field {1} in type {0} is derived and can not have an @index directive")]
+    IndexOnDerivedField(String, String),
+    #[error(
+        "Field {1} in type {0} has an @index directive whose `where` argument is not a string"
+    )]
+    IndexInvalidWhere(String, String),
+    #[error("The `where` argument of the @index directive on field {1} in type {0} refers to `{2}` which is not a field of {0} that is stored")]
+    IndexUnknownField(String, String, String),
+    #[error("Field {1} in type {0} has a @scale directive but is not of type BigDecimal")]
+    ScaleOnNonBigDecimalField(String, String),
+    #[error("The `decimals` argument of the @scale directive on field {1} in type {0} must be an integer between 0 and 34")]
+    ScaleInvalidDecimals(String, String),
+    #[error("The @scale directive is not supported with spec version {0}; please migrate the subgraph to the latest version")]
+    ScaleNotSupported(Version),
+    #[error("The @deprecated directive on field {1} in type {0} can only have a string argument `reason`")]
+    DeprecatedInvalidArgument(String, String),
+    #[error("Abstract types are not supported with spec version {0}; please migrate the subgraph to the latest version")]
+    AbstractTypesNotSupported(Version),
+    #[error("Type {0} is @abstract and can therefore not be an @entity")]
+    AbstractEntity(String),
+    #[error("The `extends` argument of the @entity directive on type {0} must be the name of an @abstract type or a list of them")]
+    InvalidExtends(String),
+    #[error("Type {0} extends {1}, but there is no @abstract type of that name")]
+    ExtendsUnknownType(String, String),
+    #[error("Type {0} inherits the field {1} from {2}, but it already has a field of that name")]
+    InheritedFieldConflict(String, String, String),
+    #[error("Field {1} in type {0} has the @abstract type {2}, but abstract types can not be used as field types")]
+    AbstractTypeAsFieldType(String, String, String),
+    #[error(
+        "The generated type `{0}` would have two fields named `{1}`, one for {2} and one for {3}"
+    )]
+    GeneratedNameCollision(String, String, String, String), // (generated type, name, first source, second source)
+    #[error("The schema has {0} entity types and aggregations, but at most {1} are allowed")]
+    TooManyEntityTypes(usize, usize),
+    #[error("Type {0} has {1} fields, but at most {2} are allowed")]
+    TooManyFields(String, usize, usize),
+    #[error("Enum {0} has {1} values, but at most {2} are allowed")]
+    TooManyEnumValues(String, usize, usize),
+    #[error("The schema has {0} fulltext indexes, but at most {1} are allowed")]
+    TooManyFulltextIndexes(usize, usize),
+}
+
+impl SchemaValidationError {
+    /// A stable code for the kind of error. The code is the name of the
+    /// variant and can be used to categorize errors across many schemas
+    pub fn kind(&self) -> &'static str {
+        self.into()
+    }
+}
+
+/// All the errors that validating a subgraph schema found
+#[derive(Debug, Error, PartialEq, Eq)]
+pub struct SchemaValidationErrors {
+    pub id: DeploymentHash,
+    pub errors: Vec<SchemaValidationError>,
+}
+
+impl fmt::Display for SchemaValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Validation errors in subgraph `{}`:", self.id)?;
+        for (n, e) in self.errors.iter().enumerate() {
+            write!(f, "\n  ({}) - {}", n + 1, e)?;
+        }
+        Ok(())
+    }
 }
 
 /// A validated and preprocessed GraphQL schema for a subgraph.
@@ -55,7 +55,9 @@ impl ExponentialBackoff {
         delay.mul_f64(1.0 + jitter)
     }
 
-    fn next_attempt(&mut self) -> Duration {
+    /// Record that we made an attempt and return how long to wait before
+    /// the next one
+    pub fn next_attempt(&mut self) -> Duration {
         let delay = self.delay();
         self.attempt += 1;
         delay
@@ -1,4 +1,4 @@
-use graph::prelude::{debug, BlockPtr, CheapClone, Logger, QueryResult};
+use graph::prelude::{debug, BlockPtr, CacheWeight, CheapClone, Logger, QueryResult};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::{collections::HashMap, time::Duration};
@@ -12,8 +12,8 @@ struct CacheByBlock {
     max_weight: usize,
     weight: usize,
 
-    // The value is `(result, n_hits)`.
-    cache: HashMap<QueryHash, (Arc<QueryResult>, AtomicU64)>,
+    // The value is `(result, n_hits, inserted_at)`.
+    cache: HashMap<QueryHash, (Arc<QueryResult>, AtomicU64, Instant)>,
     total_insert_time: Duration,
 }
 
@@ -28,13 +28,18 @@ impl CacheByBlock {
         }
     }
 
-    fn get(&self, key: &QueryHash) -> Option<&Arc<QueryResult>> {
-        let (value, hit_count) = self.cache.get(key)?;
+    fn get(&self, key: &QueryHash, ttl: Option<Duration>) -> Option<&Arc<QueryResult>> {
+        let (value, hit_count, inserted_at) = self.cache.get(key)?;
+        if ttl.map_or(false, |ttl| inserted_at.elapsed() > ttl) {
+            return None;
+        }
         hit_count.fetch_add(1, Ordering::SeqCst);
         Some(value)
     }
 
     /// Returns `true` if the insert was successful or `false` if the cache was full.
+    /// Inserting a key that is already present replaces its entry, e.g.,
+    /// when the existing entry has expired
     fn insert(&mut self, key: QueryHash, value: Arc<QueryResult>, weight: usize) -> bool {
         // We never try to insert errors into this cache, and always resolve some value.
         assert!(!value.has_errors());
@@ -42,7 +47,12 @@ impl CacheByBlock {
         if fits_in_cache {
             let start = Instant::now();
             self.weight += weight;
-            self.cache.insert(key, (value, AtomicU64::new(0)));
+            if let Some((old, _, _)) = self
+                .cache
+                .insert(key, (value, AtomicU64::new(0), Instant::now()))
+            {
+                self.weight -= old.weight();
+            }
             self.total_insert_time += start.elapsed();
         }
         fits_in_cache
@@ -57,15 +67,20 @@ pub struct QueryBlockCache {
     cache_by_network: Vec<(String, VecDeque<CacheByBlock>)>,
     max_weight: usize,
     max_blocks: usize,
+    ttl: Option<Duration>,
 }
 
 impl QueryBlockCache {
-    pub fn new(max_blocks: usize, shard: u8, max_weight: usize) -> Self {
+    /// Create a cache that holds results for `max_blocks` blocks per
+    /// network. Results that were inserted more than `ttl` ago are not
+    /// returned anymore
+    pub fn new(max_blocks: usize, shard: u8, max_weight: usize, ttl: Option<Duration>) -> Self {
         QueryBlockCache {
             shard,
             cache_by_network: Vec::new(),
             max_weight,
             max_blocks,
+            ttl,
         }
     }
 
@@ -124,7 +139,7 @@ impl QueryBlockCache {
                 let insert_time_ms = block.total_insert_time.as_millis();
                 let mut dead_inserts = 0;
                 let mut total_hits = 0;
-                for (_, hits) in block.cache.values() {
+                for (_, hits, _) in block.cache.values() {
                     let hits = hits.load(Ordering::SeqCst);
                     total_hits += hits;
                     if hits == 0 {
@@ -165,7 +180,7 @@ impl QueryBlockCache {
         {
             // Iterate from the most recent block looking for a block that matches.
             if let Some(cache_by_block) = cache.iter().find(|c| &c.block == block_ptr) {
-                if let Some(response) = cache_by_block.get(key) {
+                if let Some(response) = cache_by_block.get(key, self.ttl) {
                     return Some(response.cheap_clone());
                 }
             }
@@ -173,3 +188,35 @@ impl QueryBlockCache {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use graph::data::value::Object;
+    use graph::prelude::{o, web3::types::H256};
+
+    use super::*;
+
+    #[test]
+    fn expired_results_are_not_returned() {
+        let logger = Logger::root(graph::slog::Discard, o!());
+        let block = BlockPtr::from((H256::zero(), 1_i32));
+        let key = [1u8; 32];
+        let result = Arc::new(QueryResult::new(Object::default()));
+
+        let mut cache = QueryBlockCache::new(1, 0, 1_000_000, None);
+        assert!(cache.insert(
+            "mainnet",
+            block.clone(),
+            key,
+            result.cheap_clone(),
+            1,
+            logger.clone()
+        ));
+        assert!(cache.get("mainnet", &block, &key).is_some());
+
+        let mut cache = QueryBlockCache::new(1, 0, 1_000_000, Some(Duration::from_millis(1)));
+        assert!(cache.insert("mainnet", block.clone(), key, result, 1, logger));
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(cache.get("mainnet", &block, &key).is_none());
+    }
+}
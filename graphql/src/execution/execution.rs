@@ -23,7 +23,7 @@ use graph::prelude::*;
 use graph::schema::ast as sast;
 use graph::util::{lfu_cache::LfuCache, stable_hash_glue::impl_stable_hash};
 
-use super::QueryHash;
+use super::{DataVersion, QueryHash};
 use crate::execution::ast as a;
 use crate::prelude::*;
 
@@ -55,7 +55,9 @@ lazy_static! {
             let mut caches = Vec::new();
             for i in 0..shards {
                 let id = format!("query_block_cache_{}", i);
-                caches.push(TimedMutex::new(QueryBlockCache::new(blocks, i, *MAX_WEIGHT), id))
+                let cache =
+                    QueryBlockCache::new(blocks, i, *MAX_WEIGHT, ENV_VARS.graphql.query_cache_ttl);
+                caches.push(TimedMutex::new(cache, id))
             }
             caches
     };
@@ -65,6 +67,16 @@ lazy_static! {
 struct WeightedResult {
     result: Arc<QueryResult>,
     weight: usize,
+    inserted_at: Instant,
+}
+
+impl WeightedResult {
+    fn expired(&self) -> bool {
+        ENV_VARS
+            .graphql
+            .query_cache_ttl
+            .map_or(false, |ttl| self.inserted_at.elapsed() > ttl)
+    }
 }
 
 impl CacheWeight for WeightedResult {
@@ -78,12 +90,15 @@ impl Default for WeightedResult {
         WeightedResult {
             result: Arc::new(QueryResult::new(Object::default())),
             weight: 0,
+            inserted_at: Instant::now(),
         }
     }
 }
 
 struct HashableQuery<'a> {
     query_schema_id: &'a DeploymentHash,
+    deployment_id: i32,
+    reorg_count: u32,
     selection_set: &'a a::SelectionSet,
     block_ptr: &'a BlockPtr,
 }
@@ -106,6 +121,8 @@ struct HashableQuery<'a> {
 // But, the wrong result should not be served.
 impl_stable_hash!(HashableQuery<'_> {
     query_schema_id,
+    deployment_id,
+    reorg_count,
     // Not stable! Uses to_string
     // TODO: Performance: Save a cryptographic hash (Blake3) of the original query
     // and pass it through, rather than formatting the selection set.
@@ -117,7 +134,9 @@ fn format_selection_set(s: &a::SelectionSet) -> String {
     format!("{:?}", s)
 }
 
-// The key is: subgraph id + selection set + variables + fragment definitions
+// The key is: subgraph id + data version + selection set + variables +
+// fragment definitions. Including the data version makes sure that results
+// computed before the deployment was reverted or redeployed are never used
 fn cache_key(
     ctx: &ExecutionContext<impl Resolver>,
     selection_set: &a::SelectionSet,
@@ -125,8 +144,14 @@ fn cache_key(
 ) -> QueryHash {
     // It is very important that all data used for the query is included.
     // Otherwise, incorrect results may be returned.
+    let DataVersion {
+        deployment_id,
+        reorg_count,
+    } = ctx.resolver.data_version();
     let query = HashableQuery {
         query_schema_id: ctx.query.schema.id(),
+        deployment_id,
+        reorg_count,
         selection_set,
         block_ptr,
     };
@@ -268,6 +293,22 @@ where
     }
 }
 
+/// Return `true` if `field` is a history query. History queries are
+/// resolved by the resolver directly and can not be prefetched
+fn is_history_field(
+    ctx: &ExecutionContext<impl Resolver>,
+    root_type: &sast::ObjectType,
+    field: &a::Field,
+) -> bool {
+    sast::get_field(root_type, &field.name)
+        .and_then(|field| {
+            ctx.query
+                .schema
+                .object_or_interface(field.field_type.get_base_type())
+        })
+        .map_or(false, |typ| typ.version_of().is_some())
+}
+
 pub(crate) async fn execute_root_selection_set_uncached(
     ctx: &ExecutionContext<impl Resolver>,
     selection_set: &a::SelectionSet,
@@ -285,7 +326,10 @@ pub(crate) async fn execute_root_selection_set_uncached(
         // the data_set SelectionSet
         if is_introspection_field(&field.name) {
             intro_set.push(field)?
-        } else if field.name == META_FIELD_NAME || field.name == "__typename" {
+        } else if field.name == META_FIELD_NAME
+            || field.name == "__typename"
+            || is_history_field(ctx, root_type, field)
+        {
             meta_items.push(field)
         } else {
             data_set.push(field)?
@@ -358,8 +402,10 @@ pub(crate) async fn execute_root_selection_set<R: Resolver>(
                 }
                 if let Some(mut cache) = lfu_cache(&ctx.logger, &cache_key) {
                     if let Some(weighted) = cache.get(&cache_key) {
-                        ctx.cache_status.store(CacheStatus::Hit);
-                        return weighted.result.cheap_clone();
+                        if !weighted.expired() {
+                            ctx.cache_status.store(CacheStatus::Hit);
+                            return weighted.result.cheap_clone();
+                        }
                     }
                 }
                 key = Some(cache_key);
@@ -469,6 +515,7 @@ pub(crate) async fn execute_root_selection_set<R: Resolver>(
                 WeightedResult {
                     result: result.cheap_clone(),
                     weight,
+                    inserted_at: Instant::now(),
                 },
             );
             ctx.cache_status.store(CacheStatus::Insert);
@@ -12,6 +12,6 @@ use stable_hash_legacy::{crypto::SetHasher, StableHasher};
 
 pub use self::execution::*;
 pub use self::query::Query;
-pub use self::resolver::Resolver;
+pub use self::resolver::{DataVersion, Resolver};
 
 type QueryHash = <SetHasher as StableHasher>::Out;
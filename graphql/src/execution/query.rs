@@ -137,6 +137,9 @@ pub struct Query {
     pub query_text: Arc<String>,
     pub variables_text: Arc<String>,
     pub query_id: String,
+
+    /// The complexity of the query as computed by `check_complexity`
+    pub complexity: u64,
 }
 
 fn validate_query(
@@ -258,10 +261,9 @@ impl Query {
         };
 
         // It's important to check complexity first, so `validate_fields`
-        // doesn't risk a stack overflow from invalid queries. We don't
-        // really care about the resulting complexity, only that all the
-        // checks that `check_complexity` performs pass successfully
-        let _ = raw_query.check_complexity(max_complexity, max_depth)?;
+        // doesn't risk a stack overflow from invalid queries. The resulting
+        // complexity is only used for reporting it in traces
+        let complexity = raw_query.check_complexity(max_complexity, max_depth)?;
         raw_query.validate_fields()?;
         let selection_set = raw_query.convert()?;
 
@@ -276,6 +278,7 @@ impl Query {
             query_text: query.query_text.cheap_clone(),
             variables_text: query.variables_text.cheap_clone(),
             query_id,
+            complexity,
         };
 
         Ok(Arc::new(query))
@@ -286,6 +289,7 @@ impl Query {
             &self.query_text,
             &self.variables_text,
             &self.query_id,
+            self.complexity,
             do_trace,
         )
     }
@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use graph::components::store::{QueryPermit, UnitStream};
 use graph::data::query::{CacheStatus, Trace};
+use graph::derive::CheapClone;
 use graph::prelude::{async_trait, s, Error, QueryExecutionError};
 use graph::schema::ApiSchema;
 use graph::{
@@ -13,11 +14,29 @@ use crate::execution::{ast as a, ExecutionContext};
 
 use super::Query;
 
+/// Identifies the version of a deployment's data that a resolver reads.
+/// Cached query results are only reused for resolvers with the same data
+/// version
+#[derive(Clone, Copy, CheapClone, Debug, Default, PartialEq, Eq)]
+pub struct DataVersion {
+    /// The internal id of the deployment; it changes when a deployment is
+    /// removed and deployed again
+    pub deployment_id: i32,
+    /// How many blocks the deployment has ever reverted
+    pub reorg_count: u32,
+}
+
 /// A GraphQL resolver that can resolve entities, enum values, scalar types and interfaces/unions.
 #[async_trait]
 pub trait Resolver: Sized + Send + Sync + 'static {
     const CACHEABLE: bool;
 
+    /// The version of the data this resolver reads. Only needs to be
+    /// implemented by resolvers that are `CACHEABLE`
+    fn data_version(&self) -> DataVersion {
+        DataVersion::default()
+    }
+
     async fn query_permit(&self) -> Result<QueryPermit, QueryExecutionError>;
 
     /// Prepare for executing a query by prefetching as much data as possible
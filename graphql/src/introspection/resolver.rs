@@ -1,5 +1,5 @@
 use graph::components::store::QueryPermit;
-use graph::data::graphql::ext::{FieldExt, TypeDefinitionExt};
+use graph::data::graphql::ext::{DirectiveFinder, FieldExt, TypeDefinitionExt};
 use graph::data::query::Trace;
 use std::collections::BTreeMap;
 
@@ -178,14 +178,28 @@ fn field_objects(
     )
 }
 
+/// The reason that the GraphQL spec prescribes for `@deprecated` when the
+/// directive does not give one
+const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+fn deprecation_reason(field: &s::Field) -> Option<String> {
+    field
+        .find_directive("deprecated")
+        .map(|dir| match dir.argument("reason") {
+            Some(s::Value::String(reason)) => reason.clone(),
+            _ => DEFAULT_DEPRECATION_REASON.to_string(),
+        })
+}
+
 fn field_object(schema: &Schema, type_objects: &mut TypeObjectsMap, field: &s::Field) -> r::Value {
+    let deprecation_reason = deprecation_reason(field);
     object! {
         name: field.name.clone(),
         description: field.description.clone(),
         args: input_values(schema, type_objects, &field.arguments),
         type: type_object(schema, type_objects, &field.field_type),
-        isDeprecated: false,
-        deprecationReason: r::Value::Null,
+        isDeprecated: deprecation_reason.is_some(),
+        deprecationReason: deprecation_reason,
     }
 }
 
@@ -408,6 +422,28 @@ impl Resolver for IntrospectionResolver {
                     Ok(r::Value::Null)
                 }
             }
+            "fields" => {
+                // Deprecated fields are only listed when the query asks for
+                // them with `fields(includeDeprecated: true)`
+                let include_deprecated = matches!(
+                    field.argument_value("includeDeprecated"),
+                    Some(r::Value::Boolean(true))
+                );
+                match prefetched_objects {
+                    Some(r::Value::List(fields)) if !include_deprecated => Ok(r::Value::List(
+                        fields
+                            .into_iter()
+                            .filter(|field| match field {
+                                r::Value::Object(field) => {
+                                    field.get("isDeprecated") != Some(&r::Value::Boolean(true))
+                                }
+                                _ => true,
+                            })
+                            .collect(),
+                    )),
+                    objects => Ok(objects.unwrap_or(r::Value::Null)),
+                }
+            }
             _ => Ok(prefetched_objects.unwrap_or(r::Value::Null)),
         }
     }
@@ -15,6 +15,7 @@ pub struct GraphQLMetrics {
     query_result_size_max: Box<Gauge>,
     query_validation_error_counter: Box<CounterVec>,
     query_blocks_behind: Box<HistogramVec>,
+    query_slow_count: Box<CounterVec>,
 }
 
 impl fmt::Debug for GraphQLMetrics {
@@ -147,6 +148,14 @@ impl GraphQLMetrics {
             )
             .unwrap();
 
+        let query_slow_count = registry
+            .new_counter_vec(
+                "query_slow_count",
+                "the number of GraphQL queries that took longer than the slow query threshold",
+                vec![String::from("deployment")],
+            )
+            .unwrap();
+
         Self {
             query_execution_time,
             query_parsing_time,
@@ -155,6 +164,7 @@ impl GraphQLMetrics {
             query_result_size_max,
             query_validation_error_counter,
             query_blocks_behind,
+            query_slow_count,
         }
     }
 
@@ -164,6 +174,12 @@ impl GraphQLMetrics {
         Self::new(registry)
     }
 
+    pub fn observe_slow_query(&self, id: &DeploymentHash) {
+        self.query_slow_count
+            .with_label_values(&[id.as_str()])
+            .inc();
+    }
+
     pub fn observe_query_result_size(&self, size: usize) {
         let size = size as f64;
         self.query_result_size.observe(size);
@@ -1,23 +1,26 @@
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::metrics::GraphQLMetrics;
 use crate::prelude::{QueryExecutionOptions, StoreResolver, SubscriptionExecutionOptions};
 use crate::query::execute_query;
 use crate::subscription::execute_prepared_subscription;
 use graph::futures03::future;
-use graph::prelude::MetricsRegistry;
+use graph::prelude::{regex::Regex, MetricsRegistry};
 use graph::{
     components::store::SubscriptionManager,
     prelude::{
-        async_trait, o, CheapClone, DeploymentState, GraphQLMetrics as GraphQLMetricsTrait,
+        async_trait, info, o, CheapClone, DeploymentState, GraphQLMetrics as GraphQLMetricsTrait,
         GraphQlRunner as GraphQlRunnerTrait, Logger, Query, QueryExecutionError, Subscription,
         SubscriptionError, SubscriptionResult, ENV_VARS,
     },
 };
-use graph::{data::graphql::load_manager::LoadManager, prelude::QueryStoreManager};
 use graph::{
-    data::query::{QueryResults, QueryTarget},
+    data::graphql::{disabled_fields::DisabledFields, load_manager::LoadManager},
+    prelude::QueryStoreManager,
+};
+use graph::{
+    data::query::{QueryResults, QueryTarget, Trace},
     prelude::QueryStore,
 };
 
@@ -27,6 +30,7 @@ pub struct GraphQlRunner<S, SM> {
     store: Arc<S>,
     subscription_manager: Arc<SM>,
     load_manager: Arc<LoadManager>,
+    disabled_fields: DisabledFields,
     graphql_metrics: Arc<GraphQLMetrics>,
 }
 
@@ -51,11 +55,13 @@ where
     ) -> Self {
         let logger = logger.new(o!("component" => "GraphQlRunner"));
         let graphql_metrics = Arc::new(GraphQLMetrics::new(registry));
+        let disabled_fields = DisabledFields::from_env(&logger);
         GraphQlRunner {
             logger,
             store,
             subscription_manager,
             load_manager,
+            disabled_fields,
             graphql_metrics,
         }
     }
@@ -115,7 +121,10 @@ where
         let store = self.store.query_store(target.clone(), false).await?;
         let state = store.deployment_state().await?;
         let network = Some(store.network_name().to_string());
-        let schema = store.api_schema()?;
+        let schema = self
+            .disabled_fields
+            .api_schema(store.api_schema()?)
+            .map_err(QueryExecutionError::from)?;
 
         // Test only, see c435c25decbc4ad7bbbadf8e0ced0ff2
         #[cfg(debug_assertions)]
@@ -125,8 +134,15 @@ where
             .clone()
             .unwrap_or(state);
 
+        // A limit for this deployment takes precedence over the one we
+        // were given
+        let max_complexity = ENV_VARS
+            .graphql
+            .max_complexity_for(schema.id().as_str(), max_complexity);
         let max_depth = max_depth.unwrap_or(ENV_VARS.graphql.max_depth);
         let do_trace = query.trace;
+        // Logging slow queries needs the SQL timing from the trace
+        let collect_trace = do_trace || ENV_VARS.graphql.slow_query_threshold.is_some();
         let query = crate::execution::Query::new(
             &self.logger,
             schema,
@@ -145,10 +161,12 @@ where
                 query.query_text.as_ref(),
             )
             .to_result()?;
+        // Hold on to the permit until the query has finished running
+        let _permit = self.load_manager.admit(store.shard()).await?;
         let by_block_constraint =
             StoreResolver::locate_blocks(store.as_ref(), &state, &query).await?;
         let mut max_block = 0;
-        let mut result: QueryResults = QueryResults::empty(query.root_trace(do_trace));
+        let mut result: QueryResults = QueryResults::empty(query.root_trace(collect_trace));
         let mut query_res_futures: Vec<_> = vec![];
         let setup_elapsed = execute_start.elapsed();
 
@@ -176,7 +194,7 @@ where
                     deadline: ENV_VARS.graphql.query_timeout.map(|t| Instant::now() + t),
                     max_first: max_first.unwrap_or(ENV_VARS.graphql.max_first),
                     max_skip: max_skip.unwrap_or(ENV_VARS.graphql.max_skip),
-                    trace: do_trace,
+                    trace: collect_trace,
                 },
             ));
         }
@@ -197,11 +215,65 @@ where
 
         query.log_execution(max_block);
         result.trace.finish(setup_elapsed, execute_start.elapsed());
+        if let Some(threshold) = ENV_VARS.graphql.slow_query_threshold {
+            let elapsed = execute_start.elapsed();
+            if elapsed >= threshold {
+                self.log_slow_query(&query, &result.trace, elapsed);
+            }
+        }
+        if !do_trace {
+            result.trace = Trace::None;
+        }
         self.deployment_changed(store.as_ref(), state, max_block as u64)
             .await
             .map_err(QueryResults::from)
             .map(|()| result)
     }
+
+    /// Log a query that took longer than the slow query threshold. The
+    /// `trace` must have been collected for the query
+    fn log_slow_query(&self, query: &crate::execution::Query, trace: &Trace, elapsed: Duration) {
+        let timing = trace.timing();
+        let sql_timing = timing
+            .sql
+            .iter()
+            .filter_map(|trace| match trace {
+                Trace::Query { elapsed, .. } => Some(elapsed.as_millis().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (query_text, variables) = if ENV_VARS.graphql.slow_query_redact {
+            (redact(&query.query_text), "(redacted)".to_string())
+        } else {
+            (
+                query.query_text.to_string(),
+                query.variables_text.to_string(),
+            )
+        };
+        info!(
+            query.logger,
+            "Slow query";
+            "query" => query_text,
+            "variables" => variables,
+            "query_time_ms" => elapsed.as_millis(),
+            "complexity" => query.complexity,
+            "planning_ms" => timing.planning.as_millis(),
+            "sql_ms" => timing.sql_total.as_millis(),
+            "sql_count" => timing.sql.len(),
+            "sql_timing_ms" => sql_timing,
+            "assembly_ms" => timing.assembly.as_millis(),
+        );
+        self.graphql_metrics.observe_slow_query(query.schema.id());
+    }
+}
+
+/// Replace all string literals in `query` with `"?"`
+fn redact(query: &str) -> String {
+    lazy_static::lazy_static! {
+        static ref STRING_LITERAL: Regex = Regex::new(r#""([^"\\]|\\.)*""#).unwrap();
+    }
+    STRING_LITERAL.replace_all(query, r#""?""#).into_owned()
 }
 
 #[async_trait]
@@ -250,15 +322,21 @@ where
         target: QueryTarget,
     ) -> Result<SubscriptionResult, SubscriptionError> {
         let store = self.store.query_store(target.clone(), true).await?;
-        let schema = store.api_schema()?;
+        let schema = self
+            .disabled_fields
+            .api_schema(store.api_schema()?)
+            .map_err(QueryExecutionError::from)?;
         let network = store.network_name().to_string();
+        let max_complexity = ENV_VARS
+            .graphql
+            .max_complexity_for(schema.id().as_str(), ENV_VARS.graphql.max_complexity);
 
         let query = crate::execution::Query::new(
             &self.logger,
             schema,
             Some(network),
             subscription.query,
-            ENV_VARS.graphql.max_complexity,
+            max_complexity,
             ENV_VARS.graphql.max_depth,
             self.graphql_metrics.cheap_clone(),
         )?;
@@ -284,7 +362,7 @@ where
                 store,
                 subscription_manager: self.subscription_manager.cheap_clone(),
                 timeout: ENV_VARS.graphql.query_timeout,
-                max_complexity: ENV_VARS.graphql.max_complexity,
+                max_complexity,
                 max_depth: ENV_VARS.graphql.max_depth,
                 max_first: ENV_VARS.graphql.max_first,
                 max_skip: ENV_VARS.graphql.max_skip,
@@ -0,0 +1,151 @@
+//! Opaque cursors for connection queries. A cursor records the attribute
+//! and the direction by which a connection was ordered together with the
+//! value of that attribute and the id of one entity, so that the next page
+//! can be fetched with a keyset filter instead of skipping rows
+
+use graph::components::store::{EntityFilter, EntityOrder};
+use graph::data::store::{Value, ID};
+use graph::data::value::Object;
+use graph::prelude::{hex, r, serde_json};
+use graph::schema::EntityType;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Cursor {
+    attr: String,
+    ascending: bool,
+    value: r::Value,
+    id: r::Value,
+}
+
+/// The attribute and direction that `order` sorts by, or `None` if
+/// connections can not be sorted that way
+fn order_key(order: &EntityOrder) -> Option<(&str, bool)> {
+    match order {
+        EntityOrder::Ascending(attr, _) => Some((attr.as_str(), true)),
+        EntityOrder::Descending(attr, _) => Some((attr.as_str(), false)),
+        EntityOrder::Default => Some((ID.as_str(), true)),
+        EntityOrder::ChildAscending(_)
+        | EntityOrder::ChildDescending(_)
+        | EntityOrder::Unordered => None,
+    }
+}
+
+impl Cursor {
+    /// The cursor that points at `object` in a connection that is ordered
+    /// by `order`
+    pub fn new(order: &EntityOrder, object: &Object) -> Option<Cursor> {
+        let (attr, ascending) = order_key(order)?;
+        let id = object.get(ID.as_str())?.clone();
+        let value = object.get(attr).cloned().unwrap_or(r::Value::Null);
+        Some(Cursor {
+            attr: attr.to_string(),
+            ascending,
+            value,
+            id,
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        let json = serde_json::json!([self.attr, self.ascending, self.value, self.id]);
+        hex::encode(json.to_string())
+    }
+
+    /// Parse a cursor that `encode` produced. Return `None` if `text` is
+    /// not a valid cursor
+    pub fn decode(text: &str) -> Option<Cursor> {
+        let bytes = hex::decode(text).ok()?;
+        let json: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+        let serde_json::Value::Array(elems) = json else {
+            return None;
+        };
+        let [attr, ascending, value, id]: [serde_json::Value; 4] = elems.try_into().ok()?;
+        Some(Cursor {
+            attr: attr.as_str()?.to_string(),
+            ascending: ascending.as_bool()?,
+            value: r::Value::from(value),
+            id: r::Value::from(id),
+        })
+    }
+
+    /// Return the filter for the entities of type `entity_type` that come
+    /// after this cursor when they are sorted by `order`. Return `None` if
+    /// the cursor was made for a different order, or if its values do not
+    /// fit the types of the attributes
+    pub fn filter(&self, order: &EntityOrder, entity_type: &EntityType) -> Option<EntityFilter> {
+        if order_key(order)? != (self.attr.as_str(), self.ascending) {
+            return None;
+        }
+        let value_type = |attr: &str| entity_type.field(attr).map(|field| &field.field_type);
+        let value = Value::from_query_value(&self.value, value_type(&self.attr)?).ok()?;
+        let id = Value::from_query_value(&self.id, value_type(ID.as_str())?).ok()?;
+        order.keyset_filter(value, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use graph::components::store::{EntityFilter as f, EntityOrder};
+    use graph::data::store::Value;
+    use graph::data::value::{Object, Word};
+    use graph::prelude::{hex, r, DeploymentHash, ValueType};
+    use graph::schema::InputSchema;
+
+    use super::Cursor;
+
+    const SCHEMA: &str = "type Thing @entity(connection: true) { id: ID!, size: BigInt }";
+
+    fn object(id: &str, size: Option<&str>) -> Object {
+        let size = size
+            .map(|size| r::Value::String(size.to_string()))
+            .unwrap_or(r::Value::Null);
+        Object::from_iter(vec![
+            (Word::from("id"), r::Value::String(id.to_string())),
+            (Word::from("size"), size),
+        ])
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let order = EntityOrder::Descending("size".to_string(), ValueType::BigInt);
+        for obj in [
+            object("t1", Some("12345678901234567890")),
+            object("t2", None),
+        ] {
+            let cursor = Cursor::new(&order, &obj).unwrap();
+            assert_eq!(Some(cursor.clone()), Cursor::decode(&cursor.encode()));
+        }
+        assert_eq!(None, Cursor::decode("not a cursor"));
+        assert_eq!(None, Cursor::decode(&hex::encode("[\"size\", true]")));
+    }
+
+    #[test]
+    fn cursor_filter() {
+        let schema = InputSchema::parse_latest(SCHEMA, DeploymentHash::new("test").unwrap())
+            .expect("schema is valid");
+        let thing = schema.entity_type("Thing").unwrap();
+        let order = EntityOrder::Ascending("size".to_string(), ValueType::BigInt);
+
+        let cursor = Cursor::new(&order, &object("t1", Some("7"))).unwrap();
+        let size = Value::BigInt(7i32.into());
+        let exp = f::Or(vec![
+            f::GreaterThan("size".to_string(), size.clone()),
+            f::And(vec![
+                f::Equal("size".to_string(), size),
+                f::GreaterThan("id".to_string(), Value::from("t1")),
+            ]),
+            f::Equal("size".to_string(), Value::Null),
+        ]);
+        assert_eq!(Some(exp), cursor.filter(&order, &thing));
+
+        // A cursor can only be used with the order it was made for
+        let desc = EntityOrder::Descending("size".to_string(), ValueType::BigInt);
+        assert_eq!(None, cursor.filter(&desc, &thing));
+        assert_eq!(None, cursor.filter(&EntityOrder::Default, &thing));
+
+        let cursor = Cursor::new(&EntityOrder::Default, &object("t1", None)).unwrap();
+        assert_eq!(
+            Some(f::GreaterThan("id".to_string(), Value::from("t1"))),
+            cursor.filter(&EntityOrder::Default, &thing)
+        );
+    }
+}
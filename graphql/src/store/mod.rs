@@ -1,3 +1,4 @@
+mod cursor;
 mod prefetch;
 mod query;
 mod resolver;
@@ -8,7 +8,8 @@ use graph::data::store::IdList;
 use graph::data::store::IdType;
 use graph::data::store::QueryObject;
 use graph::data::value::{Object, Word};
-use graph::prelude::{r, CacheWeight, CheapClone};
+use graph::prelude::{q, r, s, CacheWeight, CheapClone};
+use graph::schema::connection;
 use graph::schema::kw;
 use graph::schema::AggregationInterval;
 use graph::schema::Field;
@@ -21,13 +22,15 @@ use std::time::Instant;
 use graph::data::graphql::TypeExt;
 use graph::prelude::{
     AttributeNames, ChildMultiplicity, EntityCollection, EntityFilter, EntityLink, EntityOrder,
-    EntityWindow, ParentLink, QueryExecutionError, Value as StoreValue, WindowAttribute, ENV_VARS,
+    EntityRange, EntityWindow, ParentLink, QueryExecutionError, Value as StoreValue,
+    WindowAttribute, ENV_VARS,
 };
 use graph::schema::{EntityType, InputSchema, ObjectOrInterface};
 
 use crate::execution::ast as a;
 use crate::metrics::GraphQLMetrics;
-use crate::store::query::build_query;
+use crate::store::cursor::Cursor;
+use crate::store::query::{build_query, bytes_from_query};
 use crate::store::StoreResolver;
 
 pub const ARG_ID: &str = "id";
@@ -188,6 +191,22 @@ impl ValueExt for r::Value {
 }
 
 impl Node {
+    /// A node for an object of type `typename` that is not an entity, like
+    /// the objects that make up a connection
+    fn object(typename: &str, fields: Vec<(&str, r::Value)>) -> Self {
+        let entity = Object::from_iter(
+            std::iter::once(("__typename", r::Value::String(typename.to_string())))
+                .chain(fields)
+                .map(|(key, value)| (Word::from(key), value)),
+        );
+        Node {
+            children_weight: entity.weight(),
+            parent: None,
+            entity,
+            children: BTreeMap::default(),
+        }
+    }
+
     fn id(&self, schema: &InputSchema) -> Result<Id, QueryExecutionError> {
         let entity_type = schema.entity_type(self.typename())?;
         match self.get("id") {
@@ -257,8 +276,9 @@ impl JoinField {
 
 #[derive(Debug)]
 enum JoinRelation {
-    // Name of field in which child stores parent ids
-    Direct(JoinField),
+    // Name of field in which child stores parent ids; fields derived from
+    // several fields have one entry for each of them
+    Direct(Vec<JoinField>),
     // Name of the field in the parent type containing child ids
     Derived(JoinField),
 }
@@ -281,8 +301,9 @@ impl JoinCond {
         child_type: EntityType,
         field: &Field,
     ) -> Self {
-        let relation = if let Some(derived_from_field) = field.derived_from(schema) {
-            JoinRelation::Direct(JoinField::new(derived_from_field))
+        let derived_from = field.derived_from(schema);
+        let relation = if !derived_from.is_empty() {
+            JoinRelation::Direct(derived_from.into_iter().map(JoinField::new).collect())
         } else {
             JoinRelation::Derived(JoinField::new(field))
         };
@@ -299,16 +320,20 @@ impl JoinCond {
         multiplicity: ChildMultiplicity,
     ) -> Result<(IdList, EntityLink), QueryExecutionError> {
         match &self.relation {
-            JoinRelation::Direct(field) => {
+            JoinRelation::Direct(fields) => {
                 // we only need the parent ids
                 let ids = IdList::try_from_iter(
                     self.parent_type.id_type()?,
                     parents_by_id.into_iter().map(|(id, _)| id),
                 )?;
-                Ok((
-                    ids,
-                    EntityLink::Direct(field.window_attribute(), multiplicity),
-                ))
+                let link = match fields.as_slice() {
+                    [field] => EntityLink::Direct(field.window_attribute(), multiplicity),
+                    _ => EntityLink::Composite(
+                        fields.iter().map(JoinField::window_attribute).collect(),
+                        multiplicity,
+                    ),
+                };
+                Ok((ids, link))
             }
             JoinRelation::Derived(field) => {
                 let (ids, parent_link) = match field {
@@ -622,6 +647,28 @@ impl<'a> Loader<'a> {
                 let field_type = object_type
                     .field(&field.name)
                     .expect("field names are valid");
+                let connection_of = self
+                    .ctx
+                    .query
+                    .schema
+                    .object_or_interface(field_type.field_type.get_base_type())
+                    .and_then(|typ| typ.connection_of());
+                if let (true, Some(entity)) = (at_root, connection_of) {
+                    match self.fetch_connection(entity, field) {
+                        Ok((node, trace)) => {
+                            add_children(
+                                &input_schema,
+                                &mut parents,
+                                vec![node],
+                                field.response_key(),
+                            )?;
+                            self.check_result_size(&parents)?;
+                            parent_trace.push(field.response_key(), trace);
+                        }
+                        Err(mut e) => errors.append(&mut e),
+                    }
+                    continue;
+                }
                 let child_type = input_schema
                     .object_or_interface(field_type.field_type.get_base_type(), child_interval)
                     .expect("we only collect fields that are objects or interfaces");
@@ -720,10 +767,12 @@ impl<'a> Loader<'a> {
         }
         query.logger = Some(self.ctx.logger.cheap_clone());
         if let Some(r::Value::String(id)) = field.argument_value(ARG_ID) {
-            query.filter = Some(
-                EntityFilter::Equal(ARG_ID.to_owned(), StoreValue::from(id.clone()))
-                    .and_maybe(query.filter),
-            );
+            let id = StoreValue::from(id.clone());
+            let id = match child_type.field(ARG_ID) {
+                Some(id_field) => bytes_from_query(id, id_field.value_type)?,
+                None => id,
+            };
+            query.filter = Some(EntityFilter::Equal(ARG_ID.to_owned(), id).and_maybe(query.filter));
         }
 
         if let MaybeJoin::Nested(join) = join {
@@ -746,6 +795,168 @@ impl<'a> Loader<'a> {
             .map(|(values, trace)| (values.into_iter().map(Node::from).collect(), trace))
     }
 
+    /// Fetch one page of the connection query `field` for entities of type
+    /// `entity`. Instead of skipping rows, the page starts after the
+    /// `after` cursor by filtering on the order attribute and the id of the
+    /// entity that the cursor points at. We ask for one more entity than
+    /// fits on the page to find out whether there is a next page.
+    ///
+    /// The result is one node for the `<Type>Connection` object whose
+    /// children are the nodes for its edges and its page info
+    fn fetch_connection(
+        &self,
+        entity: &str,
+        field: &a::Field,
+    ) -> Result<(Node, Trace), Vec<QueryExecutionError>> {
+        fn fields_named<'f>(selection_set: &'f a::SelectionSet, name: &str) -> Vec<&'f a::Field> {
+            selection_set
+                .fields()
+                .flat_map(|(_, fields)| fields)
+                .filter(|field| field.name == name)
+                .collect()
+        }
+
+        let schema = &self.ctx.query.schema;
+        let input_schema = self.resolver.store.input_schema()?;
+        let entity_type = input_schema
+            .entity_type(entity)
+            .map_err(QueryExecutionError::from)?;
+        let child_type = input_schema
+            .object_or_interface(entity, None)
+            .expect("connections are only generated for entity types");
+
+        let edges = fields_named(&field.selection_set, connection::EDGES);
+        let page_infos = fields_named(&field.selection_set, connection::PAGE_INFO);
+
+        // Select the fields of all `node` fields in the query for the entities
+        let object_type = match schema.get_named_type(entity) {
+            Some(s::TypeDefinition::Object(object_type)) => schema.object_type(object_type),
+            _ => unreachable!("connections are only generated for object types"),
+        };
+        let mut selection_set = a::SelectionSet::new(vec![object_type.into()]);
+        for edge in &edges {
+            for node in fields_named(&edge.selection_set, connection::NODE) {
+                selection_set.merge(node.selection_set.clone(), vec![])?;
+            }
+        }
+        let page_field = a::Field {
+            position: field.position,
+            alias: None,
+            name: field.name.clone(),
+            arguments: field.arguments.clone(),
+            directives: vec![],
+            selection_set,
+            multiplicity: ChildMultiplicity::Many,
+        };
+
+        let mut query = build_query(
+            &child_type,
+            self.resolver.block_number(),
+            &page_field,
+            self.ctx.max_first,
+            self.ctx.max_skip,
+            &input_schema,
+        )?;
+        if matches!(
+            query.order,
+            EntityOrder::ChildAscending(_) | EntityOrder::ChildDescending(_)
+        ) {
+            return Err(vec![QueryExecutionError::NotSupported(
+                "connections can not be sorted by fields of child entities".to_string(),
+            )]);
+        }
+        let after = match field.argument_value(connection::AFTER) {
+            Some(r::Value::String(after)) => Some(after),
+            Some(r::Value::Null) | None => None,
+            Some(_) => unreachable!("after is a String"),
+        };
+        if let Some(after) = after {
+            let filter = Cursor::decode(after)
+                .and_then(|cursor| cursor.filter(&query.order, &entity_type))
+                .ok_or_else(|| {
+                    QueryExecutionError::InvalidArgumentError(
+                        field.position,
+                        connection::AFTER.to_string(),
+                        q::Value::String(after.clone()),
+                    )
+                })?;
+            query.filter = Some(filter.and_maybe(query.filter));
+        }
+        let first = query.range.first.unwrap_or(EntityRange::FIRST) as usize;
+        query.range.first = Some(first as u32 + 1);
+        query.trace = self.ctx.trace;
+        query.query_id = Some(self.ctx.query.query_id.clone());
+        query.logger = Some(self.ctx.logger.cheap_clone());
+        let order = query.order.clone();
+
+        let (values, trace) = self.resolver.store.find_query_values(query)?;
+        let children = values.into_iter().map(Node::from).collect();
+        let (mut nodes, trace) =
+            self.execute_selection_set(children, trace, &page_field.selection_set, None)?;
+        let has_next_page = nodes.len() > first;
+        nodes.truncate(first);
+
+        let cursors = nodes
+            .iter()
+            .map(|node| {
+                Cursor::new(&order, &node.entity)
+                    .map(|cursor| cursor.encode())
+                    .ok_or(QueryExecutionError::IdMissing)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let nodes: Vec<_> = nodes.into_iter().map(Rc::new).collect();
+
+        let mut conn = Node::object(&format!("{}Connection", entity), vec![]);
+        let edge_type = format!("{}Edge", entity);
+        for edge in &edges {
+            let node_fields = fields_named(&edge.selection_set, connection::NODE);
+            let edge_nodes = nodes
+                .iter()
+                .zip(&cursors)
+                .map(|(node, cursor)| {
+                    let mut edge_node = Node::object(
+                        &edge_type,
+                        vec![(connection::CURSOR, r::Value::String(cursor.clone()))],
+                    );
+                    for node_field in &node_fields {
+                        edge_node.set_children(
+                            node_field.response_key().to_string(),
+                            vec![Rc::clone(node)],
+                        );
+                    }
+                    Rc::new(edge_node)
+                })
+                .collect();
+            conn.set_children(edge.response_key().to_string(), edge_nodes);
+        }
+
+        let cursor_value = |cursor: Option<&String>| {
+            cursor
+                .map(|cursor| r::Value::String(cursor.clone()))
+                .unwrap_or(r::Value::Null)
+        };
+        let page_info = Rc::new(Node::object(
+            connection::PAGE_INFO_TYPE,
+            vec![
+                (connection::HAS_NEXT_PAGE, r::Value::Boolean(has_next_page)),
+                (
+                    connection::HAS_PREVIOUS_PAGE,
+                    r::Value::Boolean(after.is_some()),
+                ),
+                (connection::START_CURSOR, cursor_value(cursors.first())),
+                (connection::END_CURSOR, cursor_value(cursors.last())),
+            ],
+        ));
+        for page_info_field in page_infos {
+            conn.set_children(
+                page_info_field.response_key().to_string(),
+                vec![Rc::clone(&page_info)],
+            );
+        }
+
+        Ok((conn, trace))
+    }
+
     fn check_result_size(&self, parents: &[&mut Node]) -> Result<(), QueryExecutionError> {
         let size = parents.iter().map(|parent| parent.weight()).sum::<usize>();
 
@@ -8,12 +8,13 @@ use graph::components::store::{
 };
 use graph::data::graphql::TypeExt as _;
 use graph::data::query::QueryExecutionError;
+use graph::data::store::scalar::Bytes;
 use graph::data::store::{Attribute, SubscriptionFilter, Value, ValueType};
 use graph::data::value::Object;
 use graph::data::value::Value as DataValue;
 use graph::prelude::{r, s, TryFromValue, ENV_VARS};
 use graph::schema::ast::{self as sast, FilterOp};
-use graph::schema::{ApiSchema, EntityType, InputSchema, ObjectOrInterface};
+use graph::schema::{ApiSchema, EntityType, Field, InputSchema, ObjectOrInterface};
 
 use crate::execution::ast as a;
 
@@ -54,7 +55,7 @@ pub(crate) fn build_query<'a>(
 }
 
 /// Parses GraphQL arguments into a EntityRange, if present.
-fn build_range(
+pub(crate) fn build_range(
     field: &a::Field,
     max_first: u32,
     max_skip: u32,
@@ -70,7 +71,7 @@ fn build_range(
                 ));
             }
         }
-        Some(r::Value::Null) | None => 100,
+        Some(r::Value::Null) | None => max_first.min(100),
         _ => unreachable!("first is an Int with a default value"),
     };
 
@@ -305,6 +306,8 @@ fn build_filter_from_object<'a>(
                     })?;
                     let ty = &field.field_type;
                     let store_value = Value::from_query_value(value, ty)?;
+                    let store_value = bytes_from_query(store_value, field.value_type)?;
+                    let store_value = field.scale_value(store_value);
                     return build_entity_filter(field_name, op, store_value);
                 }
             })
@@ -330,25 +333,45 @@ fn build_child_filter_from_object(
         object,
         schema,
     )?));
+
+    // The attributes that connect parent and child. A field that is
+    // derived from several fields only matches a child through all of them
+    fn join_attrs(field: &Field, field_name: &str, schema: &InputSchema) -> Vec<String> {
+        if field.is_derived() {
+            field
+                .derived_from(schema)
+                .into_iter()
+                .map(|field| field.name.to_string())
+                .collect()
+        } else {
+            vec![field_name.to_string()]
+        }
+    }
+
+    fn child_filter(
+        attrs: &[String],
+        entity_type: EntityType,
+        filter: Box<EntityFilter>,
+        derived: bool,
+    ) -> EntityFilter {
+        EntityFilter::Child(Child {
+            attr: attrs[0].clone(),
+            more_attrs: attrs[1..].to_vec(),
+            entity_type,
+            filter,
+            derived,
+        })
+    }
+
     let derived = field.is_derived();
-    let attr = match field.derived_from(schema) {
-        Some(field) => field.name.to_string(),
-        None => field_name.clone(),
-    };
+    let attrs = join_attrs(field, &field_name, schema);
 
     if child_entity.is_interface() {
         Ok(EntityFilter::Or(
             child_entity
                 .object_types()
                 .into_iter()
-                .map(|entity_type| {
-                    EntityFilter::Child(Child {
-                        attr: attr.clone(),
-                        entity_type,
-                        filter: filter.clone(),
-                        derived,
-                    })
-                })
+                .map(|entity_type| child_filter(&attrs, entity_type, filter.clone(), derived))
                 .collect(),
         ))
     } else if entity.is_interface() {
@@ -361,28 +384,44 @@ fn build_child_filter_from_object(
                         .field(&field_name)
                         .ok_or(QueryExecutionError::InvalidFilterError)?;
                     let derived = field.is_derived();
+                    let attrs = join_attrs(field, &field_name, schema);
 
-                    let attr = match field.derived_from(schema) {
-                        Some(derived_from) => derived_from.name.to_string(),
-                        None => field_name.clone(),
-                    };
-
-                    Ok(EntityFilter::Child(Child {
-                        attr,
-                        entity_type: child_entity.entity_type(),
-                        filter: filter.clone(),
+                    Ok(child_filter(
+                        &attrs,
+                        child_entity.entity_type(),
+                        filter.clone(),
                         derived,
-                    }))
+                    ))
                 })
                 .collect::<Result<Vec<EntityFilter>, QueryExecutionError>>()?,
         ))
     } else {
-        Ok(EntityFilter::Child(Child {
-            attr,
-            entity_type: schema.entity_type(*type_name)?,
+        Ok(child_filter(
+            &attrs,
+            schema.entity_type(*type_name)?,
             filter,
             derived,
-        }))
+        ))
+    }
+}
+
+/// Turn strings that are compared to a `Bytes` attribute into `Bytes`,
+/// accepting hex strings in any of the forms that `Bytes::from_query_str`
+/// understands. Filters on references to entities with `Bytes` ids and
+/// lookups by id have string values since their GraphQL type is not
+/// `Bytes`. Values that are written to the store never go through here
+pub(crate) fn bytes_from_query(
+    value: Value,
+    value_type: ValueType,
+) -> Result<Value, QueryExecutionError> {
+    match (value, value_type) {
+        (Value::String(s), ValueType::Bytes) => Ok(Value::Bytes(Bytes::from_query_str(&s)?)),
+        (Value::List(values), ValueType::Bytes) => values
+            .into_iter()
+            .map(|value| bytes_from_query(value, value_type))
+            .collect::<Result<_, _>>()
+            .map(Value::List),
+        (value, _) => Ok(value),
     }
 }
 
@@ -570,6 +609,14 @@ fn build_order_by(
                         )
                     })?;
                 let derived_from = field.derived_from(schema);
+                if derived_from.len() > 1 {
+                    // Sorting joins with the child along a single field,
+                    // which is not enough for fields derived from several
+                    return Err(QueryExecutionError::OrderByNotSupportedError(
+                        entity.typename().to_owned(),
+                        parent_field_name.clone(),
+                    ));
+                }
                 let base_type = field.field_type.get_base_type();
 
                 let child_entity = schema
@@ -585,7 +632,7 @@ fn build_order_by(
                             )
                         })?;
 
-                let (join_attribute, derived) = match derived_from {
+                let (join_attribute, derived) = match derived_from.first() {
                     Some(child_field) => (child_field.name.to_string(), true),
                     None => (parent_field_name, false),
                 };
@@ -653,6 +700,11 @@ fn build_order_direction(field: &a::Field) -> Result<OrderDirection, QueryExecut
 }
 
 /// Recursively collects entities involved in a query field as `(subgraph ID, name)` tuples.
+///
+/// Subscriptions depend on entity types, not on individual entities: store
+/// events only record the type of a changed entity, so a subscription is
+/// rerun for every change to one of the collected types, even if the query
+/// only selects entities with specific ids.
 pub(crate) fn collect_entities_from_query_field(
     input_schema: &InputSchema,
     schema: &ApiSchema,
@@ -671,9 +723,9 @@ pub(crate) fn collect_entities_from_query_field(
         if let Some(field_type) = sast::get_field(&object_type, &field.name) {
             // Check if the field type corresponds to a type definition (in a valid schema,
             // this should always be the case)
-            if let Some(type_definition) = schema.get_type_definition_from_field(field_type) {
+            match schema.get_type_definition_from_field(field_type) {
                 // If the field's type definition is an object type, extract that type
-                if let s::TypeDefinition::Object(object_type) = type_definition {
+                Some(s::TypeDefinition::Object(object_type)) => {
                     // Only collect whether the field's type has an @entity directive
                     if sast::get_object_type_directive(object_type, String::from("entity"))
                         .is_some()
@@ -689,6 +741,29 @@ pub(crate) fn collect_entities_from_query_field(
                         queue.push_back((object_type.cheap_clone(), sub_field))
                     }
                 }
+                // A field of interface type can return entities of any type
+                // that implements the interface, and the selection set has
+                // the fields for each of these types. Without collecting all
+                // of them, changes to the implementing types would not cause
+                // the subscription to be rerun
+                Some(s::TypeDefinition::Interface(_)) => {
+                    for (object_type, sub_fields) in field.selection_set.fields() {
+                        if sast::get_object_type_directive(object_type, String::from("entity"))
+                            .is_some()
+                        {
+                            entities.insert((
+                                input_schema.id().cheap_clone(),
+                                object_type.name.clone(),
+                            ));
+                        }
+                        for sub_field in sub_fields {
+                            queue.push_back((object_type.cheap_clone(), sub_field))
+                        }
+                    }
+                }
+                // Scalars and enums don't depend on entities, and in a valid
+                // schema, the type definition always exists
+                _ => {}
             }
         }
     }
@@ -724,7 +799,10 @@ mod tests {
     use std::collections::BTreeSet;
     use std::{iter::FromIterator, sync::Arc};
 
-    use super::{a, build_query};
+    use graph::cheap_clone::CheapClone;
+    use graph::data::store::SubscriptionFilter;
+
+    use super::{a, build_query, bytes_from_query};
 
     const DEFAULT_OBJECT: &str = "DefaultObject";
     const ENTITY1: &str = "Entity1";
@@ -873,6 +951,91 @@ mod tests {
         field_with_vec(DEFAULT_OBJECT, args)
     }
 
+    #[test]
+    fn collect_entities_through_interfaces() {
+        use graph::components::graphql::GraphQLMetrics;
+        use graph::data::query::{Query as GraphDataQuery, QueryResults};
+        use graph::log::discard;
+        use graph::prelude::q;
+        use std::time::Duration;
+
+        use crate::execution::Query;
+
+        struct NoopMetrics;
+
+        impl GraphQLMetrics for NoopMetrics {
+            fn observe_query_execution(&self, _: Duration, _: &QueryResults) {}
+            fn observe_query_parsing(&self, _: Duration, _: &QueryResults) {}
+            fn observe_query_validation(&self, _: Duration, _: &DeploymentHash) {}
+            fn observe_query_validation_error(&self, _: Vec<&str>, _: &DeploymentHash) {}
+            fn observe_query_blocks_behind(&self, _: i32, _: &DeploymentHash) {}
+        }
+
+        const SCHEMA: &str = r#"
+            interface Pet { id: ID!, name: String!, owner: Owner! }
+            type Dog implements Pet @entity { id: ID!, name: String!, owner: Owner!, toy: Toy }
+            type Cat implements Pet @entity { id: ID!, name: String!, owner: Owner! }
+            type Owner @entity { id: ID!, pets: [Pet!]! @derivedFrom(field: "owner") }
+            type Toy @entity { id: ID! }
+            type Unused @entity { id: ID! }
+        "#;
+
+        let id = DeploymentHash::new("pets").unwrap();
+        let input_schema = InputSchema::parse_latest(SCHEMA, id.clone()).unwrap();
+        let api_schema = Arc::new(input_schema.api_schema().unwrap());
+
+        let collect = |text: &str| {
+            let document = q::parse_query(text).unwrap().into_static();
+            let query = Query::new(
+                &discard(),
+                api_schema.clone(),
+                None,
+                GraphDataQuery::new(document, None, false),
+                None,
+                u8::MAX,
+                Arc::new(NoopMetrics),
+            )
+            .unwrap();
+            let mut names = BTreeSet::new();
+            for (object_type, fields) in query.selection_set.fields() {
+                for field in fields {
+                    let filters = super::collect_entities_from_query_field(
+                        &input_schema,
+                        &api_schema,
+                        object_type.cheap_clone(),
+                        field,
+                    )
+                    .unwrap();
+                    for filter in filters {
+                        match filter {
+                            SubscriptionFilter::Entities(_, entity_type) => {
+                                names.insert(entity_type.as_str().to_string())
+                            }
+                            SubscriptionFilter::Assignment => panic!("unexpected assignment"),
+                        };
+                    }
+                }
+            }
+            names.into_iter().collect::<Vec<_>>()
+        };
+
+        // A root field of interface type depends on all implementers
+        assert_eq!(vec!["Cat", "Dog"], collect("{ pets { name } }"));
+        // Fields that only exist on some implementers are followed through
+        // the selection for that implementer
+        assert_eq!(
+            vec!["Cat", "Dog", "Toy"],
+            collect("{ pets { name ... on Dog { toy { id } } } }")
+        );
+        // A derived field of interface type depends on all implementers
+        assert_eq!(
+            vec!["Cat", "Dog", "Owner"],
+            collect("{ owners { pets { name } } }")
+        );
+        // Object fields still only depend on their own type
+        assert_eq!(vec!["Toy"], collect("{ toys { id } }"));
+    }
+
     #[test]
     fn build_query_uses_the_entity_name() {
         let attrs = if ENV_VARS.enable_select_by_specific_attributes {
@@ -1016,4 +1179,36 @@ mod tests {
             Some(EntityFilter::And(vec![EntityFilter::ChangeBlockGte(10)]))
         )
     }
+
+    #[test]
+    fn bytes_from_query_only_converts_bytes() {
+        let bytes = |s: &str| Value::Bytes(s.parse().unwrap());
+        let string = |s: &str| Value::String(s.to_string());
+
+        for s in ["0xB1", "0Xb1", "b1"] {
+            assert_eq!(
+                bytes("0xb1"),
+                bytes_from_query(string(s), ValueType::Bytes).unwrap()
+            );
+        }
+        assert_eq!(
+            bytes("0x0abc"),
+            bytes_from_query(string("0xabc"), ValueType::Bytes).unwrap()
+        );
+        assert_eq!(
+            Value::List(vec![bytes("0xb1"), bytes("0x0c")]),
+            bytes_from_query(
+                Value::List(vec![string("B1"), string("c")]),
+                ValueType::Bytes
+            )
+            .unwrap()
+        );
+        assert!(bytes_from_query(string("0xnothex"), ValueType::Bytes).is_err());
+
+        // Other types are left alone
+        assert_eq!(
+            string("0xB1"),
+            bytes_from_query(string("0xB1"), ValueType::String).unwrap()
+        );
+    }
 }
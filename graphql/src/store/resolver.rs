@@ -4,6 +4,7 @@ use std::sync::Arc;
 
 use graph::components::graphql::GraphQLMetrics as _;
 use graph::components::store::{QueryPermit, SubscriptionManager, UnitStream};
+use graph::data::graphql::ext::DirectiveFinder;
 use graph::data::graphql::load_manager::LoadManager;
 use graph::data::graphql::{object, ObjectOrInterface};
 use graph::data::query::{CacheStatus, QueryResults, Trace};
@@ -11,16 +12,16 @@ use graph::data::value::{Object, Word};
 use graph::derive::CheapClone;
 use graph::prelude::*;
 use graph::schema::{
-    ast as sast, ApiSchema, INTROSPECTION_SCHEMA_FIELD_NAME, INTROSPECTION_TYPE_FIELD_NAME,
-    META_FIELD_NAME, META_FIELD_TYPE,
+    ApiSchema, BLOCK_END_FIELD_NAME, BLOCK_START_FIELD_NAME, INTROSPECTION_SCHEMA_FIELD_NAME,
+    INTROSPECTION_TYPE_FIELD_NAME, META_FIELD_NAME, META_FIELD_TYPE,
 };
 use graph::schema::{ErrorPolicy, BLOCK_FIELD_TYPE};
 
-use crate::execution::{ast as a, Query};
+use crate::execution::{ast as a, DataVersion, Query};
 use crate::metrics::GraphQLMetrics;
 use crate::prelude::{ExecutionContext, Resolver};
 use crate::query::ext::BlockConstraint;
-use crate::store::query::collect_entities_from_query_field;
+use crate::store::query::{build_range, collect_entities_from_query_field};
 
 /// A resolver that fetches entities from a `Store`.
 #[derive(Clone, CheapClone)]
@@ -35,6 +36,7 @@ pub struct StoreResolver {
     error_policy: ErrorPolicy,
     graphql_metrics: Arc<GraphQLMetrics>,
     load_manager: Arc<LoadManager>,
+    data_version: DataVersion,
 }
 
 impl StoreResolver {
@@ -62,6 +64,8 @@ impl StoreResolver {
             error_policy: ErrorPolicy::Deny,
             graphql_metrics,
             load_manager,
+            // Queries without a block are never cached
+            data_version: DataVersion::default(),
         }
     }
 
@@ -85,6 +89,10 @@ impl StoreResolver {
         graphql_metrics.observe_query_blocks_behind(blocks_behind, &deployment);
 
         let has_non_fatal_errors = state.has_deterministic_errors(&block_ptr);
+        let data_version = DataVersion {
+            deployment_id: store.deployment_id().0,
+            reorg_count: state.reorg_count,
+        };
 
         let resolver = StoreResolver {
             logger: logger.new(o!("component" => "StoreResolver")),
@@ -96,6 +104,7 @@ impl StoreResolver {
             error_policy,
             graphql_metrics,
             load_manager,
+            data_version,
         };
         Ok(resolver)
     }
@@ -147,6 +156,17 @@ impl StoreResolver {
                     };
                     let ptr = BlockPtr::new(hash, *number);
                     block_queryable(state, ptr.number)?;
+                    let on_chain = store
+                        .is_ancestor(&state.latest_block, &ptr)
+                        .await
+                        .map_err(QueryExecutionError::from)?;
+                    if !on_chain {
+                        return Err(QueryExecutionError::BlockNotOnChain(
+                            ptr.to_string(),
+                            state.id.to_string(),
+                        )
+                        .into());
+                    }
                     ptr
                 }
                 BlockConstraint::Number(number) => {
@@ -180,6 +200,55 @@ impl StoreResolver {
         Ok(ptrs_and_sels)
     }
 
+    /// Resolve a history query `<type>History(id: ..)` by loading all
+    /// versions of the entity `entity` with that id. The versions are
+    /// returned as objects of type `version_type`
+    fn lookup_history(
+        &self,
+        field: &a::Field,
+        entity: &str,
+        version_type: &str,
+    ) -> Result<r::Value, QueryExecutionError> {
+        let entity_type = self.store.input_schema()?.entity_type(entity)?;
+        let id = match field.argument_value("id") {
+            Some(r::Value::String(id)) => entity_type.parse_id(id.as_str())?,
+            _ => unreachable!("id is a required argument of type ID"),
+        };
+        let range = build_range(field, ENV_VARS.graphql.max_first, ENV_VARS.graphql.max_skip)?;
+        let versions = self.store.find_history(
+            &entity_type,
+            &id,
+            self.block_number(),
+            range.first.unwrap_or(100),
+            range.skip,
+        )?;
+
+        let versions = versions
+            .into_iter()
+            .map(|version| {
+                let block_end = version
+                    .block_end
+                    .map(|block| r::Value::Int(block.into()))
+                    .unwrap_or(r::Value::Null);
+                let mut object = version.object.entity;
+                object.remove("__typename");
+                object.extend(vec![
+                    (
+                        Word::from("__typename"),
+                        r::Value::String(version_type.to_string()),
+                    ),
+                    (
+                        Word::from(BLOCK_START_FIELD_NAME),
+                        r::Value::Int(version.block_start.into()),
+                    ),
+                    (Word::from(BLOCK_END_FIELD_NAME), block_end),
+                ]);
+                r::Value::Object(object)
+            })
+            .collect();
+        Ok(r::Value::List(versions))
+    }
+
     /// Lookup information for the `_meta` field `field`
     async fn lookup_meta(&self, field: &a::Field) -> Result<r::Value, QueryExecutionError> {
         // These constants are closely related to the `_Meta_` type in
@@ -287,6 +356,10 @@ impl StoreResolver {
 impl Resolver for StoreResolver {
     const CACHEABLE: bool = true;
 
+    fn data_version(&self) -> DataVersion {
+        self.data_version
+    }
+
     async fn query_permit(&self) -> Result<QueryPermit, QueryExecutionError> {
         self.store.query_permit().await.map_err(Into::into)
     }
@@ -307,6 +380,9 @@ impl Resolver for StoreResolver {
         _field_definition: &s::Field,
         object_type: ObjectOrInterface<'_>,
     ) -> Result<r::Value, QueryExecutionError> {
+        if let Some(entity) = object_type.version_of() {
+            return self.lookup_history(field, entity, object_type.name());
+        }
         if let Some(child) = prefetched_objects {
             Ok(child)
         } else {
@@ -331,15 +407,17 @@ impl Resolver for StoreResolver {
         }
         if let Some(r::Value::List(children)) = prefetched_object {
             if children.len() > 1 {
-                let derived_from_field =
-                    sast::get_derived_from_field(object_type, field_definition)
-                        .expect("only derived fields can lead to multiple children here");
+                let derived_from_fields = field_definition.derived_from_fields();
+                assert!(
+                    !derived_from_fields.is_empty(),
+                    "only derived fields can lead to multiple children here"
+                );
 
                 return Err(QueryExecutionError::AmbiguousDerivedFromResult(
                     field.position,
                     field.name.clone(),
                     object_type.name().to_owned(),
-                    derived_from_field.name.clone(),
+                    derived_from_fields.join("`, `"),
                 ));
             } else {
                 Ok(children.into_iter().next().unwrap_or(r::Value::Null))
@@ -5,7 +5,7 @@ use graph::bail;
 use graph::endpoint::EndpointMetrics;
 use graph::env::ENV_VARS;
 use graph::log::logger_with_levels;
-use graph::prelude::{MetricsRegistry, BLOCK_NUMBER_MAX};
+use graph::prelude::{BlockNumber, MetricsRegistry, BLOCK_NUMBER_MAX};
 use graph::{data::graphql::load_manager::LoadManager, prelude::chrono, prometheus::Registry};
 use graph::{
     prelude::{
@@ -33,6 +33,7 @@ use graph_store_postgres::{
 };
 use lazy_static::lazy_static;
 use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::{collections::HashMap, num::ParseIntError, sync::Arc, time::Duration};
 const VERSION_LABEL_KEY: &str = "version";
 
@@ -139,6 +140,10 @@ pub enum Command {
         /// List only used (current and pending) versions
         #[clap(long, short)]
         used: bool,
+        /// Print everything that is known about each deployment: its
+        /// status, errors, graft base, and the features it uses
+        #[clap(long, short)]
+        full: bool,
     },
     /// Manage unused deployments
     ///
@@ -172,13 +177,22 @@ pub enum Command {
     Pause {
         /// The deployment (see `help info`)
         deployment: DeploymentSearch,
+        /// Why the deployment is paused; shown by `info` and the status API
+        /// until the deployment is resumed
+        #[clap(long, short)]
+        reason: Option<String>,
     },
     /// Resume a deployment
+    ///
+    /// This also clears the reason given when the deployment was paused
     Resume {
         /// The deployment (see `help info`)
         deployment: DeploymentSearch,
     },
     /// Pause and resume a deployment
+    ///
+    /// After pausing, waits for the node to finish writing the block it
+    /// is working on before resuming the deployment
     Restart {
         /// The deployment (see `help info`)
         deployment: DeploymentSearch,
@@ -262,8 +276,11 @@ pub enum Command {
     #[clap(subcommand)]
     Copy(CopyCommand),
     /// Run a GraphQL query
+    ///
+    /// Prints the JSON query result to stdout and a summary of where the
+    /// time for the query was spent to stderr
     Query {
-        /// Save the JSON query result in this file
+        /// Save the JSON query result in this file instead of printing it
         #[clap(long, short)]
         output: Option<String>,
         /// Save the query trace in this file
@@ -276,7 +293,12 @@ pub enum Command {
         target: String,
         /// The GraphQL query
         query: String,
-        /// The variables in the form `key=value`
+        /// The variables as a JSON object, e.g. `{"first": 10}`
+        #[clap(long = "vars", value_name = "JSON")]
+        json_vars: Option<String>,
+        /// The variables in the form `key=value`; their values are always
+        /// strings, and they take precedence over the variables given
+        /// with `--vars`
         vars: Vec<String>,
     },
     /// Get information about chains and manipulate them
@@ -299,6 +321,9 @@ pub enum Command {
     /// Unless `--once` is given, this setting is permanent and the subgraph
     /// will periodically be pruned to remove history as the subgraph head
     /// moves forward.
+    ///
+    /// If an earlier prune of the deployment was interrupted, pruning
+    /// resumes with the tables that had not been pruned yet.
     Prune {
         /// The deployment to prune (see `help info`)
         deployment: DeploymentSearch,
@@ -347,10 +372,40 @@ pub enum Command {
         force: bool,
     },
 
+    /// Check that the files of a deployment can still be fetched from IPFS
+    ///
+    /// Fetch the manifest of the deployment and all the files it references
+    /// from the configured IPFS nodes, report the files that can not be
+    /// fetched, and resolve the manifest the same way that deploying it
+    /// would. The deployment is not changed in any way. The deployment can
+    /// be specified as either a subgraph name, an IPFS hash `Qm..`, or the
+    /// database namespace `sgdNNN`
+    Resolve {
+        /// The deployment to check
+        deployment: DeploymentSearch,
+    },
+
+    /// Export the data of a deployment
+    #[clap(subcommand)]
+    Export(ExportCommand),
+
+    /// Import data into a new deployment
+    #[clap(subcommand)]
+    Import(ImportCommand),
+
     // Deploy a subgraph
+    //
+    // `graphman deploy <name> <deployment>` deploys; the subcommands are
+    // for checks to run before deploying
+    #[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Deploy {
-        name: DeploymentSearch,
-        deployment: DeploymentSearch,
+        #[clap(subcommand)]
+        cmd: Option<DeployCommand>,
+
+        #[clap(required = true)]
+        name: Option<DeploymentSearch>,
+        #[clap(required = true)]
+        deployment: Option<DeploymentSearch>,
 
         /// The url of the graph-node
         #[clap(long, short, default_value = "http://localhost:8020")]
@@ -405,10 +460,20 @@ pub enum UnusedCommand {
 #[derive(Clone, Debug, Subcommand)]
 pub enum ConfigCommand {
     /// Check and validate the configuration file
+    ///
+    /// After checking the syntax, try to connect to every database shard
+    /// and replica, every Ethereum and Firehose provider, and every IPFS
+    /// node, and report for each of them whether the connection worked.
+    /// Exits with an error if any of them failed
+    #[clap(alias = "validate")]
     Check {
         /// Print the configuration as JSON
         #[clap(long)]
         print: bool,
+        /// Only check the syntax of the configuration, do not connect to
+        /// anything
+        #[clap(long)]
+        skip_network: bool,
     },
     /// Print how a specific subgraph would be placed
     Place {
@@ -496,6 +561,19 @@ pub enum CopyCommand {
         /// The name of the database shard that holds the copy
         shard: String,
     },
+    /// Estimate how long copying a deployment would take
+    ///
+    /// Print for each table of the deployment `src` an estimate of how many
+    /// rows it has, how many vids copying it would go through, and how long
+    /// that would take based on the throughput of copies that have finished
+    /// in the past. Nothing is copied
+    Estimate {
+        /// How far behind `src` subgraph head to copy
+        #[clap(long, short, default_value = "200")]
+        offset: u32,
+        /// The source deployment (see `help info`)
+        src: DeploymentSearch,
+    },
     /// List all currently running copy and graft operations
     List,
     /// Print the progress of a copy operation
@@ -537,11 +615,19 @@ pub enum ChainCommand {
         #[clap(empty_values = false)]
         chain_name: String,
     },
-    /// Truncates the whole block cache for the given chain.
+    /// Truncates the block cache for the given chain.
+    ///
+    /// Without a block, the whole block cache is removed. With a block,
+    /// only blocks above that block are removed and the chain head is set
+    /// to that block, which must be in the block cache. That is refused if
+    /// any deployment has already indexed past the block. Nodes that index
+    /// the chain should be stopped while doing this
     Truncate {
         /// Chain name (must be an existing chain, see 'chain list')
         #[clap(empty_values = false)]
         chain_name: String,
+        /// Only remove blocks above this block
+        block: Option<BlockNumber>,
         /// Skips confirmation prompt
         #[clap(long, short)]
         force: bool,
@@ -557,6 +643,29 @@ pub enum ChainCommand {
         shard: String,
     },
 
+    /// Count the triggers that a subgraph would process in a block range
+    ///
+    /// Build the trigger filter for each data source in the manifest and
+    /// count how many logs, calls and blocks match it in the blocks from
+    /// `--from` to `--to`, without running any mappings. The manifest must
+    /// be on the local filesystem, for example the `subgraph.yaml` that
+    /// `graph build` produces; the files it references are resolved
+    /// relative to the manifest.
+    Scan {
+        /// Chain name (must be an existing chain, see 'chain list')
+        #[clap(empty_values = false)]
+        chain_name: String,
+        /// The first block to scan
+        #[clap(long, short)]
+        from: BlockNumber,
+        /// The last block to scan
+        #[clap(long, short)]
+        to: BlockNumber,
+        /// Path to the subgraph manifest
+        #[clap(long, short)]
+        manifest: PathBuf,
+    },
+
     /// Execute operations on call cache.
     CallCache {
         #[clap(subcommand)]
@@ -609,9 +718,19 @@ pub enum StatsCommand {
     /// Show how many distinct entities and how many versions the tables of
     /// each subgraph have. The data is based on the statistics that
     /// Postgres keeps, and only refreshed when a table is analyzed.
+    ///
+    /// With `--since-block` or `--until-block`, also count how many entity
+    /// versions in each table have a block range that overlaps that window
+    /// of blocks. Counting scans the tables and can take a long time.
     Show {
         /// The deployment (see `help info`).
         deployment: DeploymentSearch,
+        /// Count entity versions that are visible at this block or later
+        #[clap(long)]
+        since_block: Option<BlockNumber>,
+        /// Count entity versions that are visible at this block or earlier
+        #[clap(long)]
+        until_block: Option<BlockNumber>,
     },
     /// Perform a SQL ANALYZE in a Entity table
     Analyze {
@@ -661,6 +780,90 @@ pub enum StatsCommand {
     },
 }
 
+#[derive(Clone, Debug, Subcommand)]
+pub enum DeployCommand {
+    /// Compare the schemas of two deployments
+    ///
+    /// List the entity types and attributes that were added, removed, or
+    /// changed between the `old` and the `new` deployment, and whether the
+    /// data stored for `old` could be used with the schema of `new` or if
+    /// the changes require indexing `new` from scratch. The deployments can
+    /// be specified as either a subgraph name, an IPFS hash `Qm..`, or the
+    /// database namespace `sgdNNN`
+    Diff {
+        /// The deployment with the old schema
+        old: DeploymentSearch,
+        /// The deployment with the new schema
+        new: DeploymentSearch,
+    },
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ExportCommand {
+    /// Export all entities of a deployment as newline-delimited JSON
+    ///
+    /// Write one JSON object per line for every entity of the deployment,
+    /// with the entity type in the `__typename` field. By default, the
+    /// current version of each entity is exported; with `--at-block`, the
+    /// versions that were visible at that block are exported instead, which
+    /// only works for blocks for which the deployment still has history.
+    /// Tables are read in batches so that memory use stays bounded
+    Entities {
+        /// The deployment (see `help info`).
+        deployment: DeploymentSearch,
+        /// Write the entities to this file instead of stdout
+        #[clap(long, short)]
+        output: Option<String>,
+        /// Export the entities as they were at this block
+        #[clap(long)]
+        at_block: Option<BlockNumber>,
+        /// How many rows to read from the database at once
+        #[clap(long, default_value = "10000")]
+        batch_size: usize,
+    },
+    /// Export a deployment as an archive that can be imported elsewhere
+    ///
+    /// Write the schema, the metadata, and every version of every entity
+    /// of the deployment to a file that `graphman import deployment` can
+    /// load into another installation, for example to move a deployment
+    /// to a different cluster. The archive does not depend on how the
+    /// data is stored in Postgres. The deployment is read in one
+    /// transaction and can keep indexing while it is exported
+    Deployment {
+        /// The deployment (see `help info`).
+        deployment: DeploymentSearch,
+        /// Write the archive to this file instead of stdout
+        #[clap(long, short)]
+        output: Option<String>,
+        /// How many rows to read from the database at once
+        #[clap(long, default_value = "10000")]
+        batch_size: usize,
+    },
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ImportCommand {
+    /// Create a deployment from an archive
+    ///
+    /// Load an archive written by `graphman export deployment` into a new
+    /// deployment and assign it to a node once all its data has been
+    /// loaded. The deployment must not exist yet; if loading fails, the
+    /// partially created deployment is removed again
+    Deployment {
+        /// The file with the archive
+        file: String,
+        /// The name of the node that should index the deployment
+        #[clap(long, short)]
+        node: String,
+        /// The name of the database shard for the deployment
+        #[clap(long, default_value = "primary")]
+        shard: String,
+        /// How many rows to insert into the database at once
+        #[clap(long, default_value = "10000")]
+        batch_size: usize,
+    },
+}
+
 #[derive(Clone, Debug, Subcommand)]
 pub enum IndexCommand {
     /// Creates a new database index.
@@ -1101,8 +1304,9 @@ async fn main() -> anyhow::Result<()> {
             status,
             used,
             all,
+            full,
         } => {
-            let (primary, store) = if status {
+            let (primary, store) = if status || full {
                 let (store, primary) = ctx.store_and_primary();
                 (primary, Some(store))
             } else {
@@ -1111,13 +1315,18 @@ async fn main() -> anyhow::Result<()> {
 
             match deployment {
                 Some(deployment) => {
-                    commands::info::run(primary, store, deployment, current, pending, used).err();
+                    commands::info::run(primary, store, deployment, current, pending, used, full)
+                        .await
+                        .err();
                 }
                 None => {
                     if all {
                         let deployment = DeploymentSearch::All;
-                        commands::info::run(primary, store, deployment, current, pending, used)
-                            .err();
+                        commands::info::run(
+                            primary, store, deployment, current, pending, used, full,
+                        )
+                        .await
+                        .err();
                     } else {
                         bail!("Please specify a deployment or use --all to list all deployments");
                     }
@@ -1153,7 +1362,10 @@ async fn main() -> anyhow::Result<()> {
                 Place { name, network } => {
                     commands::config::place(&ctx.config.deployment, &name, &network)
                 }
-                Check { print } => commands::config::check(&ctx.config, print),
+                Check {
+                    print,
+                    skip_network,
+                } => commands::config::check(&ctx.config, print, skip_network, &ctx.ipfs_url).await,
                 Pools { nodes, shard } => commands::config::pools(&ctx.config, nodes, shard),
                 Provider { features, network } => {
                     let logger = ctx.logger.clone();
@@ -1174,17 +1386,24 @@ async fn main() -> anyhow::Result<()> {
             let sender = ctx.notification_sender();
             commands::assign::reassign(ctx.primary_pool(), &sender, &deployment, node)
         }
-        Pause { deployment } => {
+        Pause { deployment, reason } => {
             let sender = ctx.notification_sender();
-            commands::assign::pause_or_resume(ctx.primary_pool(), &sender, &deployment, true)
+            commands::assign::pause_or_resume(
+                ctx.primary_pool(),
+                &sender,
+                &deployment,
+                true,
+                reason.as_deref(),
+            )
         }
         Resume { deployment } => {
             let sender = ctx.notification_sender();
-            commands::assign::pause_or_resume(ctx.primary_pool(), &sender, &deployment, false)
+            commands::assign::pause_or_resume(ctx.primary_pool(), &sender, &deployment, false, None)
         }
         Restart { deployment, sleep } => {
             let sender = ctx.notification_sender();
-            commands::assign::restart(ctx.primary_pool(), &sender, &deployment, sleep)
+            let (store, primary) = ctx.store_and_primary();
+            commands::assign::restart(primary, store.subgraph_store(), &sender, &deployment, sleep)
         }
         Rewind {
             force,
@@ -1273,6 +1492,10 @@ async fn main() -> anyhow::Result<()> {
                     )
                     .await
                 }
+                Estimate { src, offset } => {
+                    let (store, pools) = ctx.store_and_pools();
+                    commands::copy::estimate(store, pools, src, offset).await
+                }
                 Activate { deployment, shard } => {
                     commands::copy::activate(ctx.subgraph_store(), deployment, shard)
                 }
@@ -1285,8 +1508,20 @@ async fn main() -> anyhow::Result<()> {
             trace,
             target,
             query,
+            json_vars,
             vars,
-        } => commands::query::run(ctx.graphql_runner(), target, query, vars, output, trace).await,
+        } => {
+            commands::query::run(
+                ctx.graphql_runner(),
+                target,
+                query,
+                json_vars,
+                vars,
+                output,
+                trace,
+            )
+            .await
+        }
         Chain(cmd) => {
             use ChainCommand::*;
             match cmd {
@@ -1355,11 +1590,40 @@ async fn main() -> anyhow::Result<()> {
                         }
                     }
                 }
-                Truncate { chain_name, force } => {
+                Truncate {
+                    chain_name,
+                    block: None,
+                    force,
+                } => {
                     use commands::check_blocks::truncate;
                     let chain_store = ctx.chain_store(&chain_name)?;
                     truncate(chain_store, force)
                 }
+                Truncate {
+                    chain_name,
+                    block: Some(block),
+                    force,
+                } => {
+                    use graph::components::store::BlockStore as _;
+                    let (store, primary) = ctx.store_and_primary();
+                    let chain_store = store
+                        .block_store()
+                        .chain_store(&chain_name)
+                        .ok_or_else(|| anyhow::anyhow!("unknown chain: {}", chain_name))?;
+                    commands::chain::truncate_to(primary, store, chain_store, block, force).await
+                }
+                Scan {
+                    chain_name,
+                    from,
+                    to,
+                    manifest,
+                } => {
+                    let logger = ctx.logger.clone();
+                    let registry = ctx.metrics_registry();
+                    let (_, ethereum_adapter) = ctx.chain_store_and_adapter(&chain_name).await?;
+                    commands::scan::run(&logger, registry, ethereum_adapter, manifest, from, to)
+                        .await
+                }
                 CallCache { method, chain_name } => {
                     match method {
                         CallCacheCommand::Remove {
@@ -1402,7 +1666,11 @@ async fn main() -> anyhow::Result<()> {
                     )
                     .await
                 }
-                Show { deployment } => commands::stats::show(ctx.pools(), &deployment),
+                Show {
+                    deployment,
+                    since_block,
+                    until_block,
+                } => commands::stats::show(ctx.pools(), &deployment, since_block, until_block),
                 Analyze { deployment, entity } => {
                     let (store, primary_pool) = ctx.store_and_primary();
                     let subgraph_store = store.subgraph_store();
@@ -1557,12 +1825,85 @@ async fn main() -> anyhow::Result<()> {
             .await
         }
 
+        Resolve { deployment } => {
+            let logger = ctx.logger.clone();
+            let ipfs_url = ctx.ipfs_url.clone();
+            commands::resolve::run(logger, ctx.primary_pool(), ipfs_url, deployment).await
+        }
+        Export(cmd) => {
+            use ExportCommand::*;
+            match cmd {
+                Entities {
+                    deployment,
+                    output,
+                    at_block,
+                    batch_size,
+                } => {
+                    let (store, pools) = ctx.store_and_pools();
+                    commands::export::entities(
+                        store.subgraph_store(),
+                        pools,
+                        &deployment,
+                        output,
+                        at_block,
+                        batch_size,
+                    )
+                }
+                Deployment {
+                    deployment,
+                    output,
+                    batch_size,
+                } => {
+                    let (store, primary) = ctx.store_and_primary();
+                    commands::export::deployment(
+                        store.subgraph_store(),
+                        primary,
+                        &deployment,
+                        output,
+                        batch_size,
+                    )
+                }
+            }
+        }
+        Import(cmd) => {
+            use ImportCommand::*;
+            match cmd {
+                Deployment {
+                    file,
+                    node,
+                    shard,
+                    batch_size,
+                } => {
+                    let shards: Vec<_> = ctx.config.stores.keys().cloned().collect();
+                    commands::import::deployment(
+                        ctx.subgraph_store(),
+                        file,
+                        shard,
+                        shards,
+                        node,
+                        batch_size,
+                    )
+                }
+            }
+        }
         Deploy {
+            cmd: Some(DeployCommand::Diff { old, new }),
+            ..
+        } => {
+            let (store, primary_pool) = ctx.store_and_primary();
+            commands::diff::run(primary_pool, store.subgraph_store(), old, new)
+        }
+        Deploy {
+            cmd: None,
             deployment,
             name,
             url,
             create,
         } => {
+            // clap requires both when there is no subcommand
+            let (Some(name), Some(deployment)) = (name, deployment) else {
+                bail!("graphman deploy needs a subgraph name and a deployment");
+            };
             let store = ctx.store();
             let subgraph_store = store.subgraph_store();
 
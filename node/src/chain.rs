@@ -3,7 +3,7 @@ use ethereum::{EthereumNetworks, ProviderEthRpcMetrics};
 use graph::anyhow::{bail, Error};
 use graph::blockchain::{Block as BlockchainBlock, BlockchainKind, ChainIdentifier};
 use graph::cheap_clone::CheapClone;
-use graph::endpoint::EndpointMetrics;
+use graph::endpoint::{EndpointMetrics, RateLimiter};
 use graph::firehose::{FirehoseEndpoint, FirehoseNetworks, SubgraphLimit};
 use graph::futures03::future::{join_all, try_join_all};
 use graph::futures03::TryFutureExt;
@@ -37,18 +37,19 @@ pub enum ProviderNetworkStatus {
 /// continue regardless.
 const NET_VERSION_WAIT_TIME: Duration = Duration::from_secs(30);
 
+/// Turn an address from the `--ipfs` command line argument into a URL,
+/// defaulting to `http` if it does not have a scheme
+pub fn ipfs_address(uri: &str) -> String {
+    if uri.starts_with("http://") || uri.starts_with("https://") {
+        String::from(uri)
+    } else {
+        format!("http://{}", uri)
+    }
+}
+
 pub fn create_ipfs_clients(logger: &Logger, ipfs_addresses: &Vec<String>) -> Vec<IpfsClient> {
     // Parse the IPFS URL from the `--ipfs` command line argument
-    let ipfs_addresses: Vec<_> = ipfs_addresses
-        .iter()
-        .map(|uri| {
-            if uri.starts_with("http://") || uri.starts_with("https://") {
-                String::from(uri)
-            } else {
-                format!("http://{}", uri)
-            }
-        })
-        .collect();
+    let ipfs_addresses: Vec<_> = ipfs_addresses.iter().map(|uri| ipfs_address(uri)).collect();
 
     ipfs_addresses
         .into_iter()
@@ -131,21 +132,29 @@ pub fn create_substreams_networks(
                     .entry(chain.protocol)
                     .or_insert_with(FirehoseNetworks::new);
 
+                // All connections to the provider share its rate limit
+                let rate_limiter = firehose
+                    .requests_per_second
+                    .map(|rate| Arc::new(RateLimiter::new(rate)));
+
                 for _ in 0..firehose.conn_pool_size {
                     parsed_networks.insert(
                         name.to_string(),
-                        Arc::new(FirehoseEndpoint::new(
-                            // This label needs to be the original label so that the metrics
-                            // can be deduped.
-                            &provider.label,
-                            &firehose.url,
-                            firehose.token.clone(),
-                            firehose.key.clone(),
-                            firehose.filters_enabled(),
-                            firehose.compression_enabled(),
-                            SubgraphLimit::Unlimited,
-                            endpoint_metrics.clone(),
-                        )),
+                        Arc::new(
+                            FirehoseEndpoint::new(
+                                // This label needs to be the original label so that the metrics
+                                // can be deduped.
+                                &provider.label,
+                                &firehose.url,
+                                firehose.token.clone(),
+                                firehose.key.clone(),
+                                firehose.filters_enabled(),
+                                firehose.compression_enabled(),
+                                SubgraphLimit::Unlimited,
+                                endpoint_metrics.clone(),
+                            )
+                            .with_rate_limiter(rate_limiter.clone()),
+                        ),
                     );
                 }
             }
@@ -187,22 +196,29 @@ pub fn create_firehose_networks(
                 // instance will have their own subgraph limit.
                 // eg: pool_size = 3 and sg_limit 2 will result in 3 separate instances
                 // of FirehoseEndpoint and each of those instance can be used in 2 different
-                // SubgraphInstances.
+                // SubgraphInstances. All instances share the rate limit of the provider.
+                let rate_limiter = firehose
+                    .requests_per_second
+                    .map(|rate| Arc::new(RateLimiter::new(rate)));
+
                 for _ in 0..firehose.conn_pool_size {
                     parsed_networks.insert(
                         name.to_string(),
-                        Arc::new(FirehoseEndpoint::new(
-                            // This label needs to be the original label so that the metrics
-                            // can be deduped.
-                            &provider.label,
-                            &firehose.url,
-                            firehose.token.clone(),
-                            firehose.key.clone(),
-                            firehose.filters_enabled(),
-                            firehose.compression_enabled(),
-                            firehose.limit_for(&config.node),
-                            endpoint_metrics.cheap_clone(),
-                        )),
+                        Arc::new(
+                            FirehoseEndpoint::new(
+                                // This label needs to be the original label so that the metrics
+                                // can be deduped.
+                                &provider.label,
+                                &firehose.url,
+                                firehose.token.clone(),
+                                firehose.key.clone(),
+                                firehose.filters_enabled(),
+                                firehose.compression_enabled(),
+                                firehose.limit_for(&config.node),
+                                endpoint_metrics.cheap_clone(),
+                            )
+                            .with_rate_limiter(rate_limiter.clone()),
+                        ),
                     );
                 }
             }
@@ -499,13 +515,17 @@ pub async fn create_ethereum_networks_for_chain(
 
         use crate::config::Transport::*;
 
+        let rate_limiter = web3
+            .requests_per_second
+            .map(|rate| Arc::new(RateLimiter::new(rate)));
         let transport = match web3.transport {
             Rpc => Transport::new_rpc(
                 Url::parse(&web3.url)?,
                 web3.headers.clone(),
                 endpoint_metrics.cheap_clone(),
                 &provider.label,
-            ),
+            )
+            .with_rate_limiter(rate_limiter),
             Ipc => Transport::new_ipc(&web3.url).await,
             Ws => Transport::new_ws(&web3.url).await,
         };
@@ -524,7 +544,8 @@ pub async fn create_ethereum_networks_for_chain(
                     supports_eip_1898,
                     call_only,
                 )
-                .await,
+                .await
+                .with_polling_interval(web3.polling_interval),
             ),
             web3.limit_for(&config.node),
         );
@@ -18,10 +18,11 @@ use graph::{
 use graph_chain_ethereum::{self as ethereum, NodeCapabilities};
 use graph_store_postgres::{DeploymentPlacer, Shard as ShardName, PRIMARY_SHARD};
 
-use graph::http::{HeaderMap, Uri};
+use graph::http::{HeaderMap, HeaderName, HeaderValue, Uri};
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt,
+    num::NonZeroU32,
 };
 use std::{fs::read_to_string, time::Duration};
 use url::Url;
@@ -490,7 +491,10 @@ impl ChainSection {
                         url: url.to_string(),
                         features,
                         headers: Default::default(),
+                        header_files: BTreeMap::new(),
                         rules: vec![],
+                        polling_interval: None,
+                        requests_per_second: None,
                     }),
                 };
                 let entry = chains.entry(name.to_string()).or_insert_with(|| Chain {
@@ -587,6 +591,16 @@ fn btree_map_to_http_headers(kvs: BTreeMap<String, String>) -> HeaderMap {
     headers
 }
 
+/// Read a secret like an auth token from the file at `path`. Environment
+/// variables in `path` are expanded, and whitespace around the contents of
+/// the file is removed
+fn read_secret(path: &str) -> Result<String> {
+    let path = shellexpand::env(path)?;
+    let secret =
+        read_to_string(path.as_ref()).with_context(|| format!("can not read `{}`", path))?;
+    Ok(secret.trim().to_string())
+}
+
 #[derive(Clone, Debug, Serialize, PartialEq)]
 pub struct Provider {
     pub label: String,
@@ -615,13 +629,26 @@ fn twenty() -> u16 {
 pub struct FirehoseProvider {
     pub url: String,
     pub token: Option<String>,
+    /// A file that contains the token; it is read when the configuration
+    /// is loaded and can not be combined with `token`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_file: Option<String>,
     pub key: Option<String>,
+    /// A file that contains the key; it is read when the configuration is
+    /// loaded and can not be combined with `key`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<String>,
     #[serde(default = "twenty")]
     pub conn_pool_size: u16,
     #[serde(default)]
     pub features: BTreeSet<String>,
     #[serde(default, rename = "match")]
     rules: Vec<Web3Rule>,
+    /// The maximum number of requests per second sent to this provider,
+    /// across all of its connections. When not set, requests are not
+    /// limited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_second: Option<NonZeroU32>,
 }
 
 impl FirehoseProvider {
@@ -692,8 +719,29 @@ pub struct Web3Provider {
     )]
     pub headers: HeaderMap,
 
+    /// HTTP headers whose values are read from files, mapping the header
+    /// name to the path of the file. The files are read when the
+    /// configuration is loaded, and the headers are added to `headers`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub header_files: BTreeMap<String, String>,
+
     #[serde(default, rename = "match")]
     rules: Vec<Web3Rule>,
+
+    /// How often the block ingestor polls this provider for new blocks.
+    /// When not set, the `polling_interval` of the chain is used
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_opt_duration_millis"
+    )]
+    pub polling_interval: Option<Duration>,
+
+    /// The maximum number of requests per second sent to this provider.
+    /// Only supported for the `rpc` transport. When not set, requests are
+    /// not limited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_second: Option<NonZeroU32>,
 }
 
 impl Web3Provider {
@@ -739,6 +787,27 @@ impl Provider {
                 if let Some(key) = &firehose.key {
                     firehose.key = Some(shellexpand::env(key)?.into_owned());
                 }
+                if let Some(path) = &firehose.token_file {
+                    if firehose.token.is_some() {
+                        bail!(
+                            "provider {} can not set both `token` and `token_file`",
+                            label
+                        );
+                    }
+                    let token = read_secret(path).with_context(|| {
+                        format!("failed to read the token for provider {}", label)
+                    })?;
+                    firehose.token = Some(token);
+                }
+                if let Some(path) = &firehose.key_file {
+                    if firehose.key.is_some() {
+                        bail!("provider {} can not set both `key` and `key_file`", label);
+                    }
+                    let key = read_secret(path).with_context(|| {
+                        format!("failed to read the key for provider {}", label)
+                    })?;
+                    firehose.key = Some(key);
+                }
 
                 if firehose
                     .features
@@ -774,6 +843,13 @@ impl Provider {
                 web3.url = shellexpand::env(&web3.url)?.into_owned();
 
                 let label = &self.label;
+                if web3.requests_per_second.is_some() && web3.transport != Transport::Rpc {
+                    bail!(
+                        "provider {} sets `requests_per_second`, which is only supported for the `rpc` transport",
+                        label
+                    );
+                }
+
                 Url::parse(&web3.url).map_err(|e| {
                     anyhow!(
                         "the url `{}` for provider {} is not a legal URL: {}",
@@ -782,6 +858,31 @@ impl Provider {
                         e
                     )
                 })?;
+
+                for (name, path) in &web3.header_files {
+                    let header = name.parse::<HeaderName>().map_err(|_| {
+                        anyhow!("invalid HTTP header name `{}` for provider {}", name, label)
+                    })?;
+                    if web3.headers.contains_key(&header) {
+                        bail!(
+                            "provider {} sets the header `{}` in both `headers` and `header_files`",
+                            label,
+                            name
+                        );
+                    }
+                    let value = read_secret(path).with_context(|| {
+                        format!("failed to read header `{}` for provider {}", name, label)
+                    })?;
+                    let value = value.parse::<HeaderValue>().map_err(|_| {
+                        anyhow!(
+                            "the contents of `{}` are not a valid value for header `{}` of provider {}",
+                            path,
+                            name,
+                            label
+                        )
+                    })?;
+                    web3.headers.insert(header, value);
+                }
             }
         }
 
@@ -814,6 +915,7 @@ impl<'de> Deserialize<'de> for Provider {
                 let mut transport = None;
                 let mut features = None;
                 let mut headers = None;
+                let mut polling_interval = None;
                 let mut nodes = Vec::new();
 
                 while let Some(key) = map.next_key()? {
@@ -856,6 +958,12 @@ impl<'de> Deserialize<'de> for Provider {
                             let raw_headers: BTreeMap<String, String> = map.next_value()?;
                             headers = Some(btree_map_to_http_headers(raw_headers));
                         }
+                        ProviderField::PollingInterval => {
+                            if polling_interval.is_some() {
+                                return Err(serde::de::Error::duplicate_field("polling_interval"));
+                            }
+                            polling_interval = Some(Duration::from_millis(map.next_value()?));
+                        }
                         ProviderField::Match => {
                             nodes = map.next_value()?;
                         }
@@ -869,8 +977,9 @@ impl<'de> Deserialize<'de> for Provider {
                             || transport.is_some()
                             || features.is_some()
                             || headers.is_some()
+                            || polling_interval.is_some()
                         {
-                            return Err(serde::de::Error::custom("when `details` field is provided, deprecated `url`, `transport`, `features` and `headers` cannot be specified"));
+                            return Err(serde::de::Error::custom("when `details` field is provided, deprecated `url`, `transport`, `features`, `headers` and `polling_interval` cannot be specified"));
                         }
 
                         match v {
@@ -889,7 +998,10 @@ impl<'de> Deserialize<'de> for Provider {
                         features: features
                             .ok_or_else(|| serde::de::Error::missing_field("features"))?,
                         headers: headers.unwrap_or_else(HeaderMap::new),
+                        header_files: BTreeMap::new(),
                         rules: nodes,
+                        polling_interval,
+                        requests_per_second: None,
                     }),
                 };
 
@@ -904,6 +1016,7 @@ impl<'de> Deserialize<'de> for Provider {
             "url",
             "features",
             "headers",
+            "polling_interval",
         ];
         deserializer.deserialize_struct("Provider", FIELDS, ProviderVisitor)
     }
@@ -921,6 +1034,8 @@ enum ProviderField {
     Transport,
     Features,
     Headers,
+    #[serde(rename = "polling_interval")]
+    PollingInterval,
 }
 
 #[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq)]
@@ -1167,6 +1282,14 @@ where
     Ok(Duration::from_millis(millis))
 }
 
+fn deserialize_opt_duration_millis<'de, D>(data: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let millis = Option::<u64>::deserialize(data)?;
+    Ok(millis.map(Duration::from_millis))
+}
+
 // From https://github.com/serde-rs/serde/issues/889#issuecomment-295988865
 fn string_or_vec<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where
@@ -1215,6 +1338,7 @@ mod tests {
     use std::collections::BTreeSet;
     use std::fs::read_to_string;
     use std::path::{Path, PathBuf};
+    use std::time::Duration;
 
     #[test]
     fn it_works_on_standard_config() {
@@ -1295,7 +1419,10 @@ mod tests {
                     url: "http://localhost:8545".to_owned(),
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
+                    header_files: BTreeMap::new(),
                     rules: Vec::new(),
+                    polling_interval: None,
+                    requests_per_second: None,
                 }),
             },
             actual
@@ -1321,7 +1448,10 @@ mod tests {
                     url: "http://localhost:8545".to_owned(),
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
+                    header_files: BTreeMap::new(),
                     rules: Vec::new(),
+                    polling_interval: None,
+                    requests_per_second: None,
                 }),
             },
             actual
@@ -1428,13 +1558,98 @@ mod tests {
                     url: "http://localhost:8545".to_owned(),
                     features,
                     headers,
+                    header_files: BTreeMap::new(),
                     rules: Vec::new(),
+                    polling_interval: None,
+                    requests_per_second: None,
                 }),
             },
             actual
         );
     }
 
+    #[test]
+    fn it_works_on_web3_provider_with_polling_interval_from_toml() {
+        let actual: Provider = toml::from_str(
+            r#"
+            label = "peering"
+            details = { type = "web3", url = "http://localhost:8545", features = [], polling_interval = 2000 }
+        "#,
+        )
+        .unwrap();
+        match actual.details {
+            ProviderDetails::Web3(web3) => {
+                assert_eq!(Some(Duration::from_secs(2)), web3.polling_interval)
+            }
+            details => panic!("unexpected provider details {:?}", details),
+        }
+
+        let actual: Provider = toml::from_str(
+            r#"
+            label = "peering"
+            url = "http://localhost:8545"
+            features = []
+            polling_interval = 250
+        "#,
+        )
+        .unwrap();
+        match actual.details {
+            ProviderDetails::Web3(web3) => {
+                assert_eq!(Some(Duration::from_millis(250)), web3.polling_interval)
+            }
+            details => panic!("unexpected provider details {:?}", details),
+        }
+    }
+
+    #[test]
+    fn it_works_on_provider_with_requests_per_second_from_toml() {
+        let actual: Provider = toml::from_str(
+            r#"
+            label = "peering"
+            details = { type = "web3", url = "http://localhost:8545", features = [], requests_per_second = 50 }
+        "#,
+        )
+        .unwrap();
+        match actual.details {
+            ProviderDetails::Web3(web3) => {
+                assert_eq!(NonZeroU32::new(50), web3.requests_per_second)
+            }
+            details => panic!("unexpected provider details {:?}", details),
+        }
+
+        let actual: Provider = toml::from_str(
+            r#"
+            label = "firehose"
+            details = { type = "firehose", url = "http://localhost:9000", requests_per_second = 20 }
+        "#,
+        )
+        .unwrap();
+        match actual.details {
+            ProviderDetails::Firehose(firehose) => {
+                assert_eq!(NonZeroU32::new(20), firehose.requests_per_second)
+            }
+            details => panic!("unexpected provider details {:?}", details),
+        }
+
+        let actual = toml::from_str::<Provider>(
+            r#"
+            label = "peering"
+            details = { type = "web3", url = "http://localhost:8545", features = [], requests_per_second = 0 }
+        "#,
+        );
+        assert!(actual.is_err());
+
+        let mut actual: Provider = toml::from_str(
+            r#"
+            label = "peering"
+            details = { type = "web3", transport = "ws", url = "ws://localhost:8546", features = [], requests_per_second = 50 }
+        "#,
+        )
+        .unwrap();
+        let err = actual.validate().unwrap_err();
+        assert!(err.to_string().contains("requests_per_second"));
+    }
+
     #[test]
     fn it_works_on_new_web3_provider_without_transport_from_toml() {
         let actual = toml::from_str(
@@ -1453,7 +1668,10 @@ mod tests {
                     url: "http://localhost:8545".to_owned(),
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
+                    header_files: BTreeMap::new(),
                     rules: Vec::new(),
+                    polling_interval: None,
+                    requests_per_second: None,
                 }),
             },
             actual
@@ -1491,9 +1709,12 @@ mod tests {
                 details: ProviderDetails::Firehose(FirehoseProvider {
                     url: "http://localhost:9000".to_owned(),
                     token: None,
+                    token_file: None,
                     key: None,
+                    key_file: None,
                     features: BTreeSet::new(),
                     conn_pool_size: 20,
+                    requests_per_second: None,
                     rules: vec![],
                 }),
             },
@@ -1517,9 +1738,12 @@ mod tests {
                 details: ProviderDetails::Substreams(FirehoseProvider {
                     url: "http://localhost:9000".to_owned(),
                     token: None,
+                    token_file: None,
                     key: None,
+                    key_file: None,
                     features: BTreeSet::new(),
                     conn_pool_size: 20,
+                    requests_per_second: None,
                     rules: vec![],
                 }),
             },
@@ -1543,9 +1767,12 @@ mod tests {
                 details: ProviderDetails::Substreams(FirehoseProvider {
                     url: "http://localhost:9000".to_owned(),
                     token: None,
+                    token_file: None,
                     key: Some("KEY".to_owned()),
+                    key_file: None,
                     features: BTreeSet::new(),
                     conn_pool_size: 20,
+                    requests_per_second: None,
                     rules: vec![],
                 }),
             },
@@ -1569,9 +1796,12 @@ mod tests {
                 details: ProviderDetails::Firehose(FirehoseProvider {
                     url: "http://localhost:9000".to_owned(),
                     token: None,
+                    token_file: None,
                     key: None,
+                    key_file: None,
                     features: BTreeSet::new(),
                     conn_pool_size: 20,
+                    requests_per_second: None,
                     rules: vec![],
                 }),
             },
@@ -1599,9 +1829,12 @@ mod tests {
                 details: ProviderDetails::Firehose(FirehoseProvider {
                     url: "http://localhost:9000".to_owned(),
                     token: None,
+                    token_file: None,
                     key: None,
+                    key_file: None,
                     features: BTreeSet::new(),
                     conn_pool_size: 20,
+                    requests_per_second: None,
                     rules: vec![
                         Web3Rule {
                             name: Regex::new("some_node_.*").unwrap(),
@@ -1638,9 +1871,12 @@ mod tests {
                 details: ProviderDetails::Substreams(FirehoseProvider {
                     url: "http://localhost:9000".to_owned(),
                     token: None,
+                    token_file: None,
                     key: None,
+                    key_file: None,
                     features: BTreeSet::new(),
                     conn_pool_size: 20,
+                    requests_per_second: None,
                     rules: vec![
                         Web3Rule {
                             name: Regex::new("some_node_.*").unwrap(),
@@ -1677,9 +1913,12 @@ mod tests {
                 details: ProviderDetails::Substreams(FirehoseProvider {
                     url: "http://localhost:9000".to_owned(),
                     token: None,
+                    token_file: None,
                     key: None,
+                    key_file: None,
                     features: BTreeSet::new(),
                     conn_pool_size: 20,
+                    requests_per_second: None,
                     rules: vec![
                         Web3Rule {
                             name: Regex::new("some_node_.*").unwrap(),
@@ -1716,9 +1955,12 @@ mod tests {
                 details: ProviderDetails::Substreams(FirehoseProvider {
                     url: "http://localhost:9000".to_owned(),
                     token: None,
+                    token_file: None,
                     key: None,
+                    key_file: None,
                     features: BTreeSet::new(),
                     conn_pool_size: 20,
+                    requests_per_second: None,
                     rules: vec![
                         Web3Rule {
                             name: Regex::new("some_node_.*").unwrap(),
@@ -1736,6 +1978,70 @@ mod tests {
         assert! { actual.validate().is_err()};
     }
 
+    #[test]
+    fn it_reads_provider_credentials_from_files() {
+        let dir = std::env::temp_dir();
+        let token_file = dir.join(format!("graph-node-token-{}", std::process::id()));
+        let auth_file = dir.join(format!("graph-node-auth-{}", std::process::id()));
+        std::fs::write(&token_file, "TOKEN\n").unwrap();
+        std::fs::write(&auth_file, "Bearer foo\n").unwrap();
+
+        let mut firehose = toml::from_str::<Provider>(&format!(
+            r#"
+                label = "firehose"
+                details = {{ type = "firehose", url = "http://localhost:9000", token_file = "{}" }}
+            "#,
+            token_file.display()
+        ))
+        .unwrap();
+        firehose.validate().unwrap();
+        match firehose.details {
+            ProviderDetails::Firehose(firehose) => {
+                assert_eq!(Some("TOKEN".to_string()), firehose.token)
+            }
+            _ => panic!("expected a firehose provider"),
+        }
+
+        let mut web3 = toml::from_str::<Provider>(&format!(
+            r#"
+                label = "peering"
+                details = {{ type = "web3", url = "http://localhost:8545", features = [], header_files = {{ Authorization = "{}" }} }}
+            "#,
+            auth_file.display()
+        ))
+        .unwrap();
+        web3.validate().unwrap();
+        match web3.details {
+            ProviderDetails::Web3(web3) => assert_eq!(
+                Some(&HeaderValue::from_static("Bearer foo")),
+                web3.headers.get("Authorization")
+            ),
+            _ => panic!("expected a web3 provider"),
+        }
+
+        let mut both = toml::from_str::<Provider>(&format!(
+            r#"
+                label = "firehose"
+                details = {{ type = "firehose", url = "http://localhost:9000", token = "TOKEN", token_file = "{}" }}
+            "#,
+            token_file.display()
+        ))
+        .unwrap();
+        assert!(both.validate().is_err());
+
+        let mut missing = toml::from_str::<Provider>(
+            r#"
+                label = "firehose"
+                details = { type = "firehose", url = "http://localhost:9000", token_file = "/does/not/exist" }
+            "#,
+        )
+        .unwrap();
+        assert!(missing.validate().is_err());
+
+        std::fs::remove_file(token_file).unwrap();
+        std::fs::remove_file(auth_file).unwrap();
+    }
+
     #[test]
     fn it_works_on_new_firehose_provider_from_toml_unsupported_features() {
         let actual = toml::from_str::<Provider>(
@@ -1822,7 +2128,10 @@ mod tests {
                     url: "http://localhost:8545".to_owned(),
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
+                    header_files: BTreeMap::new(),
                     rules: Vec::new(),
+                    polling_interval: None,
+                    requests_per_second: None,
                 }),
             },
             actual
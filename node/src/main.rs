@@ -55,7 +55,7 @@ use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
 git_testament!(TESTAMENT);
@@ -318,6 +318,10 @@ async fn main() {
     )
     .await;
 
+    // Hand the subgraph store back out of `launch_services` so that we can
+    // flush pending writes when shutting down
+    let (subgraph_store_sender, mut subgraph_store_receiver) = tokio::sync::oneshot::channel();
+
     let launch_services = |logger: Logger, env_vars: Arc<EnvVars>| async move {
         let subscription_manager = store_builder.subscription_manager();
         let chain_head_update_listener = store_builder.chain_head_update_listener();
@@ -414,6 +418,9 @@ async fn main() {
         network_identifiers.extend(starknet_idents);
 
         let network_store = store_builder.network_store(network_identifiers);
+        subgraph_store_sender
+            .send(network_store.subgraph_store())
+            .ok();
 
         let arweave_chains = networks_as_chains::<arweave::Chain>(
             &env_vars,
@@ -519,7 +526,8 @@ async fn main() {
             load_manager,
             graphql_metrics_registry,
         ));
-        let graphql_server = GraphQLQueryServer::new(&logger_factory, graphql_runner.clone());
+        let graphql_server = GraphQLQueryServer::new(&logger_factory, graphql_runner.clone())
+            .with_change_feed(network_store.clone(), subscription_manager.clone());
         let subscription_server =
             GraphQLSubscriptionServer::new(&logger, graphql_runner.clone(), network_store.clone());
 
@@ -725,7 +733,32 @@ async fn main() {
         }
     });
 
-    graph::futures03::future::pending::<()>().await;
+    let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("failed to listen for SIGTERM");
+    sigterm.recv().await;
+
+    // Stop accepting connections and give in-flight queries and queued
+    // writes a chance to finish before we exit
+    let timeout = ENV_VARS.shutdown_timeout;
+    info!(logger, "Received SIGTERM, shutting down";
+                  "timeout_s" => timeout.as_secs());
+    let start = Instant::now();
+    let open = graph::components::server::server::DRAIN
+        .drain(timeout)
+        .await;
+    if open > 0 {
+        warn!(logger, "Closing connections with requests still in flight";
+                      "connections" => open);
+    }
+    if let Ok(subgraph_store) = subgraph_store_receiver.try_recv() {
+        let remaining = timeout.saturating_sub(start.elapsed());
+        match tokio::time::timeout(remaining, subgraph_store.flush_writables()).await {
+            Ok(Ok(())) => info!(logger, "Finished writing queued blocks"),
+            Ok(Err(e)) => warn!(logger, "Failed to write queued blocks"; "error" => e.to_string()),
+            Err(_) => warn!(logger, "Timed out writing queued blocks"),
+        }
+    }
+    info!(logger, "Shutdown complete");
 }
 
 /// Return the hashmap of chains and also add them to `blockchain_map`.
@@ -1,7 +1,8 @@
 use graph::prelude::{anyhow::anyhow, Error, NodeId, StoreEvent};
 use graph_store_postgres::{
-    command_support::catalog, connection_pool::ConnectionPool, NotificationSender,
+    command_support::catalog, connection_pool::ConnectionPool, NotificationSender, SubgraphStore,
 };
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
@@ -72,11 +73,14 @@ pub fn reassign(
     Ok(())
 }
 
+/// Pause or resume the deployment. When pausing, `reason` is stored with
+/// the assignment; resuming clears it
 pub fn pause_or_resume(
     primary: ConnectionPool,
     sender: &NotificationSender,
     search: &DeploymentSearch,
     should_pause: bool,
+    reason: Option<&str>,
 ) -> Result<(), Error> {
     let locator = search.locate_unique(&primary)?;
 
@@ -95,7 +99,7 @@ pub fn pause_or_resume(
                     return Ok(());
                 }
                 println!("pausing {locator}");
-                conn.pause_subgraph(&site)?
+                conn.pause_subgraph(&site, reason)?
             } else {
                 println!("resuming {locator}");
                 conn.resume_subgraph(&site)?
@@ -114,16 +118,28 @@ pub fn pause_or_resume(
 
 pub fn restart(
     primary: ConnectionPool,
+    store: Arc<SubgraphStore>,
     sender: &NotificationSender,
     search: &DeploymentSearch,
     sleep: Duration,
 ) -> Result<(), Error> {
-    pause_or_resume(primary.clone(), sender, search, true)?;
+    let locator = search.locate_unique(&primary)?;
+
+    pause_or_resume(primary.clone(), sender, search, true, Some("restart"))?;
     println!(
         "Waiting {}s to make sure pausing was processed",
         sleep.as_secs()
     );
     thread::sleep(sleep);
-    pause_or_resume(primary, sender, search, false)?;
+
+    // The node might still be in the middle of writing a block; make sure
+    // that has been committed before we start the deployment again
+    println!("Waiting for pending writes to finish");
+    match store.wait_for_writes(&locator)? {
+        Some(ptr) => println!("{locator} stopped at block {}", ptr.number),
+        None => println!("{locator} stopped before processing any blocks"),
+    }
+
+    pause_or_resume(primary, sender, search, false, None)?;
     Ok(())
 }
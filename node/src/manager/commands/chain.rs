@@ -5,7 +5,9 @@ use diesel::Connection;
 use diesel::RunQueryDsl;
 use graph::blockchain::BlockPtr;
 use graph::cheap_clone::CheapClone;
+use graph::components::store::StatusStore;
 use graph::components::store::StoreError;
+use graph::data::subgraph::status;
 use graph::prelude::BlockNumber;
 use graph::prelude::ChainStore as _;
 use graph::prelude::EthereumBlock;
@@ -21,10 +23,13 @@ use graph_store_postgres::BlockStore;
 use graph_store_postgres::ChainStatus;
 use graph_store_postgres::ChainStore;
 use graph_store_postgres::Shard;
+use graph_store_postgres::Store;
 use graph_store_postgres::{
     command_support::catalog::block_store, connection_pool::ConnectionPool,
 };
 
+use crate::manager::prompt::prompt_for_confirmation;
+
 pub async fn list(primary: ConnectionPool, store: Arc<BlockStore>) -> Result<(), Error> {
     let mut chains = {
         let mut conn = primary.get()?;
@@ -154,6 +159,85 @@ pub fn remove(primary: ConnectionPool, store: Arc<BlockStore>, name: String) ->
     Ok(())
 }
 
+/// Delete all blocks above `block` from the block cache of the chain and
+/// make `block` the chain head. Refuse to do that if any deployment has
+/// already indexed past `block`
+pub async fn truncate_to(
+    primary: ConnectionPool,
+    store: Arc<Store>,
+    chain_store: Arc<ChainStore>,
+    block: BlockNumber,
+    skip_confirmation: bool,
+) -> Result<(), Error> {
+    let name = chain_store.chain.clone();
+    if block < 0 {
+        bail!("the block number must not be negative");
+    }
+
+    let sites = {
+        let mut conn =
+            graph_store_postgres::command_support::catalog::Connection::new(primary.get()?);
+        conn.find_sites_for_network(&name)?
+    };
+    let ids = sites.iter().map(|site| site.id).collect();
+    let ahead: Vec<_> = store
+        .status(status::Filter::DeploymentIds(ids))?
+        .into_iter()
+        .filter_map(|info| {
+            info.chains
+                .iter()
+                .filter(|chain| chain.network == name)
+                .find_map(|chain| chain.latest_block.as_ref().map(|b| b.number()))
+                .filter(|latest| *latest > block)
+                .map(|latest| (info.subgraph, latest))
+        })
+        .collect();
+    if !ahead.is_empty() {
+        println!(
+            "there are {} deployments that have indexed {} past block {}:",
+            ahead.len(),
+            name,
+            block
+        );
+        for (deployment, latest) in ahead {
+            println!("{:<46} | {}", deployment, latest);
+        }
+        bail!(
+            "rewind or remove these deployments before truncating {} to block {}",
+            name,
+            block
+        );
+    }
+
+    let head = chain_store.cheap_clone().chain_head_ptr().await?;
+    match head {
+        Some(head) if head.number > block => {}
+        _ => {
+            println!(
+                "the chain head for {} is not above block {}, nothing to do",
+                name, block
+            );
+            return Ok(());
+        }
+    }
+
+    let prompt = format!(
+        "This will delete all cached blocks above block {} for {} and make block {} the chain head.\nProceed?",
+        block, name, block
+    );
+    if !skip_confirmation && !prompt_for_confirmation(&prompt)? {
+        println!("Aborting.");
+        return Ok(());
+    }
+
+    let deleted = chain_store.truncate_to(block)?;
+    println!(
+        "Deleted {} blocks; the chain head for {} is now block {}",
+        deleted, name, block
+    );
+    Ok(())
+}
+
 pub fn change_block_cache_shard(
     primary_store: ConnectionPool,
     store: Arc<BlockStore>,
@@ -1,21 +1,37 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, future::Future, sync::Arc, time::Duration};
 
+use diesel::{Connection, PgConnection, RunQueryDsl};
 use graph::{
     anyhow::{bail, Context},
+    blockchain::BlockchainKind,
     components::subgraph::{Setting, Settings},
     endpoint::EndpointMetrics,
     env::EnvVars,
+    firehose::{FirehoseEndpoint, SubgraphLimit},
+    ipfs_client::IpfsClient,
     itertools::Itertools,
     prelude::{
         anyhow::{anyhow, Error},
-        MetricsRegistry, NodeId, SubgraphName,
+        tokio, web3, MetricsRegistry, NodeId, SubgraphName,
     },
-    slog::Logger,
+    slog::{o, Discard, Logger},
+    url::Url,
+    util::security::SafeDisplay,
 };
-use graph_chain_ethereum::{NodeCapabilities, ProviderEthRpcMetrics};
+use graph_chain_arweave::Block as ArweaveBlock;
+use graph_chain_cosmos::Block as CosmosBlock;
+use graph_chain_ethereum::{codec::HeaderOnlyBlock, NodeCapabilities, ProviderEthRpcMetrics};
+use graph_chain_near::HeaderOnlyBlock as NearHeaderOnlyBlock;
+use graph_chain_starknet::Block as StarknetBlock;
 use graph_store_postgres::DeploymentPlacer;
 
-use crate::{chain::create_ethereum_networks_for_chain, config::Config};
+use crate::{
+    chain::{create_ethereum_networks_for_chain, ipfs_address},
+    config::{Config, FirehoseProvider, ProviderDetails, Web3Provider},
+};
+
+/// How long `check` waits for a database, provider or IPFS node to respond
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub fn place(placer: &dyn DeploymentPlacer, name: &str, network: &str) -> Result<(), Error> {
     match placer.place(name, network).map_err(|s| anyhow!(s))? {
@@ -36,7 +52,12 @@ pub fn place(placer: &dyn DeploymentPlacer, name: &str, network: &str) -> Result
     Ok(())
 }
 
-pub fn check(config: &Config, print: bool) -> Result<(), Error> {
+pub async fn check(
+    config: &Config,
+    print: bool,
+    skip_network: bool,
+    ipfs: &[String],
+) -> Result<(), Error> {
     match config.to_json() {
         Ok(txt) => {
             if print {
@@ -61,9 +82,167 @@ pub fn check(config: &Config, print: bool) -> Result<(), Error> {
     };
 
     println!("Successfully validated configuration");
+    if skip_network {
+        return Ok(());
+    }
+
+    println!("Checking connections:");
+    let metrics = Arc::new(EndpointMetrics::mock());
+    let mut failed = 0;
+    let mut report = |target: String, res: Result<String, Error>| match res {
+        Ok(msg) => println!("  ok      {target}: {msg}"),
+        Err(e) => {
+            failed += 1;
+            println!("  FAILED  {target}: {e:#}");
+        }
+    };
+
+    for (name, shard) in &config.stores {
+        let res = probe(probe_postgres(shard.connection.clone())).await;
+        report(format!("shard {name}"), res);
+        for (replica_name, replica) in &shard.replicas {
+            let res = probe(probe_postgres(replica.connection.clone())).await;
+            report(format!("shard {name}.{replica_name}"), res);
+        }
+    }
+
+    for (network, chain) in &config.chains.chains {
+        for provider in &chain.providers {
+            let target = format!("provider {} for {network}", provider.label);
+            let res = match &provider.details {
+                ProviderDetails::Web3(web3) | ProviderDetails::Web3Call(web3) => {
+                    let fut = probe_web3(web3.clone(), provider.label.clone(), metrics.clone());
+                    probe(fut).await
+                }
+                ProviderDetails::Firehose(firehose) => {
+                    let fut = probe_firehose(
+                        chain.protocol,
+                        firehose.clone(),
+                        provider.label.clone(),
+                        metrics.clone(),
+                    );
+                    probe(fut).await
+                }
+                ProviderDetails::Substreams(_) => {
+                    println!("  skipped {target}: substreams providers are not checked");
+                    continue;
+                }
+            };
+            report(target, res);
+        }
+    }
+
+    for address in ipfs {
+        let address = ipfs_address(address);
+        let res = probe(probe_ipfs(address.clone())).await;
+        report(format!("IPFS node {}", SafeDisplay(&address)), res);
+    }
+
+    if failed > 0 {
+        bail!("{failed} connection check(s) failed");
+    }
+    println!("Successfully connected to all databases, providers and IPFS nodes");
     Ok(())
 }
 
+/// Run `probe` as its own task and limit it to `PROBE_TIMEOUT`. Creating
+/// an IPC or WebSocket transport panics when the connection can not be
+/// established; running as a separate task turns that into a failed check
+async fn probe<F>(probe: F) -> Result<String, Error>
+where
+    F: Future<Output = Result<String, Error>> + Send + 'static,
+{
+    match tokio::time::timeout(PROBE_TIMEOUT, tokio::spawn(probe)).await {
+        Ok(Ok(res)) => res,
+        Ok(Err(e)) => Err(anyhow!("check aborted: {}", e)),
+        Err(_) => Err(anyhow!(
+            "no response after {} seconds",
+            PROBE_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Connect to the database and run a trivial query. Diesel connections
+/// are blocking and can not be interrupted, we therefore connect on a
+/// separate thread and simply stop waiting for it when we time out
+async fn probe_postgres(connection: String) -> Result<String, Error> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let res = PgConnection::establish(&connection)
+            .map_err(Error::from)
+            .and_then(|mut conn| {
+                diesel::sql_query("select 1")
+                    .execute(&mut conn)
+                    .map_err(Error::from)
+            });
+        sender.send(res).ok();
+    });
+    receiver
+        .await
+        .map_err(|_| anyhow!("connection attempt aborted"))??;
+    Ok("connected".to_string())
+}
+
+async fn probe_web3(
+    web3: Web3Provider,
+    label: String,
+    metrics: Arc<EndpointMetrics>,
+) -> Result<String, Error> {
+    use crate::config::Transport::*;
+    use graph_chain_ethereum::Transport;
+
+    let transport = match web3.transport {
+        Rpc => Transport::new_rpc(Url::parse(&web3.url)?, web3.headers, metrics, &label),
+        Ipc => Transport::new_ipc(&web3.url).await,
+        Ws => Transport::new_ws(&web3.url).await,
+    };
+    let chain_id = web3::Web3::new(transport).eth().chain_id().await?;
+    Ok(format!("chain id {}", chain_id))
+}
+
+async fn probe_firehose(
+    protocol: BlockchainKind,
+    firehose: FirehoseProvider,
+    label: String,
+    metrics: Arc<EndpointMetrics>,
+) -> Result<String, Error> {
+    let endpoint = FirehoseEndpoint::new(
+        &label,
+        &firehose.url,
+        firehose.token.clone(),
+        firehose.key.clone(),
+        firehose.filters_enabled(),
+        firehose.compression_enabled(),
+        SubgraphLimit::Unlimited,
+        metrics,
+    );
+    let logger = Logger::root(Discard, o!());
+    let ptr = match protocol {
+        BlockchainKind::Arweave => endpoint.genesis_block_ptr::<ArweaveBlock>(&logger).await?,
+        BlockchainKind::Ethereum => {
+            endpoint
+                .genesis_block_ptr::<HeaderOnlyBlock>(&logger)
+                .await?
+        }
+        BlockchainKind::Near => {
+            endpoint
+                .genesis_block_ptr::<NearHeaderOnlyBlock>(&logger)
+                .await?
+        }
+        BlockchainKind::Cosmos => endpoint.genesis_block_ptr::<CosmosBlock>(&logger).await?,
+        BlockchainKind::Starknet => endpoint.genesis_block_ptr::<StarknetBlock>(&logger).await?,
+        BlockchainKind::Substreams => {
+            bail!("firehose providers are not supported for substreams chains")
+        }
+    };
+    Ok(format!("genesis block {}", ptr))
+}
+
+async fn probe_ipfs(address: String) -> Result<String, Error> {
+    IpfsClient::new(&address)?.test().await?;
+    Ok("connected".to_string())
+}
+
 pub fn pools(config: &Config, nodes: Vec<String>, shard: bool) -> Result<(), Error> {
     // Quietly replace `-` with `_` in node names to make passing in pod names
     // from k8s less annoying
@@ -2,12 +2,12 @@ use diesel::{ExpressionMethods, JoinOnDsl, OptionalExtension, QueryDsl, RunQuery
 use std::{collections::HashMap, sync::Arc, time::SystemTime};
 
 use graph::{
-    components::store::{BlockStore as _, DeploymentId},
+    components::store::{BlockStore as _, DeploymentId, DeploymentLocator},
     data::query::QueryTarget,
     prelude::{
         anyhow::{anyhow, bail, Error},
         chrono::{DateTime, Duration, SecondsFormat, Utc},
-        BlockPtr, ChainStore, DeploymentHash, NodeId, QueryStoreManager,
+        BlockNumber, BlockPtr, ChainStore, DeploymentHash, NodeId, QueryStore, QueryStoreManager,
     },
 };
 use graph_store_postgres::{
@@ -84,6 +84,28 @@ impl CopyState {
     }
 }
 
+/// Return the query store for `src` and the number of the block up to
+/// which a copy of `src` that stays `block_offset` blocks behind the
+/// subgraph head of `src` would copy
+async fn copy_target(
+    store: &Store,
+    src: &DeploymentLocator,
+    block_offset: i32,
+) -> Result<(Arc<dyn QueryStore + Send + Sync>, BlockNumber), Error> {
+    let query_store = store
+        .query_store(
+            QueryTarget::Deployment(src.hash.clone(), Default::default()),
+            true,
+        )
+        .await?;
+
+    let src_ptr = query_store.block_ptr().await?.ok_or_else(|| anyhow!("subgraph {} has not indexed any blocks yet and can not be used as the source of a copy", src))?;
+    if src_ptr.number <= block_offset {
+        bail!("subgraph {} has only indexed up to block {}, but we need at least block {} before we can copy from it", src, src_ptr.number, block_offset);
+    }
+    Ok((query_store, src_ptr.number - block_offset))
+}
+
 pub async fn create(
     store: Arc<Store>,
     primary: ConnectionPool,
@@ -105,21 +127,9 @@ pub async fn create(
 
     let subgraph_store = store.subgraph_store();
     let src = src.locate_unique(&primary)?;
-    let query_store = store
-        .query_store(
-            QueryTarget::Deployment(src.hash.clone(), Default::default()),
-            true,
-        )
-        .await?;
+    let (query_store, src_number) = copy_target(&store, &src, block_offset).await?;
     let network = query_store.network_name();
 
-    let src_ptr = query_store.block_ptr().await?.ok_or_else(|| anyhow!("subgraph {} has not indexed any blocks yet and can not be used as the source of a copy", src))?;
-    let src_number = if src_ptr.number <= block_offset {
-        bail!("subgraph {} has only indexed up to block {}, but we need at least block {} before we can copy from it", src, src_ptr.number, block_offset);
-    } else {
-        src_ptr.number - block_offset
-    };
-
     let chain_store = store
         .block_store()
         .chain_store(network)
@@ -154,6 +164,106 @@ pub async fn create(
     Ok(())
 }
 
+/// Estimate how long copying `src` would take without actually copying
+/// anything. The throughput is based on all the copies that have finished
+/// in any shard
+pub async fn estimate(
+    store: Arc<Store>,
+    pools: HashMap<Shard, ConnectionPool>,
+    src: DeploymentSearch,
+    block_offset: u32,
+) -> Result<(), Error> {
+    use copy_table_state as cts;
+
+    fn human_duration(ms: f64) -> String {
+        let duration = Duration::milliseconds(ms as i64);
+        if duration.num_minutes() < 5 {
+            format!("{}s", duration.num_seconds())
+        } else if duration.num_hours() < 5 {
+            format!("{}m", duration.num_minutes())
+        } else {
+            format!("{}h", duration.num_hours())
+        }
+    }
+
+    let primary = pools.get(&*PRIMARY_SHARD).expect("there is a primary pool");
+    let src = src.locate_unique(primary)?;
+    let (_, target_block) = copy_target(&store, &src, block_offset as i32).await?;
+    let tables = store.subgraph_store().copy_estimate(&src, target_block)?;
+
+    // The number of vids per millisecond that past copies achieved
+    let mut vids = 0;
+    let mut duration_ms = 0;
+    for pool in pools.values() {
+        let mut conn = pool.get()?;
+        let finished = cts::table
+            .filter(cts::finished_at.is_not_null())
+            .filter(cts::target_vid.ge(0))
+            .select((cts::target_vid, cts::duration_ms))
+            .load::<(i64, i64)>(&mut conn)?;
+        for (target_vid, ms) in finished {
+            vids += target_vid + 1;
+            duration_ms += ms;
+        }
+    }
+    let throughput = if vids > 0 && duration_ms > 0 {
+        Some(vids as f64 / duration_ms as f64)
+    } else {
+        None
+    };
+
+    println!("estimate for copying {} up to block {}", src, target_block);
+    match throughput {
+        Some(throughput) => println!(
+            "based on past copies with {:.0} rows/s",
+            throughput * 1000.0
+        ),
+        None => println!("no copies have finished yet, can not estimate durations"),
+    }
+    println!();
+
+    let estimate = |vids: i64| {
+        throughput
+            .map(|throughput| human_duration(vids as f64 / throughput))
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    let rows = |rows: Option<i64>| {
+        rows.map(|rows| rows.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    println!(
+        "{:^30} | {:^12} | {:^12} | {:^8}",
+        "entity type", "rows (est.)", "vids", "duration"
+    );
+    println!("{:-<71}", "-");
+    for table in &tables {
+        println!(
+            "{:<30} | {:>12} | {:>12} | {:>8}",
+            table.entity_type,
+            rows(table.rows),
+            table.vids,
+            estimate(table.vids)
+        );
+    }
+    let total_rows = tables.iter().map(|table| table.rows).sum::<Option<i64>>();
+    let total: i64 = tables.iter().map(|table| table.vids).sum();
+    println!("{:-<71}", "-");
+    println!(
+        "{:<30} | {:>12} | {:>12} | {:>8}",
+        "total",
+        rows(total_rows),
+        total,
+        estimate(total)
+    );
+    println!();
+    println!("rows are estimated from table statistics for the entire table; durations");
+    println!("depend on the number of vids up to the target block");
+
+    Ok(())
+}
+
 pub fn activate(store: Arc<SubgraphStore>, deployment: String, shard: String) -> Result<(), Error> {
     let shard = Shard::new(shard)?;
     let deployment =
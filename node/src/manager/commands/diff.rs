@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use graph::prelude::anyhow;
+use graph_store_postgres::command_support::{Column, Layout, Table};
+use graph_store_postgres::connection_pool::ConnectionPool;
+use graph_store_postgres::SubgraphStore;
+
+use crate::manager::deployment::DeploymentSearch;
+
+/// How a change between two schemas affects the data that is stored for
+/// a deployment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Impact {
+    /// Data stored for the old schema can be used as is with the new
+    /// schema
+    Compatible,
+    /// Data stored for the old schema can not be used with the new schema
+    /// or queries against the old schema will stop working; the deployment
+    /// needs to be indexed from scratch
+    Breaking,
+}
+
+impl fmt::Display for Impact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Impact::Compatible => write!(f, "compatible"),
+            Impact::Breaking => write!(f, "BREAKING"),
+        }
+    }
+}
+
+struct Change {
+    entity: String,
+    description: String,
+    impact: Impact,
+}
+
+impl Change {
+    fn new(entity: &str, description: String, impact: Impact) -> Self {
+        Self {
+            entity: entity.to_string(),
+            description,
+            impact,
+        }
+    }
+}
+
+fn diff_columns(table: &Table, old: &Column, new: &Column, changes: &mut Vec<Change>) {
+    if old.field_type == new.field_type
+        && old.column_type == new.column_type
+        && old.scale == new.scale
+    {
+        return;
+    }
+
+    let entity = table.object.as_str();
+    match new.is_assignable_from(old, &table.object) {
+        Some(reason) => changes.push(Change::new(entity, reason, Impact::Breaking)),
+        None => changes.push(Change::new(
+            entity,
+            format!(
+                "changed type of attribute {} from {} to {}",
+                new.field, old.field_type, new.field_type
+            ),
+            Impact::Compatible,
+        )),
+    }
+}
+
+fn diff(old: &Layout, new: &Layout) -> Vec<Change> {
+    use Impact::*;
+
+    let old_tables: BTreeMap<_, _> = old
+        .tables
+        .values()
+        .map(|table| (table.object.as_str(), table))
+        .collect();
+    let new_tables: BTreeMap<_, _> = new
+        .tables
+        .values()
+        .map(|table| (table.object.as_str(), table))
+        .collect();
+
+    let mut changes = Vec::new();
+    for (entity, table) in &old_tables {
+        if !new_tables.contains_key(entity) {
+            changes.push(Change::new(
+                entity,
+                format!("removed entity type with table {}", table.name),
+                Breaking,
+            ));
+        }
+    }
+
+    for (entity, new_table) in &new_tables {
+        let old_table = match old_tables.get(entity) {
+            Some(old_table) => old_table,
+            None => {
+                changes.push(Change::new(
+                    entity,
+                    "added entity type".to_string(),
+                    Compatible,
+                ));
+                continue;
+            }
+        };
+
+        if old_table.object.is_immutable() != new_table.object.is_immutable() {
+            let (from, to) = if new_table.object.is_immutable() {
+                ("mutable", "immutable")
+            } else {
+                ("immutable", "mutable")
+            };
+            changes.push(Change::new(
+                entity,
+                format!("changed entity type from {from} to {to}"),
+                Breaking,
+            ));
+        }
+
+        let old_columns: BTreeMap<&str, &Column> = old_table
+            .columns
+            .iter()
+            .map(|column| (column.name.as_str(), column))
+            .collect();
+        let new_columns: BTreeMap<&str, &Column> = new_table
+            .columns
+            .iter()
+            .map(|column| (column.name.as_str(), column))
+            .collect();
+
+        for (name, old_column) in &old_columns {
+            if !new_columns.contains_key(name) {
+                // Every attribute is indexed by default, and removing it
+                // drops the index and breaks queries that use it
+                changes.push(Change::new(
+                    entity,
+                    format!(
+                        "removed attribute {} of type {}",
+                        old_column.field, old_column.field_type
+                    ),
+                    Breaking,
+                ));
+            }
+        }
+
+        for (name, new_column) in &new_columns {
+            match old_columns.get(name) {
+                Some(old_column) => diff_columns(new_table, old_column, new_column, &mut changes),
+                None => {
+                    // Existing rows have no value for a new attribute,
+                    // which is only fine if the attribute is nullable
+                    let impact = if new_column.is_nullable() {
+                        Compatible
+                    } else {
+                        Breaking
+                    };
+                    changes.push(Change::new(
+                        entity,
+                        format!(
+                            "added attribute {} of type {}",
+                            new_column.field, new_column.field_type
+                        ),
+                        impact,
+                    ));
+                }
+            }
+        }
+    }
+    changes
+}
+
+pub fn run(
+    primary_pool: ConnectionPool,
+    store: Arc<SubgraphStore>,
+    old: DeploymentSearch,
+    new: DeploymentSearch,
+) -> Result<(), anyhow::Error> {
+    let old = old.locate_unique(&primary_pool)?;
+    let new = new.locate_unique(&primary_pool)?;
+
+    let old_layout = store.layout(&old.hash)?;
+    let new_layout = store.layout(&new.hash)?;
+
+    println!("Comparing {} with {}", old, new);
+    let changes = diff(&old_layout, &new_layout);
+    if changes.is_empty() {
+        println!("The schemas have identical storage");
+        return Ok(());
+    }
+
+    let width = changes
+        .iter()
+        .map(|change| change.entity.len())
+        .max()
+        .unwrap_or(0);
+    for change in &changes {
+        println!(
+            "{:<10}  {:<width$}  {}",
+            change.impact.to_string(),
+            change.entity,
+            change.description
+        );
+    }
+
+    let breaking = changes
+        .iter()
+        .filter(|change| change.impact == Impact::Breaking)
+        .count();
+    if breaking == 0 {
+        println!("\nAll changes are storage-compatible");
+    } else {
+        println!(
+            "\n{} of {} changes are breaking; the new deployment needs to be indexed from scratch",
+            breaking,
+            changes.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::sync::Arc;
+
+    use graph::prelude::DeploymentHash;
+    use graph::schema::InputSchema;
+    use graph_store_postgres::layout_for_tests::{make_dummy_site, Catalog, Layout, Namespace};
+
+    use super::{diff, Impact::*};
+
+    const OLD_GQL: &str = "
+        type Thing @entity { id: ID!, name: String!, size: Int, gone: String }
+        type Event @entity(immutable: true) { id: ID! }
+        type Removed @entity { id: ID! }";
+
+    const NEW_GQL: &str = "
+        type Thing @entity { id: ID!, name: String, size: Int!, nick: String, color: String! }
+        type Event @entity { id: ID! }
+        type Added @entity { id: ID! }";
+
+    fn layout(gql: &str) -> Layout {
+        let subgraph = DeploymentHash::new("subgraph").unwrap();
+        let schema = InputSchema::parse_latest(gql, subgraph.clone()).expect("valid schema");
+        let namespace = Namespace::new("sgd0815".to_owned()).unwrap();
+        let site = Arc::new(make_dummy_site(subgraph, namespace, "anet".to_string()));
+        let catalog = Catalog::for_tests(site.clone(), BTreeSet::new()).unwrap();
+        Layout::new(site, &schema, catalog).expect("valid layout")
+    }
+
+    #[test]
+    fn identical_schemas() {
+        assert!(diff(&layout(OLD_GQL), &layout(OLD_GQL)).is_empty());
+    }
+
+    #[test]
+    fn changed_schemas() {
+        let changes: Vec<_> = diff(&layout(OLD_GQL), &layout(NEW_GQL))
+            .into_iter()
+            .map(|change| (change.entity, change.impact, change.description))
+            .collect();
+
+        let exp = vec![
+            (
+                "Removed",
+                Breaking,
+                "removed entity type with table removed",
+            ),
+            ("Added", Compatible, "added entity type"),
+            (
+                "Event",
+                Breaking,
+                "changed entity type from immutable to mutable",
+            ),
+            ("Thing", Breaking, "removed attribute gone of type String"),
+            ("Thing", Breaking, "added attribute color of type String!"),
+            (
+                "Thing",
+                Compatible,
+                "changed type of attribute name from String! to String",
+            ),
+            ("Thing", Compatible, "added attribute nick of type String"),
+            (
+                "Thing",
+                Breaking,
+                "The attribute Thing.size is non-nullable, \
+                 but the corresponding attribute in the source is nullable",
+            ),
+        ];
+        let exp: Vec<_> = exp
+            .into_iter()
+            .map(|(entity, impact, description)| {
+                (entity.to_string(), impact, description.to_string())
+            })
+            .collect();
+        assert_eq!(exp, changes);
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::sync::Arc;
+
+use graph::prelude::{anyhow, BlockNumber, BLOCK_NUMBER_MAX};
+use graph_store_postgres::connection_pool::ConnectionPool;
+use graph_store_postgres::{Shard, SubgraphStore};
+
+use crate::manager::commands::stats::site_and_conn;
+use crate::manager::deployment::DeploymentSearch;
+
+/// Write all entities of the deployment `search` that are visible at
+/// `at_block`, or the current entities if `at_block` is not given, as
+/// newline-delimited JSON to `output` or stdout. Tables are read in pages
+/// of `batch_size` rows ordered by `vid` so that memory use does not
+/// depend on the size of the deployment
+pub fn entities(
+    store: Arc<SubgraphStore>,
+    pools: HashMap<Shard, ConnectionPool>,
+    search: &DeploymentSearch,
+    output: Option<String>,
+    at_block: Option<BlockNumber>,
+    batch_size: usize,
+) -> Result<(), anyhow::Error> {
+    let (site, mut conn) = site_and_conn(pools, search)?;
+    let layout = store.layout(&site.deployment)?;
+
+    let mut out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let block = at_block.unwrap_or(BLOCK_NUMBER_MAX);
+    let batch_size = batch_size.max(1) as i64;
+
+    let mut tables: Vec<_> = layout.tables.values().collect();
+    tables.sort_by_key(|table| table.object.as_str());
+    for table in tables {
+        let mut count = 0;
+        let mut after = -1;
+        loop {
+            let rows = table.export_page(&mut conn, block, after, batch_size)?;
+            let Some((last, _)) = rows.last() else {
+                break;
+            };
+            after = *last;
+            count += rows.len();
+            for (_, data) in rows {
+                writeln!(out, "{}", data)?;
+            }
+        }
+        eprintln!("{:<30} {:>10} entities", table.object.as_str(), count);
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Write the deployment `search` with all its entity versions and its
+/// metadata as an archive to `output` or stdout, so that it can be loaded
+/// into another installation with `graphman import deployment`
+pub fn deployment(
+    store: Arc<SubgraphStore>,
+    primary: ConnectionPool,
+    search: &DeploymentSearch,
+    output: Option<String>,
+    batch_size: usize,
+) -> Result<(), anyhow::Error> {
+    let locator = search.locate_unique(&primary)?;
+
+    let mut out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let rows = store.export_deployment(&locator, &mut out, batch_size)?;
+    out.flush()?;
+    eprintln!("exported {} with {} rows", locator, rows);
+    Ok(())
+}
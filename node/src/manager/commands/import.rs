@@ -0,0 +1,34 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+use graph::prelude::{
+    anyhow::{anyhow, bail, Error},
+    NodeId,
+};
+use graph_store_postgres::{Shard, SubgraphStore};
+
+/// Create a deployment in `shard` from the archive in `file` that was
+/// written by `graphman export deployment` and assign it to `node`
+pub fn deployment(
+    store: Arc<SubgraphStore>,
+    file: String,
+    shard: String,
+    shards: Vec<String>,
+    node: String,
+    batch_size: usize,
+) -> Result<(), Error> {
+    if !shards.contains(&shard) {
+        bail!(
+            "unknown shard {shard}, only shards {} are configured",
+            shards.join(", ")
+        )
+    }
+    let shard = Shard::new(shard)?;
+    let node = NodeId::new(node.clone()).map_err(|()| anyhow!("invalid node id `{}`", node))?;
+
+    let mut input = BufReader::new(File::open(&file)?);
+    let (locator, rows) = store.import_deployment(&mut input, shard, node, batch_size)?;
+    println!("imported {} with {} rows from {}", locator, rows, file);
+    Ok(())
+}
@@ -1,29 +1,193 @@
 use std::sync::Arc;
 
-use graph::{components::store::StatusStore, data::subgraph::status, prelude::anyhow};
+use graph::{
+    components::store::{StatusStore, SubgraphStore as _},
+    data::subgraph::{schema::SubgraphError, status},
+    prelude::anyhow,
+};
 use graph_store_postgres::{connection_pool::ConnectionPool, Store};
 
 use crate::manager::deployment::{Deployment, DeploymentSearch};
+use crate::manager::display::List;
 
-pub fn run(
+pub async fn run(
     pool: ConnectionPool,
     store: Option<Arc<Store>>,
     search: DeploymentSearch,
     current: bool,
     pending: bool,
     used: bool,
+    full: bool,
 ) -> Result<(), anyhow::Error> {
     let deployments = search.find(pool, current, pending, used)?;
     let ids: Vec<_> = deployments.iter().map(|d| d.locator().id).collect();
-    let statuses = match store {
+    let statuses = match &store {
         Some(store) => store.status(status::Filter::DeploymentIds(ids))?,
         None => vec![],
     };
 
     if deployments.is_empty() {
         println!("No matches");
+    } else if full {
+        let store = store.expect("the store is always available with `--full`");
+        print_full(store, deployments, statuses).await?;
     } else {
         Deployment::print_table(deployments, statuses);
     }
     Ok(())
 }
+
+fn format_error(error: &SubgraphError) -> String {
+    let block = error
+        .block_ptr
+        .as_ref()
+        .map(|ptr| ptr.number.to_string())
+        .unwrap_or("-".to_string());
+    let handler = error.handler.as_deref().unwrap_or("-");
+    format!(
+        "{} (block: {}, handler: {}, deterministic: {})",
+        error.message, block, handler, error.deterministic
+    )
+}
+
+/// Print everything we know about each deployment, one deployment at a
+/// time
+async fn print_full(
+    store: Arc<Store>,
+    deployments: Vec<Deployment>,
+    statuses: Vec<status::Info>,
+) -> Result<(), anyhow::Error> {
+    let subgraph_store = store.subgraph_store();
+    let mut first = true;
+
+    for deployment in deployments {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let locator = deployment.locator();
+        let status = statuses.iter().find(|status| status.id == locator.id);
+        let chain = status.and_then(|status| status.chains.first());
+        let block = |ptr: Option<i32>| ptr.map(|n| n.to_string()).unwrap_or("-".to_string());
+        let latest = chain.and_then(|chain| chain.latest_block.as_ref().map(|b| b.number()));
+        let head = chain.and_then(|chain| chain.chain_head_block.as_ref().map(|b| b.number()));
+        let behind = match (latest, head) {
+            (Some(latest), Some(head)) => (head - latest).to_string(),
+            _ => "-".to_string(),
+        };
+
+        let entity_tables = subgraph_store
+            .layout(&locator.hash)
+            .map(|layout| layout.tables.len().to_string())
+            .unwrap_or_else(|e| format!("unknown: {}", e));
+        let graft = match subgraph_store.graft_point(&locator.hash)? {
+            Some((base, ptr)) => format!("{} at block {}", base, ptr.number),
+            None => "-".to_string(),
+        };
+        let features = subgraph_store.subgraph_features(&locator.hash).await?;
+        let join = |list: &Vec<String>| {
+            if list.is_empty() {
+                "-".to_string()
+            } else {
+                list.join(", ")
+            }
+        };
+
+        let mut list = List::new(vec![
+            "name",
+            "status",
+            "id",
+            "namespace",
+            "shard",
+            "active",
+            "chain",
+            "node_id",
+            "paused",
+            "paused reason",
+            "synced",
+            "health",
+            "earliest block",
+            "latest block",
+            "chain head block",
+            "blocks behind",
+            "entity count",
+            "entity tables",
+            "graft base",
+            "spec version",
+            "api version",
+            "features",
+            "data sources",
+            "handlers",
+        ]);
+        list.append(vec![
+            deployment.name,
+            deployment.status,
+            deployment.deployment,
+            deployment.namespace,
+            deployment.shard,
+            deployment.active.to_string(),
+            deployment.chain,
+            deployment.node_id.unwrap_or("---".to_string()),
+            status
+                .and_then(|status| status.paused)
+                .map(|b| b.to_string())
+                .unwrap_or("---".to_string()),
+            status
+                .and_then(|status| status.paused_reason.clone())
+                .unwrap_or("-".to_string()),
+            status
+                .map(|status| status.synced.to_string())
+                .unwrap_or("-".to_string()),
+            status
+                .map(|status| status.health.as_str().to_string())
+                .unwrap_or("-".to_string()),
+            block(chain.map(|chain| chain.earliest_block_number)),
+            block(latest),
+            block(head),
+            behind,
+            status
+                .map(|status| status.entity_count.to_string())
+                .unwrap_or("-".to_string()),
+            entity_tables,
+            graft,
+            features
+                .as_ref()
+                .map(|f| f.spec_version.clone())
+                .unwrap_or("-".to_string()),
+            features
+                .as_ref()
+                .and_then(|f| f.api_version.clone())
+                .unwrap_or("-".to_string()),
+            features
+                .as_ref()
+                .map(|f| join(&f.features))
+                .unwrap_or("-".to_string()),
+            features
+                .as_ref()
+                .map(|f| join(&f.data_source_kinds))
+                .unwrap_or("-".to_string()),
+            features
+                .as_ref()
+                .map(|f| join(&f.handler_kinds))
+                .unwrap_or("-".to_string()),
+        ]);
+        list.render();
+
+        if let Some(status) = status {
+            match &status.fatal_error {
+                Some(error) => println!("\nFatal error:\n  {}", format_error(error)),
+                None => println!("\nFatal error: none"),
+            }
+            if status.non_fatal_errors.is_empty() {
+                println!("Non-fatal errors: none");
+            } else {
+                println!("Non-fatal errors:");
+                for error in &status.non_fatal_errors {
+                    println!("  {}", format_error(error));
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -6,15 +6,20 @@ pub mod copy;
 pub mod create;
 pub mod database;
 pub mod deploy;
+pub mod diff;
 pub mod drop;
+pub mod export;
+pub mod import;
 pub mod index;
 pub mod info;
 pub mod listen;
 pub mod prune;
 pub mod query;
 pub mod remove;
+pub mod resolve;
 pub mod rewind;
 pub mod run;
+pub mod scan;
 pub mod stats;
 pub mod txn_speed;
 pub mod unused_deployments;
@@ -1,12 +1,12 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     io::Write,
     sync::Arc,
     time::{Duration, Instant},
 };
 
 use graph::{
-    components::store::{PrunePhase, PruneRequest},
+    components::store::{PrunePhase, PruneRequest, PruningStrategy},
     env::ENV_VARS,
 };
 use graph::{
@@ -28,7 +28,17 @@ struct Progress {
     switch_time: Duration,
     table_start: Instant,
     table_rows: usize,
+    table_strategy: PruningStrategy,
     initial_analyze: bool,
+    /// The number of entity versions in each table before pruning, as
+    /// estimated by the initial analysis
+    versions: HashMap<String, i64>,
+    prune_start: Instant,
+    tables_total: usize,
+    tables_done: usize,
+    versions_total: i64,
+    versions_done: i64,
+    rows_removed: i64,
 }
 
 impl Progress {
@@ -40,14 +50,47 @@ impl Progress {
             switch_time: Duration::from_secs(0),
             table_start: Instant::now(),
             table_rows: 0,
+            table_strategy: PruningStrategy::Rebuild,
             initial_analyze: true,
+            versions: HashMap::new(),
+            prune_start: Instant::now(),
+            tables_total: 0,
+            tables_done: 0,
+            versions_total: 0,
+            versions_done: 0,
+            rows_removed: 0,
         }
     }
+
+    /// How many tables are done, and an estimate of how long pruning the
+    /// remaining tables will take, assuming that the time needed for a
+    /// table is proportional to the number of its entity versions
+    fn progress(&self) -> String {
+        let remaining = if self.versions_done > 0 {
+            let elapsed = self.prune_start.elapsed().as_secs_f64();
+            let remaining = (self.versions_total - self.versions_done).max(0);
+            elapsed * remaining as f64 / self.versions_done as f64
+        } else {
+            0.0
+        };
+        format!(
+            "{}/{} tables, ~{}s left",
+            self.tables_done,
+            self.tables_total,
+            remaining.round() as u64
+        )
+    }
 }
 
 fn print_copy_header() {
-    println!("{:^30} | {:^10} | {:^11}", "table", "versions", "time");
-    println!("{:-^30}-+-{:-^10}-+-{:-^11}", "", "", "");
+    println!(
+        "{:^30} | {:^10} | {:^10} | {:^11} | {:^20}",
+        "table", "versions", "removed", "time", "progress"
+    );
+    println!(
+        "{:-^30}-+-{:-^10}-+-{:-^10}-+-{:-^11}-+-{:-^20}",
+        "", "", "", "", ""
+    );
     std::io::stdout().flush().ok();
 }
 
@@ -65,14 +108,31 @@ fn print_batch(
         (false, PrunePhase::Delete) => "(delete)",
     };
     print!(
-        "\r{:<30} | {:>10} | {:>9}s {phase}",
+        "\r{:<30} | {:>10} | {:>10} | {:>9}s {phase}",
         abbreviate_table_name(table, 30),
         total_rows,
+        "",
         elapsed.as_secs()
     );
     std::io::stdout().flush().ok();
 }
 
+fn print_table_done(
+    table: &str,
+    total_rows: usize,
+    removed: i64,
+    elapsed: Duration,
+    progress: &str,
+) {
+    println!(
+        "\r{:<30} | {:>10} | {:>10} | {:>9}s | {progress}",
+        abbreviate_table_name(table, 30),
+        total_rows,
+        removed,
+        elapsed.as_secs()
+    );
+}
+
 impl PruneReporter for Progress {
     fn start(&mut self, req: &PruneRequest) {
         println!("Prune to {} historical blocks", req.history_blocks);
@@ -96,6 +156,12 @@ impl PruneReporter for Progress {
         stats: &[graph::components::store::VersionStats],
         analyzed: &[&str],
     ) {
+        if self.initial_analyze {
+            self.versions = stats
+                .iter()
+                .map(|stat| (stat.tablename.clone(), stat.versions))
+                .collect();
+        }
         let stats: Vec<_> = stats
             .iter()
             .filter(|stat| self.initial_analyze || analyzed.contains(&stat.tablename.as_str()))
@@ -110,14 +176,31 @@ impl PruneReporter for Progress {
         show_stats(stats.as_slice(), HashSet::new()).ok();
         println!();
 
-        if self.initial_analyze {
-            // After analyzing, we start the actual work
-            println!("Pruning tables");
-            print_copy_header();
-        }
         self.initial_analyze = false;
     }
 
+    fn start_tables(&mut self, tables: &[&str], resumed: &[&str]) {
+        self.tables_total = tables.len();
+        self.tables_done = resumed.len();
+        self.versions_total = tables
+            .iter()
+            .filter(|table| !resumed.contains(table))
+            .map(|table| self.versions.get(*table).copied().unwrap_or(0))
+            .sum();
+        self.prune_start = Instant::now();
+
+        if !resumed.is_empty() {
+            println!(
+                "Resuming an interrupted prune; {} of {} tables were already pruned",
+                resumed.len(),
+                tables.len()
+            );
+        }
+        // After analyzing, we start the actual work
+        println!("Pruning tables");
+        print_copy_header();
+    }
+
     fn start_table(&mut self, _table: &str) {
         self.table_start = Instant::now();
         self.table_rows = 0
@@ -125,6 +208,7 @@ impl PruneReporter for Progress {
 
     fn prune_batch(&mut self, table: &str, rows: usize, phase: PrunePhase, finished: bool) {
         self.table_rows += rows;
+        self.table_strategy = phase.strategy();
         print_batch(
             table,
             self.table_rows,
@@ -143,11 +227,28 @@ impl PruneReporter for Progress {
         self.switch_time += self.switch_start.elapsed();
     }
 
-    fn finish_table(&mut self, _table: &str) {
-        println!();
+    fn finish_table(&mut self, table: &str) {
+        let versions = self.versions.get(table).copied().unwrap_or(0);
+        let removed = match self.table_strategy {
+            // We only know how many rows we copied; the number of removed
+            // rows is an estimate based on the table's statistics
+            PruningStrategy::Rebuild => (versions - self.table_rows as i64).max(0),
+            PruningStrategy::Delete => self.table_rows as i64,
+        };
+        self.rows_removed += removed;
+        self.tables_done += 1;
+        self.versions_done += versions;
+        print_table_done(
+            table,
+            self.table_rows,
+            removed,
+            self.table_start.elapsed(),
+            &self.progress(),
+        );
     }
 
     fn finish(&mut self) {
+        println!("Removed about {} entity versions", self.rows_removed);
         println!(
             "Finished pruning in {}s. Writing was blocked for {}s",
             self.start.elapsed().as_secs(),
@@ -186,11 +287,6 @@ pub async fn run(
         return Err(anyhow!("deployment {deployment} has only indexed up to block {latest} and we can't preserve {history} blocks of history"));
     }
 
-    println!("prune {deployment}");
-    println!("    latest: {latest}");
-    println!("     final: {}", latest - ENV_VARS.reorg_threshold);
-    println!("  earliest: {}\n", latest - history);
-
     let mut req = PruneRequest::new(
         &deployment,
         history,
@@ -205,6 +301,25 @@ pub async fn run(
         req.delete_threshold = delete_threshold;
     }
 
+    // If an earlier prune was interrupted, prune to the same block again
+    // so that tables it already finished do not need to be pruned again
+    let resume = store
+        .subgraph_store()
+        .prune_checkpoint_block(&deployment)?
+        .filter(|block| *block >= status.earliest_block_number && *block < req.final_block);
+    if let Some(block) = resume {
+        req.earliest_block = block;
+    }
+
+    println!("prune {deployment}");
+    println!("    latest: {latest}");
+    println!("     final: {}", req.final_block);
+    println!("  earliest: {}", req.earliest_block);
+    if resume.is_some() {
+        println!("            (resuming an interrupted prune)");
+    }
+    println!();
+
     let reporter = Box::new(Progress::new());
 
     store
@@ -1,7 +1,6 @@
 use std::fs::File;
 use std::io::Write;
-use std::iter::FromIterator;
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 
 use graph::data::query::Trace;
 use graph::log::escape_control_chars;
@@ -22,11 +21,12 @@ pub async fn run(
     runner: Arc<GraphQlRunner<Store, PanicSubscriptionManager>>,
     target: String,
     query: String,
+    json_vars: Option<String>,
     vars: Vec<String>,
     output: Option<String>,
     trace: Option<String>,
 ) -> Result<(), anyhow::Error> {
-    let target = if target.starts_with("Qm") {
+    let target = if target.starts_with("Qm") || target.starts_with("bafy") {
         let id =
             DeploymentHash::new(target).map_err(|id| anyhow!("illegal deployment id `{}`", id))?;
         QueryTarget::Deployment(id, Default::default())
@@ -52,24 +52,28 @@ pub async fn run(
             }
         })
         .collect::<Result<_, _>>()?;
-    let query = Query::new(
-        document,
-        Some(QueryVariables::new(HashMap::from_iter(vars))),
-        true,
-    );
+    let mut variables = match json_vars {
+        Some(json) => serde_json::from_str::<QueryVariables>(&json)
+            .map_err(|e| anyhow!("invalid variables `{}`: {}", json, e))?,
+        None => QueryVariables::default(),
+    };
+    variables.extend(vars);
+    let query = Query::new(document, Some(variables), true);
 
     let res = runner.run_query(query, target).await;
     if let Some(err) = res.errors().first().cloned() {
         return Err(err.into());
     }
 
-    if let Some(output) = output {
-        let mut f = File::create(output)?;
-
-        // Escape control characters in the query output, as a precaution against injecting control
-        // characters in a terminal.
-        let json = escape_control_chars(serde_json::to_string(&res)?);
-        writeln!(f, "{}", json)?;
+    // Escape control characters in the query output, as a precaution against injecting control
+    // characters in a terminal.
+    let json = escape_control_chars(serde_json::to_string(&res)?);
+    match output {
+        Some(output) => {
+            let mut f = File::create(output)?;
+            writeln!(f, "{}", json)?;
+        }
+        None => println!("{}", json),
     }
 
     // The format of this file is pretty awful, but good enough to fish out
@@ -100,7 +104,7 @@ fn print_brief_trace(name: &str, trace: &Trace, indent: usize) -> Result<(), any
             let qt = trace.query_total();
             let pt = elapsed - qt.elapsed;
 
-            println!(
+            eprintln!(
                 "{space:indent$}{name:rest$} {setup:7}ms {elapsed:7}ms",
                 space = " ",
                 indent = indent,
@@ -112,9 +116,9 @@ fn print_brief_trace(name: &str, trace: &Trace, indent: usize) -> Result<(), any
             for twc in children {
                 print_brief_trace(name, &twc.trace, indent + 2)?;
             }
-            println!("\nquery:      {:7}ms", qt.elapsed.as_millis());
-            println!("other:      {:7}ms", pt.as_millis());
-            println!("total:      {:7}ms", elapsed.as_millis())
+            eprintln!("\nquery:      {:7}ms", qt.elapsed.as_millis());
+            eprintln!("other:      {:7}ms", pt.as_millis());
+            eprintln!("total:      {:7}ms", elapsed.as_millis())
         }
         Block { children, .. } => {
             for (name, trace) in children {
@@ -128,7 +132,7 @@ fn print_brief_trace(name: &str, trace: &Trace, indent: usize) -> Result<(), any
             children,
             ..
         } => {
-            println!(
+            eprintln!(
                 "{space:indent$}{name:rest$} {elapsed:7}ms [{count:7} entities]",
                 space = " ",
                 indent = indent,
@@ -0,0 +1,147 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use graph::anyhow::{anyhow, bail};
+use graph::blockchain::{Blockchain, BlockchainKind};
+use graph::components::link_resolver::LinkResolver;
+use graph::env::{EnvVars, ENV_VARS};
+use graph::prelude::{
+    anyhow, serde_yaml, DeploymentHash, IpfsResolver, Link, Logger, SubgraphManifest,
+};
+use graph_store_postgres::connection_pool::ConnectionPool;
+
+use crate::chain::create_ipfs_clients;
+use crate::manager::deployment::DeploymentSearch;
+
+/// Collect all IPLD links like `file: { /: /ipfs/Qm.. }` that appear
+/// anywhere in the manifest
+fn collect_links(value: &serde_yaml::Value, links: &mut BTreeSet<String>) {
+    match value {
+        serde_yaml::Value::Mapping(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    (Some("/"), serde_yaml::Value::String(link)) => {
+                        links.insert(link.clone());
+                    }
+                    _ => collect_links(value, links),
+                }
+            }
+        }
+        serde_yaml::Value::Sequence(seq) => {
+            seq.iter().for_each(|value| collect_links(value, links))
+        }
+        _ => {}
+    }
+}
+
+async fn resolve<C: Blockchain>(
+    logger: &Logger,
+    resolver: &Arc<dyn LinkResolver>,
+    hash: DeploymentHash,
+    raw: serde_yaml::Mapping,
+) -> Result<(), anyhow::Error> {
+    SubgraphManifest::<C>::resolve_from_raw(
+        hash,
+        raw,
+        resolver,
+        logger,
+        ENV_VARS.max_spec_version.clone(),
+    )
+    .await
+    .map(|_| ())
+    .map_err(|e| anyhow!("{}", e))
+}
+
+/// Fetch the manifest of a deployment and all the files it references from
+/// IPFS and resolve the manifest the same way that deploying it would,
+/// reporting the files that can not be fetched anymore. Nothing about the
+/// deployment is changed
+pub async fn run(
+    logger: Logger,
+    primary: ConnectionPool,
+    ipfs_url: Vec<String>,
+    search: DeploymentSearch,
+) -> Result<(), anyhow::Error> {
+    let locator = search.locate_unique(&primary)?;
+    let hash = locator.hash;
+
+    let env_vars = Arc::new(EnvVars::from_env()?);
+    let clients = create_ipfs_clients(&logger, &ipfs_url);
+    let resolver: Arc<dyn LinkResolver> = Arc::new(IpfsResolver::new(clients, env_vars));
+
+    let manifest = hash.to_ipfs_link();
+    let raw: serde_yaml::Mapping = match resolver.cat(&logger, &manifest).await {
+        Ok(bytes) => serde_yaml::from_slice(&bytes)?,
+        Err(e) => bail!(
+            "the manifest {} of deployment {} can not be fetched: {}",
+            manifest.link,
+            hash,
+            e
+        ),
+    };
+
+    let mut links = BTreeSet::new();
+    collect_links(&serde_yaml::Value::Mapping(raw.clone()), &mut links);
+
+    println!("{:<60} | {}", "file", "status");
+    println!("{:-<60}-+-{:-<20}", "", "");
+    println!("{:<60} | ok", manifest.link);
+    let mut failed = 0;
+    for link in &links {
+        let link = Link::from(link);
+        match resolver.cat(&logger, &link).await {
+            Ok(_) => println!("{:<60} | ok", link.link),
+            Err(e) => {
+                failed += 1;
+                println!("{:<60} | FAILED: {}", link.link, e);
+            }
+        }
+    }
+    println!();
+
+    if failed > 0 {
+        bail!(
+            "{} of the {} files of deployment {} can not be fetched",
+            failed,
+            links.len() + 1,
+            hash
+        );
+    }
+
+    let kind = BlockchainKind::from_manifest(&raw)?;
+    let res = match kind {
+        BlockchainKind::Arweave => {
+            resolve::<graph_chain_arweave::Chain>(&logger, &resolver, hash.clone(), raw).await
+        }
+        BlockchainKind::Ethereum => {
+            resolve::<graph_chain_ethereum::Chain>(&logger, &resolver, hash.clone(), raw).await
+        }
+        BlockchainKind::Near => {
+            resolve::<graph_chain_near::Chain>(&logger, &resolver, hash.clone(), raw).await
+        }
+        BlockchainKind::Cosmos => {
+            resolve::<graph_chain_cosmos::Chain>(&logger, &resolver, hash.clone(), raw).await
+        }
+        BlockchainKind::Substreams => {
+            resolve::<graph_chain_substreams::Chain>(&logger, &resolver, hash.clone(), raw).await
+        }
+        BlockchainKind::Starknet => {
+            resolve::<graph_chain_starknet::Chain>(&logger, &resolver, hash.clone(), raw).await
+        }
+    };
+    match res {
+        Ok(()) => {
+            println!(
+                "all {} files of deployment {} can be fetched and the manifest resolves",
+                links.len() + 1,
+                hash
+            );
+            Ok(())
+        }
+        Err(e) => bail!(
+            "all files of deployment {} can be fetched but the manifest does not resolve: {}",
+            hash,
+            e
+        ),
+    }
+}
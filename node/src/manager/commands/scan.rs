@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use graph::anyhow::{anyhow, bail, Context};
+use graph::blockchain::TriggerFilter as _;
+use graph::components::link_resolver::{JsonValueStream, LinkResolver};
+use graph::components::metrics::MetricsRegistry;
+use graph::env::ENV_VARS;
+use graph::prelude::{
+    anyhow, async_trait, serde_yaml, BlockNumber, DeploymentHash, Link, Logger, SubgraphManifest,
+};
+use graph_chain_ethereum::trigger::EthereumTrigger;
+use graph_chain_ethereum::{
+    triggers_in_block_range, Chain, EthereumAdapter, SubgraphEthRpcMetrics, TriggerFilter,
+};
+
+/// Resolves the files that a manifest references by reading them from the
+/// local filesystem, relative to the directory that contains the manifest
+#[derive(Clone, Debug)]
+struct FileResolver {
+    base: PathBuf,
+}
+
+impl FileResolver {
+    fn path(&self, link: &Link) -> PathBuf {
+        self.base.join(&link.link)
+    }
+}
+
+#[async_trait]
+impl LinkResolver for FileResolver {
+    fn with_timeout(&self, _timeout: Duration) -> Box<dyn LinkResolver> {
+        Box::new(self.clone())
+    }
+
+    fn with_retries(&self) -> Box<dyn LinkResolver> {
+        Box::new(self.clone())
+    }
+
+    async fn cat(&self, _logger: &Logger, link: &Link) -> Result<Vec<u8>, anyhow::Error> {
+        let path = self.path(link);
+        std::fs::read(&path).with_context(|| format!("failed to read {}", path.display()))
+    }
+
+    async fn get_block(&self, logger: &Logger, link: &Link) -> Result<Vec<u8>, anyhow::Error> {
+        self.cat(logger, link).await
+    }
+
+    async fn json_stream(
+        &self,
+        _logger: &Logger,
+        link: &Link,
+    ) -> Result<JsonValueStream, anyhow::Error> {
+        bail!("can not stream JSON from {}", self.path(link).display())
+    }
+}
+
+/// Manifests produced by `graph build` refer to files with plain paths
+/// like `file: ./schema.graphql`, while deployed manifests use IPLD links
+/// like `file: { /: /ipfs/Qm.. }`. Turn the former into the latter so the
+/// manifest can be parsed
+fn paths_to_links(value: &mut serde_yaml::Value) {
+    match value {
+        serde_yaml::Value::Mapping(map) => {
+            for (key, value) in map.iter_mut() {
+                if key.as_str() == Some("file") {
+                    if let serde_yaml::Value::String(path) = value {
+                        let mut link = serde_yaml::Mapping::new();
+                        link.insert("/".into(), path.clone().into());
+                        *value = serde_yaml::Value::Mapping(link);
+                        continue;
+                    }
+                }
+                paths_to_links(value);
+            }
+        }
+        serde_yaml::Value::Sequence(seq) => seq.iter_mut().for_each(paths_to_links),
+        _ => {}
+    }
+}
+
+async fn load_manifest(
+    logger: &Logger,
+    path: &Path,
+) -> Result<SubgraphManifest<Chain>, anyhow::Error> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read manifest {}", path.display()))?;
+    let mut raw: serde_yaml::Value = serde_yaml::from_str(&raw)?;
+    paths_to_links(&mut raw);
+    let raw = match raw {
+        serde_yaml::Value::Mapping(raw) => raw,
+        _ => bail!("manifest {} is not a YAML mapping", path.display()),
+    };
+
+    let base = path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let resolver: Arc<dyn LinkResolver> = Arc::new(FileResolver { base });
+    let id = DeploymentHash::new("scan").unwrap();
+    SubgraphManifest::resolve_from_raw(
+        id,
+        raw,
+        &resolver,
+        logger,
+        ENV_VARS.max_spec_version.clone(),
+    )
+    .await
+    .map_err(|e| anyhow!("failed to resolve manifest {}: {}", path.display(), e))
+}
+
+#[derive(Default)]
+struct Counts {
+    logs: usize,
+    calls: usize,
+    blocks: usize,
+}
+
+impl Counts {
+    fn add(&mut self, triggers: &[EthereumTrigger]) {
+        let mut blocks = HashSet::new();
+        for trigger in triggers {
+            match trigger {
+                EthereumTrigger::Log(_) => self.logs += 1,
+                EthereumTrigger::Call(_) => self.calls += 1,
+                EthereumTrigger::Block(ptr, _) => {
+                    blocks.insert(ptr.number);
+                }
+            }
+        }
+        self.blocks += blocks.len();
+    }
+}
+
+/// Count how many logs, calls and blocks the data sources in the manifest
+/// at `manifest` match in the blocks from `from` to `to`, without running
+/// any mappings. Each data source is scanned on its own so that the
+/// counts can be attributed to it, which means that blocks are scanned
+/// once for each data source that covers them
+pub async fn run(
+    logger: &Logger,
+    registry: Arc<MetricsRegistry>,
+    adapter: Arc<EthereumAdapter>,
+    manifest: PathBuf,
+    from: BlockNumber,
+    to: BlockNumber,
+) -> Result<(), anyhow::Error> {
+    if from > to {
+        bail!("--from ({}) must not be after --to ({})", from, to);
+    }
+
+    let manifest = load_manifest(logger, &manifest).await?;
+    let api_version = manifest.unified_mapping_api_version()?;
+    let metrics = Arc::new(SubgraphEthRpcMetrics::new(registry, "graphman_scan"));
+    let step = graph_chain_ethereum::ENV_VARS.max_block_range_size.max(1);
+
+    println!(
+        "{:^30} | {:^10} | {:^10} | {:^10}",
+        "data source", "logs", "calls", "blocks"
+    );
+    println!("{:-^30}-+-{:-^10}-+-{:-^10}-+-{:-^10}", "", "", "", "");
+
+    let mut total = Counts::default();
+    for ds in manifest
+        .data_sources
+        .iter()
+        .filter_map(|ds| ds.as_onchain())
+    {
+        let start = from.max(ds.start_block);
+        let end = ds.end_block.map_or(to, |end_block| to.min(end_block));
+        let filter = TriggerFilter::from_data_sources(std::iter::once(ds));
+
+        let mut counts = Counts::default();
+        let mut lo = start;
+        while lo <= end {
+            let hi = (lo + step - 1).min(end);
+            let triggers = triggers_in_block_range(
+                &adapter,
+                logger,
+                metrics.clone(),
+                lo,
+                hi,
+                &filter,
+                &api_version,
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to scan blocks {} to {} for data source {}",
+                    lo, hi, ds.name
+                )
+            })?;
+            counts.add(&triggers);
+            lo = hi + 1;
+        }
+
+        println!(
+            "{:<30} | {:>10} | {:>10} | {:>10}",
+            ds.name, counts.logs, counts.calls, counts.blocks
+        );
+        total.logs += counts.logs;
+        total.calls += counts.calls;
+        total.blocks += counts.blocks;
+    }
+
+    println!("{:-^30}-+-{:-^10}-+-{:-^10}-+-{:-^10}", "", "", "", "");
+    println!(
+        "{:<30} | {:>10} | {:>10} | {:>10}",
+        "total", total.logs, total.calls, total.blocks
+    );
+    Ok(())
+}
@@ -9,6 +9,7 @@ use diesel::PgConnection;
 use graph::components::store::DeploymentLocator;
 use graph::components::store::VersionStats;
 use graph::prelude::anyhow;
+use graph::prelude::BlockNumber;
 use graph_store_postgres::command_support::catalog as store_catalog;
 use graph_store_postgres::command_support::catalog::Site;
 use graph_store_postgres::connection_pool::ConnectionPool;
@@ -16,7 +17,7 @@ use graph_store_postgres::Shard;
 use graph_store_postgres::SubgraphStore;
 use graph_store_postgres::PRIMARY_SHARD;
 
-fn site_and_conn(
+pub(crate) fn site_and_conn(
     pools: HashMap<Shard, ConnectionPool>,
     search: &DeploymentSearch,
 ) -> Result<(Site, PooledConnection<ConnectionManager<PgConnection>>), anyhow::Error> {
@@ -102,9 +103,21 @@ pub fn show_stats(
     Ok(())
 }
 
+fn show_versions_in_range(counts: &[(String, i64)], since: BlockNumber, until: BlockNumber) {
+    println!();
+    println!("Entity versions visible in blocks {since}..={until}");
+    println!("{:^30} | {:^10}", "table", "versions");
+    println!("{:-^30}-+-{:-^10}", "", "");
+    for (table, count) in counts {
+        println!("{:<30} | {:>10}", abbreviate_table_name(table, 30), count);
+    }
+}
+
 pub fn show(
     pools: HashMap<Shard, ConnectionPool>,
     search: &DeploymentSearch,
+    since_block: Option<BlockNumber>,
+    until_block: Option<BlockNumber>,
 ) -> Result<(), anyhow::Error> {
     let (site, mut conn) = site_and_conn(pools, search)?;
 
@@ -112,7 +125,24 @@ pub fn show(
 
     let account_like = store_catalog::account_like(&mut conn, &site)?;
 
-    show_stats(stats.as_slice(), account_like)
+    show_stats(stats.as_slice(), account_like)?;
+
+    if since_block.is_some() || until_block.is_some() {
+        let since = since_block.unwrap_or(0);
+        // Postgres can not represent an inclusive `int4range` that ends at
+        // `BlockNumber::MAX`
+        let until = until_block
+            .unwrap_or(BlockNumber::MAX)
+            .min(BlockNumber::MAX - 1);
+        if since > until {
+            return Err(anyhow!(
+                "--since-block {since} must not be after --until-block {until}"
+            ));
+        }
+        let counts = store_catalog::versions_in_range(&mut conn, &site, since, until)?;
+        show_versions_in_range(&counts, since, until);
+    }
+    Ok(())
 }
 
 pub fn analyze(
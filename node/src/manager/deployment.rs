@@ -18,8 +18,8 @@ use graph_store_postgres::unused;
 use crate::manager::display::List;
 
 lazy_static! {
-    // `Qm...` optionally follow by `:$shard`
-    static ref HASH_RE: Regex = Regex::new("\\A(?P<hash>Qm[^:]+)(:(?P<shard>[a-z0-9_]+))?\\z").unwrap();
+    // `Qm...` or `bafy...` optionally follow by `:$shard`
+    static ref HASH_RE: Regex = Regex::new("\\A(?P<hash>(Qm|bafy)[^:]+)(:(?P<shard>[a-z0-9_]+))?\\z").unwrap();
     // `sgdNNN`
     static ref DEPLOYMENT_RE: Regex = Regex::new("\\A(?P<nsp>sgd[0-9]+)\\z").unwrap();
 }
@@ -220,6 +220,7 @@ impl Deployment {
         if !statuses.is_empty() {
             rows.extend(vec![
                 "paused",
+                "paused reason",
                 "synced",
                 "health",
                 "earliest block",
@@ -252,6 +253,7 @@ impl Deployment {
                         .paused
                         .map(|b| b.to_string())
                         .unwrap_or("---".to_string()),
+                    status.paused_reason.clone().unwrap_or("-".to_string()),
                     status.synced.to_string(),
                     status.health.as_str().to_string(),
                     chain.earliest_block_number.to_string(),
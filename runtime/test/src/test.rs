@@ -1,11 +1,13 @@
+use atomic_refcell::AtomicRefCell;
 use graph::blockchain::BlockTime;
 use graph::components::metrics::gas::GasMetrics;
+use graph::components::subgraph::{ProofOfIndexing, ProofOfIndexingVersion};
 use graph::data::store::{scalar, Id, IdType};
 use graph::data::subgraph::*;
 use graph::data::value::Word;
 use graph::prelude::web3::types::U256;
-use graph::runtime::gas::GasCounter;
-use graph::runtime::{AscIndexId, AscType, HostExportError};
+use graph::runtime::gas::{Gas, GasCounter, HOST_EXPORT_GAS};
+use graph::runtime::{AscIndexId, AscType, DeterministicHostError, HostExportError};
 use graph::runtime::{AscPtr, ToAscObj};
 use graph::schema::{EntityType, InputSchema};
 use graph::{components::store::*, ipfs_client::IpfsClient};
@@ -148,6 +150,17 @@ pub trait WasmInstanceExt {
         C: AscType + AscIndexId,
         T: ToAscObj<C> + ?Sized;
     fn invoke_export2<C1, T1, T2, C2, R>(&mut self, f: &str, arg0: &T1, arg1: &T2) -> AscPtr<R>
+    where
+        C1: AscType + AscIndexId,
+        C2: AscType + AscIndexId,
+        T1: ToAscObj<C1> + ?Sized,
+        T2: ToAscObj<C2> + ?Sized;
+    fn try_invoke_export2<C1, T1, T2, C2, R>(
+        &mut self,
+        f: &str,
+        arg0: &T1,
+        arg1: &T2,
+    ) -> Result<AscPtr<R>, Error>
     where
         C1: AscType + AscIndexId,
         C2: AscType + AscIndexId,
@@ -233,6 +246,21 @@ impl WasmInstanceExt for WasmInstance {
     }
 
     fn invoke_export2<C1, T1, T2, C2, R>(&mut self, f: &str, arg0: &T1, arg1: &T2) -> AscPtr<R>
+    where
+        C1: AscType + AscIndexId,
+        C2: AscType + AscIndexId,
+        T1: ToAscObj<C1> + ?Sized,
+        T2: ToAscObj<C2> + ?Sized,
+    {
+        self.try_invoke_export2(f, arg0, arg1).unwrap()
+    }
+
+    fn try_invoke_export2<C1, T1, T2, C2, R>(
+        &mut self,
+        f: &str,
+        arg0: &T1,
+        arg1: &T2,
+    ) -> Result<AscPtr<R>, Error>
     where
         C1: AscType + AscIndexId,
         C2: AscType + AscIndexId,
@@ -246,13 +274,11 @@ impl WasmInstanceExt for WasmInstance {
             .clone();
         let arg0 = self.asc_new(arg0).unwrap();
         let arg1 = self.asc_new(arg1).unwrap();
-        let ptr: u32 = func
-            .call(
-                &mut self.store.as_context_mut(),
-                (arg0.wasm_ptr(), arg1.wasm_ptr()),
-            )
-            .unwrap();
-        ptr.into()
+        let ptr: u32 = func.call(
+            &mut self.store.as_context_mut(),
+            (arg0.wasm_ptr(), arg1.wasm_ptr()),
+        )?;
+        Ok(ptr.into())
     }
 
     fn invoke_export2_void<C1, T1, T2, C2>(
@@ -842,6 +868,47 @@ async fn big_int_arithmetic_v0_0_5() {
     test_big_int_arithmetic(API_VERSION_0_0_5, 7318364).await;
 }
 
+#[tokio::test]
+async fn big_int_arithmetic_errors() {
+    for api_version in [API_VERSION_0_0_10, API_VERSION_0_0_11] {
+        let mut module = test_module(
+            "BigIntArithmeticErrors",
+            mock_data_source(
+                &wasm_file_path("big_int_arithmetic.wasm", API_VERSION_0_0_5),
+                api_version.clone(),
+            ),
+            api_version.clone(),
+        )
+        .await;
+
+        let five = BigInt::from(5);
+        let zero = BigInt::from(0);
+        let err = module
+            .try_invoke_export2::<_, _, _, _, AscBigInt>("dividedBy", &five, &zero)
+            .unwrap_err();
+        assert!(
+            format!("{err:?}").contains("attempted to divide BigInt `5` by zero"),
+            "{}",
+            err
+        );
+
+        // Both factors are within the size limit, but their product is not
+        let big = BigInt::from_unsigned_bytes_le(&[255; 54426]).unwrap();
+        let res = module.try_invoke_export2::<_, _, _, _, AscBigInt>("times", &big, &big);
+        if api_version < API_VERSION_0_0_11 {
+            // Older mappings get the product that is too big
+            assert!(res.is_ok());
+        } else {
+            let err = res.unwrap_err();
+            assert!(
+                format!("{err:?}").contains("BigInt is too big, total bits 870817 (max 435412)"),
+                "{}",
+                err
+            );
+        }
+    }
+}
+
 async fn test_abort(api_version: Version, error_msg: &str) {
     let mut instance = test_module(
         "abort",
@@ -1286,6 +1353,34 @@ async fn recursion_limit() {
     );
 }
 
+#[tokio::test]
+async fn handler_exceeds_gas_limit() {
+    let module = test_module_latest("HandlerGasLimit", "recursion_limit.wasm").await;
+    let host_metrics = module.store.data().host_metrics.cheap_clone();
+
+    // Leave almost no gas so that the handler runs out right away instead
+    // of after running for a long time
+    let max_gas = ENV_VARS.max_gas_per_handler;
+    let remaining = max_gas - module.gas_used() - HOST_EXPORT_GAS.value() - 1;
+    module.gas.consume_host_fn(Gas::new(remaining)).unwrap();
+
+    let (state, gas_used) = module
+        .invoke_handler_for_test("recursionLimit", 10)
+        .unwrap();
+    assert!(gas_used.value() >= max_gas);
+    assert_eq!(1, state.deterministic_errors.len());
+    err_says(
+        &state.deterministic_errors[0].message,
+        &format!("Handler 'recursionLimit' exceeded the gas limit of {max_gas}"),
+    );
+    err_says(&state.deterministic_errors[0].message, "Gas limit exceeded");
+
+    // The gas of handlers that run out of gas is recorded, too
+    let (count, sum) = host_metrics.handler_gas_used("recursionLimit");
+    assert_eq!(1, count);
+    assert_eq!(gas_used.value() as f64, sum);
+}
+
 struct Host {
     ctx: MappingContext,
     host_exports: host_exports::test_support::HostExports,
@@ -1375,6 +1470,28 @@ impl Host {
             &self.gas,
         )
     }
+
+    fn json_try_from_bytes(
+        &mut self,
+        s: &str,
+    ) -> Result<Result<serde_json::Value, anyhow::Error>, DeterministicHostError> {
+        self.host_exports.json_try_from_bytes(
+            &s.as_bytes().to_vec(),
+            &self.gas,
+            &mut self.ctx.state,
+        )
+    }
+
+    fn crypto_random(&mut self) -> [u8; 32] {
+        self.host_exports
+            .crypto_random(&self.ctx.block_ptr, &self.gas, &mut self.ctx.state)
+            .unwrap()
+    }
+
+    fn block_timestamp(&mut self) -> Result<i64, DeterministicHostError> {
+        self.host_exports
+            .block_timestamp(self.ctx.timestamp, &self.gas, &mut self.ctx.state)
+    }
 }
 
 #[track_caller]
@@ -1566,6 +1683,56 @@ async fn test_store_set_invalid_fields() {
     assert!(err_is_none);
 }
 
+/// Test that `store_set` checks that all non-nullable fields have a value,
+/// ignoring derived fields, and that a missing value is a deterministic error
+/// from API version 0.0.11 on
+#[tokio::test]
+async fn test_store_set_missing_fields() {
+    test_store_set_missing_fields_with(API_VERSION_0_0_10, false).await;
+    test_store_set_missing_fields_with(API_VERSION_0_0_11, true).await;
+}
+
+async fn test_store_set_missing_fields_with(api_version: Version, deterministic: bool) {
+    const UID: &str = "u1";
+    const USER: &str = "User";
+
+    let schema = "
+    type User @entity {
+        id: ID!,
+        name: String!,
+        posts: [Post!]! @derivedFrom(field: \"author\")
+    }
+
+    type Post @entity {
+        id: ID!,
+        author: User!
+    }";
+
+    let name = format!("hostStoreSetMissingFields{}", api_version.patch);
+    let mut host = Host::new(schema, &name, "boolean.wasm", Some(api_version.clone())).await;
+
+    let err = host
+        .store_set(USER, UID, vec![("id", "u1")])
+        .expect_err("setting without a non-nullable field fails");
+    assert_eq!(
+        deterministic,
+        matches!(err, HostExportError::Deterministic(_)),
+        "missing fields with API version {api_version} cause a deterministic error: {deterministic}"
+    );
+    err_says(
+        err,
+        "Entity User[u1]: missing value for non-nullable field `name`",
+    );
+
+    host.store_set(USER, UID, vec![("id", "u1"), ("name", "user1")])
+        .expect("derived fields do not need a value");
+
+    host.store_set(USER, UID, vec![("id", "u1")])
+        .expect("updates can rely on the existing value of a field");
+    let entity = host.store_get(USER, UID).unwrap().unwrap();
+    assert_eq!(Some(&Value::from("user1")), entity.get("name"));
+}
+
 /// Test generating ids through `store_set`
 #[tokio::test]
 async fn generate_id() {
@@ -1629,6 +1796,60 @@ async fn generate_id() {
     }
 }
 
+/// Values of fields with a `@scale` directive are scaled before they are
+/// written to the PoI and the store
+#[tokio::test]
+async fn test_store_set_scale() {
+    const TOKEN: &str = "Token";
+
+    const SCHEMA: &str = "type Token @entity {
+        id: ID!,
+        supply: BigDecimal! @scale(decimals: 18),
+    }";
+
+    // Set the supply of a token and return the value that was stored and
+    // the PoI digests for the block
+    async fn set_supply(deployment: &str, supply: Value) -> (Value, HashMap<Id, Vec<u8>>) {
+        let mut host = Host::new(SCHEMA, deployment, "boolean.wasm", None).await;
+        host.ctx.proof_of_indexing = Some(Arc::new(AtomicRefCell::new(ProofOfIndexing::new(
+            12,
+            ProofOfIndexingVersion::Fast,
+        ))));
+
+        host.store_setv(TOKEN, "t1", vec![("supply", supply)])
+            .expect("setting the supply works");
+        let stored = host
+            .store_get(TOKEN, "t1")
+            .unwrap()
+            .expect("the token was stored")
+            .get("supply")
+            .cloned()
+            .unwrap();
+
+        let poi = host.ctx.proof_of_indexing.take().unwrap();
+        let digests = Arc::try_unwrap(poi)
+            .unwrap()
+            .into_inner()
+            .take()
+            .into_iter()
+            .map(|(region, stream)| (region, stream.pause(None)))
+            .collect();
+        (stored, digests)
+    }
+
+    let raw = Value::BigInt(scalar::BigInt::from_str("1234500000000000000000").unwrap());
+    let scaled = Value::BigDecimal(scalar::BigDecimal::from_str("1234.5").unwrap());
+
+    let (stored_raw, poi_raw) = set_supply("hostStoreSetScaleRaw", raw).await;
+    let (stored, poi) = set_supply("hostStoreSetScale", scaled.clone()).await;
+
+    assert_eq!(scaled, stored_raw);
+    assert_eq!(scaled, stored);
+    // Setting the raw value and setting the scaled value result in the
+    // same PoI
+    assert_eq!(poi, poi_raw);
+}
+
 #[tokio::test]
 async fn test_store_intf() {
     const UID: &str = "u1";
@@ -1720,3 +1941,193 @@ async fn test_store_ts() {
         "Cannot get entity of type `Stats`. The type must be an @entity type",
     );
 }
+
+#[tokio::test]
+async fn json_try_from_bytes_out_of_gas() {
+    const SCHEMA: &str = "type Thing @entity { id: ID! }";
+
+    async fn host(api_version: Version) -> Host {
+        let name = format!("hostJsonTry{}", api_version.patch);
+        Host::new(SCHEMA, &name, "boolean.wasm", Some(api_version)).await
+    }
+
+    for api_version in [API_VERSION_0_0_10, API_VERSION_0_0_11] {
+        let mut host = host(api_version.clone()).await;
+        let value = host.json_try_from_bytes("\"foo\"").unwrap().unwrap();
+        assert_eq!(serde_json::Value::from("foo"), value);
+        let err = host.json_try_from_bytes("foo").unwrap().unwrap_err();
+        err_says(err, "expected value");
+
+        // Use up all the gas for the handler
+        host.gas
+            .consume_host_fn(Gas::new(ENV_VARS.max_gas_per_handler))
+            .unwrap_err();
+        let res = host.json_try_from_bytes("\"foo\"");
+        if api_version < API_VERSION_0_0_11 {
+            // Older mappings get the gas error reported like invalid input
+            err_says(res.unwrap().unwrap_err(), "Gas limit exceeded");
+        } else {
+            // Newer mappings abort the handler
+            let err = res.unwrap_err();
+            assert!(matches!(err, DeterministicHostError::Gas(_)));
+            err_says(err, "Gas limit exceeded");
+        }
+    }
+}
+
+#[tokio::test]
+async fn crypto_random_sequence() {
+    const SCHEMA: &str = "type Thing @entity { id: ID! }";
+
+    let mut host = Host::new(
+        SCHEMA,
+        "hostCryptoRandom",
+        "boolean.wasm",
+        Some(API_VERSION_0_0_10),
+    )
+    .await;
+    let expected = |n: u64| {
+        let mut seed = host.ctx.block_ptr.hash_slice().to_vec();
+        seed.extend_from_slice(&n.to_be_bytes());
+        tiny_keccak::keccak256(&seed)
+    };
+    let expected: Vec<_> = (0..5).map(expected).collect();
+
+    assert_eq!(expected[0], host.crypto_random());
+    assert_eq!(expected[1], host.crypto_random());
+
+    // Callbacks of `ipfs.map` run with derived states that are merged back
+    // in order; they continue the sequence of the handler that called
+    // `ipfs.map` and of each other
+    let mut child = host.ctx.derive_with_empty_block_state();
+    let random = host
+        .host_exports
+        .crypto_random(&child.block_ptr, &host.gas, &mut child.state)
+        .unwrap();
+    assert_eq!(expected[2], random);
+    let mut sibling = child.derive_with_empty_block_state();
+    let random = host
+        .host_exports
+        .crypto_random(&sibling.block_ptr, &host.gas, &mut sibling.state)
+        .unwrap();
+    assert_eq!(expected[3], random);
+
+    host.ctx.state.extend(child.state);
+    host.ctx.state.extend(sibling.state);
+    assert_eq!(expected[4], host.crypto_random());
+}
+
+#[tokio::test]
+async fn block_timestamp() {
+    const SCHEMA: &str = "type Thing @entity { id: ID! }";
+
+    // For a block trigger, the mapping context has the time of the block
+    let mut host = Host::new(
+        SCHEMA,
+        "hostBlockTimestamp",
+        "boolean.wasm",
+        Some(API_VERSION_0_0_10),
+    )
+    .await;
+    host.ctx.timestamp = BlockTime::since_epoch(1_700_000_000, 0);
+    assert_eq!(1_700_000_000, host.block_timestamp().unwrap());
+
+    // Data sources that are not triggered by a block, like file data
+    // sources, have no block time
+    host.ctx.timestamp = BlockTime::NONE;
+    let err = host
+        .block_timestamp()
+        .expect_err("there is no block time for offchain triggers");
+    err_says(
+        err,
+        "block.timestamp is not available for data sources that are not triggered by a block",
+    );
+
+    let mut host = Host::new(
+        SCHEMA,
+        "hostBlockTimestampOld",
+        "boolean.wasm",
+        Some(API_VERSION_0_0_9),
+    )
+    .await;
+    host.ctx.timestamp = BlockTime::since_epoch(1_700_000_000, 0);
+    let err = host
+        .block_timestamp()
+        .expect_err("block.timestamp needs API version 0.0.10");
+    err_says(
+        err,
+        "block.timestamp is not supported before API version 0.0.10",
+    );
+}
+
+fn set_many_user(id: &str, n: i32) -> Vec<(Word, Value)> {
+    vec![
+        (Word::from("id"), Value::from(id)),
+        (Word::from("name"), Value::String(format!("user{n}"))),
+        (Word::from("count"), Value::BigInt(n.into())),
+    ]
+}
+
+fn set_many_mods(instance: WasmInstance) -> Vec<EntityModification> {
+    let mut mods = instance
+        .take_ctx()
+        .take_state()
+        .entity_cache
+        .as_modifications(0)
+        .unwrap()
+        .modifications;
+    mods.sort_by(|a, b| a.key().entity_id.partial_cmp(&b.key().entity_id).unwrap());
+    mods
+}
+
+/// Test that `store.setMany` changes the entity cache in the same way and
+/// uses the same amount of gas as calling `store.set` for each entity
+#[tokio::test]
+async fn store_set_many_is_like_store_set() {
+    const USER: &str = "User";
+
+    // `u1` is set twice so that the second `set` updates the first one
+    let ids = ["u1", "u2", "u3", "u1"];
+    let data: Vec<_> = ids
+        .iter()
+        .enumerate()
+        .map(|(n, id)| set_many_user(id, n as i32))
+        .collect();
+
+    let mut single = test_module_latest("storeSetManySingle", "boolean.wasm").await;
+    for (id, data) in ids.iter().zip(&data) {
+        single.store_set(USER, id, data.clone()).unwrap();
+    }
+
+    let mut many = test_module_latest("storeSetManyMany", "boolean.wasm").await;
+    many.store_set_many(&[USER; 4], &ids, &data).unwrap();
+
+    assert!(single.gas_used() > 0);
+    assert_eq!(single.gas_used(), many.gas_used());
+
+    let single = set_many_mods(single);
+    assert_eq!(3, single.len());
+    assert_eq!(single, set_many_mods(many));
+
+    let mut module = test_module_latest("storeSetManyLen", "boolean.wasm").await;
+    let err = module
+        .store_set_many(&[USER; 2], &ids[..1], &data[..1])
+        .expect_err("arrays of different length fail");
+    assert!(matches!(err, HostExportError::Deterministic(_)));
+    err_says(err, "got 2 entity types, 1 ids and 1 data");
+    assert_eq!(0, set_many_mods(module).len());
+
+    let mut module = test_module(
+        "storeSetManyOld",
+        mock_data_source(
+            &wasm_file_path("boolean.wasm", API_VERSION_0_0_5),
+            API_VERSION_0_0_9,
+        ),
+        API_VERSION_0_0_9,
+    )
+    .await;
+    let err = module
+        .store_set_many(&[USER; 1], &ids[..1], &data[..1])
+        .expect_err("store.setMany needs API version 0.0.10");
+    err_says(err, "not supported before API version 0.0.10");
+}
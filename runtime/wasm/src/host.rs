@@ -1,4 +1,5 @@
 use std::cmp::PartialEq;
+use std::collections::BTreeMap;
 use std::time::Instant;
 
 use async_trait::async_trait;
@@ -26,6 +27,9 @@ use super::host_exports::DataSourceDetails;
 
 pub struct RuntimeHostBuilder<C: Blockchain> {
     runtime_adapter: Arc<dyn RuntimeAdapter<C>>,
+    /// Runtime adapters for data sources on networks other than the one
+    /// that `runtime_adapter` is for
+    network_runtime_adapters: BTreeMap<String, Arc<dyn RuntimeAdapter<C>>>,
     link_resolver: Arc<dyn LinkResolver>,
     ens_lookup: Arc<dyn EnsLookup>,
 }
@@ -34,6 +38,7 @@ impl<C: Blockchain> Clone for RuntimeHostBuilder<C> {
     fn clone(&self) -> Self {
         RuntimeHostBuilder {
             runtime_adapter: self.runtime_adapter.cheap_clone(),
+            network_runtime_adapters: self.network_runtime_adapters.clone(),
             link_resolver: self.link_resolver.cheap_clone(),
             ens_lookup: self.ens_lookup.cheap_clone(),
         }
@@ -48,10 +53,22 @@ impl<C: Blockchain> RuntimeHostBuilder<C> {
     ) -> Self {
         RuntimeHostBuilder {
             runtime_adapter,
+            network_runtime_adapters: BTreeMap::new(),
             link_resolver,
             ens_lookup,
         }
     }
+
+    /// Use `runtime_adapter` for the hosts of data sources on `network`
+    pub fn with_network(
+        mut self,
+        network: String,
+        runtime_adapter: Arc<dyn RuntimeAdapter<C>>,
+    ) -> Self {
+        self.network_runtime_adapters
+            .insert(network, runtime_adapter);
+        self
+    }
 }
 
 impl<C: Blockchain> RuntimeHostBuilderTrait<C> for RuntimeHostBuilder<C>
@@ -90,8 +107,12 @@ where
         mapping_request_sender: Sender<WasmRequest<C>>,
         metrics: Arc<HostMetrics>,
     ) -> Result<Self::Host, Error> {
+        let runtime_adapter = self
+            .network_runtime_adapters
+            .get(&network_name)
+            .unwrap_or(&self.runtime_adapter);
         RuntimeHost::new(
-            self.runtime_adapter.cheap_clone(),
+            runtime_adapter.cheap_clone(),
             self.link_resolver.clone(),
             network_name,
             subgraph_id,
@@ -3,7 +3,7 @@ use std::ops::Deref;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 
-use graph::data::subgraph::API_VERSION_0_0_8;
+use graph::data::subgraph::{API_VERSION_0_0_10, API_VERSION_0_0_11, API_VERSION_0_0_8};
 use graph::data::value::Word;
 
 use graph::futures03::stream::StreamExt;
@@ -18,7 +18,7 @@ use graph::components::store::{EnsLookup, GetScope, LoadRelatedRequest};
 use graph::components::subgraph::{
     InstanceDSTemplate, PoICausalityRegion, ProofOfIndexingEvent, SharedProofOfIndexing,
 };
-use graph::data::store::{self};
+use graph::data::store::{self, EntityValidationError};
 use graph::data_source::{CausalityRegion, DataSource, EntityTypeAccess};
 use graph::ensure;
 use graph::prelude::ethabi::param_type::Reader;
@@ -330,11 +330,17 @@ impl HostExports {
                 .has_field_with_name(&key.entity_type, field_name)
         });
 
-        let entity = state
+        let mut entity = state
             .entity_cache
             .make_entity(filtered_entity_data)
             .map_err(|e| HostExportError::Deterministic(anyhow!(e)))?;
 
+        // Scale values before they go into the PoI so that it records what
+        // is actually stored
+        entity
+            .apply_scale(&key)
+            .map_err(|e| HostExportError::Deterministic(key.unknown_attribute(e).into()))?;
+
         let poi_section = stopwatch.start_section("host_export_store_set__proof_of_indexing");
         write_poi_event(
             proof_of_indexing,
@@ -350,7 +356,19 @@ impl HostExports {
 
         state.metrics.track_entity_write(&entity_type, &entity);
 
-        state.entity_cache.set(key, entity)?;
+        // The entity cache validates the entity against the schema, e.g.,
+        // that all non-nullable fields have a value. Such errors are
+        // caused by the mapping and therefore deterministic. Mappings
+        // before `API_VERSION_0_0_11` get them as unknown errors as they
+        // always did
+        let validation_is_deterministic = self.data_source.api_version >= API_VERSION_0_0_11;
+        state.entity_cache.set(key, entity).map_err(|e| {
+            if validation_is_deterministic && e.is::<EntityValidationError>() {
+                HostExportError::Deterministic(e)
+            } else {
+                HostExportError::Unknown(e)
+            }
+        })?;
 
         Ok(())
     }
@@ -530,7 +548,7 @@ impl HostExports {
 
         let host_metrics = wasm_ctx.host_metrics.clone();
         let valid_module = wasm_ctx.valid_module.clone();
-        let ctx = wasm_ctx.ctx.derive_with_empty_block_state();
+        let mut ctx = wasm_ctx.ctx.derive_with_empty_block_state();
         let callback = callback.to_owned();
         // Create a base error message to avoid borrowing headaches
         let errmsg = format!(
@@ -555,6 +573,10 @@ impl HostExports {
                     wasm_ctx.experimental_features,
                 )?;
                 let result = module.handle_json_callback(&callback, &sv.value, &user_data)?;
+                // The states are merged into the parent in this order, and
+                // the next callback has to pick up the random sequence
+                // where this one left it
+                ctx.state.continue_random_calls(&result);
                 // Log progress every 15s
                 if last_log.elapsed() > Duration::from_secs(15) {
                     debug!(
@@ -664,6 +686,34 @@ impl HostExports {
         Ok(tiny_keccak::keccak256(data))
     }
 
+    /// Produce 32 pseudo-random bytes, computed as the keccak256 hash of
+    /// the hash of `block_ptr` and the number of values handed out for
+    /// this block so far. The values are the same on every node, but they
+    /// are predictable for anybody who knows the block hash and change
+    /// when the block is reorged out. They must not be used where
+    /// cryptographic security is needed
+    pub(crate) fn crypto_random(
+        &self,
+        block_ptr: &BlockPtr,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<[u8; 32], DeterministicHostError> {
+        if self.data_source.api_version < API_VERSION_0_0_10 {
+            return Err(DeterministicHostError::from(anyhow!(
+                "crypto.random is not supported before API version 0.0.10"
+            )));
+        }
+        let mut seed = block_ptr.hash_slice().to_vec();
+        seed.extend_from_slice(&state.next_random_call().to_be_bytes());
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::DEFAULT_GAS_OP.with_args(complexity::Size, &seed),
+            "crypto_random",
+        )?;
+        Ok(tiny_keccak::keccak256(&seed))
+    }
+
     pub(crate) fn big_int_plus(
         &self,
         x: BigInt,
@@ -677,7 +727,7 @@ impl HostExports {
             gas::BIG_MATH_GAS_OP.with_args(complexity::Max, (&x, &y)),
             "big_int_plus",
         )?;
-        Ok(x + y)
+        self.checked_big_int(x + y)
     }
 
     pub(crate) fn big_int_minus(
@@ -693,7 +743,7 @@ impl HostExports {
             gas::BIG_MATH_GAS_OP.with_args(complexity::Max, (&x, &y)),
             "big_int_minus",
         )?;
-        Ok(x - y)
+        self.checked_big_int(x - y)
     }
 
     pub(crate) fn big_int_times(
@@ -709,7 +759,7 @@ impl HostExports {
             gas::BIG_MATH_GAS_OP.with_args(complexity::Mul, (&x, &y)),
             "big_int_times",
         )?;
-        Ok(x * y)
+        self.checked_big_int(x * y)
     }
 
     pub(crate) fn big_int_divided_by(
@@ -771,9 +821,22 @@ impl HostExports {
                 .with_args(complexity::Exponential, (&x, (exp as f32).log2() as u8)),
             "big_int_pow",
         )?;
+        if self.data_source.api_version >= API_VERSION_0_0_11 {
+            x.check_pow(exp)?;
+        }
         Ok(x.pow(exp)?)
     }
 
+    /// Check `x`, the result of one of the BigInt operators, against
+    /// `BigInt::MAX_BITS`. Mappings before `API_VERSION_0_0_11` get the
+    /// unchecked value
+    fn checked_big_int(&self, x: BigInt) -> Result<BigInt, DeterministicHostError> {
+        if self.data_source.api_version < API_VERSION_0_0_11 {
+            return Ok(x);
+        }
+        Ok(x.checked()?)
+    }
+
     pub(crate) fn big_int_from_string(
         &self,
         s: String,
@@ -804,7 +867,7 @@ impl HostExports {
             gas::BIG_MATH_GAS_OP.with_args(complexity::Max, (&x, &y)),
             "big_int_bit_or",
         )?;
-        Ok(x | y)
+        self.checked_big_int(x | y)
     }
 
     pub(crate) fn big_int_bit_and(
@@ -820,7 +883,7 @@ impl HostExports {
             gas::BIG_MATH_GAS_OP.with_args(complexity::Min, (&x, &y)),
             "big_int_bit_and",
         )?;
-        Ok(x & y)
+        self.checked_big_int(x & y)
     }
 
     pub(crate) fn big_int_left_shift(
@@ -836,7 +899,7 @@ impl HostExports {
             gas::BIG_MATH_GAS_OP.with_args(complexity::Linear, (&x, &bits)),
             "big_int_left_shift",
         )?;
-        Ok(x << bits)
+        self.checked_big_int(x << bits)
     }
 
     pub(crate) fn big_int_right_shift(
@@ -919,7 +982,9 @@ impl HostExports {
         Ok(x * y)
     }
 
-    /// Maximum precision of 100 decimal digits.
+    /// The quotient is computed to 100 significant digits and then
+    /// rounded to `BigDecimal::MAX_SIGNFICANT_DIGITS` digits like the
+    /// result of any other `BigDecimal` operation
     pub(crate) fn big_decimal_divided_by(
         &self,
         x: BigDecimal,
@@ -1116,6 +1181,34 @@ impl HostExports {
         Ok(self.subgraph_network.clone())
     }
 
+    /// The timestamp of the block that is being processed in seconds since
+    /// the Unix epoch, taken from the block header. It is the same for all
+    /// handlers that run for a block, whatever triggered them
+    pub(crate) fn block_timestamp(
+        &self,
+        timestamp: BlockTime,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<i64, DeterministicHostError> {
+        if self.data_source.api_version < API_VERSION_0_0_10 {
+            return Err(DeterministicHostError::from(anyhow!(
+                "block.timestamp is not supported before API version 0.0.10"
+            )));
+        }
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            Gas::new(gas::DEFAULT_BASE_COST),
+            "block_timestamp",
+        )?;
+        if timestamp == BlockTime::NONE {
+            return Err(DeterministicHostError::from(anyhow!(
+                "block.timestamp is not available for data sources that are not triggered by a block"
+            )));
+        }
+        Ok(timestamp.as_secs_since_epoch())
+    }
+
     pub(crate) fn data_source_context(
         &self,
         gas: &GasCounter,
@@ -1136,6 +1229,32 @@ impl HostExports {
         gas: &GasCounter,
         state: &mut BlockState,
     ) -> Result<serde_json::Value, DeterministicHostError> {
+        Self::parse_json(bytes, gas, state)?.map_err(DeterministicHostError::from)
+    }
+
+    /// Like `json_from_bytes`, but the inner error means that `bytes` are
+    /// not valid JSON or too large, and should be reported to the mapping.
+    /// The outer error is for failures that must abort the handler, like
+    /// running out of gas. Before API version 0.0.11, running out of gas
+    /// was also reported to the mapping, and we need to keep doing that
+    /// for older mappings since it changes how they process blocks
+    pub(crate) fn json_try_from_bytes(
+        &self,
+        bytes: &Vec<u8>,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<Result<serde_json::Value, Error>, DeterministicHostError> {
+        match Self::parse_json(bytes, gas, state) {
+            Err(e) if self.data_source.api_version < API_VERSION_0_0_11 => Ok(Err(e.inner())),
+            result => result,
+        }
+    }
+
+    fn parse_json(
+        bytes: &Vec<u8>,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<Result<serde_json::Value, Error>, DeterministicHostError> {
         // Max JSON size is 10MB.
         const MAX_JSON_SIZE: usize = 10_000_000;
 
@@ -1147,13 +1266,13 @@ impl HostExports {
         )?;
 
         if bytes.len() > MAX_JSON_SIZE {
-            return Err(DeterministicHostError::Other(
-                anyhow!("JSON size exceeds max size of {}", MAX_JSON_SIZE).into(),
-            ));
+            return Ok(Err(anyhow!(
+                "JSON size exceeds max size of {}",
+                MAX_JSON_SIZE
+            )));
         }
 
-        serde_json::from_slice(bytes.as_slice())
-            .map_err(|e| DeterministicHostError::from(Error::from(e)))
+        Ok(serde_json::from_slice(bytes.as_slice()).map_err(Error::from))
     }
 
     pub(crate) fn string_to_h160(
@@ -1265,14 +1384,14 @@ pub mod test_support {
     use std::{collections::HashMap, sync::Arc};
 
     use graph::{
-        blockchain::BlockTime,
+        blockchain::{BlockPtr, BlockTime},
         components::{
             store::{BlockNumber, GetScope},
             subgraph::SharedProofOfIndexing,
         },
         data::value::Word,
-        prelude::{BlockState, Entity, StopwatchMetrics, Value},
-        runtime::{gas::GasCounter, HostExportError},
+        prelude::{serde_json, BlockState, Entity, StopwatchMetrics, Value},
+        runtime::{gas::GasCounter, DeterministicHostError, HostExportError},
         slog::Logger,
     };
 
@@ -1327,6 +1446,33 @@ pub mod test_support {
             self.host_exports
                 .store_get(state, entity_type, entity_id, gas, GetScope::Store)
         }
+
+        pub fn json_try_from_bytes(
+            &self,
+            bytes: &Vec<u8>,
+            gas: &GasCounter,
+            state: &mut BlockState,
+        ) -> Result<Result<serde_json::Value, anyhow::Error>, DeterministicHostError> {
+            self.host_exports.json_try_from_bytes(bytes, gas, state)
+        }
+
+        pub fn crypto_random(
+            &self,
+            block_ptr: &BlockPtr,
+            gas: &GasCounter,
+            state: &mut BlockState,
+        ) -> Result<[u8; 32], DeterministicHostError> {
+            self.host_exports.crypto_random(block_ptr, gas, state)
+        }
+
+        pub fn block_timestamp(
+            &self,
+            timestamp: BlockTime,
+            gas: &GasCounter,
+            state: &mut BlockState,
+        ) -> Result<i64, DeterministicHostError> {
+            self.host_exports.block_timestamp(timestamp, gas, state)
+        }
     }
 }
 #[test]
@@ -209,12 +209,14 @@ pub struct MappingContext {
 
 impl MappingContext {
     pub fn derive_with_empty_block_state(&self) -> Self {
+        let mut state = BlockState::new(self.state.entity_cache.store.clone(), Default::default());
+        state.continue_random_calls(&self.state);
         MappingContext {
             logger: self.logger.cheap_clone(),
             host_exports: self.host_exports.cheap_clone(),
             block_ptr: self.block_ptr.cheap_clone(),
             timestamp: self.timestamp,
-            state: BlockState::new(self.state.entity_cache.store.clone(), Default::default()),
+            state,
             proof_of_indexing: self.proof_of_indexing.cheap_clone(),
             host_fns: self.host_fns.cheap_clone(),
             debug_fork: self.debug_fork.cheap_clone(),
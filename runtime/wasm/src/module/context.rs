@@ -1,3 +1,4 @@
+use graph::data::subgraph::{API_VERSION_0_0_10, API_VERSION_0_0_11};
 use graph::data::value::Word;
 use graph::runtime::gas;
 use graph::util::lfu_cache::LfuCache;
@@ -9,7 +10,6 @@ use wasmtime::StoreContextMut;
 use std::sync::Arc;
 use std::time::Instant;
 
-use anyhow::Error;
 use graph::components::store::GetScope;
 use never::Never;
 
@@ -288,6 +288,80 @@ impl WasmInstanceContext<'_> {
         Ok(())
     }
 
+    /// function store.setMany(entities: Array<string>, ids: Array<string>, data: Array<Entity>): void
+    ///
+    /// Has the same effect as calling `store.set(entities[i], ids[i],
+    /// data[i])` for each `i` in order, but only crosses the WASM boundary
+    /// once
+    pub fn store_set_many(
+        &mut self,
+        gas: &GasCounter,
+        entities_ptr: AscPtr<Array<AscPtr<AscString>>>,
+        ids_ptr: AscPtr<Array<AscPtr<AscString>>>,
+        data_ptr: AscPtr<Array<AscPtr<AscEntity>>>,
+    ) -> Result<(), HostExportError> {
+        if self.as_ref().ctx.host_exports.data_source.api_version < API_VERSION_0_0_10 {
+            return Err(HostExportError::Deterministic(anyhow!(
+                "store.setMany is not supported before API version 0.0.10"
+            )));
+        }
+
+        let stopwatch = self.as_ref().host_metrics.stopwatch.cheap_clone();
+        let logger = self.as_ref().ctx.logger.cheap_clone();
+        let block_number = self.as_ref().ctx.block_ptr.block_number();
+        stopwatch.start_section("host_export_store_set__wasm_instance_context_store_set");
+
+        // Reading the arrays themselves is free so that `store.setMany`
+        // costs exactly as much gas as the equivalent `store.set` calls;
+        // reading each element below is charged like in `store_set`
+        let free = GasCounter::new(self.as_ref().host_metrics.gas_metrics.cheap_clone());
+        let entity_ptrs = entities_ptr.read_ptr(self, &free)?.to_vec(self, &free)?;
+        let id_ptrs = ids_ptr.read_ptr(self, &free)?.to_vec(self, &free)?;
+        let data_ptrs = data_ptr.read_ptr(self, &free)?.to_vec(self, &free)?;
+
+        if entity_ptrs.len() != id_ptrs.len() || entity_ptrs.len() != data_ptrs.len() {
+            return Err(HostExportError::Deterministic(anyhow!(
+                "store.setMany needs the same number of entity types, ids and data, \
+                 but got {} entity types, {} ids and {} data",
+                entity_ptrs.len(),
+                id_ptrs.len(),
+                data_ptrs.len()
+            )));
+        }
+
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let instrument = self.as_ref().ctx.instrument;
+
+        for ((entity_ptr, id_ptr), data_ptr) in entity_ptrs.into_iter().zip(id_ptrs).zip(data_ptrs)
+        {
+            let entity: String = asc_get(self, entity_ptr, gas)?;
+            let id: String = asc_get(self, id_ptr, gas)?;
+            let data = asc_get(self, data_ptr, gas)?;
+
+            if instrument {
+                debug!(logger, "store_set";
+                        "type" => &entity,
+                        "id" => &id);
+            }
+
+            let ctx = &mut self.as_mut().ctx;
+            host_exports.store_set(
+                &logger,
+                block_number,
+                &mut ctx.state,
+                &ctx.proof_of_indexing,
+                ctx.timestamp,
+                entity,
+                id,
+                data,
+                &stopwatch,
+                gas,
+            )?;
+        }
+
+        Ok(())
+    }
+
     /// function store.remove(entity: string, id: string): void
     pub fn store_remove(
         &mut self,
@@ -488,13 +562,15 @@ impl WasmInstanceContext<'_> {
         let bytes: Vec<u8> = asc_get(self, bytes_ptr, gas)?;
         let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
         let ctx = &mut self.as_mut().ctx;
+        // Errors like running out of gas abort the handler for newer
+        // mappings; invalid input is always reported back to the mapping
         let result = host_exports
-            .json_from_bytes(&bytes, gas, &mut ctx.state)
+            .json_try_from_bytes(&bytes, gas, &mut ctx.state)?
             .map_err(|e| {
                 warn!(
                     &self.as_ref().ctx.logger,
                     "Failed to parse JSON from byte array";
-                    "bytes" => format!("{:?}", bytes),
+                    "bytes" => format!("{:?}", &bytes[..bytes.len().min(1024)]),
                     "error" => format!("{}", e)
                 );
 
@@ -704,6 +780,18 @@ impl WasmInstanceContext<'_> {
         asc_new(self, input.as_ref(), gas)
     }
 
+    /// function crypto.random(): Bytes
+    pub fn crypto_random(
+        &mut self,
+        gas: &GasCounter,
+    ) -> Result<AscPtr<Uint8Array>, HostExportError> {
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        let random = host_exports.crypto_random(&ctx.block_ptr, gas, &mut ctx.state)?;
+        asc_new(self, random.as_ref(), gas)
+    }
+
     /// function bigInt.plus(x: BigInt, y: BigInt): BigInt
     pub fn big_int_plus(
         &mut self,
@@ -807,6 +895,23 @@ impl WasmInstanceContext<'_> {
         asc_new(self, &result, gas)
     }
 
+    /// Convert the exponent or shift amount `value` of a BigInt host
+    /// function to a `u8`. Mappings before `API_VERSION_0_0_11` get the
+    /// plain conversion error
+    fn big_int_u8_arg(&self, value: u32, what: &str) -> Result<u8, DeterministicHostError> {
+        u8::try_from(value).map_err(|e| {
+            if self.as_ref().ctx.host_exports.data_source.api_version < API_VERSION_0_0_11 {
+                DeterministicHostError::from(Error::from(e))
+            } else {
+                DeterministicHostError::from(anyhow!(
+                    "BigInt {} must be at most 255, but is {}",
+                    what,
+                    value
+                ))
+            }
+        })
+    }
+
     /// function bigInt.pow(x: BigInt, exp: u8): BigInt
     pub fn big_int_pow(
         &mut self,
@@ -815,7 +920,7 @@ impl WasmInstanceContext<'_> {
         x_ptr: AscPtr<AscBigInt>,
         exp: u32,
     ) -> Result<AscPtr<AscBigInt>, HostExportError> {
-        let exp = u8::try_from(exp).map_err(|e| DeterministicHostError::from(Error::from(e)))?;
+        let exp = self.big_int_u8_arg(exp, "exponent")?;
         let x = asc_get(self, x_ptr, gas)?;
         let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
 
@@ -866,7 +971,7 @@ impl WasmInstanceContext<'_> {
         x_ptr: AscPtr<AscBigInt>,
         bits: u32,
     ) -> Result<AscPtr<AscBigInt>, HostExportError> {
-        let bits = u8::try_from(bits).map_err(|e| DeterministicHostError::from(Error::from(e)))?;
+        let bits = self.big_int_u8_arg(bits, "shift")?;
         let x = asc_get(self, x_ptr, gas)?;
         let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
         let ctx = &mut self.as_mut().ctx;
@@ -882,7 +987,7 @@ impl WasmInstanceContext<'_> {
         x_ptr: AscPtr<AscBigInt>,
         bits: u32,
     ) -> Result<AscPtr<AscBigInt>, HostExportError> {
-        let bits = u8::try_from(bits).map_err(|e| DeterministicHostError::from(Error::from(e)))?;
+        let bits = self.big_int_u8_arg(bits, "shift")?;
         let x = asc_get(self, x_ptr, gas)?;
         let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
 
@@ -1084,6 +1189,13 @@ impl WasmInstanceContext<'_> {
         asc_new(self, &data_source_network, gas)
     }
 
+    /// function block.timestamp(): i64
+    pub fn block_timestamp(&mut self, gas: &GasCounter) -> Result<i64, DeterministicHostError> {
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+        host_exports.block_timestamp(ctx.timestamp, gas, &mut ctx.state)
+    }
+
     /// function dataSource.context(): DataSourceContext
     pub fn data_source_context(
         &mut self,
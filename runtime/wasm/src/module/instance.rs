@@ -40,11 +40,19 @@ pub struct WasmInstance {
 
 #[cfg(debug_assertions)]
 mod impl_for_tests {
+    use std::sync::Arc;
+
+    use graph::components::subgraph::MappingError;
+    use graph::data::store;
+    use graph::data::value::Word;
+    use graph::prelude::{o, BlockState, CheapClone};
     use graph::runtime::{
-        asc_new, AscIndexId, AscPtr, AscType, DeterministicHostError, FromAscObj, HostExportError,
-        ToAscObj,
+        asc_new,
+        gas::{Gas, GasCounter},
+        AscIndexId, AscPtr, AscType, DeterministicHostError, FromAscObj, HostExportError, ToAscObj,
     };
 
+    use crate::asc_abi::class::{Array, AscEntity, AscString};
     use crate::module::{asc_get, WasmInstanceContext};
 
     impl super::WasmInstance {
@@ -65,6 +73,53 @@ mod impl_for_tests {
             let mut ctx = WasmInstanceContext::new(&mut self.store);
             asc_new(&mut ctx, rust_obj, &self.gas)
         }
+
+        /// Call `store.set` like a mapping would. Only the host export is
+        /// charged to `self.gas`, not writing the arguments into the heap
+        pub fn store_set(
+            &mut self,
+            entity: &str,
+            id: &str,
+            data: Vec<(Word, store::Value)>,
+        ) -> Result<(), HostExportError> {
+            let untracked = self.untracked_gas();
+            let mut ctx = WasmInstanceContext::new(&mut self.store);
+            let entity: AscPtr<AscString> = asc_new(&mut ctx, entity, &untracked)?;
+            let id: AscPtr<AscString> = asc_new(&mut ctx, id, &untracked)?;
+            let data: AscPtr<AscEntity> = asc_new(&mut ctx, &data, &untracked)?;
+            ctx.store_set(&self.gas, entity, id, data)
+        }
+
+        /// Call `store.setMany` like a mapping would. Only the host export
+        /// is charged to `self.gas`, not writing the arguments into the heap
+        pub fn store_set_many(
+            &mut self,
+            entities: &[&str],
+            ids: &[&str],
+            data: &[Vec<(Word, store::Value)>],
+        ) -> Result<(), HostExportError> {
+            let untracked = self.untracked_gas();
+            let mut ctx = WasmInstanceContext::new(&mut self.store);
+            let entities: AscPtr<Array<AscPtr<AscString>>> =
+                asc_new(&mut ctx, entities, &untracked)?;
+            let ids: AscPtr<Array<AscPtr<AscString>>> = asc_new(&mut ctx, ids, &untracked)?;
+            let data: AscPtr<Array<AscPtr<AscEntity>>> = asc_new(&mut ctx, data, &untracked)?;
+            ctx.store_set_many(&self.gas, entities, ids, data)
+        }
+
+        /// Run `handler` like a trigger for it would, passing `arg` as
+        /// its only argument
+        pub fn invoke_handler_for_test(
+            self,
+            handler: &str,
+            arg: u32,
+        ) -> Result<(BlockState, Gas), MappingError> {
+            self.invoke_handler(handler, AscPtr::<()>::new(arg), Arc::new(o!()), None)
+        }
+
+        fn untracked_gas(&self) -> GasCounter {
+            GasCounter::new(self.store.data().host_metrics.gas_metrics.cheap_clone())
+        }
     }
 }
 
@@ -176,52 +231,70 @@ impl WasmInstance {
         // Caution: Make sure all exit paths from this function call `exit_handler`.
         self.instance_ctx().as_mut().ctx.state.enter_handler();
 
+        let result = func.call(self.store.as_context_mut(), arg.wasm_ptr());
+
+        let memory = self.store.data().asc_heap_ref().memory;
+        let memory_size = memory.data_size(&self.store);
+        self.store
+            .data()
+            .host_metrics
+            .observe_handler_memory_peak(memory_size, handler);
+
         // This `match` will return early if there was a non-deterministic trap.
-        let deterministic_error: Option<Error> =
-            match func.call(self.store.as_context_mut(), arg.wasm_ptr()) {
-                Ok(()) => {
-                    assert!(self.instance_ctx().as_ref().possible_reorg == false);
-                    assert!(self.instance_ctx().as_ref().deterministic_host_trap == false);
-                    None
-                }
-                Err(trap) if self.instance_ctx().as_ref().possible_reorg => {
-                    self.instance_ctx().as_mut().ctx.state.exit_handler();
-                    return Err(MappingError::PossibleReorg(trap.into()));
-                }
+        let deterministic_error: Option<Error> = match result {
+            Ok(()) => {
+                assert!(self.instance_ctx().as_ref().possible_reorg == false);
+                assert!(self.instance_ctx().as_ref().deterministic_host_trap == false);
+                None
+            }
+            Err(trap) if self.instance_ctx().as_ref().possible_reorg => {
+                self.instance_ctx().as_mut().ctx.state.exit_handler();
+                return Err(MappingError::PossibleReorg(trap.into()));
+            }
 
-                // Treat timeouts anywhere in the error chain as a special case to have a better error
-                // message. Any `TrapCode::Interrupt` is assumed to be a timeout.
-                // See also: runtime-timeouts
-                Err(trap)
-                    if trap
-                        .chain()
-                        .any(|e| e.downcast_ref::<Trap>() == Some(&Trap::Interrupt)) =>
-                {
-                    self.instance_ctx().as_mut().ctx.state.exit_handler();
-                    return Err(MappingError::Unknown(Error::from(trap).context(format!(
+            // Treat timeouts anywhere in the error chain as a special case to have a better error
+            // message. Any `TrapCode::Interrupt` is assumed to be a timeout.
+            // See also: runtime-timeouts
+            Err(trap)
+                if trap
+                    .chain()
+                    .any(|e| e.downcast_ref::<Trap>() == Some(&Trap::Interrupt)) =>
+            {
+                self.instance_ctx().as_mut().ctx.state.exit_handler();
+                return Err(MappingError::Unknown(Error::from(trap).context(format!(
                         "Handler '{}' hit the timeout of '{}' seconds",
                         handler,
                         self.instance_ctx().as_ref().valid_module.timeout.unwrap().as_secs()
                     ))));
-                }
-                Err(trap) => {
-                    let trap_is_deterministic = is_trap_deterministic(&trap)
-                        || self.instance_ctx().as_ref().deterministic_host_trap;
-                    match trap_is_deterministic {
-                        true => Some(trap),
-                        false => {
-                            self.instance_ctx().as_mut().ctx.state.exit_handler();
-                            return Err(MappingError::Unknown(trap));
-                        }
+            }
+            Err(trap) => {
+                let trap_is_deterministic = is_trap_deterministic(&trap)
+                    || self.instance_ctx().as_ref().deterministic_host_trap;
+                match trap_is_deterministic {
+                    true => Some(trap),
+                    false => {
+                        self.instance_ctx().as_mut().ctx.state.exit_handler();
+                        return Err(MappingError::Unknown(trap));
                     }
                 }
-            };
+            }
+        };
 
         if let Some(deterministic_error) = deterministic_error {
             let deterministic_error = match error_context {
                 Some(error_context) => deterministic_error.context(error_context),
                 None => deterministic_error,
             };
+            // Running out of gas happens at the same point on every node;
+            // make sure the error says which handler ran out
+            let deterministic_error = if self.gas.get().0 >= ENV_VARS.max_gas_per_handler {
+                deterministic_error.context(format!(
+                    "Handler '{}' exceeded the gas limit of {}",
+                    handler, ENV_VARS.max_gas_per_handler
+                ))
+            } else {
+                deterministic_error
+            };
             let message = format!("{:#}", deterministic_error).replace('\n', "\t");
 
             // Log the error and restore the updates snapshot, effectively reverting the handler.
@@ -254,6 +327,10 @@ impl WasmInstance {
         }
 
         let gas = self.gas.get();
+        self.store
+            .data()
+            .host_metrics
+            .observe_handler_gas_used(gas, handler);
         Ok((self.take_ctx().take_state(), gas))
     }
 }
@@ -426,6 +503,14 @@ impl WasmInstance {
             id,
             data
         );
+        link!(
+            "store.setMany",
+            store_set_many,
+            "host_export_store_set",
+            entities,
+            ids,
+            data
+        );
 
         // All IPFS-related functions exported by the host WASM runtime should be listed in the
         // graph::data::subgraph::features::IPFS_ON_ETHEREUM_CONTRACTS_FUNCTION_NAMES array for
@@ -469,6 +554,7 @@ impl WasmInstance {
         link!("json.toBigInt", json_to_big_int, ptr);
 
         link!("crypto.keccak256", crypto_keccak_256, ptr);
+        link!("crypto.random", crypto_random,);
 
         link!("bigInt.plus", big_int_plus, x_ptr, y_ptr);
         link!("bigInt.minus", big_int_minus, x_ptr, y_ptr);
@@ -503,6 +589,8 @@ impl WasmInstance {
         link!("dataSource.network", data_source_network,);
         link!("dataSource.context", data_source_context,);
 
+        link!("block.timestamp", block_timestamp,);
+
         link!("ens.nameByHash", ens_name_by_hash, ptr);
 
         link!("log.log", log_log, level, msg_ptr);
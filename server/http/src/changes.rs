@@ -0,0 +1,249 @@
+//! A feed of entity changes for a deployment, served as server-sent events
+//! on `/subgraphs/id/<ID>/changes` and `/subgraphs/name/<NAME>/changes`.
+//!
+//! The feed is built on the store event bus, which only records which
+//! entity types of a deployment changed, not the ids of the changed
+//! entities or whether they were created, updated, or removed. Clients get
+//! one event per changed entity type and block, and need to query the
+//! deployment to find out what actually changed.
+
+use std::collections::{BTreeSet, HashSet};
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use graph::cheap_clone::CheapClone;
+use graph::components::server::query::ServerError;
+use graph::components::store::SubscriptionManager;
+use graph::components::versions::ApiVersion;
+use graph::futures03::compat::Stream01CompatExt;
+use graph::futures03::stream::{self, StreamExt};
+use graph::http_body_util::combinators::UnsyncBoxBody;
+use graph::http_body_util::{BodyExt, Full, StreamBody};
+use graph::hyper::body::{Bytes, Frame};
+use graph::hyper::header::{ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_TYPE};
+use graph::hyper::{Request, Response};
+use graph::prelude::serde_json::{self, json};
+use graph::prelude::{
+    BlockNumber, DeploymentHash, EntityChange, QueryError, QueryStoreManager, QueryTarget,
+    SubgraphName, SubscriptionFilter,
+};
+use graph::schema::TypeKind;
+use graph::slog::{error, Logger};
+use graph::url::form_urlencoded;
+
+pub type ChangeFeedResponse = Response<UnsyncBoxBody<Bytes, Infallible>>;
+
+/// Format a single event. The id of the event is the block number so that
+/// clients send it back to us in the `Last-Event-ID` header when they
+/// reconnect
+fn sse_event(event: &str, entity_type: &str, block: BlockNumber) -> String {
+    let data = json!({ "entityType": entity_type, "block": block });
+    format!("id: {}\nevent: {}\ndata: {}\n\n", block, event, data)
+}
+
+fn error_response(status: u16, msg: impl Into<String>) -> ChangeFeedResponse {
+    let body = serde_json::to_string(&json!({ "error": msg.into() })).unwrap();
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Full::from(body).boxed_unsync())
+        .unwrap()
+}
+
+pub struct ChangeFeed {
+    logger: Logger,
+    store: Arc<dyn QueryStoreManager>,
+    subscriptions: Arc<dyn SubscriptionManager>,
+}
+
+impl ChangeFeed {
+    pub fn new(
+        logger: Logger,
+        store: Arc<dyn QueryStoreManager>,
+        subscriptions: Arc<dyn SubscriptionManager>,
+    ) -> Self {
+        ChangeFeed {
+            logger,
+            store,
+            subscriptions,
+        }
+    }
+
+    /// If `path` is the path of a change feed, return the deployment whose
+    /// changes should be sent
+    pub fn target(path: &str) -> Option<QueryTarget> {
+        let segments: Vec<_> = path.trim_start_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["subgraphs", "id", id, "changes"] => DeploymentHash::new(*id)
+                .ok()
+                .map(|id| QueryTarget::Deployment(id, ApiVersion::default())),
+            ["subgraphs", "name", name @ .., "changes"] if !name.is_empty() => {
+                SubgraphName::new(name.join("/"))
+                    .ok()
+                    .map(|name| QueryTarget::Name(name, ApiVersion::default()))
+            }
+            _ => None,
+        }
+    }
+
+    pub async fn call<T>(&self, target: QueryTarget, req: Request<T>) -> ChangeFeedResponse {
+        match self.handle_call(target, req).await {
+            Ok(response) => response,
+            Err(e @ ServerError::InternalError(_)) => {
+                error!(self.logger, "Change feed failed: {}", e);
+                error_response(500, e.to_string())
+            }
+            Err(e) => error_response(400, e.to_string()),
+        }
+    }
+
+    async fn handle_call<T>(
+        &self,
+        target: QueryTarget,
+        req: Request<T>,
+    ) -> Result<ChangeFeedResponse, ServerError> {
+        let names: BTreeSet<String> = req
+            .uri()
+            .query()
+            .and_then(|query| {
+                form_urlencoded::parse(query.as_bytes())
+                    .find(|(key, _)| key == "entities")
+                    .map(|(_, value)| value.into_owned())
+            })
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if names.is_empty() {
+            return Err(ServerError::ClientError(
+                "the `entities` parameter must list at least one entity type".to_string(),
+            ));
+        }
+        let last_block: Option<BlockNumber> = req
+            .headers()
+            .get("Last-Event-ID")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok());
+
+        // Use the primary so that the block we report is never behind the
+        // events we get from the store
+        let store = self
+            .store
+            .query_store(target, true)
+            .await
+            .map_err(QueryError::from)?;
+        let schema = store.input_schema().map_err(QueryError::from)?;
+        let state = store.deployment_state().await.map_err(QueryError::from)?;
+
+        let mut filters = BTreeSet::new();
+        for name in &names {
+            if schema.kind_of_declared_type(name) != Some(TypeKind::Object) {
+                return Err(ServerError::ClientError(format!(
+                    "`{}` is not an entity type of deployment {}",
+                    name, state.id
+                )));
+            }
+            let entity_type = schema
+                .entity_type(name.as_str())
+                .map_err(|e| ServerError::InternalError(e.to_string()))?;
+            filters.insert(SubscriptionFilter::Entities(state.id.clone(), entity_type));
+        }
+
+        // Subscribe before looking at where the client left off so that we
+        // can't miss changes in between
+        let events = self.subscriptions.subscribe(filters).compat();
+
+        // We can't replay the changes that happened since the block the
+        // client saw last. If the deployment has moved past it, tell the
+        // client which entity types it needs to resync
+        let resync = match last_block {
+            Some(last_block) if last_block < state.latest_block.number => names
+                .iter()
+                .map(|name| Bytes::from(sse_event("resync", name, state.latest_block.number)))
+                .collect(),
+            _ => vec![],
+        };
+
+        let id = state.id;
+        let changes = events.filter_map(move |event| {
+            let store = store.cheap_clone();
+            let id = id.clone();
+            let names = names.clone();
+            async move {
+                let event = event.ok()?;
+                let types: HashSet<_> = event
+                    .changes
+                    .iter()
+                    .filter_map(|change| match change {
+                        EntityChange::Data {
+                            subgraph_id,
+                            entity_type,
+                        } if subgraph_id == &id && names.contains(entity_type) => {
+                            Some(entity_type.as_str())
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                if types.is_empty() {
+                    return None;
+                }
+                // Events are sent after the block is committed, so the
+                // latest block is the block with the changes unless the
+                // deployment has moved on since then
+                let block = store.deployment_state().await.ok()?.latest_block.number;
+                let events: String = types
+                    .into_iter()
+                    .map(|entity_type| sse_event("change", entity_type, block))
+                    .collect();
+                Some(Bytes::from(events))
+            }
+        });
+
+        let body = stream::iter(resync)
+            .chain(changes)
+            .map(|bytes| Ok::<_, Infallible>(Frame::data(bytes)));
+
+        Ok(Response::builder()
+            .status(200)
+            .header(CONTENT_TYPE, "text/event-stream")
+            .header(CACHE_CONTROL, "no-cache")
+            .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+            .body(StreamBody::new(body).boxed_unsync())
+            .unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn change_feed_target() {
+        let hash = "QmXW3qvxV7zXnwRntpj7yoK8HZVtaraZ67uMqaLRvXdxha";
+        match ChangeFeed::target(&format!("/subgraphs/id/{}/changes", hash)) {
+            Some(QueryTarget::Deployment(id, _)) => assert_eq!(hash, id.as_str()),
+            target => panic!("unexpected target {:?}", target),
+        }
+        match ChangeFeed::target("/subgraphs/name/user/subgraph/changes") {
+            Some(QueryTarget::Name(name, _)) => assert_eq!("user/subgraph", name.as_str()),
+            target => panic!("unexpected target {:?}", target),
+        }
+        assert!(ChangeFeed::target("/subgraphs/name/changes").is_none());
+        assert!(ChangeFeed::target(&format!("/subgraphs/id/{}", hash)).is_none());
+        assert!(ChangeFeed::target(&format!("/subgraphs/id/{}/graphql", hash)).is_none());
+    }
+
+    #[test]
+    fn change_event() {
+        assert_eq!(
+            "id: 17\nevent: change\ndata: {\"block\":17,\"entityType\":\"Token\"}\n\n",
+            sse_event("change", "Token", 17)
+        );
+    }
+}
@@ -2,6 +2,7 @@ extern crate graph;
 extern crate graph_graphql;
 extern crate serde;
 
+mod changes;
 mod request;
 mod server;
 mod service;
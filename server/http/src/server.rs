@@ -3,16 +3,20 @@ use std::sync::Arc;
 use graph::anyhow;
 use graph::cheap_clone::CheapClone;
 use graph::components::server::server::{start, ServerHandle};
+use graph::components::store::SubscriptionManager;
+use graph::hyper::Method;
 use graph::log::factory::{ComponentLoggerConfig, ElasticComponentLoggerConfig};
 use graph::slog::info;
 
+use crate::changes::ChangeFeed;
 use crate::service::GraphQLService;
-use graph::prelude::{GraphQlRunner, Logger, LoggerFactory};
+use graph::prelude::{GraphQlRunner, Logger, LoggerFactory, QueryStoreManager};
 
 /// A GraphQL server based on Hyper.
 pub struct GraphQLServer<Q> {
     logger: Logger,
     graphql_runner: Arc<Q>,
+    change_feed: Option<Arc<ChangeFeed>>,
 }
 
 impl<Q: GraphQlRunner> GraphQLServer<Q> {
@@ -29,9 +33,21 @@ impl<Q: GraphQlRunner> GraphQLServer<Q> {
         GraphQLServer {
             logger,
             graphql_runner,
+            change_feed: None,
         }
     }
 
+    /// Also serve feeds of entity changes as server-sent events
+    pub fn with_change_feed(
+        mut self,
+        store: Arc<dyn QueryStoreManager>,
+        subscriptions: Arc<dyn SubscriptionManager>,
+    ) -> Self {
+        let feed = ChangeFeed::new(self.logger.clone(), store, subscriptions);
+        self.change_feed = Some(Arc::new(feed));
+        self
+    }
+
     pub async fn start(&self, port: u16, ws_port: u16) -> Result<ServerHandle, anyhow::Error> {
         let logger = self.logger.clone();
 
@@ -43,10 +59,21 @@ impl<Q: GraphQlRunner> GraphQLServer<Q> {
         let graphql_runner = self.graphql_runner.clone();
 
         let service = Arc::new(GraphQLService::new(logger.clone(), graphql_runner, ws_port));
+        let change_feed = self.change_feed.clone();
 
         start(logger, port, move |req| {
             let service = service.cheap_clone();
-            async move { Ok::<_, _>(service.cheap_clone().call(req).await) }
+            let change_feed = change_feed.clone();
+            async move {
+                // Change feeds stream their response and therefore can't
+                // go through the `GraphQLService`
+                if let Some(change_feed) = change_feed.filter(|_| req.method() == Method::GET) {
+                    if let Some(target) = ChangeFeed::target(req.uri().path()) {
+                        return Ok(change_feed.call(target, req).await);
+                    }
+                }
+                Ok::<_, _>(service.cheap_clone().call(req).await)
+            }
         })
         .await
     }
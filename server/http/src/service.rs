@@ -7,6 +7,7 @@ use graph::cheap_clone::CheapClone;
 use graph::components::graphql::GraphQlRunner;
 use graph::components::server::query::ServerResponse;
 use graph::components::server::query::ServerResult;
+use graph::components::server::query::StreamingServerResponse;
 use graph::components::versions::ApiVersion;
 use graph::data::query::QueryResult;
 use graph::data::subgraph::DeploymentHash;
@@ -43,6 +44,12 @@ fn client_error(msg: impl Into<String>) -> ServerResponse {
         .unwrap()
 }
 
+/// Box the body of `response` so that it has the same type as responses
+/// whose body is streamed
+fn boxed(response: ServerResponse) -> StreamingServerResponse {
+    response.map(|body| body.boxed_unsync())
+}
+
 /// A Hyper Service that serves GraphQL over a POST / endpoint.
 #[derive(Debug)]
 pub struct GraphQLService<Q> {
@@ -127,7 +134,7 @@ where
         &self,
         subgraph_name: String,
         request: Request<T>,
-    ) -> ServerResult {
+    ) -> Result<StreamingServerResponse, ServerError> {
         let version = self.resolve_api_version(&request)?;
         let subgraph_name = SubgraphName::new(subgraph_name.as_str()).map_err(|()| {
             ServerError::ClientError(format!("Invalid subgraph name {:?}", subgraph_name))
@@ -141,7 +148,7 @@ where
         &self,
         id: String,
         request: Request<T>,
-    ) -> ServerResult {
+    ) -> Result<StreamingServerResponse, ServerError> {
         let id = DeploymentHash::new(id)
             .map_err(|id| ServerError::ClientError(format!("Invalid subgraph id `{}`", id)))?;
         let version = self.resolve_api_version(&request)?;
@@ -154,7 +161,7 @@ where
         &self,
         target: QueryTarget,
         request: Request<T>,
-    ) -> ServerResult {
+    ) -> Result<StreamingServerResponse, ServerError> {
         let start = Instant::now();
         let trace = {
             !ENV_VARS.graphql.query_trace_token.is_empty()
@@ -195,7 +202,7 @@ where
             .metrics()
             .observe_query_execution(start.elapsed(), &result);
 
-        Ok(result.as_http_response())
+        Ok(result.into_http_response())
     }
 
     // Handles OPTIONS requests
@@ -263,7 +270,10 @@ where
         false
     }
 
-    async fn handle_call<T: Body>(&self, req: Request<T>) -> ServerResult {
+    async fn handle_call<T: Body>(
+        &self,
+        req: Request<T>,
+    ) -> Result<StreamingServerResponse, ServerError> {
         let method = req.method().clone();
 
         let path = req.uri().path().to_owned();
@@ -283,11 +293,11 @@ where
 
         if !less_strict_graphql_compliance {
             if method == Method::POST && (content_type.is_none()) {
-                return self.handle_requests_without_content_type();
+                return self.handle_requests_without_content_type().map(boxed);
             }
 
             if method == Method::POST && !self.has_request_body(&req) {
-                return self.handle_requests_without_body();
+                return self.handle_requests_without_body().map(boxed);
             }
         }
 
@@ -313,7 +323,7 @@ where
             .trim()
             .to_lowercase()
             .starts_with("mutation");
-        match (method, path_segments.as_slice()) {
+        let response = match (method, path_segments.as_slice()) {
             (Method::GET, [""]) => self.index().await,
             (Method::GET, &["subgraphs", "id", _, "graphql"])
             | (Method::GET, &["subgraphs", "name", .., "graphql"])
@@ -332,22 +342,27 @@ where
             }
 
             (Method::POST, &["subgraphs", "id", subgraph_id]) => {
-                self.handle_graphql_query_by_id(subgraph_id.to_owned(), req)
-                    .await
+                return self
+                    .handle_graphql_query_by_id(subgraph_id.to_owned(), req)
+                    .await;
             }
             (Method::OPTIONS, ["subgraphs", "id", _]) => self.handle_graphql_options(req),
             (Method::POST, path @ ["subgraphs", "name", ..]) => {
                 let subgraph_name = filter_and_join_segments(&path[2..]);
-                self.handle_graphql_query_by_name(subgraph_name, req).await
+                return self.handle_graphql_query_by_name(subgraph_name, req).await;
             }
 
             (Method::OPTIONS, ["subgraphs", "name", ..]) => self.handle_graphql_options(req),
 
             _ => self.handle_not_found(),
-        }
+        };
+        response.map(boxed)
     }
 
-    pub async fn call<T: Body + std::fmt::Debug>(&self, req: Request<T>) -> ServerResponse {
+    pub async fn call<T: Body + std::fmt::Debug>(
+        &self,
+        req: Request<T>,
+    ) -> StreamingServerResponse {
         // Returning Err here will prevent the client from receiving any response.
         // Instead, we generate a Response with an error code and return Ok
         let result = self.handle_call(req).await;
@@ -360,12 +375,14 @@ where
                 });
                 let response_str = serde_json::to_string(&response_obj).unwrap();
 
-                Response::builder()
-                    .status(400)
-                    .header(CONTENT_TYPE, "application/json")
-                    .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-                    .body(Full::from(response_str))
-                    .unwrap()
+                boxed(
+                    Response::builder()
+                        .status(400)
+                        .header(CONTENT_TYPE, "application/json")
+                        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+                        .body(Full::from(response_str))
+                        .unwrap(),
+                )
             }
             Err(err @ ServerError::QueryError(_)) => {
                 error!(self.logger, "GraphQLService call failed: {}", err);
@@ -375,22 +392,26 @@ where
                 });
                 let response_str = serde_json::to_string(&response_obj).unwrap();
 
-                Response::builder()
-                    .status(400)
-                    .header(CONTENT_TYPE, "application/json")
-                    .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-                    .body(Full::from(response_str))
-                    .unwrap()
+                boxed(
+                    Response::builder()
+                        .status(400)
+                        .header(CONTENT_TYPE, "application/json")
+                        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+                        .body(Full::from(response_str))
+                        .unwrap(),
+                )
             }
             Err(err @ ServerError::InternalError(_)) => {
                 error!(self.logger, "GraphQLService call failed: {}", err);
 
-                Response::builder()
-                    .status(500)
-                    .header(CONTENT_TYPE, "text/plain; charset=utf-8")
-                    .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-                    .body(Full::from(format!("Internal server error: {}", err)))
-                    .unwrap()
+                boxed(
+                    Response::builder()
+                        .status(500)
+                        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+                        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+                        .body(Full::from(format!("Internal server error: {}", err)))
+                        .unwrap(),
+                )
             }
         }
     }
@@ -484,7 +505,7 @@ mod tests {
         let content_type_header = response.headers().get(CONTENT_TYPE).unwrap();
         assert_eq!(content_type_header, "application/json");
 
-        let body_bytes = response.body().clone().collect().await.unwrap().to_bytes();
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
         let json: serde_json::Result<serde_json::Value> =
             serde_json::from_str(String::from_utf8(body_bytes.to_vec()).unwrap().as_str());
 
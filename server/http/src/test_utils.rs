@@ -1,11 +1,16 @@
-use graph::http_body_util::{BodyExt, Full};
-use graph::hyper::{body::Bytes, header::ACCESS_CONTROL_ALLOW_ORIGIN, Response, StatusCode};
+use std::fmt::Debug;
+
+use graph::http_body_util::BodyExt;
+use graph::hyper::{body::Body, header::ACCESS_CONTROL_ALLOW_ORIGIN, Response, StatusCode};
 use graph::prelude::serde_json;
 
 /// Asserts that the response is a successful GraphQL response; returns its `"data"` field.
-pub async fn assert_successful_response(
-    response: Response<Full<Bytes>>,
-) -> serde_json::Map<String, serde_json::Value> {
+pub async fn assert_successful_response<B: Body>(
+    response: Response<B>,
+) -> serde_json::Map<String, serde_json::Value>
+where
+    B::Error: Debug,
+{
     assert_expected_headers(&response);
     let body = response.collect().await.unwrap().to_bytes();
     let json: serde_json::Value =
@@ -21,11 +26,14 @@ pub async fn assert_successful_response(
 }
 
 /// Asserts that the response is a failed GraphQL response; returns its `"errors"` field.
-pub async fn assert_error_response(
-    response: Response<Full<Bytes>>,
+pub async fn assert_error_response<B: Body>(
+    response: Response<B>,
     expected_status: StatusCode,
     graphql_response: bool,
-) -> Vec<serde_json::Value> {
+) -> Vec<serde_json::Value>
+where
+    B::Error: Debug,
+{
     assert_eq!(response.status(), expected_status);
     assert_expected_headers(&response);
     let body = response.collect().await.unwrap().to_bytes().to_vec();
@@ -49,7 +57,7 @@ pub async fn assert_error_response(
 }
 
 #[track_caller]
-pub fn assert_expected_headers(response: &Response<Full<Bytes>>) {
+pub fn assert_expected_headers<B>(response: &Response<B>) {
     assert_eq!(
         response
             .headers()
@@ -20,6 +20,36 @@ async fn generates_valid_json_for_an_empty_result() {
     assert!(data.is_empty());
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn streams_the_same_json_as_buffered_responses() {
+    use graph::http_body_util::BodyExt;
+
+    let results = || {
+        let musicians: Vec<_> = (0..20_000)
+            .map(|i| object! { id: format!("m{}", i), name: "Jimi" })
+            .collect();
+        let mut results = QueryResults::from(Object::from_iter([(
+            "musicians".into(),
+            r::Value::List(musicians),
+        )]));
+        results.append(
+            Arc::new(QueryResult::from(QueryExecutionError::Timeout)),
+            Default::default(),
+        );
+        results
+    };
+
+    let buffered = results().as_http_response();
+    let buffered = buffered.into_body().collect().await.unwrap().to_bytes();
+
+    let streamed = results().as_streaming_http_response();
+    test_utils::assert_expected_headers(&streamed);
+    let streamed = streamed.into_body().collect().await.unwrap().to_bytes();
+
+    assert!(buffered.len() > 64 * 1024);
+    assert_eq!(buffered, streamed);
+}
+
 #[test]
 fn canonical_serialization() {
     macro_rules! assert_resp {
@@ -91,6 +91,19 @@ where
             .unwrap()
     }
 
+    /// Report the state of the connection pools of all shards as JSON
+    fn handle_pool_status(&self) -> ServerResult {
+        let status = self.store.pool_status();
+        let body = serde_json::to_string(&status)
+            .map_err(|e| ServerError::InternalError(e.to_string()))?;
+        Ok(Response::builder()
+            .status(200)
+            .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Full::from(body))
+            .unwrap())
+    }
+
     fn handle_graphiql() -> ServerResponse {
         Self::serve_file(Self::graphiql_html(), "text/html")
     }
@@ -231,6 +244,8 @@ where
 
             (Method::GET, ["explorer", rest @ ..]) => self.explorer.handle(&self.logger, rest),
 
+            (Method::GET, ["pool-status"]) => self.handle_pool_status(),
+
             _ => Ok(Self::handle_not_found()),
         }
     }
@@ -0,0 +1 @@
+drop table subgraphs.prune_checkpoint;
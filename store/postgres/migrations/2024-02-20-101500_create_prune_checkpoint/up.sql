@@ -0,0 +1,11 @@
+-- Tables that an ongoing prune of a deployment has already finished. If
+-- pruning is interrupted, a later run with the same earliest block skips
+-- these tables
+create table subgraphs.prune_checkpoint(
+  deployment     int not null
+                 references subgraphs.subgraph_deployment
+                 on delete cascade,
+  earliest_block int not null,
+  table_name     text not null,
+  primary key(deployment, earliest_block, table_name)
+);
@@ -0,0 +1,3 @@
+-- Why a deployment was paused; only set while the deployment is paused
+alter table subgraphs.subgraph_deployment_assignment
+  add column paused_reason text;
@@ -0,0 +1,4 @@
+-- The block time at which data points of timeseries with a retention
+-- window were last removed; null if that never happened
+alter table subgraphs.subgraph_deployment
+  add column timeseries_pruned_at timestamptz;
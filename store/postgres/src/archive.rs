@@ -0,0 +1,400 @@
+//! Export a deployment to, and import it from, a portable archive. The
+//! archive only relies on the GraphQL schema of the deployment and not on
+//! how the relational layout stores its data, and can therefore be used to
+//! move deployments between clusters and across graph-node versions.
+//!
+//! An archive is a file with one JSON object per line:
+//!
+//! - the first line is a [`Header`] with the deployment metadata, including
+//!   the raw GraphQL schema and manifest
+//! - for each entity type, a line `{"table": "<entity type>"}` followed by
+//!   one line for every version of every entity of that type, with the
+//!   `vid`, the block range (or `block` for immutable entity types), the
+//!   causality region and the entity data keyed by GraphQL field name
+//! - a line `"data_sources"` followed by one line for every dynamic data
+//!   source
+//! - a line `{"end": {"rows": <n>}}` with the total number of rows in the
+//!   archive, which makes it possible to detect truncated archives
+//!
+//! Block ranges are written as `[lower, upper]` with `null` for an open
+//! upper end, so that importing an archive recreates them exactly.
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+use diesel::{sql_query, Connection, PgConnection, RunQueryDsl};
+use graph::data::subgraph::schema::{DeploymentCreate, SubgraphManifestEntity};
+use graph::prelude::{anyhow, serde_json, BlockNumber, BlockPtr, DeploymentHash, StoreError};
+use graph::schema::InputSchema;
+use graph::semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::dynds::DataSourcesTable;
+use crate::primary::Site;
+use crate::relational::{Layout, Table};
+use crate::{deployment, detail};
+
+/// Identifies a file as a deployment archive
+const FORMAT: &str = "graph-node-deployment-archive";
+
+/// The version of the archive format; it needs to be increased whenever
+/// the format changes in a way that older versions can not read
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct ArchiveBlock {
+    number: BlockNumber,
+    hash: String,
+}
+
+impl ArchiveBlock {
+    fn from_ptr(ptr: &BlockPtr) -> Self {
+        ArchiveBlock {
+            number: ptr.number,
+            hash: ptr.hash_hex(),
+        }
+    }
+
+    fn to_ptr(&self) -> Result<BlockPtr, StoreError> {
+        Ok(BlockPtr::try_from((
+            self.hash.as_str(),
+            self.number as i64,
+        ))?)
+    }
+}
+
+/// The first line of an archive with the metadata of the deployment
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Header {
+    format: String,
+    version: u32,
+    deployment: String,
+    pub(crate) network: String,
+    spec_version: String,
+    description: Option<String>,
+    repository: Option<String>,
+    features: Vec<String>,
+    schema: String,
+    raw_yaml: Option<String>,
+    entities_with_causality_region: Vec<String>,
+    history_blocks: BlockNumber,
+    start_block: Option<ArchiveBlock>,
+    latest_block: Option<ArchiveBlock>,
+    earliest_block: BlockNumber,
+    synced: bool,
+    debug_fork: Option<String>,
+}
+
+impl Header {
+    pub(crate) fn deployment(&self) -> Result<DeploymentHash, StoreError> {
+        DeploymentHash::new(self.deployment.clone()).map_err(|id| {
+            StoreError::Unknown(anyhow!("archive contains invalid deployment id `{}`", id))
+        })
+    }
+
+    pub(crate) fn schema(&self) -> Result<InputSchema, StoreError> {
+        let spec_version = Version::parse(&self.spec_version).map_err(|e| {
+            anyhow!(
+                "archive contains invalid spec version `{}`: {}",
+                self.spec_version,
+                e
+            )
+        })?;
+        Ok(InputSchema::parse(
+            &spec_version,
+            &self.schema,
+            self.deployment()?,
+        )?)
+    }
+
+    /// The data needed to create the deployment. The deployment is not
+    /// grafted since the archive contains all its data
+    pub(crate) fn deployment_create(
+        &self,
+        schema: &InputSchema,
+    ) -> Result<DeploymentCreate, StoreError> {
+        let entities_with_causality_region = self
+            .entities_with_causality_region
+            .iter()
+            .map(|name| schema.entity_type(name))
+            .collect::<Result<_, _>>()?;
+        let manifest = SubgraphManifestEntity {
+            spec_version: self.spec_version.clone(),
+            description: self.description.clone(),
+            repository: self.repository.clone(),
+            features: self.features.clone(),
+            schema: self.schema.clone(),
+            raw_yaml: self.raw_yaml.clone(),
+            entities_with_causality_region,
+            history_blocks: self.history_blocks,
+        };
+        let debug_fork = self
+            .debug_fork
+            .as_ref()
+            .map(|id| {
+                DeploymentHash::new(id.clone())
+                    .map_err(|id| anyhow!("archive contains invalid debug fork `{}`", id))
+            })
+            .transpose()?;
+        Ok(DeploymentCreate {
+            manifest,
+            start_block: self
+                .start_block
+                .as_ref()
+                .map(ArchiveBlock::to_ptr)
+                .transpose()?,
+            graft_base: None,
+            graft_block: None,
+            debug_fork,
+            history_blocks_override: None,
+        })
+    }
+}
+
+/// The lines that separate the rows of different tables
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Marker {
+    Table(String),
+    DataSources,
+    End { rows: usize },
+}
+
+fn write_line(out: &mut dyn Write, line: &str) -> Result<(), StoreError> {
+    writeln!(out, "{}", line).map_err(|e| StoreError::Unknown(e.into()))
+}
+
+fn read_line(input: &mut dyn BufRead) -> Result<Option<String>, StoreError> {
+    let mut line = String::new();
+    match input.read_line(&mut line) {
+        Ok(0) => Ok(None),
+        Ok(_) => Ok(Some(line)),
+        Err(e) => Err(StoreError::Unknown(e.into())),
+    }
+}
+
+/// Write the deployment `site` as an archive to `out`, reading `batch_size`
+/// rows at a time, and return the number of rows that were written. All
+/// data is read in one transaction so that the archive is consistent even
+/// if the deployment is being indexed at the same time
+pub(crate) fn export(
+    conn: &mut PgConnection,
+    site: &Site,
+    layout: &Layout,
+    out: &mut dyn Write,
+    batch_size: usize,
+) -> Result<usize, StoreError> {
+    if !site.schema_version.private_data_sources() {
+        return Err(StoreError::Unknown(anyhow!(
+            "can not export deployment {} because it uses an old storage scheme for data sources",
+            site.deployment
+        )));
+    }
+    let batch_size = batch_size.max(1) as i64;
+
+    conn.build_transaction()
+        .read_only()
+        .repeatable_read()
+        .run(|conn| {
+            let deployment = detail::deployment_entity(conn, site, &layout.input_schema)?;
+            if deployment.failed {
+                return Err(StoreError::Unknown(anyhow!(
+                    "can not export deployment {} because it has failed",
+                    site.deployment
+                )));
+            }
+            let manifest = deployment.manifest;
+            let header = Header {
+                format: FORMAT.to_string(),
+                version: VERSION,
+                deployment: site.deployment.to_string(),
+                network: site.network.clone(),
+                spec_version: manifest.spec_version,
+                description: manifest.description,
+                repository: manifest.repository,
+                features: manifest.features,
+                schema: manifest.schema,
+                raw_yaml: manifest.raw_yaml,
+                entities_with_causality_region: manifest
+                    .entities_with_causality_region
+                    .iter()
+                    .map(|entity_type| entity_type.typename().to_string())
+                    .collect(),
+                history_blocks: manifest.history_blocks,
+                start_block: deployment.start_block.as_ref().map(ArchiveBlock::from_ptr),
+                latest_block: deployment.latest_block.as_ref().map(ArchiveBlock::from_ptr),
+                earliest_block: deployment.earliest_block_number,
+                synced: deployment.synced,
+                debug_fork: deployment.debug_fork.map(|id| id.to_string()),
+            };
+            write_line(out, &serde_json::to_string(&header)?)?;
+
+            let mut count = 0;
+            let mut tables: Vec<_> = layout.tables.values().collect();
+            tables.sort_by_key(|table| table.object.as_str());
+            for table in tables {
+                let marker = Marker::Table(table.object.as_str().to_string());
+                write_line(out, &serde_json::to_string(&marker)?)?;
+                count += write_pages(out, |after| table.archive_page(conn, after, batch_size))?;
+            }
+
+            let data_sources = DataSourcesTable::new(site.namespace.clone());
+            write_line(out, &serde_json::to_string(&Marker::DataSources)?)?;
+            count += write_pages(out, |after| {
+                data_sources.archive_page(conn, after, batch_size)
+            })?;
+
+            write_line(out, &serde_json::to_string(&Marker::End { rows: count })?)?;
+            Ok(count)
+        })
+}
+
+fn write_pages<F>(out: &mut dyn Write, mut page: F) -> Result<usize, StoreError>
+where
+    F: FnMut(i64) -> Result<Vec<(i64, String)>, StoreError>,
+{
+    let mut count = 0;
+    let mut after = -1;
+    loop {
+        let rows = page(after)?;
+        let Some((last, _)) = rows.last() else {
+            return Ok(count);
+        };
+        after = *last;
+        count += rows.len();
+        for (_, row) in rows {
+            write_line(out, &row)?;
+        }
+    }
+}
+
+/// Read the header of an archive and check that we understand its format
+pub(crate) fn read_header(input: &mut dyn BufRead) -> Result<Header, StoreError> {
+    let line = read_line(input)?.ok_or_else(|| anyhow!("the archive is empty"))?;
+    let header: Header = serde_json::from_str(&line)
+        .map_err(|e| anyhow!("the file is not a deployment archive: {}", e))?;
+    if header.format != FORMAT {
+        return Err(StoreError::Unknown(anyhow!(
+            "the file is not a deployment archive but has format `{}`",
+            header.format
+        )));
+    }
+    if header.version > VERSION {
+        return Err(StoreError::Unknown(anyhow!(
+            "the archive has format version {} but only versions up to {} are supported",
+            header.version,
+            VERSION
+        )));
+    }
+    Ok(header)
+}
+
+enum Target<'a> {
+    Table(&'a Arc<Table>),
+    DataSources(&'a DataSourcesTable),
+}
+
+impl<'a> Target<'a> {
+    fn restore(&self, conn: &mut PgConnection, rows: &mut Vec<String>) -> Result<(), StoreError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let json = format!("[{}]", rows.join(","));
+        match self {
+            Target::Table(table) => table.restore_page(conn, &json)?,
+            Target::DataSources(data_sources) => data_sources.restore_page(conn, &json)?,
+        };
+        rows.clear();
+        Ok(())
+    }
+}
+
+/// Load the rows from `input`, which must be positioned right after the
+/// header, into the freshly created deployment `site` and set its metadata
+/// from `header`. Rows are inserted `batch_size` at a time, but all in one
+/// transaction
+pub(crate) fn import(
+    conn: &mut PgConnection,
+    site: &Site,
+    layout: &Layout,
+    header: &Header,
+    input: &mut dyn BufRead,
+    batch_size: usize,
+) -> Result<usize, StoreError> {
+    let batch_size = batch_size.max(1);
+    let data_sources = DataSourcesTable::new(site.namespace.clone());
+
+    conn.transaction(|conn| {
+        let mut target = None;
+        let mut rows = Vec::new();
+        let mut count = 0;
+        let expected = loop {
+            let line = read_line(input)?.ok_or_else(|| {
+                anyhow!("the archive is truncated; it does not have an end marker")
+            })?;
+            let value: serde_json::Value = serde_json::from_str(&line)?;
+            if value.get("vid").is_some() {
+                let current = target
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("the archive contains rows before the first table"))?;
+                rows.push(line.trim_end().to_string());
+                count += 1;
+                if rows.len() >= batch_size {
+                    current.restore(conn, &mut rows)?;
+                }
+                continue;
+            }
+
+            if let Some(target) = &target {
+                target.restore(conn, &mut rows)?;
+            }
+            match serde_json::from_value(value)? {
+                Marker::Table(name) => {
+                    let table = layout
+                        .tables
+                        .values()
+                        .find(|table| table.object.as_str() == name)
+                        .ok_or_else(|| {
+                            anyhow!("the archive contains unknown entity type `{}`", name)
+                        })?;
+                    target = Some(Target::Table(table));
+                }
+                Marker::DataSources => target = Some(Target::DataSources(&data_sources)),
+                Marker::End { rows } => break rows,
+            }
+        };
+        if count != expected {
+            return Err(StoreError::Unknown(anyhow!(
+                "the archive should contain {} rows but contains {}",
+                expected,
+                count
+            )));
+        }
+
+        // Inserting rows with an explicit `vid` does not advance the
+        // sequences for the `vid`, and we need to do that by hand so that
+        // indexing can continue
+        let qualified_names = layout
+            .tables
+            .values()
+            .map(|table| table.qualified_name.as_str())
+            .chain(std::iter::once(data_sources.qualified_name()));
+        for qualified_name in qualified_names {
+            let query = format!(
+                "select setval(pg_get_serial_sequence('{qualified_name}', 'vid'), max(vid)) \
+                   from {qualified_name} \
+                 having max(vid) is not null"
+            );
+            sql_query(query).execute(conn)?;
+        }
+
+        if let Some(latest_block) = &header.latest_block {
+            deployment::forward_block_ptr(conn, &site.deployment, &latest_block.to_ptr()?)?;
+        }
+        deployment::set_earliest_block(conn, site, header.earliest_block)?;
+        deployment::set_entity_count(conn, site, &layout.count_query)?;
+        if header.synced {
+            deployment::set_synced(conn, &site.deployment)?;
+        }
+        Ok(count)
+    })
+}
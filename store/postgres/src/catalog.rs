@@ -1,6 +1,6 @@
 use diesel::sql_types::{Bool, Integer};
 use diesel::{connection::SimpleConnection, prelude::RunQueryDsl, select};
-use diesel::{insert_into, OptionalExtension};
+use diesel::{delete, insert_into, OptionalExtension};
 use diesel::{pg::PgConnection, sql_query};
 use diesel::{
     sql_types::{Array, BigInt, Double, Nullable, Text},
@@ -24,6 +24,7 @@ use graph::{
 
 use crate::connection_pool::ForeignServer;
 use crate::{
+    block_range::{BLOCK_COLUMN, BLOCK_RANGE_COLUMN},
     primary::{Namespace, Site, NAMESPACE_PUBLIC},
     relational::SqlName,
 };
@@ -105,6 +106,14 @@ table! {
     }
 }
 
+table! {
+    subgraphs.prune_checkpoint(deployment, earliest_block, table_name) {
+        deployment -> Integer,
+        earliest_block -> Integer,
+        table_name -> Text,
+    }
+}
+
 table! {
     __diesel_schema_migrations(version) {
         version -> Text,
@@ -509,6 +518,78 @@ pub fn set_last_pruned_block(
     Ok(())
 }
 
+/// Return the earliest block of an unfinished prune of `site`, if there
+/// is one, i.e., of a prune that was interrupted after it had finished
+/// some but not all of its tables
+pub fn prune_checkpoint_block(
+    conn: &mut PgConnection,
+    site: &Site,
+) -> Result<Option<BlockNumber>, StoreError> {
+    use diesel::dsl::max;
+    use prune_checkpoint as pc;
+
+    pc::table
+        .filter(pc::deployment.eq(site.id))
+        .select(max(pc::earliest_block))
+        .get_result::<Option<BlockNumber>>(conn)
+        .map_err(StoreError::from)
+}
+
+/// Return the names of the tables that an earlier prune of `site` to
+/// `earliest_block` has already finished. Checkpoints for any other
+/// earliest block are discarded since they can not be resumed anymore
+pub fn pruned_tables(
+    conn: &mut PgConnection,
+    site: &Site,
+    earliest_block: BlockNumber,
+) -> Result<HashSet<String>, StoreError> {
+    use prune_checkpoint as pc;
+
+    delete(
+        pc::table
+            .filter(pc::deployment.eq(site.id))
+            .filter(pc::earliest_block.ne(earliest_block)),
+    )
+    .execute(conn)?;
+
+    let names = pc::table
+        .filter(pc::deployment.eq(site.id))
+        .select(pc::table_name)
+        .get_results::<String>(conn)?
+        .into_iter()
+        .collect();
+    Ok(names)
+}
+
+/// Record that pruning `site` to `earliest_block` has finished the table
+/// `table_name`
+pub fn checkpoint_pruned_table(
+    conn: &mut PgConnection,
+    site: &Site,
+    table_name: &SqlName,
+    earliest_block: BlockNumber,
+) -> Result<(), StoreError> {
+    use prune_checkpoint as pc;
+
+    insert_into(pc::table)
+        .values((
+            pc::deployment.eq(site.id),
+            pc::earliest_block.eq(earliest_block),
+            pc::table_name.eq(table_name.as_str()),
+        ))
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Remove the checkpoint for pruning `site` once pruning has finished
+pub fn clear_prune_checkpoint(conn: &mut PgConnection, site: &Site) -> Result<(), StoreError> {
+    use prune_checkpoint as pc;
+
+    delete(pc::table.filter(pc::deployment.eq(site.id))).execute(conn)?;
+    Ok(())
+}
+
 pub(crate) mod table_schema {
     use super::*;
 
@@ -765,9 +846,155 @@ pub fn stats(conn: &mut PgConnection, site: &Site) -> Result<Vec<VersionStats>,
     Ok(stats.into_iter().map(|s| s.into()).collect())
 }
 
+/// The size of a table and an estimate of how many distinct entities it
+/// contains
+#[derive(QueryableByName)]
+pub struct TableSize {
+    #[diesel(sql_type = Text)]
+    pub namespace: String,
+    #[diesel(sql_type = Text)]
+    pub table_name: String,
+    #[diesel(sql_type = BigInt)]
+    pub entities: i64,
+    /// The size of the table, its indexes and its TOAST table
+    #[diesel(sql_type = BigInt)]
+    pub total_bytes: i64,
+}
+
+/// Return the sizes of all tables in the given namespaces. Like `stats`,
+/// this uses Postgres' statistics to estimate how many entities a table
+/// contains. Tables that have never been analyzed have no statistics for
+/// the `id` column, and for those we use the number of rows instead
+pub fn table_sizes(
+    conn: &mut PgConnection,
+    namespaces: &[&str],
+) -> Result<Vec<TableSize>, StoreError> {
+    const QUERY: &str = "select n.nspname as namespace,
+                                c.relname as table_name,
+                                case when s.n_distinct is null then greatest(c.reltuples, 0)::int8
+                                     when s.n_distinct < 0 then (- s.n_distinct * c.reltuples)::int8
+                                     else s.n_distinct::int8
+                                 end as entities,
+                                pg_total_relation_size(c.oid)::int8 as total_bytes
+                           from pg_namespace n
+                                join pg_class c on c.relnamespace = n.oid
+                                left outer join pg_stats s
+                                  on (s.schemaname = n.nspname
+                                  and s.tablename = c.relname
+                                  and s.attname = 'id')
+                          where n.nspname = any($1)
+                            and c.relkind = 'r'
+                          order by n.nspname, c.relname";
+
+    sql_query(QUERY)
+        .bind::<Array<Text>, _>(namespaces)
+        .load::<TableSize>(conn)
+        .map_err(StoreError::from)
+}
+
+/// a block range that overlaps the blocks `since..=until`. Versions of
+/// immutable entities are valid from the block at which they were created
+/// onwards. Unlike `stats`, this does not use estimates but actually counts
+/// rows which can take a long time for large tables
+pub fn versions_in_range(
+    conn: &mut PgConnection,
+    site: &Site,
+    since: BlockNumber,
+    until: BlockNumber,
+) -> Result<Vec<(String, i64)>, StoreError> {
+    #[derive(QueryableByName)]
+    struct BlockColumn {
+        #[diesel(sql_type = Text)]
+        table_name: String,
+        #[diesel(sql_type = Text)]
+        column_name: String,
+    }
+
+    #[derive(QueryableByName)]
+    struct Count {
+        #[diesel(sql_type = BigInt)]
+        count: i64,
+    }
+
+    let query = "select table_name::text, column_name::text
+                   from information_schema.columns
+                  where table_schema = $1
+                    and column_name in ($2, $3)
+                  order by table_name";
+    let columns = sql_query(query)
+        .bind::<Text, _>(site.namespace.as_str())
+        .bind::<Text, _>(BLOCK_RANGE_COLUMN)
+        .bind::<Text, _>(BLOCK_COLUMN)
+        .load::<BlockColumn>(conn)?;
+
+    let mut counts = Vec::with_capacity(columns.len());
+    for BlockColumn {
+        table_name,
+        column_name,
+    } in columns
+    {
+        let cond = if column_name == BLOCK_RANGE_COLUMN {
+            format!("{BLOCK_RANGE_COLUMN} && int4range($1, $2, '[]')")
+        } else {
+            format!("\"{BLOCK_COLUMN}\" <= $2")
+        };
+        let query = format!(
+            "select count(*) as count from \"{}\".\"{}\" where {cond}",
+            site.namespace, table_name
+        );
+        let count = sql_query(query)
+            .bind::<Integer, _>(since)
+            .bind::<Integer, _>(until)
+            .get_result::<Count>(conn)?
+            .count;
+        counts.push((table_name, count));
+    }
+    Ok(counts)
+}
+
 /// Return by how much the slowest replica connected to the database `conn`
 /// is lagging. The returned value has millisecond precision. If the
 /// database has no replicas, return `0`
+/// Set the `statement_timeout` for the rest of the current transaction to
+/// `timeout`. If `timeout` is `None`, leave the timeout unchanged
+pub(crate) fn set_local_statement_timeout(
+    conn: &mut PgConnection,
+    timeout: Option<Duration>,
+) -> Result<(), StoreError> {
+    if let Some(timeout) = timeout {
+        conn.batch_execute(&format!(
+            "set local statement_timeout = {}",
+            timeout.as_millis()
+        ))?;
+    }
+    Ok(())
+}
+
+/// Run `f` with the `statement_timeout` of `conn` set to `timeout`, and
+/// reset it to the default for the connection afterwards. If `timeout` is
+/// `None`, just run `f`
+pub(crate) fn with_statement_timeout<T, E, F>(
+    conn: &mut PgConnection,
+    timeout: Option<Duration>,
+    f: F,
+) -> Result<T, E>
+where
+    E: From<diesel::result::Error>,
+    F: FnOnce(&mut PgConnection) -> Result<T, E>,
+{
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return f(conn),
+    };
+
+    conn.batch_execute(&format!("set statement_timeout = {}", timeout.as_millis()))?;
+    let res = f(conn);
+    let reset = conn.batch_execute("reset statement_timeout");
+    let res = res?;
+    reset?;
+    Ok(res)
+}
+
 pub(crate) fn replication_lag(conn: &mut PgConnection) -> Result<Duration, StoreError> {
     #[derive(Queryable, QueryableByName)]
     struct Lag {
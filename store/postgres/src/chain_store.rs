@@ -24,9 +24,9 @@ use graph::blockchain::{Block, BlockHash, ChainIdentifier};
 use graph::cheap_clone::CheapClone;
 use graph::prelude::web3::types::H256;
 use graph::prelude::{
-    async_trait, serde_json as json, transaction_receipt::LightTransactionReceipt, BlockNumber,
-    BlockPtr, CachedEthereumCall, CancelableError, ChainStore as ChainStoreTrait, Error,
-    EthereumCallCache, StoreError,
+    anyhow, async_trait, serde_json as json, transaction_receipt::LightTransactionReceipt,
+    BlockNumber, BlockPtr, CachedEthereumCall, CancelableError, ChainStore as ChainStoreTrait,
+    Error, EthereumCallCache, StoreError,
 };
 use graph::{constraint_violation, ensure};
 
@@ -1077,6 +1077,33 @@ mod data {
             }
         }
 
+        /// Delete all blocks with a number greater than `block`
+        pub(super) fn delete_blocks_after(
+            &self,
+            conn: &mut PgConnection,
+            chain: &str,
+            block: i64,
+        ) -> Result<usize, Error> {
+            match self {
+                Storage::Shared => {
+                    use public::ethereum_blocks as b;
+
+                    diesel::delete(b::table)
+                        .filter(b::network_name.eq(chain))
+                        .filter(b::number.gt(block))
+                        .execute(conn)
+                        .map_err(Error::from)
+                }
+                Storage::Private(Schema { blocks, .. }) => {
+                    let query = format!("delete from {} where number > $1", blocks.qname);
+                    sql_query(query)
+                        .bind::<BigInt, _>(block)
+                        .execute(conn)
+                        .map_err(Error::from)
+                }
+            }
+        }
+
         pub(super) fn delete_blocks_by_hash(
             &self,
             conn: &mut PgConnection,
@@ -1782,12 +1809,103 @@ impl ChainStore {
         self.recent_blocks_cache.blocks()
     }
 
+    /// Check whether `block` is `head` or one of its ancestors. Once a
+    /// block is final, other blocks with the same number are removed from
+    /// the cache, and we therefore only need to walk back from `head` when
+    /// the cache has several blocks with the number of `block`. Return an
+    /// error if that walk can not reach the number of `block` because some
+    /// of the blocks in between are missing from the chain store
+    pub async fn is_ancestor(&self, head: &BlockPtr, block: &BlockPtr) -> Result<bool, StoreError> {
+        if block.number >= head.number {
+            return Ok(block == head);
+        }
+
+        let storage = self.storage.clone();
+        let chain = self.chain.clone();
+        let head = head.clone();
+        let block = block.clone();
+        self.pool
+            .with_conn(move |conn, _| {
+                let hashes = storage
+                    .block_hashes_by_block_number(conn, &chain, block.number)
+                    .map_err(StoreError::from)?;
+                if !hashes.contains(&block.hash) {
+                    return Ok(false);
+                }
+                if hashes.len() == 1 {
+                    return Ok(true);
+                }
+                let offset = head.number - block.number;
+                match storage
+                    .ancestor_block(conn, head.clone(), offset)
+                    .map_err(StoreError::from)?
+                {
+                    Some((_, ptr)) => Ok(ptr == block),
+                    None => Err(StoreError::Unknown(anyhow!(
+                        "can not check whether block {} is an ancestor of block {} \
+                         since the chain store is missing some of the blocks in between",
+                        block,
+                        head
+                    ))
+                    .into()),
+                }
+            })
+            .await
+    }
+
     pub fn delete_blocks(&self, block_hashes: &[&H256]) -> Result<usize, Error> {
         let mut conn = self.get_conn()?;
         self.storage
             .delete_blocks_by_hash(&mut conn, &self.chain, block_hashes)
     }
 
+    /// Delete all blocks above `block` and make the block with number
+    /// `block` the chain head. That block must be in the store, and it
+    /// must be the only block with that number. Any firehose cursor is
+    /// cleared since it points past the new chain head. Return the number
+    /// of blocks that were deleted
+    pub fn truncate_to(&self, block: BlockNumber) -> Result<usize, Error> {
+        use public::ethereum_networks as n;
+
+        let mut conn = self.get_conn()?;
+        let deleted = conn.transaction(|conn| -> Result<usize, Error> {
+            let hashes = self
+                .storage
+                .block_hashes_by_block_number(conn, &self.chain, block)?;
+            let hash = match hashes.as_slice() {
+                [hash] => hash.hash_hex(),
+                [] => anyhow::bail!(
+                    "there is no block {} for {} in the store",
+                    block,
+                    self.chain
+                ),
+                _ => anyhow::bail!(
+                    "there are {} blocks with number {} for {}; remove the duplicates \
+                     with `graphman chain check-blocks {} by-number {} --delete-duplicates` first",
+                    hashes.len(),
+                    block,
+                    self.chain,
+                    self.chain,
+                    block
+                ),
+            };
+
+            let deleted = self
+                .storage
+                .delete_blocks_after(conn, &self.chain, block as i64)?;
+            update(n::table.filter(n::name.eq(&self.chain)))
+                .set((
+                    n::head_block_hash.eq(&hash),
+                    n::head_block_number.eq(block as i64),
+                    n::head_block_cursor.eq::<Option<String>>(None),
+                ))
+                .execute(conn)?;
+            Ok(deleted)
+        })?;
+        self.recent_blocks_cache.clear();
+        Ok(deleted)
+    }
+
     pub fn cleanup_shallow_blocks(&self, lowest_block: i32) -> Result<(), StoreError> {
         let mut conn = self.get_conn()?;
         self.storage
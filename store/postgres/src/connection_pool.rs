@@ -10,6 +10,7 @@ use diesel_migrations::{EmbeddedMigrations, HarnessWithOutput};
 use graph::cheap_clone::CheapClone;
 use graph::components::store::QueryPermit;
 use graph::constraint_violation;
+use graph::data::subgraph::status::PoolStatus;
 use graph::prelude::tokio::time::Instant;
 use graph::prelude::{tokio, MetricsRegistry};
 use graph::slog::warn;
@@ -26,7 +27,7 @@ use graph::{
 };
 
 use std::fmt::{self, Write};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::{collections::HashMap, sync::RwLock};
@@ -514,6 +515,15 @@ impl ConnectionPool {
         })
     }
 
+    /// Report how busy this pool is. Returns `None` if the pool is
+    /// disabled
+    pub(crate) fn status(&self) -> Option<PoolStatus> {
+        match &*self.inner.lock(&self.logger) {
+            PoolState::Created(pool, _) | PoolState::Ready(pool) => Some(pool.status()),
+            PoolState::Disabled => None,
+        }
+    }
+
     pub(crate) fn wait_stats(&self) -> Result<PoolWaitStats, StoreError> {
         match &*self.inner.lock(&self.logger) {
             PoolState::Created(pool, _) | PoolState::Ready(pool) => Ok(pool.wait_stats.clone()),
@@ -696,6 +706,7 @@ impl HandleEvent for EventHandler {
 pub struct PoolInner {
     logger: Logger,
     pub shard: Shard,
+    name: String,
     pool: Pool<ConnectionManager<PgConnection>>,
     // A separate pool for connections that will use foreign data wrappers.
     // Once such a connection accesses a foreign table, Postgres keeps a
@@ -719,6 +730,25 @@ pub struct PoolInner {
     query_semaphore: Arc<tokio::sync::Semaphore>,
     semaphore_wait_stats: Arc<RwLock<MovingStats>>,
     semaphore_wait_gauge: Box<Gauge>,
+
+    /// The number of callers that are currently waiting for a connection
+    waiting: Arc<AtomicU32>,
+}
+
+/// Counts a caller as waiting for a connection for as long as it is alive
+struct WaitGuard(Arc<AtomicU32>);
+
+impl WaitGuard {
+    fn new(waiting: &Arc<AtomicU32>) -> Self {
+        waiting.fetch_add(1, Ordering::Relaxed);
+        WaitGuard(waiting.cheap_clone())
+    }
+}
+
+impl Drop for WaitGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl PoolInner {
@@ -811,6 +841,7 @@ impl PoolInner {
         PoolInner {
             logger: logger_pool,
             shard,
+            name: pool_name.to_string(),
             postgres_url,
             pool,
             fdw_pool,
@@ -819,6 +850,19 @@ impl PoolInner {
             semaphore_wait_stats: Arc::new(RwLock::new(MovingStats::default())),
             query_semaphore,
             semaphore_wait_gauge,
+            waiting: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    fn status(&self) -> PoolStatus {
+        let state = self.pool.state();
+        PoolStatus {
+            shard: self.shard.to_string(),
+            pool: self.name.clone(),
+            max_connections: self.pool.max_size(),
+            connections: state.connections,
+            in_use: state.connections - state.idle_connections,
+            waiting: self.waiting.load(Ordering::Relaxed),
         }
     }
 
@@ -871,7 +915,10 @@ impl PoolInner {
                 &CancelHandle,
             ) -> Result<T, CancelableError<StoreError>>,
     ) -> Result<T, StoreError> {
-        let _permit = self.limiter.acquire().await;
+        let _permit = {
+            let _waiting = WaitGuard::new(&self.waiting);
+            self.limiter.acquire().await
+        };
         let pool = self.clone();
 
         let cancel_guard = CancelGuard::new();
@@ -911,6 +958,7 @@ impl PoolInner {
     }
 
     pub fn get(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, StoreError> {
+        let _waiting = WaitGuard::new(&self.waiting);
         self.pool.get().map_err(|_| StoreError::DatabaseUnavailable)
     }
 
@@ -918,6 +966,7 @@ impl PoolInner {
         &self,
         logger: &Logger,
     ) -> Result<PooledConnection<ConnectionManager<PgConnection>>, StoreError> {
+        let _waiting = WaitGuard::new(&self.waiting);
         loop {
             match self.pool.get_timeout(ENV_VARS.store.connection_timeout) {
                 Ok(conn) => return Ok(conn),
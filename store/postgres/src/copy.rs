@@ -27,7 +27,7 @@ use diesel::{
     serialize::Output,
     serialize::ToSql,
     sql_query,
-    sql_types::{BigInt, Integer},
+    sql_types::{BigInt, Integer, Text},
     update, Connection as _, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl,
     RunQueryDsl,
 };
@@ -397,6 +397,104 @@ impl BatchCopy {
     }
 }
 
+/// Return the largest `vid` in `src` that a copy up to `target_block` has
+/// to copy, or -1 if there are no such rows
+fn target_vid(
+    conn: &mut PgConnection,
+    src: &Table,
+    target_block: BlockNumber,
+) -> Result<i64, StoreError> {
+    #[derive(QueryableByName)]
+    struct MaxVid {
+        #[diesel(sql_type = BigInt)]
+        max_vid: i64,
+    }
+
+    let max_block_clause = if src.immutable {
+        "block$ <= $1"
+    } else {
+        "lower(block_range) <= $1"
+    };
+    let target_vid = sql_query(format!(
+        "select coalesce(max(vid), -1) as max_vid from {} where {}",
+        src.qualified_name.as_str(),
+        max_block_clause
+    ))
+    .bind::<Integer, _>(&target_block)
+    .load::<MaxVid>(conn)?
+    .first()
+    .map(|v| v.max_vid)
+    .unwrap_or(-1);
+    Ok(target_vid)
+}
+
+/// Return Postgres' estimate of the number of rows in `src`, or `None` if
+/// the table has never been analyzed
+fn estimated_rows(
+    conn: &mut PgConnection,
+    namespace: &str,
+    src: &Table,
+) -> Result<Option<i64>, StoreError> {
+    #[derive(QueryableByName)]
+    struct Rows {
+        #[diesel(sql_type = BigInt)]
+        rows: i64,
+    }
+
+    let rows = sql_query(
+        "select c.reltuples::int8 as rows
+           from pg_class c, pg_namespace n
+          where c.relnamespace = n.oid
+            and n.nspname = $1
+            and c.relname = $2",
+    )
+    .bind::<Text, _>(namespace)
+    .bind::<Text, _>(src.name.as_str())
+    .get_result::<Rows>(conn)
+    .optional()?
+    .map(|r| r.rows)
+    // `reltuples` is -1 for tables that have never been analyzed
+    .filter(|rows| *rows >= 0);
+    Ok(rows)
+}
+
+/// How much work copying one table of a deployment will be
+#[derive(Debug)]
+pub struct TableEstimate {
+    pub entity_type: String,
+    /// The number of rows in the table according to the statistics that
+    /// Postgres keeps, or `None` if the table has never been analyzed
+    pub rows: Option<i64>,
+    /// The number of `vid` values that copying has to go through; since
+    /// copying proceeds in batches of `vid` ranges, this is what the
+    /// duration of the copy depends on. With deleted or pruned rows, this
+    /// can be considerably higher than the number of rows
+    pub vids: i64,
+}
+
+/// Estimate how much data copying `src` up to `target_block` moves. This
+/// uses the same tables and the same `vid` ranges that an actual copy would
+/// use, sorted by entity type
+pub(crate) fn estimate(
+    conn: &mut PgConnection,
+    src: &Layout,
+    target_block: BlockNumber,
+) -> Result<Vec<TableEstimate>, StoreError> {
+    let mut tables = src
+        .tables
+        .values()
+        .map(|table| {
+            Ok(TableEstimate {
+                entity_type: table.object.to_string(),
+                rows: estimated_rows(conn, src.site.namespace.as_str(), table)?,
+                vids: target_vid(conn, table, target_block)? + 1,
+            })
+        })
+        .collect::<Result<Vec<_>, StoreError>>()?;
+    tables.sort_by(|a, b| a.entity_type.cmp(&b.entity_type));
+    Ok(tables)
+}
+
 struct TableState {
     batch: BatchCopy,
     dst_site: Arc<Site>,
@@ -411,27 +509,7 @@ impl TableState {
         dst: Arc<Table>,
         target_block: &BlockPtr,
     ) -> Result<Self, StoreError> {
-        #[derive(QueryableByName)]
-        struct MaxVid {
-            #[diesel(sql_type = BigInt)]
-            max_vid: i64,
-        }
-
-        let max_block_clause = if src.immutable {
-            "block$ <= $1"
-        } else {
-            "lower(block_range) <= $1"
-        };
-        let target_vid = sql_query(format!(
-            "select coalesce(max(vid), -1) as max_vid from {} where {}",
-            src.qualified_name.as_str(),
-            max_block_clause
-        ))
-        .bind::<Integer, _>(&target_block.number)
-        .load::<MaxVid>(conn)?
-        .first()
-        .map(|v| v.max_vid)
-        .unwrap_or(-1);
+        let target_vid = target_vid(conn, &src, target_block.number)?;
 
         Ok(Self {
             batch: BatchCopy::new(src, dst, 0, target_vid),
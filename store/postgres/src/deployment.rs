@@ -5,7 +5,7 @@ use crate::{advisory_lock, detail::GraphNodeVersion, primary::DeploymentId};
 use diesel::{
     connection::SimpleConnection,
     dsl::{count, delete, insert_into, select, sql, update},
-    sql_types::{Bool, Integer},
+    sql_types::{BigInt, Bool, Integer, Timestamptz},
 };
 use diesel::{expression::SqlLiteral, pg::PgConnection, sql_types::Numeric};
 use diesel::{
@@ -14,7 +14,8 @@ use diesel::{
     sql_types::{Nullable, Text},
 };
 use graph::{
-    blockchain::block_stream::FirehoseCursor, data::subgraph::schema::SubgraphError,
+    blockchain::{block_stream::FirehoseCursor, BlockTime},
+    data::subgraph::schema::SubgraphError,
     schema::EntityType,
 };
 use graph::{
@@ -24,6 +25,7 @@ use graph::{
         DeploymentState, StoreError,
     },
     schema::InputSchema,
+    semver::Version,
 };
 use graph::{
     data::subgraph::{
@@ -299,13 +301,24 @@ pub fn debug_fork(
     }
 }
 
+/// Load the schema of the deployment. It is parsed with the spec version
+/// of the deployment since that determines how some directives, like
+/// `@scale`, are interpreted
 pub fn schema(conn: &mut PgConnection, site: &Site) -> Result<(InputSchema, bool), StoreError> {
     use subgraph_manifest as sm;
-    let (s, use_bytea_prefix) = sm::table
-        .select((sm::schema, sm::use_bytea_prefix))
+    let (s, spec_version, use_bytea_prefix) = sm::table
+        .select((sm::schema, sm::spec_version, sm::use_bytea_prefix))
         .filter(sm::id.eq(site.id))
-        .first::<(String, bool)>(conn)?;
-    InputSchema::parse_latest(s.as_str(), site.deployment.clone())
+        .first::<(String, String, bool)>(conn)?;
+    let spec_version = Version::parse(&spec_version).map_err(|e| {
+        anyhow!(
+            "deployment {} has invalid spec version `{}`: {}",
+            site.deployment,
+            spec_version,
+            e
+        )
+    })?;
+    InputSchema::parse(&spec_version, s.as_str(), site.deployment.clone())
         .map_err(StoreError::Unknown)
         .map(|schema| (schema, use_bytea_prefix))
 }
@@ -1220,6 +1233,32 @@ pub fn set_entity_count(
     Ok(())
 }
 
+/// Record in the deployment metadata that data points of timeseries with a
+/// retention window are being removed at `block_time`, unless that already
+/// happened less than `interval` of block time before. Return `true` if
+/// the removal is due. Since this is recorded with the deployment, the
+/// removal happens at the same cadence no matter which node writes the
+/// deployment
+pub fn claim_timeseries_prune(
+    conn: &mut PgConnection,
+    site: &Site,
+    block_time: BlockTime,
+    interval: Duration,
+) -> Result<bool, StoreError> {
+    let query = "\
+      update subgraphs.subgraph_deployment
+         set timeseries_pruned_at = $2
+       where id = $1
+         and (timeseries_pruned_at is null
+              or timeseries_pruned_at <= $2 - $3 * interval '1 second')";
+    let rows = sql_query(query)
+        .bind::<Integer, _>(site.id)
+        .bind::<Timestamptz, _>(block_time)
+        .bind::<BigInt, _>(interval.as_secs() as i64)
+        .execute(conn)?;
+    Ok(rows > 0)
+}
+
 /// Set the earliest block of `site` to the larger of `earliest_block` and
 /// the current value. This means that the `earliest_block_number` can never
 /// go backwards, only forward. This is important so that copying into
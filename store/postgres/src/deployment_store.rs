@@ -24,6 +24,7 @@ use graph::prelude::{
 };
 use graph::semver::Version;
 use graph::tokio::task::JoinHandle;
+use graph::util::timed_cache::TimedCache;
 use itertools::Itertools;
 use lru_time_cache::LruCache;
 use rand::{seq::SliceRandom, thread_rng};
@@ -47,7 +48,9 @@ use graph::prelude::{
 use graph::schema::{ApiSchema, EntityKey, EntityType, InputSchema};
 use web3::types::Address;
 
+use crate::archive;
 use crate::block_range::{BLOCK_COLUMN, BLOCK_RANGE_COLUMN};
+use crate::copy::TableEstimate;
 use crate::deployment::{self, OnSync};
 use crate::detail::ErrorDetail;
 use crate::dynds::DataSourcesTable;
@@ -113,6 +116,11 @@ pub struct StoreInner {
     pub(crate) layout_cache: LayoutCache,
 
     prune_handles: Mutex<HashMap<DeploymentId, PruneHandle>>,
+
+    /// The storage sizes that the status API reports, cached for
+    /// `GRAPH_STORE_STORAGE_STATS_TTL` since computing them requires
+    /// looking at every table of the deployment
+    storage_stats: TimedCache<DeploymentId, status::StorageStats>,
 }
 
 /// Storage of the data for individual deployments. Each `DeploymentStore`
@@ -167,6 +175,7 @@ impl DeploymentStore {
             subgraph_cache: Mutex::new(LruCache::with_capacity(100)),
             layout_cache: LayoutCache::new(ENV_VARS.store.query_stats_refresh_interval),
             prune_handles: Mutex::new(HashMap::new()),
+            storage_stats: TimedCache::new(ENV_VARS.store.storage_stats_ttl),
         };
 
         DeploymentStore(Arc::new(store))
@@ -264,6 +273,32 @@ impl DeploymentStore {
         })
     }
 
+    /// Write the deployment `site` as an archive to `out`
+    pub(crate) fn export_archive(
+        &self,
+        site: Arc<Site>,
+        out: &mut dyn std::io::Write,
+        batch_size: usize,
+    ) -> Result<usize, StoreError> {
+        let mut conn = self.get_conn()?;
+        let layout = self.layout(&mut conn, site.clone())?;
+        archive::export(&mut conn, &site, &layout, out, batch_size)
+    }
+
+    /// Load the data from the archive `input` into the newly created
+    /// deployment `site`
+    pub(crate) fn import_archive(
+        &self,
+        site: Arc<Site>,
+        header: &archive::Header,
+        input: &mut dyn std::io::BufRead,
+        batch_size: usize,
+    ) -> Result<usize, StoreError> {
+        let mut conn = self.get_conn()?;
+        let layout = self.layout(&mut conn, site.clone())?;
+        archive::import(&mut conn, &site, &layout, header, input, batch_size)
+    }
+
     pub(crate) fn execute_query<T: FromEntityData>(
         &self,
         conn: &mut PgConnection,
@@ -279,6 +314,22 @@ impl DeploymentStore {
         layout.query(&logger, conn, query)
     }
 
+    pub(crate) fn find_history<T: FromEntityData>(
+        &self,
+        conn: &mut PgConnection,
+        site: Arc<Site>,
+        entity_type: &EntityType,
+        id: &Id,
+        block: BlockNumber,
+        first: u32,
+        skip: u32,
+    ) -> Result<Vec<(T, BlockNumber, Option<BlockNumber>)>, QueryExecutionError> {
+        let layout = self.layout(conn, site)?;
+        layout
+            .find_history(conn, entity_type, id, block, first, skip)
+            .map_err(QueryExecutionError::from)
+    }
+
     fn check_interface_entity_uniqueness(
         &self,
         conn: &mut PgConnection,
@@ -437,6 +488,15 @@ impl DeploymentStore {
         }
     }
 
+    /// The state of the main connection pool and the pools for read
+    /// replicas of this shard
+    pub(crate) fn pool_status(&self) -> Vec<status::PoolStatus> {
+        std::iter::once(&self.pool)
+            .chain(self.read_only_pools.iter())
+            .filter_map(|pool| pool.status())
+            .collect()
+    }
+
     /// Return the layout for a deployment. Since constructing a `Layout`
     /// object takes a bit of computation, we cache layout objects that do
     /// not have a pending migration in the Store, i.e., for the lifetime of
@@ -546,9 +606,79 @@ impl DeploymentStore {
         sites: &[Arc<Site>],
     ) -> Result<Vec<status::Info>, StoreError> {
         let conn = &mut *self.get_conn()?;
-        conn.transaction(|conn| -> Result<Vec<status::Info>, StoreError> {
+        let mut infos = conn.transaction(|conn| -> Result<Vec<status::Info>, StoreError> {
             detail::deployment_statuses(conn, sites)
-        })
+        })?;
+
+        let mut missing = Vec::new();
+        for info in &mut infos {
+            let id = DeploymentId::from(info.id);
+            info.storage = self.storage_stats.get(&id);
+            if info.storage.is_none() {
+                missing.extend(sites.iter().find(|site| site.id == id).cloned());
+            }
+        }
+        if !missing.is_empty() {
+            for (id, stats) in self.compute_storage_stats(conn, &missing)? {
+                let stats = Arc::new(stats);
+                self.storage_stats.set(id, stats.cheap_clone());
+                if let Some(info) = infos
+                    .iter_mut()
+                    .find(|info| DeploymentId::from(info.id) == id)
+                {
+                    info.storage = Some(stats);
+                }
+            }
+        }
+        Ok(infos)
+    }
+
+    /// Compute how much space each of the `sites` takes up in the database
+    /// from the statistics Postgres keeps about their tables
+    fn compute_storage_stats(
+        &self,
+        conn: &mut PgConnection,
+        sites: &[Arc<Site>],
+    ) -> Result<Vec<(DeploymentId, status::StorageStats)>, StoreError> {
+        let namespaces: Vec<_> = sites.iter().map(|site| site.namespace.as_str()).collect();
+        let mut sizes: HashMap<String, HashMap<String, catalog::TableSize>> = HashMap::new();
+        for size in catalog::table_sizes(conn, &namespaces)? {
+            sizes
+                .entry(size.namespace.clone())
+                .or_default()
+                .insert(size.table_name.clone(), size);
+        }
+
+        let mut stats = Vec::new();
+        for site in sites {
+            let layout = self.layout(conn, site.cheap_clone())?;
+            let tables = sizes.remove(site.namespace.as_str()).unwrap_or_default();
+
+            let storage_bytes = tables.values().map(|size| size.total_bytes as u64).sum();
+            let mut entity_types: Vec<_> = layout
+                .tables
+                .values()
+                .filter(|table| !table.object.is_poi())
+                .map(|table| {
+                    let size = tables.get(table.name.as_str());
+                    status::EntityTypeStorage {
+                        entity_type: table.object.to_string(),
+                        entity_count: size.map(|size| size.entities as u64).unwrap_or(0),
+                        storage_bytes: size.map(|size| size.total_bytes as u64).unwrap_or(0),
+                    }
+                })
+                .collect();
+            entity_types.sort_by(|a, b| a.entity_type.cmp(&b.entity_type));
+
+            stats.push((
+                site.id,
+                status::StorageStats {
+                    storage_bytes,
+                    entity_types,
+                },
+            ));
+        }
+        Ok(stats)
     }
 
     pub(crate) fn deployment_exists_and_synced(
@@ -628,10 +758,22 @@ impl DeploymentStore {
             .transpose()?
             .map(|table| vec![table])
             .unwrap_or_else(|| layout.tables.values().map(Arc::as_ref).collect());
-        for table in tables {
-            table.analyze(&mut conn)?;
-        }
-        Ok(())
+        catalog::with_statement_timeout(&mut conn, ENV_VARS.store.admin_statement_timeout, |conn| {
+            for table in tables {
+                table.analyze(conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub(crate) fn copy_estimate(
+        &self,
+        site: Arc<Site>,
+        target_block: BlockNumber,
+    ) -> Result<Vec<TableEstimate>, StoreError> {
+        let mut conn = self.get_conn()?;
+        let layout = self.layout(&mut conn, site)?;
+        crate::copy::estimate(&mut conn, &layout, target_block)
     }
 
     pub(crate) fn stats_targets(
@@ -710,7 +852,11 @@ impl DeploymentStore {
             )?;
 
             // This might take a long time.
-            sql_query(sql).execute(conn)?;
+            catalog::with_statement_timeout(
+                conn,
+                ENV_VARS.store.admin_statement_timeout,
+                |conn| sql_query(sql).execute(conn),
+            )?;
             // check if the index creation was successfull
             let index_is_valid =
                 catalog::check_index_is_valid(conn, schema_name.as_str(), &index_name)?;
@@ -756,9 +902,12 @@ impl DeploymentStore {
         index_name: &str,
     ) -> Result<(), StoreError> {
         let index_name = String::from(index_name);
-        self.with_conn(move |mut conn, _| {
+        self.with_conn(move |conn, _| {
             let schema_name = site.namespace.clone();
-            catalog::drop_index(&mut conn, schema_name.as_str(), &index_name).map_err(Into::into)
+            catalog::with_statement_timeout(conn, ENV_VARS.store.admin_statement_timeout, |conn| {
+                catalog::drop_index(conn, schema_name.as_str(), &index_name)
+            })
+            .map_err(Into::into)
         })
         .await
     }
@@ -780,6 +929,25 @@ impl DeploymentStore {
         .await
     }
 
+    /// The earliest block of an interrupted prune of `site` that can be
+    /// resumed, if there is one
+    pub(crate) fn prune_checkpoint_block(
+        &self,
+        site: &Site,
+    ) -> Result<Option<BlockNumber>, StoreError> {
+        let mut conn = self.get_conn()?;
+        catalog::prune_checkpoint_block(&mut conn, site)
+    }
+
+    /// Wait until a block that is being written for `site` has been
+    /// committed and return the block pointer of the deployment after that
+    pub(crate) fn wait_for_writes(&self, site: &Site) -> Result<Option<BlockPtr>, StoreError> {
+        let mut conn = self.get_conn()?;
+        deployment::with_lock(&mut conn, site, |conn| {
+            deployment::block_ptr(conn, &site.deployment)
+        })
+    }
+
     pub(crate) fn set_history_blocks(
         &self,
         site: &Site,
@@ -811,7 +979,7 @@ impl DeploymentStore {
     ) -> Result<Box<dyn PruneReporter>, StoreError> {
         fn do_prune(
             store: Arc<DeploymentStore>,
-            mut conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+            mut conn: &mut PgConnection,
             site: Arc<Site>,
             cancel: &CancelHandle,
             req: PruneRequest,
@@ -848,7 +1016,10 @@ impl DeploymentStore {
             // deployment is reassigned to another node, that node won't
             // kick off a pruning run while this node might still be pruning
             if advisory_lock::try_lock_pruning(conn, &site)? {
-                let res = do_prune(store, conn, site.cheap_clone(), cancel, req, reporter);
+                let timeout = ENV_VARS.store.admin_statement_timeout;
+                let res = catalog::with_statement_timeout(conn, timeout, |conn| {
+                    do_prune(store, conn, site.cheap_clone(), cancel, req, reporter)
+                });
                 advisory_lock::unlock_pruning(conn, &site)?;
                 res
             } else {
@@ -1103,6 +1274,8 @@ impl DeploymentStore {
 
         let (layout, earliest_block) = deployment::with_lock(&mut conn, &site, |conn| {
             conn.transaction(|conn| -> Result<_, StoreError> {
+                catalog::set_local_statement_timeout(conn, ENV_VARS.store.write_statement_timeout)?;
+
                 // Make the changes
                 let layout = self.layout(conn, site.clone())?;
 
@@ -1117,6 +1290,11 @@ impl DeploymentStore {
 
                 layout.rollup(conn, last_rollup, &batch.block_times)?;
 
+                if let Some(block_time) = self.timeseries_prune_due(conn, &site, &layout, batch)? {
+                    let _section = stopwatch.start_section("transact_blocks_prune_timeseries");
+                    layout.prune_timeseries(logger, conn, batch.block_ptr.number, block_time)?;
+                }
+
                 dynds::insert(conn, &site, &batch.data_sources, manifest_idx_and_name)?;
 
                 dynds::update_offchain_status(conn, &site, &batch.offchain_to_remove)?;
@@ -1171,6 +1349,31 @@ impl DeploymentStore {
         Ok(event)
     }
 
+    /// Return the time of the last block in `batch` if timeseries data
+    /// points that fell out of their retention window should be removed
+    /// as part of writing `batch`. We do that at most once per
+    /// `TIMESERIES_PRUNE_INTERVAL` of block time; when that last happened
+    /// is kept in the deployment metadata
+    fn timeseries_prune_due(
+        &self,
+        conn: &mut PgConnection,
+        site: &Site,
+        layout: &Layout,
+        batch: &Batch,
+    ) -> Result<Option<BlockTime>, StoreError> {
+        const TIMESERIES_PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+        if !layout.has_timeseries_retention() {
+            return Ok(None);
+        }
+        let Some((_, block_time)) = batch.block_times.last() else {
+            return Ok(None);
+        };
+        let due =
+            deployment::claim_timeseries_prune(conn, site, *block_time, TIMESERIES_PRUNE_INTERVAL)?;
+        Ok(due.then_some(*block_time))
+    }
+
     fn spawn_prune(
         self: &Arc<Self>,
         logger: &Logger,
@@ -1260,6 +1463,8 @@ impl DeploymentStore {
     ) -> Result<StoreEvent, StoreError> {
         let event = deployment::with_lock(conn, &site, |conn| {
             conn.transaction(|conn| -> Result<_, StoreError> {
+                catalog::set_local_statement_timeout(conn, ENV_VARS.store.write_statement_timeout)?;
+
                 // The revert functions want the number of the first block that we need to get rid of
                 let block = block_ptr_to.number + 1;
 
@@ -1472,6 +1677,14 @@ impl DeploymentStore {
         deployment::graft_pending(&mut conn, id)
     }
 
+    pub(crate) fn graft_point(
+        &self,
+        id: &DeploymentHash,
+    ) -> Result<Option<(DeploymentHash, BlockPtr)>, StoreError> {
+        let mut conn = self.get_conn()?;
+        deployment::graft_point(&mut conn, id)
+    }
+
     /// Bring the subgraph into a state where we can start or resume
     /// indexing.
     ///
@@ -1996,6 +2209,13 @@ impl PruneReporter for OngoingPruneReporter {
         debug!(&self.logger, "Analyzed {} tables", analyzed.len(); "time_s" => self.analyze_start.elapsed().as_secs());
     }
 
+    fn start_tables(&mut self, _tables: &[&str], resumed: &[&str]) {
+        if !resumed.is_empty() {
+            info!(&self.logger, "Resuming interrupted pruning";
+                  "pruned_tables" => format!("[{}]", resumed.iter().join(",")));
+        }
+    }
+
     fn start_table(&mut self, table: &str) {
         self.tables.push(table.to_string());
     }
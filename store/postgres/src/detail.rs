@@ -234,19 +234,25 @@ pub(crate) fn info_from_details(
         .map(SubgraphError::try_from)
         .collect::<Result<Vec<SubgraphError>, StoreError>>()?;
 
-    // 'node' needs to be filled in later from a different shard
+    // 'node' needs to be filled in later from a different shard, and
+    // 'recent_reorgs' and 'blocks_per_second' from the in-memory state of
+    // the deployment's writable
     Ok(status::Info {
         id: id.into(),
         subgraph: deployment,
         synced,
         health,
         paused: None,
+        paused_reason: None,
         fatal_error,
         non_fatal_errors,
         chains: vec![chain],
         entity_count,
         node: None,
         history_blocks: subgraph_history_blocks,
+        recent_reorgs: Vec::new(),
+        blocks_per_second: None,
+        storage: None,
     })
 }
 
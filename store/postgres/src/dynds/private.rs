@@ -4,7 +4,7 @@ use diesel::{
     pg::sql_types,
     prelude::*,
     sql_query,
-    sql_types::{Binary, Bool, Integer, Jsonb, Nullable},
+    sql_types::{BigInt, Binary, Bool, Integer, Jsonb, Nullable, Text},
     PgConnection, QueryDsl, RunQueryDsl,
 };
 
@@ -301,6 +301,76 @@ impl DataSourcesTable {
         Ok(count)
     }
 
+    /// Load up to `limit` data sources whose `vid` is bigger than `after`,
+    /// ordered by `vid`, as JSON objects for a deployment archive
+    pub(crate) fn archive_page(
+        &self,
+        conn: &mut PgConnection,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<(i64, String)>, StoreError> {
+        #[derive(QueryableByName)]
+        struct Row {
+            #[diesel(sql_type = BigInt)]
+            vid: i64,
+            #[diesel(sql_type = Text)]
+            data: String,
+        }
+
+        let query = format!(
+            "select vid::int8 as vid, \
+                    jsonb_build_object(\
+                      'vid', vid, \
+                      'block_range', jsonb_build_array(lower(block_range), upper(block_range)), \
+                      'causality_region', causality_region, \
+                      'manifest_idx', manifest_idx, \
+                      'parent', parent, \
+                      'id', '0x' || encode(id, 'hex'), \
+                      'param', '0x' || encode(param, 'hex'), \
+                      'context', context, \
+                      'done_at', done_at)::text as data \
+               from {} \
+              where vid > $1 \
+              order by vid \
+              limit $2",
+            self.qname
+        );
+        let rows = sql_query(query)
+            .bind::<BigInt, _>(after)
+            .bind::<BigInt, _>(limit)
+            .load::<Row>(conn)?;
+        Ok(rows.into_iter().map(|row| (row.vid, row.data)).collect())
+    }
+
+    /// Insert the data sources in `rows`, a JSON array of objects produced
+    /// by `archive_page`, and return how many were inserted
+    pub(crate) fn restore_page(
+        &self,
+        conn: &mut PgConnection,
+        rows: &str,
+    ) -> Result<usize, StoreError> {
+        let query = format!(
+            "insert into {}(vid, block_range, causality_region, manifest_idx, parent, id, param, context, done_at) \
+             select (r->>'vid')::int4, \
+                    int4range((r->'block_range'->>0)::int4, (r->'block_range'->>1)::int4), \
+                    (r->>'causality_region')::int4, \
+                    (r->>'manifest_idx')::int4, \
+                    (r->>'parent')::int4, \
+                    decode(substr(r->>'id', 3), 'hex'), \
+                    decode(substr(r->>'param', 3), 'hex'), \
+                    nullif(r->'context', 'null'::jsonb), \
+                    (r->>'done_at')::int4 \
+               from jsonb_array_elements($1::jsonb) r",
+            self.qname
+        );
+        Ok(sql_query(query).bind::<Text, _>(rows).execute(conn)?)
+    }
+
+    /// The name of the table, qualified with its schema
+    pub(crate) fn qualified_name(&self) -> &str {
+        &self.qname
+    }
+
     // Remove offchain data sources by checking the causality region, which currently uniquely
     // identifies an offchain data source.
     pub(super) fn update_offchain_status(
@@ -12,6 +12,7 @@ extern crate diesel_migrations;
 extern crate diesel_derive_enum;
 
 mod advisory_lock;
+mod archive;
 mod block_range;
 mod block_store;
 mod catalog;
@@ -71,8 +72,8 @@ pub use self::subgraph_store::{unused, DeploymentPlacer, Shard, SubgraphStore, P
 pub mod command_support {
     pub mod catalog {
         pub use crate::block_store::primary as block_store;
-        pub use crate::catalog::{account_like, stats};
-        pub use crate::copy::{copy_state, copy_table_state};
+        pub use crate::catalog::{account_like, stats, versions_in_range};
+        pub use crate::copy::{copy_state, copy_table_state, TableEstimate};
         pub use crate::primary::{
             active_copies, deployment_schemas, ens_names, subgraph, subgraph_deployment_assignment,
             subgraph_version, Site,
@@ -84,5 +85,5 @@ pub mod command_support {
     }
     pub use crate::deployment::{on_sync, OnSync};
     pub use crate::primary::Namespace;
-    pub use crate::relational::{Catalog, Column, ColumnType, Layout, SqlName};
+    pub use crate::relational::{Catalog, Column, ColumnType, Layout, SqlName, Table};
 }
@@ -107,6 +107,7 @@ table! {
         node_id -> Text,
         paused_at -> Nullable<Timestamptz>,
         assigned_at -> Nullable<Timestamptz>,
+        paused_reason -> Nullable<Text>,
     }
 }
 
@@ -627,14 +628,21 @@ mod queries {
         let nodes: HashMap<_, _> = a::table
             .inner_join(ds::table.on(ds::id.eq(a::id)))
             .filter(ds::subgraph.eq_any(ids))
-            .select((ds::subgraph, a::node_id, a::paused_at.is_not_null()))
-            .load::<(String, String, bool)>(conn)?
+            .select((
+                ds::subgraph,
+                a::node_id,
+                a::paused_at.is_not_null(),
+                a::paused_reason,
+            ))
+            .load::<(String, String, bool, Option<String>)>(conn)?
             .into_iter()
-            .map(|(subgraph, node, paused)| (subgraph, (node, paused)))
+            .map(|(subgraph, node, paused, reason)| (subgraph, (node, paused, reason)))
             .collect();
         for info in infos {
-            info.node = nodes.get(&info.subgraph).map(|(node, _)| node.clone());
-            info.paused = nodes.get(&info.subgraph).map(|(_, paused)| *paused);
+            let assignment = nodes.get(&info.subgraph);
+            info.node = assignment.map(|(node, _, _)| node.clone());
+            info.paused = assignment.map(|(_, paused, _)| *paused);
+            info.paused_reason = assignment.and_then(|(_, _, reason)| reason.clone());
         }
         Ok(())
     }
@@ -1047,13 +1055,19 @@ impl<'a> Connection<'a> {
         }
     }
 
-    pub fn pause_subgraph(&mut self, site: &Site) -> Result<Vec<EntityChange>, StoreError> {
+    /// Pause the deployment, recording `reason` as the explanation for
+    /// why it was paused
+    pub fn pause_subgraph(
+        &mut self,
+        site: &Site,
+        reason: Option<&str>,
+    ) -> Result<Vec<EntityChange>, StoreError> {
         use subgraph_deployment_assignment as a;
 
         let conn = self.conn.as_mut();
 
         let updates = update(a::table.filter(a::id.eq(site.id)))
-            .set(a::paused_at.eq(sql("now()")))
+            .set((a::paused_at.eq(sql("now()")), a::paused_reason.eq(reason)))
             .execute(conn)?;
         match updates {
             0 => Err(StoreError::DeploymentNotFound(site.deployment.to_string())),
@@ -1076,7 +1090,10 @@ impl<'a> Connection<'a> {
         let conn = self.conn.as_mut();
 
         let updates = update(a::table.filter(a::id.eq(site.id)))
-            .set(a::paused_at.eq(sql("null")))
+            .set((
+                a::paused_at.eq(sql("null")),
+                a::paused_reason.eq(None::<String>),
+            ))
             .execute(conn)?;
         match updates {
             0 => Err(StoreError::DeploymentNotFound(site.deployment.to_string())),
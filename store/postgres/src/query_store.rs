@@ -2,11 +2,13 @@ use std::collections::HashMap;
 use std::time::Instant;
 
 use crate::deployment_store::{DeploymentStore, ReplicaId};
-use graph::components::store::{DeploymentId, QueryPermit, QueryStore as QueryStoreTrait};
+use graph::components::store::{
+    DeploymentId, EntityVersion, QueryPermit, QueryStore as QueryStoreTrait,
+};
 use graph::data::query::Trace;
-use graph::data::store::QueryObject;
+use graph::data::store::{Id, QueryObject};
 use graph::prelude::*;
-use graph::schema::{ApiSchema, InputSchema};
+use graph::schema::{ApiSchema, EntityType, InputSchema};
 
 use crate::primary::Site;
 
@@ -57,6 +59,37 @@ impl QueryStoreTrait for QueryStore {
             })
     }
 
+    fn find_history(
+        &self,
+        entity_type: &EntityType,
+        id: &Id,
+        block: BlockNumber,
+        first: u32,
+        skip: u32,
+    ) -> Result<Vec<EntityVersion>, QueryExecutionError> {
+        let mut conn = self
+            .store
+            .get_replica_conn(self.replica_id)
+            .map_err(|e| QueryExecutionError::StoreError(e.into()))?;
+        let versions = self.store.find_history::<QueryObject>(
+            &mut conn,
+            self.site.clone(),
+            entity_type,
+            id,
+            block,
+            first,
+            skip,
+        )?;
+        Ok(versions
+            .into_iter()
+            .map(|(object, block_start, block_end)| EntityVersion {
+                block_start,
+                block_end,
+                object,
+            })
+            .collect())
+    }
+
     /// Return true if the deployment with the given id is fully synced,
     /// and return false otherwise. Errors from the store are passed back up
     async fn is_deployment_synced(&self) -> Result<bool, Error> {
@@ -112,6 +145,10 @@ impl QueryStoreTrait for QueryStore {
         self.chain_store.block_numbers(block_hashes).await
     }
 
+    async fn is_ancestor(&self, head: &BlockPtr, block: &BlockPtr) -> Result<bool, StoreError> {
+        self.chain_store.is_ancestor(head, block).await
+    }
+
     fn wait_stats(&self) -> Result<PoolWaitStats, StoreError> {
         self.store.wait_stats(self.replica_id)
     }
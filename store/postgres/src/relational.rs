@@ -56,8 +56,9 @@ use crate::relational_queries::{
 use crate::{
     primary::{Namespace, Site},
     relational_queries::{
-        ClampRangeQuery, ConflictingEntityQuery, EntityData, EntityDeletion, FilterCollection,
-        FilterQuery, FindManyQuery, FindQuery, InsertQuery, RevertClampQuery, RevertRemoveQuery,
+        ClampRangeQuery, ConflictingEntityQuery, EntityData, EntityDeletion, EntityVersionData,
+        FilterCollection, FilterQuery, FindHistoryQuery, FindManyQuery, FindQuery, InsertQuery,
+        RevertClampQuery, RevertRemoveQuery,
     },
 };
 use graph::components::store::DerivedEntityQuery;
@@ -68,7 +69,7 @@ use graph::prelude::{
     QueryExecutionError, StoreError, StoreEvent, ValueType, BLOCK_NUMBER_MAX,
 };
 
-use crate::block_range::{BLOCK_COLUMN, BLOCK_RANGE_COLUMN};
+use crate::block_range::{BLOCK_COLUMN, BLOCK_RANGE_COLUMN, CAUSALITY_REGION_COLUMN};
 pub use crate::catalog::Catalog;
 use crate::connection_pool::ForeignServer;
 use crate::{catalog, deployment};
@@ -330,6 +331,7 @@ impl Layout {
                 fulltext_fields: None,
                 is_reference: false,
                 use_prefix_comparison: false,
+                scale: None,
             },
             Column {
                 name: SqlName::from(PRIMARY_KEY_COLUMN),
@@ -339,6 +341,7 @@ impl Layout {
                 fulltext_fields: None,
                 is_reference: false,
                 use_prefix_comparison: false,
+                scale: None,
             },
         ];
 
@@ -355,6 +358,7 @@ impl Layout {
                 fulltext_fields: None,
                 is_reference: false,
                 use_prefix_comparison: false,
+                scale: None,
             };
             columns.push(ts_column);
         }
@@ -475,6 +479,36 @@ impl Layout {
             .transpose()
     }
 
+    /// Find the versions of the entity `entity_type[id]` that were created
+    /// at or before `block`, ordered by the block at which they were
+    /// created
+    pub fn find_history<T: crate::relational_queries::FromEntityData>(
+        &self,
+        conn: &mut PgConnection,
+        entity_type: &EntityType,
+        id: &Id,
+        block: BlockNumber,
+        first: u32,
+        skip: u32,
+    ) -> Result<Vec<(T, BlockNumber, Option<BlockNumber>)>, StoreError> {
+        let table = self.table_for_entity(entity_type)?;
+        if table.immutable {
+            return Err(constraint_violation!(
+                "history queries are not supported for immutable entity type {}",
+                entity_type
+            ));
+        }
+        FindHistoryQuery::new(table.as_ref(), id, block, first, skip)
+            .load::<EntityVersionData>(conn)?
+            .into_iter()
+            .map(|version| {
+                let (data, block_start, block_end) = version.into_entity_data();
+                data.deserialize_with_layout(self, None)
+                    .map(|entity| (entity, block_start, block_end))
+            })
+            .collect()
+    }
+
     // An optimization when looking up multiple entities, it will generate a single sql query using `UNION ALL`.
     pub fn find_many(
         &self,
@@ -752,14 +786,17 @@ impl Layout {
         }
 
         let section = stopwatch.start_section("update_modification_clamp_range_query");
+        let chunk_size = ClampRangeQuery::chunk_size(usize::MAX);
         for (block, rows) in group.clamps_by_block() {
             let entity_keys: Vec<_> = rows.iter().map(|row| row.id()).collect();
-            // FIXME: we clone all the ids here
-            let entity_keys = IdList::try_from_iter(
-                group.entity_type.id_type()?,
-                entity_keys.into_iter().map(|id| id.to_owned()),
-            )?;
-            ClampRangeQuery::new(table, &entity_keys, block)?.execute(conn)?;
+            for chunk in entity_keys.chunks(chunk_size) {
+                // FIXME: we clone all the ids here
+                let chunk = IdList::try_from_iter(
+                    group.entity_type.id_type()?,
+                    chunk.iter().map(|id| (*id).to_owned()),
+                )?;
+                ClampRangeQuery::new(table, &chunk, block)?.execute(conn)?;
+            }
         }
         section.end();
 
@@ -797,9 +834,10 @@ impl Layout {
 
         let _section = stopwatch.start_section("delete_modification_clamp_range_query");
         let mut count = 0;
+        let chunk_size = ClampRangeQuery::chunk_size(DELETE_OPERATION_CHUNK_SIZE);
         for (block, rows) in group.clamps_by_block() {
             let ids: Vec<_> = rows.iter().map(|eref| eref.id()).collect();
-            for chunk in ids.chunks(DELETE_OPERATION_CHUNK_SIZE) {
+            for chunk in ids.chunks(chunk_size) {
                 // FIXME: we clone all the ids here
                 let chunk = IdList::try_from_iter(
                     group.entity_type.id_type()?,
@@ -822,6 +860,11 @@ impl Layout {
     /// numbers. After this operation, only entity versions inserted or
     /// updated at blocks with numbers strictly lower than `block` will
     /// remain
+    ///
+    /// All reverted blocks are handled with the same two statements per
+    /// table, no matter how many blocks there are, and the tables end up in
+    /// the same state as if the blocks had been reverted one at a time,
+    /// starting with the highest block
     pub fn revert_block(
         &self,
         conn: &mut PgConnection,
@@ -1242,6 +1285,9 @@ pub struct Column {
     /// Whether to use a prefix of the column for comparisons and index
     /// creation, or column values in their entirety
     pub use_prefix_comparison: bool,
+    /// For `BigDecimal` attributes with a `@scale` directive, the number of
+    /// digits after the decimal point to which values are rounded
+    pub scale: Option<i64>,
 }
 
 impl Column {
@@ -1295,6 +1341,7 @@ impl Column {
             fulltext_fields: None,
             is_reference,
             use_prefix_comparison,
+            scale: field.scale(),
         })
     }
 
@@ -1310,6 +1357,7 @@ impl Column {
             fulltext_fields: Some(def.included_fields.clone()),
             is_reference: false,
             use_prefix_comparison: false,
+            scale: None,
         })
     }
 
@@ -1339,6 +1387,48 @@ impl Column {
         self.field_type.get_base_type() == "fulltext"
     }
 
+    /// An SQL expression that turns the value of this column for the row
+    /// `c` into JSON. Bytes are hex encoded, and big numbers are turned
+    /// into strings so that they do not lose precision
+    fn json_expr(&self) -> String {
+        let name = self.name.quoted();
+        match (&self.column_type, self.is_list()) {
+            (ColumnType::Bytes, false) => format!("'0x' || encode(c.{name}, 'hex')"),
+            (ColumnType::Bytes, true) => format!(
+                "case when c.{name} is null then null \
+                      else coalesce((select jsonb_agg('0x' || encode(b, 'hex')) \
+                                       from unnest(c.{name}) b), '[]'::jsonb) end"
+            ),
+            (ColumnType::BigInt | ColumnType::BigDecimal | ColumnType::Int8, false) => {
+                format!("c.{name}::text")
+            }
+            (ColumnType::BigInt | ColumnType::BigDecimal | ColumnType::Int8, true) => {
+                format!("c.{name}::text[]")
+            }
+            _ => format!("c.{name}"),
+        }
+    }
+
+    /// The inverse of `json_expr`: an SQL expression that turns the JSON
+    /// value `value` back into a value for this column
+    fn from_json_expr(&self, value: &str) -> String {
+        let element = |text: &str| match self.column_type {
+            ColumnType::Bytes => format!("decode(substr({text}, 3), 'hex')"),
+            _ => format!("({text})::{}", self.sql_type()),
+        };
+        if self.is_list() {
+            format!(
+                "case when jsonb_typeof({value}) = 'array' \
+                      then array(select {} \
+                                   from jsonb_array_elements_text({value}) with ordinality as x(e, i) \
+                                  order by x.i) end",
+                element("x.e")
+            )
+        } else {
+            element(&format!("{value} #>> '{{}}'"))
+        }
+    }
+
     pub fn is_reference(&self) -> bool {
         self.is_reference
     }
@@ -1347,6 +1437,17 @@ impl Column {
         self.name.as_str() == PRIMARY_KEY_COLUMN
     }
 
+    /// Values that were rounded to `source` decimals can be used for an
+    /// attribute whose values are rounded to `target` decimals without
+    /// losing precision
+    fn scale_fits(target: Option<i64>, source: Option<i64>) -> bool {
+        match (target, source) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(target), Some(source)) => source <= target,
+        }
+    }
+
     pub fn is_assignable_from(&self, source: &Self, object: &EntityType) -> Option<String> {
         if !self.is_nullable() && source.is_nullable() {
             Some(format!(
@@ -1370,6 +1471,18 @@ impl Column {
                              but its type in the source is {}",
                 object, self.field, self.field_type, source.field_type
             ))
+        } else if !Self::scale_fits(self.scale, source.scale) {
+            Some(format!(
+                "The attribute {}.{} is rounded to {} decimals, \
+                             but the source allows {}",
+                object,
+                self.field,
+                self.scale.unwrap_or_default(),
+                source
+                    .scale
+                    .map(|scale| format!("{scale} decimals"))
+                    .unwrap_or_else(|| "any number of decimals".to_string())
+            ))
         } else {
             None
         }
@@ -1539,6 +1652,166 @@ impl Table {
             &crate::block_range::BLOCK_RANGE_COLUMN_SQL
         }
     }
+
+    /// Load up to `limit` entity versions from this table that are visible
+    /// at `block` and whose `vid` is bigger than `after`, ordered by `vid`.
+    /// Each version is returned as its `vid` together with a JSON object
+    /// that is keyed by the GraphQL field names and has the entity type
+    /// in `__typename`. Bytes are hex encoded and big numbers are turned
+    /// into strings, the same way GraphQL queries return them. Callers can
+    /// page through the whole table by passing the last `vid` they saw as
+    /// `after` until no more rows are returned
+    pub fn export_page(
+        &self,
+        conn: &mut PgConnection,
+        block: BlockNumber,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<(i64, String)>, StoreError> {
+        use diesel::sql_types::{BigInt, Integer};
+
+        #[derive(QueryableByName)]
+        struct Row {
+            #[diesel(sql_type = BigInt)]
+            vid: i64,
+            #[diesel(sql_type = Text)]
+            data: String,
+        }
+
+        let mut fields = vec![format!("'__typename', '{}'", self.object.as_str())];
+        fields.extend(
+            self.columns
+                .iter()
+                .filter(|column| !column.is_fulltext())
+                .map(|column| format!("'{}', {}", column.field, column.json_expr())),
+        );
+        let data = Self::json_object(fields);
+
+        let visible = if self.immutable {
+            format!("c.\"{BLOCK_COLUMN}\" <= $1")
+        } else {
+            format!("c.{BLOCK_RANGE_COLUMN} @> $1")
+        };
+        let query = format!(
+            "select c.vid, ({data})::text as data \
+               from {} c \
+              where {visible} and c.vid > $2 \
+              order by c.vid \
+              limit $3",
+            self.qualified_name
+        );
+        let rows = sql_query(query)
+            .bind::<Integer, _>(block)
+            .bind::<BigInt, _>(after)
+            .bind::<BigInt, _>(limit)
+            .load::<Row>(conn)?;
+        Ok(rows.into_iter().map(|row| (row.vid, row.data)).collect())
+    }
+
+    /// Load up to `limit` rows from this table whose `vid` is bigger than
+    /// `after`, ordered by `vid`, for a deployment archive. Unlike
+    /// `export_page`, this includes all versions of each entity, and each
+    /// row is a JSON object with the `vid`, the block range, the causality
+    /// region and the entity data in `data`, which are all the information
+    /// that `restore_page` needs to recreate the row exactly
+    pub(crate) fn archive_page(
+        &self,
+        conn: &mut PgConnection,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<(i64, String)>, StoreError> {
+        use diesel::sql_types::BigInt;
+
+        #[derive(QueryableByName)]
+        struct Row {
+            #[diesel(sql_type = BigInt)]
+            vid: i64,
+            #[diesel(sql_type = Text)]
+            data: String,
+        }
+
+        let fields = self
+            .columns
+            .iter()
+            .map(|column| format!("'{}', {}", column.field, column.json_expr()))
+            .collect();
+        let data = Self::json_object(fields);
+
+        let block = if self.immutable {
+            format!("'block', c.\"{BLOCK_COLUMN}\"")
+        } else {
+            format!(
+                "'block_range', jsonb_build_array(lower(c.{BLOCK_RANGE_COLUMN}), upper(c.{BLOCK_RANGE_COLUMN}))"
+            )
+        };
+        let causality_region = if self.has_causality_region {
+            format!(", '{CAUSALITY_REGION_COLUMN}', c.{CAUSALITY_REGION_COLUMN}")
+        } else {
+            String::new()
+        };
+        let query = format!(
+            "select c.vid, \
+                    jsonb_build_object('vid', c.vid, {block}{causality_region}, 'data', ({data}))::text as data \
+               from {} c \
+              where c.vid > $1 \
+              order by c.vid \
+              limit $2",
+            self.qualified_name
+        );
+        let rows = sql_query(query)
+            .bind::<BigInt, _>(after)
+            .bind::<BigInt, _>(limit)
+            .load::<Row>(conn)?;
+        Ok(rows.into_iter().map(|row| (row.vid, row.data)).collect())
+    }
+
+    /// Insert the rows in `rows`, a JSON array of objects produced by
+    /// `archive_page`, into this table and return how many rows were
+    /// inserted. Fields that are missing from the entity data are set to
+    /// `null`
+    pub(crate) fn restore_page(
+        &self,
+        conn: &mut PgConnection,
+        rows: &str,
+    ) -> Result<usize, StoreError> {
+        let mut columns = vec![VID_COLUMN.to_string()];
+        let mut values = vec![format!("(r->>'{VID_COLUMN}')::int8")];
+        if self.immutable {
+            columns.push(format!("\"{BLOCK_COLUMN}\""));
+            values.push("(r->>'block')::int4".to_string());
+        } else {
+            columns.push(BLOCK_RANGE_COLUMN.to_string());
+            values.push(format!(
+                "int4range((r->'{BLOCK_RANGE_COLUMN}'->>0)::int4, (r->'{BLOCK_RANGE_COLUMN}'->>1)::int4)"
+            ));
+        }
+        if self.has_causality_region {
+            columns.push(CAUSALITY_REGION_COLUMN.to_string());
+            values.push(format!("(r->>'{CAUSALITY_REGION_COLUMN}')::int4"));
+        }
+        for column in &self.columns {
+            columns.push(column.name.quoted());
+            values.push(column.from_json_expr(&format!("r->'data'->'{}'", column.field)));
+        }
+
+        let query = format!(
+            "insert into {}({}) select {} from jsonb_array_elements($1::jsonb) r",
+            self.qualified_name,
+            columns.join(", "),
+            values.join(", ")
+        );
+        Ok(sql_query(query).bind::<Text, _>(rows).execute(conn)?)
+    }
+
+    /// Combine `fields`, a list of `'name', value` pairs, into one JSON
+    /// object. Postgres functions accept at most 100 arguments, and we
+    /// therefore build the object from chunks of fields
+    fn json_object(fields: Vec<String>) -> String {
+        fields
+            .chunks(50)
+            .map(|chunk| format!("jsonb_build_object({})", chunk.join(", ")))
+            .join(" || ")
+    }
 }
 
 #[derive(Clone)]
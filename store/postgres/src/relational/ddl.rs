@@ -14,7 +14,7 @@ use crate::relational::{
     VID_COLUMN,
 };
 
-use super::{Catalog, Column, Layout, SqlName, Table};
+use super::{rollup, Catalog, Column, Layout, SqlName, Table};
 
 // In debug builds (for testing etc.) unconditionally create exclusion constraints, in release
 // builds for production, skip them
@@ -294,17 +294,37 @@ impl Table {
             if !column.is_list() || ENV_VARS.store.create_gin_indexes {
                 write!(
                     out,
-                    "create index attr_{table_index}_{column_index}_{table_name}_{column_name}\n    on {qname} using {method}({index_expr});\n",
+                    "create index attr_{table_index}_{column_index}_{table_name}_{column_name}\n    on {qname} using {method}({index_expr}){cond};\n",
                     table_index = self.position,
                     table_name = self.name,
                     column_name = column.name,
                     qname = self.qualified_name,
+                    cond = self.attr_index_cond(column)?,
                 )?;
             }
         }
         writeln!(out)
     }
 
+    /// Return the `where` clause for the attribute index on `column` if
+    /// the field for it has an `@index(where: ..)` directive, and an empty
+    /// string otherwise. The predicate refers to fields, and we rewrite it
+    /// to refer to the corresponding columns
+    fn attr_index_cond(&self, column: &Column) -> Result<String, fmt::Error> {
+        let Some(pred) = self
+            .object
+            .field(column.field.as_str())
+            .and_then(|field| field.index_where())
+        else {
+            return Ok(String::new());
+        };
+        // Schema validation has checked the predicate, so this can only
+        // fail if the table does not match the schema. We can only return
+        // a generic formatting error in that case
+        let (pred, _) = rollup::rewrite(self, pred).map_err(|_| fmt::Error)?;
+        Ok(format!(" where {pred}"))
+    }
+
     /// If `self` is an aggregation and has cumulative aggregates, create an
     /// index on the dimensions. That supports the lookup of previous
     /// aggregation values we do in the rollup query since that filters by
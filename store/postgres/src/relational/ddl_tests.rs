@@ -223,6 +223,56 @@ fn forward_enum() {
     assert!(column.is_enum());
 }
 
+#[test]
+fn partial_attr_index() {
+    const GQL: &str = r#"
+    type Token @entity {
+        id: ID!,
+        active: Boolean!,
+        totalSupply: BigInt! @index(where: "active and totalSupply > 0")
+    }"#;
+
+    let layout = test_layout(GQL);
+    let sql = layout.as_ddl().expect("Failed to generate DDL");
+    let sql = sql.split_whitespace().join(" ");
+
+    // Only the index for `totalSupply` is partial
+    assert!(sql.contains(r#"using btree("active");"#));
+    assert!(sql.contains(r#"using btree("total_supply") where "active" AND "total_supply" > 0;"#));
+}
+
+#[test]
+fn abstract_types_are_inlined() {
+    const ABSTRACT_GQL: &str = r#"
+    type Timestamped @abstract {
+        createdAt: BigInt!,
+        updatedAt: BigInt
+    }
+
+    type Token @entity(extends: "Timestamped") {
+        id: ID!,
+        symbol: String!
+    }"#;
+
+    const EXPLICIT_GQL: &str = r#"
+    type Token @entity {
+        id: ID!,
+        symbol: String!,
+        createdAt: BigInt!,
+        updatedAt: BigInt
+    }"#;
+
+    // The base type's fields become ordinary columns of the entity's table
+    let sql = test_layout(ABSTRACT_GQL)
+        .as_ddl()
+        .expect("Failed to generate DDL");
+    let expected = test_layout(EXPLICIT_GQL)
+        .as_ddl()
+        .expect("Failed to generate DDL");
+    check_eqv(&expected, &sql);
+    assert!(!sql.contains("timestamped"));
+}
+
 #[test]
 fn can_copy_from() {
     let source = test_layout(THING_GQL);
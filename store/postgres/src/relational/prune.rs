@@ -3,21 +3,24 @@ use std::{fmt::Write, sync::Arc, time::Instant};
 use diesel::{
     connection::SimpleConnection,
     sql_query,
-    sql_types::{BigInt, Integer},
+    sql_types::{BigInt, Integer, Timestamptz},
     Connection, PgConnection, RunQueryDsl,
 };
 use graph::{
+    blockchain::BlockTime,
     components::store::{PrunePhase, PruneReporter, PruneRequest, PruningStrategy, VersionStats},
+    env::ENV_VARS,
     prelude::{
         BlockNumber, CancelHandle, CancelToken, CancelableError, CheapClone, StoreError,
         BLOCK_NUMBER_MAX,
     },
-    schema::InputSchema,
-    slog::{warn, Logger},
+    schema::{kw, InputSchema, Retention},
+    slog::{debug, warn, Logger},
 };
 use itertools::Itertools;
 
 use crate::{
+    block_range::BLOCK_COLUMN,
     catalog,
     copy::AdaptiveBatchSize,
     deployment,
@@ -384,6 +387,15 @@ impl Layout {
     /// also block queries to the deployment, often for extended periods of
     /// time. The rebuild strategy never blocks reads, it only ever blocks
     /// writes.
+    ///
+    /// Each table is pruned on its own, and every table that has been
+    /// pruned completely is recorded in a checkpoint. If pruning is
+    /// interrupted, a later call with the same `req.earliest_block` skips
+    /// the tables that have already been pruned. Since the deployment's
+    /// earliest block is set before pruning starts and a table is only
+    /// ever replaced by a copy that has all the data for blocks from
+    /// `req.earliest_block` on, tables that have and have not been
+    /// pruned can coexist without affecting queries.
     pub fn prune(
         &self,
         logger: &Logger,
@@ -398,6 +410,20 @@ impl Layout {
 
         let prunable_tables: Vec<_> = self.prunable_tables(&stats, req).into_iter().collect();
 
+        // Tables that an earlier, interrupted run with the same
+        // `earliest_block` already pruned do not need to be pruned again
+        let pruned = catalog::pruned_tables(conn, &self.site, req.earliest_block)?;
+        let table_names: Vec<_> = prunable_tables
+            .iter()
+            .map(|(table, _)| table.name.as_str())
+            .collect();
+        let resumed: Vec<_> = table_names
+            .iter()
+            .copied()
+            .filter(|table| pruned.contains(*table))
+            .collect();
+        reporter.start_tables(&table_names, &resumed);
+
         // create a shadow namespace where we will put the copies of our
         // tables, but only create it in the database if we really need it
         let dst_nsp = Namespace::prune(self.site.id);
@@ -414,6 +440,9 @@ impl Layout {
         // stays final even if a revert happens during this loop, but that
         // is the definition of 'final'
         for (table, strat) in &prunable_tables {
+            if pruned.contains(table.name.as_str()) {
+                continue;
+            }
             reporter.start_table(table.name.as_str());
             match strat {
                 PruningStrategy::Rebuild => {
@@ -487,6 +516,10 @@ impl Layout {
                     }
                 }
             }
+            // Pruning a table again is harmless, so it is fine if we get
+            // interrupted before recording that we are done with it
+            catalog::set_last_pruned_block(conn, &self.site, &table.name, req.earliest_block)?;
+            catalog::checkpoint_pruned_table(conn, &self.site, &table.name, req.earliest_block)?;
             reporter.finish_table(table.name.as_str());
         }
         // Get rid of the temporary prune schema if we actually created it
@@ -494,9 +527,7 @@ impl Layout {
             catalog::drop_schema(conn, dst_nsp.as_str())?;
         }
 
-        for (table, _) in &prunable_tables {
-            catalog::set_last_pruned_block(conn, &self.site, &table.name, req.earliest_block)?;
-        }
+        catalog::clear_prune_checkpoint(conn, &self.site)?;
 
         // Analyze the new tables
         let tables = prunable_tables.iter().map(|(table, _)| *table).collect();
@@ -506,4 +537,90 @@ impl Layout {
 
         Ok(())
     }
+
+    /// Return `true` if any timeseries in this layout has a `retention`
+    pub(crate) fn has_timeseries_retention(&self) -> bool {
+        self.tables
+            .values()
+            .any(|table| Self::retention(table).is_some())
+    }
+
+    fn retention(table: &Table) -> Option<Retention> {
+        table
+            .object
+            .object_type()
+            .ok()
+            .and_then(|obj_type| obj_type.retention)
+    }
+
+    /// Delete the data points of timeseries with a `retention` that are
+    /// outside their retention window when the subgraph is at `block`
+    /// with block time `block_time`.
+    ///
+    /// A data point is only deleted once it is final and every bucket
+    /// that it belongs to has been rolled up at a final block, so that
+    /// neither a revert nor a rollup can ever need it again. We find the
+    /// latest data point that was added at a final block; buckets of the
+    /// largest interval of any aggregation over the timeseries that end
+    /// before it have been rolled up when that data point was written,
+    /// and data points in them can go.
+    pub(crate) fn prune_timeseries(
+        &self,
+        logger: &Logger,
+        conn: &mut PgConnection,
+        block: BlockNumber,
+        block_time: BlockTime,
+    ) -> Result<(), StoreError> {
+        let final_block = block - ENV_VARS.reorg_threshold;
+
+        for table in self.tables.values() {
+            let retention = match Self::retention(table) {
+                Some(retention) => retention,
+                None => continue,
+            };
+            let ts = &table.column_for_field(kw::TIMESTAMP)?.name;
+            let qname = &table.qualified_name;
+
+            let interval = self
+                .input_schema
+                .agg_mappings()
+                .filter(|mapping| mapping.source_type(&self.input_schema) == table.object)
+                .map(|mapping| mapping.interval)
+                .max();
+            let rolled_up = match interval {
+                Some(interval) => format!(
+                    "date_bin('{}s', max({ts}), 'epoch'::timestamptz)",
+                    interval.as_duration().as_secs()
+                ),
+                None => format!("max({ts})"),
+            };
+
+            let query = format!(
+                "/* controller=prune,phase=retention */ \
+                 delete from {qname} \
+                  where {ts} < (select {rolled_up} from {qname} where {BLOCK_COLUMN} <= $1)"
+            );
+            let rows = match retention {
+                Retention::Blocks(blocks) => sql_query(format!("{query} and {BLOCK_COLUMN} <= $2"))
+                    .bind::<Integer, _>(final_block)
+                    .bind::<Integer, _>(block - blocks)
+                    .execute(conn)?,
+                Retention::Duration(duration) => {
+                    let cutoff = BlockTime::since_epoch(
+                        block_time.as_secs_since_epoch() - duration.as_secs() as i64,
+                        0,
+                    );
+                    sql_query(format!("{query} and {ts} < $2"))
+                        .bind::<Integer, _>(final_block)
+                        .bind::<Timestamptz, _>(cutoff)
+                        .execute(conn)?
+                }
+            };
+            if rows > 0 {
+                debug!(logger, "Removed timeseries data points outside the retention window";
+                       "table" => table.name.as_str(), "rows" => rows);
+            }
+        }
+        Ok(())
+    }
 }
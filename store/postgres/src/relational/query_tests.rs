@@ -2,6 +2,8 @@ use std::{collections::BTreeSet, sync::Arc};
 
 use diesel::{debug_query, pg::Pg};
 use graph::{
+    components::store::write::{EntityModification, RowGroup},
+    entity,
     prelude::{r, serde_json as json, DeploymentHash, EntityFilter},
     schema::InputSchema,
 };
@@ -9,7 +11,7 @@ use graph::{
 use crate::{
     layout_for_tests::{make_dummy_site, Namespace},
     relational::{Catalog, ColumnType, Layout},
-    relational_queries::FromColumnValue,
+    relational_queries::{cap_chunk_size, FromColumnValue, InsertQuery},
 };
 
 use crate::relational_queries::Filter;
@@ -85,3 +87,93 @@ fn prefix() {
     let filter = EntityFilter::In("address".to_string(), vec!["0xbeef".into()]);
     filter_contains(filter, r#"substring(c."address", 1, 64) in ($1)"#);
 }
+
+#[test]
+fn fulltext_weights() {
+    const SCHEMA: &str = r#"
+    type _Schema_ @fulltext(
+        name: "search"
+        language: en
+        algorithm: rank
+        include: [
+            {
+                entity: "Thing",
+                fields: [{ name: "name", weight: A }, { name: "description" }]
+            }
+        ]
+    )
+
+    type Thing @entity {
+        id: ID!,
+        name: String!,
+        description: String!
+    }"#;
+    let layout = test_layout(SCHEMA);
+    let entity_type = layout.input_schema.entity_type("Thing").unwrap();
+    let table = layout.table_for_entity(&entity_type).unwrap();
+
+    let entity = entity! { layout.input_schema =>
+        id: "1",
+        name: "Bibi",
+        description: "A thing",
+    };
+    let mut group = RowGroup::new(entity_type.clone(), false);
+    group
+        .push(
+            EntityModification::insert(entity_type.parse_key("1").unwrap(), entity, 1),
+            1,
+        )
+        .unwrap();
+    let chunk = group.write_chunks(10).next().unwrap();
+    let query = InsertQuery::new(table.as_ref(), &chunk).unwrap();
+    let sql = debug_query::<Pg, _>(&query).to_string();
+
+    // Only the field with a weight gets labeled; the other one keeps the
+    // default weight as before
+    assert_eq!(2, sql.matches("to_tsvector('english', $").count(), "{sql}");
+    assert_eq!(1, sql.matches("setweight(to_tsvector(").count(), "{sql}");
+    assert!(sql.contains("), 'A')"), "{sql}");
+}
+
+#[test]
+fn max_rows_per_insert_limits_chunks() {
+    const SCHEMA: &str = "
+    type Thing @entity {
+        id: ID!,
+        name: String!
+    }";
+    let layout = test_layout(SCHEMA);
+    let entity_type = layout.input_schema.entity_type("Thing").unwrap();
+    let table = layout.table_for_entity(&entity_type).unwrap();
+
+    assert_eq!(1000, cap_chunk_size(1000, None));
+    assert_eq!(2, cap_chunk_size(1000, Some(2)));
+    assert_eq!(1000, cap_chunk_size(1000, Some(5000)));
+
+    let mut group = RowGroup::new(entity_type.clone(), false);
+    for i in 0..5 {
+        let id = i.to_string();
+        let entity = entity! { layout.input_schema =>
+            id: id.as_str(),
+            name: format!("thing {i}"),
+        };
+        group
+            .push(
+                EntityModification::insert(entity_type.parse_key(id.as_str()).unwrap(), entity, 1),
+                1,
+            )
+            .unwrap();
+    }
+
+    let chunk_size = cap_chunk_size(InsertQuery::chunk_size(table.as_ref()), Some(2));
+    let rows: Vec<_> = group
+        .write_chunks(chunk_size)
+        .map(|chunk| {
+            let query = InsertQuery::new(table.as_ref(), &chunk).unwrap();
+            let sql = debug_query::<Pg, _>(&query).to_string();
+            // Rows in the `values` list are separated by `,\n`
+            sql.matches("),\n(").count() + 1
+        })
+        .collect();
+    assert_eq!(vec![2, 2, 1], rows);
+}
@@ -77,7 +77,10 @@ use super::{Column, SqlName};
 
 /// Rewrite `expr` by replacing field names with column names and return the
 /// rewritten SQL expression and the columns used in the expression
-fn rewrite<'a>(table: &'a Table, expr: &str) -> Result<(String, Vec<&'a str>), StoreError> {
+pub(super) fn rewrite<'a>(
+    table: &'a Table,
+    expr: &str,
+) -> Result<(String, Vec<&'a str>), StoreError> {
     struct Rewriter<'a> {
         table: &'a Table,
         // All columns used in the expression
@@ -170,6 +173,7 @@ impl<'a> Agg<'a> {
             Sum => write!(w, "sum({})", src)?,
             Max => write!(w, "max({})", src)?,
             Min => write!(w, "min({})", src)?,
+            Avg => write!(w, "avg({})", src)?,
             First => {
                 let sql_type = self.agg_column.column_type.sql_type();
                 write!(w, "arg_min_{}(({}, {time}))", sql_type, src)?
@@ -199,9 +203,11 @@ impl<'a> Agg<'a> {
         use AggregateFn::*;
 
         match self.aggregate.func {
-            Sum | Max | Min | First | Last => {
+            Sum | Max | Min | First | Last | Avg => {
                 // For these, combining and aggregating is done by the same
-                // function
+                // function. For `avg`, that is only correct because it can
+                // not be cumulative, and we therefore only ever combine the
+                // average for the bucket with a `null`
                 let name = format!("\"{}\"", self.agg_column.name);
                 return self.aggregate_over(&name, time, w);
             }
@@ -592,6 +598,15 @@ mod tests {
         total_count: Int8! @aggregate(fn: "count", cumulative: true)
         total_sum: BigDecimal! @aggregate(fn: "sum", arg: "amount", cumulative: true)
       }
+
+      type Prices @aggregation(intervals: ["day"], source: "Data") {
+        id: Int8!
+        timestamp: Timestamp!
+        token: Bytes!
+        avg: BigDecimal! @aggregate(fn: "avg", arg: "price")
+        low: BigDecimal! @aggregate(fn: "min", arg: "price")
+        high: BigDecimal! @aggregate(fn: "max", arg: "price")
+      }
       "#;
 
         const STATS_HOUR_SQL: &str = r#"\
@@ -664,6 +679,16 @@ mod tests {
         select id, timestamp, $3 as block$, "count", "sum", "total_count", "total_sum" from combined
         "#;
 
+        const PRICES_SQL: &str = r#"\
+        insert into "sgd007"."prices_day"(id, timestamp, block$, "token", "avg", "low", "high") \
+        select max(id) as id, timestamp, $3, "token", avg("price") as "avg", \
+               min("price") as "low", max("price") as "high" from (\
+            select id, date_bin('86400s', timestamp, 'epoch'::timestamptz) as timestamp, "token", "price" \
+              from "sgd007"."data" \
+             where "sgd007"."data".timestamp >= $1 and "sgd007"."data".timestamp < $2 \
+             order by "sgd007"."data".timestamp) data \
+        group by timestamp, "token""#;
+
         #[track_caller]
         fn rollup_for<'a>(layout: &'a Layout, table_name: &str) -> &'a Rollup {
             layout
@@ -679,7 +704,7 @@ mod tests {
         let site = Arc::new(make_dummy_site(hash, nsp, "rollup".to_string()));
         let catalog = Catalog::for_tests(site.clone(), BTreeSet::new()).unwrap();
         let layout = Layout::new(site, &schema, catalog).unwrap();
-        assert_eq!(5, layout.rollups.len());
+        assert_eq!(6, layout.rollups.len());
 
         // Intervals are non-decreasing
         assert!(layout.rollups[0].interval <= layout.rollups[1].interval);
@@ -698,5 +723,8 @@ mod tests {
 
         let lifetime = rollup_for(&layout, "lifetime_day");
         check_eqv(LIFETIME_SQL, &lifetime.insert_sql);
+
+        let prices = rollup_for(&layout, "prices_day");
+        check_eqv(PRICES_SQL, &prices.insert_sql);
     }
 }
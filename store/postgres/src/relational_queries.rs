@@ -10,7 +10,9 @@ use diesel::query_builder::{AstPass, Query, QueryFragment, QueryId};
 use diesel::query_dsl::RunQueryDsl;
 use diesel::result::{Error as DieselError, QueryResult};
 use diesel::sql_types::Untyped;
-use diesel::sql_types::{Array, BigInt, Binary, Bool, Int8, Integer, Jsonb, Text, Timestamptz};
+use diesel::sql_types::{
+    Array, BigInt, Binary, Bool, Int8, Integer, Jsonb, Nullable, Text, Timestamptz,
+};
 use graph::components::store::write::{EntityWrite, WriteChunk};
 use graph::components::store::{Child as StoreChild, DerivedEntityQuery};
 use graph::data::store::{Id, IdType, NULL};
@@ -22,7 +24,7 @@ use graph::prelude::{
     EntityLink, EntityOrder, EntityOrderByChild, EntityOrderByChildInfo, EntityRange, EntityWindow,
     ParentLink, QueryExecutionError, StoreError, Value, ENV_VARS,
 };
-use graph::schema::{EntityKey, EntityType, FulltextAlgorithm, FulltextConfig, InputSchema};
+use graph::schema::{EntityKey, EntityType, FulltextAlgorithm, InputSchema};
 use graph::{components::store::AttributeNames, data::store::scalar};
 use inflector::Inflector;
 use itertools::Itertools;
@@ -749,7 +751,9 @@ impl<'a> QueryFragment<Pg> for QueryValue<'a> {
                     }
                     // TSVector will only be in a Value::List() for inserts so "to_tsvector" can always be used here
                     ColumnType::TSVector(config) => {
-                        process_vec_ast(values, &mut out, config.language.as_sql())?;
+                        let language = config.language.as_sql();
+                        let values = values.iter().map(|value| (value, language, None));
+                        process_vec_ast(values, &mut out)?;
                         Ok(())
                     }
                     ColumnType::BigDecimal | ColumnType::BigInt => {
@@ -774,25 +778,37 @@ impl<'a> QueryFragment<Pg> for QueryValue<'a> {
     }
 }
 
-fn process_vec_ast<'a, T: diesel::serialize::ToSql<Text, Pg>>(
-    values: &'a Vec<T>,
+/// Generate a tsvector from `values`, where each value is turned into a
+/// tsvector with the language config that comes with it, and labeled with
+/// its weight if it has one
+fn process_vec_ast<'a, T: diesel::serialize::ToSql<Text, Pg> + 'a>(
+    values: impl IntoIterator<Item = (&'a T, &'static str, Option<&'static str>)>,
     out: &mut AstPass<'_, 'a, Pg>,
-    sql_language: &str,
 ) -> Result<(), DieselError> {
-    if values.is_empty() {
+    let mut values = values.into_iter().peekable();
+    if values.peek().is_none() {
         out.push_sql("''::tsvector");
     } else {
         out.push_sql("(");
-        for (i, value) in values.iter().enumerate() {
+        for (i, (value, sql_language, sql_weight)) in values.enumerate() {
             if i > 0 {
-                out.push_sql(") || ");
+                out.push_sql(" || ");
+            }
+            if sql_weight.is_some() {
+                out.push_sql("setweight(");
             }
             out.push_sql("to_tsvector(");
             out.push_sql(sql_language);
             out.push_sql(", ");
             out.push_bind_param::<Text, _>(value)?;
+            out.push_sql(")");
+            if let Some(sql_weight) = sql_weight {
+                out.push_sql(", ");
+                out.push_sql(sql_weight);
+                out.push_sql(")");
+            }
         }
-        out.push_sql("))");
+        out.push_sql(")");
     }
     Ok(())
 }
@@ -1146,12 +1162,14 @@ impl<'a> QueryFragment<Pg> for PrefixComparison<'a> {
 
 /// A child filter on `parent_table`. The join between the tables happens
 /// along `parent_column = child_column` and the `child_table` must be
-/// filtered with `child_filter``
+/// filtered with `child_filter``. For fields that are derived from several
+/// fields, the join needs `parent_column` to equal every one of
+/// `child_columns`
 #[derive(Debug)]
 pub struct QueryChild<'a> {
     parent_column: &'a Column,
     child_table: &'a Table,
-    child_column: &'a Column,
+    child_columns: Vec<&'a Column>,
     child_filter: Filter<'a>,
     derived: bool,
     br_column: BlockRangeColumn<'a>,
@@ -1168,27 +1186,29 @@ impl<'a> QueryChild<'a> {
 
         let StoreChild {
             attr,
+            more_attrs,
             entity_type,
             filter,
             derived,
         } = child;
         let derived = *derived;
         let child_table = layout.table_for_entity(entity_type)?;
-        let (parent_column, child_column) = if derived {
-            // If the parent is derived, the child column is picked based on
-            // the provided attribute and the parent column is the primary
-            // key of the parent table
-            (
-                parent_table.primary_key(),
-                child_table.column_for_field(attr)?,
-            )
+        let (parent_column, child_columns) = if derived {
+            // If the parent is derived, the child columns are picked based
+            // on the provided attributes and the parent column is the
+            // primary key of the parent table
+            let child_columns = std::iter::once(attr)
+                .chain(more_attrs.iter())
+                .map(|attr| child_table.column_for_field(attr))
+                .collect::<Result<Vec<_>, _>>()?;
+            (parent_table.primary_key(), child_columns)
         } else {
             // If the parent is not derived, we do the opposite. The parent
             // column is picked based on the provided attribute and the
             // child column is the primary key of the child table
             (
                 parent_table.column_for_field(attr)?,
-                child_table.primary_key(),
+                vec![child_table.primary_key()],
             )
         };
         let br_column = BlockRangeColumn::new(child_table, CHILD_PREFIX, block);
@@ -1197,7 +1217,7 @@ impl<'a> QueryChild<'a> {
         Ok(Self {
             parent_column,
             child_table,
-            child_column,
+            child_columns,
             child_filter,
             derived,
             br_column,
@@ -1212,7 +1232,7 @@ impl<'a> QueryFragment<Pg> for QueryChild<'a> {
         let QueryChild {
             parent_column,
             child_table,
-            child_column,
+            child_columns,
             child_filter,
             derived,
             br_column,
@@ -1232,24 +1252,30 @@ impl<'a> QueryFragment<Pg> for QueryChild<'a> {
 
         // Join tables
         if derived {
-            if child_column.is_list() {
-                // Type A: c.id = any(i.{parent_field})
-                out.push_sql(parent_prefix);
-                out.push_identifier(parent_column.name.as_str())?;
-                out.push_sql(" = any(");
-                out.push_sql(child_prefix);
-                out.push_identifier(child_column.name.as_str())?;
-                out.push_sql(")");
-            } else {
-                // Type B: c.id = i.{parent_field}
-                out.push_sql(parent_prefix);
-                out.push_identifier(parent_column.name.as_str())?;
-                out.push_sql(" = ");
-                out.push_sql(child_prefix);
-                out.push_identifier(child_column.name.as_str())?;
+            for (i, child_column) in child_columns.iter().enumerate() {
+                if i > 0 {
+                    out.push_sql(" and ");
+                }
+                if child_column.is_list() {
+                    // Type A: c.id = any(i.{parent_field})
+                    out.push_sql(parent_prefix);
+                    out.push_identifier(parent_column.name.as_str())?;
+                    out.push_sql(" = any(");
+                    out.push_sql(child_prefix);
+                    out.push_identifier(child_column.name.as_str())?;
+                    out.push_sql(")");
+                } else {
+                    // Type B: c.id = i.{parent_field}
+                    out.push_sql(parent_prefix);
+                    out.push_identifier(parent_column.name.as_str())?;
+                    out.push_sql(" = ");
+                    out.push_sql(child_prefix);
+                    out.push_identifier(child_column.name.as_str())?;
+                }
             }
         } else {
             is_type_c_or_d = true;
+            let child_column = child_columns[0];
 
             if parent_column.is_list() {
                 // Type C: i.id = any(c.child_ids)
@@ -1879,7 +1905,11 @@ impl<'a> fmt::Display for Filter<'a> {
             Child(child /* a, et, cf, _ */) => write!(
                 f,
                 "join on {} with {}({})",
-                child.child_column.name(),
+                child
+                    .child_columns
+                    .iter()
+                    .map(|col| col.name())
+                    .join(" and "),
                 child.child_table.name,
                 child.child_filter
             ),
@@ -1978,6 +2008,112 @@ impl<'a> Query for FindQuery<'a> {
 
 impl<'a, Conn> RunQueryDsl<Conn> for FindQuery<'a> {}
 
+/// The data for one version of an entity as returned by
+/// `FindHistoryQuery`
+#[derive(QueryableByName, Debug)]
+pub struct EntityVersionData {
+    #[diesel(sql_type = Text)]
+    entity: String,
+    #[diesel(sql_type = Jsonb)]
+    data: serde_json::Value,
+    #[diesel(sql_type = Integer)]
+    pub block_start: BlockNumber,
+    #[diesel(sql_type = Nullable<Integer>)]
+    pub block_end: Option<BlockNumber>,
+}
+
+impl EntityVersionData {
+    pub fn into_entity_data(self) -> (EntityData, BlockNumber, Option<BlockNumber>) {
+        let data = EntityData {
+            entity: self.entity,
+            data: self.data,
+        };
+        (data, self.block_start, self.block_end)
+    }
+}
+
+/// A query that finds all versions of an entity that were created at or
+/// before `block`, ordered by the block at which they were created. Only
+/// makes sense for mutable entity types. Used for history queries.
+#[derive(Debug, Clone)]
+pub struct FindHistoryQuery<'a> {
+    table: &'a Table,
+    id: &'a Id,
+    block: BlockNumber,
+    first: i64,
+    skip: i64,
+}
+
+impl<'a> FindHistoryQuery<'a> {
+    pub fn new(table: &'a Table, id: &'a Id, block: BlockNumber, first: u32, skip: u32) -> Self {
+        Self {
+            table,
+            id,
+            block,
+            first: first as i64,
+            skip: skip as i64,
+        }
+    }
+}
+
+impl<'a> QueryFragment<Pg> for FindHistoryQuery<'a> {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+
+        // Generate
+        //    select '..' as entity, to_jsonb(e.*) as data,
+        //           lower(e.block_range) as block_start,
+        //           case when upper_inf(e.block_range)
+        //                  or upper(e.block_range) > $block then null
+        //                else upper(e.block_range) end as block_end
+        //      from schema.table e
+        //     where id = $1 and lower(e.block_range) <= $block
+        //     order by lower(e.block_range)
+        //     limit $first offset $skip
+        out.push_sql("select ");
+        out.push_bind_param::<Text, _>(self.table.object.as_str())?;
+        out.push_sql(" as entity, to_jsonb(e.*) as data,\n");
+        out.push_sql("       lower(e.");
+        out.push_sql(BLOCK_RANGE_COLUMN);
+        out.push_sql(") as block_start,\n");
+        out.push_sql("       case when upper_inf(e.");
+        out.push_sql(BLOCK_RANGE_COLUMN);
+        out.push_sql(") or upper(e.");
+        out.push_sql(BLOCK_RANGE_COLUMN);
+        out.push_sql(") > ");
+        out.push_bind_param::<Integer, _>(&self.block)?;
+        out.push_sql(" then null else upper(e.");
+        out.push_sql(BLOCK_RANGE_COLUMN);
+        out.push_sql(") end as block_end\n");
+        out.push_sql("  from ");
+        out.push_sql(self.table.qualified_name.as_str());
+        out.push_sql(" e\n where ");
+        self.table.primary_key().eq(self.id, &mut out)?;
+        out.push_sql(" and lower(e.");
+        out.push_sql(BLOCK_RANGE_COLUMN);
+        out.push_sql(") <= ");
+        out.push_bind_param::<Integer, _>(&self.block)?;
+        out.push_sql("\n order by lower(e.");
+        out.push_sql(BLOCK_RANGE_COLUMN);
+        out.push_sql(")\n limit ");
+        out.push_bind_param::<BigInt, _>(&self.first)?;
+        out.push_sql(" offset ");
+        out.push_bind_param::<BigInt, _>(&self.skip)
+    }
+}
+
+impl<'a> QueryId for FindHistoryQuery<'a> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<'a> Query for FindHistoryQuery<'a> {
+    type SqlType = Untyped;
+}
+
+impl<'a, Conn> RunQueryDsl<Conn> for FindHistoryQuery<'a> {}
+
 /// Builds a query over a given set of [`Table`]s in an attempt to find updated
 /// and/or newly inserted entities at a given block number; i.e. such that the
 /// block range's lower bound is equal to said block number.
@@ -2251,15 +2387,20 @@ impl<'a, Conn> RunQueryDsl<Conn> for FindDerivedQuery<'a> {}
 #[derive(Debug)]
 enum InsertValue<'a> {
     Value(QueryValue<'a>),
-    Fulltext(Vec<&'a String>, &'a FulltextConfig),
+    /// The values of the included fields together with the language config
+    /// and the weight for each of them
+    Fulltext(Vec<(&'a String, &'static str, Option<&'static str>)>),
 }
 
 impl<'a> QueryFragment<Pg> for InsertValue<'a> {
     fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
         match self {
             InsertValue::Value(qv) => qv.walk_ast(out),
-            InsertValue::Fulltext(qvs, config) => {
-                process_vec_ast(qvs, &mut out, config.language.as_sql())?;
+            InsertValue::Fulltext(qvs) => {
+                let values = qvs
+                    .iter()
+                    .map(|(value, language, weight)| (*value, *language, *weight));
+                process_vec_ast(values, &mut out)?;
                 Ok(())
             }
         }
@@ -2284,22 +2425,26 @@ impl<'a> InsertRow<'a> {
         let mut values = Vec::with_capacity(columns.len());
         for column in columns {
             let iv = if let Some(fields) = column.fulltext_fields.as_ref() {
+                let config = match &column.column_type {
+                    ColumnType::TSVector(config) => config,
+                    _ => return Err(StoreError::FulltextColumnMissingConfig),
+                };
                 let fulltext_field_values: Vec<_> = fields
                     .iter()
-                    .filter_map(|field| row.entity.get(field))
-                    .map(|value| match value {
-                        Value::String(s) => Ok(s),
+                    .filter_map(|field| row.entity.get(field).map(|value| (field, value)))
+                    .map(|(field, value)| match value {
+                        Value::String(s) => Ok((
+                            s,
+                            config.language_for(field).as_sql(),
+                            config.weight_for(field).map(|weight| weight.as_sql()),
+                        )),
                         _ => Err(constraint_violation!(
                             "fulltext fields must be strings but got {:?}",
                             value
                         )),
                     })
                     .collect::<Result<_, _>>()?;
-                if let ColumnType::TSVector(config) = &column.column_type {
-                    InsertValue::Fulltext(fulltext_field_values, &config)
-                } else {
-                    return Err(StoreError::FulltextColumnMissingConfig);
-                }
+                InsertValue::Fulltext(fulltext_field_values)
             } else {
                 let value = row.entity.get(&column.field).unwrap_or(&NULL);
                 let qv = QueryValue::new(value, &column.column_type)?;
@@ -2374,7 +2519,8 @@ impl<'a> InsertQuery<'a> {
     /// invocation of `InsertQuery`. The number makes it so that we do not
     /// exceed the maximum number of bind variables that can be used in a
     /// query, and depends on what columns `table` has and how they get put
-    /// into the query
+    /// into the query. It is further limited by
+    /// `GRAPH_STORE_MAX_ROWS_PER_INSERT` if that is set
     pub fn chunk_size(table: &Table) -> usize {
         let mut count = 1;
         for column in table.columns.iter() {
@@ -2389,7 +2535,20 @@ impl<'a> InsertQuery<'a> {
                 count += 1
             }
         }
-        POSTGRES_MAX_PARAMETERS / count
+        cap_chunk_size(
+            POSTGRES_MAX_PARAMETERS / count,
+            ENV_VARS.store.max_rows_per_insert,
+        )
+    }
+}
+
+/// Lower `chunk_size` to `max_rows` if that is set; this is how
+/// `GRAPH_STORE_MAX_ROWS_PER_INSERT` gets applied to all statements that
+/// write several rows at once
+pub(crate) fn cap_chunk_size(chunk_size: usize, max_rows: Option<usize>) -> usize {
+    match max_rows {
+        Some(max_rows) => chunk_size.min(max_rows),
+        None => chunk_size,
     }
 }
 
@@ -2543,6 +2702,8 @@ impl ParentIds {
 #[derive(Debug, Clone)]
 enum TableLink<'a> {
     Direct(&'a Column, ChildMultiplicity),
+    /// The parent id is stored in all of these columns
+    Composite(Vec<&'a Column>, ChildMultiplicity),
     /// The `Table` is the parent table
     Parent(&'a Table, ParentIds),
 }
@@ -2558,6 +2719,13 @@ impl<'a> TableLink<'a> {
                 let column = child_table.column_for_field(attribute.name())?;
                 Ok(TableLink::Direct(column, multiplicity))
             }
+            EntityLink::Composite(attributes, multiplicity) => {
+                let columns = attributes
+                    .iter()
+                    .map(|attribute| child_table.column_for_field(attribute.name()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(TableLink::Composite(columns, multiplicity))
+            }
             EntityLink::Parent(parent_type, parent_link) => {
                 let parent_table = layout.table_for_entity(&parent_type)?;
                 Ok(TableLink::Parent(
@@ -2669,6 +2837,9 @@ impl<'a> FilterWindow<'a> {
     fn parent_type(&self) -> QueryResult<IdType> {
         match &self.link {
             TableLink::Direct(column, _) => column.column_type.id_type(),
+            // Schema validation ensures that all columns reference the
+            // parent type and therefore have the same id type
+            TableLink::Composite(columns, _) => columns[0].column_type.id_type(),
             TableLink::Parent(parent_table, _) => parent_table.primary_key().column_type.id_type(),
         }
     }
@@ -2826,6 +2997,88 @@ impl<'a> FilterWindow<'a> {
         Ok(())
     }
 
+    /// Generate the condition that `p.id` is stored in all of `columns`
+    fn all_columns_match(columns: &[&Column], out: &mut AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql(" and (");
+        for (i, column) in columns.iter().enumerate() {
+            if i > 0 {
+                out.push_sql(" and ");
+            }
+            if column.is_list() {
+                out.push_sql("p.id = any(c.");
+                out.push_identifier(column.name.as_str())?;
+                out.push_sql(")");
+            } else {
+                out.push_sql("p.id = c.");
+                out.push_identifier(column.name.as_str())?;
+            }
+        }
+        out.push_sql(")");
+        Ok(())
+    }
+
+    fn children_composite<'b>(
+        &'b self,
+        columns: &[&Column],
+        is_outer: bool,
+        limit: &'b ParentLimit<'_>,
+        out: &mut AstPass<'_, 'b, Pg>,
+    ) -> QueryResult<()> {
+        // Generate
+        //      from unnest({parent_ids}) as p(id)
+        //           cross join lateral
+        //           (select {column names}
+        //              from children c
+        //             where (p.id = c.{parent_field1}
+        //                    and p.id = any(c.{parent_field2}) ..)
+        //               and .. other conditions on c ..
+        //             order by c.{sort_key}
+        //             limit {first} offset {skip}) c
+        //     order by c.{sort_key}
+
+        out.push_sql("\n/* children_composite */  from unnest(");
+        self.ids.push_bind_param(out)?;
+        out.push_sql(") as p(id) cross join lateral (select ");
+        write_column_names(&self.column_names, self.table, None, out)?;
+        out.push_sql(" from ");
+        out.push_sql(self.table.qualified_name.as_str());
+        out.push_sql(" c where ");
+        self.br_column.contains(out, false)?;
+        limit.filter(is_outer, out);
+        Self::all_columns_match(columns, out)?;
+        self.and_filter(out)?;
+        limit.restrict(is_outer, out)?;
+        out.push_sql(") c");
+        Ok(())
+    }
+
+    fn child_composite<'b>(
+        &'b self,
+        columns: &[&Column],
+        is_outer: bool,
+        limit: &'b ParentLimit<'_>,
+        out: &mut AstPass<'_, 'b, Pg>,
+    ) -> QueryResult<()> {
+        // Generate
+        //      from unnest({parent_ids}) as p(id), children c
+        //     where (p.id = c.{parent_field1}
+        //            and p.id = any(c.{parent_field2}) ..)
+        //       and .. other conditions on c ..
+        //     limit {parent_ids.len} + 1
+
+        out.push_sql("\n/* child_composite */  from unnest(");
+        self.ids.push_bind_param(out)?;
+        out.push_sql(") as p(id), ");
+        out.push_sql(self.table.qualified_name.as_str());
+        out.push_sql(" c where ");
+        self.br_column.contains(out, false)?;
+        limit.filter(is_outer, out);
+        Self::all_columns_match(columns, out)?;
+        self.and_filter(out)?;
+        limit.single_limit(is_outer, self.ids.len(), out);
+        Ok(())
+    }
+
     fn children_type_c<'b>(
         &'b self,
         child_ids: &'b [IdList],
@@ -2956,6 +3209,10 @@ impl<'a> FilterWindow<'a> {
                     }
                 }
             }
+            TableLink::Composite(columns, multiplicity) => match multiplicity {
+                ChildMultiplicity::Many => self.children_composite(columns, is_outer, limit, out),
+                ChildMultiplicity::Single => self.child_composite(columns, is_outer, limit, out),
+            },
             TableLink::Parent(_, ParentIds::List(child_ids)) => {
                 self.children_type_c(child_ids, is_outer, limit, out)
             }
@@ -3077,6 +3334,18 @@ impl<'a> fmt::Display for FilterCollection<'a> {
                     TableLink::Direct(col, Many) => {
                         write!(f, "many:{}={}", col.name(), ids.join(","))?
                     }
+                    TableLink::Composite(cols, Single) => write!(
+                        f,
+                        "uniq:{}={}",
+                        cols.iter().map(|col| col.name()).join("&"),
+                        ids.join(",")
+                    )?,
+                    TableLink::Composite(cols, Many) => write!(
+                        f,
+                        "many:{}={}",
+                        cols.iter().map(|col| col.name()).join("&"),
+                        ids.join(",")
+                    )?,
                     TableLink::Parent(_, ParentIds::List(css)) => {
                         let css = css
                             .iter()
@@ -3921,12 +4190,14 @@ impl<'a> SortKey<'a> {
                     ChildKey::ManyIdAsc(children, br_column) => {
                         let prefixes: Vec<&str> =
                             children.iter().map(|child| child.prefix.as_str()).collect();
-                        SortKey::multi_sort_id_expr(prefixes, ASC, br_column, out)
+                        SortKey::multi_sort_id_expr(prefixes, ASC, br_column, out)?;
+                        SortKey::parent_id_tiebreaker(ASC, use_sort_key_alias, out)
                     }
                     ChildKey::ManyIdDesc(children, br_column) => {
                         let prefixes: Vec<&str> =
                             children.iter().map(|child| child.prefix.as_str()).collect();
-                        SortKey::multi_sort_id_expr(prefixes, DESC, br_column, out)
+                        SortKey::multi_sort_id_expr(prefixes, DESC, br_column, out)?;
+                        SortKey::parent_id_tiebreaker(DESC, use_sort_key_alias, out)
                     }
 
                     ChildKey::IdAsc(child, br_column) => {
@@ -3939,7 +4210,7 @@ impl<'a> SortKey<'a> {
                             out.push_sql(".");
                             br_column.bare_name(out);
                         }
-                        Ok(())
+                        SortKey::parent_id_tiebreaker(ASC, use_sort_key_alias, out)
                     }
                     ChildKey::IdDesc(child, br_column) => {
                         out.push_sql(child.prefix.as_str());
@@ -3953,13 +4224,32 @@ impl<'a> SortKey<'a> {
                             br_column.bare_name(out);
                             out.push_sql(" desc");
                         }
-                        Ok(())
+                        SortKey::parent_id_tiebreaker(DESC, use_sort_key_alias, out)
                     }
                 }
             }
         }
     }
 
+    /// Generate
+    ///   , [c.]id direction
+    /// Several parents can share the same child, and sorting by the id of
+    /// the child alone would put them into an arbitrary order
+    fn parent_id_tiebreaker(
+        direction: &str,
+        use_sort_key_alias: bool,
+        out: &mut AstPass<Pg>,
+    ) -> QueryResult<()> {
+        out.push_sql(", ");
+        if !use_sort_key_alias {
+            out.push_sql("c.");
+        }
+        out.push_identifier(PRIMARY_KEY_COLUMN)?;
+        out.push_sql(" ");
+        out.push_sql(direction);
+        Ok(())
+    }
+
     /// Generate
     ///   order by g$parent_id, [name direction], id
     /// TODO: Let's think how to detect if we need to use sort_key$ alias or not
@@ -4012,6 +4302,9 @@ impl<'a> SortKey<'a> {
 
     /// Generate
     ///   [name direction,] id
+    ///
+    /// The `id` makes the order deterministic when several entities have
+    /// the same value for `name`
     fn sort_expr<'b>(
         column: &Column,
         value: &'b Option<&str>,
@@ -4189,11 +4482,26 @@ impl<'a> SortKey<'a> {
             prefix: &str,
             out: &mut AstPass<'_, 'b, Pg>,
         ) -> QueryResult<()> {
-            out.push_sql(" left join ");
+            // When the child stores the reference to the parent, i.e., when
+            // the parent field is derived, nothing keeps several children
+            // from pointing at the same parent. We only join the child with
+            // the smallest id so that each parent appears exactly once and
+            // is sorted the same way every time
+            let derived = !child_column.is_primary_key();
+
+            if derived {
+                out.push_sql(" left join lateral (select * from ");
+            } else {
+                out.push_sql(" left join ");
+            }
             out.push_sql(child_table.qualified_name.as_str());
             out.push_sql(" as ");
             out.push_sql(prefix);
-            out.push_sql(" on (");
+            if derived {
+                out.push_sql(" where ");
+            } else {
+                out.push_sql(" on (");
+            }
 
             if child_column.is_list() {
                 // Type C: p.id = any(c.child_ids)
@@ -4225,10 +4533,26 @@ impl<'a> SortKey<'a> {
             out.push_sql(" and ");
             out.push_sql(prefix);
             out.push_sql(".");
-            out.push_identifier(BLOCK_RANGE_COLUMN)?;
-            out.push_sql(" @> ");
+            if child_table.immutable {
+                out.push_identifier(BLOCK_COLUMN)?;
+                out.push_sql(" <= ");
+            } else {
+                out.push_identifier(BLOCK_RANGE_COLUMN)?;
+                out.push_sql(" @> ");
+            }
             out.push_bind_param::<Integer, _>(block)?;
-            out.push_sql(") ");
+
+            if derived {
+                out.push_sql(" order by ");
+                out.push_sql(prefix);
+                out.push_sql(".");
+                out.push_identifier(PRIMARY_KEY_COLUMN)?;
+                out.push_sql(" limit 1) as ");
+                out.push_sql(prefix);
+                out.push_sql(" on true ");
+            } else {
+                out.push_sql(") ");
+            }
 
             Ok(())
         }
@@ -4713,6 +5037,14 @@ impl<'a> ClampRangeQuery<'a> {
             })
         }
     }
+
+    /// Return the maximum number of entities that should be clamped with
+    /// one invocation of `ClampRangeQuery`. Since all ids are passed in one
+    /// bind variable, the only limits are `max_rows` and
+    /// `GRAPH_STORE_MAX_ROWS_PER_INSERT` if that is set
+    pub fn chunk_size(max_rows: usize) -> usize {
+        cap_chunk_size(max_rows, ENV_VARS.store.max_rows_per_insert)
+    }
 }
 
 impl<'a> QueryFragment<Pg> for ClampRangeQuery<'a> {
@@ -118,6 +118,10 @@ impl StatusStore for Store {
         Ok(infos)
     }
 
+    fn pool_status(&self) -> Vec<status::PoolStatus> {
+        self.subgraph_store.pool_status()
+    }
+
     fn version_info(&self, version_id: &str) -> Result<VersionInfo, StoreError> {
         let mut info = self.subgraph_store.version_info(version_id)?;
 
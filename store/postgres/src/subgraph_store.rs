@@ -37,7 +37,9 @@ use graph::{
 };
 
 use crate::{
+    archive,
     connection_pool::ConnectionPool,
+    copy::TableEstimate,
     deployment::{OnSync, SubgraphHealth},
     primary,
     primary::{DeploymentId, Mirror as PrimaryMirror, Site},
@@ -429,11 +431,23 @@ impl SubgraphStoreInner {
             .ok_or_else(|| StoreError::UnknownShard(site.shard.to_string()))
     }
 
-    pub(crate) fn layout(&self, id: &DeploymentHash) -> Result<Arc<Layout>, StoreError> {
+    /// Return the relational layout, and with it the input schema, of the
+    /// deployment `id`
+    pub fn layout(&self, id: &DeploymentHash) -> Result<Arc<Layout>, StoreError> {
         let (store, site) = self.store(id)?;
         store.find_layout(site)
     }
 
+    /// Return the deployment and block that the deployment `id` was
+    /// grafted onto, or `None` if it is not a graft
+    pub fn graft_point(
+        &self,
+        id: &DeploymentHash,
+    ) -> Result<Option<(DeploymentHash, BlockPtr)>, StoreError> {
+        let (store, _) = self.store(id)?;
+        store.graft_point(id)
+    }
+
     fn place_on_node(
         &self,
         mut nodes: Vec<NodeId>,
@@ -683,6 +697,73 @@ impl SubgraphStoreInner {
         Ok(dst.as_ref().into())
     }
 
+    /// Write the deployment `loc` as a portable archive to `out`, reading
+    /// `batch_size` rows from the database at a time, and return how many
+    /// rows were written. See `archive` for a description of the format
+    pub fn export_deployment(
+        &self,
+        loc: &DeploymentLocator,
+        out: &mut dyn std::io::Write,
+        batch_size: usize,
+    ) -> Result<usize, StoreError> {
+        let site = self.find_site(loc.id.into())?;
+        let store = self.for_site(site.as_ref())?;
+        store.export_archive(site, out, batch_size)
+    }
+
+    /// Create a new deployment in `shard` from the archive `input`, which
+    /// must have been written by `export_deployment`, and assign it to
+    /// `node` once all its data has been loaded. The deployment must not
+    /// exist yet. If loading the data fails, the deployment is removed
+    /// again
+    pub fn import_deployment(
+        &self,
+        input: &mut dyn std::io::BufRead,
+        shard: Shard,
+        node: NodeId,
+        batch_size: usize,
+    ) -> Result<(DeploymentLocator, usize), StoreError> {
+        let header = archive::read_header(input)?;
+        let schema = header.schema()?;
+        let deployment = header.deployment_create(&schema)?;
+
+        self.evict(schema.id())?;
+        let (site, created) =
+            self.primary_conn()?
+                .allocate_site(shard, schema.id(), header.network.clone(), None)?;
+        if !created {
+            return Err(StoreError::Unknown(anyhow!(
+                "deployment {} already exists in shard {}; it needs to be removed before it can be imported",
+                site.deployment,
+                site.shard
+            )));
+        }
+        let site = Arc::new(site);
+
+        let store = self.for_site(site.as_ref())?;
+        let rows = store
+            .create_deployment(&schema, deployment, site.clone(), None, false, OnSync::None)
+            .and_then(|()| store.import_archive(site.clone(), &header, input, batch_size));
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(e) => {
+                store.drop_deployment(&site)?;
+                self.primary_conn()?.drop_site(site.as_ref())?;
+                return Err(e);
+            }
+        };
+
+        let mut pconn = self.primary_conn()?;
+        pconn.transaction(|conn| -> Result<_, StoreError> {
+            let mut pconn = primary::Connection::new(conn);
+            let changes = pconn.assign_subgraph(site.as_ref(), &node)?;
+            let event = StoreEvent::new(changes);
+            pconn.send_store_event(&self.sender, &event)?;
+            Ok(())
+        })?;
+        Ok((site.as_ref().into(), rows))
+    }
+
     /// Mark `deployment` as the only active deployment amongst all sites
     /// with the same deployment hash. Activating this specific deployment
     /// will make queries use that instead of whatever was active before
@@ -871,6 +952,16 @@ impl SubgraphStoreInner {
         self.status(filter).unwrap().into_iter().next().unwrap()
     }
 
+    pub(crate) fn pool_status(&self) -> Vec<status::PoolStatus> {
+        let mut status: Vec<_> = self
+            .stores
+            .values()
+            .flat_map(|store| store.pool_status())
+            .collect();
+        status.sort_by(|a, b| (&a.shard, &a.pool).cmp(&(&b.shard, &b.pool)));
+        status
+    }
+
     pub(crate) fn status(&self, filter: status::Filter) -> Result<Vec<status::Info>, StoreError> {
         let sites = match filter {
             status::Filter::SubgraphName(name) => {
@@ -910,6 +1001,14 @@ impl SubgraphStoreInner {
             infos.extend(store.deployment_statuses(&sites)?);
         }
         self.mirror.fill_assignments(&mut infos)?;
+
+        let writables = self.writables.lock().unwrap();
+        for info in &mut infos {
+            if let Some(writable) = writables.get(&DeploymentId::from(info.id)) {
+                info.recent_reorgs = writable.recent_reorgs();
+                info.blocks_per_second = writable.blocks_per_second();
+            }
+        }
         Ok(infos)
     }
 
@@ -1096,6 +1195,17 @@ impl SubgraphStoreInner {
         store.analyze(site, entity_name)
     }
 
+    /// Estimate how much data copying `deployment` up to `target_block`
+    /// moves for each of its tables
+    pub fn copy_estimate(
+        &self,
+        deployment: &DeploymentLocator,
+        target_block: BlockNumber,
+    ) -> Result<Vec<TableEstimate>, StoreError> {
+        let (store, site) = self.store(&deployment.hash)?;
+        store.copy_estimate(site, target_block)
+    }
+
     /// Return the statistics targets for all tables of `deployment`. The
     /// first return value is the default target, and the second value maps
     /// the name of each table to a map of column name to its statistics
@@ -1185,6 +1295,47 @@ impl SubgraphStoreInner {
         store.prune(reporter, site, req).await
     }
 
+    /// Return the earliest block of a prune of `deployment` that was
+    /// interrupted and can be resumed by pruning to that block again
+    pub fn prune_checkpoint_block(
+        &self,
+        deployment: &DeploymentLocator,
+    ) -> Result<Option<BlockNumber>, StoreError> {
+        let site = self.find_site(deployment.id.into())?;
+        let store = self.for_site(&site)?;
+
+        store.prune_checkpoint_block(&site)
+    }
+
+    /// Wait until the blocks that have been queued for writing for any
+    /// deployment have been written. Used when the node shuts down so that
+    /// indexing work that was already done is not lost
+    pub async fn flush_writables(&self) -> Result<(), StoreError> {
+        let writables: Vec<_> = self.writables.lock().unwrap().values().cloned().collect();
+        join_all(
+            writables
+                .iter()
+                .map(|writable| store::WritableStore::flush(writable.as_ref())),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map(|_| ())
+    }
+
+    /// Wait until the block that is currently being written for
+    /// `deployment`, if any, has been committed, and return the block
+    /// pointer of the deployment after that
+    pub fn wait_for_writes(
+        &self,
+        deployment: &DeploymentLocator,
+    ) -> Result<Option<BlockPtr>, StoreError> {
+        let site = self.find_site(deployment.id.into())?;
+        let store = self.for_site(&site)?;
+
+        store.wait_for_writes(&site)
+    }
+
     pub fn set_history_blocks(
         &self,
         deployment: &DeploymentLocator,
@@ -1340,7 +1491,7 @@ impl SubgraphStoreTrait for SubgraphStore {
         let mut pconn = self.primary_conn()?;
         pconn.transaction(|conn| -> Result<_, StoreError> {
             let mut pconn = primary::Connection::new(conn);
-            let changes = pconn.pause_subgraph(site.as_ref())?;
+            let changes = pconn.pause_subgraph(site.as_ref(), None)?;
             pconn.send_store_event(&self.sender, &StoreEvent::new(changes))
         })
     }
@@ -1,8 +1,8 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, VecDeque};
 use std::ops::Deref;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, RwLock, TryLockError as RwLockError};
-use std::time::Instant;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use std::{collections::BTreeMap, sync::Arc};
 
 use graph::blockchain::block_stream::FirehoseCursor;
@@ -10,7 +10,7 @@ use graph::blockchain::BlockTime;
 use graph::components::store::{Batch, DeploymentCursorTracker, DerivedEntityQuery, ReadStore};
 use graph::constraint_violation;
 use graph::data::store::IdList;
-use graph::data::subgraph::schema;
+use graph::data::subgraph::{schema, status};
 use graph::data_source::CausalityRegion;
 use graph::prelude::{
     BlockNumber, CacheWeight, Entity, MetricsRegistry, SubgraphDeploymentEntity,
@@ -1486,6 +1486,15 @@ pub struct WritableStore {
 
     // Cached to avoid querying the database.
     is_deployment_synced: AtomicBool,
+
+    /// The last `GRAPH_STORE_RECENT_REORGS` reverts, oldest first, so that
+    /// they can be reported through the status API
+    recent_reorgs: Mutex<VecDeque<status::Reorg>>,
+
+    /// When recent blocks were written, oldest first, covering at most the
+    /// last `GRAPH_STORE_THROUGHPUT_WINDOW`, so that the status API can
+    /// estimate how long it will take to reach the chain head
+    recent_progress: Mutex<VecDeque<(Instant, BlockNumber)>>,
 }
 
 impl WritableStore {
@@ -1527,6 +1536,8 @@ impl WritableStore {
             block_cursor,
             writer,
             is_deployment_synced: AtomicBool::new(is_deployment_synced),
+            recent_reorgs: Mutex::new(VecDeque::new()),
+            recent_progress: Mutex::new(VecDeque::new()),
         })
     }
 
@@ -1537,6 +1548,74 @@ impl WritableStore {
     pub(crate) async fn stop(&self) -> Result<(), StoreError> {
         self.writer.stop().await
     }
+
+    pub(crate) fn recent_reorgs(&self) -> Vec<status::Reorg> {
+        self.recent_reorgs.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn record_reorg(&self, block_ptr_from: Option<&BlockPtr>, block_ptr_to: &BlockPtr) {
+        let capacity = ENV_VARS.store.recent_reorgs;
+        if capacity == 0 {
+            return;
+        }
+
+        let depth = block_ptr_from
+            .map(|ptr| ptr.number - block_ptr_to.number)
+            .unwrap_or(0);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut reorgs = self.recent_reorgs.lock().unwrap();
+        while reorgs.len() >= capacity {
+            reorgs.pop_front();
+        }
+        reorgs.push_back(status::Reorg {
+            block_number: block_ptr_to.number,
+            depth,
+            timestamp,
+        });
+    }
+
+    /// The average number of blocks per second that were written during
+    /// the last `GRAPH_STORE_THROUGHPUT_WINDOW`, or `None` if there is not
+    /// enough recent progress to tell
+    pub(crate) fn blocks_per_second(&self) -> Option<f64> {
+        let mut progress = self.recent_progress.lock().unwrap();
+        Self::expire_progress(&mut progress, Instant::now());
+
+        let (first_time, first_block) = progress.front()?;
+        let (last_time, last_block) = progress.back()?;
+        let elapsed = last_time.duration_since(*first_time).as_secs_f64();
+        if elapsed == 0.0 {
+            return None;
+        }
+        Some((last_block - first_block) as f64 / elapsed)
+    }
+
+    fn record_progress(&self, block: BlockNumber) {
+        // Keep the number of samples bounded for deployments that write
+        // many blocks per second
+        const MAX_SAMPLES: usize = 1000;
+
+        let now = Instant::now();
+        let mut progress = self.recent_progress.lock().unwrap();
+        Self::expire_progress(&mut progress, now);
+        while progress.len() >= MAX_SAMPLES {
+            progress.pop_front();
+        }
+        progress.push_back((now, block));
+    }
+
+    fn expire_progress(progress: &mut VecDeque<(Instant, BlockNumber)>, now: Instant) {
+        let window = ENV_VARS.store.throughput_window;
+        while let Some((time, _)) = progress.front() {
+            if now.duration_since(*time) <= window {
+                break;
+            }
+            progress.pop_front();
+        }
+    }
 }
 
 impl ReadStore for WritableStore {
@@ -1598,8 +1677,12 @@ impl WritableStoreTrait for WritableStore {
         block_ptr_to: BlockPtr,
         firehose_cursor: FirehoseCursor,
     ) -> Result<(), StoreError> {
-        *self.block_ptr.lock().unwrap() = Some(block_ptr_to.clone());
+        let block_ptr_from = self.block_ptr.lock().unwrap().replace(block_ptr_to.clone());
         *self.block_cursor.lock().unwrap() = firehose_cursor.clone();
+        self.record_reorg(block_ptr_from.as_ref(), &block_ptr_to);
+        // Progress from before the revert says nothing about how fast we
+        // are moving along the new chain
+        self.recent_progress.lock().unwrap().clear();
 
         self.writer.revert(block_ptr_to, firehose_cursor).await
     }
@@ -1670,6 +1753,7 @@ impl WritableStoreTrait for WritableStore {
         )?;
         self.writer.write(batch, stopwatch).await?;
 
+        self.record_progress(block_ptr_to.number);
         *self.block_ptr.lock().unwrap() = Some(block_ptr_to);
         *self.block_cursor.lock().unwrap() = firehose_cursor;
 
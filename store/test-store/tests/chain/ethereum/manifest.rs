@@ -1271,6 +1271,129 @@ dataSources:
     });
 }
 
+const MULTIPLE_NETWORKS_YAML: &str = "
+specVersion: 0.0.4
+schema:
+  file:
+    /: /ipfs/Qmschema
+features:
+  - ipfsOnEthereumContracts
+  - FEATURE
+dataSources:
+  - kind: ethereum/contract
+    name: Mainnet
+    network: mainnet
+    source:
+      abi: Factory
+      startBlock: 100
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+      callHandlers:
+        - function: get(address)
+          handler: handleget
+  - kind: ethereum/contract
+    name: Gnosis
+    network: gnosis
+    source:
+      abi: Factory
+      startBlock: 7
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+      callHandlers:
+        - function: get(address)
+          handler: handleget
+";
+
+async fn resolve_multiple_networks(feature: &str) -> UnvalidatedSubgraphManifest<Chain> {
+    let yaml = MULTIPLE_NETWORKS_YAML.replace("FEATURE", feature);
+    let mut resolver = TextResolver::default();
+    let id = DeploymentHash::new("Qmmanifest").unwrap();
+    resolver.add(id.as_str(), &yaml);
+    resolver.add("/ipfs/Qmabi", &ABI);
+    resolver.add("/ipfs/Qmschema", &GQL_SCHEMA);
+    resolver.add("/ipfs/Qmmapping", &MAPPING_WITH_IPFS_FUNC_WASM);
+
+    let resolver: Arc<dyn LinkResolverTrait> = Arc::new(resolver);
+
+    let raw = serde_yaml::from_str(&yaml).unwrap();
+    UnvalidatedSubgraphManifest::resolve(id, raw, &resolver, &LOGGER, SPEC_VERSION_0_0_4.clone())
+        .await
+        .expect("Parsing manifest with multiple networks works")
+}
+
+#[test]
+fn undeclared_multiple_networks_feature_causes_feature_validation_error() {
+    test_store::run_test_sequentially(|store| async move {
+        let store = store.subgraph_store();
+        let unvalidated = resolve_multiple_networks("nonFatalErrors").await;
+
+        let error_msg = unvalidated
+            .validate(store.clone(), true)
+            .await
+            .expect_err("Validation must fail")
+            .into_iter()
+            .find(|e| {
+                matches!(
+                    e,
+                    SubgraphManifestValidationError::FeatureValidationError(_)
+                )
+            })
+            .expect("There must be a FeatureValidationError")
+            .to_string();
+
+        assert_eq!(
+            "The feature `multipleNetworks` is used by the subgraph but it is not declared in the manifest.",
+            error_msg
+        );
+    });
+}
+
+#[test]
+fn declared_multiple_networks_feature_causes_no_errors() {
+    test_store::run_test_sequentially(|store| async move {
+        let store = store.subgraph_store();
+        let unvalidated = resolve_multiple_networks("multipleNetworks").await;
+
+        let errors = unvalidated
+            .validate(store.clone(), true)
+            .await
+            .expect_err("Validation must fail");
+        assert!(!errors.iter().any(|e| matches!(
+            e,
+            SubgraphManifestValidationError::FeatureValidationError(_)
+                | SubgraphManifestValidationError::MultipleEthereumNetworks(_)
+        )));
+
+        // The first data source determines the main network, and only its
+        // data sources determine where the main network's blocks start
+        let yaml = MULTIPLE_NETWORKS_YAML.replace("FEATURE", "multipleNetworks");
+        let manifest = resolve_manifest(&yaml, SPEC_VERSION_0_0_4).await;
+        assert_eq!("mainnet", manifest.network_name());
+        assert_eq!(vec!["mainnet", "gnosis"], manifest.networks());
+        assert_eq!(vec![100], manifest.start_blocks());
+    });
+}
+
 #[test]
 fn can_detect_features_in_subgraphs_with_spec_version_lesser_than_0_0_4() {
     const YAML: &str = "
@@ -140,6 +140,78 @@ async fn one_interface_multiple_entities() {
     assert_eq!(data, exp);
 }
 
+#[tokio::test]
+async fn interleaved_implementors_order() {
+    let subgraph_id = "InterleavedImplementorsOrder";
+    let document = "interface Legged { id: ID!, legs: Int!, room: Room! }
+                  type Animal implements Legged @entity { id: ID!, legs: Int!, room: Room! }
+                  type Furniture implements Legged @entity { id: ID!, legs: Int!, room: Room! }
+                  type Room @entity { id: ID!, things: [Legged!]! @derivedFrom(field: \"room\") }
+                  ";
+    let schema = InputSchema::raw(document, subgraph_id);
+
+    // The order of the ids is the opposite of the order of `legs`, and the
+    // values for `legs` alternate between the two implementors
+    let entities = vec![
+        ("Room", entity! { schema => id: "r" }),
+        ("Animal", entity! { schema => id: "6", legs: 0, room: "r" }),
+        (
+            "Furniture",
+            entity! { schema => id: "5", legs: 1, room: "r" },
+        ),
+        ("Animal", entity! { schema => id: "4", legs: 2, room: "r" }),
+        (
+            "Furniture",
+            entity! { schema => id: "3", legs: 3, room: "r" },
+        ),
+        ("Animal", entity! { schema => id: "2", legs: 4, room: "r" }),
+        (
+            "Furniture",
+            entity! { schema => id: "1", legs: 6, room: "r" },
+        ),
+    ];
+
+    let legged = |id: &str, legs: i32, typename: &str| {
+        object! { __typename: typename, id: id, legs: legs }
+    };
+
+    let query = "query {
+                    asc: leggeds(orderBy: legs) { __typename id legs }
+                    desc: leggeds(orderBy: legs, orderDirection: desc, first: 3, skip: 1) {
+                      __typename id legs
+                    }
+                    rooms { things(orderBy: legs, first: 4, skip: 1) { __typename id legs } }
+                }";
+    let res = insert_and_query(subgraph_id, document, entities, query)
+        .await
+        .unwrap();
+    let data = extract_data!(res).unwrap();
+    let exp = object! {
+        asc: vec![
+            legged("6", 0, "Animal"),
+            legged("5", 1, "Furniture"),
+            legged("4", 2, "Animal"),
+            legged("3", 3, "Furniture"),
+            legged("2", 4, "Animal"),
+            legged("1", 6, "Furniture"),
+        ],
+        desc: vec![
+            legged("2", 4, "Animal"),
+            legged("3", 3, "Furniture"),
+            legged("4", 2, "Animal"),
+        ],
+        rooms: vec![object! {
+            things: vec![
+                legged("5", 1, "Furniture"),
+                legged("4", 2, "Animal"),
+                legged("3", 3, "Furniture"),
+                legged("2", 4, "Animal"),
+            ]
+        }]
+    };
+    assert_eq!(data, exp);
+}
+
 #[tokio::test]
 async fn reference_interface() {
     let subgraph_id = "ReferenceInterface";
@@ -316,11 +316,20 @@ const ACCOUNT_GQL: &str = "
         email: String!
         age: Int!
         wallets: [Wallet!]! @derivedFrom(field: \"account\")
+        purses: [Purse!]! @derivedFrom(field: \"account\")
+        selfTransfers: [Transfer!]! @derivedFrom(fields: [\"from\", \"to\"])
+    }
+
+    type Transfer @entity {
+        id: ID!
+        from: Account!
+        to: Account!
     }
 
     interface Purse {
         id: ID!
         balance: Int!
+        account: Account!
     }
 
     type Wallet implements Purse @entity {
@@ -328,11 +337,19 @@ const ACCOUNT_GQL: &str = "
         balance: Int!
         account: Account!
     }
+
+    type Vault implements Purse @entity {
+        id: ID!
+        balance: Int!
+        account: Account!
+    }
 ";
 
 const ACCOUNT: &str = "Account";
 const WALLET: &str = "Wallet";
 const PURSE: &str = "Purse";
+const VAULT: &str = "Vault";
+const TRANSFER: &str = "Transfer";
 
 lazy_static! {
     static ref LOAD_RELATED_ID_STRING: String = String::from("loadrelatedsubgraph");
@@ -351,6 +368,8 @@ lazy_static! {
     static ref WALLET_TYPE: EntityType = LOAD_RELATED_SUBGRAPH.entity_type(WALLET).unwrap();
     static ref ACCOUNT_TYPE: EntityType = LOAD_RELATED_SUBGRAPH.entity_type(ACCOUNT).unwrap();
     static ref PURSE_TYPE: EntityType = LOAD_RELATED_SUBGRAPH.entity_type(PURSE).unwrap();
+    static ref VAULT_TYPE: EntityType = LOAD_RELATED_SUBGRAPH.entity_type(VAULT).unwrap();
+    static ref TRANSFER_TYPE: EntityType = LOAD_RELATED_SUBGRAPH.entity_type(TRANSFER).unwrap();
 }
 
 fn remove_test_data(store: Arc<DieselSubgraphStore>) {
@@ -480,6 +499,14 @@ fn create_wallet_operation(id: &str, account_id: &Id, balance: i32) -> EntityOpe
     }
 }
 
+fn create_vault_operation(id: &str, account_id: &Id, balance: i32) -> EntityOperation {
+    let account_id = Value::from(account_id.clone());
+    EntityOperation::Set {
+        key: VAULT_TYPE.parse_key(id).unwrap(),
+        data: entity! { LOAD_RELATED_SUBGRAPH => id: id, account: account_id, balance: balance },
+    }
+}
+
 #[test]
 fn check_for_account_with_multiple_wallets() {
     run_store_test(|mut cache, _store, _deployment, _writable| async move {
@@ -636,6 +663,124 @@ fn check_for_insert_async_not_related() {
     });
 }
 
+#[test]
+fn check_for_interface_related() {
+    run_store_test(|mut cache, store, deployment, _writable| async move {
+        let account_id = ACCOUNT_TYPE.parse_id("1").unwrap();
+        let other_id = ACCOUNT_TYPE.parse_id("2").unwrap();
+        transact_entity_operations(
+            &store,
+            &deployment,
+            TEST_BLOCK_1_PTR.clone(),
+            vec![
+                create_vault_operation("10", &account_id, 1000_i32),
+                create_vault_operation("25", &account_id, 2500_i32),
+                create_vault_operation("30", &other_id, 3000_i32),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let mut request = LoadRelatedRequest {
+            entity_type: ACCOUNT_TYPE.clone(),
+            entity_field: "purses".into(),
+            entity_id: account_id.clone(),
+            causality_region: CausalityRegion::ONCHAIN,
+        };
+        let vault = |id: &str, account_id: &Id, balance: i32| {
+            let account_id = Value::from(account_id.clone());
+            entity! { LOAD_RELATED_SUBGRAPH => id: id, account: account_id, balance: balance }
+        };
+
+        // Wallets and vaults are interleaved by id
+        let result = cache.load_related(&request).unwrap();
+        let expected = vec![
+            create_wallet_entity("1", &account_id, 67_i32),
+            vault("10", &account_id, 1000_i32),
+            create_wallet_entity("2", &account_id, 92_i32),
+            vault("25", &account_id, 2500_i32),
+            create_wallet_entity("3", &account_id, 192_i32),
+        ];
+        assert_eq!(result, expected);
+
+        // Changes in the cache are taken into account for all types
+        cache
+            .set(
+                VAULT_TYPE.parse_key("15").unwrap(),
+                vault("15", &other_id, 1500_i32),
+            )
+            .unwrap();
+        request.entity_id = other_id.clone();
+        let result = cache.load_related(&request).unwrap();
+        let expected = vec![
+            vault("15", &other_id, 1500_i32),
+            vault("30", &other_id, 3000_i32),
+            create_wallet_entity("4", &other_id, 32_i32),
+        ];
+        assert_eq!(result, expected);
+    });
+}
+
+#[test]
+fn check_for_composite_related() {
+    run_store_test(|mut cache, store, deployment, _writable| async move {
+        let account_id = ACCOUNT_TYPE.parse_id("1").unwrap();
+        let other_id = ACCOUNT_TYPE.parse_id("2").unwrap();
+        let transfer = |id: &str, from: &Id, to: &Id| {
+            let from = Value::from(from.clone());
+            let to = Value::from(to.clone());
+            entity! { LOAD_RELATED_SUBGRAPH => id: id, from: from, to: to }
+        };
+        let transfer_op = |id: &str, from: &Id, to: &Id| EntityOperation::Set {
+            key: TRANSFER_TYPE.parse_key(id).unwrap(),
+            data: transfer(id, from, to),
+        };
+        // Only `t1` references account 1 in both `from` and `to`; `t2`
+        // and `t3` only reference it in one of them
+        transact_entity_operations(
+            &store,
+            &deployment,
+            TEST_BLOCK_1_PTR.clone(),
+            vec![
+                transfer_op("t1", &account_id, &account_id),
+                transfer_op("t2", &account_id, &other_id),
+                transfer_op("t3", &other_id, &account_id),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let request = LoadRelatedRequest {
+            entity_type: ACCOUNT_TYPE.clone(),
+            entity_field: "selfTransfers".into(),
+            entity_id: account_id.clone(),
+            causality_region: CausalityRegion::ONCHAIN,
+        };
+        let result = cache.load_related(&request).unwrap();
+        assert_eq!(result, vec![transfer("t1", &account_id, &account_id)]);
+
+        // Changes in the cache follow the same rule
+        cache
+            .set(
+                TRANSFER_TYPE.parse_key("t4").unwrap(),
+                transfer("t4", &account_id, &account_id),
+            )
+            .unwrap();
+        cache
+            .set(
+                TRANSFER_TYPE.parse_key("t5").unwrap(),
+                transfer("t5", &other_id, &account_id),
+            )
+            .unwrap();
+        let result = cache.load_related(&request).unwrap();
+        let expected = vec![
+            transfer("t1", &account_id, &account_id),
+            transfer("t4", &account_id, &account_id),
+        ];
+        assert_eq!(result, expected);
+    });
+}
+
 #[test]
 fn check_for_update_async_related() {
     run_store_test(|mut cache, store, deployment, writable| async move {
@@ -744,6 +889,49 @@ fn scoped_get() {
     })
 }
 
+#[test]
+fn scoped_get_in_block() {
+    run_store_test(|mut cache, _store, _deployment, _writable| async move {
+        let account1 = ACCOUNT_TYPE.parse_id("1").unwrap();
+
+        // Create an entity and read it back in the same block
+        let key6 = WALLET_TYPE.parse_key("6").unwrap();
+        let wallet6 = create_wallet_entity("6", &account1, 6);
+        cache.set(key6.clone(), wallet6.clone()).unwrap();
+        let act6 = cache.get(&key6, GetScope::InBlock).unwrap();
+        assert_eq!(Some(&wallet6), act6.as_ref().map(|e| e.as_ref()));
+
+        // Create an entity and remove it in the same block
+        let key7 = WALLET_TYPE.parse_key("7").unwrap();
+        let wallet7 = create_wallet_entity("7", &account1, 7);
+        cache.set(key7.clone(), wallet7).unwrap();
+        cache.remove(key7.clone());
+        assert_eq!(None, cache.get(&key7, GetScope::InBlock).unwrap());
+        assert_eq!(None, cache.get(&key7, GetScope::Store).unwrap());
+
+        // Remove an entity that is in the store
+        let key2 = WALLET_TYPE.parse_key("2").unwrap();
+        cache.remove(key2.clone());
+        assert_eq!(None, cache.get(&key2, GetScope::InBlock).unwrap());
+        assert_eq!(None, cache.get(&key2, GetScope::Store).unwrap());
+
+        // A partial update of an entity in the store is visible in the
+        // block as the whole entity
+        let key1 = ACCOUNT_TYPE.parse_key("1").unwrap();
+        cache
+            .set(
+                key1.clone(),
+                entity! { LOAD_RELATED_SUBGRAPH => id: "1", age: 70 },
+            )
+            .unwrap();
+        let expected = entity! { LOAD_RELATED_SUBGRAPH => id: "1", name: "Johnton", email: "tonofjohn@email.com", age: 70 };
+        let act1 = cache.get(&key1, GetScope::InBlock).unwrap();
+        assert_eq!(Some(&expected), act1.as_ref().map(|e| e.as_ref()));
+        let act1 = cache.get(&key1, GetScope::Store).unwrap();
+        assert_eq!(Some(&expected), act1.as_ref().map(|e| e.as_ref()));
+    })
+}
+
 /// Entities should never contain a `__typename` or `g$parent_id` field, if
 /// they do, that can cause PoI divergences, because entities will differ
 /// depending on whether they had to be loaded from the database or stuck
@@ -960,3 +960,72 @@ async fn introspection_possible_types() {
         )])
     )
 }
+
+#[tokio::test]
+async fn introspection_deprecated_fields() {
+    let schema = api_schema(
+        "
+        type Token @entity {
+          id: ID!
+          name: String! @deprecated(reason: \"Use `symbol`\")
+          symbol: String!
+          decimals: Int! @deprecated
+        }
+        ",
+        "deprecatedschema",
+    );
+
+    let response = introspection_query(
+        schema,
+        "query {
+          __type(name: \"Token\") {
+              current: fields { name }
+              all: fields(includeDeprecated: true) {
+                name
+                isDeprecated
+                deprecationReason
+              }
+          }
+        }",
+    )
+    .await
+    .to_result()
+    .unwrap()
+    .unwrap();
+
+    fn field(name: &str, reason: Option<&str>) -> r::Value {
+        object_value(vec![
+            ("name", r::Value::String(name.to_string())),
+            ("isDeprecated", r::Value::Boolean(reason.is_some())),
+            (
+                "deprecationReason",
+                reason.map_or(r::Value::Null, |reason| {
+                    r::Value::String(reason.to_string())
+                }),
+            ),
+        ])
+    }
+
+    fn name(name: &str) -> r::Value {
+        object_value(vec![("name", r::Value::String(name.to_string()))])
+    }
+
+    assert_eq!(
+        response,
+        object_value(vec![(
+            "__type",
+            object_value(vec![
+                ("current", r::Value::List(vec![name("id"), name("symbol")])),
+                (
+                    "all",
+                    r::Value::List(vec![
+                        field("id", None),
+                        field("name", Some("Use `symbol`")),
+                        field("symbol", None),
+                        field("decimals", Some("No longer supported")),
+                    ])
+                )
+            ])
+        )])
+    )
+}
@@ -15,7 +15,7 @@ use std::{
     marker::PhantomData,
 };
 use test_store::block_store::{
-    FakeBlock, BLOCK_FOUR, BLOCK_ONE, BLOCK_THREE, BLOCK_TWO, GENESIS_BLOCK,
+    FakeBlock, BLOCK_FOUR, BLOCK_ONE, BLOCK_ONE_SIBLING, BLOCK_THREE, BLOCK_TWO, GENESIS_BLOCK,
 };
 
 use graph::futures03::stream::StreamExt;
@@ -192,7 +192,9 @@ async fn setup(
             static ref STORE_CLEAN: AtomicBool = AtomicBool::new(false);
         }
         if !STORE_CLEAN.load(Ordering::SeqCst) {
-            let chain = CHAIN.iter().collect();
+            // Also put a sibling of block 1 into the chain store to mimic
+            // a block that was reorged out
+            let chain = CHAIN.iter().chain(Some(&*BLOCK_ONE_SIBLING)).collect();
             block_store::set_chain(chain, NETWORK_NAME).await;
             test_store::remove_subgraphs();
             STORE_CLEAN.store(true, Ordering::SeqCst);
@@ -255,7 +257,7 @@ fn test_schema(id: DeploymentHash, id_type: IdType) -> InputSchema {
             ]
         )
 
-    type Musician @entity {
+    type Musician @entity(connection: true) {
         id: ID!
         name: String!
         mainBand: Band
@@ -263,12 +265,21 @@ fn test_schema(id: DeploymentHash, id_type: IdType) -> InputSchema {
         writtenSongs: [Song!]! @derivedFrom(field: \"writtenBy\")
         favoriteCount: Int8!
         birthDate: Timestamp!
+        instruments: [Instrument!]
+    }
+
+    enum Instrument {
+        GUITAR
+        BASS
+        DRUMS
+        VOCALS
     }
 
     type Band @entity {
         id: ID!
         name: String!
         members: [Musician!]! @derivedFrom(field: \"bands\")
+        allMembers: [Musician!]! @derivedFrom(fields: [\"mainBand\", \"bands\"])
         reviews: [BandReview!]! @derivedFrom(field: \"band\")
         originalSongs: [Song!]!
     }
@@ -467,8 +478,8 @@ async fn insert_test_entities(
         (
             "Musician",
             vec![
-                entity! { is => id: "m1", name: "John", mainBand: "b1", bands: vec!["b1", "b2"], favoriteCount: 10, birthDate: timestamp.clone() },
-                entity! { is => id: "m2", name: "Lisa", mainBand: "b1", bands: vec!["b1"], favoriteCount: 100, birthDate: timestamp.clone() },
+                entity! { is => id: "m1", name: "John", mainBand: "b1", bands: vec!["b1", "b2"], favoriteCount: 10, birthDate: timestamp.clone(), instruments: vec!["GUITAR", "VOCALS"] },
+                entity! { is => id: "m2", name: "Lisa", mainBand: "b1", bands: vec!["b1"], favoriteCount: 100, birthDate: timestamp.clone(), instruments: vec!["BASS"] },
             ],
         ),
         ("Publisher", vec![entity! { is => id: pub1 }]),
@@ -574,7 +585,7 @@ async fn insert_test_entities(
     let entities1 = vec![(
         "Musician",
         vec![
-            entity! { is => id: "m3", name: "Tom", mainBand: "b2", bands: vec!["b1", "b2"], favoriteCount: 5, birthDate: timestamp.clone() },
+            entity! { is => id: "m3", name: "Tom", mainBand: "b2", bands: vec!["b1", "b2"], favoriteCount: 5, birthDate: timestamp.clone(), instruments: vec!["DRUMS", "GUITAR"] },
             entity! { is => id: "m4", name: "Valerie", bands: Vec::<String>::new(), favoriteCount: 20, birthDate: timestamp.clone() },
         ],
     )];
@@ -863,6 +874,47 @@ fn can_filter_by_timestamp() {
     })
 }
 
+#[test]
+fn can_filter_by_list_of_enums() {
+    const QUERY1: &str = "
+    query {
+        musicians(first: 100, orderBy: id, where: { instruments_contains: [GUITAR] }) {
+            name
+            instruments
+        }
+    }
+    ";
+
+    const QUERY2: &str = "
+    query {
+        musicians(first: 100, orderBy: id, where: { instruments_not_contains: [GUITAR] }) {
+            name
+        }
+    }
+    ";
+
+    run_query(QUERY1, |result, _| {
+        let exp = object! {
+            musicians: vec![
+                object! { name: "John", instruments: vec!["GUITAR", "VOCALS"] },
+                object! { name: "Tom", instruments: vec!["DRUMS", "GUITAR"] },
+            ],
+        };
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    });
+
+    run_query(QUERY2, |result, _| {
+        let exp = object! {
+            musicians: vec![
+                object! { name: "Lisa" },
+            ],
+        };
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    })
+}
+
 #[test]
 fn can_query_one_to_many_relationships_in_both_directions() {
     const QUERY: &str = "
@@ -957,6 +1009,50 @@ fn can_query_many_to_many_relationship() {
     })
 }
 
+#[test]
+fn can_query_composite_derived_field() {
+    const QUERY: &str = "
+    query {
+        bands(first: 100, orderBy: id) {
+            name
+            allMembers(first: 100, orderBy: id) {
+                name
+            }
+        }
+        lisasBands: bands(first: 100, orderBy: id, where: { allMembers_: { name: \"Lisa\" } }) {
+            name
+        }
+        tomsBands: bands(first: 100, orderBy: id, where: { allMembers_: { name: \"Tom\" } }) {
+            name
+        }
+    }";
+
+    run_query(QUERY, |result, _| {
+        fn members(names: Vec<&str>) -> Vec<r::Value> {
+            names
+                .into_iter()
+                .map(|name| object! { name: name })
+                .collect()
+        }
+
+        // A musician is only a member if both `mainBand` and `bands`
+        // reference the band. Tom has 'The Musicians' in `bands` but not
+        // as `mainBand`, and John has 'The Amateurs' in `bands` but not as
+        // `mainBand`
+        let exp = object! {
+            bands: vec![
+                object! { name: "The Musicians", allMembers: members(vec!["John", "Lisa"]) },
+                object! { name: "The Amateurs", allMembers: members(vec!["Tom"]) },
+            ],
+            lisasBands: vec![object! { name: "The Musicians" }],
+            tomsBands: vec![object! { name: "The Amateurs" }]
+        };
+
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    })
+}
+
 #[test]
 fn can_query_with_fulltext_search() {
     const QUERY: &str = "
@@ -1138,6 +1234,66 @@ fn can_query_with_sorting_by_child_entity_id() {
     })
 }
 
+#[test]
+fn can_query_with_sorting_by_child_entity_id_with_ties() {
+    // John and Lisa have the same `mainBand` and are sorted by their own id
+    const QUERY: &str = "
+    query {
+        desc: musicians(first: 100, orderBy: mainBand__id, orderDirection: desc) {
+            name
+        }
+        asc: musicians(first: 100, orderBy: mainBand__id, orderDirection: asc) {
+            name
+        }
+    }";
+
+    run_query(QUERY, |result, _| {
+        let exp = object! {
+            desc: vec![
+                object! { name: "Valerie" },
+                object! { name: "Tom" },
+                object! { name: "Lisa" },
+                object! { name: "John" },
+            ],
+            asc: vec![
+                object! { name: "John" },
+                object! { name: "Lisa" },
+                object! { name: "Tom" },
+                object! { name: "Valerie" },
+            ]
+        };
+
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    })
+}
+
+#[test]
+fn can_query_with_sorting_by_ambiguous_derived_child_entity() {
+    // 'Cheesy Tune' is in the `originalSongs` of both bands. It must only
+    // be returned once, and is sorted by the band with the smaller id
+    const QUERY: &str = "
+    query {
+        songs(first: 100, orderBy: band__name, orderDirection: asc) {
+            title
+        }
+    }";
+
+    run_query(QUERY, |result, _| {
+        let exp = object! {
+            songs: vec![
+                object! { title: "Pop Tune" },
+                object! { title: "Folk Tune" },
+                object! { title: "Cheesy Tune" },
+                object! { title: "Rock Tune" },
+            ]
+        };
+
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    })
+}
+
 #[test]
 fn can_query_with_sorting_by_derived_child_entity_id() {
     const QUERY: &str = "
@@ -2443,6 +2599,7 @@ fn query_at_block() {
     const BLOCK_NOT_INDEXED2: &str = "subgraph @DEPLOYMENT@ has only indexed \
          up to block number 2 and data for block number 3 is therefore not yet available";
     const BLOCK_HASH_NOT_FOUND: &str = "no block with that hash found";
+    const BLOCK_NOT_ON_CHAIN: &str = "is not on the chain indexed by subgraph @DEPLOYMENT@";
 
     musicians_at("number: 7000", Err(BLOCK_NOT_INDEXED), "n7000");
     musicians_at("number: 0", Ok(vec!["m1", "m2"]), "n0");
@@ -2453,6 +2610,11 @@ fn query_at_block() {
     musicians_at(&hash(&BLOCKS[2]), Ok(vec!["m1", "m2", "m3", "m4"]), "h2");
     musicians_at(&hash(&BLOCKS[3]), Err(BLOCK_NOT_INDEXED2), "h3");
     musicians_at(&hash(&BLOCKS[4]), Err(BLOCK_HASH_NOT_FOUND), "h4");
+    musicians_at(
+        &hash(&BLOCK_ONE_SIBLING.ptr()),
+        Err(BLOCK_NOT_ON_CHAIN),
+        "h1s",
+    );
 }
 
 #[test]
@@ -2488,6 +2650,7 @@ fn query_at_block_with_vars() {
     const BLOCK_NOT_INDEXED2: &str = "subgraph @DEPLOYMENT@ has only indexed \
          up to block number 2 and data for block number 3 is therefore not yet available";
     const BLOCK_HASH_NOT_FOUND: &str = "no block with that hash found";
+    const BLOCK_NOT_ON_CHAIN: &str = "is not on the chain indexed by subgraph @DEPLOYMENT@";
 
     musicians_at_nr(7000, Err(BLOCK_NOT_INDEXED), "n7000");
     musicians_at_nr(0, Ok(vec!["m1", "m2"]), "n0");
@@ -2502,6 +2665,7 @@ fn query_at_block_with_vars() {
     musicians_at_hash(&BLOCKS[2], Ok(vec!["m1", "m2", "m3", "m4"]), "h2");
     musicians_at_hash(&BLOCKS[3], Err(BLOCK_NOT_INDEXED2), "h3");
     musicians_at_hash(&BLOCKS[4], Err(BLOCK_HASH_NOT_FOUND), "h4");
+    musicians_at_hash(&BLOCK_ONE_SIBLING.ptr(), Err(BLOCK_NOT_ON_CHAIN), "h1s");
 }
 
 #[test]
@@ -2636,6 +2800,84 @@ fn can_query_meta() {
     });
 }
 
+#[test]
+fn can_page_through_connection() {
+    const PAGE: &str = "
+    query {
+        musiciansConnection(first: 2, orderBy: favoriteCount, orderDirection: desc, after: @AFTER@) {
+            edges { cursor node { name } }
+            pageInfo { hasNextPage hasPreviousPage startCursor endCursor }
+        }
+    }";
+
+    fn page(after: Option<&str>) -> String {
+        let after = after
+            .map(|after| format!("\"{}\"", after))
+            .unwrap_or("null".to_string());
+        PAGE.replace("@AFTER@", &after)
+    }
+
+    /// Return the names of the musicians on the page, the `pageInfo`
+    /// without the cursors, and the `endCursor`
+    fn summary(data: &r::Value) -> (Vec<String>, (bool, bool), String) {
+        let get = |value: &r::Value, key: &str| match value {
+            r::Value::Object(obj) => obj.get(key).cloned().unwrap(),
+            _ => panic!("expected an object but got {:?}", value),
+        };
+        let conn = get(data, "musiciansConnection");
+        let r::Value::List(edges) = get(&conn, "edges") else {
+            panic!("edges must be a list")
+        };
+        let names = edges
+            .iter()
+            .map(|edge| match get(&get(edge, "node"), "name") {
+                r::Value::String(name) => name,
+                _ => panic!("name must be a string"),
+            })
+            .collect();
+        let cursors: Vec<_> = edges.iter().map(|edge| get(edge, "cursor")).collect();
+        let page_info = get(&conn, "pageInfo");
+        assert_eq!(cursors.first(), Some(&get(&page_info, "startCursor")));
+        assert_eq!(cursors.last(), Some(&get(&page_info, "endCursor")));
+        let flag = |key| match get(&page_info, key) {
+            r::Value::Boolean(b) => b,
+            _ => panic!("{key} must be a boolean"),
+        };
+        let r::Value::String(end_cursor) = get(&page_info, "endCursor") else {
+            panic!("endCursor must be a string")
+        };
+        (
+            names,
+            (flag("hasNextPage"), flag("hasPreviousPage")),
+            end_cursor,
+        )
+    }
+
+    run_test_sequentially(|store| async move {
+        let deployment = setup_readonly(store.as_ref()).await;
+
+        let result = execute_query(&deployment, &page(None)).await;
+        let data = extract_data!(result).unwrap();
+        let (names, flags, cursor) = summary(&data);
+        assert_eq!(vec!["Lisa", "Valerie"], names);
+        assert_eq!((true, false), flags);
+
+        let result = execute_query(&deployment, &page(Some(&cursor))).await;
+        let data = extract_data!(result).unwrap();
+        let (names, flags, _) = summary(&data);
+        assert_eq!(vec!["John", "Tom"], names);
+        assert_eq!((false, true), flags);
+
+        // Cursors are tied to the order they were made for
+        let query = page(Some(&cursor)).replace("orderDirection: desc", "orderDirection: asc");
+        let result = execute_query(&deployment, &query).await;
+        assert!(result.has_errors());
+
+        let result = execute_query(&deployment, &page(Some("not a cursor"))).await;
+        assert!(result.has_errors());
+    })
+}
+
 #[test]
 fn non_fatal_errors() {
     use serde_json::json;
@@ -3071,6 +3313,37 @@ fn can_compare_id() {
     }
 }
 
+/// Check that filters on `Bytes` ids accept hex strings regardless of
+/// case, a `0x` prefix, or an odd number of digits
+#[test]
+fn can_filter_bytes_id_with_any_hex_form() {
+    // `Publisher` has an id of type `Bytes`; the only publisher has id
+    // `0xb1`
+    let checks = [
+        "publishers(where: { id: \"0xB1\" })",
+        "publishers(where: { id: \"b1\" })",
+        "publishers(where: { id_in: [\"0XB1\", \"0xc2\"] })",
+        "publishers(where: { id_gt: \"0xA\" })",
+        "publishers(where: { id_gt: \"a\" })",
+    ];
+
+    for cond in checks {
+        let query = format!("query {{ {cond} {{ id }} }}");
+        run_query(query, move |result, id_type| {
+            let exp = object! { publishers: vec![object! { id: &*PUB1 }] };
+            let data = extract_data!(result).unwrap();
+            assert_eq!(data, exp, "check {} for {:?} ids", cond, id_type);
+        })
+    }
+
+    const QUERY: &str = "query { publisher(id: \"B1\") { id } }";
+    run_query(QUERY, |result, _| {
+        let exp = object! { publisher: object! { id: &*PUB1 } };
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    });
+}
+
 #[test]
 fn empty_type_c() {
     // Single `rl4` has no songs. Make sure our SQL query generation does
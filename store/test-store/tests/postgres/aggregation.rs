@@ -51,6 +51,23 @@ type Data @entity(timeseries: true) {
     timestamp: Timestamp!
     max: BigDecimal! @aggregate(fn: "max", arg: "price")
   }
+
+  type Averages @aggregation(intervals: ["hour"], source: "Data") {
+    id: Int8!
+    timestamp: Timestamp!
+    token: Bytes!
+    price: BigDecimal! @aggregate(fn: "avg", arg: "price")
+    amount: BigDecimal! @aggregate(fn: "avg", arg: "amount")
+    quarter: BigDecimal! @aggregate(fn: "avg", arg: "price / 4")
+    low: BigDecimal! @aggregate(fn: "min", arg: "price / 4")
+    high: BigDecimal! @aggregate(fn: "max", arg: "price / 4")
+  }
+
+  type TotalAverages @aggregation(intervals: ["hour"], source: "Data") {
+    id: Int8!
+    timestamp: Timestamp!
+    price: BigDecimal! @aggregate(fn: "avg", arg: "case when amount = 20 then null else price end")
+  }
   "#;
 
 fn minutes(n: u32) -> BlockTime {
@@ -120,6 +137,10 @@ fn bd(n: i32) -> Value {
     Value::BigDecimal(BigDecimal::from(n))
 }
 
+fn bds(s: &str) -> Value {
+    Value::BigDecimal(s.parse().unwrap())
+}
+
 async fn insert_test_data(store: Arc<dyn WritableStore>, deployment: DeploymentLocator) {
     let schema = ReadStore::input_schema(&store);
 
@@ -198,6 +219,56 @@ fn stats_hour(schema: &InputSchema) -> Vec<Vec<Entity>> {
     vec![vec![], vec![], block2, block3]
 }
 
+fn averages_hour(schema: &InputSchema) -> Vec<Vec<Entity>> {
+    // Averages_hour aggregations over BLOCKS[0..=1], i.e., at BLOCKS[2]
+    let ts2 = BlockTime::since_epoch(0, 0);
+    let block2 = vec![
+        entity! { schema => id: 11i64, timestamp: ts2, token: TOKEN1.clone(),
+        price: bds("1.5"), amount: bd(6), quarter: bds("0.375"),
+        low: bds("0.25"), high: bds("0.5") },
+        entity! { schema => id: 12i64, timestamp: ts2, token: TOKEN2.clone(),
+        price: bds("1.5"), amount: bds("10.5"), quarter: bds("0.375"),
+        low: bds("0.25"), high: bds("0.5") },
+    ];
+
+    let ts3 = BlockTime::since_epoch(3600, 0);
+    let block3 = {
+        let mut v1 = block2.clone();
+        // Averages_hour aggregations over BLOCKS[2], i.e., at BLOCKS[3]
+        let mut v2 = vec![
+            entity! { schema => id: 21i64, timestamp: ts3, token: TOKEN1.clone(),
+            price: bd(3), amount: bd(30), quarter: bds("0.75"),
+            low: bds("0.75"), high: bds("0.75") },
+            entity! { schema => id: 22i64, timestamp: ts3, token: TOKEN2.clone(),
+            price: bd(3), amount: bd(3), quarter: bds("0.75"),
+            low: bds("0.75"), high: bds("0.75") },
+        ];
+        v1.append(&mut v2);
+        v1
+    };
+
+    vec![vec![], vec![], block2, block3]
+}
+
+fn total_averages_hour(schema: &InputSchema) -> Vec<Vec<Entity>> {
+    // The first hour has prices 1, 1, 2 and 2, but the `case` leaves out
+    // the last 2. The average of 1, 1 and 2 does not have a finite decimal
+    // expansion, and Postgres cuts it off at 16 digits after the point
+    let ts2 = BlockTime::since_epoch(0, 0);
+    let block2 = vec![entity! { schema => id: 12i64, timestamp: ts2,
+    price: bds("1.3333333333333333") }];
+
+    let ts3 = BlockTime::since_epoch(3600, 0);
+    let block3 = {
+        let mut v1 = block2.clone();
+        let mut v2 = vec![entity! { schema => id: 22i64, timestamp: ts3, price: bd(3) }];
+        v1.append(&mut v2);
+        v1
+    };
+
+    vec![vec![], vec![], block2, block3]
+}
+
 struct TestEnv {
     store: Arc<DieselStore>,
     writable: Arc<dyn WritableStore>,
@@ -301,3 +372,33 @@ fn simple() {
         }
     })
 }
+
+#[test]
+fn averages() {
+    run_test(|env| async move {
+        let exp = averages_hour(&env.writable.input_schema());
+        for i in 0..4 {
+            let act = env.all_entities("Averages_hour", BLOCKS[i].number);
+            let diff = entity_diff(&exp[i], &act).unwrap();
+            if !diff.is_empty() {
+                panic!("entities for BLOCKS[{}] differ:\n{}", i, diff);
+            }
+            assert_eq!(exp[i], act, "entities for BLOCKS[{}] are the same", i);
+        }
+    })
+}
+
+#[test]
+fn non_terminating_average() {
+    run_test(|env| async move {
+        let exp = total_averages_hour(&env.writable.input_schema());
+        for i in 0..4 {
+            let act = env.all_entities("TotalAverages_hour", BLOCKS[i].number);
+            let diff = entity_diff(&exp[i], &act).unwrap();
+            if !diff.is_empty() {
+                panic!("entities for BLOCKS[{}] differ:\n{}", i, diff);
+            }
+            assert_eq!(exp[i], act, "entities for BLOCKS[{}] are the same", i);
+        }
+    })
+}
@@ -370,6 +370,35 @@ fn ancestor_block_ommers() {
     });
 }
 
+#[test]
+fn is_ancestor() {
+    let chain = vec![
+        &*GENESIS_BLOCK,
+        &*BLOCK_ONE,
+        &*BLOCK_ONE_SIBLING,
+        &*BLOCK_TWO,
+        &*BLOCK_THREE,
+        &*BLOCK_THREE_NO_PARENT,
+    ];
+
+    run_test(chain, move |store, _| -> Result<(), Error> {
+        let is_ancestor = |head: &FakeBlock, block: &FakeBlock| {
+            executor::block_on(store.is_ancestor(&head.block_ptr(), &block.block_ptr()))
+        };
+
+        assert!(is_ancestor(&BLOCK_THREE, &BLOCK_THREE)?);
+        assert!(!is_ancestor(&BLOCK_THREE, &BLOCK_THREE_NO_PARENT)?);
+        assert!(!is_ancestor(&BLOCK_TWO, &BLOCK_THREE)?);
+        assert!(is_ancestor(&BLOCK_THREE, &GENESIS_BLOCK)?);
+        assert!(is_ancestor(&BLOCK_THREE, &BLOCK_ONE)?);
+        assert!(!is_ancestor(&BLOCK_THREE, &BLOCK_ONE_SIBLING)?);
+        // The parent of BLOCK_THREE_NO_PARENT is not in the store, and we
+        // can not tell which of the two blocks at number 1 is its ancestor
+        assert!(is_ancestor(&BLOCK_THREE_NO_PARENT, &BLOCK_ONE).is_err());
+        Ok(())
+    });
+}
+
 #[test]
 fn eth_call_cache() {
     let chain = vec![&*GENESIS_BLOCK, &*BLOCK_ONE, &*BLOCK_TWO];
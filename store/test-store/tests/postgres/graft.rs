@@ -14,7 +14,7 @@ use graph::data::subgraph::schema::*;
 use graph::data::subgraph::*;
 use graph::semver::Version;
 use graph::{entity, prelude::*};
-use graph_store_postgres::{Shard, SubgraphStore as DieselSubgraphStore};
+use graph_store_postgres::{Shard, SubgraphStore as DieselSubgraphStore, PRIMARY_SHARD};
 
 const USER_GQL: &str = "
 enum Color { yellow, red, blue, green }
@@ -544,6 +544,70 @@ fn on_sync() {
     })
 }
 
+#[test]
+fn export_import() {
+    run_test_sequentially(|store| async move {
+        let store = store.subgraph_store();
+
+        remove_test_data(store.clone());
+        let src = insert_test_data(store.clone()).await;
+        flush(&src).await.unwrap();
+
+        let mut archive = Vec::new();
+        let rows = store.export_deployment(&src, &mut archive, 2).unwrap();
+
+        // Remove the deployment so that we can import it again
+        store
+            .remove_subgraph(SubgraphName::new("test/graft").unwrap())
+            .unwrap();
+        let mut primary = primary_connection();
+        let site = primary.locate_site(src.clone()).unwrap().unwrap();
+        primary.unassign_subgraph(&site).unwrap();
+        store.remove_deployment(site.id).unwrap();
+
+        let (dst, imported) = store
+            .import_deployment(
+                &mut archive.as_slice(),
+                PRIMARY_SHARD.clone(),
+                NODE_ID.clone(),
+                2,
+            )
+            .unwrap();
+        assert_eq!(rows, imported);
+        assert_ne!(src.id, dst.id);
+
+        // Exporting the imported deployment produces the same archive,
+        // which means that all block ranges were restored exactly
+        let mut reexported = Vec::new();
+        store
+            .export_deployment(&dst, &mut reexported, 1000)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(archive).unwrap(),
+            String::from_utf8(reexported).unwrap()
+        );
+
+        let (_, ids) = find_entities(store.as_ref(), &dst);
+        let ids_str = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        assert_eq!(vec!["3", "1", "2"], ids_str);
+
+        // Importing an archive for a deployment that exists fails
+        let mut reexported = Vec::new();
+        store
+            .export_deployment(&dst, &mut reexported, 1000)
+            .unwrap();
+        let err = store
+            .import_deployment(
+                &mut reexported.as_slice(),
+                PRIMARY_SHARD.clone(),
+                NODE_ID.clone(),
+                2,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{}", err);
+    })
+}
+
 #[test]
 fn prune() {
     struct Progress;
@@ -1,6 +1,7 @@
 //! Test mapping of GraphQL schema to a relational schema
 use diesel::connection::SimpleConnection as _;
 use diesel::pg::PgConnection;
+use diesel::{sql_query, Connection as _, RunQueryDsl as _};
 use graph::data::store::{scalar, Id};
 use graph::entity;
 use graph::prelude::{
@@ -1012,6 +1013,98 @@ fn revert_block() {
     });
 }
 
+#[test]
+fn revert_block_range_matches_sequential_revert() {
+    /// All versions of cats and minks, including their block ranges
+    fn snapshot(conn: &mut PgConnection) -> Vec<String> {
+        #[derive(diesel::QueryableByName)]
+        struct Row {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            row: String,
+        }
+
+        let query = format!(
+            "select format('cat %s %s %s', id, name, block_range) as row from {0}.cat
+             union all
+             select format('mink %s %s %s', id, \"order\", \"block$\") from {0}.mink
+             order by 1",
+            NAMESPACE.as_str()
+        );
+        sql_query(query)
+            .load::<Row>(conn)
+            .unwrap()
+            .into_iter()
+            .map(|row| row.row)
+            .collect()
+    }
+
+    fn make_history(conn: &mut PgConnection, layout: &Layout) {
+        let cat = |id: &str, name: &str| entity! { layout.input_schema => id: id, name: name };
+        let marty = |block: BlockNumber| {
+            let id = format!("marty-{}", block);
+            entity! { layout.input_schema => id: id, order: block }
+        };
+        let delete_cats = |conn: &mut PgConnection, ids: &[&str], block| {
+            let keys = ids.iter().map(|id| CAT_TYPE.parse_key(*id).unwrap());
+            let group = row_group_delete(&CAT_TYPE, block, keys);
+            layout.delete(conn, &group, &MOCK_STOPWATCH).unwrap();
+        };
+
+        let cats = vec![
+            cat("fred", "zero"),
+            cat("garfield", "zero"),
+            cat("tom", "zero"),
+        ];
+        insert_entity_at(conn, layout, &*CAT_TYPE, cats, 0);
+        insert_entity_at(conn, layout, &*MINK_TYPE, vec![marty(0)], 0);
+
+        update_entity_at(conn, layout, &*CAT_TYPE, vec![cat("fred", "one")], 1);
+        insert_entity_at(conn, layout, &*CAT_TYPE, vec![cat("felix", "one")], 1);
+        insert_entity_at(conn, layout, &*MINK_TYPE, vec![marty(1)], 1);
+
+        update_entity_at(conn, layout, &*CAT_TYPE, vec![cat("fred", "two")], 2);
+        delete_cats(conn, &["garfield"], 2);
+        insert_entity_at(conn, layout, &*MINK_TYPE, vec![marty(2)], 2);
+
+        let cats = vec![cat("fred", "three"), cat("felix", "three")];
+        update_entity_at(conn, layout, &*CAT_TYPE, cats, 3);
+        insert_entity_at(conn, layout, &*CAT_TYPE, vec![cat("garfield", "three")], 3);
+        delete_cats(conn, &["tom"], 3);
+
+        delete_cats(conn, &["fred"], 4);
+        insert_entity_at(conn, layout, &*CAT_TYPE, vec![cat("tom", "four")], 4);
+        insert_entity_at(conn, layout, &*MINK_TYPE, vec![marty(4)], 4);
+
+        insert_entity_at(conn, layout, &*CAT_TYPE, vec![cat("fred", "five")], 5);
+        update_entity_at(conn, layout, &*CAT_TYPE, vec![cat("felix", "five")], 5);
+    }
+
+    run_test(|conn, layout| {
+        make_history(conn, layout);
+        let before = snapshot(conn);
+
+        // Revert blocks 5 down to 2 one at a time, remember the result, and
+        // roll everything back so we can try again with a single revert
+        let mut sequential = None;
+        conn.transaction::<(), _, _>(|conn| {
+            let count: i32 = (2..=5)
+                .rev()
+                .map(|block| layout.revert_block(conn, block).unwrap().1)
+                .sum();
+            sequential = Some((snapshot(conn), count));
+            Err(diesel::result::Error::RollbackTransaction)
+        })
+        .unwrap_err();
+        let (sequential, sequential_count) = sequential.unwrap();
+        assert_eq!(before, snapshot(conn));
+        assert_ne!(before, sequential);
+
+        let (_, count) = layout.revert_block(conn, 2).unwrap();
+        assert_eq!(sequential, snapshot(conn));
+        assert_eq!(sequential_count, count);
+    });
+}
+
 struct QueryChecker<'a> {
     conn: &'a mut PgConnection,
     layout: &'a Layout,
@@ -1596,6 +1689,9 @@ fn check_find() {
             .check(vec!["1", "2", "3"], user_query().desc("age"))
             .check(vec!["2", "1", "3"], user_query().asc("name"))
             .check(vec!["3", "1", "2"], user_query().desc("name"))
+            // Users 1 and 3 both don't drink coffee, the id breaks the tie
+            .check(vec!["1", "3", "2"], user_query().asc("coffee"))
+            .check(vec!["2", "3", "1"], user_query().desc("coffee"))
             .check(
                 vec!["1", "2"],
                 user_query()
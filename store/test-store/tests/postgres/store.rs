@@ -332,6 +332,29 @@ fn delete_entity() {
     })
 }
 
+#[test]
+fn status_reports_storage_stats() {
+    run_test(|store, _, deployment| async move {
+        let infos = store
+            .status(status::Filter::Deployments(vec![deployment
+                .hash
+                .to_string()]))
+            .unwrap();
+        let storage = infos.first().unwrap().storage.clone().unwrap();
+
+        let entity_types: Vec<_> = storage
+            .entity_types
+            .iter()
+            .map(|storage| storage.entity_type.as_str())
+            .collect();
+        assert_eq!(vec!["Manual", "Person", "User"], entity_types);
+
+        let user = &storage.entity_types[2];
+        assert!(user.storage_bytes > 0);
+        assert!(storage.storage_bytes >= user.storage_bytes);
+    })
+}
+
 /// Check that user 1 was inserted correctly
 #[test]
 fn get_entity_1() {
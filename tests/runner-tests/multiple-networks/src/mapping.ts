@@ -0,0 +1,10 @@
+import { dataSource } from '@graphprotocol/graph-ts';
+import { TestEvent } from '../generated/Contract/Contract';
+import { NetworkEvent } from '../generated/schema';
+
+export function handleTestEvent(event: TestEvent): void {
+  let entity = new NetworkEvent(event.params.testCommand);
+  entity.network = dataSource.network();
+  entity.blockNumber = event.block.number;
+  entity.save();
+}
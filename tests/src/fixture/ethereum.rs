@@ -1,31 +1,109 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
 use std::marker::PhantomData;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use super::{
     test_ptr, CommonChainConfig, MutexBlockStreamBuilder, NoopAdapterSelector,
-    NoopRuntimeAdapterBuilder, StaticBlockRefetcher, StaticStreamBuilder, Stores, TestChain,
+    NoopRuntimeAdapterBuilder, StaticBlockRefetcher, StaticStream, StaticStreamBuilder, Stores,
+    TestChain,
+};
+use anyhow::Context;
+use graph::blockchain::block_stream::{
+    BlockStream, BlockStreamBuilder, BlockStreamError, BlockStreamEvent, FirehoseCursor,
 };
 use graph::blockchain::client::ChainClient;
 use graph::blockchain::{BlockPtr, TriggersAdapterSelector};
 use graph::cheap_clone::CheapClone;
+use graph::components::store::DeploymentLocator;
+use graph::data::subgraph::UnifiedMappingApiVersion;
+use graph::futures03::stream;
 use graph::prelude::ethabi::ethereum_types::H256;
 use graph::prelude::web3::types::{Address, Log, Transaction, H160};
-use graph::prelude::{ethabi, tiny_keccak, LightEthereumBlock, ENV_VARS};
+use graph::prelude::{
+    async_trait, ethabi, serde_json, tiny_keccak, BlockNumber, EthereumBlock,
+    EthereumBlockWithCalls, LightEthereumBlock, ENV_VARS,
+};
+use graph::schema::InputSchema;
 use graph::{blockchain::block_stream::BlockWithTriggers, prelude::ethabi::ethereum_types::U64};
+use graph_chain_ethereum::chain::triggers_in_full_block;
 use graph_chain_ethereum::network::EthereumNetworkAdapters;
 use graph_chain_ethereum::trigger::LogRef;
-use graph_chain_ethereum::Chain;
 use graph_chain_ethereum::{
     chain::BlockFinality,
     trigger::{EthereumBlockTriggerType, EthereumTrigger},
 };
+use graph_chain_ethereum::{Chain, TriggerFilter};
+use slog::{o, Discard, Logger};
 
 pub async fn chain(
     test_name: &str,
     blocks: Vec<BlockWithTriggers<Chain>>,
     stores: &Stores,
     triggers_adapter: Option<Arc<dyn TriggersAdapterSelector<Chain>>>,
+) -> TestChain<Chain> {
+    let static_block_stream = Arc::new(StaticStreamBuilder { chain: blocks });
+    let network = stores.network_name.clone();
+    chain_with_stream(
+        test_name,
+        &network,
+        true,
+        static_block_stream,
+        stores,
+        triggers_adapter,
+    )
+    .await
+}
+
+/// A chain for `network` that is polled over RPC instead of using
+/// Firehose. Like the RPC block stream, its block stream skips `blocks`
+/// that have no triggers. `network` must be one of the chains in the store
+/// configuration
+pub async fn rpc_chain(
+    test_name: &str,
+    network: &str,
+    blocks: Vec<BlockWithTriggers<Chain>>,
+    stores: &Stores,
+    triggers_adapter: Option<Arc<dyn TriggersAdapterSelector<Chain>>>,
+) -> TestChain<Chain> {
+    let static_block_stream = Arc::new(StaticStreamBuilder { chain: blocks });
+    chain_with_stream(
+        test_name,
+        network,
+        false,
+        static_block_stream,
+        stores,
+        triggers_adapter,
+    )
+    .await
+}
+
+/// A chain that feeds the subgraph the blocks in the file `path` instead
+/// of blocks from the network. The triggers for each block are found with
+/// the subgraph's filter, just like they would be for a block from the
+/// network, so that replaying blocks that were captured from a real chain
+/// exercises the same trigger processing. See `read_blocks` for the format
+/// of the file
+pub async fn replay_chain(
+    test_name: &str,
+    path: impl AsRef<Path>,
+    stores: &Stores,
+) -> anyhow::Result<TestChain<Chain>> {
+    let blocks = read_blocks(path)?;
+    let replay_block_stream = Arc::new(ReplayStreamBuilder { blocks });
+    let network = stores.network_name.clone();
+    Ok(chain_with_stream(test_name, &network, true, replay_block_stream, stores, None).await)
+}
+
+async fn chain_with_stream(
+    test_name: &str,
+    network: &str,
+    firehose: bool,
+    block_stream: Arc<dyn BlockStreamBuilder<Chain>>,
+    stores: &Stores,
+    triggers_adapter: Option<Arc<dyn TriggersAdapterSelector<Chain>>>,
 ) -> TestChain<Chain> {
     let triggers_adapter = triggers_adapter.unwrap_or(Arc::new(NoopAdapterSelector {
         triggers_in_block_sleep: Duration::ZERO,
@@ -35,21 +113,25 @@ pub async fn chain(
     let CommonChainConfig {
         logger_factory,
         mock_registry,
-        chain_store,
         firehose_endpoints,
         node_id,
+        ..
     } = CommonChainConfig::new(test_name, stores).await;
+    let chain_store = stores.network_chain_store(network);
 
-    let client = Arc::new(ChainClient::<Chain>::new_firehose(firehose_endpoints));
+    let client = match firehose {
+        true => ChainClient::<Chain>::new_firehose(firehose_endpoints),
+        false => ChainClient::Rpc(EthereumNetworkAdapters::default()),
+    };
+    let client = Arc::new(client);
 
-    let static_block_stream = Arc::new(StaticStreamBuilder { chain: blocks });
-    let block_stream_builder = Arc::new(MutexBlockStreamBuilder(Mutex::new(static_block_stream)));
+    let block_stream_builder = Arc::new(MutexBlockStreamBuilder(Mutex::new(block_stream)));
 
     let eth_adapters = Arc::new(EthereumNetworkAdapters::default());
 
     let chain = Chain::new(
         logger_factory,
-        stores.network_name.clone(),
+        network.to_string(),
         node_id,
         mock_registry,
         chain_store.cheap_clone(),
@@ -151,9 +233,107 @@ pub fn push_test_log(block: &mut BlockWithTriggers<Chain>, payload: impl Into<St
         .push(EthereumTrigger::Log(LogRef::FullLog(log, None)))
 }
 
+pub fn set_block_timestamp(block: &mut BlockWithTriggers<Chain>, timestamp: u64) {
+    let block = match &mut block.block {
+        BlockFinality::Final(block) => block,
+        BlockFinality::NonFinal(block) => &mut block.ethereum_block.block,
+    };
+    Arc::make_mut(block).timestamp = timestamp.into();
+}
+
 pub fn push_test_polling_trigger(block: &mut BlockWithTriggers<Chain>) {
     block.trigger_data.push(EthereumTrigger::Block(
         block.ptr(),
         EthereumBlockTriggerType::End,
     ))
 }
+
+/// Read a file of captured blocks. The file must contain a JSON array of
+/// blocks in the format in which the chain store keeps them in the `data`
+/// column of its `blocks` table, i.e., objects with the block and its
+/// transaction receipts. Blocks need to be in ascending order of their
+/// number; calls are not part of that format, and call handlers will
+/// therefore never be triggered by replayed blocks
+pub fn read_blocks(path: impl AsRef<Path>) -> anyhow::Result<Vec<EthereumBlock>> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("failed to read blocks from {}", path.display()))
+}
+
+/// Write `blocks` to `path` in the format that `read_blocks` expects
+pub fn write_blocks(path: impl AsRef<Path>, blocks: &[EthereumBlock]) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), blocks)
+        .with_context(|| format!("failed to write blocks to {}", path.display()))
+}
+
+/// Streams captured blocks, starting after the block the subgraph has
+/// already processed. Since the blocks come from a file, there are never
+/// any reorgs
+struct ReplayStreamBuilder {
+    blocks: Vec<EthereumBlock>,
+}
+
+#[async_trait]
+impl BlockStreamBuilder<Chain> for ReplayStreamBuilder {
+    async fn build_firehose(
+        &self,
+        _chain: &Chain,
+        _deployment: DeploymentLocator,
+        _block_cursor: FirehoseCursor,
+        _start_blocks: Vec<BlockNumber>,
+        current_block: Option<BlockPtr>,
+        filter: Arc<TriggerFilter>,
+        _unified_api_version: UnifiedMappingApiVersion,
+    ) -> anyhow::Result<Box<dyn BlockStream<Chain>>> {
+        let logger = Logger::root(Discard, o!());
+        let current = current_block.map(|ptr| ptr.number);
+
+        let events = self
+            .blocks
+            .iter()
+            .filter(|block| Some(BlockPtr::from(*block).number) > current)
+            .map(|block| {
+                let block = EthereumBlockWithCalls {
+                    ethereum_block: block.clone(),
+                    calls: None,
+                };
+                let block = triggers_in_full_block(&logger, block, &filter)?;
+                Ok(BlockStreamEvent::ProcessBlock(block, FirehoseCursor::None))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Box::new(StaticStream {
+            stream: Box::pin(stream::iter(
+                events.into_iter().map(Ok::<_, BlockStreamError>),
+            )),
+        }))
+    }
+
+    async fn build_substreams(
+        &self,
+        _chain: &Chain,
+        _schema: InputSchema,
+        _deployment: DeploymentLocator,
+        _block_cursor: FirehoseCursor,
+        _subgraph_current_block: Option<BlockPtr>,
+        _filter: Arc<TriggerFilter>,
+    ) -> anyhow::Result<Box<dyn BlockStream<Chain>>> {
+        unimplemented!()
+    }
+
+    async fn build_polling(
+        &self,
+        _chain: &Chain,
+        _deployment: DeploymentLocator,
+        _start_blocks: Vec<BlockNumber>,
+        _subgraph_current_block: Option<BlockPtr>,
+        _filter: Arc<TriggerFilter>,
+        _unified_api_version: UnifiedMappingApiVersion,
+    ) -> anyhow::Result<Box<dyn BlockStream<Chain>>> {
+        unimplemented!("only firehose mode should be used for tests")
+    }
+}
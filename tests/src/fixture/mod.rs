@@ -371,6 +371,17 @@ graph::prelude::lazy_static! {
     pub static ref STORE_MUTEX: Mutex<()> = Mutex::new(());
 }
 
+impl Stores {
+    /// The chain store for `network`, which must be one of the chains in
+    /// the store configuration
+    pub fn network_chain_store(&self, network: &str) -> Arc<ChainStore> {
+        self.network_store
+            .block_store()
+            .chain_store(network)
+            .unwrap_or_else(|| panic!("No chain store for {}", network))
+    }
+}
+
 fn test_logger(test_name: &str) -> Logger {
     graph::log::logger(true).new(o!("test" => test_name.to_string()))
 }
@@ -398,17 +409,25 @@ pub async fn stores(test_name: &str, store_config_path: &str) -> Stores {
     let store_builder =
         StoreBuilder::new(&logger, &node_id, &config, None, mock_registry.clone()).await;
 
+    // Tests run against the first chain in the configuration; the other
+    // chains are only used by subgraphs with data sources on several
+    // networks
     let network_name: String = config.chains.chains.iter().next().unwrap().0.to_string();
     let chain_head_listener = store_builder.chain_head_update_listener();
-    let network_identifiers = vec![(
-        network_name.clone(),
-        ChainIdentifier {
-            net_version: "".into(),
-            genesis_block_hash: test_ptr(0).hash,
-        },
-    )]
-    .into_iter()
-    .collect();
+    let network_identifiers = config
+        .chains
+        .chains
+        .keys()
+        .map(|network| {
+            (
+                network.clone(),
+                ChainIdentifier {
+                    net_version: "".into(),
+                    genesis_block_hash: test_ptr(0).hash,
+                },
+            )
+        })
+        .collect();
     let network_store = store_builder.network_store(network_identifiers);
     let chain_store = network_store
         .block_store()
@@ -436,6 +455,19 @@ pub async fn setup<C: Blockchain>(
     chain: &impl TestChainTrait<C>,
     graft_block: Option<BlockPtr>,
     env_vars: Option<EnvVars>,
+) -> TestContext {
+    let chains = vec![(stores.network_name.clone(), chain.chain())];
+    setup_with_chains(test_info, stores, chains, graft_block, env_vars).await
+}
+
+/// Like `setup`, but with a chain for each of the networks that the data
+/// sources of the subgraph use
+pub async fn setup_with_chains<C: Blockchain>(
+    test_info: &TestInfo,
+    stores: &Stores,
+    chains: Vec<(String, Arc<C>)>,
+    graft_block: Option<BlockPtr>,
+    env_vars: Option<EnvVars>,
 ) -> TestContext {
     let env_vars = Arc::new(match env_vars {
         Some(ev) => ev,
@@ -452,7 +484,9 @@ pub async fn setup<C: Blockchain>(
     cleanup(&subgraph_store, &test_info.subgraph_name, &test_info.hash).unwrap();
 
     let mut blockchain_map = BlockchainMap::new();
-    blockchain_map.insert(stores.network_name.clone(), chain.chain());
+    for (network, chain) in chains {
+        blockchain_map.insert(network, chain);
+    }
 
     let static_filters = env_vars.experimental_static_filters;
 
@@ -712,14 +746,25 @@ impl<C: Blockchain> BlockStreamBuilder<C> for MutexBlockStreamBuilder<C> {
 
     async fn build_polling(
         &self,
-        _chain: &C,
-        _deployment: DeploymentLocator,
-        _start_blocks: Vec<BlockNumber>,
-        _subgraph_current_block: Option<BlockPtr>,
-        _filter: Arc<<C as Blockchain>::TriggerFilter>,
-        _unified_api_version: graph::data::subgraph::UnifiedMappingApiVersion,
+        chain: &C,
+        deployment: DeploymentLocator,
+        start_blocks: Vec<BlockNumber>,
+        subgraph_current_block: Option<BlockPtr>,
+        filter: Arc<<C as Blockchain>::TriggerFilter>,
+        unified_api_version: graph::data::subgraph::UnifiedMappingApiVersion,
     ) -> anyhow::Result<Box<dyn BlockStream<C>>> {
-        unimplemented!("only firehose mode should be used for tests")
+        let builder = self.0.lock().unwrap().clone();
+
+        builder
+            .build_polling(
+                chain,
+                deployment,
+                start_blocks,
+                subgraph_current_block,
+                filter,
+                unified_api_version,
+            )
+            .await
     }
 }
 
@@ -759,29 +804,49 @@ where
         _filter: Arc<C::TriggerFilter>,
         _unified_api_version: graph::data::subgraph::UnifiedMappingApiVersion,
     ) -> anyhow::Result<Box<dyn BlockStream<C>>> {
-        let current_idx = current_block.map(|current_block| {
-            self.chain
-                .iter()
-                .enumerate()
-                .find(|(_, b)| b.ptr() == current_block)
-                .unwrap()
-                .0
-        });
         Ok(Box::new(StaticStream {
-            stream: Box::pin(stream_events(self.chain.clone(), current_idx)),
+            stream: Box::pin(stream_events(
+                self.chain.clone(),
+                self.current_idx(current_block),
+            )),
         }))
     }
 
+    /// Like the RPC block stream, the polling stream does not emit blocks
+    /// without triggers
     async fn build_polling(
         &self,
         _chain: &C,
         _deployment: DeploymentLocator,
         _start_blocks: Vec<graph::prelude::BlockNumber>,
-        _subgraph_current_block: Option<graph::blockchain::BlockPtr>,
+        current_block: Option<graph::blockchain::BlockPtr>,
         _filter: Arc<C::TriggerFilter>,
         _unified_api_version: graph::data::subgraph::UnifiedMappingApiVersion,
     ) -> anyhow::Result<Box<dyn BlockStream<C>>> {
-        unimplemented!("only firehose mode should be used for tests")
+        let events =
+            stream_events(self.chain.clone(), self.current_idx(current_block)).filter(|event| {
+                let skip = matches!(
+                    event,
+                    Ok(BlockStreamEvent::ProcessBlock(block, _)) if block.trigger_count() == 0
+                );
+                graph::futures03::future::ready(!skip)
+            });
+        Ok(Box::new(StaticStream {
+            stream: Box::pin(events),
+        }))
+    }
+}
+
+impl<C: Blockchain> StaticStreamBuilder<C> {
+    fn current_idx(&self, current_block: Option<BlockPtr>) -> Option<usize> {
+        current_block.map(|current_block| {
+            self.chain
+                .iter()
+                .enumerate()
+                .find(|(_, b)| b.ptr() == current_block)
+                .unwrap()
+                .0
+        })
     }
 }
 
@@ -876,6 +941,7 @@ impl RuntimeAdapterBuilder for NoopRuntimeAdapterBuilder {
         _: Arc<EthereumNetworkAdapters>,
         _: Arc<dyn EthereumCallCache + 'static>,
         _: Arc<ChainIdentifier>,
+        _: Arc<dyn graph::components::store::ChainStore>,
     ) -> Arc<dyn graph::blockchain::RuntimeAdapter<graph_chain_ethereum::Chain> + 'static> {
         Arc::new(NoopRuntimeAdapter { x: PhantomData })
     }
@@ -979,3 +1045,70 @@ impl<C: Blockchain> TriggersAdapter<C> for MockTriggersAdapter<C> {
         }
     }
 }
+
+/// Selects a triggers adapter for a chain that has a block at every block
+/// number; `blocks` returns the block with the given number. Unlike the
+/// other adapters, it can scan ranges of blocks for triggers, which is
+/// needed when the blocks of the chain are merged into another chain
+pub struct ScanAdapterSelector<C: Blockchain> {
+    pub blocks: Arc<dyn Fn(BlockNumber) -> BlockWithTriggers<C> + Sync + Send>,
+}
+
+impl<C: Blockchain> TriggersAdapterSelector<C> for ScanAdapterSelector<C> {
+    fn triggers_adapter(
+        &self,
+        _loc: &DeploymentLocator,
+        _capabilities: &<C as Blockchain>::NodeCapabilities,
+        _unified_api_version: graph::data::subgraph::UnifiedMappingApiVersion,
+    ) -> Result<Arc<dyn graph::blockchain::TriggersAdapter<C>>, Error> {
+        Ok(Arc::new(ScanTriggersAdapter {
+            blocks: self.blocks.clone(),
+        }))
+    }
+}
+
+struct ScanTriggersAdapter<C: Blockchain> {
+    blocks: Arc<dyn Fn(BlockNumber) -> BlockWithTriggers<C> + Sync + Send>,
+}
+
+#[async_trait]
+impl<C: Blockchain> TriggersAdapter<C> for ScanTriggersAdapter<C> {
+    async fn ancestor_block(
+        &self,
+        _ptr: BlockPtr,
+        _offset: BlockNumber,
+    ) -> Result<Option<<C as Blockchain>::Block>, Error> {
+        todo!()
+    }
+
+    /// Return every block in the range. Callers already skip blocks
+    /// without triggers since `to` is returned whether it has any or not
+    async fn scan_triggers(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+        _filter: &<C as Blockchain>::TriggerFilter,
+    ) -> Result<Vec<BlockWithTriggers<C>>, Error> {
+        Ok((from..=to).map(|number| (self.blocks)(number)).collect())
+    }
+
+    async fn triggers_in_block(
+        &self,
+        _logger: &Logger,
+        block: <C as Blockchain>::Block,
+        _filter: &<C as Blockchain>::TriggerFilter,
+    ) -> Result<BlockWithTriggers<C>, Error> {
+        Ok((self.blocks)(block.number()))
+    }
+
+    async fn is_on_main_chain(&self, _ptr: BlockPtr) -> Result<bool, Error> {
+        todo!()
+    }
+
+    async fn parent_ptr(&self, block: &BlockPtr) -> Result<Option<BlockPtr>, Error> {
+        match block.number {
+            0 => Ok(None),
+            n => Ok(Some((self.blocks)(n - 1).ptr())),
+        }
+    }
+}
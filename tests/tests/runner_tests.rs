@@ -16,19 +16,20 @@ use graph::env::EnvVars;
 use graph::ipfs_client::IpfsClient;
 use graph::object;
 use graph::prelude::ethabi::ethereum_types::H256;
-use graph::prelude::web3::types::Address;
+use graph::prelude::web3::types::{Address, U64};
 use graph::prelude::{
-    hex, CheapClone, DeploymentHash, SubgraphAssignmentProvider, SubgraphName, SubgraphStore,
+    hex, BlockNumber, ChainStore, CheapClone, DeploymentHash, EthereumBlock, LightEthereumBlock,
+    SubgraphAssignmentProvider, SubgraphName, SubgraphStore, ENV_VARS,
 };
 use graph_tests::fixture::ethereum::{
     chain, empty_block, generate_empty_blocks_for_range, genesis, push_test_log,
-    push_test_polling_trigger,
+    push_test_polling_trigger, replay_chain, rpc_chain, set_block_timestamp, write_blocks,
 };
 
 use graph_tests::fixture::substreams::chain as substreams_chain;
 use graph_tests::fixture::{
-    self, stores, test_ptr, test_ptr_reorged, MockAdapterSelector, NoopAdapterSelector, Stores,
-    TestChainTrait, TestContext, TestInfo,
+    self, stores, test_ptr, test_ptr_reorged, MockAdapterSelector, NoopAdapterSelector,
+    ScanAdapterSelector, Stores, TestChainTrait, TestContext, TestInfo,
 };
 use graph_tests::helpers::run_cmd;
 use slog::{o, Discard, Logger};
@@ -964,6 +965,173 @@ async fn block_handlers() {
     );
 }
 
+#[tokio::test]
+async fn replay_captured_blocks() {
+    let RunnerTestRecipe { stores, test_info } =
+        RunnerTestRecipe::new("replay_captured_blocks", "block-handlers").await;
+
+    // Capture blocks the way they would be taken from the chain store
+    let blocks: Vec<_> = (0..=8)
+        .map(|number| {
+            let ptr = test_ptr(number);
+            let parent_hash = match number {
+                0 => H256::zero(),
+                _ => H256::from_slice(test_ptr(number - 1).hash.as_slice()),
+            };
+            EthereumBlock {
+                block: Arc::new(LightEthereumBlock {
+                    hash: Some(H256::from_slice(ptr.hash.as_slice())),
+                    number: Some(U64::from(ptr.number)),
+                    parent_hash,
+                    ..Default::default()
+                }),
+                transaction_receipts: vec![],
+            }
+        })
+        .collect();
+    let path = std::env::temp_dir().join(format!("{}-blocks.json", test_info.test_name));
+    write_blocks(&path, &blocks).unwrap();
+
+    let chain = replay_chain(&test_info.test_name, &path, &stores)
+        .await
+        .unwrap();
+    let ctx = fixture::setup(&test_info, &stores, &chain, None, None).await;
+
+    ctx.start_and_sync_to(test_ptr(8)).await;
+
+    // The polling handler runs every 4 blocks, and the triggers for it
+    // were found from the subgraph's filter, not supplied by the test
+    let query = "{ blockFromPollingHandlers(orderBy: number) { id, hash } }";
+    let query_res = ctx.query(query).await.unwrap();
+
+    let block = |number: i32| {
+        object! {
+            id: number.to_string(),
+            hash: format!("0x{}", test_ptr(number).hash_hex()),
+        }
+    };
+    assert_eq!(
+        query_res,
+        Some(object! {
+            blockFromPollingHandlers: vec![block(0), block(4), block(8)]
+        })
+    );
+}
+
+#[tokio::test]
+async fn multiple_networks() {
+    let RunnerTestRecipe {
+        stores,
+        mut test_info,
+    } = RunnerTestRecipe::new("multiple_networks", "multiple-networks").await;
+    test_info.hash = add_data_source_network(&test_info.hash, "test2").await;
+
+    let block = |number: BlockNumber, timestamp: u64, payload: Option<&str>| {
+        let mut block = match number {
+            0 => genesis(),
+            n => empty_block(test_ptr(n - 1), test_ptr(n)),
+        };
+        set_block_timestamp(&mut block, timestamp);
+        block.trigger_data.clear();
+        if let Some(payload) = payload {
+            push_test_log(&mut block, payload);
+        }
+        block
+    };
+
+    // Block `n` of the secondary network is 5 seconds older than block `n`
+    // of the main network, and is therefore merged into it. The block
+    // stream of the main network skips blocks 2 and 3 since they have no
+    // triggers, but block 2 still has to be processed for the secondary
+    // block that is merged into it
+    let main_blocks: Vec<_> = (0..=4)
+        .map(|n| block(n, 100 + 10 * n as u64, (n == 1).then_some("main-1")))
+        .collect();
+    let secondary_block = move |n: BlockNumber| {
+        let payload = format!("test2-{}", n);
+        block(
+            n,
+            95 + 10 * n as u64,
+            [1, 2, 4].contains(&n).then_some(payload.as_str()),
+        )
+    };
+
+    // Secondary blocks are only merged once they are final
+    let secondary_head = ENV_VARS.reorg_threshold + 100;
+    stores
+        .network_chain_store("test2")
+        .set_chain_head(Arc::new(secondary_block(secondary_head).block), "".into())
+        .await
+        .unwrap();
+
+    let main_adapter = {
+        let main_blocks = main_blocks.clone();
+        ScanAdapterSelector {
+            blocks: Arc::new(move |n| main_blocks[n as usize].clone()),
+        }
+    };
+    let main = rpc_chain(
+        &test_info.test_name,
+        "test",
+        main_blocks,
+        &stores,
+        Some(Arc::new(main_adapter)),
+    )
+    .await;
+    let secondary_adapter = ScanAdapterSelector {
+        blocks: Arc::new(secondary_block),
+    };
+    let secondary = rpc_chain(
+        &test_info.test_name,
+        "test2",
+        vec![],
+        &stores,
+        Some(Arc::new(secondary_adapter)),
+    )
+    .await;
+
+    let chains = vec![
+        ("test".to_string(), main.chain()),
+        ("test2".to_string(), secondary.chain()),
+    ];
+    let ctx = fixture::setup_with_chains(&test_info, &stores, chains, None, None).await;
+    ctx.start_and_sync_to(test_ptr(4)).await;
+
+    // Entity changes for secondary blocks are written at the number of
+    // the main network block they are merged into
+    let event = |id: &str, network: &str, number: BlockNumber| {
+        object! {
+            id: id,
+            network: network,
+            blockNumber: number.to_string(),
+        }
+    };
+    let at_block_1 = vec![event("main-1", "test", 1), event("test2-1", "test2", 1)];
+    let mut at_block_2 = at_block_1.clone();
+    at_block_2.push(event("test2-2", "test2", 2));
+    let mut at_block_4 = at_block_2.clone();
+    at_block_4.push(event("test2-4", "test2", 4));
+
+    for (number, events) in [
+        (1, at_block_1),
+        (2, at_block_2.clone()),
+        (3, at_block_2),
+        (4, at_block_4),
+    ] {
+        let query = format!(
+            "{{ networkEvents(orderBy: id, block: {{ number: {} }}) {{ id network blockNumber }} }}",
+            number
+        );
+        let query_res = ctx.query(&query).await.unwrap();
+        assert_eq!(
+            query_res,
+            Some(object! { networkEvents: events }),
+            "events at block {}",
+            number
+        );
+    }
+}
+
 #[tokio::test]
 async fn template_static_filters_false_positives() {
     let RunnerTestRecipe { stores, test_info } = RunnerTestRecipe::new(
@@ -1216,6 +1384,32 @@ async fn arweave_file_data_sources() {
     );
 }
 
+/// Add a copy of the data source of the subgraph `hash` on `network` and
+/// declare the `multipleNetworks` feature, and return the hash of the new
+/// manifest. This can't be done in `subgraph.yaml` since graph-cli rejects
+/// manifests whose data sources use different networks
+async fn add_data_source_network(hash: &DeploymentHash, network: &str) -> DeploymentHash {
+    let ipfs = IpfsClient::localhost();
+    let bytes = ipfs.cat_all(hash.as_str(), None, usize::MAX).await.unwrap();
+    let mut manifest: serde_yaml::Mapping = serde_yaml::from_slice(&bytes).unwrap();
+
+    manifest.insert("features".into(), vec!["multipleNetworks"].into());
+    let data_sources = manifest
+        .get_mut("dataSources")
+        .and_then(|data_sources| data_sources.as_sequence_mut())
+        .unwrap();
+    let mut data_source = data_sources[0].clone();
+    let mapping = data_source.as_mapping_mut().unwrap();
+    let name = format!("{}-{}", mapping["name"].as_str().unwrap(), network);
+    mapping.insert("name".into(), name.into());
+    mapping.insert("network".into(), network.into());
+    data_sources.push(data_source);
+
+    let manifest = serde_yaml::to_string(&manifest).unwrap();
+    let added = ipfs.add(manifest.into_bytes()).await.unwrap();
+    DeploymentHash::new(added.hash).unwrap()
+}
+
 /// deploy_cmd is the command to run to deploy the subgraph. If it is None, the
 /// default `yarn deploy:test` is used.
 async fn build_subgraph(dir: &str, deploy_cmd: Option<&str>) -> DeploymentHash {